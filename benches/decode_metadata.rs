@@ -0,0 +1,58 @@
+//! Benchmarks of decoding the start of logfile lines (see src/custom/log_prefix.rs)
+//! against the regex and chrono parsing it replaced. Run with 'cargo bench', while
+//! 'cargo test' runs each once to check they still work.
+use chrono::DateTime;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use regex::Regex;
+
+#[allow(dead_code, unused_imports)] // Not all of it is benchmarked, nor its tests run here
+#[path = "../src/custom/log_prefix.rs"]
+mod log_prefix;
+use log_prefix::{split_log_line, TimestampParser};
+
+/// A minute of lines, as in a busy node's logfile
+fn sample_lines() -> Vec<String> {
+	(0..1000)
+		.map(|i| {
+			format!(
+				"[2024-03-23T19:00:{:02}.{:06}Z INFO ant_networking::record_store] Wrote record {:x} to disk",
+				i * 60 / 1000,
+				i * 997 % 1_000_000,
+				i
+			)
+		})
+		.collect()
+}
+
+fn decode_metadata(c: &mut Criterion) {
+	let lines = sample_lines();
+
+	let pattern = Regex::new(
+		r"\[(?P<time_string>[^ ]{27}) (?P<category>[A-Z]{4,6}) (?P<source>[^\]]*)\] (?P<message>.*)",
+	)
+	.unwrap();
+	c.bench_function("regex and parse_from_str", |b| {
+		b.iter(|| {
+			for line in &lines {
+				let captures = pattern.captures(black_box(line)).unwrap();
+				let time_string = captures.name("time_string").unwrap().as_str();
+				black_box(DateTime::parse_from_str(time_string, "%+").unwrap());
+				black_box(captures.name("message").unwrap().as_str());
+			}
+		})
+	});
+
+	c.bench_function("split_log_line and TimestampParser", |b| {
+		let mut parser = TimestampParser::default();
+		b.iter(|| {
+			for line in &lines {
+				let prefix = split_log_line(black_box(line)).unwrap();
+				black_box(parser.parse(prefix.time_string).unwrap());
+				black_box(prefix.message);
+			}
+		})
+	});
+}
+
+criterion_group!(benches, decode_metadata);
+criterion_main!(benches);
@@ -0,0 +1,32 @@
+//! Benchmarks of gathering metrics from the sample logfiles in tests/fixtures
+//! with parse_lines(), as vdash does for each line of a node's logfile. Run
+//! with 'cargo bench', while 'cargo test' runs each once to check they still work.
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use vdash_core::parse_lines;
+
+/// (name, contents) of each logfile in tests/fixtures, as checked by src/custom/test_support.rs
+const FIXTURES: [(&str, &str); 3] = [
+	("antnode-earning.log", include_str!("../tests/fixtures/antnode-earning.log")),
+	("antnode-shunned.log", include_str!("../tests/fixtures/antnode-shunned.log")),
+	("safenode-nanos.log", include_str!("../tests/fixtures/safenode-nanos.log")),
+];
+
+/// Copies of a fixture enough to make about a thousand lines
+fn repeated_lines(contents: &'static str) -> Vec<&'static str> {
+	let lines: Vec<&str> = contents.lines().collect();
+	let copies = 1000 / lines.len() + 1;
+	lines.iter().cycle().take(lines.len() * copies).copied().collect()
+}
+
+fn parse_fixtures(c: &mut Criterion) {
+	for (name, contents) in FIXTURES {
+		let lines = repeated_lines(contents);
+		c.bench_function(&format!("parse_lines {}", name), |b| {
+			b.iter(|| parse_lines(black_box(&lines)))
+		});
+	}
+}
+
+criterion_group!(benches, parse_fixtures);
+criterion_main!(benches);
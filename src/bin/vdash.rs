@@ -11,24 +11,26 @@
 
 #![recursion_limit = "1024"] // Prevent select! macro blowing up
 
-#[path = "../custom/mod.rs"]
-pub mod custom;
-use self::custom::app::{OPT, App, DashViewMain};
+use vdash_core::custom;
+use self::custom::app::{set_opt, OPT, App};
+use self::custom::control_socket::{handle_control_request, spawn_control_socket, ControlRequest};
+use self::custom::frame_limiter::FrameLimiter;
+use self::custom::profile::{enable_profiling, profile_report};
+use self::custom::journal_logs::spawn_journal_reader;
+use self::custom::log_listener::{listen_address, spawn_log_listener};
+use self::custom::mini::{mini_lines, MiniWriter};
+use self::custom::opt::opt_from_args_or_config;
+use self::custom::parse_pipeline::{ParsePipeline, MAX_LINES_PER_BATCH};
+use self::custom::remote_logs::{spawn_remote_tail, RemoteEvent, RemoteSource};
+use self::custom::setup_wizard::{is_first_run, run_setup_wizard};
+use self::custom::terminal_guard::{install_panic_hook, restore_terminal, TerminalGuard};
 use self::custom::ui::draw_dashboard;
 
 #[macro_use]
 extern crate log;
 extern crate env_logger;
 
-///! logtail and its forks share code in src/
-#[path = "../mod.rs"]
-pub mod shared;
-
-use crossterm::{
-	event::{self, DisableMouseCapture, EnableMouseCapture, Event as CEvent},
-	execute,
-	terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
-};
+use crossterm::event::{self, Event as CEvent};
 
 use std::{
 	error::Error,
@@ -57,32 +59,76 @@ use tokio::sync::mpsc;
 
 #[tokio::main]
 pub async fn main() -> Result<(), Box<dyn Error>> {
-	let (opt_tick_rate, checkpoint_interval, opt_debug_window,
-		coingecho_api_key, coinmarketcap_api_key, currency_apiname) = {
+	install_panic_hook();
+
+	// Must be before the options are read, so that they come from the new config file
+	if is_first_run() && !run_setup_wizard()? {
+		return Ok(());
+	}
+	set_opt(opt_from_args_or_config());
+
+	let (opt_tick_rate, checkpoint_interval, opt_debug_window, opt_profile, opt_mini, opt_mini_lines) = {
 		let opt = OPT.lock().unwrap();
-		(opt.tick_rate, opt.checkpoint_interval, opt.debug_window,
-			opt.coingecko_key.clone(), opt.coinmarketcap_key.clone(), opt.currency_apiname.clone())
+		(opt.tick_rate, opt.checkpoint_interval, opt.debug_window, opt.profile, opt.mini, opt.mini_lines)
 	};
 
 	env_logger::init();
 	info!("Started");
+	if opt_profile {
+		enable_profiling();
+	}
 
 	let mut app = match App::new().await {
 		Ok(app) => app,
 		Err(_e) => return Ok(()),
 	};
 
-	let mut web_apis = crate::custom::web_requests::WebPriceAPIs::new(coingecho_api_key, coinmarketcap_api_key, &currency_apiname);
+	// Requests from --control scripts, answered in the main loop. control_tx is kept so the channel stays open without one.
+	let (control_tx, mut control_rx) = mpsc::unbounded_channel::<ControlRequest>();
+	let (opt_control, opt_control_token) = {
+		let opt = OPT.lock().unwrap();
+		(opt.control.clone(), opt.control_token.clone())
+	};
+	if let Some(control) = opt_control {
+		if let Err(e) = spawn_control_socket(&control, opt_control_token, control_tx.clone()) {
+			eprintln!("{}", e);
+			return Ok(());
+		}
+	}
 
-	// Terminal initialization
-	enable_raw_mode()?;
+	let mut web_apis = crate::custom::web_requests::WebPriceAPIs::new();
 
-	let mut stdout = stdout();
-	execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-	let backend = CrosstermBackend::new(stdout);
-	let mut terminal = Terminal::new(backend)?;
-	let mut rx = initialise_events(opt_tick_rate);
-	terminal.clear()?;
+	// Lines from --remote, --listen and --journal sources. remote_tx is kept so the channel stays open without any.
+	let (remote_tx, mut remote_rx) = mpsc::unbounded_channel::<RemoteEvent>();
+	for remote in OPT.lock().unwrap().remote.iter() {
+		match RemoteSource::parse(remote) {
+			Some(source) => spawn_remote_tail(source, remote_tx.clone()),
+			None => app.dash_state.vdash_status.message(&format!("Invalid --remote '{}', expected user@host:/path/glob", remote), None),
+		}
+	}
+	if let Some(listen) = OPT.lock().unwrap().listen.as_ref() {
+		spawn_log_listener(listen_address(listen), remote_tx.clone());
+	}
+	for unit_glob in OPT.lock().unwrap().journal.iter() {
+		spawn_journal_reader(unit_glob.clone(), remote_tx.clone());
+	}
+
+	// Lines from logfiles followed by linemux are parsed by worker tasks and applied here in batches
+	let (parse_pipeline, mut parsed_rx) = ParsePipeline::spawn();
+
+	// Terminal initialization, except for --mini which writes lines in place and takes no keys.
+	// The guard restores the terminal however main() returns.
+	let mut mini = MiniWriter::stdout();
+	let _terminal_guard = if opt_mini { None } else { Some(TerminalGuard::enter(true)?) };
+	let mut terminal = if opt_mini {
+		None
+	} else {
+		let backend = CrosstermBackend::new(stdout());
+		let mut terminal = Terminal::new(backend)?;
+		terminal.clear()?;
+		Some(terminal)
+	};
+	let mut rx = initialise_events(opt_tick_rate, !opt_mini);
 
 	// Use futures of async functions to handle events
 	// concurrently with logfile changes.
@@ -91,11 +137,14 @@ pub async fn main() -> Result<(), Box<dyn Error>> {
 		.duration_since(UNIX_EPOCH)
 		.expect("Time went backwards");
 	let mut next_update = start - Duration::from_secs(2);
+	// However busy the logfiles, frames are drawn at most once per tick. The interval is
+	// a little under tick_rate so that a tick which arrives early isn't left undrawn.
+	let mut frames = FrameLimiter::new(Duration::from_millis(opt_tick_rate * 9 / 10));
 	loop {
 		if next_update < SystemTime::now()
 			.duration_since(UNIX_EPOCH)
 			.expect("Time went backwards") {
-			terminal.draw(|f| draw_dashboard(f, &mut app))?;
+			frames.request();
 			next_update += Duration::from_secs(1);
 			match web_apis.handle_web_requests().await {
 				Ok(Some(currency_per_token)) => {
@@ -104,104 +153,158 @@ pub async fn main() -> Result<(), Box<dyn Error>> {
 				},
 				Ok(None) => {},
 				Err(e) => {
-					_ = reset_terminal(&mut terminal);
-					eprintln!("Web API error, {}", e);
-					return Ok(());
+					let api_name = web_apis.current_provider_name().unwrap_or("none");
+					app.dash_state.vdash_status.message(&format!("Web API error, {} (next API: {})", e, api_name), None);
 				},
 			};
-			let prices = custom::app::WEB_PRICES.lock().unwrap();
+			let mut prices = custom::app::WEB_PRICES.lock().unwrap();
+			prices.update_timelines(&Utc::now());
 			if prices.snt_rate.is_some() {
 				app.dash_state.currency_per_token = prices.snt_rate;
 			}
 		}
 
-		let logfiles_future = app.logfiles_manager.linemux_files.next().fuse();
-		let events_future = rx.recv().fuse();
-
-		pin_mut!(logfiles_future, events_future);
+		let mut input_redraw_pending = false;
+		let mut parsed_lines = Vec::new();
+		{
+			let logfiles_future = app.logfiles_manager.linemux_files.next().fuse();
+			let parsed_future = parsed_rx.recv().fuse();
+			let events_future = rx.recv().fuse();
+			let remote_future = remote_rx.recv().fuse();
+			let control_future = control_rx.recv().fuse();
+
+			pin_mut!(logfiles_future, parsed_future, events_future, remote_future, control_future);
+
+			select! {
+					e = events_future => {
+					match e {
+						Some(Event::Input(event)) => {
+							if !self::custom::ui_keyboard::handle_keyboard_event(&mut app, &event, opt_debug_window).await {
+								return exit_terminal(&mut terminal, &app);
+							}
+							input_redraw_pending = true;
+						}
 
-		select! {
-				e = events_future => {
-				match e {
-					Some(Event::Input(event)) => {
-						if !self::custom::ui_keyboard::handle_keyboard_event(&mut app, &event, opt_debug_window).await {
-							return reset_terminal(&mut terminal);
+						Some(Event::Tick) => {
+							app.on_tick().await;
+							frames.request();
 						}
-						terminal.draw(|f| draw_dashboard(f, &mut app)).unwrap();
-					}
 
-					Some(Event::Tick) => {
-						app.update_timelines(&Utc::now());
-						app.scan_glob_paths(true, true).await;
-						terminal.draw(|f| draw_dashboard(f, &mut app)).unwrap();
-						// draw_dashboard(&mut f, &dash_state, &mut monitors).unwrap();
-						// draw_dashboard(f, &dash_state, &mut monitors)?;
+						None => {},
+					}
+				},
+					parsed_line = parsed_future => {
+					if let Some(parsed_line) = parsed_line {
+						parsed_lines.push(parsed_line);
+					}
+				},
+					remote_event = remote_future => {
+					if let Some(remote_event) = remote_event {
+						app.handle_remote_event(remote_event);
+					}
+				},
+					control_request = control_future => {
+					if let Some(ControlRequest { request, reply }) = control_request {
+						let response = handle_control_request(&mut app, &request, opt_debug_window).await;
+						let _ = reply.send(response);
+						input_redraw_pending = true;
 					}
+				},
+					line = logfiles_future => {
+					match line {
+						// Logfiles are not followed while replaying them
+						Some(Ok(_line)) if app.dash_state.replay.is_some() => {},
+						Some(Ok(line)) => {
+							trace!("logfiles_future line");
+							let source = line.source().to_string_lossy().to_string();
+							parse_pipeline.send(source, line.line().to_string());
+						},
+						Some(Err(e)) if e.kind() == std::io::ErrorKind::InvalidData => app.skip_invalid_line(),
+						Some(Err(e)) => app.handle_logfile_error(e),
+						None => {
+							app.dash_state._debug_window(format!("logfile error: None").as_str());
+							()
+						}
+					}
+				},
+			}
+		}
 
-					None => {},
+		if !parsed_lines.is_empty() {
+			// Take any other lines already parsed, so that a burst is applied with one update of the view
+			while parsed_lines.len() < MAX_LINES_PER_BATCH {
+				match parsed_rx.try_recv() {
+					Ok(parsed_line) => parsed_lines.push(parsed_line),
+					Err(_) => break,
 				}
-			},
-				line = logfiles_future => {
-				match line {
-					Some(Ok(line)) => {
-						trace!("logfiles_future line");
-						let source_str = line.source().to_str().unwrap();
-						let source = String::from(source_str);
-						// app.dash_state._debug_window(format!("{}: {}", source, line.line()).as_str());
-
-						let mut checkpoint_result: Result<String, std::io::Error> = Ok("".to_string());
-						match app.get_monitor_for_file_path(&source) {
-							Some(monitor) => {
-								checkpoint_result = monitor.append_to_content(line.line(), checkpoint_interval);
-								if monitor.is_debug_dashboard_log {
-									app.dash_state._debug_window(line.line());
-								} else if app.dash_state.main_view == DashViewMain::DashSummary {
-									app.update_summary_window();
-								}
-							},
-							None => {
-								app.dash_state._debug_window(format!("NO MONITOR FOR: {}", source).as_str());
-							},
-						}
-						match checkpoint_result {
-							Ok(message) => {
-								if message.len() > 0 {
-									app.dash_state.vdash_status.message(&message, None);
-								}
-							},
-							Err(e) => {
-								app.dash_state.vdash_status.message(&e.to_string(), None);
-							}
+			}
+			app.apply_parsed_lines(parsed_lines, checkpoint_interval);
+		}
+		if app.view_outdated {
+			frames.request();
+		}
+
+		if input_redraw_pending {
+			// Coalesce keyboard auto-repeat by handling all pending input before a single redraw
+			let mut tick_pending = false;
+			while let Ok(pending) = rx.try_recv() {
+				match pending {
+					Event::Input(event) => {
+						if !self::custom::ui_keyboard::handle_keyboard_event(&mut app, &event, opt_debug_window).await {
+							return exit_terminal(&mut terminal, &app);
 						}
-					},
-					Some(Err(e)) => {
-						app.dash_state._debug_window(format!("logfile error: {:#?}", e).as_str());
-						panic!("{}", e)
-					}
-					None => {
-						app.dash_state._debug_window(format!("logfile error: None").as_str());
-						()
 					}
+					Event::Tick => tick_pending = true,
 				}
-			},
+			}
+			if tick_pending {
+				app.on_tick().await;
+			}
+			frames.request();
+		}
+
+		if frames.take_frame(Instant::now()) {
+			match terminal.as_mut() {
+				Some(terminal) => {
+					app.update_view_if_outdated();
+					terminal.draw(|f| draw_dashboard(f, &mut app))?;
+				}
+				None => mini.write(mini_lines(&app, opt_mini_lines, &Utc::now()))?,
+			}
 		}
 	}
 }
 
+/// Restore the terminal and print any parser stats and profiling summary
+fn exit_terminal(terminal: &mut Option<Terminal::<CrosstermBackend<std::io::Stdout>>>, app: &App) -> Result<(), Box<dyn Error>> {
+	let result = match terminal {
+		Some(terminal) => reset_terminal(terminal),
+		None => Ok(()),
+	};
+	match app.write_session_report() {
+		Some(Ok(message)) => println!("{}", message),
+		Some(Err(e)) => eprintln!("{}", e),
+		None => {},
+	}
+	if let Some(report) = app.parser_stats_report() {
+		println!("{}", report);
+	}
+	if let Some(report) = profile_report() {
+		eprintln!("{}", report);
+	}
+	result
+}
+
 fn reset_terminal(terminal: &mut Terminal::<CrosstermBackend<std::io::Stdout>>) -> Result<(), Box<dyn Error>> {
-	disable_raw_mode()?;
-	execute!(
-		terminal.backend_mut(),
-		LeaveAlternateScreen,
-		DisableMouseCapture
-	)?;
+	restore_terminal()?;
 	terminal.show_cursor()?;
 	Ok(())
 }
 
 type Rx = tokio::sync::mpsc::UnboundedReceiver<Event<crossterm::event::KeyEvent>>;
 
-fn initialise_events(tick_rate: u64) -> Rx {
+/// Ticks, and keys pressed if read_keys
+fn initialise_events(tick_rate: u64, read_keys: bool) -> Rx {
 	let tick_rate = Duration::from_millis(tick_rate);
 	let (tx, rx) = mpsc::unbounded_channel(); // Setup input handling
 
@@ -209,8 +312,10 @@ fn initialise_events(tick_rate: u64) -> Rx {
 		let mut last_tick = Instant::now();
 		loop {
 			// poll for tick rate duration, if no events, sent tick event.
-			if event::poll(tick_rate - last_tick.elapsed()).unwrap() {
-				if let CEvent::Key(key) = event::read().unwrap() {
+			if !read_keys {
+				thread::sleep(tick_rate.saturating_sub(last_tick.elapsed()));
+			} else if event::poll(tick_rate.saturating_sub(last_tick.elapsed())).unwrap_or(false) {
+				if let Ok(CEvent::Key(key)) = event::read() {
 					match tx.send(Event::Input(key)) {
 						Ok(()) => {},
 						Err(e) => eprintln!("send error: {}", e),
@@ -0,0 +1,73 @@
+//! Sampling of anomalous node log lines (see --anomalies and --anomalies-file)
+//!
+//! Lines logged as ERROR or WARN, and lines which look like the start of a log
+//! entry but can't be parsed, are copied from every monitored logfile as they
+//! arrive, prefixed with the node number. They can go to the debug window
+//! (with --debug-window) and/or be appended to a file, which makes it easier
+//! to see what is going wrong across a fleet than reading each logfile.
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+
+use super::node_metrics::LogEntry;
+
+/// The kind of anomaly a line is, if any
+pub fn anomaly_kind(line: &str) -> Option<String> {
+	match LogEntry::decode_metadata(line) {
+		Some(metadata) if metadata.category == "ERROR" || metadata.category == "WARN" => Some(metadata.category),
+		Some(_) => None,
+		// Continuation lines of an entry don't start with '['
+		None if line.starts_with('[') => Some(String::from("UNPARSED")),
+		None => None,
+	}
+}
+
+pub struct AnomalySampler {
+	pub to_debug_window: bool,
+	file: Option<File>,
+}
+
+impl AnomalySampler {
+	/// A sampler if either destination was given
+	pub fn new(to_debug_window: bool, path: &Option<String>) -> std::io::Result<Option<AnomalySampler>> {
+		let file = match path {
+			Some(path) => Some(OpenOptions::new().create(true).append(true).open(path)?),
+			None => None,
+		};
+		if !to_debug_window && file.is_none() {
+			return Ok(None);
+		}
+		Ok(Some(AnomalySampler { to_debug_window, file }))
+	}
+
+	/// The line for the debug window if it is an anomaly, after appending it to any file
+	pub fn sample(&mut self, node_index: usize, line: &str) -> std::io::Result<Option<String>> {
+		if anomaly_kind(line).is_none() {
+			return Ok(None);
+		}
+
+		let sample = format!("node {}: {}", node_index + 1, line);
+		if let Some(file) = &mut self.file {
+			writeln!(file, "{}", sample)?;
+		}
+		Ok(if self.to_debug_window { Some(sample) } else { None })
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn it_recognises_anomalies() {
+		assert_eq!(anomaly_kind("[2024-03-23T19:38:32.350118Z WARN ant_networking] Slow").as_deref(), Some("WARN"));
+		assert_eq!(anomaly_kind("[2024-03-23T19:38:32.350118Z ERROR ant_node] Failed").as_deref(), Some("ERROR"));
+		assert_eq!(anomaly_kind("[2024-03-23T19:38:32.350118Z INFO ant_node] Fine"), None);
+		assert_eq!(anomaly_kind("[garbled ERR"), Some(String::from("UNPARSED")));
+		assert_eq!(anomaly_kind("  continuation of a message"), None);
+
+		let mut sampler = AnomalySampler::new(true, &None).unwrap().unwrap();
+		let sample = sampler.sample(2, "[garbled").unwrap();
+		assert_eq!(sample.as_deref(), Some("node 3: [garbled"));
+		assert!(AnomalySampler::new(false, &None).unwrap().is_none());
+	}
+}
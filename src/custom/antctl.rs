@@ -0,0 +1,90 @@
+//! Control nodes managed by antctl (see --antctl-command)
+//!
+//! Each logfile is mapped to the antctl service which writes it by the name
+//! of its directory (e.g. /var/log/antnode/antnode3/antnode.log is written by
+//! service 'antnode3'). Commands run in the background and their results are
+//! collected for display in the status bar.
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use tokio::process::Command;
+
+use super::background_results;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NodeAction {
+	Restart,
+	Stop,
+	Upgrade,
+}
+
+impl NodeAction {
+	fn subcommand(&self) -> &'static str {
+		match self {
+			NodeAction::Restart => "restart",
+			NodeAction::Stop => "stop",
+			NodeAction::Upgrade => "upgrade",
+		}
+	}
+
+	pub fn name(&self) -> &'static str {
+		match self {
+			NodeAction::Restart => "Restart",
+			NodeAction::Stop => "Stop",
+			NodeAction::Upgrade => "Upgrade",
+		}
+	}
+}
+
+/// An action awaiting confirmation by the user
+#[derive(Clone, Debug)]
+pub struct PendingNodeAction {
+	pub action: NodeAction,
+	pub service_name: String,
+	pub node_index: usize,
+	pub expires: DateTime<Utc>, // After which a key no longer confirms it
+}
+
+/// The antctl service name for a logfile: the directory holding it, or its
+/// parent if that directory is named 'logs'
+pub fn service_name_for_logfile(logfile: &str) -> Option<String> {
+	let mut dir = Path::new(logfile).parent()?;
+	if dir.file_name()? == "logs" {
+		dir = dir.parent()?;
+	}
+	Some(dir.file_name()?.to_string_lossy().to_string())
+}
+
+/// Run the action in the background using antctl_command (e.g. "sudo antctl")
+pub fn spawn_node_action(antctl_command: &str, pending: PendingNodeAction) {
+	let mut words = antctl_command.split_whitespace().map(String::from);
+	let Some(program) = words.next() else {
+		return;
+	};
+	let mut args: Vec<String> = words.collect();
+	args.extend([
+		pending.action.subcommand().to_string(),
+		String::from("--service-name"),
+		pending.service_name.clone(),
+	]);
+
+	tokio::spawn(async move {
+		let result = match Command::new(&program).args(&args).output().await {
+			Ok(output) if output.status.success() => format!(
+				"{} of {} (node {}) succeeded",
+				pending.action.name(),
+				pending.service_name,
+				pending.node_index + 1
+			),
+			Ok(output) => format!(
+				"{} of {} failed: {}",
+				pending.action.name(),
+				pending.service_name,
+				String::from_utf8_lossy(&output.stderr).lines().last().unwrap_or("unknown error")
+			),
+			Err(e) => format!("Failed to run '{}': {}", program, e),
+		};
+		background_results::push(result);
+	});
+}
+
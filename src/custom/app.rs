@@ -3,66 +3,68 @@
 // TODO consider colouring logfiles using regex's from https://github.com/bensadeh/tailspin
 use std::collections::HashMap;
 use std::fs::File;
-use std::io::{Error, ErrorKind, Write};
+use std::io::{Error, ErrorKind};
 use std::path::Path;
-use std::sync::LazyLock;
 
 use chrono::{DateTime, Duration, Utc};
-use serde::{Deserialize, Serialize};
 use structopt::StructOpt;
 use tempfile::NamedTempFile;
 
 use crate::shared::util::StatefulList;
 
-use super::app_timelines::{AppTimelines, APP_TIMELINES, TIMESCALES};
-use super::app_timelines::{
-	CONNECTIONS_TIMELINE_KEY, EARNINGS_TIMELINE_KEY, ERRORS_TIMELINE_KEY, GETS_TIMELINE_KEY,
-	PUTS_TIMELINE_KEY, RAM_TIMELINE_KEY, STORAGE_COST_TIMELINE_KEY,
-};
-use super::logfile_checkpoints::save_checkpoint;
+use super::antctl::{NodeAction, PendingNodeAction};
+use super::app_timelines::{parse_timescales, timeline_colours, timeline_order, TIMESCALES};
+use super::app_timelines::GETS_TIMELINE_KEY;
+use super::anomalies::AnomalySampler;
+use super::averages::AverageMode;
+use super::clipboard::Copied;
+use super::custom_metrics::parse_custom_metrics;
+use super::debug_lines::{DebugLines, MAX_DEBUG_LINES};
+use super::disk_space::DiskSpace;
+use super::earnings_ledger::{earnings_by_period, EarningsPeriod};
+use super::error_categories::parse_error_categories;
+use super::events_out::EventsOut;
+use super::keymap::Keymap;
+use super::metrics_db::MetricsDb;
+use super::metrics_push::{MetricsPush, PushFormat};
+use super::node_filter::{parse_tags, NodeFilter};
+use super::node_groups::{group_of_row_key, group_rows, GroupBy, GroupTotals};
+use super::node_jump::find_node;
+use super::number_format::NumberFormat;
+use super::remote_logs::RemoteEvent;
+use super::replay::{dashboard_time, set_replay_time, Replay};
+use super::session::{write_session_report, SessionCounts};
+use super::store_size::StoreSize;
+use super::triggers::Triggers;
+use super::wallet_watch::WalletWatch;
+use super::logfile_checkpoints::checkpoint_path;
+use super::mapped_logfile::{map_logfile, LossyLines};
 use super::logfiles_manager::LogfilesManager;
+use super::node_metrics::{read_lossy_line, DEBUG_LOGFILE};
 use super::opt::{Opt, MIN_TIMELINE_STEPS};
-use super::timelines::{get_duration_text, MinMeanMax};
+use super::parse_pipeline::ParsedLine;
+use super::parser_stats::parser_stats_report;
+use super::preferences::{NodeLayout, Preferences};
+use super::process_metrics::{needs_sampling, ProcessSampler, SAMPLE_INTERVAL_S};
+use super::profile::profile_scope;
+use super::timeline_window::{parse_timeline_window, timescale_reaching, PAN_COLUMNS};
+use super::timelines::MinMeanMax;
+use super::token_units::TokenUnit;
+
+pub use super::node_metrics::{
+	debug_log, node_status_as_string, parse_lines, LogEntry, LogMeta, LogMonitor, MmmStat, NodeMetrics, NodeStatus, OPT,
+	set_opt,
+};
+pub use super::web_requests::WEB_PRICES;
 
 pub const NODE_BINARY_NAME: &str = "safenode";
 pub static SUMMARY_WINDOW_NAME: &str = "Summary of Monitored Nodes";
 pub static HELP_WINDOW_NAME: &str = "Help";
-pub static DEBUG_WINDOW_NAME: &str = "Debug Window";
-
-use std::sync::Mutex;
-static DEBUG_LOGFILE: LazyLock<Mutex<Option<NamedTempFile>>> =
-	LazyLock::new(|| Mutex::<Option<NamedTempFile>>::new(None));
-
-#[macro_export]
-macro_rules! debug_log {
-	($message:expr) => {
-		unsafe {
-			debug_log($message);
-		}
-	};
-}
-pub use crate::debug_log;
+pub static EARNINGS_WINDOW_NAME: &str = "Earnings Ledger";
+pub static FLEET_WINDOW_NAME: &str = "Fleet Daily Comparison";
 
-pub unsafe fn debug_log(message: &str) {
-	// --debug-window - prints parser results for a single logfile
-	// to a temp logfile which is displayed in the adjacent window.
-	match &(*DEBUG_LOGFILE.lock().unwrap()) {
-		Some(f) => {
-			use std::io::Seek;
-			if let Ok(mut file) = f.reopen() {
-				file.seek(std::io::SeekFrom::End(0)).unwrap();
-				writeln!(file, "{}", message).unwrap();
-			}
-		}
-		None => (),
-	};
-}
-
-pub static OPT: LazyLock<Mutex<Opt>> = LazyLock::new(|| Mutex::<Opt>::new(Opt::from_args()));
-
-pub static WEB_PRICES: LazyLock<Mutex<super::web_requests::WebPrices>> = LazyLock::new(|| {
-	Mutex::<super::web_requests::WebPrices>::new(super::web_requests::WebPrices::new())
-});
+const NODE_ACTION_CONFIRM_TIMEOUT_S: i64 = 30;
+pub static DEBUG_WINDOW_NAME: &str = "Debug Window";
 
 pub struct App {
 	pub dash_state: DashState,
@@ -71,6 +73,20 @@ pub struct App {
 
 	pub logfiles_manager: LogfilesManager,
 	pub next_glob_scan: Option<DateTime<Utc>>,
+	pub next_wallet_poll: Option<DateTime<Utc>>,
+	pub next_disk_poll: Option<DateTime<Utc>>,
+	pub next_store_poll: Option<DateTime<Utc>>,
+	pub store_poll_next_node: usize, // Position in monitors of the next record store to scan
+	pub events_out: Option<EventsOut>, // See --events-out
+	pub metrics_db: Option<MetricsDb>, // See --db
+	pub metrics_push: Option<MetricsPush>, // See --metrics-push
+	pub triggers: Option<Triggers>,       // See --trigger
+	pub anomaly_sampler: Option<AnomalySampler>, // See --anomalies
+	pub process_sampler: Option<ProcessSampler>, // See --process-metrics
+	pub process_checker: ProcessSampler,          // See --pid-check
+	pub invalid_lines_skipped: u64,               // Followed lines which were not valid UTF-8
+	pub view_outdated: bool,                      // New lines are not yet in the active view (see update_view_if_outdated)
+	pub preferences: Preferences,
 }
 
 impl App {
@@ -78,44 +94,145 @@ impl App {
 		let (
 			opt_files,
 			opt_globpaths,
+			opt_snapshot_globpaths,
+			opt_remotes,
+			opt_listen,
+			opt_journals,
 			opt_debug_window,
 			opt_timeline_steps,
 			opt_currency_token_rate,
 			opt_currency_symbol,
 			opt_currency_apiname,
+			opt_events_out,
 		) = {
 			let opt = OPT.lock().unwrap();
 			(
 				opt.files.clone(),
 				opt.glob_paths.clone(),
+				opt.snapshot_glob_path.clone(),
+				opt.remote.clone(),
+				opt.listen.clone(),
+				opt.journal.clone(),
 				opt.debug_window,
 				opt.timeline_steps,
 				opt.currency_token_rate,
 				opt.currency_symbol.clone(),
 				opt.currency_apiname.clone(),
+				opt.events_out.clone(),
 			)
 		};
 
+		let logfiles_manager = match LogfilesManager::new(
+			[opt_globpaths.clone(), opt_snapshot_globpaths.clone()].concat(),
+			opt_snapshot_globpaths.clone(),
+		) {
+			Ok(logfiles_manager) => logfiles_manager,
+			Err(e) => {
+				eprintln!("{}", e);
+				return Err(e);
+			}
+		};
+
+		set_replay_time(None);
 		let mut app = App {
 			dash_state: DashState::new(),
 			monitors: HashMap::new(),
 			logfile_with_focus: String::new(),
 
-			logfiles_manager: LogfilesManager::new(opt_globpaths.clone()),
+			logfiles_manager,
 			next_glob_scan: None,
+			next_wallet_poll: None,
+			next_disk_poll: None,
+			next_store_poll: None,
+			store_poll_next_node: 0,
+			events_out: opt_events_out.map(|path| EventsOut::new(&path)),
+			metrics_db: None,
+			metrics_push: None,
+			triggers: None,
+			anomaly_sampler: None,
+			process_sampler: None,
+			process_checker: ProcessSampler::new(),
+			invalid_lines_skipped: 0,
+			view_outdated: false,
+			preferences: Preferences::load(),
 		};
+		super::ui_summary_table::apply_sort_preference(&mut app.dash_state, &app.preferences.summary_sort);
+		app.apply_layout_preference();
+		app.dash_state.sparkline_braille = app.preferences.sparkline_braille;
 
-		app.dash_state.currency_symbol = opt_currency_symbol.clone();
+		{
+			// Released before loading logfiles, which look up prices for payments
+			let mut web_prices = WEB_PRICES.lock().unwrap();
+			web_prices.set_currencies(&opt_currency_apiname, &opt_currency_symbol);
+			app.dash_state.currency_symbol = web_prices.currency_symbol.clone();
+		}
+		{
+			let opt = OPT.lock().unwrap();
+			if opt.idle_minutes > 0 {
+				app.dash_state.idle_timeout = Some(Duration::minutes(opt.idle_minutes as i64));
+			}
+			app.dash_state.idle_dim = opt.idle_dim;
+			app.dash_state.change_highlight = Duration::seconds(opt.highlight_changes as i64);
+			match NumberFormat::parse(&opt.number_format) {
+				Ok(number_format) => app.dash_state.number_format = number_format,
+				Err(e) => {
+					eprintln!("{}", e);
+					return exit_with_usage("invalid parameter");
+				}
+			}
+			if opt.quiet {
+				app.dash_state.vdash_status.log_instead_of_console();
+			}
+			app.anomaly_sampler = match AnomalySampler::new(opt.anomalies, &opt.anomalies_file) {
+				Ok(anomaly_sampler) => anomaly_sampler,
+				Err(e) => {
+					eprintln!("Unable to open --anomalies-file: {}", e);
+					return Err(e);
+				}
+			};
+			if opt.process_metrics {
+				app.process_sampler = Some(ProcessSampler::new());
+			}
+			if let Some(db_path) = &opt.db {
+				match MetricsDb::open(db_path, Duration::seconds(opt.db_interval.max(1))) {
+					Ok(metrics_db) => app.metrics_db = Some(metrics_db),
+					Err(e) => {
+						eprintln!("{}", e);
+						return exit_with_usage("invalid parameter");
+					}
+				}
+			}
+			if !opt.trigger.is_empty() {
+				match Triggers::new(&opt.trigger) {
+					Ok(triggers) => app.triggers = Some(triggers),
+					Err(e) => {
+						eprintln!("{}", e);
+						return exit_with_usage("invalid parameter");
+					}
+				}
+			}
+			if let Some(target) = &opt.metrics_push {
+				match PushFormat::parse(&opt.metrics_push_format).and_then(|format| MetricsPush::new(target, format)) {
+					Ok(metrics_push) => app.metrics_push = Some(metrics_push),
+					Err(e) => {
+						eprintln!("{}", e);
+						return exit_with_usage("invalid parameter");
+					}
+				}
+			}
+		}
 		if opt_currency_token_rate > 0.0 {
 			app.dash_state.currency_per_token = Some(opt_currency_token_rate);
 			app.dash_state.ui_uses_currency = true;
 		}
 
-		let mut web_prices = WEB_PRICES.lock().unwrap();
-		web_prices.currency_symbol = opt_currency_symbol;
-		web_prices.currency_apiname = opt_currency_apiname;
-
-		if opt_files.is_empty() && opt_globpaths.is_empty() {
+		if opt_files.is_empty()
+			&& opt_globpaths.is_empty()
+			&& opt_snapshot_globpaths.is_empty()
+			&& opt_remotes.is_empty()
+			&& opt_listen.is_none()
+			&& opt_journals.is_empty()
+		{
 			eprintln!(
 				"{}: no logfile(s) or 'glob' paths provided.",
 				Opt::clap().get_name()
@@ -131,10 +248,46 @@ impl App {
 			return exit_with_usage("invalid parameter");
 		}
 
-		let mut dash_state = DashState::new();
-		dash_state.debug_window = opt_debug_window;
+		{
+			let opt = OPT.lock().unwrap();
+			if let Some(Err(e)) = opt.group_by.as_ref().map(|group_by| GroupBy::parse(group_by)) {
+				eprintln!("{}", e);
+				return exit_with_usage("invalid parameter");
+			}
+			if let Err(e) = parse_custom_metrics(&opt.custom_metric) {
+				eprintln!("{}", e);
+				return exit_with_usage("invalid parameter");
+			}
+			if let Err(e) = parse_error_categories(&opt.error_category) {
+				eprintln!("{}", e);
+				return exit_with_usage("invalid parameter");
+			}
+			match Keymap::new(&opt.keymap, &opt.key) {
+				Ok(keymap) => app.dash_state.keymap = keymap,
+				Err(e) => {
+					eprintln!("{}", e);
+					return exit_with_usage("invalid parameter");
+				}
+			}
+			app.dash_state.summary_grouped = opt.group_by.is_some() || !opt.group.is_empty();
+		}
+
+		// Timelines can name custom metrics, which are read from OPT, so it must not be locked here
+		let (opt_timelines, opt_timeline_colour, opt_timescale) = {
+			let opt = OPT.lock().unwrap();
+			(opt.timelines.clone(), opt.timeline_colour.clone(), opt.timescale.clone())
+		};
+		if let Err(e) = timeline_order(&opt_timelines)
+			.and(timeline_colours(&opt_timeline_colour))
+			.and(parse_timescales(&opt_timescale))
+		{
+			eprintln!("{}", e);
+			return exit_with_usage("invalid parameter");
+		}
+
+		app.dash_state.debug_window = opt_debug_window;
 		if opt_debug_window {
-			dash_state.main_view = DashViewMain::DashDebug;
+			app.dash_state.main_view = DashViewMain::DashDebug;
 		}
 
 		let mut files_to_load = opt_files.clone();
@@ -145,8 +298,7 @@ impl App {
 				return exit_with_usage("missing logfile");
 			}
 
-			// For debug: only use first logfile, plus one for debug messages
-			files_to_load = opt_files[0..1].to_vec();
+			// Plus one for debug messages, shown by the Debug view
 			let debug_file = NamedTempFile::new()?;
 			let path = debug_file.path();
 			let path_str = path
@@ -176,6 +328,9 @@ impl App {
 		}
 
 		app.update_timelines(&Utc::now());
+		if OPT.lock().unwrap().replay {
+			app.start_replay();
+		}
 		app.update_summary_window();
 
 		if !app.logfile_with_focus.is_empty() {
@@ -187,8 +342,513 @@ impl App {
 		Ok(app)
 	}
 
+	/// Start entering a filter expression or tags for the node with focus
+	pub fn start_input_prompt(&mut self, kind: PromptKind) {
+		let text = match kind {
+			PromptKind::Filter => self
+				.dash_state
+				.node_filter
+				.as_ref()
+				.map_or(String::new(), |filter| filter.expression.clone()),
+			PromptKind::Tags => match self.get_monitor_with_focus() {
+				Some(monitor) if monitor.is_node() => monitor.tags.join(", "),
+				_ => return,
+			},
+			PromptKind::TimelineWindow | PromptKind::JumpToNode | PromptKind::Command => String::new(),
+		};
+		self.dash_state.input_prompt = Some(InputPrompt { kind, text });
+		self.show_input_prompt();
+	}
+
+	pub fn show_input_prompt(&mut self) {
+		if let Some(prompt) = &self.dash_state.input_prompt {
+			let label = match prompt.kind {
+				PromptKind::Filter => "Filter (e.g. tag:home AND status:connected)",
+				PromptKind::Tags => "Tags for node (comma separated)",
+				PromptKind::TimelineWindow => "Show timelines for (e.g. 14:00-16:00 yesterday, UTC)",
+				PromptKind::JumpToNode => "Jump to node (number, or part of its path or tags)",
+				PromptKind::Command => "Command (e.g. sort earnings desc, filter tag:home, export csv nodes.csv)",
+			};
+			let message = format!("{}: {}_", label, prompt.text);
+			self
+				.dash_state
+				.vdash_status
+				.message(&message, Some(Duration::seconds(INPUT_PROMPT_TIMEOUT_S)));
+		}
+	}
+
+	/// Apply the text entered at the prompt, or cancel it
+	pub fn finish_input_prompt(&mut self, apply: bool) {
+		let Some(prompt) = self.dash_state.input_prompt.take() else {
+			return;
+		};
+		if !apply {
+			self.dash_state.vdash_status.clear_status();
+			return;
+		}
+
+		match prompt.kind {
+			PromptKind::Filter => self.set_node_filter(&prompt.text),
+			PromptKind::Tags => {
+				if let Some(monitor) = self.get_monitor_with_focus() {
+					monitor.tags = parse_tags(&prompt.text);
+				}
+			}
+			PromptKind::TimelineWindow => self.set_timeline_window(&prompt.text),
+			PromptKind::JumpToNode => self.jump_to_node(&prompt.text),
+			PromptKind::Command => {} // Run by the keyboard handler, as commands may be async
+		}
+		self.update_summary_window();
+	}
+
+	/// Filter the Summary with the expression, or show all nodes if it is empty
+	pub fn set_node_filter(&mut self, expression: &str) {
+		if expression.trim().is_empty() {
+			self.dash_state.node_filter = None;
+			self.dash_state.vdash_status.message(&String::from("Filter cleared"), None);
+		} else {
+			match NodeFilter::parse(expression) {
+				Ok(node_filter) => {
+					self.dash_state.node_filter = Some(node_filter);
+					self.dash_state.vdash_status.clear_status();
+				}
+				Err(e) => {
+					self
+						.dash_state
+						.vdash_status
+						.message(&format!("Filter not changed, {}", e), None);
+				}
+			}
+		}
+		self.update_summary_window();
+	}
+
+	/// Delete checkpoints which couldn't be restored, so they are replaced by
+	/// ones built from the logfiles rather than reported at every start
+	pub fn delete_incompatible_checkpoints(&mut self) {
+		let logfiles = std::mem::take(&mut self.logfiles_manager.incompatible_checkpoints);
+		if logfiles.is_empty() {
+			return;
+		}
+
+		let mut deleted = 0;
+		for logfile in &logfiles {
+			if std::fs::remove_file(checkpoint_path(logfile)).is_ok() {
+				deleted += 1;
+			}
+			if let Some(monitor) = self.monitors.get_mut(logfile) {
+				monitor.latest_checkpoint_time = None; // Save a new checkpoint soon
+			}
+		}
+		self.dash_state.vdash_status.message(
+			&format!("Deleted {} of {} incompatible checkpoints", deleted, logfiles.len()),
+			None,
+		);
+	}
+
+	/// Replay the logfiles being monitored (see --replay), starting at their end
+	pub fn start_replay(&mut self) {
+		match Replay::load(&self.logfiles_manager.logfiles_added) {
+			Some(replay) => {
+				let end_time = replay.end_time;
+				self.dash_state.replay = Some(replay);
+				self.replay_to(end_time, true);
+			}
+			None => self.dash_state.vdash_status.message(
+				&String::from("Nothing to replay, no log entries found in the logfiles"),
+				None,
+			),
+		}
+	}
+
+	/// Show the monitors as they were at time, rebuilding them from the start
+	/// of their logfiles if rebuild is true (needed to go back in time)
+	pub fn replay_to(&mut self, time: DateTime<Utc>, rebuild: bool) {
+		let Some(mut replay) = self.dash_state.replay.take() else {
+			return;
+		};
+		let time = replay.clamp_time(time);
+
+		if rebuild {
+			replay.rewind();
+			// New timelines begin at the start of the logfiles
+			set_replay_time(Some(replay.start_time));
+			for replay_logfile in replay.logfiles.iter() {
+				if let Some(previous) = self.monitors.get(&replay_logfile.logfile) {
+					let mut monitor = LogMonitor::new(replay_logfile.logfile.clone());
+					monitor.index = previous.index;
+					monitor.tags = previous.tags.clone();
+					monitor.metrics.is_snapshot_source = previous.metrics.is_snapshot_source;
+					self.monitors.insert(replay_logfile.logfile.clone(), monitor);
+				}
+			}
+		}
+
+		set_replay_time(Some(time));
+		for replay_logfile in replay.logfiles.iter_mut() {
+			if let Some(monitor) = self.monitors.get_mut(&replay_logfile.logfile) {
+				for (_time, line) in replay_logfile.take_lines_until(&time) {
+					if let Err(e) = monitor.append_to_content_from_time(line, None) {
+						self.dash_state.vdash_status.message(&e.to_string(), None);
+					}
+				}
+				monitor.metrics.update_node_status_string();
+			}
+		}
+		replay.time = time;
+		self.dash_state.replay = Some(replay);
+
+		self.update_timelines(&time);
+		self.update_view_for_new_line();
+	}
+
+	/// Move the replay time by one column of the active timeline
+	pub fn replay_step(&mut self, forward: bool) {
+		let Some(replay) = &self.dash_state.replay else {
+			return;
+		};
+		let step = TIMESCALES
+			.get(self.dash_state.active_timescale)
+			.map_or(Duration::seconds(1), |(_name, duration)| *duration);
+		if forward {
+			self.replay_to(replay.time + step, false);
+		} else {
+			self.replay_to(replay.time - step, true);
+		}
+	}
+
+	/// Start or stop playing the replay, starting again if at the end
+	pub fn toggle_replay_playing(&mut self) {
+		let Some(replay) = &mut self.dash_state.replay else {
+			return;
+		};
+		replay.playing = !replay.playing;
+		if replay.playing && replay.time >= replay.end_time {
+			let start_time = replay.start_time;
+			self.replay_to(start_time, true);
+		}
+	}
+
+	/// Advance the timelines to now, or when playing a replay, by a step
+	pub fn update_timelines_on_tick(&mut self) {
+		match &mut self.dash_state.replay {
+			Some(replay) if replay.playing => {
+				if replay.time >= replay.end_time {
+					replay.playing = false;
+				} else {
+					self.replay_step(true);
+				}
+			}
+			Some(_) => {}
+			None => self.update_timelines(&Utc::now()),
+		}
+	}
+
+	/// Everything done once per tick, whether the tick is handled alone or
+	/// after a burst of keys
+	pub async fn on_tick(&mut self) {
+		self.update_timelines_on_tick();
+		self.expire_confirmations(&Utc::now());
+		self.note_session_start();
+		self.scan_glob_paths(true, true).await;
+		self.reattach_logfiles().await;
+		self.prune_stale_nodes();
+		self.poll_wallets();
+		self.poll_disks();
+		self.poll_store_sizes();
+		self.sample_processes();
+		self.check_processes();
+		self.report_background_results();
+		self.write_events();
+		self.run_triggers();
+		self.write_history();
+		self.push_metrics();
+	}
+
+	/// Refresh the active view after a new line has been added to a node's monitor
+	pub fn update_view_for_new_line(&mut self) {
+		match self.dash_state.main_view {
+			DashViewMain::DashSummary => self.update_summary_window(),
+			DashViewMain::DashEarnings => self.update_earnings_window(),
+			DashViewMain::DashFleet => self.update_fleet_window(),
+			_ => {}
+		}
+	}
+
+	/// Refresh the active view if lines have arrived since it was last refreshed. New
+	/// lines only mark the view outdated, so that however many arrive it is rebuilt
+	/// once before each frame is drawn.
+	pub fn update_view_if_outdated(&mut self) {
+		if self.view_outdated {
+			self.view_outdated = false;
+			self.update_view_for_new_line();
+		}
+	}
+
+	/// Apply lines from the logfiles followed by linemux once they have been parsed
+	/// (see ParsePipeline), leaving the view to be updated before the next frame
+	pub fn apply_parsed_lines(&mut self, parsed_lines: Vec<ParsedLine>, checkpoint_interval: u64) {
+		profile_scope!("apply_parsed_lines");
+		let mut update_view = false;
+		for parsed in parsed_lines {
+			let mut checkpoint_result: Result<String, std::io::Error> = Ok("".to_string());
+			// Removed nodes are still followed by linemux
+			let is_removed = self.logfiles_manager.logfiles_removed.contains(&parsed.source);
+			match self.get_monitor_for_file_path(&parsed.source) {
+				Some(monitor) => {
+					checkpoint_result =
+						monitor.append_parsed_line(&parsed.line, parsed.metadata, &parsed.patterns, checkpoint_interval);
+					if !monitor.is_debug_dashboard_log {
+						update_view = true;
+					}
+					if let Some(parser_output) = monitor.record_parser_output() {
+						self.dash_state._debug_window(&parser_output);
+					}
+				}
+				None if is_removed => {}
+				None => {
+					self.dash_state._debug_window(format!("NO MONITOR FOR: {}", parsed.source).as_str());
+				}
+			}
+			self.sample_anomaly(&parsed.source, &parsed.line);
+			match checkpoint_result {
+				Ok(message) => {
+					if message.len() > 0 {
+						self.dash_state.vdash_status.message(&message, None);
+					}
+				}
+				Err(e) => {
+					self.dash_state.vdash_status.message(&e.to_string(), None);
+				}
+			}
+		}
+		if update_view {
+			self.view_outdated = true;
+		}
+	}
+
+	/// Handle output from a remote source (see --remote and --listen), adding a
+	/// monitor for each remote logfile as its first line arrives
+	pub fn handle_remote_event(&mut self, event: RemoteEvent) {
+		match event {
+			RemoteEvent::Line { logfile, line } => {
+				if !self.monitors.contains_key(&logfile) {
+					let mut monitor = LogMonitor::new(logfile.clone());
+					monitor.is_remote = true;
+					monitor.canonicalise_monitor_index(&mut self.monitors);
+					self.monitors.insert(logfile.clone(), monitor);
+					self.logfiles_manager.logfiles_added.push(logfile.clone());
+					if self.logfile_with_focus.is_empty() {
+						self.set_logfile_with_focus(logfile.clone());
+						self.dash_state.dash_node_focus = logfile.clone();
+					}
+				}
+
+				if let Some(monitor) = self.monitors.get_mut(&logfile) {
+					// Checkpoints are not kept for remote logfiles
+					if let Err(e) = monitor.append_to_content(&line, 0) {
+						self.dash_state.vdash_status.message(&e.to_string(), None);
+					}
+				}
+				self.sample_anomaly(&logfile, &line);
+				self.view_outdated = true;
+			}
+			RemoteEvent::Closed { destination, message } => {
+				self
+					.dash_state
+					.vdash_status
+					.message(&format!("Remote {}: {}", destination, message), None);
+			}
+		}
+	}
+
+	/// Poll the wallet balance file of each node every --wallet-poll seconds
+	pub fn poll_wallets(&mut self) {
+		let (opt_wallet_poll, opt_wallet_filename) = {
+			let opt = OPT.lock().unwrap();
+			(opt.wallet_poll, opt.wallet_filename.clone())
+		};
+		if opt_wallet_poll <= 0 || self.dash_state.replay.is_some() {
+			return;
+		}
+
+		let current_time = Utc::now();
+		if let Some(next_wallet_poll) = self.next_wallet_poll {
+			if current_time < next_wallet_poll {
+				return;
+			}
+		}
+		self.next_wallet_poll = Some(current_time + Duration::seconds(opt_wallet_poll));
+
+		let mut mismatch_message: Option<String> = None;
+		for (logfile, monitor) in self.monitors.iter_mut() {
+			if !monitor.is_node() || monitor.is_remote {
+				continue;
+			}
+			if let Some(wallet_path) = WalletWatch::wallet_path(logfile, &opt_wallet_filename) {
+				let earnings_total = monitor.metrics.attos_earned.total;
+				if let Some(change) = monitor
+					.metrics
+					.wallet_watch
+					.poll(&wallet_path, &current_time, earnings_total)
+				{
+					if change.unexplained > 0 {
+						mismatch_message = Some(format!(
+							"Node {} wallet increased by {} attos more than logged earnings",
+							monitor.index + 1,
+							change.unexplained
+						));
+					}
+				}
+			}
+		}
+
+		if let Some(message) = mismatch_message {
+			self.dash_state.vdash_status.message(&message, None);
+		}
+	}
+
+	/// Check the free space of each node's device every --disk-poll seconds
+	pub fn poll_disks(&mut self) {
+		let (opt_disk_poll, opt_disk_free_alert) = {
+			let opt = OPT.lock().unwrap();
+			(opt.disk_poll, opt.disk_free_alert)
+		};
+		if opt_disk_poll <= 0 || self.dash_state.replay.is_some() {
+			return;
+		}
+
+		let current_time = Utc::now();
+		if let Some(next_disk_poll) = self.next_disk_poll {
+			if current_time < next_disk_poll {
+				return;
+			}
+		}
+		self.next_disk_poll = Some(current_time + Duration::seconds(opt_disk_poll));
+
+		let mut low_message: Option<String> = None;
+		for (logfile, monitor) in self.monitors.iter_mut() {
+			if !monitor.is_node() || monitor.is_remote || !Path::new(logfile).exists() {
+				continue;
+			}
+			if let Some(data_dir) = DiskSpace::data_dir(logfile) {
+				let disk_space = &mut monitor.metrics.disk_space;
+				if disk_space.poll(&data_dir, opt_disk_free_alert) {
+					low_message = Some(format!(
+						"Node {} disk free space {:.1}% is below {}%",
+						monitor.index + 1,
+						disk_space.free_percent().unwrap_or(0.0),
+						opt_disk_free_alert
+					));
+				}
+			}
+		}
+
+		if let Some(message) = low_message {
+			self.dash_state.vdash_status.message(&message, None);
+		}
+	}
+
+	/// Sum the size of a node's record store, scanning the next node each time so that
+	/// every node is scanned once per --store-poll seconds
+	pub fn poll_store_sizes(&mut self) {
+		let (opt_store_poll, opt_store_dirname) = {
+			let opt = OPT.lock().unwrap();
+			(opt.store_poll, opt.store_dirname.clone())
+		};
+		if opt_store_poll <= 0 || self.dash_state.replay.is_some() {
+			return;
+		}
+
+		let current_time = Utc::now();
+		if let Some(next_store_poll) = self.next_store_poll {
+			if current_time < next_store_poll {
+				return;
+			}
+		}
+		let mut logfiles: Vec<&String> = self
+			.monitors
+			.iter()
+			.filter(|(logfile, monitor)| monitor.is_node() && !monitor.is_remote && Path::new(logfile).exists())
+			.map(|(logfile, _monitor)| logfile)
+			.collect();
+		if logfiles.is_empty() {
+			return;
+		}
+		logfiles.sort();
+		let interval_ms = opt_store_poll * 1000 / logfiles.len() as i64;
+		self.next_store_poll = Some(current_time + Duration::milliseconds(interval_ms));
+
+		let next_node = self.store_poll_next_node % logfiles.len();
+		self.store_poll_next_node = next_node + 1;
+		let logfile = logfiles[next_node].clone();
+		if let (Some(store_dir), Some(monitor)) =
+			(StoreSize::store_dir(&logfile, &opt_store_dirname), self.monitors.get_mut(&logfile))
+		{
+			monitor.metrics.store_size.poll(&store_dir);
+		}
+	}
+
+	/// Sample the CPU and memory of local node processes whose logfiles have no recent
+	/// metrics lines (see --process-metrics)
+	pub fn sample_processes(&mut self) {
+		let Some(process_sampler) = &mut self.process_sampler else {
+			return;
+		};
+		let current_time = Utc::now();
+		if self.dash_state.replay.is_some() || !process_sampler.is_due(&current_time, SAMPLE_INTERVAL_S) {
+			return;
+		}
+
+		for monitor in self.monitors.values_mut() {
+			if !monitor.is_node() || monitor.is_remote || monitor.metrics.process_dead {
+				continue;
+			}
+			let Some(process_id) = monitor.metrics.node_process_id else {
+				continue;
+			};
+			if !needs_sampling(monitor.metrics.latest_logged_metrics, &current_time) {
+				continue;
+			}
+			if let Some(sample) = process_sampler.sample(process_id) {
+				monitor.metrics.apply_process_sample(&current_time, &sample);
+			}
+		}
+	}
+
+	/// Check every --pid-check seconds that the process of each local node is still running
+	pub fn check_processes(&mut self) {
+		let opt_pid_check = OPT.lock().unwrap().pid_check;
+		let current_time = Utc::now();
+		if opt_pid_check <= 0
+			|| self.dash_state.replay.is_some()
+			|| !self.process_checker.is_due(&current_time, opt_pid_check)
+		{
+			return;
+		}
+
+		let mut changed = false;
+		for monitor in self.monitors.values_mut() {
+			if !monitor.is_node() || monitor.is_remote || monitor.metrics.is_snapshot_source {
+				continue;
+			}
+			let Some(process_id) = monitor.metrics.node_process_id else {
+				continue;
+			};
+			let process_dead = !self.process_checker.is_running(process_id);
+			if process_dead != monitor.metrics.process_dead {
+				monitor.metrics.process_dead = process_dead;
+				monitor.metrics.update_node_status_string();
+				changed = true;
+			}
+		}
+		if changed {
+			self.update_view_for_new_line();
+		}
+	}
+
 	pub async fn scan_glob_paths(&mut self, timed: bool, disable_status: bool) {
-		if self.logfiles_manager.globpaths.len() == 0 {
+		if self.logfiles_manager.globpaths.len() == 0 || self.dash_state.replay.is_some() {
 			return;
 		}
 		let opt_globs_scan = OPT.lock().unwrap().glob_scan;
@@ -208,7 +868,10 @@ impl App {
 		}
 
 		if do_scan {
-			let opt_glob_paths = OPT.lock().unwrap().glob_paths.clone();
+			let opt_glob_paths = {
+				let opt = OPT.lock().unwrap();
+				[opt.glob_paths.clone(), opt.snapshot_glob_path.clone()].concat()
+			};
 			self
 				.logfiles_manager
 				.scan_multi_globpaths(
@@ -221,9 +884,34 @@ impl App {
 		}
 	}
 
+	/// Monitor logfiles matching another glob path, as if given with --glob-path. A leading
+	/// '~' is taken to be the home directory.
+	pub async fn add_glob_path(&mut self, glob: &str) {
+		let glob = match (glob.strip_prefix('~'), std::env::var("HOME")) {
+			(Some(rest), Ok(home)) => format!("{}{}", home, rest),
+			_ => glob.to_string(),
+		};
+		if let Err(e) = glob::Pattern::new(&glob) {
+			self.dash_state.vdash_status.message(&format!("Invalid glob path '{}': {}", glob, e), None);
+			return;
+		}
+		OPT.lock().unwrap().glob_paths.push(glob.clone());
+		self.logfiles_manager.globpaths.push(glob.clone());
+
+		let nodes_before = self.logfiles_manager.logfiles_added.len();
+		self.scan_glob_paths(false, true).await;
+		let added = self.logfiles_manager.logfiles_added.len() - nodes_before;
+		self.dash_state.vdash_status.message(&format!("Added {} logfiles matching {}", added, glob), None);
+		self.update_summary_window();
+	}
+
 	pub fn update_timelines(&mut self, now: &DateTime<Utc>) {
+		profile_scope!("update_timelines");
 		for (_monitor_file, monitor) in self.monitors.iter_mut() {
 			monitor.metrics.update_timelines(now);
+			if let Some(generic) = &mut monitor.generic {
+				generic.lines_timeline.update_current_time(now);
+			}
 		}
 	}
 
@@ -276,7 +964,7 @@ impl App {
 
 		match self.get_monitor_with_focus() {
 			Some(fading_monitor) => {
-				fading_monitor.has_focus = false;
+				fading_monitor.lose_focus();
 				self.logfile_with_focus = String::new();
 			}
 			None => (),
@@ -290,8 +978,13 @@ impl App {
 			self.dash_state.debug_window_has_focus = false;
 		}
 
+		// A replay shows the content as it was, so it isn't replaced by the end of the logfile
+		let load_tail = self.dash_state.replay.is_none();
 		if let Some(focus_monitor) = (&mut self.monitors).get_mut(&logfile_name) {
-			focus_monitor.has_focus = true;
+			focus_monitor.gain_focus(load_tail);
+			if let Some(debug_lines) = &focus_monitor.debug_lines {
+				self.dash_state.show_debug_lines(debug_lines);
+			}
 			self.logfile_with_focus = logfile_name.clone();
 		} else {
 			error!("Unable to focus UI on: {}", logfile_name);
@@ -317,6 +1010,7 @@ impl App {
 				< self.dash_state.summary_window_headings.items.len() - 1
 			{
 				self.dash_state.summary_window_heading_selected += 1;
+				self.save_sort_preference();
 				self.update_summary_window();
 			}
 		}
@@ -363,6 +1057,7 @@ impl App {
 		if self.dash_state.main_view == DashViewMain::DashSummary {
 			if self.dash_state.summary_window_heading_selected > 0 {
 				self.dash_state.summary_window_heading_selected -= 1;
+				self.save_sort_preference();
 				self.update_summary_window();
 			}
 		}
@@ -400,6 +1095,74 @@ impl App {
 		}
 	}
 
+	/// Show the node numbered text, or else best matching part of its logfile path or tags, in the Node view
+	pub fn jump_to_node(&mut self, text: &str) {
+		let nodes = self
+			.monitors
+			.iter()
+			.filter(|(_, monitor)| monitor.is_node())
+			.map(|(logfile, monitor)| (logfile.as_str(), monitor.index, monitor.tags.as_slice()));
+		let Some(logfile) = find_node(text, nodes) else {
+			self
+				.dash_state
+				.vdash_status
+				.message(&format!("No node matches '{}'", text.trim()), None);
+			return;
+		};
+
+		self.dash_state.vdash_status.clear_status();
+		set_main_view(DashViewMain::DashNode, self);
+		self.dash_state.dash_node_focus = logfile.clone();
+		self.set_logfile_with_focus(logfile);
+	}
+
+	/// Switch between the Summary alone and the Summary above the node of its selected row
+	pub fn toggle_split_view(&mut self) {
+		self.dash_state.split_view = !self.dash_state.split_view;
+		if self.dash_state.split_view {
+			if self.dash_state.main_view == DashViewMain::DashNode {
+				self.preserve_node_selection();
+			}
+			set_main_view(DashViewMain::DashSummary, self);
+			self.focus_summary_selection();
+		}
+	}
+
+	/// In the split view, focus the node of the selected Summary row so it is shown below
+	pub fn focus_summary_selection(&mut self) {
+		if !self.dash_state.is_split_view() {
+			return;
+		}
+		let Some(selected) = self.dash_state.summary_window_rows.state.selected() else {
+			return;
+		};
+		if let Some(logfile) = self.dash_state.logfile_names_sorted.get(selected).cloned() {
+			if logfile != self.logfile_with_focus && self.monitors.contains_key(&logfile) {
+				self.set_logfile_with_focus(logfile);
+			}
+		}
+	}
+
+	/// Move the Summary selection a page up or down, as shown when last drawn
+	pub fn summary_page(&mut self, down: bool) {
+		let page_rows = self.dash_state.summary_window_page_rows.max(1);
+		let selected = self.dash_state.summary_window_rows.state.selected().unwrap_or(0);
+		let selected = if down { selected + page_rows } else { selected.saturating_sub(page_rows) };
+		self.summary_select_row(selected);
+	}
+
+	/// Select the first or last row of the Summary
+	pub fn summary_select_end(&mut self, last: bool) {
+		self.summary_select_row(if last { usize::MAX } else { 0 });
+	}
+
+	fn summary_select_row(&mut self, row: usize) {
+		let len = self.dash_state.summary_window_rows.items.len();
+		if len > 0 {
+			self.dash_state.summary_window_rows.state.select(Some(row.min(len - 1)));
+		}
+	}
+
 	pub fn handle_arrow_up(&mut self) {
 		self.handle_arrow(false);
 	}
@@ -409,6 +1172,10 @@ impl App {
 	}
 
 	pub fn handle_arrow(&mut self, is_down: bool) {
+		if self.dash_state.main_view == DashViewMain::DashHelp {
+			self.scroll_help(if is_down { 1 } else { -1 });
+			return;
+		}
 		if self.logfiles_manager.logfiles_added.len() == 0 {
 			return;
 		}
@@ -430,6 +1197,8 @@ impl App {
 				}
 			}
 			DashViewMain::DashHelp => None,
+			DashViewMain::DashEarnings => Some(&mut self.dash_state.earnings_window_rows),
+			DashViewMain::DashFleet => Some(&mut self.dash_state.fleet_window_rows),
 			DashViewMain::DashDebug => {
 				if opt_debug_window {
 					Some(&mut self.dash_state.debug_window_list)
@@ -444,6 +1213,12 @@ impl App {
 		}
 	}
 
+	/// Scroll Help by rows, which is kept from going past the end when drawn
+	pub fn scroll_help(&mut self, rows: isize) {
+		let state = &mut self.dash_state.help_status.state;
+		*state.offset_mut() = state.offset().saturating_add_signed(rows);
+	}
+
 	pub fn preserve_node_selection(&mut self) {
 		if self.logfiles_manager.logfiles_added.len() == 0 {
 			return;
@@ -494,6 +1269,7 @@ impl App {
 	// TODO this regenerates every line. May be worth just updating the line for the updated node/monitor
 	// Needs to be on the app to manage focus for DashSummary and DashNode through sorting of summary table
 	pub fn update_summary_window(&mut self) {
+		profile_scope!("update_summary_window");
 		let current_selection = self.dash_state.summary_window_rows.state.selected();
 
 		self.dash_state.summary_window_rows = StatefulList::new();
@@ -505,12 +1281,31 @@ impl App {
 			.iter()
 			.map(|f| f.clone())
 			.collect();
+		if let Some(node_filter) = &self.dash_state.node_filter {
+			let monitors = &self.monitors;
+			self
+				.dash_state
+				.logfile_names_sorted
+				.retain(|logfile| monitors.get(logfile).is_some_and(|monitor| node_filter.matches(monitor)));
+		}
 
 		super::ui_summary_table::sort_nodes_by_column(&mut self.dash_state, &mut self.monitors);
+		if self.dash_state.summary_grouped {
+			let (rows, group_totals) = group_rows(
+				&self.dash_state.logfile_names_sorted,
+				&self.monitors,
+				&self.dash_state.collapsed_groups,
+			);
+			self.dash_state.logfile_names_sorted = rows;
+			self.dash_state.group_totals = group_totals;
+		}
 
 		for i in 0..self.dash_state.logfile_names_sorted.len() {
 			let filepath = self.dash_state.logfile_names_sorted[i].clone();
-			if let Some(monitor) = self.monitors.get_mut(&filepath) {
+			if let Some(group) = group_of_row_key(&filepath) {
+				let group_summary = super::ui_summary_table::format_group_row(&self.dash_state, group);
+				self.append_to_summary_window(&group_summary);
+			} else if let Some(monitor) = self.monitors.get_mut(&filepath) {
 				if !monitor.is_debug_dashboard_log {
 					monitor.metrics.update_node_status_string();
 					let node_summary = super::ui_summary_table::format_table_row(&self.dash_state, monitor);
@@ -519,1108 +1314,803 @@ impl App {
 			}
 		}
 
+		let len = self.dash_state.summary_window_rows.items.len();
 		self
 			.dash_state
 			.summary_window_rows
 			.state
-			.select(current_selection);
+			.select(current_selection.filter(|selected| *selected < len));
 	}
 
-	fn append_to_summary_window(&mut self, text: &str) {
-		self
-			.dash_state
-			.summary_window_rows
-			.items
-			.push(text.to_string());
+	/// Ask the user to confirm an antctl action on the node with focus
+	pub fn request_node_action(&mut self, action: NodeAction) {
+		if OPT.lock().unwrap().antctl_command.is_none() {
+			self.dash_state.vdash_status.message(
+				&String::from("Node control needs --antctl-command (e.g. --antctl-command antctl)"),
+				None,
+			);
+			return;
+		}
 
-		let len = self.dash_state.summary_window_rows.items.len();
+		let (node_index, logfile) = match self.get_monitor_with_focus() {
+			Some(monitor) if monitor.is_remote => {
+				let message = format!("{} is not supported for remote node {}", action.name(), monitor.index + 1);
+				self.dash_state.vdash_status.message(&message, None);
+				return;
+			}
+			Some(monitor) if monitor.is_node() => (monitor.index, monitor.logfile.clone()),
+			_ => return,
+		};
 
-		if len > self.dash_state.max_summary_window {
-			self.dash_state.summary_window_rows.items = self
-				.dash_state
-				.summary_window_rows
-				.items
-				.split_off(len - self.dash_state.max_summary_window);
-		} else {
-			self
-				.dash_state
-				.summary_window_rows
-				.state
-				.select(Some(len - 1));
+		match super::antctl::service_name_for_logfile(&logfile) {
+			Some(service_name) => {
+				let timeout = Duration::seconds(NODE_ACTION_CONFIRM_TIMEOUT_S);
+				self.dash_state.vdash_status.message(
+					&format!(
+						"{} {} (node {})? Press 'y' to confirm, any other key to cancel",
+						action.name(),
+						service_name,
+						node_index + 1
+					),
+					Some(timeout),
+				);
+				self.dash_state.pending_node_action = Some(PendingNodeAction {
+					action,
+					service_name,
+					node_index,
+					expires: Utc::now() + timeout,
+				});
+			}
+			None => self.dash_state.vdash_status.message(
+				&format!("No antctl service found for {}", logfile),
+				None,
+			),
 		}
 	}
 
-	pub fn toggle_logfile_area(&mut self) {
-		self.dash_state.node_logfile_visible = !self.dash_state.node_logfile_visible;
-	}
-
-	pub fn scale_timeline_up(&mut self) {
-		if self.dash_state.active_timescale == 0 {
-			return;
+	/// Cancel any confirmation whose prompt has timed out, so that a later key isn't taken as the answer
+	pub fn expire_confirmations(&mut self, now: &DateTime<Utc>) {
+		if self.dash_state.pending_node_action.as_ref().is_some_and(|pending| pending.expires <= *now) {
+			self.dash_state.pending_node_action = None;
+		}
+		if self.dash_state.pending_node_removal.as_ref().is_some_and(|(_, expires)| expires <= now) {
+			self.dash_state.pending_node_removal = None;
 		}
-		self.dash_state.active_timescale -= 1;
 	}
 
-	pub fn scale_timeline_down(&mut self) {
-		if self.dash_state.active_timescale == TIMESCALES.len() - 1 {
+	/// Run or cancel the action awaiting confirmation
+	pub fn confirm_node_action(&mut self, confirmed: bool) {
+		let Some(pending) = self.dash_state.pending_node_action.take() else {
 			return;
-		}
-		self.dash_state.active_timescale += 1;
-	}
+		};
 
-	pub fn top_timeline_next(&mut self) {
-		if self.dash_state.top_timeline < APP_TIMELINES.len() {
-			self.dash_state.top_timeline += 1;
-		} else {
-			self.dash_state.top_timeline = 0;
+		let opt_antctl_command = OPT.lock().unwrap().antctl_command.clone();
+		match opt_antctl_command {
+			Some(antctl_command) if confirmed => {
+				self.dash_state.vdash_status.message(
+					&format!("{} of {} requested...", pending.action.name(), pending.service_name),
+					None,
+				);
+				super::antctl::spawn_node_action(&antctl_command, pending);
+			}
+			_ => self.dash_state.vdash_status.message(
+				&format!("{} of {} cancelled", pending.action.name(), pending.service_name),
+				None,
+			),
 		}
 	}
 
-	pub fn top_timeline_previous(&mut self) {
-		if self.dash_state.top_timeline > 0 {
-			self.dash_state.top_timeline -= 1;
-		} else {
-			self.dash_state.top_timeline = APP_TIMELINES.len() - 1;
-		}
+	/// Reverse the order of the Summary's selected column
+	pub fn toggle_sort_direction(&mut self) {
+		self.dash_state.logfile_names_sorted_ascending = !self.dash_state.logfile_names_sorted_ascending;
+		self.save_sort_preference();
+		self.update_summary_window();
 	}
 
-	// Rotate UI display state through Min, Mean, Max values
-	pub fn bump_mmm_ui_mode(&mut self) {
-		self.dash_state.bump_mmm_ui_mode();
+	/// Sort the Summary by a column, as if it had been selected
+	pub fn sort_summary_by(&mut self, column: usize, ascending: bool) {
+		self.dash_state.summary_window_heading_selected = column;
+		self.dash_state.logfile_names_sorted_ascending = ascending;
+		self.save_sort_preference();
+		self.update_summary_window();
 	}
 
-	pub fn mmm_ui_mode(&mut self) -> &MinMeanMax {
-		return self.dash_state.mmm_ui_mode();
-	}
-}
-
-/// Move selection forward or back without wrapping at start or end
-fn do_bracketed_next_previous(list: &mut StatefulList<String>, next: bool) {
-	if next {
-		if let Some(selected) = list.state.selected() {
-			if selected != list.items.len() - 1 {
-				list.next();
+	/// Keep the order of the selected Summary column for nodes which are equal in the
+	/// column selected next, or stop using it if already kept
+	pub fn keep_sort_key(&mut self) {
+		let column = self.dash_state.summary_window_heading_selected;
+		let ascending = self.dash_state.logfile_names_sorted_ascending;
+		let heading = super::ui_summary_table::SUMMARY_COLUMNS[column].1.clone();
+		let then_by = &mut self.dash_state.summary_sort_then_by;
+		let message = match then_by.iter().position(|(then_by_column, _ascending)| *then_by_column == column) {
+			Some(index) => {
+				then_by.remove(index);
+				format!("No longer sorting by {}", heading)
 			}
-		} else {
-			list.previous();
-		}
-	} else {
-		if let Some(selected) = list.state.selected() {
-			if selected != 0 {
-				list.previous();
+			None => {
+				then_by.insert(0, (column, ascending));
+				format!("Sorting ties by {} {}", heading, if ascending { "ascending" } else { "descending" })
 			}
-		} else {
-			list.previous();
-		}
+		};
+		self.dash_state.vdash_status.message(&message, None);
+		self.save_sort_preference();
+		self.update_summary_window();
 	}
-}
 
-fn exit_with_usage(reason: &str) -> Result<App, std::io::Error> {
-	eprintln!(
-		"Try '{} --help' for more information.",
-		Opt::clap().get_name()
-	);
-	return Err(Error::new(ErrorKind::Other, reason));
-}
+	fn save_sort_preference(&mut self) {
+		self.preferences.summary_sort = super::ui_summary_table::sort_preference(&self.dash_state);
+		if let Err(e) = self.preferences.save() {
+			self.dash_state.vdash_status.message(&format!("Unable to save preferences: {}", e), None);
+		}
+	}
 
-const NODE_INACTIVITY_TIMEOUT_S: i64 = 20; // Seconds with no log message before node becomes 'inactive'
-
-pub struct LogMonitor {
-	pub index: usize,
-	pub content: StatefulList<String>,
-	max_content: usize, // Limit number of lines in content
-	pub has_focus: bool,
-	pub logfile: String,
-	pub metrics: NodeMetrics,
-	pub metrics_status: StatefulList<String>,
-	pub is_debug_dashboard_log: bool,
-	pub latest_checkpoint_time: Option<DateTime<Utc>>,
-}
+	/// Show or hide groups in the Summary
+	/// List payments in the Earnings view one by one, or totalled by day or week
+	pub fn next_earnings_period(&mut self) {
+		self.dash_state.earnings_period = self.dash_state.earnings_period.next();
+		self.dash_state.earnings_window_rows.state.select(None);
+		self.update_earnings_window();
+	}
 
-use std::sync::atomic::{AtomicUsize, Ordering};
-static NEXT_MONITOR: AtomicUsize = AtomicUsize::new(0);
+	/// Show counts in the Summary since the session started, or totals again
+	pub fn toggle_session_delta(&mut self) {
+		self.dash_state.session_delta = !self.dash_state.session_delta;
+		self.update_summary_window();
+	}
 
-fn next_unused_index(monitors: &mut HashMap<String, LogMonitor>) -> usize {
-	let mut next_index = 0;
+	/// Note the counts of nodes seen for the first time, from which the session deltas are counted
+	pub fn note_session_start(&mut self) {
+		for monitor in self.monitors.values_mut().filter(|monitor| monitor.is_node() && monitor.session_start.is_none()) {
+			monitor.session_start = Some(SessionCounts::of(&monitor.metrics));
+		}
+	}
 
-	let mut index_unused = false;
-	while !index_unused {
-		next_index = NEXT_MONITOR.fetch_add(1, Ordering::Relaxed);
+	/// Write the --session-report, if one was asked for, returning a message saying where
+	pub fn write_session_report(&self) -> Option<Result<String, String>> {
+		let path = OPT.lock().unwrap().session_report.clone()?;
+		Some(
+			write_session_report(&path, &self.monitors, &self.dash_state.session_started, &Utc::now())
+				.map(|()| format!("Session report written to {}", path))
+				.map_err(|e| format!("Failed to write session report to {}: {}", path, e)),
+		)
+	}
 
-		index_unused = true;
-		for (_logfile, monitor) in monitors.iter() {
-			if next_index == monitor.index {
-				index_unused = false;
-			}
+	/// The --parser-stats report, if one was asked for
+	pub fn parser_stats_report(&self) -> Option<String> {
+		if !OPT.lock().unwrap().parser_stats {
+			return None;
 		}
+		Some(parser_stats_report(&self.monitors))
 	}
 
-	next_index
-}
+	pub fn toggle_summary_groups(&mut self) {
+		self.dash_state.summary_grouped = !self.dash_state.summary_grouped;
+		self.update_summary_window();
+	}
 
-use super::logfile_checkpoints::LogfileCheckpoint;
+	/// Collapse or expand the group whose header is selected in the Summary, returning
+	/// false if a node is selected instead
+	pub fn toggle_selected_group(&mut self) -> bool {
+		let selected_group = self
+			.dash_state
+			.summary_window_rows
+			.state
+			.selected()
+			.and_then(|selected| self.dash_state.logfile_names_sorted.get(selected))
+			.and_then(|key| group_of_row_key(key))
+			.map(|group| group.to_string());
+		let Some(group) = selected_group else {
+			return false;
+		};
 
-impl LogMonitor {
-	pub fn new(logfile_path: String) -> LogMonitor {
-		let mut is_debug_dashboard_log = false;
-		if let Some(debug_logfile) = &*DEBUG_LOGFILE.lock().unwrap() {
-			if let Some(debug_logfile_path) = debug_logfile.path().to_str() {
-				is_debug_dashboard_log = logfile_path.eq(debug_logfile_path);
+		let collapsed_groups = &mut self.dash_state.collapsed_groups;
+		match collapsed_groups.iter().position(|collapsed| *collapsed == group) {
+			Some(index) => {
+				collapsed_groups.remove(index);
 			}
+			None => collapsed_groups.push(group),
 		}
-
-		let opt_lines_max = { OPT.lock().unwrap().lines_max };
-		LogMonitor {
-			index: 0,
-			logfile: logfile_path,
-			max_content: opt_lines_max,
-			metrics: NodeMetrics::new(),
-			content: StatefulList::with_items(vec![]),
-			has_focus: false,
-			metrics_status: StatefulList::with_items(vec![]),
-			is_debug_dashboard_log,
-			latest_checkpoint_time: None,
-		}
+		self.update_summary_window();
+		true
 	}
 
-	/// Resolve any clash between self.index and index of other monitors which may happen
-	/// when mixing creation of new monitors with initialisation by restoring a checkpoint.
-	///
-	/// For a restored checkpoint the metrics should be set, so if one has metrics and the other
-	/// doesn't, the former is treated as older and given the lower index.
-	pub fn canonicalise_monitor_index(&mut self, monitors: &mut HashMap<String, LogMonitor>) {
-		if self.index == 0 {
-			self.index = NEXT_MONITOR.fetch_add(1, Ordering::Relaxed);
-		}
+	/// Ask the user to confirm removal of the node with focus
+	pub fn request_node_removal(&mut self) {
+		let (node_index, logfile) = match self.get_monitor_with_focus() {
+			Some(monitor) if monitor.is_node() => (monitor.index, monitor.logfile.clone()),
+			_ => return,
+		};
+		let timeout = Duration::seconds(NODE_ACTION_CONFIRM_TIMEOUT_S);
+		self.dash_state.vdash_status.message(
+			&format!(
+				"Remove node {} and delete its checkpoint ({})? Press 'y' to confirm, any other key to cancel",
+				node_index + 1,
+				logfile
+			),
+			Some(timeout),
+		);
+		self.dash_state.pending_node_removal = Some((logfile, Utc::now() + timeout));
+	}
 
-		let existing_index = NEXT_MONITOR.fetch_add(0, Ordering::Relaxed);
-		let next_index = next_unused_index(monitors);
+	/// Remove or keep the node awaiting confirmation
+	pub fn confirm_node_removal(&mut self, confirmed: bool) {
+		let Some((logfile, _)) = self.dash_state.pending_node_removal.take() else {
+			return;
+		};
+		let message = if confirmed {
+			self.remove_node(&logfile, true);
+			format!("Removed {}", logfile)
+		} else {
+			format!("Removal of {} cancelled", logfile)
+		};
+		self.dash_state.vdash_status.message(&message, None);
+	}
 
-		let mut clash_monitor = None;
-		for (other_logfile, other) in monitors.iter_mut() {
-			if self.index == other.index && &self.logfile != other_logfile {
-				clash_monitor = Some(other);
-			}
+	/// Stop showing a node, forgetting its metrics and deleting its checkpoint. A node
+	/// removed_by_user is not added again when glob paths are re-scanned.
+	pub fn remove_node(&mut self, logfile: &str, removed_by_user: bool) {
+		if self.monitors.remove(logfile).is_none() {
+			return;
 		}
+		self.logfiles_manager.forget_logfile(logfile, removed_by_user);
+		let _ = std::fs::remove_file(checkpoint_path(logfile));
 
-		if let Some(other) = clash_monitor {
-			let mut lower_index = self.index;
-			let mut higher_index = next_index;
-			if lower_index > higher_index {
-				lower_index = next_index;
-				higher_index = self.index;
-			}
-
-			// Default
-			self.index = higher_index;
-			other.index = lower_index;
-
-			// If we know the earlier of the two metrics, use that to order the index in self and other
-			if let Some(self_start_time) = self.metrics.node_started {
-				let flip = if let Some(other_start_time) = other.metrics.node_started {
-					self_start_time < other_start_time
-				} else {
-					true
-				};
-				if flip {
-					self.index = lower_index;
-					other.index = higher_index;
+		if self.logfile_with_focus == logfile {
+			self.logfile_with_focus = String::new();
+			match self.logfiles_manager.logfiles_added.first().cloned() {
+				Some(next_logfile) => {
+					self.dash_state.dash_node_focus = next_logfile.clone();
+					self.set_logfile_with_focus(next_logfile);
+				}
+				None => {
+					self.dash_state.dash_node_focus = String::new();
+					set_main_view(DashViewMain::DashSummary, self);
 				}
 			}
-		} else {
-			// next_index not used so restore state to avoid unnecessary increments
-			NEXT_MONITOR.store(existing_index, Ordering::Relaxed);
 		}
+		self.update_summary_window();
 	}
 
-	pub fn is_node(&self) -> bool {
-		return !self.is_debug_dashboard_log;
-	}
-
-	pub fn from_checkpoint(&mut self, checkpoint: &LogfileCheckpoint) {
-		self.index = checkpoint.monitor_index;
-		self.latest_checkpoint_time = checkpoint.latest_entry_time;
-		self.metrics = checkpoint.monitor_metrics.clone();
-	}
+	/// Remove nodes whose logfile no longer exists once they have been inactive for --prune-after seconds
+	pub fn prune_stale_nodes(&mut self) {
+		let opt_prune_after = OPT.lock().unwrap().prune_after;
+		if opt_prune_after <= 0 || self.dash_state.replay.is_some() {
+			return;
+		}
 
-	pub fn to_checkpoint(&mut self, checkpoint: &mut LogfileCheckpoint) {
-		checkpoint.latest_entry_time = self.latest_checkpoint_time;
-		checkpoint.monitor_index = self.index;
-		checkpoint.monitor_metrics = self.metrics.clone();
-	}
+		let inactive_since = Utc::now() - Duration::seconds(opt_prune_after);
+		let stale_logfiles: Vec<(usize, String)> = self
+			.monitors
+			.iter()
+			.filter(|(logfile, monitor)| {
+				monitor.is_node()
+					&& !monitor.is_remote
+					&& monitor.metrics.entry_metadata.as_ref().is_some_and(|metadata| metadata.system_time < inactive_since)
+					&& !Path::new(logfile).exists()
+			})
+			.map(|(logfile, monitor)| (monitor.index, logfile.clone()))
+			.collect();
 
-	// TODO if speed is an issue look at speeding up:
-	// TODO - LogEntry::decode_metadata()
-	// TODO - finding first log entry to decode using a bisection search
-	pub fn load_logfile_from_time(
-		&mut self,
-		dash_state: &mut DashState,
-		after_time: Option<DateTime<Utc>>,
-	) -> std::io::Result<()> {
-		if let Some(after_time) = after_time {
-			dash_state.vdash_status.message(
-				&format!("loading logfile after time: {}", after_time).to_string(),
+		for (node_index, logfile) in stale_logfiles {
+			self.remove_node(&logfile, false);
+			self.dash_state.vdash_status.message(
+				&format!("Pruned node {} as its logfile no longer exists ({})", node_index + 1, logfile),
 				None,
 			);
 		}
+	}
 
-		use std::io::{BufRead, BufReader};
-
-		let f = File::open(self.logfile.to_string());
-		let f = match f {
-			Ok(file) => file,
-			Err(_e) => return Ok(()), // It's ok for a logfile not to exist yet
+	/// Copy a line newly read from a logfile to the debug window or file if it is anomalous
+	pub fn sample_anomaly(&mut self, logfile: &str, line: &str) {
+		let (Some(anomaly_sampler), Some(monitor)) = (&mut self.anomaly_sampler, self.monitors.get(logfile)) else {
+			return;
 		};
-
-		let f = BufReader::new(f);
-
-		for line in f.lines() {
-			let line = line.expect("Unable to read line");
-			self.append_to_content_from_time(dash_state, &line, after_time)?;
-			if self.is_debug_dashboard_log {
-				dash_state._debug_window(&line);
-			}
+		if !monitor.is_node() {
+			return;
 		}
-
-		if self.content.items.len() > 0 {
-			self
-				.content
-				.state
-				.select(Some(self.content.items.len() - 1));
+		match anomaly_sampler.sample(monitor.index, line) {
+			Ok(Some(sample)) => self.dash_state._debug_window(&sample),
+			Ok(None) => {}
+			Err(e) => self
+				.dash_state
+				.vdash_status
+				.message(&format!("Failed to write to --anomalies-file: {}", e), None),
 		}
-
-		Ok(())
 	}
 
-	pub fn append_to_content(
-		&mut self,
-		line: &str,
-		checkpoint_interval: u64,
-	) -> Result<String, std::io::Error> {
-		self.metrics.parser_output = format!("LogMeta::decode_metadata() failed on: {}", line); // For debugging
-																																													// debug_log!(&self.parser_output.clone());
-
-		self.metrics.entry_metadata = LogEntry::decode_metadata(line);
-
-		if self.metrics.entry_metadata.is_none() {
-			// debug_log!("gather_metrics() - skipping bec. metadata missing");
-			return Ok("".to_string()); // Skip until start of first log message
-		}
+	/// Note a followed line which was not valid UTF-8. These are dropped before
+	/// vdash sees them, without saying which logfile they were in.
+	pub fn skip_invalid_line(&mut self) {
+		self.invalid_lines_skipped += 1;
+		self.dash_state.vdash_status.message(
+			&format!("Skipped {} logfile lines which were not valid UTF-8", self.invalid_lines_skipped),
+			None,
+		);
+	}
 
-		self._append_to_content(line)?; // Show in TUI
-		if self.is_debug_dashboard_log {
-			return Ok("".to_string());
+	/// Append events since the last tick to the --events-out file
+	pub fn write_events(&mut self) {
+		// Replaying is not happening now, so has no events
+		if self.dash_state.replay.is_some() {
+			return;
 		}
-
-		self.metrics.gather_metrics(&line)?;
-
-		if checkpoint_interval > 0 {
-			// Checkpoints disabled by zero interval
-			return self.update_checkpoint(checkpoint_interval);
+		if let Some(events_out) = &mut self.events_out {
+			if let Err(e) = events_out.write_events(&self.monitors, &Utc::now()) {
+				let message = format!("Failed to write events to {}: {}", events_out.path, e);
+				self.dash_state.vdash_status.message(&message, None);
+			}
 		}
-
-		Ok("".to_string())
 	}
 
-	pub fn update_checkpoint(&mut self, checkpoint_interval: u64) -> Result<String, Error> {
-		if let Some(metadata) = &self.metrics.entry_metadata {
-			if self.latest_checkpoint_time.is_none() {
-				return save_checkpoint(self);
-			} else {
-				if let Some(latest_checkpoint_time) = self.latest_checkpoint_time {
-					if latest_checkpoint_time + Duration::seconds(checkpoint_interval as i64)
-						< metadata.message_time
-					{
-						return save_checkpoint(self);
-					}
-				}
-			}
+	/// Run the commands of any --trigger whose condition has been met
+	pub fn run_triggers(&mut self) {
+		if self.dash_state.replay.is_some() {
+			return;
+		}
+		if let Some(triggers) = &mut self.triggers {
+			triggers.run(&self.monitors, &Utc::now());
 		}
-
-		Ok("".to_string())
 	}
 
-	pub fn append_to_content_from_time(
-		&mut self,
-		_dash_state: &mut DashState,
-		line: &str,
-		after_time: Option<DateTime<Utc>>,
-	) -> Result<(), std::io::Error> {
-		self.metrics.parser_output = format!("LogMeta::decode_metadata() failed on: {}", line); // For debugging
-																																													// debug_log!(&self.parser_output.clone());
-
-		if let Some(entry_metadata) = LogEntry::decode_metadata(line) {
-			if let Some(after_time) = after_time {
-				if !entry_metadata.message_time.gt(&after_time) {
-					return Ok(());
-				}
-			}
-
-			self.metrics.entry_metadata = Some(entry_metadata);
-		} else {
-			// debug_log!("gather_metrics() - skipping bec. metadata missing");
-			if after_time.is_some() {
-				return Ok(());
-			}
+	/// Add any new events, and samples of node metrics when due, to the --db database
+	pub fn write_history(&mut self) {
+		if self.dash_state.replay.is_some() {
+			return;
 		}
-
-		self._append_to_content(line)?; // Show in TUI
-		if self.is_debug_dashboard_log {
-			return Ok(());
+		if let Some(metrics_db) = &mut self.metrics_db {
+			metrics_db.record(&self.monitors, &Utc::now());
 		}
-
-		self.metrics.gather_metrics(&line)?;
-
-		Ok(())
 	}
 
-	pub fn _append_to_content(&mut self, text: &str) -> Result<(), std::io::Error> {
-		self.content.items.push(text.to_string());
-		let len = self.content.items.len();
-		if len > self.max_content {
-			self.content.items = self.content.items.split_off(len - self.max_content);
-		} else {
-			self.content.state.select(Some(len - 1));
+	/// Send the metrics of every node to --metrics-push
+	pub fn push_metrics(&mut self) {
+		if self.dash_state.replay.is_some() {
+			return;
+		}
+		if let Some(metrics_push) = &mut self.metrics_push {
+			if let Err(message) = metrics_push.push(&self.monitors, &Utc::now()) {
+				self.dash_state.vdash_status.message(&message, None);
+			}
 		}
-		Ok(())
 	}
-}
 
-use regex::Regex;
-pub static LOG_LINE_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
-	Regex::new(
-		r"\[(?P<time_string>[^ ]{27}) (?P<category>[A-Z]{4,6}) (?P<source>[^\]]*)\] (?P<message>.*)",
-	)
-	.expect("The regex failed to compile. This is a bug.")
-});
-
-#[derive(PartialEq, Clone, Default, Debug, Serialize, Deserialize)]
-pub enum NodeStatus {
-	Started,
-	Connected,
-	#[default]
-	Stopped,
-	Shunned,
-}
-
-pub fn node_status_as_string(node_status: &NodeStatus) -> String {
-	match node_status {
-		NodeStatus::Connected => "Connected".to_string(),
-		NodeStatus::Stopped => "Stopped".to_string(),
-		NodeStatus::Started => "Started".to_string(),
-		NodeStatus::Shunned => "SHUNNED".to_string(),
+	/// Handle an error following logfiles without giving up on the ones still readable
+	pub fn handle_logfile_error(&mut self, error: std::io::Error) {
+		self.dash_state._debug_window(format!("logfile error: {:#?}", error).as_str());
+		self.logfiles_manager.handle_tail_error(&error, &mut self.monitors, &mut self.dash_state);
 	}
-}
-
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct MmmStat {
-	sample_count: u64,
 
-	pub most_recent: u64,
-	pub total: u64,
-	pub min: u64,
-	pub mean: u64,
-	pub max: u64,
-}
-
-impl MmmStat {
-	pub fn new() -> MmmStat {
-		MmmStat {
-			sample_count: 0,
-			most_recent: 0,
-			total: 0,
-			min: u64::MAX,
-			mean: 0,
-			max: 0,
+	/// Re-attach logfiles which failed while being followed, once they are readable again
+	pub async fn reattach_logfiles(&mut self) {
+		if !self.logfiles_manager.logfiles_reattaching.is_empty() {
+			self.logfiles_manager.reattach_logfiles(&mut self.monitors, &mut self.dash_state).await;
 		}
 	}
 
-	pub fn add_sample(&mut self, value: u64) {
-		self.most_recent = value;
-		self.sample_count += 1;
-		self.total += value;
-		self.mean = self.total / self.sample_count;
-
-		if self.min > value || self.min == u64::MAX {
-			self.min = value;
-		}
-		if self.max < value {
-			self.max = value;
+	/// Show the results of any completed background work in the status bar
+	pub fn report_background_results(&mut self) {
+		for result in super::background_results::take() {
+			self.dash_state.vdash_status.message(&result, None);
 		}
 	}
-}
-
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct NodeMetrics {
-	pub node_started: Option<DateTime<Utc>>,
-	pub running_message: Option<String>,
-	pub running_version: Option<String>,
-	pub node_process_id: Option<u64>,
-	pub node_peer_id: Option<String>,
-	pub category_count: HashMap<String, usize>,
-
-	pub app_timelines: AppTimelines,
-
-	pub entry_metadata: Option<LogMeta>,
-	pub node_status: NodeStatus,
-	pub node_bad_behaviour: String,
-	pub node_status_string: String,
-	pub node_inactive: bool,
-
-	pub activity_gets: MmmStat,
-	pub activity_puts: MmmStat,
-	pub activity_errors: MmmStat,
-	pub attos_earned: MmmStat,
-	pub storage_cost: MmmStat,
-	pub peers_connected: MmmStat,
-	pub memory_used_mb: MmmStat,
-
-	pub wallet_balance: u64,
-	pub latest_earning: u64,
-
-	pub records_stored: u64,
-	pub records_max: u64,
-
-	pub shun_notifications: u64,
-
-	pub system_cpu: f32,
-	pub system_memory: f32,
-	pub system_memory_used_mb: f32,
-	pub system_memory_usage_percent: f32,
-
-	pub interface_name: String,
-	pub bytes_received: u64,
-	pub bytes_transmitted: u64,
-	pub total_mb_received: f32,
-	pub total_mb_transmitted: f32,
-
-	pub cpu_usage_percent: f32,
-	pub cpu_usage_percent_max: f32,
-	pub bytes_read: u64,
-	pub bytes_written: u64,
-	pub total_mb_read: f32,
-	pub total_mb_written: f32,
-
-	pub parser_output: String,
-}
 
-impl NodeMetrics {
-	pub fn new() -> NodeMetrics {
-		let mut metrics = NodeMetrics {
-			// Start
-			node_started: None,
-			running_message: None,
-			running_version: None,
-			node_process_id: None,
-			node_peer_id: None,
-
-			// Logfile entries
-			entry_metadata: None,
-
-			// A predefined set of Timelines (Sparklines)
-			app_timelines: AppTimelines::new(),
-
-			// Counts
-			category_count: HashMap::new(),
-			activity_gets: MmmStat::new(),
-			activity_puts: MmmStat::new(),
-			activity_errors: MmmStat::new(),
-
-			// Storage Payments
-			attos_earned: MmmStat::new(),
-			storage_cost: MmmStat::new(),
-			peers_connected: MmmStat::new(),
-
-			// State (node)
-			node_status: NodeStatus::Stopped,
-			node_bad_behaviour: String::from(""),
-			node_status_string: String::from(""),
-			node_inactive: false,
-
-			// State (network)
-
-			// Wallet event:
-			wallet_balance: 0,
-			latest_earning: 0,
-
-			// Storage use:
-			records_stored: 0,
-			records_max: 0,
-
-			shun_notifications: 0,
-
-			system_cpu: 0.0,
-			system_memory: 0.0,
-			system_memory_used_mb: 0.0,
-			system_memory_usage_percent: 0.0,
-
-			interface_name: String::from("unknown"),
-			bytes_received: 0,
-			bytes_transmitted: 0,
-			total_mb_received: 0.0,
-			total_mb_transmitted: 0.0,
-
-			memory_used_mb: MmmStat::new(),
-			cpu_usage_percent: 0.0,
-			cpu_usage_percent_max: 0.0,
-			bytes_read: 0,
-			bytes_written: 0,
-			total_mb_read: 0.0,
-			total_mb_written: 0.0,
-
-			// Debug
-			parser_output: String::from("-"),
+	/// Copy the PeerId and logfile path of the node with focus to the clipboard
+	pub fn copy_node_ids_to_clipboard(&mut self) {
+		let (index, peer_id, logfile) = match self.get_monitor_with_focus() {
+			Some(monitor) if monitor.is_node() => (
+				monitor.index,
+				monitor.metrics.node_peer_id.clone(),
+				monitor.logfile.clone(),
+			),
+			_ => return,
 		};
-		metrics.update_timelines(&Utc::now());
-		metrics
-	}
-
-	pub fn is_node_active(&self) -> bool {
-		return !self.node_inactive;
-	}
-
-	pub fn update_node_status_string(&mut self) {
-		let node_inactive_timeout = Duration::seconds(NODE_INACTIVITY_TIMEOUT_S);
-
-		let mut node_status_string = node_status_as_string(&self.node_status);
 
-		if self.node_status == NodeStatus::Shunned {
-			node_status_string = format!(
-				"Shunned x{} ({})",
-				self.shun_notifications, self.node_bad_behaviour
-			);
-		} else if let Some(metadata) = &self.entry_metadata {
-			let idle_time = Utc::now() - metadata.system_time;
-			if idle_time > node_inactive_timeout {
-				self.node_inactive = true;
-				node_status_string = format!("INACTIVE ({})", get_duration_text(idle_time));
-			} else {
-				self.node_inactive = false;
+		let text = match &peer_id {
+			Some(peer_id) => format!("{}\n{}", peer_id, logfile),
+			None => logfile,
+		};
+		let destination = match super::clipboard::copy_to_clipboard(&text) {
+			Ok(Copied::Clipboard) => "copied to clipboard",
+			Ok(Copied::Terminal) => "sent to terminal clipboard (OSC 52)",
+			Err(e) => {
+				self.dash_state.vdash_status.message(&format!("Failed to copy to clipboard: {}", e), None);
+				return;
 			}
+		};
+		let message = match peer_id {
+			Some(_) => format!("PeerId and logfile of node {} {}", index + 1, destination),
+			None => format!("PeerId of node {} not known, logfile {}", index + 1, destination),
+		};
+		self.dash_state.vdash_status.message(&message, None);
+	}
+
+	/// Regenerate the Earnings view from the ledgers of all nodes, oldest first, if
+	/// they or how they are shown have changed since it was last generated
+	pub fn update_earnings_window(&mut self) {
+		profile_scope!("update_earnings_window");
+		let currency_apiname = WEB_PRICES.lock().unwrap().currency_apiname.clone();
+		let nodes = self.monitors.values().filter(|monitor| monitor.is_node());
+		let source = Some(EarningsSource {
+			payments: nodes.clone().map(|monitor| monitor.metrics.earnings_ledger.recorded).sum(),
+			nodes: nodes.count(),
+			period: self.dash_state.earnings_period,
+			currency_apiname: currency_apiname.clone(),
+			currency_per_token: self.dash_state.currency_per_token,
+		});
+		if source == self.dash_state.earnings_window_source {
+			return;
 		}
+		self.dash_state.earnings_window_source = source;
+
+		let current_selection = self.dash_state.earnings_window_rows.state.selected();
+
+		let earnings = super::earnings_ledger::collect_node_earnings(&self.monitors);
+		let items: Vec<String> = match self.dash_state.earnings_period {
+			EarningsPeriod::Payment => earnings
+				.iter()
+				.map(|e| super::ui_earnings::format_earnings_row(&self.dash_state, &currency_apiname, e))
+				.collect(),
+			period => earnings_by_period(&earnings, period, &currency_apiname)
+				.iter()
+				.map(|row| super::ui_earnings::format_period_row(&self.dash_state, period, row))
+				.collect(),
+		};
+		let len = items.len();
+		self.dash_state.earnings_window_rows = StatefulList::with_items(items);
 
-		self.node_status_string = node_status_string;
+		let selection = match current_selection {
+			Some(selected) if selected < len => Some(selected),
+			_ if len > 0 => Some(len - 1),
+			_ => None,
+		};
+		self.dash_state.earnings_window_rows.state.select(selection);
 	}
 
-	fn reset_metrics(&mut self) {
-		self.node_status = NodeStatus::Started;
-		self.activity_gets = MmmStat::new();
-		self.activity_puts = MmmStat::new();
-		self.activity_errors = MmmStat::new();
-		self.storage_cost = MmmStat::new();
-		self.peers_connected = MmmStat::new();
-		self.memory_used_mb = MmmStat::new();
+	/// Regenerate the Fleet view comparing days for each node, in node order
+	pub fn update_fleet_window(&mut self) {
+		let current_selection = self.dash_state.fleet_window_rows.state.selected();
+
+		let mut monitors: Vec<&LogMonitor> = self.monitors.values().filter(|m| m.is_node()).collect();
+		monitors.sort_by_key(|m| m.index);
+		let (rows, significant): (Vec<String>, Vec<bool>) = monitors
+			.iter()
+			.map(|monitor| super::ui_fleet::format_fleet_row(&self.dash_state, monitor))
+			.unzip();
+
+		let len = rows.len();
+		self.dash_state.fleet_window_rows = StatefulList::with_items(rows);
+		self.dash_state.fleet_rows_significant = significant;
+		let selection = match current_selection {
+			Some(selected) if selected < len => Some(selected),
+			_ if len > 0 => Some(0),
+			_ => None,
+		};
+		self.dash_state.fleet_window_rows.state.select(selection);
+	}
+
+	/// Write all payments from the node ledgers to a CSV file, by default in the current directory
+	pub fn export_earnings_csv(&mut self, path: Option<String>) {
+		let currency_apiname = WEB_PRICES.lock().unwrap().currency_apiname.clone();
+		let earnings = super::earnings_ledger::collect_node_earnings(&self.monitors);
+		let filename = path.unwrap_or_else(|| format!("vdash-earnings-{}.csv", Utc::now().format("%Y%m%d-%H%M%S")));
+
+		let message = match super::earnings_ledger::write_earnings_csv(
+			std::path::Path::new(&filename),
+			&earnings,
+			&currency_apiname,
+			self.dash_state.currency_per_token,
+		) {
+			Ok(()) => format!(
+				"Exported {} payments to {}",
+				earnings.iter().map(|e| e.entry.payments).sum::<u64>(),
+				filename
+			),
+			Err(e) => format!("Failed to export earnings: {}", e),
+		};
+		self.dash_state.vdash_status.message(&message, None);
 	}
 
-	///! Process a line from a  Node logfile.
-	///! Use a created LogMeta to update metrics.
-	pub fn gather_metrics(&mut self, line: &str) -> Result<(), std::io::Error> {
-		let entry = LogEntry {
-			logstring: String::from(line),
+	/// Export payments with their value when received, for tax reporting
+	pub fn export_tax_report(&mut self, path: Option<String>) {
+		let currency_apiname = WEB_PRICES.lock().unwrap().currency_apiname.clone();
+		if currency_apiname.is_empty() {
+			self
+				.dash_state
+				.vdash_status
+				.message(&String::from("Set a currency with --currency-apiname to export a tax report"), None);
+			return;
+		}
+		let entries = super::tax_report::collect_tax_report_entries(&self.monitors, &currency_apiname);
+		let filename = path.unwrap_or_else(|| format!("vdash-tax-report-{}.csv", Utc::now().format("%Y%m%d-%H%M%S")));
+		self.dash_state.vdash_status.message(
+			&format!("Exporting {} payments to {}...", entries.len(), filename),
+			None,
+		);
+		super::tax_report::spawn_tax_report(filename, entries, currency_apiname);
+	}
+
+	/// Write the Summary table, as sorted and filtered, to a CSV file, by default in the current directory
+	pub fn export_summary_csv(&mut self, path: Option<String>) {
+		let filename = path.unwrap_or_else(|| format!("vdash-summary-{}.csv", Utc::now().format("%Y%m%d-%H%M%S")));
+		let message = match super::ui_summary_table::write_summary_csv(
+			std::path::Path::new(&filename),
+			&self.dash_state,
+			&self.monitors,
+		) {
+			Ok(nodes) => format!("Exported {} nodes to {}", nodes, filename),
+			Err(e) => format!("Failed to export the Summary: {}", e),
 		};
-		let entry_metadata = self.entry_metadata.as_ref().unwrap().clone();
-		let entry_time = entry_metadata.message_time;
+		self.dash_state.vdash_status.message(&message, None);
+	}
 
-		debug_log!(format!("gather_metrics() entry_time: {:?}", entry_time).as_str());
+	fn append_to_summary_window(&mut self, text: &str) {
+		self
+			.dash_state
+			.summary_window_rows
+			.items
+			.push(text.to_string());
 
-		self.update_timelines(&entry_time);
-		self.parser_output = entry_metadata.parser_output.clone();
-		self.process_logfile_entry(&entry.logstring, &entry_metadata); // May overwrite self.parser_output
+		let len = self.dash_state.summary_window_rows.items.len();
 
-		// --debug-dashboard - prints parser results for a single logfile
-		// to a temp logfile which is displayed in the adjacent window.
-		debug_log!(&self.parser_output.clone());
+		if len > self.dash_state.max_summary_window {
+			self.dash_state.summary_window_rows.items = self
+				.dash_state
+				.summary_window_rows
+				.items
+				.split_off(len - self.dash_state.max_summary_window);
+		} else {
+			self
+				.dash_state
+				.summary_window_rows
+				.state
+				.select(Some(len - 1));
+		}
+	}
 
-		Ok(())
+	pub fn toggle_logfile_area(&mut self) {
+		self.dash_state.node_logfile_visible = !self.dash_state.node_logfile_visible;
+		self.save_layout_preference();
 	}
 
-	pub fn update_timelines(&mut self, now: &DateTime<Utc>) {
-		self.app_timelines.update_timelines(now);
-	}
-
-	///! Return a LogMeta and capture metadata for logfile node start:
-	///!	'Running safenode v0.98.32'
-	pub fn parse_start(&mut self, line: &String, entry_metadata: &LogMeta) -> bool {
-		let running_prefix = String::from("Running safenode ");
-
-		if line.starts_with(&running_prefix) {
-			self.set_node_status(NodeStatus::Started);
-			let message = line.to_string();
-			let version = String::from(line[running_prefix.len()..].to_string());
-			self.node_started = Some(entry_metadata.message_time);
-			self.parser_output = format!(
-				"START node {} at {}",
-				String::from(version.clone()),
-				self
-					.node_started
-					.map_or(String::from("None"), |m| format!("{}", m))
-			);
+	/// Give the Node Status timelines more or fewer rows, taking them from or giving them to the logfile
+	pub fn resize_node_timelines(&mut self, grow: bool) {
+		let height = if grow {
+			self.dash_state.node_timelines_height.saturating_add(NODE_TIMELINES_HEIGHT_STEP)
+		} else {
+			self.dash_state.node_timelines_height.saturating_sub(NODE_TIMELINES_HEIGHT_STEP)
+		};
+		self.dash_state.node_timelines_height = height.clamp(NODE_TIMELINES_HEIGHT_MIN, NODE_TIMELINES_HEIGHT_MAX);
+		self.save_layout_preference();
+	}
 
-			self.running_message = Some(message);
-			self.running_version = Some(version);
-			self.reset_metrics();
-			return true;
+	fn apply_layout_preference(&mut self) {
+		if let Some(layout) = &self.preferences.node_layout {
+			self.dash_state.node_timelines_height =
+				layout.timelines_height.clamp(NODE_TIMELINES_HEIGHT_MIN, NODE_TIMELINES_HEIGHT_MAX);
+			self.dash_state.node_logfile_visible = layout.logfile_visible;
+			self.dash_state.top_timeline = layout.top_timeline.min(self.num_timelines().saturating_sub(1));
 		}
+	}
 
-		let process_id_prefix = "Node (PID: ";
-		if line.contains(&process_id_prefix) {
-			self.node_process_id = self.parse_u64(process_id_prefix, line);
-			let process_id = match &self.node_process_id {
-				Some(process_id) => process_id.to_string(),
-				None => String::from("unknown"),
-			};
-
-			if let Some(peer_id) = self.parse_string("PeerId: ", line) {
-				self.parser_output = format!(
-					"Node pid: {} peer_id: {}",
-					String::from(process_id.clone()),
-					peer_id
-				);
-				self.node_peer_id = Some(peer_id);
-			}
-			return true;
-		}
-
-		false
-	}
-
-	///! Process a logfile entry
-	///! Returns true if node is being shunned, or the line has been processed and can be discarded
-	pub fn process_logfile_entry(&mut self, line: &String, entry_metadata: &LogMeta) -> bool {
-		return self.parse_timed_data(&line, &entry_metadata.message_time)
-			|| self.parse_states(&line, &entry_metadata)
-			|| self.parse_start(&line, &entry_metadata);
-	}
-
-	fn parse_timed_data(&mut self, line: &String, entry_time: &DateTime<Utc>) -> bool {
-		if line.contains("Retrieved record from disk") {
-			self.count_get(&entry_time);
-			self.set_node_status(NodeStatus::Connected);
-			return true;
-		} else if line.contains("Wrote record") || line.contains("ValidSpendRecordPutFromNetwork") {
-			self.count_put(&entry_time);
-			self.set_node_status(NodeStatus::Connected);
-			return true;
-		} else if line.contains("Editing Register success") {
-			// TODO: no longer present, find new log message
-			self.count_put(&entry_time);
-			self.set_node_status(NodeStatus::Connected);
-			return true;
-		} else if line.contains("Cost is now") {
-			if let Some(storage_cost) = self.parse_u64("Cost is now ", line) {
-				// Ignore storage cost of zero as that means the record is already paid for
-				if storage_cost > 0 {
-					self.count_storage_cost(entry_time, storage_cost);
-					self.parser_output = format!("Storage cost: {}", storage_cost);
-				}
-			};
-			return false; // Continue processing for records stored (parse_states())
-		} else if line.contains("Total payment of") {
-			if let Some(attos_earned) = self.parse_u64("Total payment of", line) {
-				self.count_attos_earned(entry_time, attos_earned);
-				self.parser_output = format!("Payment received: {}", attos_earned);
-				return true;
-			};
-		} else if line.contains("PeersInRoutingTable") {
-			let mut parser_output = String::from("connected peers:");
-			if let Some(peers_connected) = self.parse_u64("PeersInRoutingTable(", line) {
-				self.count_peers_connected(entry_time, peers_connected);
-				parser_output = format!("{} {}", &parser_output, peers_connected);
-			};
-			self.parser_output = parser_output;
-			return true;
-		} else if line.contains("consider us as BAD") {
-			let mut parser_output = String::from("Node being SHUNNED");
-			self.set_node_status(NodeStatus::Shunned);
-			self.shun_notifications = self.shun_notifications + 1;
-			if let Some(bad_behaviour) = self.parse_string("due to \"", line) {
-				self.node_bad_behaviour = bad_behaviour.clone();
-				parser_output = format!("Shunned due to '{}'", bad_behaviour);
-			};
-			self.parser_output = parser_output;
-			return true;
+	fn save_layout_preference(&mut self) {
+		self.preferences.node_layout = Some(NodeLayout {
+			timelines_height: self.dash_state.node_timelines_height,
+			logfile_visible: self.dash_state.node_logfile_visible,
+			top_timeline: self.dash_state.top_timeline,
+		});
+		if let Err(e) = self.preferences.save() {
+			self.dash_state.vdash_status.message(&format!("Unable to save preferences: {}", e), None);
 		}
-		return false;
 	}
 
-	// Set status unless currently shunned
-	fn set_node_status(&mut self, new_status: NodeStatus) {
-		if self.node_status != NodeStatus::Shunned {
-			self.node_status = new_status;
+	/// Show the next of the displayed timelines for each node in the Summary
+	pub fn next_breakdown_timeline(&mut self) {
+		let opt_timelines = OPT.lock().unwrap().timelines.clone();
+		let order = timeline_order(&opt_timelines).unwrap_or_default();
+		let next = order
+			.iter()
+			.position(|key| *key == self.dash_state.breakdown_timeline)
+			.map_or(0, |index| (index + 1) % order.len());
+		if let Some(key) = order.get(next) {
+			self.dash_state.breakdown_timeline = key.clone();
 		}
 	}
 
-	///! Update data metrics from a handler response logfile entry
-	///! Returns true if the line has been processed and can be discarded
-	fn parse_string(&mut self, prefix: &str, line: &String) -> Option<String> {
-		let mut string = "";
-		if let Some(mut string_start) = line.find(prefix) {
-			string_start += prefix.len();
-
-			if let Some(string_end) = line[string_start..].find("\"") {
-				string = line.as_str()[string_start..string_start + string_end].as_ref()
-			} else {
-				string = line.as_str()[string_start..].as_ref()
-			}
-			if string.is_empty() {
-				self.parser_output = format!("failed to parse string after {} in: {}", prefix, line);
-			}
+	/// Show a vertical scale and a cursor on the Node Status timelines, or hide them
+	pub fn toggle_timeline_cursor(&mut self) {
+		self.dash_state.timeline_cursor = match self.dash_state.timeline_cursor {
+			Some(_) => None,
+			None => Some(0),
 		};
+	}
 
-		if string.len() > 0 {
-			Some(String::from(string))
-		} else {
-			None
+	/// Move the timeline cursor back (older) or forward towards the latest bucket. It
+	/// is kept within the visible buckets when the timelines are drawn.
+	pub fn move_timeline_cursor(&mut self, older: bool) {
+		if let Some(cursor) = self.dash_state.timeline_cursor {
+			self.dash_state.timeline_cursor = Some(if older { cursor + 1 } else { cursor.saturating_sub(1) });
 		}
 	}
 
-	///! Capture state updates from a logfile entry
-	///! Returns true if the line has been processed and can be discarded
-	fn parse_states(&mut self, line: &String, entry_metadata: &LogMeta) -> bool {
-		if entry_metadata.category.eq("ERROR") {
-			self.count_error(&entry_metadata.message_time);
+	/// Draw timelines as high resolution braille lines, or as bars again
+	pub fn toggle_sparkline_braille(&mut self) {
+		self.dash_state.sparkline_braille = !self.dash_state.sparkline_braille;
+		self.preferences.sparkline_braille = self.dash_state.sparkline_braille;
+		if let Err(e) = self.preferences.save() {
+			self.dash_state.vdash_status.message(&format!("Unable to save preferences: {}", e), None);
 		}
+	}
 
-		let &content = &line.as_str();
-
-		// Node Status
-		if content.contains("Node events channel closed") {
-			self.set_node_status(NodeStatus::Stopped);
-			self.parser_output = String::from("Node status: Disconnected");
-			return true;
+	/// Show the most frequent ERROR and WARN messages in place of the logfile, or the logfile again
+	pub fn toggle_top_messages(&mut self) {
+		self.dash_state.top_messages_visible = !self.dash_state.top_messages_visible;
+		if self.dash_state.top_messages_visible {
+			self.dash_state.node_logfile_visible = true;
 		}
+	}
 
-		if content.contains("Created payment quote for") {
-			if let Some(records_stored) = self.parse_u64("records_stored: ", line) {
-				self.records_stored = records_stored;
-				self.parser_output = format!("Records stored: {}", records_stored);
-			};
-			if let Some(records_max) = self.parse_u64("max_records: ", line) {
-				self.records_max = records_max;
-				self.parser_output = format!("{}, Max records: {}", self.parser_output, records_max);
-			};
-			return true;
-		}
-
-		// if content.contains("Skipping ") {
-		// 	let mut parser_output = String::from("Connected ({} lag)");
-		// 	if let Some(events_skipped) = self.parse_usize("Skipping ", content) {
-		// 		parser_output = format!("{} ({})", &parser_output, events_skipped);
-		// 	};
-		// 	self.parser_output = parser_output;
-		// 	return true;
-		// }
-
-		// Metrics
-		if content.contains("ant_logging::metrics") {
-			// System
-			let mut parser_output = String::from("system_cpu_usage_percent:");
-			if let Some(system_cpu) = self.parse_float32("system_cpu_usage_percent\":", content) {
-				self.system_cpu = system_cpu;
-				parser_output = format!("{} gl_cpu: {}", &parser_output, system_cpu);
-			};
-			if let Some(system_memory) = self.parse_float32("system_total_memory_mb\":", content) {
-				self.system_memory = system_memory;
-				parser_output = format!("{} , System Memory: {}", &parser_output, system_memory);
-			};
-			if let Some(system_memory_used_mb) = self.parse_float32("system_memory_used_mb\":", content) {
-				self.system_memory_used_mb = system_memory_used_mb;
-				parser_output = format!(
-					"{} , System Memory Use (MB): {}",
-					&parser_output, system_memory_used_mb
-				);
-			};
-			if let Some(system_memory_usage_percent) =
-				self.parse_float32("system_memory_usage_percent\":", content)
-			{
-				self.system_memory_usage_percent = system_memory_usage_percent;
-				parser_output = format!(
-					"{} , System Memory Use (%): {}",
-					&parser_output, system_memory_usage_percent
-				);
-			};
-
-			// Networking
-			if let Some(interface_name) = self.parse_word("interface_name\":", content) {
-				self.interface_name = String::from(interface_name.clone());
-				parser_output = format!("{} , interface_name: {}", &parser_output, interface_name);
-			};
-			if let Some(bytes_received) = self.parse_u64("bytes_received\":", content) {
-				self.bytes_received = bytes_received;
-				parser_output = format!("{} , bytes_received: {}", &parser_output, bytes_received);
-			};
-			if let Some(bytes_transmitted) = self.parse_u64("bytes_transmitted\":", content) {
-				self.bytes_transmitted = bytes_transmitted;
-				parser_output = format!(
-					"{} , bytes_transmitted: {}",
-					&parser_output, bytes_transmitted
-				);
-			};
-			if let Some(total_mb_received) = self.parse_float32("total_mb_received\":", content) {
-				self.total_mb_received = total_mb_received;
-				parser_output = format!(
-					"{} , total_mb_received: {}",
-					&parser_output, total_mb_received
-				);
-			};
-			if let Some(total_mb_transmitted) = self.parse_float32("total_mb_transmitted\":", content) {
-				self.total_mb_transmitted = total_mb_transmitted;
-				parser_output = format!(
-					"{} , total_mb_transmitted: {}",
-					&parser_output, total_mb_transmitted
-				);
-			};
-
-			// Node Resources
-			if let Some(cpu_usage_percent) = self.parse_float32("\"cpu_usage_percent\":", content) {
-				self.cpu_usage_percent = cpu_usage_percent;
-				if cpu_usage_percent > self.cpu_usage_percent_max {
-					self.cpu_usage_percent_max = cpu_usage_percent;
-				}
-				parser_output = format!(
-					"{}  cpu: {}, cpu_max {}",
-					&parser_output, cpu_usage_percent, self.cpu_usage_percent_max
-				);
-			};
-			if let Some(memory_used_mb) = self.parse_float32("\"memory_used_mb\":", content) {
-				self.count_memory_used_mb(&entry_metadata.message_time, memory_used_mb as u64);
-				parser_output = format!("{} , memory: {}", &parser_output, memory_used_mb);
-			};
-			if let Some(bytes_read) = self.parse_u64("bytes_read\":", content) {
-				self.bytes_read = bytes_read;
-				parser_output = format!("{} , bytes_read: {}", &parser_output, bytes_read);
-			};
-			if let Some(bytes_written) = self.parse_u64("bytes_written\":", content) {
-				self.bytes_written = bytes_written;
-				parser_output = format!("{} , bytes_written: {}", &parser_output, bytes_written);
-			};
-			if let Some(total_mb_read) = self.parse_float32("total_mb_read\":", content) {
-				self.total_mb_read = total_mb_read;
-				parser_output = format!("{} , total_mb_read: {}", &parser_output, total_mb_read);
-			};
-			if let Some(total_mb_written) = self.parse_float32("total_mb_written\":", content) {
-				self.total_mb_written = total_mb_written;
-				parser_output = format!(
-					"{} , total_mb_written: {}",
-					&parser_output, total_mb_written
-				);
-			};
+	/// Switch the Node view between its Status and Diagnostics tabs
+	pub fn toggle_node_diagnostics(&mut self) {
+		self.dash_state.node_diagnostics_visible = !self.dash_state.node_diagnostics_visible;
+	}
 
-			self.parser_output = parser_output;
-			return true;
+	pub fn scale_timeline_up(&mut self) {
+		if self.dash_state.active_timescale == 0 {
+			return;
 		}
+		self.dash_state.active_timescale -= 1;
+	}
 
-		// Misc stats
-		if content.contains("The new wallet balance is") {
-			let mut parser_output = String::from("");
-
-			if let Some(wallet_balance) = self.parse_u64("wallet balance is ", content) {
-				self.wallet_balance = wallet_balance;
-				parser_output = format!("{} , wallet_balance: {}", &parser_output, wallet_balance);
-			};
-			if let Some(latest_earning) = self.parse_u64("after earning ", content) {
-				self.latest_earning = latest_earning;
-				parser_output = format!("{} , latest_earning: {}", &parser_output, latest_earning);
-			};
-			self.parser_output = parser_output;
-			return true;
-		}
-		false
-	}
-
-	// fn parse_usize(&mut self, prefix: &str, content: &str) -> Option<usize> {
-	// 	if let Some(position) = content.find(prefix) {
-	// 		let word: Vec<&str> = content[position + prefix.len()..]
-	// 			.trim()
-	// 			.splitn(2, |c| c == ' ' || c == ',' || c== '}')
-	// 			.collect();
-	// 		if word.len() > 0 {
-	// 			match word[0].parse::<usize>() {
-	// 				Ok(value) => return Some(value),
-	// 				Err(_e) => self.parser_output = format!("failed to parse '{}' as usize from: '{}'", word[0], &content[position + prefix.len()..]),
-	// 			}
-	// 		}
-	// 	}
-	// 	None
-	// }
-
-	fn parse_u64(&mut self, prefix: &str, content: &str) -> Option<u64> {
-		if let Some(position) = content.find(prefix) {
-			let word: Vec<&str> = content[position + prefix.len()..]
-				.trim()
-				.splitn(2, |c| c == ' ' || c == ',' || c == '}' || c == ')')
-				.collect();
-			if word.len() > 0 {
-				match word[0].parse::<u64>() {
-					Ok(value) => return Some(value),
-					Err(_e) => {
-						self.parser_output = format!(
-							"failed to parse '{}' as u64 from: '{}'",
-							word[0],
-							&content[position + prefix.len()..]
-						)
-					}
-				}
-			}
+	pub fn scale_timeline_down(&mut self) {
+		if self.dash_state.active_timescale == TIMESCALES.len() - 1 {
+			return;
 		}
-		None
+		self.dash_state.active_timescale += 1;
 	}
 
-	fn parse_float32(&mut self, prefix: &str, content: &str) -> Option<f32> {
-		if let Some(position) = content.find(prefix) {
-			let word: Vec<&str> = content[position + prefix.len()..]
-				.trim()
-				.splitn(2, |c| c == ' ' || c == ',' || c == '}')
-				.collect();
-			if word.len() > 0 {
-				match word[0].parse::<f32>() {
-					Ok(value) => return Some(value),
-					Err(_e) => {
-						self.parser_output = format!(
-							"failed to parse '{}' as float from: '{}'",
-							word[0],
-							&content[position + prefix.len()..]
-						)
+	/// Show timelines for a period such as '14:00-16:00 yesterday', or up to now if empty
+	pub fn set_timeline_window(&mut self, text: &str) {
+		if text.trim().is_empty() {
+			self.dash_state.timeline_window_end = None;
+			self.dash_state.vdash_status.message(&String::from("Timelines end now"), None);
+			return;
+		}
+
+		let now = dashboard_time();
+		let message = match parse_timeline_window(text, now.date_naive()) {
+			Ok((start, end)) => {
+				let timeline_steps = OPT.lock().unwrap().timeline_steps;
+				match timescale_reaching(&start, &now, timeline_steps) {
+					Some(timescale) => {
+						self.dash_state.active_timescale = timescale;
+						self.dash_state.timeline_window_end = if end < now { Some(end) } else { None };
+						self.dash_state.vdash_status.clear_status();
+						return;
 					}
+					None => String::from("Timelines not changed, that is further back than the timelines reach"),
 				}
 			}
-		}
-		None
+			Err(e) => format!("Timelines not changed, {}", e),
+		};
+		self.dash_state.vdash_status.message(&message, None);
 	}
 
-	fn parse_word(&mut self, prefix: &str, content: &str) -> Option<String> {
-		if let Some(start) = content.find(prefix) {
-			let word: Vec<&str> = content[start + prefix.len()..]
-				.trim_start()
-				.splitn(2, |c| c == ' ' || c == ',' || c == '}')
-				.collect();
-			if word.len() > 0 {
-				return Some(word[0].to_string());
-			} else {
-				self.parser_output = format!("failed to parse word at: '{}'", &content[start..]);
-			}
-		}
-		None
+	/// Move the end of the timelines back (older) or forward towards now
+	pub fn pan_timelines(&mut self, older: bool) {
+		let Some((_name, bucket_duration)) = TIMESCALES.get(self.dash_state.active_timescale) else {
+			return;
+		};
+		let now = dashboard_time();
+		let step = *bucket_duration * PAN_COLUMNS;
+		let window_end = self.dash_state.timeline_window_end.unwrap_or(now);
+		let window_end = if older { window_end - step } else { window_end + step };
+		// Within a column of now is now, so the timelines follow the time again
+		self.dash_state.timeline_window_end = if window_end <= now - *bucket_duration { Some(window_end) } else { None };
 	}
 
-	fn count_get(&mut self, time: &DateTime<Utc>) {
-		self.activity_gets.add_sample(1);
-		self.apply_timeline_sample(GETS_TIMELINE_KEY, time, 1);
+	fn num_timelines(&self) -> usize {
+		let opt_timelines = OPT.lock().unwrap().timelines.clone();
+		timeline_order(&opt_timelines).map_or(1, |order| order.len())
 	}
 
-	fn count_put(&mut self, time: &DateTime<Utc>) {
-		self.activity_puts.add_sample(1);
-		self.apply_timeline_sample(PUTS_TIMELINE_KEY, time, 1);
+	pub fn top_timeline_next(&mut self) {
+		if self.dash_state.top_timeline < self.num_timelines() {
+			self.dash_state.top_timeline += 1;
+		} else {
+			self.dash_state.top_timeline = 0;
+		}
+		self.save_layout_preference();
 	}
 
-	fn count_error(&mut self, time: &DateTime<Utc>) {
-		self.activity_errors.add_sample(1);
-		self.apply_timeline_sample(ERRORS_TIMELINE_KEY, time, 1);
+	pub fn top_timeline_previous(&mut self) {
+		if self.dash_state.top_timeline > 0 {
+			self.dash_state.top_timeline -= 1;
+		} else {
+			self.dash_state.top_timeline = self.num_timelines() - 1;
+		}
+		self.save_layout_preference();
 	}
 
-	fn count_attos_earned(&mut self, time: &DateTime<Utc>, attos_earned: u64) {
-		self.attos_earned.add_sample(attos_earned);
-		self.apply_timeline_sample(EARNINGS_TIMELINE_KEY, time, attos_earned);
+	// Rotate UI display state through Min, Mean, Max values
+	pub fn bump_mmm_ui_mode(&mut self) {
+		self.dash_state.bump_mmm_ui_mode();
 	}
 
-	fn count_storage_cost(&mut self, time: &DateTime<Utc>, storage_cost: u64) {
-		self.storage_cost.add_sample(storage_cost);
-		self.apply_timeline_sample(STORAGE_COST_TIMELINE_KEY, time, storage_cost);
+	pub fn bump_earnings_units(&mut self) {
+		self.dash_state.bump_earnings_units();
 	}
 
-	fn count_peers_connected(&mut self, time: &DateTime<Utc>, connections: u64) {
-		self.peers_connected.add_sample(connections);
-		self.apply_timeline_sample(CONNECTIONS_TIMELINE_KEY, time, connections);
+	pub fn bump_average_mode(&mut self) {
+		self.dash_state.bump_average_mode();
 	}
 
-	fn count_memory_used_mb(&mut self, time: &DateTime<Utc>, memory_used_mb: u64) {
-		self.memory_used_mb.add_sample(memory_used_mb);
-		self.apply_timeline_sample(RAM_TIMELINE_KEY, time, memory_used_mb);
-	}
+	/// Cycle the displayed currency through those given by --currency-apiname
+	pub fn next_currency(&mut self) {
+		let opt_currency_token_rate = OPT.lock().unwrap().currency_token_rate;
+		let mut prices = WEB_PRICES.lock().unwrap();
+		if prices.currencies.len() < 2 {
+			return;
+		}
 
-	fn apply_timeline_sample(&mut self, timeline_key: &str, time: &DateTime<Utc>, value: u64) {
-		if let Some(timeline) = self.app_timelines.get_timeline_by_key(timeline_key) {
-			timeline.update_value(time, value);
+		if let Some(currency_apiname) = prices.next_currency() {
+			self.dash_state.currency_symbol = prices.currency_symbol.clone();
+			// Until the currency has a price, keep any rate given with --currency-token-rate
+			if prices.snt_rate.is_some() || opt_currency_token_rate <= 0.0 {
+				self.dash_state.currency_per_token = prices.snt_rate;
+			}
+			drop(prices);
+
+			self
+				.dash_state
+				.vdash_status
+				.message(&format!("Currency: {}", currency_apiname), None);
+			self.update_summary_window();
 		}
 	}
-}
 
-///! Metadata for a logfile line
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct LogMeta {
-	pub category: String, // First word ('INFO', 'WARN' etc.)
-	pub message_time: DateTime<Utc>,
-	pub system_time: DateTime<Utc>,
-	pub source: String,
-	pub message: String,
+	pub fn toggle_mmm_all_series(&mut self) {
+		self.dash_state.mmm_all_series = !self.dash_state.mmm_all_series;
+	}
 
-	pub parser_output: String,
+	pub fn mmm_ui_mode(&mut self) -> &MinMeanMax {
+		return self.dash_state.mmm_ui_mode();
+	}
 }
 
-impl LogMeta {
-	pub fn clone(&self) -> LogMeta {
-		LogMeta {
-			category: self.category.clone(),
-			message_time: self.message_time,
-			system_time: self.system_time,
-			source: self.source.clone(),
-			message: self.message.clone(),
-			parser_output: self.parser_output.clone(),
+/// Move selection forward or back without wrapping at start or end
+fn do_bracketed_next_previous(list: &mut StatefulList<String>, next: bool) {
+	if next {
+		if let Some(selected) = list.state.selected() {
+			if selected != list.items.len() - 1 {
+				list.next();
+			}
+		} else {
+			list.previous();
+		}
+	} else {
+		if let Some(selected) = list.state.selected() {
+			if selected != 0 {
+				list.previous();
+			}
+		} else {
+			list.previous();
 		}
 	}
 }
 
-///! Used to build a history of what is in the log, one LogMeta per line
-pub struct LogEntry {
-	pub logstring: String, // One line of raw text from the logfile
+fn exit_with_usage(reason: &str) -> Result<App, std::io::Error> {
+	eprintln!(
+		"Try '{} --help' for more information.",
+		Opt::clap().get_name()
+	);
+	return Err(Error::new(ErrorKind::Other, reason));
 }
 
-impl LogEntry {
-	///! Decode metadata from logfile line when present. Example input lines:
-	///! " INFO 2022-01-15T20:21:02.659471Z [sn/src/node/routing/core/mod.rs:L211]:"
-	///! "	 ➤ Writing our latest PrefixMap to disk"
-	///! " ERROR 2022-01-15T20:21:07.643598Z [sn/src/node/routing/api/dispatcher.rs:L450]:"
-	fn decode_metadata(line: &str) -> Option<LogMeta> {
-		if line.is_empty() {
-			return None;
-		}
-
-		if let Some(captures) = LOG_LINE_PATTERN.captures(line) {
-			let category = captures.name("category").map_or("", |m| m.as_str());
-			let time_string = captures.name("time_string").map_or("", |m| m.as_str());
-			let source = captures.name("source").map_or("", |m| m.as_str());
-			let message = captures.name("message").map_or("", |m| m.as_str());
-			let time_str: String;
-
-			let time_utc: DateTime<Utc>;
-
-			match DateTime::parse_from_str(time_string, "%+") {
-				Ok(time) => {
-					time_utc = time.with_timezone(&Utc);
-					time_str = format!("{}", time);
-				}
-				Err(e) => {
-					debug_log!(format!("ERROR parsing logfile time: {}", e).as_str());
-					return None;
-				}
-			};
-			let parser_output = format!(
-				"c: {}, t: {}, s: {}, m: {}",
-				category, time_str, source, message
-			);
-
-			return Some(LogMeta {
-				category: String::from(category),
-				message_time: time_utc,
-				system_time: Utc::now(),
-				source: String::from(source),
-				message: String::from(message),
-				parser_output,
-			});
-		}
-		None
-	}
-}
+// Rows for Node Status timelines when the logfile is shown (see App::resize_node_timelines())
+const NODE_TIMELINES_HEIGHT: u16 = 18;
+const NODE_TIMELINES_HEIGHT_MIN: u16 = 6;
+const NODE_TIMELINES_HEIGHT_MAX: u16 = 60;
+const NODE_TIMELINES_HEIGHT_STEP: u16 = 2;
 
 ///! Active UI at top level
 #[derive(PartialEq, Clone, Copy)]
@@ -1628,13 +2118,53 @@ pub enum DashViewMain {
 	DashSummary,
 	DashNode,
 	DashHelp,
+	DashEarnings,
+	DashFleet,
 	DashDebug,
 }
 
+/// Text being entered at the prompt shown in the status bar
+pub struct InputPrompt {
+	pub kind: PromptKind,
+	pub text: String,
+}
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum PromptKind {
+	Filter,
+	Tags,
+	TimelineWindow,
+	JumpToNode,
+	Command,
+}
+
+const INPUT_PROMPT_TIMEOUT_S: i64 = 3600;
+
+/// Units for earnings shown in timelines
+#[derive(PartialEq, Clone, Copy, Default)]
+pub enum EarningsUnits {
+	#[default]
+	Attos,
+	Ant,
+	Currency,
+}
+
+/// What the Earnings view was last built from, so it is only rebuilt when this changes
+#[derive(PartialEq)]
+pub struct EarningsSource {
+	payments: u64, // Recorded by the ledgers of all nodes
+	nodes: usize,
+	period: EarningsPeriod,
+	currency_apiname: String,
+	currency_per_token: Option<f64>,
+}
+
 pub struct DashState {
 	pub vdash_status: StatusMessage,
+	pub keymap: Keymap, // Actions of each key (see --keymap and --key)
 	pub main_view: DashViewMain,
 	pub previous_main_view: DashViewMain,
+	pub split_view: bool, // Summary shown above the focused node (see is_split_view())
 	pub logfile_names_sorted: Vec<String>,
 	pub logfile_names_sorted_ascending: bool,
 
@@ -1644,17 +2174,63 @@ pub struct DashState {
 
 	pub active_timescale: usize,
 	pub node_logfile_visible: bool,
+	pub node_timelines_height: u16, // Rows for Node Status timelines when the logfile is shown
+	pub top_messages_visible: bool, // Top errors panel replaces the logfile in the Node view
+	pub node_diagnostics_visible: bool, // The Node view shows its Diagnostics tab rather than Status
 	pub dash_node_focus: String,
 	pub mmm_ui_mode: MinMeanMax,
+	pub mmm_all_series: bool, // Show min, mean and max timelines together
+	pub average_mode: AverageMode, // Average of storage cost and connections in the Summary
+	pub sparkline_braille: bool, // Draw timelines as braille lines rather than bars
+	pub timeline_cursor: Option<usize>, // Columns back from the latest bucket, when the axis and cursor are shown
+	pub breakdown_timeline: String, // Key of the timeline shown for each node in the Summary
+	pub logfile_prefix: String, // Directory shared by all logfiles, left out of names shown
+	pub change_highlight: Duration, // How long changed Summary cells are coloured
+	pub number_format: NumberFormat, // How counts and amounts are written
+	pub token_unit: TokenUnit, // Unit amounts are shown in, as logged by the nodes
+	pub session_delta: bool, // Summary counts are since the session started rather than totals
+	pub earnings_period: EarningsPeriod, // Earnings view lists payments, or totals by day or week
+	pub session_started: DateTime<Utc>,
 	pub top_timeline: usize, // Timeline to show at top of UI
+	pub timeline_window_end: Option<DateTime<Utc>>, // Timelines end here rather than now
+	pub earnings_units: EarningsUnits,
 
 	pub summary_window_heading: String, // TODO delete in favour of...
 	pub summary_window_headings: StatefulList<String>,
 	pub summary_window_heading_selected: usize,
+	pub summary_sort_then_by: Vec<(usize, bool)>, // Further sort keys as (column, ascending)
 	pub summary_window_rows: StatefulList<String>,
+	pub summary_window_first_row: usize, // First row in view
+	pub summary_window_page_rows: usize, // Rows in view when last drawn
 	max_summary_window: usize,
 
+	pub earnings_window_rows: StatefulList<String>,
+	pub earnings_window_source: Option<EarningsSource>,
+
+	pub fleet_window_rows: StatefulList<String>,
+	pub fleet_rows_significant: Vec<bool>,
+
 	pub help_status: StatefulList<String>,
+	pub help_page_rows: usize, // Rows of Help shown when last drawn
+
+	// antctl action awaiting confirmation (see --antctl-command)
+	pub pending_node_action: Option<PendingNodeAction>,
+	pub pending_node_removal: Option<(String, DateTime<Utc>)>, // Logfile of node to remove when confirmed, and when that times out
+
+	pub input_prompt: Option<InputPrompt>,
+	pub node_filter: Option<NodeFilter>, // Restricts Summary table and totals
+
+	// Summary rows grouped with subtotals (see --group and --group-by)
+	pub summary_grouped: bool,
+	pub collapsed_groups: Vec<String>,
+	pub group_totals: HashMap<String, GroupTotals>,
+
+	pub replay: Option<Replay>, // See --replay
+
+	// Blank or dim the UI without input (see --idle-minutes)
+	pub last_input_time: DateTime<Utc>,
+	pub idle_timeout: Option<Duration>,
+	pub idle_dim: bool,
 
 	// For --debug-window option
 	pub debug_window_list: StatefulList<String>,
@@ -1674,9 +2250,11 @@ impl DashState {
 				&String::from(UI_STATUS_DEFAULT_MESSAGE),
 				&Duration::seconds(UI_STATUS_DEFAULT_DURATION_S),
 			),
+			keymap: Keymap::default(),
 
 			main_view: DashViewMain::DashSummary,
 			previous_main_view: DashViewMain::DashSummary,
+			split_view: false,
 			logfile_names_sorted: Vec::<String>::new(), // Sorted by column
 			logfile_names_sorted_ascending: true,
 
@@ -1686,27 +2264,89 @@ impl DashState {
 
 			active_timescale: 0,
 			node_logfile_visible: true,
+			node_timelines_height: NODE_TIMELINES_HEIGHT,
+			top_messages_visible: false,
+			node_diagnostics_visible: false,
 			dash_node_focus: String::new(),
 			mmm_ui_mode: MinMeanMax::Mean,
+			average_mode: AverageMode::Mean,
+			mmm_all_series: false,
+			sparkline_braille: false,
+			timeline_cursor: None,
+			breakdown_timeline: String::from(GETS_TIMELINE_KEY),
+			logfile_prefix: String::new(),
+			change_highlight: Duration::zero(),
+			number_format: NumberFormat::Plain,
+			token_unit: TokenUnit::Attos,
+			session_delta: false,
+			earnings_period: EarningsPeriod::Payment,
+			session_started: Utc::now(),
 			top_timeline: 0,
+			timeline_window_end: None,
+			earnings_units: EarningsUnits::Attos,
 
 			summary_window_heading: String::from(""),
 			summary_window_headings: StatefulList::new(),
 			summary_window_heading_selected: 0,
+			summary_sort_then_by: Vec::new(),
 			summary_window_rows: StatefulList::new(),
+			summary_window_first_row: 0,
+			summary_window_page_rows: 0,
 			max_summary_window: 1000,
 
+			earnings_window_rows: StatefulList::new(),
+			earnings_window_source: None,
+
+			fleet_window_rows: StatefulList::new(),
+			fleet_rows_significant: Vec::new(),
+
 			help_status: StatefulList::with_items(vec![]),
+			help_page_rows: 0,
+
+			pending_node_action: None,
+			pending_node_removal: None,
+
+			input_prompt: None,
+			node_filter: None,
+
+			summary_grouped: false,
+			collapsed_groups: Vec::new(),
+			group_totals: HashMap::new(),
+
+			replay: None,
+
+			last_input_time: Utc::now(),
+			idle_timeout: None,
+			idle_dim: false,
 
 			debug_window: false,
 			debug_window_has_focus: false,
 			debug_window_list: StatefulList::new(),
-			max_debug_window: 100,
+			max_debug_window: MAX_DEBUG_LINES,
 		};
 		super::ui_summary_table::initialise_summary_headings(&mut new_dash);
 		new_dash
 	}
 
+	/// True if the Summary is shown with the node of its selected row below it
+	pub fn is_split_view(&self) -> bool {
+		self.split_view && self.main_view == DashViewMain::DashSummary
+	}
+
+	/// True if there has been no input for longer than --idle-minutes
+	pub fn is_idle(&self, now: &DateTime<Utc>) -> bool {
+		self
+			.idle_timeout
+			.is_some_and(|idle_timeout| *now - self.last_input_time > idle_timeout)
+	}
+
+	/// Replace the debug window lines with those of the node gaining focus
+	pub fn show_debug_lines(&mut self, debug_lines: &DebugLines) {
+		self.debug_window_list.items = debug_lines.lines().cloned().collect();
+		let len = self.debug_window_list.items.len();
+		self.debug_window_list.state.select(len.checked_sub(1));
+	}
+
 	pub fn _debug_window(&mut self, text: &str) {
 		self.debug_window_list.items.push(text.to_string());
 		let len = self.debug_window_list.items.len();
@@ -1731,15 +2371,32 @@ impl DashState {
 		};
 	}
 
-	// Rotate UI display state through Min, Mean, Max values
+	// Rotate UI display state through Min, Mean, Max values and then the 50th, 95th and 99th percentiles
 	pub fn bump_mmm_ui_mode(&mut self) {
 		match &self.mmm_ui_mode {
 			MinMeanMax::Min => self.mmm_ui_mode = MinMeanMax::Mean,
 			MinMeanMax::Mean => self.mmm_ui_mode = MinMeanMax::Max,
-			MinMeanMax::Max => self.mmm_ui_mode = MinMeanMax::Min,
+			MinMeanMax::Max => self.mmm_ui_mode = MinMeanMax::P50,
+			MinMeanMax::P50 => self.mmm_ui_mode = MinMeanMax::P95,
+			MinMeanMax::P95 => self.mmm_ui_mode = MinMeanMax::P99,
+			MinMeanMax::P99 => self.mmm_ui_mode = MinMeanMax::Min,
 		}
 	}
 
+	// Rotate the Summary's average of storage cost and connections through mean, weighted and recent
+	pub fn bump_average_mode(&mut self) {
+		self.average_mode = self.average_mode.next();
+	}
+
+	// Rotate earnings timeline units through attos, ANT and currency (if a rate is known)
+	pub fn bump_earnings_units(&mut self) {
+		self.earnings_units = match self.earnings_units {
+			EarningsUnits::Attos => EarningsUnits::Ant,
+			EarningsUnits::Ant if self.currency_per_token.is_some() => EarningsUnits::Currency,
+			_ => EarningsUnits::Attos,
+		};
+	}
+
 	pub fn top_timeline_index(&self) -> usize {
 		return self.top_timeline;
 	}
@@ -1771,7 +2428,7 @@ pub fn set_main_view(view: DashViewMain, app: &mut App) {
 
 pub fn save_focus(app: &mut App) {
 	match app.dash_state.main_view {
-		DashViewMain::DashHelp => {}
+		DashViewMain::DashHelp | DashViewMain::DashEarnings | DashViewMain::DashFleet => {}
 
 		DashViewMain::DashSummary | DashViewMain::DashNode => {
 			if let Some(focus) = app.get_logfile_with_focus() {
@@ -1784,7 +2441,7 @@ pub fn save_focus(app: &mut App) {
 
 pub fn restore_focus(app: &mut App) {
 	match app.dash_state.main_view {
-		DashViewMain::DashHelp => {}
+		DashViewMain::DashHelp | DashViewMain::DashEarnings | DashViewMain::DashFleet => {}
 
 		DashViewMain::DashSummary | DashViewMain::DashNode => {
 			app.set_logfile_with_focus(app.dash_state.dash_node_focus.clone())
@@ -1796,31 +2453,178 @@ pub fn restore_focus(app: &mut App) {
 		}
 	}
 }
+
+/// Loading the logfile, which reports progress and problems in the status bar
+impl LogMonitor {
+	pub fn load_logfile_from_time(
+		&mut self,
+		dash_state: &mut DashState,
+		after_time: Option<DateTime<Utc>>,
+	) -> std::io::Result<()> {
+		profile_scope!("load_logfile");
+		if let Some(after_time) = after_time {
+			dash_state.vdash_status.message(
+				&format!("loading logfile after time: {}", after_time).to_string(),
+				None,
+			);
+		}
+
+		use std::io::{BufRead, BufReader, Cursor, Seek, SeekFrom};
+
+		let f = File::open(self.logfile.to_string());
+		let f = match f {
+			Ok(file) => file,
+			Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+				// Keep the monitor so the node is shown with its status rather than as dead
+				self.metrics.permission_denied = true;
+				self.metrics.update_node_status_string();
+				dash_state.vdash_status.message(
+					&format!(
+						"Permission denied: {} (run vdash as the node's user or grant read access, e.g. 'sudo chmod -R a+rX <node-dir>')",
+						self.logfile
+					),
+					None,
+				);
+				return Ok(());
+			}
+			Err(_e) => return Ok(()), // It's ok for a logfile not to exist yet
+		};
+		self.metrics.permission_denied = false;
+
+		// With --mmap large logfiles are mapped into memory, falling back to reading them if that fails
+		let mapped = match OPT.lock().unwrap().mmap {
+			true => map_logfile(&f).ok().flatten(),
+			false => None,
+		};
+
+		if let Some(mapped) = mapped {
+			// Skip what the checkpoint already covers without reading it line by line
+			let offset = match after_time {
+				Some(after_time) => offset_before_time(&mut Cursor::new(&mapped[..]), &after_time)? as usize,
+				None => 0,
+			};
+			let mut lines = LossyLines::new(&mapped[offset..]);
+			if offset > 0 {
+				lines.next(); // Skip the partial line
+			}
+			for (line, is_invalid) in lines {
+				self.load_line(dash_state, &line, is_invalid, after_time)?;
+			}
+		} else {
+			let mut f = BufReader::new(f);
+
+			// Skip what the checkpoint already covers without reading it line by line
+			if let Some(after_time) = after_time {
+				let offset = offset_before_time(&mut f, &after_time)?;
+				f.seek(SeekFrom::Start(offset))?;
+				if offset > 0 {
+					f.read_until(b'\n', &mut Vec::new())?; // Skip the partial line
+				}
+			}
+
+			// The line buffer is reused, so only lines which are not valid UTF-8 are copied
+			let mut bytes = Vec::new();
+			while let Some((line, is_invalid)) = read_lossy_line(&mut f, &mut bytes)? {
+				self.load_line(dash_state, &line, is_invalid, after_time)?;
+			}
+		}
+
+		if self.content.items.len() > 0 {
+			self
+				.content
+				.state
+				.select(Some(self.content.items.len() - 1));
+		}
+
+		Ok(())
+	}
+
+	fn load_line(
+		&mut self,
+		dash_state: &mut DashState,
+		line: &str,
+		is_invalid: bool,
+		after_time: Option<DateTime<Utc>>,
+	) -> std::io::Result<()> {
+		if is_invalid {
+			self.metrics.invalid_utf8_lines += 1;
+		}
+		self.append_to_content_from_time(line, after_time)?;
+		if let Some(parser_output) = self.record_parser_output() {
+			dash_state._debug_window(&parser_output);
+		}
+		Ok(())
+	}
+}
+
+// Below this size the remainder of a logfile is read line by line
+const BISECTION_MIN_BYTES: u64 = 64 * 1024;
+
+/// Find an offset in a logfile before the first entry later than time, by
+/// bisection on the times of entries (which are assumed to be in order). Only
+/// entries at or before time are in the part of the file before the offset,
+/// which may be part way through a line.
+fn offset_before_time<R: std::io::BufRead + std::io::Seek>(
+	reader: &mut R,
+	time: &DateTime<Utc>,
+) -> std::io::Result<u64> {
+	use std::io::SeekFrom;
+
+	let mut low: u64 = 0;
+	let mut high = reader.seek(SeekFrom::End(0))?;
+
+	// Time of the first entry starting after offset, if any
+	let mut entry_time_after = |offset: u64| -> std::io::Result<Option<DateTime<Utc>>> {
+		reader.seek(SeekFrom::Start(offset))?;
+		reader.read_until(b'\n', &mut Vec::new())?; // Partial line, which may not be valid UTF-8
+		let mut bytes = Vec::new();
+		while let Some((line, _is_invalid)) = read_lossy_line(reader, &mut bytes)? {
+			if let Some(metadata) = LogEntry::decode_metadata(&line) {
+				return Ok(Some(metadata.message_time));
+			}
+		}
+		Ok(None)
+	};
+
+	while high - low > BISECTION_MIN_BYTES {
+		let middle = low + (high - low) / 2;
+		match entry_time_after(middle)? {
+			Some(entry_time) if entry_time <= *time => low = middle,
+			_ => high = middle,
+		}
+	}
+	Ok(low)
+}
+
 #[cfg(test)]
 mod tests {
 
-	mod log_parsing {
-		use std::str::FromStr;
+	mod logfile_loading {
+		use std::io::Cursor;
 
-		use chrono::{DateTime, Utc};
+		use chrono::{DateTime, Duration, Utc};
 
-		use crate::custom::app::LogEntry;
+		use crate::custom::app::offset_before_time;
 
 		#[test]
-		fn it_parses() {
-			let message_time = "2024-03-23T19:38:32.350118Z";
-			let source = "sn_networking::event";
-			let category = "WARN";
-			let message = "MsgReceivedError: InternalMsgChannelDropped";
-			let line = format!("[{} {} {}] {}", message_time, category, source, message);
-			let metadata = LogEntry::decode_metadata(&line).unwrap();
-
-			let message_time: DateTime<Utc> = DateTime::from_str(message_time).unwrap();
-
-			assert_eq!(metadata.category, category);
-			assert_eq!(metadata.message_time, message_time);
-			assert_eq!(metadata.source, source);
-			assert_eq!(metadata.message, message);
+		fn it_finds_where_to_start_loading_by_bisection() {
+			let start: DateTime<Utc> = "2024-03-23T19:00:00Z".parse().unwrap();
+			let mut log = String::new();
+			let mut offsets = Vec::new();
+			for i in 0..20_000 {
+				offsets.push(log.len() as u64);
+				let time = (start + Duration::seconds(i)).format("%Y-%m-%dT%H:%M:%S%.6fZ");
+				log += &format!("[{} INFO ant_node] Entry {}\n  continued ➤\n", time, i);
+			}
+
+			let checkpoint_time = start + Duration::seconds(12_345);
+			let offset = offset_before_time(&mut Cursor::new(log.as_bytes()), &checkpoint_time).unwrap();
+			assert!(offset > 0);
+			assert!(offset < offsets[12_346]);
+			assert!(offset + 2 * 64 * 1024 > offsets[12_346]);
+
+			let offset = offset_before_time(&mut Cursor::new(log.as_bytes()), &(start - Duration::seconds(1))).unwrap();
+			assert_eq!(offset, 0);
 		}
 	}
 }
@@ -4,21 +4,68 @@ use chrono::{DateTime, Duration, Utc};
 use ratatui::style::Color;
 use serde::{Deserialize, Serialize};
 
-use super::app::OPT;
+use super::node_metrics::OPT;
+use super::custom_metrics::{CUSTOM_KEY_PREFIX, CUSTOM_METRICS};
 use super::timelines::{Buckets, Timeline};
 use std::sync::LazyLock;
 
+pub const ONE_MINUTE_TIMESCALE: &str = "1 minute columns";
+
+/// Built in timescales, to which any given with --timescale are added
+const BUILT_IN_TIMESCALES: [(&str, i64); 6] = [
+	("1 second columns", 1),
+	(ONE_MINUTE_TIMESCALE, 60),
+	("1 hour columns", 60 * 60),
+	("1 day columns", 24 * 60 * 60),
+	("1 week columns", 7 * 24 * 60 * 60),
+	("1 year columns", 365 * 24 * 60 * 60),
+];
+
+/// Timescales from shortest to longest bucket duration. Invalid --timescale values are
+/// reported when vdash starts (see App::new()).
 pub static TIMESCALES: LazyLock<std::vec::Vec<(&'static str, Duration)>> = LazyLock::new(|| {
-	vec![
-		("1 second columns", Duration::seconds(1)),
-		("1 minute columns", Duration::minutes(1)),
-		("1 hour columns", Duration::hours(1)),
-		("1 day columns", Duration::days(1)),
-		("1 week columns", Duration::days(7)),
-		("1 year columns", Duration::days(365)),
-	]
+	let opt_timescale = OPT.lock().unwrap().timescale.clone();
+	let custom = parse_timescales(&opt_timescale).unwrap_or_default();
+	let mut timescales: Vec<(&'static str, Duration)> = BUILT_IN_TIMESCALES
+		.iter()
+		.map(|(name, seconds)| (*name, Duration::seconds(*seconds)))
+		.collect();
+	for (name, duration) in custom {
+		if !timescales.iter().any(|(_name, existing)| *existing == duration) {
+			// Timescale names live as long as vdash, like those built in
+			timescales.push((Box::leak(name.into_boxed_str()), duration));
+		}
+	}
+	timescales.sort_by_key(|(_name, duration)| *duration);
+	timescales
 });
 
+/// Timescales given with --timescale as a number and unit (e.g. "10s", "5m", "6h", "2d" or "2w"),
+/// as (name, bucket duration)
+pub fn parse_timescales(opt_timescale: &[String]) -> Result<Vec<(String, Duration)>, String> {
+	let mut timescales = Vec::<(String, Duration)>::new();
+	for text in opt_timescale {
+		let text = text.trim();
+		let invalid = || format!("invalid --timescale '{}', expected a number and one of s, m, h, d or w (e.g. 10s)", text);
+		let unit_start = text.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+		let count = text[..unit_start].parse::<i64>().map_err(|_| invalid())?;
+		if count == 0 {
+			return Err(invalid());
+		}
+		let (unit_name, unit_seconds) = match text[unit_start..].trim() {
+			"s" => ("second", 1),
+			"m" => ("minute", 60),
+			"h" => ("hour", 60 * 60),
+			"d" => ("day", 24 * 60 * 60),
+			"w" => ("week", 7 * 24 * 60 * 60),
+			_ => return Err(invalid()),
+		};
+		let seconds = count.checked_mul(unit_seconds).filter(|seconds| *seconds <= 100 * 365 * 24 * 60 * 60).ok_or_else(invalid)?;
+		timescales.push((format!("{} {} columns", count, unit_name), Duration::seconds(seconds)));
+	}
+	Ok(timescales)
+}
+
 pub const EARNINGS_UNITS_TEXT: &str = "attos";
 pub const STORAGE_COST_UNITS_TEXT: &str = "attos/MB";
 
@@ -64,29 +111,114 @@ pub const APP_TIMELINES: [(&str, &str, &str, bool, bool, Color); 7] = [
 	(ERRORS_TIMELINE_KEY, "ERRORS", "", false, true, Color::Red),
 ];
 
+/// The timelines of APP_TIMELINES followed by those of any custom metrics, as
+/// (key, UI name, units_text, is_mmm, is_cumulative, colour)
+pub fn timeline_specs() -> Vec<(String, String, String, bool, bool, Color)> {
+	let mut specs: Vec<(String, String, String, bool, bool, Color)> = APP_TIMELINES
+		.iter()
+		.map(|(key, name, units_text, is_mmm, is_cumulative, colour)| {
+			(key.to_string(), name.to_string(), units_text.to_string(), *is_mmm, *is_cumulative, *colour)
+		})
+		.collect();
+	for metric in CUSTOM_METRICS.iter() {
+		specs.push((metric.key.clone(), metric.name.clone(), String::from(""), metric.is_mmm, metric.is_cumulative, metric.colour));
+	}
+	specs
+}
+
+/// The key of the timeline with a key or UI name (ignoring case)
+fn timeline_key(wanted: &str) -> Option<String> {
+	timeline_specs()
+		.into_iter()
+		.find(|(key, name, ..)| key.eq_ignore_ascii_case(wanted) || name.eq_ignore_ascii_case(wanted))
+		.map(|(key, ..)| key)
+}
+
+/// Keys of the timelines to display, in order, from --timelines (a comma separated
+/// list of timeline keys or names). All timelines are displayed by default.
+pub fn timeline_order(opt_timelines: &Option<String>) -> Result<Vec<String>, String> {
+	let Some(opt_timelines) = opt_timelines else {
+		return Ok(timeline_specs().into_iter().map(|(key, ..)| key).collect());
+	};
+
+	let mut order = Vec::<String>::new();
+	for wanted in opt_timelines.split(',').map(str::trim).filter(|wanted| !wanted.is_empty()) {
+		match timeline_key(wanted) {
+			Some(key) if !order.contains(&key) => order.push(key),
+			Some(_) => {}
+			None => return Err(format!("unknown timeline '{}' in --timelines, expected one of: {}", wanted, timeline_names())),
+		}
+	}
+	if order.is_empty() {
+		return Err(String::from("--timelines must name at least one timeline"));
+	}
+	Ok(order)
+}
+
+/// Timeline colours from --timeline-colour values of the form <timeline>=<colour>, where
+/// the colour is a name (e.g. LightRed) or #rrggbb
+pub fn timeline_colours(opt_colours: &[String]) -> Result<HashMap<String, Color>, String> {
+	let mut colours = HashMap::<String, Color>::new();
+	for opt_colour in opt_colours {
+		let Some((wanted, colour)) = opt_colour.split_once('=') else {
+			return Err(format!("invalid --timeline-colour '{}', expected <timeline>=<colour>", opt_colour));
+		};
+		let Some(key) = timeline_key(wanted.trim()) else {
+			return Err(format!("unknown timeline '{}' in --timeline-colour, expected one of: {}", wanted, timeline_names()));
+		};
+		let Ok(colour) = colour.trim().parse::<Color>() else {
+			return Err(format!("invalid colour '{}' in --timeline-colour", colour));
+		};
+		colours.insert(key, colour);
+	}
+	Ok(colours)
+}
+
+fn timeline_names() -> String {
+	let specs = timeline_specs();
+	let keys: Vec<&str> = specs
+		.iter()
+		.map(|(key, name, ..)| if key.starts_with(CUSTOM_KEY_PREFIX) { name.as_str() } else { key.as_str() })
+		.collect();
+	keys.join(", ")
+}
+
+/// Version of the serialised AppTimelines (see AppTimelines::restore())
+pub const APP_TIMELINES_VERSION: u32 = 1;
+
 /// Holds the Timeline structs for a node, as used by this app
 #[derive(Clone, Default, Debug, Serialize, Deserialize)]
 pub struct AppTimelines {
+	#[serde(default)]
+	pub version: u32, // Zero when restored from a checkpoint saved before versioning
 	timelines: HashMap<String, Timeline>,
+	#[serde(skip)]
+	order: Vec<String>, // Keys of the displayed timelines (see --timelines)
 }
 
 impl AppTimelines {
 	pub fn new() -> AppTimelines {
-		let opt_timeline_steps = {
+		let (opt_timeline_steps, opt_timelines, opt_timeline_colour) = {
 			let opt = OPT.lock().unwrap();
-			opt.timeline_steps
+			(opt.timeline_steps, opt.timelines.clone(), opt.timeline_colour.clone())
 		};
+		// Invalid options are reported when vdash starts (see App::new())
+		let order = timeline_order(&opt_timelines).unwrap_or_else(|_| timeline_order(&None).unwrap());
+		let colours = timeline_colours(&opt_timeline_colour).unwrap_or_default();
 
 		let mut app_timelines = AppTimelines {
+			version: APP_TIMELINES_VERSION,
 			timelines: HashMap::<String, Timeline>::new(),
+			order,
 		};
 
-		for (key, name, units_text, is_mmm, is_cumulative, colour) in APP_TIMELINES {
+		for (key, name, units_text, is_mmm, is_cumulative, colour) in timeline_specs() {
+			let colour = colours.get(&key).copied().unwrap_or(colour);
 			app_timelines.timelines.insert(
-				key.to_string(),
+				key,
 				Timeline::new(
-					name.to_string(),
-					units_text.to_string(),
+					name,
+					units_text,
 					is_mmm,
 					is_cumulative,
 					colour,
@@ -105,6 +237,32 @@ impl AppTimelines {
 		return app_timelines;
 	}
 
+	/// Make timelines restored from a checkpoint continue seamlessly from it
+	///
+	/// Timelines and timescales added since the checkpoint was saved are created
+	/// empty, and those removed are dropped. Buckets are resized to the current
+	/// --timeline-steps keeping the most recent, and then brought up to the
+	/// time of the last logfile entry in the checkpoint. Loading of the logfile
+	/// resumes after that entry, so the lines it skips are already counted.
+	pub fn restore(&mut self, latest_entry_time: Option<DateTime<Utc>>) {
+		let current = AppTimelines::new();
+		self.order = current.order.clone();
+		self.timelines.retain(|key, _| current.timelines.contains_key(key));
+		for (key, current_timeline) in current.timelines {
+			match self.timelines.get_mut(&key) {
+				Some(timeline) => timeline.restore(current_timeline),
+				None => {
+					self.timelines.insert(key, current_timeline);
+				}
+			}
+		}
+
+		if let Some(latest_entry_time) = latest_entry_time {
+			self.update_timelines(&latest_entry_time);
+		}
+		self.version = APP_TIMELINES_VERSION;
+	}
+
 	pub fn update_timelines(&mut self, now: &DateTime<Utc>) {
 		for (_, timeline) in self.timelines.iter_mut() {
 			timeline.update_current_time(&now);
@@ -115,21 +273,157 @@ impl AppTimelines {
 		return self.timelines.get_mut(key);
 	}
 
+	/// The index'th of the displayed timelines
 	pub fn get_timeline_by_index(&self, index: usize) -> Option<&Timeline> {
-		let (key, _, _, _, _, _) = APP_TIMELINES[index];
+		let key = self.order.get(index)?;
 		return self.timelines.get(key);
 	}
 
 	// Gets the set of buckets for the index'th Timeline, selecting with Min, Mean, Max if appropriate
 	pub fn get_timeline_buckets(&mut self, index: usize, timescale_name: &str) -> Option<&Buckets> {
-		let (key, _, _, _, _, _) = APP_TIMELINES[index];
+		let key = self.order.get(index)?;
 		if let Some(timeline) = self.timelines.get_mut(key) {
 			return timeline.get_bucket_set(timescale_name);
 		}
 		return None;
 	}
 
+	/// Number of timelines displayed
 	pub fn get_num_timelines(self: &AppTimelines) -> usize {
-		return APP_TIMELINES.len();
+		self.order.len()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn it_migrates_and_restores_checkpointed_timelines() {
+		let time = |text: &str| DateTime::parse_from_rfc3339(text).unwrap().with_timezone(&Utc);
+		let mut app_timelines = AppTimelines::new();
+		app_timelines.update_timelines(&time("2024-03-23T19:00:00Z"));
+		let earnings = app_timelines.get_timeline_by_key(EARNINGS_TIMELINE_KEY).unwrap();
+		earnings.update_value(&time("2024-03-23T19:00:00Z"), 100);
+
+		// A checkpoint saved before versioning, with fewer timelines, timescales and steps
+		let mut json: serde_json::Value = serde_json::to_value(&app_timelines).unwrap();
+		json.as_object_mut().unwrap().remove("version");
+		let timelines = json["timelines"].as_object_mut().unwrap();
+		timelines.remove(ERRORS_TIMELINE_KEY);
+		let earnings_buckets = timelines[EARNINGS_TIMELINE_KEY]["buckets"].as_object_mut().unwrap();
+		earnings_buckets.remove("1 year columns");
+		let hours = &mut earnings_buckets["1 hour columns"];
+		hours["num_buckets"] = serde_json::json!(3);
+		hours["buckets"] = serde_json::json!([0, 0, 100]);
+
+		let mut restored: AppTimelines = serde_json::from_value(json).unwrap();
+		assert_eq!(restored.version, 0);
+		restored.restore(Some(time("2024-03-23T21:30:00Z")));
+		assert_eq!(restored.version, APP_TIMELINES_VERSION);
+		assert!(restored.get_timeline_by_key(ERRORS_TIMELINE_KEY).is_some());
+
+		let steps = OPT.lock().unwrap().timeline_steps;
+		let earnings = restored.get_timeline_by_key(EARNINGS_TIMELINE_KEY).unwrap();
+		assert!(earnings.get_bucket_set("1 year columns").is_some());
+		let hours = earnings.get_bucket_set("1 hour columns").unwrap();
+		assert_eq!(hours.buckets.len(), steps);
+		assert_eq!(hours.values_total, 100);
+		// The payment has moved back two hours as the buckets caught up with the checkpoint
+		assert_eq!(hours.buckets[steps - 3], 100);
+	}
+
+	#[test]
+	fn it_parses_timescales_and_rebuckets_them() {
+		use crate::custom::timelines::MinMeanMax;
+
+		let timescales = parse_timescales(&[String::from("10s"), String::from(" 6h ")]).unwrap();
+		assert_eq!(timescales[0], (String::from("10 second columns"), Duration::seconds(10)));
+		assert_eq!(timescales[1], (String::from("6 hour columns"), Duration::hours(6)));
+		for invalid in ["10", "s", "0m", "5y", "99999999999999w"] {
+			assert!(parse_timescales(&[String::from(invalid)]).is_err());
+		}
+
+		// Ten 1 second buckets of a cumulative timeline make two 5 second buckets and part of a third
+		let time = |text: &str| DateTime::parse_from_rfc3339(text).unwrap().with_timezone(&Utc);
+		let mut finer = Buckets::new(Duration::seconds(1), 10, false);
+		finer.update_current_time(&time("2024-03-23T19:00:00Z"), true);
+		finer.update_current_time(&time("2024-03-23T19:00:09.5Z"), true);
+		for (index, value) in [(0, 1), (1, 2), (4, 3), (5, 4), (9, 5)] {
+			finer.bucket_update_value(index, value, true);
+		}
+		let mut coarser = Buckets::new(Duration::seconds(5), 4, false);
+		assert!(coarser.rebucket_from(&finer, true));
+		assert_eq!(coarser.buckets, vec![0, 3, 7, 5]);
+		assert_eq!(coarser.values_total, 15);
+		assert_eq!(coarser.bucket_time, finer.bucket_time);
+		assert!(!Buckets::new(Duration::seconds(3), 4, false).rebucket_from(&Buckets::new(Duration::seconds(2), 4, false), true));
+
+		// Min, mean and max combine the values of each finer bucket
+		let mut finer = Buckets::new(Duration::minutes(1), 4, true);
+		finer.update_current_time(&time("2024-03-23T19:00:00Z"), false);
+		for (index, value) in [(2, 10), (2, 30), (3, 20)] {
+			finer.bucket_update_value(index, value, false);
+		}
+		let mut coarser = Buckets::new(Duration::minutes(2), 2, true);
+		assert!(coarser.rebucket_from(&finer, false));
+		assert_eq!(coarser.buckets(Some(&MinMeanMax::Min)), &vec![10, 20]);
+		assert_eq!(coarser.buckets(Some(&MinMeanMax::Mean)), &vec![20, 20]);
+		assert_eq!(coarser.buckets(Some(&MinMeanMax::Max)), &vec![30, 20]);
+		let p99 = coarser.buckets(Some(&MinMeanMax::P99));
+		assert!((29..=31).contains(&p99[0]) && (19..=21).contains(&p99[1]));
+	}
+
+	#[test]
+	fn it_backfills_timescales_added_later() {
+		let time = |text: &str| DateTime::parse_from_rfc3339(text).unwrap().with_timezone(&Utc);
+		let mut timeline = Timeline::new(String::from("GETS"), String::from(""), false, true, Color::Green);
+		timeline.add_bucket_set("1 minute columns", Duration::minutes(1), 10);
+		timeline.update_current_time(&time("2024-03-23T19:00:00Z"));
+		timeline.update_current_time(&time("2024-03-23T19:05:30Z"));
+		timeline.update_value(&time("2024-03-23T19:01:10Z"), 2);
+		timeline.update_value(&time("2024-03-23T19:05:10Z"), 3);
+
+		timeline.add_bucket_set("5 minute columns", Duration::minutes(5), 3);
+		let added = timeline.get_bucket_set("5 minute columns").unwrap();
+		assert_eq!(added.buckets, vec![0, 2, 3]);
+		assert_eq!(added.values_total, 5);
+
+		// Nothing fits evenly into 90 second columns, so they start empty
+		timeline.add_bucket_set("90 second columns", Duration::seconds(90), 3);
+		assert_eq!(timeline.get_bucket_set("90 second columns").unwrap().values_total, 0);
+	}
+
+	#[test]
+	fn it_totals_earnings_beyond_u64_and_checkpoints_them() {
+		let time = |text: &str| DateTime::parse_from_rfc3339(text).unwrap().with_timezone(&Utc);
+		let mut timeline = Timeline::new(String::from("Earnings"), String::from(""), false, true, Color::Green);
+		timeline.add_bucket_set("1 minute columns", Duration::minutes(1), 10);
+		timeline.update_current_time(&time("2024-03-23T19:00:00Z"));
+		let ten_ant = 10_000_000_000_000_000_000;
+		timeline.update_value(&time("2024-03-23T19:00:10Z"), ten_ant);
+		timeline.update_value(&time("2024-03-23T19:00:20Z"), ten_ant);
+
+		let minutes = timeline.get_bucket_set("1 minute columns").unwrap();
+		assert_eq!(minutes.buckets.last(), Some(&(2 * ten_ant)));
+		assert_eq!(minutes.values_total, 2 * ten_ant);
+
+		let restored: Timeline = serde_json::from_str(&serde_json::to_string(&timeline).unwrap()).unwrap();
+		assert_eq!(restored.get_bucket_set("1 minute columns").unwrap().values_total, 2 * ten_ant);
+	}
+
+	#[test]
+	fn it_parses_timeline_order_and_colours() {
+		assert_eq!(timeline_order(&None).unwrap().len(), APP_TIMELINES.len());
+		let order = timeline_order(&Some(String::from("Earnings, errors,puts,earnings"))).unwrap();
+		assert_eq!(order, vec![EARNINGS_TIMELINE_KEY, ERRORS_TIMELINE_KEY, PUTS_TIMELINE_KEY]);
+		assert!(timeline_order(&Some(String::from("disk"))).is_err());
+		assert!(timeline_order(&Some(String::from(" , "))).is_err());
+
+		let colours = timeline_colours(&[String::from("errors=LightRed"), String::from("RAM=#ff8000")]).unwrap();
+		assert_eq!(colours[ERRORS_TIMELINE_KEY], Color::LightRed);
+		assert_eq!(colours[RAM_TIMELINE_KEY], Color::Rgb(255, 128, 0));
+		assert!(timeline_colours(&[String::from("errors=notacolour")]).is_err());
+		assert!(timeline_colours(&[String::from("errors")]).is_err());
 	}
 }
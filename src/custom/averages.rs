@@ -0,0 +1,106 @@
+//! Averages of storage cost and connections which favour current conditions
+//!
+//! The mean of an MmmStat is of every sample it has been given, which can lag
+//! well behind what nodes are seeing now. So the Summary's average column can
+//! instead weight each node by the records it stores, so that nodes holding
+//! more data count for more, or use each node's time-decayed average (an
+//! exponentially weighted moving average) in which a sample's weight halves
+//! every RECENT_HALF_LIFE_MINUTES.
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+pub const RECENT_HALF_LIFE_MINUTES: f64 = 60.0;
+
+/// Which average the Summary shows beside min and max (see key '=')
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum AverageMode {
+	#[default]
+	Mean,
+	Weighted,
+	Recent,
+}
+
+impl AverageMode {
+	pub fn next(&self) -> AverageMode {
+		match self {
+			AverageMode::Mean => AverageMode::Weighted,
+			AverageMode::Weighted => AverageMode::Recent,
+			AverageMode::Recent => AverageMode::Mean,
+		}
+	}
+
+	/// Column heading for the average
+	pub fn heading(&self) -> &'static str {
+		match self {
+			AverageMode::Mean => "mean",
+			AverageMode::Weighted => "weighted",
+			AverageMode::Recent => "recent",
+		}
+	}
+}
+
+/// Exponentially weighted moving average of samples taken at irregular times
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct DecayedAverage {
+	pub value: f64,
+	latest_time: Option<DateTime<Utc>>,
+}
+
+impl DecayedAverage {
+	pub fn add_sample(&mut self, time: &DateTime<Utc>, value: f64) {
+		match self.latest_time {
+			None => self.value = value,
+			Some(latest_time) => {
+				let elapsed_minutes = (*time - latest_time).num_milliseconds().max(0) as f64 / 60_000.0;
+				let weight = 1.0 - 0.5_f64.powf(elapsed_minutes / RECENT_HALF_LIFE_MINUTES);
+				self.value += weight * (value - self.value);
+			}
+		}
+		self.latest_time = self.latest_time.max(Some(*time));
+	}
+}
+
+/// Weighted mean of values across nodes
+#[derive(Default)]
+pub struct WeightedMean {
+	total: f64,
+	weight: f64,
+}
+
+impl WeightedMean {
+	pub fn add(&mut self, value: f64, weight: f64) {
+		self.total += value * weight;
+		self.weight += weight;
+	}
+
+	pub fn mean(&self) -> Option<f64> {
+		(self.weight > 0.0).then(|| self.total / self.weight)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use chrono::Duration;
+
+	#[test]
+	fn it_weights_and_decays_averages() {
+		let start: DateTime<Utc> = "2024-03-23T19:00:00Z".parse().unwrap();
+		let mut recent = DecayedAverage::default();
+		recent.add_sample(&start, 100.0);
+		recent.add_sample(&start, 200.0);
+		assert_eq!(recent.value, 100.0);
+		recent.add_sample(&(start + Duration::minutes(60)), 200.0);
+		assert_eq!(recent.value, 150.0);
+		recent.add_sample(&(start + Duration::days(7)), 20.0);
+		assert!((recent.value - 20.0).abs() < 0.001);
+
+		let mut weighted = WeightedMean::default();
+		assert_eq!(weighted.mean(), None);
+		weighted.add(10.0, 1.0);
+		weighted.add(40.0, 3.0);
+		assert_eq!(weighted.mean(), Some(32.5));
+
+		assert_eq!(AverageMode::Recent.next(), AverageMode::Mean);
+	}
+}
@@ -0,0 +1,18 @@
+//! Results of work done in the background, for the status bar
+//!
+//! antctl actions, tax reports, database writes and trigger commands run in
+//! their own tasks or threads, so can't show their results directly. Each
+//! pushes them here, and the App shows them when it next calls take().
+use std::sync::Mutex;
+
+static RESULTS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Add a result to be shown in the status bar
+pub fn push(result: String) {
+	RESULTS.lock().unwrap().push(result);
+}
+
+/// Results pushed since the last call, oldest first
+pub fn take() -> Vec<String> {
+	std::mem::take(&mut *RESULTS.lock().unwrap())
+}
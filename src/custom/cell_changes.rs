@@ -0,0 +1,61 @@
+//! Summary cells which have recently changed (see --highlight-changes)
+//!
+//! Each node keeps the cells of its Summary row as last formatted, and when
+//! each last changed, so that new earnings, errors and other activity can be
+//! coloured for a few seconds as it arrives.
+use chrono::{DateTime, Duration, Utc};
+
+#[derive(Clone, Debug, Default)]
+pub struct CellChanges {
+	pub cells: Vec<String>,
+	changed_at: Vec<Option<DateTime<Utc>>>,
+}
+
+impl CellChanges {
+	/// Record the cells of a row as now formatted, noting those which differ from
+	/// before. Nothing is noted for the first row, or if the columns have changed.
+	pub fn update(&mut self, cells: Vec<String>, now: DateTime<Utc>) {
+		if cells.len() == self.cells.len() {
+			for (column, cell) in cells.iter().enumerate() {
+				if *cell != self.cells[column] {
+					self.changed_at[column] = Some(now);
+				}
+			}
+		} else {
+			self.changed_at = vec![None; cells.len()];
+		}
+		self.cells = cells;
+	}
+
+	/// True if the cell in the column changed within highlight of now
+	pub fn is_recent(&self, column: usize, now: DateTime<Utc>, highlight: Duration) -> bool {
+		self.changed_at
+			.get(column)
+			.copied()
+			.flatten()
+			.is_some_and(|changed_at| now - changed_at < highlight && changed_at <= now)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn it_notes_cells_which_change() {
+		let now = Utc::now();
+		let highlight = Duration::seconds(5);
+		let row = |earnings: &str, errors: &str| vec![String::from("1 "), earnings.to_string(), errors.to_string()];
+
+		let mut changes = CellChanges::default();
+		changes.update(row("100", "0"), now);
+		assert!(!changes.is_recent(1, now, highlight));
+
+		changes.update(row("200", "0"), now + Duration::seconds(1));
+		assert!(changes.is_recent(1, now + Duration::seconds(2), highlight));
+		assert!(!changes.is_recent(2, now + Duration::seconds(2), highlight));
+		assert!(!changes.is_recent(1, now + Duration::seconds(6), highlight));
+		assert!(!changes.is_recent(1, now + Duration::seconds(2), Duration::zero()));
+		assert!(!changes.is_recent(9, now, highlight));
+	}
+}
@@ -0,0 +1,41 @@
+//! Copy text to the system clipboard
+//!
+//! The clipboard of the machine running vdash is used when there is one (with
+//! arboard). When there isn't, as over SSH without a display, the text is sent
+//! to the terminal with the OSC 52 escape sequence, which reaches the clipboard
+//! of the machine running the terminal. Most terminals support this, though
+//! some need it enabled in their settings, and none say whether it worked.
+use std::io::Write;
+use std::sync::{LazyLock, Mutex};
+
+use arboard::Clipboard;
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+/// Kept for as long as vdash runs because on Linux copied text is served by
+/// its owner, so is lost when the Clipboard is dropped
+static CLIPBOARD: LazyLock<Mutex<Option<Clipboard>>> = LazyLock::new(|| Mutex::new(Clipboard::new().ok()));
+
+/// Where copied text was sent
+#[derive(Debug, PartialEq)]
+pub enum Copied {
+	/// The system clipboard, which is known to hold the text
+	Clipboard,
+	/// The terminal, using OSC 52, which may or may not have passed it on
+	Terminal,
+}
+
+pub fn copy_to_clipboard(text: &str) -> std::io::Result<Copied> {
+	if let Some(clipboard) = CLIPBOARD.lock().unwrap().as_mut() {
+		if clipboard.set_text(text).is_ok() {
+			return Ok(Copied::Clipboard);
+		}
+	}
+	copy_with_osc52(text)?;
+	Ok(Copied::Terminal)
+}
+
+fn copy_with_osc52(text: &str) -> std::io::Result<()> {
+	let mut stdout = std::io::stdout();
+	write!(stdout, "\x1b]52;c;{}\x07", STANDARD.encode(text))?;
+	stdout.flush()
+}
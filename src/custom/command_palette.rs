@@ -0,0 +1,138 @@
+//! Commands typed at the ':' prompt
+//!
+//! The command palette gives access to features without remembering their
+//! keys, as one of:
+//!
+//!   sort <column> [asc|desc]      sort the Summary by a column heading
+//!   filter [expression]           filter the Summary as with '/', or clear it
+//!   export csv [path]             write the Summary table to a CSV file
+//!   export earnings [path]        write the Earnings Ledger as with 'x'
+//!   export tax [path]             write a tax report as with 'X'
+//!   add-glob <glob>               monitor logfiles matching another glob path
+//!
+//! or the name of any action which can be bound to a key (see --key), such
+//! as zoom-in or fleet-view.
+use crossterm::event::KeyEvent;
+
+use super::app::App;
+use super::keymap::{parse_action, Action};
+use super::ui_keyboard::perform_action;
+use super::ui_summary_table::SUMMARY_COLUMNS;
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+	Sort { column: usize, ascending: bool },
+	Filter(String),
+	ExportSummary(Option<String>),
+	ExportEarnings(Option<String>),
+	ExportTax(Option<String>),
+	AddGlob(String),
+	Action(Action),
+}
+
+pub fn parse_command(text: &str) -> Result<Command, String> {
+	let text = text.trim();
+	let (name, args) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+	let args = args.trim();
+	let path = |args: &str| if args.is_empty() { None } else { Some(args.to_string()) };
+
+	match name.to_lowercase().as_str() {
+		"" => Err(String::from("no command entered")),
+		"sort" => {
+			let (heading, ascending) = match args.rsplit_once(char::is_whitespace) {
+				Some((heading, "asc")) => (heading.trim(), true),
+				Some((heading, "desc")) => (heading.trim(), false),
+				_ => (args, true),
+			};
+			SUMMARY_COLUMNS
+				.iter()
+				.position(|(_metric, column_heading, _format)| column_heading.eq_ignore_ascii_case(heading))
+				.map(|column| Command::Sort { column, ascending })
+				.ok_or_else(|| format!("no Summary column '{}'", heading))
+		}
+		"filter" => Ok(Command::Filter(args.to_string())),
+		"export" => {
+			let (what, args) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+			match what {
+				"csv" => Ok(Command::ExportSummary(path(args.trim()))),
+				"earnings" => Ok(Command::ExportEarnings(path(args.trim()))),
+				"tax" => Ok(Command::ExportTax(path(args.trim()))),
+				_ => Err(String::from("export what? (csv, earnings or tax)")),
+			}
+		}
+		"add-glob" if !args.is_empty() => Ok(Command::AddGlob(args.to_string())),
+		"add-glob" => Err(String::from("add-glob needs a glob path")),
+		_ if args.is_empty() => parse_action(name)
+			.map(Command::Action)
+			.map_err(|_| format!("unknown command '{}'", name)),
+		_ => Err(format!("unknown command '{}'", name)),
+	}
+}
+
+/// Run the command typed at the prompt, showing any error in the status bar. Returns
+/// false if the command is to quit vdash.
+pub async fn run_command(app: &mut App, text: &str, event: &KeyEvent, opt_debug_window: bool) -> bool {
+	app.dash_state.vdash_status.clear_status();
+	let command = match parse_command(text) {
+		Ok(command) => command,
+		Err(e) => {
+			app.dash_state.vdash_status.message(&format!("Command failed, {}", e), None);
+			return true;
+		}
+	};
+
+	match command {
+		Command::Sort { column, ascending } => app.sort_summary_by(column, ascending),
+		Command::Filter(expression) => app.set_node_filter(&expression),
+		Command::ExportSummary(path) => app.export_summary_csv(path),
+		Command::ExportEarnings(path) => app.export_earnings_csv(path),
+		Command::ExportTax(path) => app.export_tax_report(path),
+		Command::AddGlob(glob) => app.add_glob_path(&glob).await,
+		Command::Action(Action::Quit) => return false,
+		Command::Action(action) => {
+			if !perform_action(app, action, event, opt_debug_window).await {
+				app.dash_state.vdash_status.message(&format!("{} does not apply here", text.trim()), None);
+			}
+		}
+	}
+	true
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn it_parses_commands() {
+		let earnings = SUMMARY_COLUMNS.iter().position(|(_, heading, _)| heading == "Earnings").unwrap();
+		assert_eq!(
+			parse_command("sort earnings desc"),
+			Ok(Command::Sort { column: earnings, ascending: false })
+		);
+		let ram = SUMMARY_COLUMNS.iter().position(|(_, heading, _)| heading == "MB RAM").unwrap();
+		assert_eq!(parse_command(" sort mb ram "), Ok(Command::Sort { column: ram, ascending: true }));
+		assert!(parse_command("sort colour").is_err());
+
+		assert_eq!(
+			parse_command("filter status:INACTIVE"),
+			Ok(Command::Filter(String::from("status:INACTIVE")))
+		);
+		assert_eq!(parse_command("filter"), Ok(Command::Filter(String::new())));
+		assert_eq!(
+			parse_command("export csv /tmp/nodes.csv"),
+			Ok(Command::ExportSummary(Some(String::from("/tmp/nodes.csv"))))
+		);
+		assert_eq!(parse_command("export tax"), Ok(Command::ExportTax(None)));
+		assert!(parse_command("export pdf").is_err());
+		assert_eq!(
+			parse_command("add-glob ~/nodes/**/antnode.log"),
+			Ok(Command::AddGlob(String::from("~/nodes/**/antnode.log")))
+		);
+		assert!(parse_command("add-glob").is_err());
+
+		assert_eq!(parse_command("zoom-in"), Ok(Command::Action(Action::ZoomIn)));
+		assert!(parse_command("zoom-in now").is_err());
+		assert!(parse_command("explode").is_err());
+		assert!(parse_command("  ").is_err());
+	}
+}
@@ -0,0 +1,65 @@
+//! The config file, which holds vdash's options for when it is run without arguments
+//!
+//! The file is written by the setup wizard (see setup_wizard.rs) and may be
+//! edited by hand. Other settings kept between runs are kept beside it (see
+//! preferences.rs).
+use std::fs;
+use std::path::PathBuf;
+
+pub const CONFIG_FILENAME: &str = "vdash.conf";
+
+/// Location of the config file, within $XDG_CONFIG_HOME or ~/.config
+pub fn config_path() -> Option<PathBuf> {
+	let config_dir = match std::env::var_os("XDG_CONFIG_HOME") {
+		Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+		_ => home_dir()?.join(".config"),
+	};
+	Some(config_dir.join("vdash").join(CONFIG_FILENAME))
+}
+
+pub fn home_dir() -> Option<PathBuf> {
+	std::env::var_os("HOME")
+		.or_else(|| std::env::var_os("USERPROFILE"))
+		.filter(|dir| !dir.is_empty())
+		.map(PathBuf::from)
+}
+
+/// Command line arguments read from the config file, if there is one
+pub fn config_args() -> Option<Vec<String>> {
+	let contents = fs::read_to_string(config_path()?).ok()?;
+	Some(parse_config(&contents))
+}
+
+/// Arguments from config file contents. Each line holds one option as given on the
+/// command line, with any value following the first space (without quotes). Lines
+/// not starting with '-' are logfiles. Blank lines and lines starting '#' are ignored.
+pub fn parse_config(contents: &str) -> Vec<String> {
+	let mut args = Vec::<String>::new();
+	for line in contents.lines().map(str::trim) {
+		if line.is_empty() || line.starts_with('#') {
+			continue;
+		}
+		match line.split_once(' ') {
+			Some((option, value)) if option.starts_with('-') => {
+				args.push(option.to_string());
+				args.push(value.trim().to_string());
+			}
+			_ => args.push(line.to_string()),
+		}
+	}
+	args
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn it_parses_config_files() {
+		let contents = "# comment\n\n--glob-path /home/ant/Application Support/*/antnode.log\n--quiet\n/tmp/antnode.log\n";
+		assert_eq!(
+			parse_config(contents),
+			vec!["--glob-path", "/home/ant/Application Support/*/antnode.log", "--quiet", "/tmp/antnode.log"]
+		);
+	}
+}
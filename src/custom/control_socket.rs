@@ -0,0 +1,324 @@
+//! Control socket for scripts to query and drive vdash (see --control)
+//!
+//! Scripts connect to a unix domain socket, or to a TCP address when given a
+//! --control-token, and send JSON-RPC 2.0 requests one per line, receiving one
+//! response per line. For example:
+//!
+//!   echo '{"jsonrpc":"2.0","id":1,"method":"list_nodes"}' | nc -U /tmp/vdash.sock
+//!
+//! The methods are:
+//!
+//!   list_nodes                  number, logfile, PeerId, status and tags of each node
+//!   get_metrics {node|logfile}  a node's value in each Summary column
+//!   get_summary                 the nodes in the Summary, in its order, with totals
+//!   command {command}           run a command as typed at the ':' prompt (e.g. rescan,
+//!                               summary-view or "sort earnings desc")
+//!
+//! Over TCP each request must include "token" with the value of --control-token.
+//! Requests are answered by the main loop, so they see the same state as the UI.
+use std::collections::HashMap;
+
+use crossterm::event::{KeyCode, KeyEvent};
+use serde_json::{json, Map, Value};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::oneshot;
+
+use super::app::{App, LogMonitor};
+use super::command_palette::{parse_command, run_command, Command};
+use super::keymap::Action;
+use super::node_groups::group_of_row_key;
+use super::token_units::attos_value;
+use super::ui_status_bar::view_name;
+use super::ui_summary_table::summary_values;
+
+// JSON-RPC 2.0 error codes, and one for a missing or wrong --control-token
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INVALID_TOKEN: i64 = -32001;
+
+/// A request received on the control socket, to be answered by the main loop
+pub struct ControlRequest {
+	pub request: Value,
+	pub reply: oneshot::Sender<Value>,
+}
+
+/// Listen on address, a unix socket path or a TCP host:port (which needs a token), sending
+/// requests to tx. Returns an error if the address can't be listened on.
+pub fn spawn_control_socket(address: &str, token: Option<String>, tx: UnboundedSender<ControlRequest>) -> Result<(), String> {
+	if address.contains('/') {
+		return spawn_unix_socket(address, tx);
+	}
+	if token.as_ref().map_or(true, |token| token.is_empty()) {
+		return Err(format!("--control {} is a TCP address so needs a --control-token", address));
+	}
+	let listener = std::net::TcpListener::bind(address)
+		.and_then(|listener| listener.set_nonblocking(true).map(|_| listener))
+		.and_then(tokio::net::TcpListener::from_std)
+		.map_err(|e| format!("unable to listen for --control on {}: {}", address, e))?;
+	tokio::spawn(async move {
+		while let Ok((stream, _peer)) = listener.accept().await {
+			tokio::spawn(serve_connection(stream, token.clone(), tx.clone()));
+		}
+	});
+	Ok(())
+}
+
+#[cfg(unix)]
+fn spawn_unix_socket(path: &str, tx: UnboundedSender<ControlRequest>) -> Result<(), String> {
+	let listener = bind_private_socket(std::path::Path::new(path))
+		.and_then(|listener| {
+			listener.set_nonblocking(true)?;
+			Ok(listener)
+		})
+		.and_then(tokio::net::UnixListener::from_std)
+		.map_err(|e| format!("unable to listen for --control on {}: {}", path, e))?;
+	tokio::spawn(async move {
+		while let Ok((stream, _peer)) = listener.accept().await {
+			tokio::spawn(serve_connection(stream, None, tx.clone()));
+		}
+	});
+	Ok(())
+}
+
+/// Bind a unix socket at path which only this user can connect to. It is bound in a
+/// new directory which only this user can enter and then moved to path, so others
+/// can't connect before its permissions are set. A stale socket at path, such as one
+/// left by an earlier vdash, is replaced, but one which is still listening or anything
+/// else there is an error.
+#[cfg(unix)]
+fn bind_private_socket(path: &std::path::Path) -> std::io::Result<std::os::unix::net::UnixListener> {
+	use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+	if let Ok(metadata) = std::fs::symlink_metadata(path) {
+		if !metadata.file_type().is_socket() {
+			return Err(std::io::Error::new(std::io::ErrorKind::AlreadyExists, "a file which is not a socket is there"));
+		}
+		if std::os::unix::net::UnixStream::connect(path).is_ok() {
+			return Err(std::io::Error::new(std::io::ErrorKind::AlreadyExists, "another process is listening on it"));
+		}
+	}
+	let parent = match path.parent() {
+		Some(parent) if !parent.as_os_str().is_empty() => parent,
+		_ => std::path::Path::new("."),
+	};
+	// Made with permissions 0700, and removed when dropped
+	let private_dir = tempfile::Builder::new().prefix(".vdash-control").tempdir_in(parent)?;
+	let private_path = private_dir.path().join("socket");
+	let listener = std::os::unix::net::UnixListener::bind(&private_path)?;
+	std::fs::set_permissions(&private_path, std::fs::Permissions::from_mode(0o600))?;
+	std::fs::rename(&private_path, path)?;
+	Ok(listener)
+}
+
+#[cfg(not(unix))]
+fn spawn_unix_socket(path: &str, _tx: UnboundedSender<ControlRequest>) -> Result<(), String> {
+	Err(format!("--control {}: unix sockets are not available here, use host:port and --control-token", path))
+}
+
+/// Answer each line of a connection until it closes
+async fn serve_connection<S: AsyncRead + AsyncWrite>(stream: S, token: Option<String>, tx: UnboundedSender<ControlRequest>) -> std::io::Result<()> {
+	let (reader, mut writer) = tokio::io::split(stream);
+	let mut lines = BufReader::new(reader).lines();
+	while let Some(line) = lines.next_line().await? {
+		if line.trim().is_empty() {
+			continue;
+		}
+		let response = match serde_json::from_str::<Value>(&line) {
+			Err(e) => error_response(Value::Null, PARSE_ERROR, &format!("invalid JSON: {}", e)),
+			Ok(request) if token.as_ref().is_some_and(|token| request["token"].as_str() != Some(token.as_str())) => {
+				error_response(request["id"].clone(), INVALID_TOKEN, "invalid or missing token")
+			}
+			Ok(request) => {
+				let (reply, response) = oneshot::channel();
+				if tx.send(ControlRequest { request, reply }).is_err() {
+					break;
+				}
+				match response.await {
+					Ok(response) => response,
+					Err(_) => break,
+				}
+			}
+		};
+		writer.write_all(format!("{}\n", response).as_bytes()).await?;
+	}
+	Ok(())
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+	json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+/// Answer a request from the control socket
+pub async fn handle_control_request(app: &mut App, request: &Value, opt_debug_window: bool) -> Value {
+	let id = request.get("id").cloned().unwrap_or(Value::Null);
+	let params = &request["params"];
+	let result = match request["method"].as_str().unwrap_or_default() {
+		"list_nodes" => Ok(list_nodes(&app.monitors)),
+		"get_metrics" => get_metrics(app, params),
+		"get_summary" => Ok(get_summary(app)),
+		"command" => command(app, params, opt_debug_window).await,
+		method => Err((METHOD_NOT_FOUND, format!("unknown method '{}'", method))),
+	};
+	match result {
+		Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+		Err((code, message)) => error_response(id, code, &message),
+	}
+}
+
+fn node_monitors(monitors: &HashMap<String, LogMonitor>) -> Vec<&LogMonitor> {
+	let mut nodes: Vec<&LogMonitor> = monitors.values().filter(|monitor| monitor.is_node()).collect();
+	nodes.sort_by_key(|monitor| monitor.index);
+	nodes
+}
+
+fn list_nodes(monitors: &HashMap<String, LogMonitor>) -> Value {
+	let nodes: Vec<Value> = node_monitors(monitors)
+		.into_iter()
+		.map(|monitor| {
+			json!({
+				"node": monitor.index + 1,
+				"logfile": monitor.logfile,
+				"peer_id": monitor.metrics.node_peer_id,
+				"status": monitor.metrics.node_status_string,
+				"active": monitor.metrics.is_node_active(),
+				"tags": monitor.tags,
+				"group": monitor.group,
+			})
+		})
+		.collect();
+	Value::from(nodes)
+}
+
+fn node_metrics(monitor: &LogMonitor) -> Value {
+	let values: Map<String, Value> = summary_values(monitor)
+		.into_iter()
+		.map(|(heading, value)| (heading, Value::from(value)))
+		.collect();
+	json!({ "node": monitor.index + 1, "logfile": monitor.logfile, "metrics": values })
+}
+
+fn get_metrics(app: &App, params: &Value) -> Result<Value, (i64, String)> {
+	let monitor = match (params["node"].as_u64(), params["logfile"].as_str()) {
+		(Some(node), _) => app.monitors.values().find(|monitor| monitor.index as u64 + 1 == node),
+		(None, Some(logfile)) => app.monitors.get(logfile),
+		(None, None) => return Err((INVALID_PARAMS, String::from("get_metrics needs a node number or logfile"))),
+	};
+	monitor
+		.filter(|monitor| monitor.is_node())
+		.map(node_metrics)
+		.ok_or((INVALID_PARAMS, String::from("no such node")))
+}
+
+fn get_summary(app: &App) -> Value {
+	let nodes: Vec<Value> = app
+		.dash_state
+		.logfile_names_sorted
+		.iter()
+		.filter(|logfile| group_of_row_key(logfile).is_none())
+		.filter_map(|logfile| app.monitors.get(logfile))
+		.map(node_metrics)
+		.collect();
+	let shown: Vec<&LogMonitor> = app
+		.dash_state
+		.logfile_names_sorted
+		.iter()
+		.filter_map(|logfile| app.monitors.get(logfile))
+		.filter(|monitor| monitor.is_node())
+		.collect();
+	let total = |value: fn(&LogMonitor) -> u64| shown.iter().map(|monitor| value(monitor)).sum::<u64>();
+	let earnings = shown.iter().fold(0u128, |total, monitor| total.saturating_add(monitor.metrics.attos_earned.total));
+	json!({
+		"view": view_name(&app.dash_state.main_view),
+		"filter": app.dash_state.node_filter.as_ref().map(|node_filter| node_filter.expression.clone()),
+		"nodes": nodes,
+		"totals": {
+			"nodes": shown.len(),
+			"active": shown.iter().filter(|monitor| monitor.metrics.is_node_active()).count(),
+			"earnings": attos_value(earnings),
+			"records": total(|m| m.metrics.records_stored),
+			"puts": total(|m| m.metrics.activity_puts.total),
+			"gets": total(|m| m.metrics.activity_gets.total),
+			"errors": total(|m| m.metrics.activity_errors.total),
+		},
+	})
+}
+
+async fn command(app: &mut App, params: &Value, opt_debug_window: bool) -> Result<Value, (i64, String)> {
+	let Some(text) = params["command"].as_str() else {
+		return Err((INVALID_PARAMS, String::from("command needs a command")));
+	};
+	// Only the person at the terminal can quit vdash
+	if parse_command(text) == Ok(Command::Action(Action::Quit)) {
+		return Err((INVALID_PARAMS, String::from("quit is not available over the control socket")));
+	}
+	run_command(app, text, &KeyEvent::from(KeyCode::Null), opt_debug_window).await;
+	Ok(json!({ "message": app.dash_state.vdash_status.current_message }))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use tokio::io::{duplex, AsyncBufReadExt};
+	use tokio::sync::mpsc::unbounded_channel;
+
+	#[tokio::test]
+	async fn it_checks_the_token_and_forwards_requests() {
+		let (tx, mut rx) = unbounded_channel::<ControlRequest>();
+		tokio::spawn(async move {
+			while let Some(ControlRequest { request, reply }) = rx.recv().await {
+				let _ = reply.send(json!({ "jsonrpc": "2.0", "id": request["id"], "result": request["method"] }));
+			}
+		});
+		let (client, server) = duplex(4096);
+		tokio::spawn(serve_connection(server, Some(String::from("secret")), tx));
+
+		let (reader, mut writer) = tokio::io::split(client);
+		let mut lines = BufReader::new(reader).lines();
+
+		writer.write_all(b"{\"id\":1,\"method\":\"list_nodes\",\"token\":\"secret\"}\n").await.unwrap();
+		let response: Value = serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+		assert_eq!(response["result"], "list_nodes");
+
+		writer.write_all(b"{\"id\":2,\"method\":\"list_nodes\",\"token\":\"guess\"}\n").await.unwrap();
+		let response: Value = serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+		assert_eq!(response["error"]["code"], INVALID_TOKEN);
+		assert_eq!(response["id"], 2);
+
+		writer.write_all(b"not json\n").await.unwrap();
+		let response: Value = serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+		assert_eq!(response["error"]["code"], PARSE_ERROR);
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn it_binds_a_socket_only_its_user_can_use_and_replaces_only_stale_sockets() {
+		use std::os::unix::fs::PermissionsExt;
+		use std::os::unix::net::UnixStream;
+
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("vdash.sock");
+		let listener = bind_private_socket(&path).unwrap();
+		assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+		assert!(UnixStream::connect(&path).is_ok());
+		// Only the socket is left in the directory
+		assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+
+		// A socket which is still listening is left alone
+		let error = bind_private_socket(&path).unwrap_err();
+		assert_eq!(error.kind(), std::io::ErrorKind::AlreadyExists);
+		assert!(UnixStream::connect(&path).is_ok());
+
+		// As when an earlier vdash didn't remove its socket
+		drop(listener);
+		assert!(UnixStream::connect(&path).is_err());
+		let _listener = bind_private_socket(&path).unwrap();
+		assert!(UnixStream::connect(&path).is_ok());
+
+		let not_a_socket = dir.path().join("notes.txt");
+		std::fs::write(&not_a_socket, "keep me").unwrap();
+		assert!(bind_private_socket(&not_a_socket).is_err());
+		assert_eq!(std::fs::read_to_string(&not_a_socket).unwrap(), "keep me");
+	}
+}
@@ -0,0 +1,149 @@
+//! Metrics defined by the user with regular expressions (see --custom-metric)
+//!
+//! Each custom metric adds a timeline to Node Status and a column to the
+//! Summary, so vdash can follow log messages it doesn't know about (such as
+//! those added by a new node release) without a new release of vdash.
+use std::sync::LazyLock;
+
+use ratatui::style::Color;
+use regex::Regex;
+use regex_syntax::hir::literal::{ExtractKind, Extractor};
+
+use super::node_metrics::{MmmStat, OPT};
+
+/// Timelines and Summary columns of custom metrics start with this, so they never
+/// clash with those built in
+pub const CUSTOM_KEY_PREFIX: &str = "custom:";
+
+/// Colours given in turn to custom metric timelines (see --timeline-colour to change them)
+const CUSTOM_COLOURS: [Color; 4] = [Color::LightGreen, Color::LightYellow, Color::LightMagenta, Color::White];
+
+#[derive(Clone, Debug)]
+pub struct CustomMetric {
+	pub name: String,
+	pub key: String,
+	regex: Regex,
+	pub is_cumulative: bool, // Values are added up, rather than each replacing the last
+	pub is_mmm: bool,        // Timeline shows min, mean and max of the values in each time-slot
+	pub colour: Color,
+}
+
+impl CustomMetric {
+	/// Parse a --custom-metric value of the form <name>[:cumulative][:mmm]=<regex>
+	pub fn parse(opt_metric: &str, index: usize) -> Result<CustomMetric, String> {
+		let Some((name_and_flags, regex)) = opt_metric.split_once('=') else {
+			return Err(format!("invalid --custom-metric '{}', expected <name>[:cumulative][:mmm]=<regex>", opt_metric));
+		};
+		let mut parts = name_and_flags.split(':');
+		let name = parts.next().unwrap_or("").trim().to_string();
+		if name.is_empty() {
+			return Err(format!("invalid --custom-metric '{}', the name is missing", opt_metric));
+		}
+
+		let (mut is_cumulative, mut is_mmm) = (false, false);
+		for flag in parts {
+			match flag.trim() {
+				"cumulative" => is_cumulative = true,
+				"mmm" => is_mmm = true,
+				flag => return Err(format!("unknown flag '{}' in --custom-metric '{}', expected 'cumulative' or 'mmm'", flag, opt_metric)),
+			}
+		}
+		let regex = Regex::new(regex).map_err(|e| format!("invalid regex in --custom-metric '{}': {}", opt_metric, e))?;
+
+		Ok(CustomMetric {
+			key: format!("{}{}", CUSTOM_KEY_PREFIX, name),
+			name,
+			regex,
+			is_cumulative,
+			is_mmm,
+			colour: CUSTOM_COLOURS[index % CUSTOM_COLOURS.len()],
+		})
+	}
+
+	/// The value in a matching line, which is the number in the first capture group
+	/// (ignoring commas) or 1 if the regex has no capture group
+	pub fn value_in(&self, line: &str) -> Option<u64> {
+		let captures = self.regex.captures(line)?;
+		match captures.get(1) {
+			Some(value) => value.as_str().replace(',', "").trim().parse::<u64>().ok(),
+			None if self.regex.captures_len() == 1 => Some(1),
+			None => None,
+		}
+	}
+
+	/// Text of which a line must contain at least one for the regex to match it, or
+	/// None if there is no such text (see LinePatterns::custom_metrics())
+	pub fn required_literals(&self) -> Option<Vec<Vec<u8>>> {
+		let hir = regex_syntax::parse(self.regex.as_str()).ok()?;
+		let prefixes = Extractor::new().kind(ExtractKind::Prefix).extract(&hir);
+		let literals = prefixes.literals()?;
+		if literals.is_empty() || literals.iter().any(|literal| literal.as_bytes().is_empty()) {
+			return None;
+		}
+		Some(literals.iter().map(|literal| literal.as_bytes().to_vec()).collect())
+	}
+
+	/// The value shown in the Summary
+	pub fn summary_value(&self, stat: Option<&MmmStat>) -> u64 {
+		match stat {
+			Some(stat) if self.is_cumulative => stat.total,
+			Some(stat) => stat.most_recent,
+			None => 0,
+		}
+	}
+}
+
+pub fn parse_custom_metrics(opt_metrics: &[String]) -> Result<Vec<CustomMetric>, String> {
+	let mut metrics = Vec::<CustomMetric>::new();
+	for (index, opt_metric) in opt_metrics.iter().enumerate() {
+		let metric = CustomMetric::parse(opt_metric, index)?;
+		if metrics.iter().any(|other| other.name == metric.name) {
+			return Err(format!("--custom-metric '{}' is defined more than once", metric.name));
+		}
+		metrics.push(metric);
+	}
+	Ok(metrics)
+}
+
+/// The custom metrics given on the command line. Invalid values are reported when vdash
+/// starts (see App::new()).
+pub static CUSTOM_METRICS: LazyLock<Vec<CustomMetric>> =
+	LazyLock::new(|| parse_custom_metrics(&OPT.lock().unwrap().custom_metric).unwrap_or_default());
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn it_parses_custom_metrics_and_their_values() {
+		let metrics = parse_custom_metrics(&[
+			String::from("Replicated:cumulative=Replicated (\\d+) records"),
+			String::from("Close group:mmm=close group size is ([\\d,]+)"),
+			String::from("Restarts=Node restarting"),
+		])
+		.unwrap();
+		assert_eq!(metrics[0].key, "custom:Replicated");
+		assert!(metrics[0].is_cumulative && !metrics[0].is_mmm);
+		assert!(metrics[1].is_mmm && !metrics[1].is_cumulative);
+		assert_ne!(metrics[0].colour, metrics[1].colour);
+
+		assert_eq!(metrics[0].value_in("[INFO] Replicated 12 records to peers"), Some(12));
+		assert_eq!(metrics[0].value_in("[INFO] Wrote record"), None);
+		assert_eq!(metrics[1].value_in("close group size is 1,024"), Some(1024));
+		assert_eq!(metrics[2].value_in("Node restarting now"), Some(1));
+
+		assert_eq!(metrics[0].required_literals(), Some(vec![b"Replicated ".to_vec()]));
+		assert_eq!(CustomMetric::parse("Any=.*", 0).unwrap().required_literals(), None);
+
+		let mut stat = MmmStat::new();
+		stat.add_sample(5);
+		stat.add_sample(7);
+		assert_eq!(metrics[0].summary_value(Some(&stat)), 12);
+		assert_eq!(metrics[1].summary_value(Some(&stat)), 7);
+
+		assert!(parse_custom_metrics(&[String::from("Broken=(")]).is_err());
+		assert!(parse_custom_metrics(&[String::from("Flagged:sometimes=x")]).is_err());
+		assert!(parse_custom_metrics(&[String::from("=x")]).is_err());
+		assert!(parse_custom_metrics(&[String::from("A=x"), String::from("A=y")]).is_err());
+	}
+}
@@ -0,0 +1,45 @@
+//! The parser output of each node for --debug-window
+//!
+//! Every monitor keeps the parser output of its most recent lines, so the
+//! debug window can follow whichever node has focus, showing how its lines
+//! were parsed from before it was focused as well as those arriving since.
+use std::collections::VecDeque;
+
+/// Lines kept per monitor, and shown by the debug window
+pub const MAX_DEBUG_LINES: usize = 100;
+
+#[derive(Debug, Default)]
+pub struct DebugLines {
+	lines: VecDeque<String>,
+}
+
+impl DebugLines {
+	/// Add a line, dropping the oldest once there are MAX_DEBUG_LINES
+	pub fn push(&mut self, line: String) {
+		if self.lines.len() == MAX_DEBUG_LINES {
+			self.lines.pop_front();
+		}
+		self.lines.push_back(line);
+	}
+
+	/// Oldest first
+	pub fn lines(&self) -> impl Iterator<Item = &String> {
+		self.lines.iter()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn it_keeps_the_most_recent_lines() {
+		let mut debug_lines = DebugLines::default();
+		for i in 0..MAX_DEBUG_LINES + 5 {
+			debug_lines.push(i.to_string());
+		}
+		assert_eq!(debug_lines.lines().count(), MAX_DEBUG_LINES);
+		assert_eq!(debug_lines.lines().next().unwrap(), "5");
+		assert_eq!(debug_lines.lines().last().unwrap(), &(MAX_DEBUG_LINES + 4).to_string());
+	}
+}
@@ -0,0 +1,82 @@
+//! Free space on the device holding a node's data (see --disk-poll)
+//!
+//! The node's data directory is taken to be its root directory, the parent of
+//! the directory holding its logfile (as for --wallet-filename). The device
+//! free space is polled with statvfs (GetDiskFreeSpaceEx on Windows) so that
+//! a node can be warned about before it runs out of room to store records.
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Debug, Default)]
+pub struct DiskSpace {
+	pub free: Option<u64>,  // Bytes available to the node
+	pub total: Option<u64>, // Bytes on the device
+	pub low: bool,          // Free space below --disk-free-alert
+	pub error: Option<String>,
+}
+
+impl DiskSpace {
+	/// The data directory of a node, which is the parent of the directory holding its logfile
+	pub fn data_dir(logfile: &str) -> Option<PathBuf> {
+		Some(Path::new(logfile).parent()?.parent()?.to_path_buf())
+	}
+
+	/// Update the free space of the device holding path. Returns true if free space
+	/// has just fallen below alert_percent.
+	pub fn poll(&mut self, path: &Path, alert_percent: f64) -> bool {
+		match (fs2::available_space(path), fs2::total_space(path)) {
+			(Ok(free), Ok(total)) => {
+				self.error = None;
+				self.update(free, total, alert_percent)
+			}
+			(Err(e), _) | (_, Err(e)) => {
+				self.error = Some(format!("{}: {}", path.display(), e));
+				false
+			}
+		}
+	}
+
+	/// Record the free space. Returns true if it has just fallen below alert_percent.
+	pub fn update(&mut self, free: u64, total: u64, alert_percent: f64) -> bool {
+		self.free = Some(free);
+		self.total = Some(total);
+		let was_low = self.low;
+		self.low = self.free_percent().is_some_and(|percent| percent < alert_percent);
+		self.low && !was_low
+	}
+
+	pub fn free_percent(&self) -> Option<f64> {
+		match (self.free, self.total) {
+			(Some(free), Some(total)) if total > 0 => Some(free as f64 * 100.0 / total as f64),
+			_ => None,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn it_alerts_when_free_space_falls_below_the_threshold() {
+		assert_eq!(
+			DiskSpace::data_dir("/var/antctl/services/antnode1/logs/antnode.log"),
+			Some(PathBuf::from("/var/antctl/services/antnode1"))
+		);
+
+		let mut disk_space = DiskSpace::default();
+		assert!(!disk_space.update(500, 1000, 10.0));
+		assert_eq!(disk_space.free_percent(), Some(50.0));
+		assert!(disk_space.update(50, 1000, 10.0));
+		assert!(disk_space.low);
+		assert!(!disk_space.update(40, 1000, 10.0));
+		assert!(!disk_space.update(200, 1000, 10.0));
+		assert!(!disk_space.low);
+
+		let dir = tempfile::TempDir::new().unwrap();
+		disk_space.poll(dir.path(), 0.0);
+		assert!(disk_space.error.is_none());
+		assert!(disk_space.total.unwrap() > 0);
+		disk_space.poll(&dir.path().join("missing"), 0.0);
+		assert!(disk_space.error.is_some());
+	}
+}
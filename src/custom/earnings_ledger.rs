@@ -0,0 +1,411 @@
+//! Ledger of node earnings, one entry per payment parsed from a logfile
+//!
+//! Each entry records the token price at the time the payment was parsed
+//! so that earnings can be valued as they were when received, as well as
+//! at the latest price. The ledgers of all nodes can be viewed in the
+//! Earnings view, payment by payment or totalled by day or week, and
+//! exported to CSV.
+//!
+//! A ledger is saved in each of its node's checkpoints, so only its most
+//! recent payments are kept one by one. Older payments are rolled up into
+//! a total for each day, which keeps their value when received.
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::node_metrics::LogMonitor;
+use super::token_units::ATTOS_PER_ANT;
+
+// A price is only attached to payments made within this time of it being parsed
+// so that payments loaded from old logfiles aren't valued at today's price
+const MAX_PRICE_AGE_S: i64 = 60 * 60;
+
+// Payments kept one by one in a ledger before the oldest are rolled up into days,
+// and how many are kept after that so that it is done once per 250 payments
+const MAX_LEDGER_PAYMENTS: usize = 1000;
+const KEPT_LEDGER_PAYMENTS: usize = 750;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EarningsEntry {
+	pub time: DateTime<Utc>,
+	pub attos: u128,
+	#[serde(default)]
+	pub record: Option<String>, // Record paid for, as given in the log
+
+	// Price when the payment was parsed (if known)
+	pub currency_apiname: Option<String>,
+	pub currency_per_token: Option<f64>,
+
+	#[serde(default = "one_payment")]
+	pub payments: u64, // More than one for the payments of a day rolled up together
+}
+
+fn one_payment() -> u64 {
+	1
+}
+
+impl EarningsEntry {
+	/// Value in the given currency using the price when the payment was received
+	pub fn value_then(&self, currency_apiname: &str) -> Option<f64> {
+		match (&self.currency_apiname, self.currency_per_token) {
+			(Some(apiname), Some(rate)) if apiname.eq_ignore_ascii_case(currency_apiname) => {
+				Some(rate * self.attos as f64 / ATTOS_PER_ANT)
+			}
+			_ => None,
+		}
+	}
+
+	/// Add a payment made on the same day and valued in the same currency, using
+	/// the rate which values the total at the sum of their values
+	fn add(&mut self, entry: &EarningsEntry) {
+		let attos = self.attos.saturating_add(entry.attos);
+		if let (Some(rate), Some(entry_rate)) = (self.currency_per_token, entry.currency_per_token) {
+			if attos > 0 {
+				self.currency_per_token =
+					Some((rate * self.attos as f64 + entry_rate * entry.attos as f64) / attos as f64);
+			}
+		}
+		self.attos = attos;
+		self.payments += entry.payments;
+	}
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct EarningsLedger {
+	#[serde(default)]
+	pub days: Vec<EarningsEntry>, // Older payments, one entry per day and currency they were valued in
+	pub entries: Vec<EarningsEntry>, // Recent payments, one by one
+	#[serde(default)]
+	pub recorded: u64, // Payments recorded by this ledger, however they are now kept
+}
+
+/// Totals of the payments in one or more ledgers, valued at the time of receipt
+#[derive(Clone, Debug, Default)]
+pub struct EarningsValue {
+	pub value_then: f64,
+	pub attos_valued: u128,
+	pub attos_unvalued: u128, // Payments received without a known price
+}
+
+impl EarningsLedger {
+	/// Record a payment, attaching the current token price if the payment is recent
+	pub fn record_payment(&mut self, time: &DateTime<Utc>, attos: u128, record: Option<String>) {
+		let mut entry = EarningsEntry {
+			time: *time,
+			attos,
+			record,
+			currency_apiname: None,
+			currency_per_token: None,
+			payments: 1,
+		};
+
+		if Utc::now() - *time < Duration::seconds(MAX_PRICE_AGE_S) {
+			if let Some((currency_apiname, currency_per_token)) = current_token_price() {
+				entry.currency_apiname = Some(currency_apiname);
+				entry.currency_per_token = Some(currency_per_token);
+			}
+		}
+
+		self.entries.push(entry);
+		self.recorded += 1;
+		if self.entries.len() > MAX_LEDGER_PAYMENTS {
+			self.roll_up_oldest();
+		}
+	}
+
+	/// Roll up all but the most recent payments into the totals for their days
+	fn roll_up_oldest(&mut self) {
+		let oldest: Vec<EarningsEntry> = self.entries.drain(..self.entries.len() - KEPT_LEDGER_PAYMENTS).collect();
+		for entry in oldest {
+			let day = entry.time.date_naive();
+			let total = self
+				.days
+				.iter_mut()
+				.rev()
+				.find(|total| total.time.date_naive() == day && total.currency_apiname == entry.currency_apiname);
+			match total {
+				Some(total) => total.add(&entry),
+				// Its time is that of the day's first payment
+				None => self.days.push(EarningsEntry { record: None, ..entry }),
+			}
+		}
+		self.days.sort_by_key(|total| total.time);
+	}
+
+	/// The days of rolled up payments followed by recent payments, oldest first
+	pub fn payments(&self) -> impl Iterator<Item = &EarningsEntry> {
+		self.days.iter().chain(self.entries.iter())
+	}
+
+	/// Add the value of this ledger's payments (at time of receipt) to earnings_value
+	pub fn add_value_then(&self, currency_apiname: &str, earnings_value: &mut EarningsValue) {
+		for entry in self.payments() {
+			if let Some(value) = entry.value_then(currency_apiname) {
+				earnings_value.value_then += value;
+				earnings_value.attos_valued = earnings_value.attos_valued.saturating_add(entry.attos);
+			} else {
+				earnings_value.attos_unvalued = earnings_value.attos_unvalued.saturating_add(entry.attos);
+			}
+		}
+	}
+}
+
+/// The token price in the active currency, from the price APIs or --currency-token-rate
+fn current_token_price() -> Option<(String, f64)> {
+	let prices = super::web_requests::WEB_PRICES.lock().unwrap();
+	if let Some(snt_rate) = prices.snt_rate {
+		return Some((prices.currency_apiname.clone(), snt_rate));
+	}
+
+	let opt_currency_token_rate = super::node_metrics::OPT.lock().unwrap().currency_token_rate;
+	if opt_currency_token_rate > 0.0 && !prices.currency_apiname.is_empty() {
+		return Some((prices.currency_apiname.clone(), opt_currency_token_rate));
+	}
+	None
+}
+
+/// A payment from the ledger of one of the monitored nodes
+pub struct NodeEarningsEntry<'a> {
+	pub node_index: usize,
+	pub logfile: &'a str,
+	pub entry: &'a EarningsEntry,
+}
+
+/// All payments received by the monitored nodes, oldest first
+pub fn collect_node_earnings(monitors: &HashMap<String, LogMonitor>) -> Vec<NodeEarningsEntry<'_>> {
+	let mut earnings = Vec::<NodeEarningsEntry>::new();
+	for (logfile, monitor) in monitors.iter() {
+		if monitor.is_node() {
+			for entry in monitor.metrics.earnings_ledger.payments() {
+				earnings.push(NodeEarningsEntry {
+					node_index: monitor.index,
+					logfile: logfile.as_str(),
+					entry,
+				});
+			}
+		}
+	}
+	earnings.sort_by_key(|e| e.entry.time);
+	earnings
+}
+
+/// How payments are listed in the Earnings view
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EarningsPeriod {
+	Payment,
+	Day,
+	Week,
+}
+
+impl EarningsPeriod {
+	pub fn next(self) -> EarningsPeriod {
+		match self {
+			EarningsPeriod::Payment => EarningsPeriod::Day,
+			EarningsPeriod::Day => EarningsPeriod::Week,
+			EarningsPeriod::Week => EarningsPeriod::Payment,
+		}
+	}
+
+	/// First day (UTC) of the period holding time. Weeks start on Monday.
+	pub fn start_of(self, time: &DateTime<Utc>) -> NaiveDate {
+		let day = time.date_naive();
+		match self {
+			EarningsPeriod::Week => day - Duration::days(day.weekday().num_days_from_monday() as i64),
+			_ => day,
+		}
+	}
+
+	/// Label of the period starting on start (e.g. "2024-03-23" or "2024-W12 from 03-18")
+	pub fn label(self, start: NaiveDate) -> String {
+		match self {
+			EarningsPeriod::Week => format!("{} from {}", start.format("%G-W%V"), start.format("%m-%d")),
+			_ => start.format("%Y-%m-%d").to_string(),
+		}
+	}
+}
+
+/// Payments received in a period by one node, or by all when node_index is None
+#[derive(Clone, Debug, PartialEq)]
+pub struct PeriodEarnings {
+	pub start: NaiveDate,
+	pub node_index: Option<usize>,
+	pub payments: usize,
+	pub attos: u128,
+	pub value_then: Option<f64>, // None unless every payment was valued when received
+}
+
+impl PeriodEarnings {
+	fn new(start: NaiveDate, node_index: Option<usize>) -> PeriodEarnings {
+		PeriodEarnings {
+			start,
+			node_index,
+			payments: 0,
+			attos: 0,
+			value_then: Some(0.0),
+		}
+	}
+
+	fn add(&mut self, entry: &EarningsEntry, currency_apiname: &str) {
+		self.payments += entry.payments as usize;
+		self.attos = self.attos.saturating_add(entry.attos);
+		self.value_then = match (self.value_then, entry.value_then(currency_apiname)) {
+			(Some(total), Some(value)) => Some(total + value),
+			_ => None,
+		};
+	}
+}
+
+/// Payments totalled for each period, oldest first, with the total for all nodes
+/// followed by each node which earned in that period
+pub fn earnings_by_period(earnings: &[NodeEarningsEntry], period: EarningsPeriod, currency_apiname: &str) -> Vec<PeriodEarnings> {
+	let mut by_start = std::collections::BTreeMap::<NaiveDate, (PeriodEarnings, Vec<PeriodEarnings>)>::new();
+	for e in earnings {
+		let start = period.start_of(&e.entry.time);
+		let (all, nodes) = by_start.entry(start).or_insert_with(|| (PeriodEarnings::new(start, None), Vec::new()));
+		all.add(e.entry, currency_apiname);
+		match nodes.iter_mut().find(|node| node.node_index == Some(e.node_index)) {
+			Some(node) => node.add(e.entry, currency_apiname),
+			None => {
+				let mut node = PeriodEarnings::new(start, Some(e.node_index));
+				node.add(e.entry, currency_apiname);
+				nodes.push(node);
+			}
+		}
+	}
+
+	let mut rows = Vec::<PeriodEarnings>::new();
+	for (_start, (all, mut nodes)) in by_start {
+		nodes.sort_by_key(|node| node.node_index);
+		rows.push(all);
+		rows.extend(nodes);
+	}
+	rows
+}
+
+/// Write payments to a CSV file, valued at receipt and at currency_per_token (if known)
+pub fn write_earnings_csv(
+	path: &Path,
+	earnings: &[NodeEarningsEntry],
+	currency_apiname: &str,
+	currency_per_token: Option<f64>,
+) -> std::io::Result<()> {
+	let mut file = File::create(path)?;
+	writeln!(
+		file,
+		"time,node,logfile,attos,ant,currency,value_then,value_now,payments"
+	)?;
+	for e in earnings {
+		let value_then = e
+			.entry
+			.value_then(currency_apiname)
+			.map_or(String::new(), |value| format!("{:.9}", value));
+		let value_now = currency_per_token.map_or(String::new(), |rate| {
+			format!("{:.9}", rate * e.entry.attos as f64 / ATTOS_PER_ANT)
+		});
+		writeln!(
+			file,
+			"{},{},\"{}\",{},{:.18},{},{},{},{}",
+			e.entry.time.to_rfc3339(),
+			e.node_index + 1,
+			e.logfile.replace('"', "\"\""),
+			e.entry.attos,
+			e.entry.attos as f64 / ATTOS_PER_ANT,
+			currency_apiname,
+			value_then,
+			value_now,
+			e.entry.payments
+		)?;
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn it_totals_earnings_by_day_and_week() {
+		let entry = |time: &str, attos: u128| EarningsEntry {
+			time: time.parse().unwrap(),
+			attos,
+			record: None,
+			currency_apiname: Some(String::from("usd")),
+			currency_per_token: Some(2.0),
+			payments: 1,
+		};
+		let entries = [
+			entry("2024-03-17T23:00:00Z", 100),
+			entry("2024-03-18T01:00:00Z", 200),
+			entry("2024-03-18T02:00:00Z", 300),
+		];
+		let earnings: Vec<NodeEarningsEntry> = entries
+			.iter()
+			.enumerate()
+			.map(|(index, entry)| NodeEarningsEntry { node_index: index % 2, logfile: "", entry })
+			.collect();
+
+		let days = earnings_by_period(&earnings, EarningsPeriod::Day, "usd");
+		let rows: Vec<(String, Option<usize>, u128)> = days
+			.iter()
+			.map(|row| (EarningsPeriod::Day.label(row.start), row.node_index, row.attos))
+			.collect();
+		assert_eq!(
+			rows,
+			[
+				(String::from("2024-03-17"), None, 100),
+				(String::from("2024-03-17"), Some(0), 100),
+				(String::from("2024-03-18"), None, 500),
+				(String::from("2024-03-18"), Some(0), 300),
+				(String::from("2024-03-18"), Some(1), 200),
+			]
+		);
+		assert_eq!(days[2].payments, 2);
+		assert_eq!(days[2].value_then, Some(2.0 * 500.0 / ATTOS_PER_ANT));
+
+		let weeks = earnings_by_period(&earnings, EarningsPeriod::Week, "usd");
+		assert_eq!(EarningsPeriod::Week.label(weeks[0].start), "2024-W11 from 03-11");
+		assert_eq!(EarningsPeriod::Week.label(weeks[2].start), "2024-W12 from 03-18");
+		assert_eq!(earnings_by_period(&earnings, EarningsPeriod::Week, "eur")[0].value_then, None);
+	}
+	#[test]
+	fn it_rolls_up_old_payments_into_days_keeping_their_value() {
+		let mut ledger = EarningsLedger::default();
+		let start: DateTime<Utc> = "2024-03-17T12:00:00Z".parse().unwrap();
+		for i in 0..MAX_LEDGER_PAYMENTS + 1 {
+			// Two days' worth, the first valued when received and the second not
+			let time = start + Duration::minutes(i as i64);
+			let mut entry = EarningsEntry {
+				time,
+				attos: 10,
+				record: None,
+				currency_apiname: None,
+				currency_per_token: None,
+				payments: 1,
+			};
+			if time.date_naive() == start.date_naive() {
+				entry.currency_apiname = Some(String::from("usd"));
+				entry.currency_per_token = Some(if i % 2 == 0 { 1.0 } else { 3.0 });
+			}
+			ledger.entries.push(entry);
+		}
+		let mut value_before = EarningsValue::default();
+		ledger.add_value_then("usd", &mut value_before);
+
+		ledger.roll_up_oldest();
+		assert_eq!(ledger.entries.len(), KEPT_LEDGER_PAYMENTS);
+		assert_eq!(ledger.days.len(), 1);
+		assert_eq!(ledger.days[0].time, start);
+		assert_eq!(ledger.days[0].payments, (MAX_LEDGER_PAYMENTS + 1 - KEPT_LEDGER_PAYMENTS) as u64);
+		assert_eq!(ledger.payments().map(|entry| entry.payments).sum::<u64>(), (MAX_LEDGER_PAYMENTS + 1) as u64);
+
+		let mut value_after = EarningsValue::default();
+		ledger.add_value_then("usd", &mut value_after);
+		assert!((value_after.value_then - value_before.value_then).abs() < 1e-24);
+		assert_eq!(value_after.attos_valued, value_before.attos_valued);
+		assert_eq!(value_after.attos_unvalued, value_before.attos_unvalued);
+	}
+}
@@ -0,0 +1,56 @@
+//! Earnings efficiency of nodes, for comparing profitability across machines
+//!
+//! Earnings are divided by the space a node uses to store records and by the
+//! GETS it has served. The space is the size of the record store when it is
+//! scanned (see --store-poll), or else the space used given by the log.
+use super::node_metrics::NodeMetrics;
+
+const BYTES_PER_GB: u128 = 1_000_000_000;
+
+/// Bytes the node uses to store records, if known
+pub fn bytes_stored(metrics: &NodeMetrics) -> Option<u64> {
+	metrics
+		.store_size
+		.bytes
+		.or((metrics.space_max > 0).then_some(metrics.space_used))
+		.filter(|bytes| *bytes > 0)
+}
+
+/// Attos earned per GB stored
+pub fn attos_per_gb(attos: u128, bytes: u64) -> Option<u128> {
+	per(attos, bytes, BYTES_PER_GB)
+}
+
+/// Attos earned per 1000 GETS
+pub fn attos_per_1k_gets(attos: u128, gets: u64) -> Option<u128> {
+	per(attos, gets, 1000)
+}
+
+fn per(attos: u128, count: u64, scale: u128) -> Option<u128> {
+	if count == 0 {
+		return None;
+	}
+	Some(attos.checked_mul(scale)? / count as u128)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn it_divides_earnings_by_space_and_gets() {
+		assert_eq!(attos_per_gb(3_000, 500_000_000), Some(6_000));
+		assert_eq!(attos_per_gb(3_000, 0), None);
+		assert_eq!(attos_per_1k_gets(3_000, 2_000), Some(1_500));
+		assert_eq!(attos_per_1k_gets(u64::MAX as u128, 1), Some(u64::MAX as u128 * 1000));
+		assert_eq!(attos_per_1k_gets(u128::MAX, 1), None);
+
+		let mut metrics = NodeMetrics::new();
+		assert_eq!(bytes_stored(&metrics), None);
+		metrics.space_used = 2_000;
+		metrics.space_max = 10_000;
+		assert_eq!(bytes_stored(&metrics), Some(2_000));
+		metrics.store_size.bytes = Some(5_000);
+		assert_eq!(bytes_stored(&metrics), Some(5_000));
+	}
+}
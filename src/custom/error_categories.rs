@@ -0,0 +1,105 @@
+//! Classification of node ERROR lines into categories (see --error-category)
+//!
+//! Each ERROR line is put in the first category whose regex it matches, or
+//! 'other' if none do, so the Node view can show what kind of errors a node
+//! has rather than just how many.
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use super::node_metrics::OPT;
+
+/// Category of errors which match no other
+pub const OTHER_CATEGORY: &str = "other";
+
+/// Built in categories, in the order they are tried
+const DEFAULT_CATEGORIES: [(&str, &str); 3] = [
+	("connection", r"(?i)\b(connection|connect|connecting|dial|dialing|timed out|timeout|transport|unreachable)\b"),
+	("storage", r"(?i)\b(record|records|chunk|disk|storage|replication)\b"),
+	("payment", r"(?i)\b(payment|quote|wallet|reward|attos)\b"),
+];
+
+#[derive(Clone, Debug)]
+pub struct ErrorCategory {
+	pub name: String,
+	regex: Regex,
+}
+
+/// The built in categories with the regex of any named by --error-category <name>=<regex>
+/// replaced, and any others added after them
+pub fn parse_error_categories(opt_categories: &[String]) -> Result<Vec<ErrorCategory>, String> {
+	let mut categories: Vec<ErrorCategory> = DEFAULT_CATEGORIES
+		.iter()
+		.map(|(name, regex)| ErrorCategory {
+			name: name.to_string(),
+			regex: Regex::new(regex).expect("The error category regexes failed to compile. This is a bug."),
+		})
+		.collect();
+
+	for opt_category in opt_categories {
+		let Some((name, regex)) = opt_category.split_once('=') else {
+			return Err(format!("invalid --error-category '{}', expected <category>=<regex>", opt_category));
+		};
+		let name = name.trim();
+		if name.is_empty() || name == OTHER_CATEGORY {
+			return Err(format!("invalid --error-category '{}', the category can't be empty or '{}'", opt_category, OTHER_CATEGORY));
+		}
+		let regex = Regex::new(regex).map_err(|e| format!("invalid regex in --error-category '{}': {}", opt_category, e))?;
+		match categories.iter_mut().find(|category| category.name == name) {
+			Some(category) => category.regex = regex,
+			None => categories.push(ErrorCategory { name: name.to_string(), regex }),
+		}
+	}
+	Ok(categories)
+}
+
+/// The categories from the command line. Invalid values are reported when vdash starts
+/// (see App::new()).
+pub static ERROR_CATEGORIES: LazyLock<Vec<ErrorCategory>> =
+	LazyLock::new(|| parse_error_categories(&OPT.lock().unwrap().error_category).unwrap_or_default());
+
+pub fn category_of<'a>(categories: &'a [ErrorCategory], line: &str) -> &'a str {
+	categories
+		.iter()
+		.find(|category| category.regex.is_match(line))
+		.map_or(OTHER_CATEGORY, |category| category.name.as_str())
+}
+
+/// Count of each category, including those with none, in the order they are tried
+pub fn category_counts(counts: &HashMap<String, u64>) -> Vec<(&'static str, u64)> {
+	ERROR_CATEGORIES
+		.iter()
+		.map(|category| category.name.as_str())
+		.chain(std::iter::once(OTHER_CATEGORY))
+		.map(|name| (name, counts.get(name).copied().unwrap_or(0)))
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn it_classifies_errors() {
+		let categories = parse_error_categories(&[]).unwrap();
+		let line = |message: &str| format!("[2024-03-23T19:38:32.350118Z ERROR ant_networking] {}", message);
+		assert_eq!(category_of(&categories, &line("Outgoing connection error to peer")), "connection");
+		assert_eq!(category_of(&categories, &line("Failed to write record to disk")), "storage");
+		assert_eq!(category_of(&categories, &line("Invalid payment quote")), "payment");
+		assert_eq!(category_of(&categories, &line("Something unexpected")), OTHER_CATEGORY);
+
+		let categories = parse_error_categories(&[
+			String::from("payment=(?i)quote"),
+			String::from("shutdown=(?i)shutting down"),
+		])
+		.unwrap();
+		assert_eq!(categories.len(), 4);
+		assert_eq!(category_of(&categories, &line("Invalid payment")), OTHER_CATEGORY);
+		assert_eq!(category_of(&categories, &line("Node shutting down")), "shutdown");
+
+		assert!(parse_error_categories(&[String::from("other=x")]).is_err());
+		assert!(parse_error_categories(&[String::from("broken=(")]).is_err());
+		assert!(parse_error_categories(&[String::from("nothing")]).is_err());
+	}
+}
@@ -0,0 +1,164 @@
+//! Stream of node events as newline-delimited JSON (see --events-out)
+//!
+//! Each tick the monitors are compared with their state at the previous tick
+//! and any payments, status changes and alerts are appended to the events
+//! file, one JSON object per line. Monitors are compared with themselves from
+//! when they were first seen, so history loaded from a logfile or checkpoint
+//! is not reported as new events.
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+
+use super::node_metrics::{node_status_as_string, LogMonitor};
+use super::token_units::attos_value;
+
+/// The state of a node when events were last reported
+struct ReportedState {
+	payments: u64,
+	status: String,
+	inactive: bool,
+	envelope_breaches: Vec<String>,
+	disk_low: bool,
+}
+
+impl ReportedState {
+	fn of(monitor: &LogMonitor) -> ReportedState {
+		ReportedState {
+			payments: monitor.metrics.earnings_ledger.recorded,
+			status: node_status_as_string(&monitor.metrics.node_status),
+			inactive: monitor.metrics.node_inactive,
+			envelope_breaches: monitor.resource_envelope.breaches(&monitor.metrics),
+			disk_low: monitor.metrics.disk_space.low,
+		}
+	}
+}
+
+pub struct EventsOut {
+	pub path: String,
+	reported: HashMap<String, ReportedState>,
+}
+
+impl EventsOut {
+	pub fn new(path: &str) -> EventsOut {
+		EventsOut {
+			path: path.to_string(),
+			reported: HashMap::new(),
+		}
+	}
+
+	/// Events since the previous call, for nodes seen before
+	pub fn take_events(&mut self, monitors: &HashMap<String, LogMonitor>, now: &DateTime<Utc>) -> Vec<Value> {
+		let mut events = Vec::<Value>::new();
+		let mut logfiles: Vec<&String> = monitors.keys().collect();
+		logfiles.sort_by_key(|logfile| monitors[*logfile].index);
+		for logfile in logfiles {
+			let monitor = &monitors[logfile];
+			if !monitor.is_node() {
+				continue;
+			}
+
+			let Some(previous) = self.reported.insert(logfile.clone(), ReportedState::of(monitor)) else {
+				continue;
+			};
+			let current = &self.reported[logfile];
+			let event = |event: &str, details: Value| {
+				let mut event = json!({
+					"time": now.to_rfc3339(),
+					"event": event,
+					"node": monitor.index + 1,
+					"logfile": logfile,
+					"peer_id": monitor.metrics.node_peer_id,
+				});
+				if let (Some(event), Some(details)) = (event.as_object_mut(), details.as_object()) {
+					event.extend(details.clone());
+				}
+				event
+			};
+
+			// Payments since those reported, which are the most recent so not yet rolled up
+			let entries = &monitor.metrics.earnings_ledger.entries;
+			let new_payments = current.payments.saturating_sub(previous.payments) as usize;
+			for payment in entries.iter().skip(entries.len().saturating_sub(new_payments)) {
+				events.push(event(
+					"payment",
+					json!({ "payment_time": payment.time.to_rfc3339(), "attos": attos_value(payment.attos), "record": payment.record }),
+				));
+			}
+			if current.status != previous.status {
+				events.push(event("status", json!({ "from": previous.status, "to": current.status })));
+			}
+			if current.inactive != previous.inactive {
+				events.push(event("alert", json!({ "alert": "inactive", "raised": current.inactive })));
+			}
+			if current.envelope_breaches.is_empty() != previous.envelope_breaches.is_empty() {
+				events.push(event(
+					"alert",
+					json!({
+						"alert": "resource_envelope",
+						"raised": !current.envelope_breaches.is_empty(),
+						"detail": current.envelope_breaches.join(", "),
+					}),
+				));
+			}
+			if current.disk_low != previous.disk_low {
+				let free_percent = monitor.metrics.disk_space.free_percent().unwrap_or(0.0);
+				events.push(event(
+					"alert",
+					json!({ "alert": "disk_space", "raised": current.disk_low, "detail": format!("{:.1}% free", free_percent) }),
+				));
+			}
+		}
+		events
+	}
+
+	/// Append any new events to the events file, returning how many were written
+	pub fn write_events(&mut self, monitors: &HashMap<String, LogMonitor>, now: &DateTime<Utc>) -> std::io::Result<usize> {
+		let events = self.take_events(monitors, now);
+		if events.is_empty() {
+			return Ok(0);
+		}
+
+		let mut lines = String::new();
+		for event in &events {
+			lines += &format!("{}\n", event);
+		}
+		let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+		file.write_all(lines.as_bytes())?;
+		Ok(events.len())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::custom::node_metrics::NodeStatus;
+
+	#[test]
+	fn it_reports_changes_since_nodes_were_first_seen() {
+		let now = Utc::now();
+		let mut monitors = HashMap::new();
+		let mut monitor = LogMonitor::new(String::from("node1/antnode.log"));
+		monitor.metrics.earnings_ledger.record_payment(&now, 100, None);
+		monitors.insert(monitor.logfile.clone(), monitor);
+
+		// Loaded history is not reported
+		let mut events_out = EventsOut::new("events.ndjson");
+		assert!(events_out.take_events(&monitors, &now).is_empty());
+
+		let monitor = monitors.get_mut("node1/antnode.log").unwrap();
+		monitor.metrics.earnings_ledger.record_payment(&now, 250, Some(String::from("0x01")));
+		monitor.metrics.node_status = NodeStatus::Connected;
+		monitor.metrics.node_inactive = true;
+		let events = events_out.take_events(&monitors, &now);
+		assert_eq!(events.len(), 3);
+		assert_eq!(events[0]["event"], "payment");
+		assert_eq!(events[0]["attos"], 250);
+		assert_eq!(events[1]["to"], "Connected");
+		assert_eq!(events[2]["alert"], "inactive");
+		assert_eq!(events[2]["raised"], true);
+		assert!(events_out.take_events(&monitors, &now).is_empty());
+	}
+}
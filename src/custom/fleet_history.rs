@@ -0,0 +1,106 @@
+//! Daily totals for each node, for comparing days in the Fleet view
+//!
+//! The totals for a day are those after the last logfile entry of that day
+//! (UTC), so the most recent days are kept with the node's checkpoint and
+//! rebuilt when a logfile is loaded.
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Days, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Days of totals to keep: enough to compare yesterday with a week before
+const DAYS_KEPT: u64 = 9;
+
+/// Change between days which is highlighted as significant (percent)
+pub const SIGNIFICANT_CHANGE_PERCENT: f64 = 50.0;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct DayTotals {
+	pub attos_earned: u128,
+	pub puts: u64,
+	pub gets: u64,
+	pub errors: u64,
+	pub records_stored: u64,
+}
+
+/// Activity during a single day
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DayActivity {
+	pub attos_earned: u128,
+	pub puts: u64,
+	pub gets: u64,
+	pub errors: u64,
+	pub records_stored: u64, // Level at the end of the day rather than a change
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct DailyTotals {
+	pub days: BTreeMap<NaiveDate, DayTotals>,
+}
+
+impl DailyTotals {
+	/// Record the totals following a logfile entry at time
+	pub fn record(&mut self, time: &DateTime<Utc>, totals: DayTotals) {
+		let date = time.date_naive();
+		self.days.insert(date, totals);
+
+		if let Some(oldest_kept) = date.checked_sub_days(Days::new(DAYS_KEPT)) {
+			self.days = self.days.split_off(&oldest_kept);
+		}
+	}
+
+	/// Activity during date, if the totals for it and the day before are known
+	pub fn activity_on(&self, date: &NaiveDate) -> Option<DayActivity> {
+		let end = self.days.get(date)?;
+		let start = self.days.get(&date.pred_opt()?)?;
+		Some(DayActivity {
+			attos_earned: end.attos_earned.saturating_sub(start.attos_earned),
+			puts: end.puts.saturating_sub(start.puts),
+			gets: end.gets.saturating_sub(start.gets),
+			errors: end.errors.saturating_sub(start.errors),
+			records_stored: end.records_stored,
+		})
+	}
+}
+
+/// True if two days differ by more than SIGNIFICANT_CHANGE_PERCENT
+pub fn is_significant_change(before: u128, after: u128) -> bool {
+	if before == 0 {
+		return after > 0;
+	}
+	let change_percent = 100.0 * (after as f64 - before as f64).abs() / before as f64;
+	change_percent > SIGNIFICANT_CHANGE_PERCENT
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::str::FromStr;
+
+	#[test]
+	fn it_derives_daily_activity_from_end_of_day_totals() {
+		let mut daily = DailyTotals::default();
+		let totals = |attos_earned, puts| DayTotals {
+			attos_earned,
+			puts,
+			..Default::default()
+		};
+		daily.record(&DateTime::from_str("2024-03-01T10:00:00Z").unwrap(), totals(10, 1));
+		daily.record(&DateTime::from_str("2024-03-01T23:00:00Z").unwrap(), totals(100, 5));
+		daily.record(&DateTime::from_str("2024-03-02T12:00:00Z").unwrap(), totals(250, 9));
+
+		let march_2 = NaiveDate::from_ymd_opt(2024, 3, 2).unwrap();
+		let activity = daily.activity_on(&march_2).unwrap();
+		assert_eq!(activity.attos_earned, 150);
+		assert_eq!(activity.puts, 4);
+		assert!(daily.activity_on(&march_2.pred_opt().unwrap()).is_none());
+
+		// Old days are dropped
+		daily.record(&DateTime::from_str("2024-03-20T12:00:00Z").unwrap(), totals(300, 10));
+		assert_eq!(daily.days.len(), 1);
+
+		assert!(is_significant_change(100, 151));
+		assert!(!is_significant_change(100, 149));
+		assert!(is_significant_change(0, 1));
+	}
+}
@@ -0,0 +1,82 @@
+//! Fleet map: numbers of nodes by age and by records stored
+//!
+//! Shown below the Fleet Daily Comparison to give an overview of a large fleet,
+//! such as how many nodes are new and how many of the older ones are still
+//! storing few records. A node's age is the days since it was first seen in its
+//! logfile, so it starts again if the logfile is replaced.
+use chrono::{DateTime, Utc};
+
+use super::app::LogMonitor;
+
+/// Row labels, with the age in days below which a node is in the row
+pub const AGE_BANDS: [(&str, i64); 5] = [
+	("< 1 day", 1),
+	("1-6 days", 7),
+	("1-4 weeks", 30),
+	("1-3 months", 90),
+	("3+ months", i64::MAX),
+];
+
+/// Column labels, with the number of records below which a node is in the column
+pub const RECORDS_BANDS: [(&str, u64); 6] = [
+	("0", 1),
+	("1-99", 100),
+	("100-999", 1_000),
+	("1k-4.9k", 5_000),
+	("5k-9.9k", 10_000),
+	("10k+", u64::MAX),
+];
+
+pub struct FleetMap {
+	pub counts: [[usize; RECORDS_BANDS.len()]; AGE_BANDS.len()],
+	pub unknown_age: usize, // Nodes with no log entries yet
+	pub max_count: usize,
+}
+
+impl FleetMap {
+	/// Count nodes given their age in days (if known) and records stored
+	pub fn new(nodes: impl Iterator<Item = (Option<i64>, u64)>) -> FleetMap {
+		let mut fleet_map = FleetMap {
+			counts: [[0; RECORDS_BANDS.len()]; AGE_BANDS.len()],
+			unknown_age: 0,
+			max_count: 0,
+		};
+		for (age_days, records) in nodes {
+			let Some(age_days) = age_days else {
+				fleet_map.unknown_age += 1;
+				continue;
+			};
+			let row = AGE_BANDS.iter().position(|(_, below)| age_days < *below).unwrap_or(AGE_BANDS.len() - 1);
+			let column = RECORDS_BANDS
+				.iter()
+				.position(|(_, below)| records < *below)
+				.unwrap_or(RECORDS_BANDS.len() - 1);
+			fleet_map.counts[row][column] += 1;
+			fleet_map.max_count = fleet_map.max_count.max(fleet_map.counts[row][column]);
+		}
+		fleet_map
+	}
+
+	pub fn from_monitors<'a>(monitors: impl Iterator<Item = &'a LogMonitor>, now: &DateTime<Utc>) -> FleetMap {
+		FleetMap::new(monitors.filter(|monitor| monitor.is_node()).map(|monitor| {
+			let first_seen = monitor.metrics.restart_history.first_entry_time().or(monitor.metrics.node_started);
+			(first_seen.map(|first_seen| (*now - first_seen).num_days()), monitor.metrics.records_stored)
+		}))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn it_counts_nodes_by_age_and_records() {
+		let nodes = [(Some(0), 0), (Some(3), 150), (Some(5), 120), (Some(400), 20_000), (None, 10)];
+		let fleet_map = FleetMap::new(nodes.into_iter());
+		assert_eq!(fleet_map.counts[0][0], 1);
+		assert_eq!(fleet_map.counts[1][2], 2);
+		assert_eq!(fleet_map.counts[4][5], 1);
+		assert_eq!(fleet_map.unknown_age, 1);
+		assert_eq!(fleet_map.max_count, 2);
+	}
+}
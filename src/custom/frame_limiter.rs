@@ -0,0 +1,66 @@
+//! Limits how often the dashboard is drawn
+//!
+//! Input, ticks and new logfile lines each ask for a frame, but a frame is
+//! drawn at most once per tick_rate however many were asked for. Those
+//! asked for in between are drawn together once the interval has passed,
+//! which the next tick ensures happens within another tick_rate, so that
+//! under heavy log load the time goes on processing lines rather than
+//! drawing frames nobody will see.
+use std::time::{Duration, Instant};
+
+pub struct FrameLimiter {
+	min_interval: Duration,
+	last_frame: Option<Instant>,
+	pending: bool, // A frame has been asked for since the last was drawn
+}
+
+impl FrameLimiter {
+	pub fn new(min_interval: Duration) -> FrameLimiter {
+		FrameLimiter {
+			min_interval,
+			last_frame: None,
+			pending: false,
+		}
+	}
+
+	/// Ask for a frame to be drawn when next allowed
+	pub fn request(&mut self) {
+		self.pending = true;
+	}
+
+	/// True if a frame has been asked for and may be drawn at now, in which
+	/// case it is assumed to be drawn
+	pub fn take_frame(&mut self, now: Instant) -> bool {
+		let allowed = self
+			.last_frame
+			.map_or(true, |last_frame| now.duration_since(last_frame) >= self.min_interval);
+		if !self.pending || !allowed {
+			return false;
+		}
+		self.pending = false;
+		self.last_frame = Some(now);
+		true
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn it_draws_at_most_one_frame_per_interval() {
+		let start = Instant::now();
+		let mut frames = FrameLimiter::new(Duration::from_millis(200));
+		assert!(!frames.take_frame(start));
+		frames.request();
+		assert!(frames.take_frame(start));
+
+		// Many requests within the interval wait to be drawn as one frame
+		for millis in 1..200 {
+			frames.request();
+			assert!(!frames.take_frame(start + Duration::from_millis(millis)));
+		}
+		assert!(frames.take_frame(start + Duration::from_millis(200)));
+		assert!(!frames.take_frame(start + Duration::from_millis(1000)));
+	}
+}
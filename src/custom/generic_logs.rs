@@ -0,0 +1,130 @@
+//! Monitoring of logfiles which are not from a node (see --generic)
+//!
+//! Any logfile, such as /var/log/auth.log or kern.log, can be followed in
+//! generic mode. Rather than node metrics, vdash then counts its lines by
+//! level (ERROR, WARN etc.) and keeps a timeline of the rate lines arrive.
+use std::sync::LazyLock;
+
+use chrono::{DateTime, Utc};
+use ratatui::style::Color;
+use regex::Regex;
+
+use super::app_timelines::{ONE_MINUTE_TIMESCALE, TIMESCALES};
+use super::timelines::Timeline;
+
+/// Levels counted, most severe first. Lines without a recognised level are OTHER.
+pub const LEVELS: [&str; 5] = ["ERROR", "WARN", "INFO", "DEBUG", "OTHER"];
+
+static LEVEL_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+	Regex::new(r"(?i)\b(emerg|alert|crit|critical|fatal|err|error|warn|warning|notice|info|debug|trace)\b")
+		.expect("The level regex failed to compile. This is a bug.")
+});
+
+/// True if a logfile matches one of the --generic globs
+pub fn is_generic_logfile(opt_generic: &[String], logfile: &str) -> bool {
+	opt_generic
+		.iter()
+		.filter_map(|globpath| glob::Pattern::new(globpath).ok())
+		.any(|pattern| pattern.matches(logfile))
+}
+
+/// The level of a line, from the first level word it contains
+pub fn level_of(line: &str) -> &'static str {
+	let Some(level) = LEVEL_PATTERN.find(line) else {
+		return "OTHER";
+	};
+	match level.as_str().to_ascii_lowercase().as_str() {
+		"emerg" | "alert" | "crit" | "critical" | "fatal" | "err" | "error" => "ERROR",
+		"warn" | "warning" => "WARN",
+		"notice" | "info" => "INFO",
+		_ => "DEBUG",
+	}
+}
+
+pub struct GenericMetrics {
+	pub lines: u64,
+	pub level_counts: [u64; LEVELS.len()],
+	pub latest_line_time: Option<DateTime<Utc>>,
+	pub lines_timeline: Timeline,
+}
+
+impl GenericMetrics {
+	pub fn new(timeline_steps: usize) -> GenericMetrics {
+		let mut lines_timeline = Timeline::new(String::from("Lines"), String::from(""), false, true, Color::LightCyan);
+		for (name, duration) in TIMESCALES.iter() {
+			lines_timeline.add_bucket_set(name, *duration, timeline_steps);
+		}
+		GenericMetrics {
+			lines: 0,
+			level_counts: [0; LEVELS.len()],
+			latest_line_time: None,
+			lines_timeline,
+		}
+	}
+
+	/// Count a line which arrived at time (generic logfiles have no common time format)
+	pub fn record_line(&mut self, line: &str, time: &DateTime<Utc>) {
+		self.lines += 1;
+		if let Some(index) = LEVELS.iter().position(|level| *level == level_of(line)) {
+			self.level_counts[index] += 1;
+		}
+		self.latest_line_time = Some(*time);
+		self.lines_timeline.update_current_time(time);
+		self.lines_timeline.update_value(time, 1);
+	}
+
+	pub fn level_count(&self, level: &str) -> u64 {
+		LEVELS
+			.iter()
+			.position(|other| *other == level)
+			.map_or(0, |index| self.level_counts[index])
+	}
+
+	/// Lines in the most recent minute
+	pub fn lines_per_minute(&self) -> u64 {
+		self.lines_timeline
+			.get_buckets(ONE_MINUTE_TIMESCALE, None)
+			.and_then(|buckets| buckets.last().copied())
+			.map_or(0, |lines| lines as u64)
+	}
+
+	/// Shown in the Status column of the Summary
+	pub fn status_text(&self) -> String {
+		format!(
+			"GENERIC {} lines, {}/min, {} ERROR, {} WARN",
+			self.lines,
+			self.lines_per_minute(),
+			self.level_count("ERROR"),
+			self.level_count("WARN")
+		)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn it_counts_lines_by_level() {
+		assert_eq!(level_of("Oct 16 10:00:01 host sshd[99]: error: PAM: Authentication failure"), "ERROR");
+		assert_eq!(level_of("kernel: [  1.0] ACPI Warning: SystemIO range conflicts"), "WARN");
+		assert_eq!(level_of("<6>[  2.5] usb 1-1: new high-speed USB device"), "OTHER");
+		assert_eq!(level_of("2024-03-23 INFO starting"), "INFO");
+		assert_eq!(level_of("Information about terrorism"), "OTHER");
+
+		let time = DateTime::parse_from_rfc3339("2024-03-23T19:00:00Z").unwrap().with_timezone(&Utc);
+		let mut metrics = GenericMetrics::new(10);
+		for line in ["sshd: error: bad key", "sshd: Accepted publickey", "sshd: fatal: timeout"] {
+			metrics.record_line(line, &time);
+		}
+		assert_eq!(metrics.lines, 3);
+		assert_eq!(metrics.level_count("ERROR"), 2);
+		assert_eq!(metrics.level_count("OTHER"), 1);
+		assert_eq!(metrics.lines_per_minute(), 3);
+		assert!(metrics.status_text().starts_with("GENERIC 3 lines, 3/min, 2 ERROR"));
+
+		let globs = [String::from("/var/log/*.log")];
+		assert!(is_generic_logfile(&globs, "/var/log/auth.log"));
+		assert!(!is_generic_logfile(&globs, "/home/ant/antnode.log"));
+	}
+}
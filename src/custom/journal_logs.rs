@@ -0,0 +1,127 @@
+//! Read node logs from journald (see --journal)
+//!
+//! For nodes run as systemd services which log to the journal rather than
+//! files. Entries are read by following 'journalctl --output=json' for the
+//! units matching a glob, and each unit becomes a monitor named 'journal:<unit>'.
+//!
+//! Lines which already start with vdash's expected metadata (e.g. when the
+//! node writes its usual log format to stdout) are passed on unchanged. Others
+//! are given metadata from the journal entry's timestamp and priority so they
+//! can be decoded in the same way as lines from a logfile.
+use std::process::Stdio;
+
+use chrono::DateTime;
+use serde_json::Value;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+use tokio::sync::mpsc::UnboundedSender;
+
+use super::log_prefix::split_log_line;
+use super::remote_logs::{spawn_stderr_reader, RemoteEvent};
+
+/// Start following journal entries for units matching unit_glob, sending their lines to tx
+pub fn spawn_journal_reader(unit_glob: String, tx: UnboundedSender<RemoteEvent>) {
+	tokio::spawn(async move {
+		let child = Command::new("journalctl")
+			.args(["--follow", "--lines=all", "--output=json", "--no-pager", "--unit", &unit_glob])
+			.stdin(Stdio::null())
+			.stdout(Stdio::piped())
+			.stderr(Stdio::piped())
+			.kill_on_drop(true)
+			.spawn();
+
+		let mut child = match child {
+			Ok(child) => child,
+			Err(e) => {
+				let _ = tx.send(RemoteEvent::Closed {
+					destination: format!("journal {}", unit_glob),
+					message: format!("failed to run journalctl: {}", e),
+				});
+				return;
+			}
+		};
+
+		let stderr_reader = spawn_stderr_reader(child.stderr.take());
+		if let Some(stdout) = child.stdout.take() {
+			let mut lines = BufReader::new(stdout).lines();
+			while let Ok(Some(json_line)) = lines.next_line().await {
+				for (logfile, line) in decode_journal_entry(&json_line) {
+					if tx.send(RemoteEvent::Line { logfile, line }).is_err() {
+						return;
+					}
+				}
+			}
+		}
+
+		let _ = child.wait().await;
+		let message = stderr_reader.await.ok().flatten().unwrap_or_else(|| String::from("journalctl exited"));
+		let _ = tx.send(RemoteEvent::Closed {
+			destination: format!("journal {}", unit_glob),
+			message,
+		});
+	});
+}
+
+/// The monitor name and log lines of a journal entry output by 'journalctl --output=json'
+fn decode_journal_entry(json_line: &str) -> Vec<(String, String)> {
+	let Ok(entry) = serde_json::from_str::<Value>(json_line) else {
+		return Vec::new();
+	};
+	// Messages which aren't valid UTF-8 are given as an array of bytes, and ignored
+	let (Some(unit), Some(message)) = (entry["_SYSTEMD_UNIT"].as_str(), entry["MESSAGE"].as_str()) else {
+		return Vec::new();
+	};
+	let logfile = format!("journal:{}", unit);
+
+	let time = entry["__REALTIME_TIMESTAMP"]
+		.as_str()
+		.and_then(|micros| micros.parse::<i64>().ok())
+		.and_then(|micros| {
+			DateTime::from_timestamp(micros.div_euclid(1_000_000), (micros.rem_euclid(1_000_000) * 1000) as u32)
+		});
+	let category = match entry["PRIORITY"].as_str().and_then(|p| p.parse::<u8>().ok()) {
+		Some(0..=3) => "ERROR",
+		Some(4) => "WARN",
+		Some(7) => "DEBUG",
+		_ => "INFO",
+	};
+	let source = entry["SYSLOG_IDENTIFIER"].as_str().unwrap_or("journal");
+
+	message
+		.lines()
+		.filter_map(|line| {
+			if split_log_line(line).is_some() {
+				return Some(line.to_string());
+			}
+			let time = time?.format("%Y-%m-%dT%H:%M:%S%.6fZ");
+			Some(format!("[{} {} {}] {}", time, category, source, line))
+		})
+		.map(|line| (logfile.clone(), line))
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn it_decodes_journal_entries() {
+		let entry = r#"{"_SYSTEMD_UNIT":"antnode3.service","PRIORITY":"6","SYSLOG_IDENTIFIER":"antnode","__REALTIME_TIMESTAMP":"1711222712350118","MESSAGE":"Running antnode v0.112.6"}"#;
+		assert_eq!(
+			decode_journal_entry(entry),
+			vec![(
+				String::from("journal:antnode3.service"),
+				String::from("[2024-03-23T19:38:32.350118Z INFO antnode] Running antnode v0.112.6")
+			)]
+		);
+
+		// Lines in the node's own log format are unchanged
+		let entry = r#"{"_SYSTEMD_UNIT":"antnode3.service","PRIORITY":"3","__REALTIME_TIMESTAMP":"1711222712350118","MESSAGE":"[2024-03-23T19:38:30.000000Z INFO ant_node] Wrote record"}"#;
+		assert_eq!(
+			decode_journal_entry(entry)[0].1,
+			"[2024-03-23T19:38:30.000000Z INFO ant_node] Wrote record"
+		);
+
+		assert!(decode_journal_entry(r#"{"_SYSTEMD_UNIT":"antnode3.service","MESSAGE":[200,201]}"#).is_empty());
+	}
+}
@@ -0,0 +1,443 @@
+//! Keys for each action of the dashboard (see --keymap and --key)
+//!
+//! Keyboard events are looked up here to give the actions bound to the key,
+//! which are tried in turn until one applies in the current view (so 'x'
+//! exports in the Earnings view but removes a node in the Summary). The
+//! 'vim' keymap moves the selection with j and k, and to the top and bottom
+//! with g and G, moving what those keys did by default to other keys. Any
+//! key can then be rebound with --key <key>=<action>.
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+	Quit,
+	DebugLine,
+	DebugView,
+	Select,
+	Help,
+	SummaryView,
+	NodeView,
+	EarningsView,
+	FleetView,
+	SplitView,
+	Up,
+	Down,
+	PageUp,
+	PageDown,
+	Top,
+	Bottom,
+	NextNode,
+	PreviousNode,
+	JumpToNode,
+	CommandPalette,
+	Filter,
+	ClearFilter,
+	Tags,
+	ReverseSort,
+	KeepSortKey,
+	ToggleGroups,
+	NextBreakdown,
+	CopyNodeIds,
+	RemoveNode,
+	RestartNode,
+	StopNode,
+	UpgradeNode,
+	DeleteCheckpoints,
+	Rescan,
+	ExportCsv,
+	ExportTaxReport,
+	ToggleCurrency,
+	NextCurrency,
+	EarningsUnits,
+	ZoomIn,
+	ZoomOut,
+	PanBack,
+	PanForward,
+	TimelineWindow,
+	NextMmm,
+	AllMmm,
+	NextAverage,
+	Braille,
+	TimelineCursor,
+	SessionDelta,
+	EarningsPeriod,
+	CursorOlder,
+	CursorNewer,
+	NextTimeline,
+	PreviousTimeline,
+	TimelinesTaller,
+	TimelinesShorter,
+	ToggleLogfile,
+	TopMessages,
+	Diagnostics,
+	PlayPause,
+	ReplayBack,
+	ReplayForward,
+	Unbound,
+}
+
+/// Names of actions for --key
+const ACTION_NAMES: &[(&str, Action)] = &[
+	("quit", Action::Quit),
+	("debug-line", Action::DebugLine),
+	("debug-view", Action::DebugView),
+	("select", Action::Select),
+	("help", Action::Help),
+	("summary-view", Action::SummaryView),
+	("node-view", Action::NodeView),
+	("earnings-view", Action::EarningsView),
+	("fleet-view", Action::FleetView),
+	("split-view", Action::SplitView),
+	("up", Action::Up),
+	("down", Action::Down),
+	("page-up", Action::PageUp),
+	("page-down", Action::PageDown),
+	("top", Action::Top),
+	("bottom", Action::Bottom),
+	("next-node", Action::NextNode),
+	("previous-node", Action::PreviousNode),
+	("jump-to-node", Action::JumpToNode),
+	("command-palette", Action::CommandPalette),
+	("filter", Action::Filter),
+	("clear-filter", Action::ClearFilter),
+	("tags", Action::Tags),
+	("reverse-sort", Action::ReverseSort),
+	("keep-sort-key", Action::KeepSortKey),
+	("toggle-groups", Action::ToggleGroups),
+	("next-breakdown", Action::NextBreakdown),
+	("copy-node-ids", Action::CopyNodeIds),
+	("remove-node", Action::RemoveNode),
+	("restart-node", Action::RestartNode),
+	("stop-node", Action::StopNode),
+	("upgrade-node", Action::UpgradeNode),
+	("delete-checkpoints", Action::DeleteCheckpoints),
+	("rescan", Action::Rescan),
+	("export-csv", Action::ExportCsv),
+	("export-tax-report", Action::ExportTaxReport),
+	("toggle-currency", Action::ToggleCurrency),
+	("next-currency", Action::NextCurrency),
+	("earnings-units", Action::EarningsUnits),
+	("zoom-in", Action::ZoomIn),
+	("zoom-out", Action::ZoomOut),
+	("pan-back", Action::PanBack),
+	("pan-forward", Action::PanForward),
+	("timeline-window", Action::TimelineWindow),
+	("next-mmm", Action::NextMmm),
+	("all-mmm", Action::AllMmm),
+	("next-average", Action::NextAverage),
+	("braille", Action::Braille),
+	("timeline-cursor", Action::TimelineCursor),
+	("session-delta", Action::SessionDelta),
+	("earnings-period", Action::EarningsPeriod),
+	("cursor-older", Action::CursorOlder),
+	("cursor-newer", Action::CursorNewer),
+	("next-timeline", Action::NextTimeline),
+	("previous-timeline", Action::PreviousTimeline),
+	("timelines-taller", Action::TimelinesTaller),
+	("timelines-shorter", Action::TimelinesShorter),
+	("toggle-logfile", Action::ToggleLogfile),
+	("top-messages", Action::TopMessages),
+	("diagnostics", Action::Diagnostics),
+	("play-pause", Action::PlayPause),
+	("replay-back", Action::ReplayBack),
+	("replay-forward", Action::ReplayForward),
+	("none", Action::Unbound),
+];
+
+/// Keys of the default keymap. Where a key has more than one action, the first
+/// which applies in the current view is taken.
+const DEFAULT_KEYS: &[(&str, Action)] = &[
+	("~", Action::DebugLine),
+	("q", Action::Quit),
+	("Q", Action::Quit),
+	("left", Action::CursorOlder),
+	("right", Action::CursorNewer),
+	("left", Action::ReplayBack),
+	("right", Action::ReplayForward),
+	("space", Action::PlayPause),
+	("enter", Action::Select),
+	("space", Action::ReverseSort),
+	("$", Action::ToggleCurrency),
+	("c", Action::NextCurrency),
+	("C", Action::NextCurrency),
+	("s", Action::SummaryView),
+	("S", Action::SummaryView),
+	("e", Action::EarningsView),
+	("E", Action::EarningsView),
+	("b", Action::SplitView),
+	("B", Action::SplitView),
+	("f", Action::FleetView),
+	("F", Action::FleetView),
+	("x", Action::ExportCsv),
+	("X", Action::ExportTaxReport),
+	("x", Action::RemoveNode),
+	("D", Action::DeleteCheckpoints),
+	("G", Action::ToggleGroups),
+	("k", Action::KeepSortKey),
+	("d", Action::SessionDelta),
+	("d", Action::EarningsPeriod),
+	("a", Action::NextBreakdown),
+	("A", Action::NextBreakdown),
+	("/", Action::Filter),
+	("esc", Action::ClearFilter),
+	("#", Action::Tags),
+	("j", Action::JumpToNode),
+	("J", Action::JumpToNode),
+	(":", Action::CommandPalette),
+	("p", Action::CopyNodeIds),
+	("P", Action::CopyNodeIds),
+	("h", Action::Help),
+	("H", Action::Help),
+	("?", Action::Help),
+	("n", Action::NodeView),
+	("N", Action::NodeView),
+	("+", Action::ZoomIn),
+	("i", Action::ZoomIn),
+	("I", Action::ZoomIn),
+	("-", Action::ZoomOut),
+	("o", Action::ZoomOut),
+	("O", Action::ZoomOut),
+	("[", Action::PanBack),
+	("]", Action::PanForward),
+	("w", Action::TimelineWindow),
+	("W", Action::TimelineWindow),
+	("l", Action::ToggleLogfile),
+	("L", Action::ToggleLogfile),
+	("v", Action::TopMessages),
+	("V", Action::TopMessages),
+	("!", Action::Diagnostics),
+	("m", Action::NextMmm),
+	("M", Action::AllMmm),
+	("=", Action::NextAverage),
+	(".", Action::Braille),
+	("y", Action::TimelineCursor),
+	("Y", Action::TimelineCursor),
+	("u", Action::EarningsUnits),
+	("U", Action::EarningsUnits),
+	("r", Action::Rescan),
+	("R", Action::Rescan),
+	("t", Action::NextTimeline),
+	("T", Action::PreviousTimeline),
+	("pagedown", Action::PageDown),
+	("pageup", Action::PageUp),
+	("home", Action::Top),
+	("end", Action::Bottom),
+	("down", Action::Down),
+	("up", Action::Up),
+	("right", Action::NextNode),
+	("tab", Action::NextNode),
+	("left", Action::PreviousNode),
+	("g", Action::DebugView),
+	("ctrl-r", Action::RestartNode),
+	("ctrl-t", Action::StopNode),
+	("ctrl-u", Action::UpgradeNode),
+	("ctrl-down", Action::TimelinesTaller),
+	("ctrl-up", Action::TimelinesShorter),
+];
+
+/// Keys changed by the 'vim' keymap, including new keys for the actions they replace
+const VIM_KEYS: &[(&str, Action)] = &[
+	("j", Action::Down),
+	("k", Action::Up),
+	("g", Action::Top),
+	("G", Action::Bottom),
+	("K", Action::KeepSortKey),
+	("z", Action::ToggleGroups),
+	("d", Action::DebugView),
+	("Z", Action::SessionDelta),
+	("Z", Action::EarningsPeriod),
+];
+
+/// A key, with or without the control key held
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Key {
+	pub code: KeyCode,
+	pub ctrl: bool,
+}
+
+impl Key {
+	pub fn from_event(event: &KeyEvent) -> Key {
+		Key {
+			code: event.code,
+			ctrl: event.modifiers.contains(KeyModifiers::CONTROL),
+		}
+	}
+}
+
+/// Key from a name such as "x", "X", "enter", "pageup" or "ctrl-r"
+pub fn parse_key(text: &str) -> Result<Key, String> {
+	let (ctrl, name) = match text.strip_prefix("ctrl-") {
+		Some(name) if !name.is_empty() => (true, name),
+		_ => (false, text),
+	};
+	let mut chars = name.chars();
+	let code = match (chars.next(), chars.next()) {
+		(Some(c), None) => KeyCode::Char(c),
+		_ => match name.to_lowercase().as_str() {
+			"enter" => KeyCode::Enter,
+			"esc" => KeyCode::Esc,
+			"space" => KeyCode::Char(' '),
+			"tab" => KeyCode::Tab,
+			"backspace" => KeyCode::Backspace,
+			"up" => KeyCode::Up,
+			"down" => KeyCode::Down,
+			"left" => KeyCode::Left,
+			"right" => KeyCode::Right,
+			"pageup" => KeyCode::PageUp,
+			"pagedown" => KeyCode::PageDown,
+			"home" => KeyCode::Home,
+			"end" => KeyCode::End,
+			_ => return Err(format!("unknown key '{}'", text)),
+		},
+	};
+	Ok(Key { code, ctrl })
+}
+
+/// Name of a key as given to parse_key(), with a character in quotes
+pub fn key_name(key: Key) -> String {
+	let name = match key.code {
+		KeyCode::Char(' ') => String::from("space"),
+		// Quoted unless named with a modifier, as in ctrl-r
+		KeyCode::Char(c) if key.ctrl => c.to_string(),
+		KeyCode::Char(c) => format!("'{}'", c),
+		KeyCode::Enter => String::from("enter"),
+		KeyCode::Esc => String::from("esc"),
+		KeyCode::Tab => String::from("tab"),
+		KeyCode::Backspace => String::from("backspace"),
+		KeyCode::Up => String::from("up"),
+		KeyCode::Down => String::from("down"),
+		KeyCode::Left => String::from("left"),
+		KeyCode::Right => String::from("right"),
+		KeyCode::PageUp => String::from("pageup"),
+		KeyCode::PageDown => String::from("pagedown"),
+		KeyCode::Home => String::from("home"),
+		KeyCode::End => String::from("end"),
+		code => format!("{:?}", code).to_lowercase(),
+	};
+	if key.ctrl {
+		format!("ctrl-{}", name)
+	} else {
+		name
+	}
+}
+
+pub fn parse_action(name: &str) -> Result<Action, String> {
+	ACTION_NAMES
+		.iter()
+		.find(|(action_name, _)| *action_name == name)
+		.map(|(_, action)| *action)
+		.ok_or_else(|| format!("unknown action '{}'", name))
+}
+
+pub struct Keymap {
+	bindings: Vec<(Key, Action)>, // In the order the actions are tried
+}
+
+impl Default for Keymap {
+	fn default() -> Keymap {
+		Keymap {
+			bindings: DEFAULT_KEYS
+				.iter()
+				.map(|(key, action)| (parse_key(key).unwrap(), *action))
+				.collect(),
+		}
+	}
+}
+
+impl Keymap {
+	/// The named keymap ('default' or 'vim') with keys rebound as <key>=<action>
+	pub fn new(name: &str, keys: &[String]) -> Result<Keymap, String> {
+		let mut keymap = Keymap::default();
+		match name {
+			"default" => {}
+			"vim" => {
+				for (index, (key, action)) in VIM_KEYS.iter().enumerate() {
+					// Keys listed more than once keep each of their actions
+					if VIM_KEYS[..index].iter().any(|(earlier, _)| earlier == key) {
+						keymap.bindings.push((parse_key(key)?, *action));
+					} else {
+						keymap.bind(key, *action)?;
+					}
+				}
+			}
+			_ => return Err(format!("unknown --keymap '{}', expected default or vim", name)),
+		}
+
+		for binding in keys {
+			let Some((key, action)) = binding.split_once('=') else {
+				return Err(format!("invalid --key '{}', expected <key>=<action>", binding));
+			};
+			keymap
+				.bind(key.trim(), parse_action(action.trim())?)
+				.map_err(|e| format!("invalid --key '{}', {}", binding, e))?;
+		}
+		Ok(keymap)
+	}
+
+	/// Bind key to action alone, or to nothing if the action is Unbound
+	pub fn bind(&mut self, key: &str, action: Action) -> Result<(), String> {
+		let parsed = parse_key(key)?;
+		self.bindings.retain(|(bound, _)| *bound != parsed);
+		if action != Action::Unbound {
+			self.bindings.push((parsed, action));
+		}
+		Ok(())
+	}
+
+	/// Keys bound to the action, for help
+	pub fn keys_for(&self, action: Action) -> Vec<Key> {
+		self
+			.bindings
+			.iter()
+			.filter(|(_, bound)| *bound == action)
+			.map(|(key, _)| *key)
+			.collect()
+	}
+
+	/// Actions bound to the key, in the order to try them
+	pub fn actions(&self, key: Key) -> impl Iterator<Item = Action> + '_ {
+		self
+			.bindings
+			.iter()
+			.filter(move |(bound, _)| *bound == key)
+			.map(|(_, action)| *action)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn actions(keymap: &Keymap, key: &str) -> Vec<Action> {
+		keymap.actions(parse_key(key).unwrap()).collect()
+	}
+
+	#[test]
+	fn it_maps_keys_to_actions() {
+		let keymap = Keymap::default();
+		assert_eq!(
+			actions(&keymap, "left"),
+			vec![Action::CursorOlder, Action::ReplayBack, Action::PreviousNode]
+		);
+		assert_eq!(actions(&keymap, "j"), vec![Action::JumpToNode]);
+		assert_eq!(actions(&keymap, "ctrl-r"), vec![Action::RestartNode]);
+		assert_eq!(actions(&keymap, "r"), vec![Action::Rescan]);
+
+		let keymap = Keymap::new("vim", &[String::from("Q=none"), String::from("ctrl-x = export-csv")]).unwrap();
+		assert_eq!(actions(&keymap, "j"), vec![Action::Down]);
+		assert_eq!(actions(&keymap, "G"), vec![Action::Bottom]);
+		assert_eq!(actions(&keymap, "J"), vec![Action::JumpToNode]);
+		assert_eq!(actions(&keymap, ":"), vec![Action::CommandPalette]);
+		assert_eq!(actions(&keymap, "Z"), vec![Action::SessionDelta, Action::EarningsPeriod]);
+		assert_eq!(actions(&keymap, "Q"), vec![]);
+		assert_eq!(actions(&keymap, "ctrl-x"), vec![Action::ExportCsv]);
+		assert_eq!(key_name(keymap.keys_for(Action::ExportCsv)[1]), "ctrl-x");
+		assert_eq!(key_name(parse_key("space").unwrap()), "space");
+
+		assert_eq!(parse_key("PageDown").unwrap().code, KeyCode::PageDown);
+		assert!(Keymap::new("emacs", &[]).is_err());
+		assert!(Keymap::new("default", &[String::from("x")]).is_err());
+		assert!(Keymap::new("default", &[String::from("x=explode")]).is_err());
+		assert!(Keymap::new("default", &[String::from("f13=quit")]).is_err());
+	}
+}
@@ -0,0 +1,345 @@
+//! Accept logfile lines pushed over TCP or HTTP (see --listen)
+//!
+//! A connection may send plain newline delimited lines (e.g. 'tail -F antnode.log | nc host port')
+//! or an HTTP POST whose body holds the lines (e.g. 'tail -F antnode.log | curl -T - http://host:port/node1').
+//!
+//! Each stream becomes a monitor named after the sender's address plus a node
+//! name, so a node which reconnects continues in the same monitor. The name is
+//! the HTTP path, or for plain TCP an optional first line 'vdash-node: <name>'.
+//!
+//! There is no authentication, so a port alone listens only on the loopback
+//! interface and other interfaces must be named in full (e.g. 0.0.0.0:4000).
+//! However many senders connect, at most MAX_LISTENER_NODES nodes are created,
+//! and a connection sending a line or HTTP chunk over MAX_LINE_BYTES is dropped.
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc::UnboundedSender;
+
+use super::remote_logs::RemoteEvent;
+
+const NODE_NAME_PREFIX: &str = "vdash-node: ";
+
+/// Most nodes senders can create, so the listener can't be used to exhaust memory
+pub const MAX_LISTENER_NODES: usize = 200;
+
+/// Longest line, HTTP header or chunk a sender can send, for the same reason
+pub const MAX_LINE_BYTES: usize = 1024 * 1024;
+
+/// Address to listen on, from a port (listens on loopback only) or address:port
+pub fn listen_address(listen: &str) -> String {
+	if listen.contains(':') {
+		listen.to_string()
+	} else {
+		format!("127.0.0.1:{}", listen)
+	}
+}
+
+/// Names of the nodes created by senders, shared by all connections
+#[derive(Clone, Default)]
+struct ListenerNodes(Arc<Mutex<HashSet<String>>>);
+
+impl ListenerNodes {
+	/// True if logfile is a node already created, or there is room to create it
+	fn admit(&self, logfile: &str) -> bool {
+		let mut nodes = self.0.lock().unwrap();
+		if nodes.contains(logfile) {
+			return true;
+		}
+		if nodes.len() >= MAX_LISTENER_NODES {
+			return false;
+		}
+		nodes.insert(logfile.to_string());
+		true
+	}
+}
+
+fn too_long(what: &str) -> std::io::Error {
+	std::io::Error::new(
+		std::io::ErrorKind::InvalidData,
+		format!("{} longer than {} bytes", what, MAX_LINE_BYTES),
+	)
+}
+
+/// Read a line of at most MAX_LINE_BYTES into line, without its line ending. Returns
+/// false at the end of the stream, or an error if the line is too long.
+async fn read_bounded_line<R: AsyncBufRead + Unpin>(reader: &mut R, line: &mut String) -> std::io::Result<bool> {
+	let mut bytes = Vec::new();
+	if reader.take(MAX_LINE_BYTES as u64 + 1).read_until(b'\n', &mut bytes).await? == 0 {
+		return Ok(false);
+	}
+	if bytes.len() > MAX_LINE_BYTES {
+		return Err(too_long("line"));
+	}
+	*line = line_text(&bytes);
+	Ok(true)
+}
+
+fn line_text(bytes: &[u8]) -> String {
+	String::from_utf8_lossy(bytes).trim_end_matches(['\r', '\n']).to_string()
+}
+
+fn refused(logfile: &str) -> std::io::Error {
+	std::io::Error::other(format!("refused {}, already receiving {} nodes", logfile, MAX_LISTENER_NODES))
+}
+
+/// Start accepting connections on address, sending their lines to tx
+pub fn spawn_log_listener(address: String, tx: UnboundedSender<RemoteEvent>) {
+	tokio::spawn(async move {
+		let listener = match TcpListener::bind(&address).await {
+			Ok(listener) => listener,
+			Err(e) => {
+				let _ = tx.send(RemoteEvent::Closed {
+					destination: format!("listener {}", address),
+					message: e.to_string(),
+				});
+				return;
+			}
+		};
+
+		let nodes = ListenerNodes::default();
+		while let Ok((stream, peer)) = listener.accept().await {
+			let tx = tx.clone();
+			let nodes = nodes.clone();
+			tokio::spawn(async move {
+				let source = format!("{}", peer.ip());
+				let message = match handle_connection(stream, &source, &nodes, &tx).await {
+					Ok(()) => String::from("connection closed"),
+					Err(e) => e.to_string(),
+				};
+				let _ = tx.send(RemoteEvent::Closed {
+					destination: peer.to_string(),
+					message,
+				});
+			});
+		}
+	});
+}
+
+fn logfile_name(source: &str, node_name: &str) -> String {
+	format!("{}/{}", source, node_name.trim_matches('/'))
+}
+
+async fn handle_connection(
+	stream: TcpStream,
+	source: &str,
+	nodes: &ListenerNodes,
+	tx: &UnboundedSender<RemoteEvent>,
+) -> std::io::Result<()> {
+	let mut reader = BufReader::new(stream);
+	let mut first_line = String::new();
+	if !read_bounded_line(&mut reader, &mut first_line).await? {
+		return Ok(());
+	}
+
+	if let Some(path) = parse_http_request_path(&first_line) {
+		let logfile = logfile_name(&format!("http:{}", source), path);
+		if !nodes.admit(&logfile) {
+			reader
+				.get_mut()
+				.write_all(b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+				.await?;
+			return Err(refused(&logfile));
+		}
+		let mut chunked = false;
+		let mut expect_continue = false;
+		let mut content_length: Option<u64> = None;
+		loop {
+			let mut header = String::new();
+			if !read_bounded_line(&mut reader, &mut header).await? || header.is_empty() {
+				break;
+			}
+			let header = header.to_ascii_lowercase();
+			if header.starts_with("transfer-encoding:") && header.contains("chunked") {
+				chunked = true;
+			}
+			if header.starts_with("expect:") && header.contains("100-continue") {
+				expect_continue = true;
+			}
+			if let Some(length) = header.strip_prefix("content-length:") {
+				content_length = length.trim().parse::<u64>().ok();
+			}
+		}
+
+		if expect_continue {
+			reader.get_mut().write_all(b"HTTP/1.1 100 Continue\r\n\r\n").await?;
+		}
+		if chunked {
+			read_chunked_lines(&mut reader, &logfile, tx).await?;
+		} else if let Some(content_length) = content_length {
+			read_lines(&mut (&mut reader).take(content_length), &logfile, tx).await?;
+		} else {
+			read_lines(&mut reader, &logfile, tx).await?;
+		}
+		reader
+			.get_mut()
+			.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+			.await
+	} else {
+		let tcp_source = format!("tcp:{}", source);
+		let node_name = first_line.strip_prefix(NODE_NAME_PREFIX);
+		let logfile = logfile_name(&tcp_source, node_name.unwrap_or("node"));
+		if !nodes.admit(&logfile) {
+			return Err(refused(&logfile));
+		}
+		if node_name.is_none() {
+			send_line(tx, &logfile, first_line)?;
+		}
+		read_lines(&mut reader, &logfile, tx).await
+	}
+}
+
+/// Path of an HTTP POST or PUT request line such as 'POST /node1 HTTP/1.1'
+fn parse_http_request_path(line: &str) -> Option<&str> {
+	let mut words = line.split_whitespace();
+	let method = words.next()?;
+	let path = words.next()?;
+	let version = words.next()?;
+	if (method == "POST" || method == "PUT") && version.starts_with("HTTP/") {
+		Some(path)
+	} else {
+		None
+	}
+}
+
+fn send_line(tx: &UnboundedSender<RemoteEvent>, logfile: &str, line: String) -> std::io::Result<()> {
+	tx.send(RemoteEvent::Line {
+		logfile: logfile.to_string(),
+		line,
+	})
+	.map_err(|_| std::io::Error::new(std::io::ErrorKind::BrokenPipe, "vdash is closing"))
+}
+
+async fn read_lines<R: AsyncBufRead + Unpin>(
+	reader: &mut R,
+	logfile: &str,
+	tx: &UnboundedSender<RemoteEvent>,
+) -> std::io::Result<()> {
+	let mut line = String::new();
+	while read_bounded_line(reader, &mut line).await? {
+		send_line(tx, logfile, std::mem::take(&mut line))?;
+	}
+	Ok(())
+}
+
+/// Read an HTTP body with chunked transfer encoding, sending each complete line
+async fn read_chunked_lines(
+	reader: &mut BufReader<TcpStream>,
+	logfile: &str,
+	tx: &UnboundedSender<RemoteEvent>,
+) -> std::io::Result<()> {
+	let mut pending = Vec::<u8>::new();
+	loop {
+		let mut size_line = String::new();
+		if !read_bounded_line(reader, &mut size_line).await? {
+			break;
+		}
+		let size_text = size_line.trim().split(';').next().unwrap_or("");
+		let size = usize::from_str_radix(size_text, 16)
+			.map_err(|_| std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid chunk size"))?;
+		if size == 0 {
+			break;
+		}
+		if size > MAX_LINE_BYTES {
+			return Err(too_long("chunk"));
+		}
+
+		let mut chunk = vec![0u8; size + 2]; // Chunk data is followed by CRLF
+		reader.read_exact(&mut chunk).await?;
+		pending.extend_from_slice(&chunk[..size]);
+
+		while let Some(end) = pending.iter().position(|&b| b == b'\n') {
+			let line: Vec<u8> = pending.drain(..=end).collect();
+			send_line(tx, logfile, line_text(&line))?;
+		}
+		if pending.len() > MAX_LINE_BYTES {
+			return Err(too_long("line"));
+		}
+	}
+
+	if !pending.is_empty() {
+		send_line(tx, logfile, line_text(&pending))?;
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn it_recognises_http_requests() {
+		assert_eq!(parse_http_request_path("POST /node1 HTTP/1.1"), Some("/node1"));
+		assert_eq!(parse_http_request_path("PUT /vps/node2 HTTP/1.1"), Some("/vps/node2"));
+		assert!(parse_http_request_path("[2024-03-23T19:38:32.350118Z INFO ant_node] POST").is_none());
+		assert_eq!(logfile_name("http:10.0.0.5", "/node1"), "http:10.0.0.5/node1");
+		assert_eq!(listen_address("4000"), "127.0.0.1:4000");
+		assert_eq!(listen_address("0.0.0.0:4000"), "0.0.0.0:4000");
+	}
+
+	#[test]
+	fn it_limits_the_nodes_senders_create() {
+		let nodes = ListenerNodes::default();
+		for i in 0..MAX_LISTENER_NODES {
+			assert!(nodes.admit(&format!("tcp:10.0.0.5/node{}", i)));
+		}
+		assert!(!nodes.admit("tcp:10.0.0.5/one-too-many"));
+		// A node which reconnects is still accepted
+		assert!(nodes.admit("tcp:10.0.0.5/node0"));
+	}
+
+	#[tokio::test]
+	async fn it_reads_chunked_http_bodies() {
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let address = listener.local_addr().unwrap();
+		let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+
+		let client = tokio::spawn(async move {
+			let mut stream = TcpStream::connect(address).await.unwrap();
+			let request = "POST /node1 HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n6\r\nline 1\r\n7\r\n\nline 2\r\n1\r\n\n\r\n0\r\n\r\n";
+			stream.write_all(request.as_bytes()).await.unwrap();
+			let mut response = String::new();
+			stream.read_to_string(&mut response).await.unwrap();
+			response
+		});
+
+		let (stream, _peer) = listener.accept().await.unwrap();
+		handle_connection(stream, "127.0.0.1", &ListenerNodes::default(), &tx).await.unwrap();
+		assert!(client.await.unwrap().starts_with("HTTP/1.1 200 OK"));
+
+		let mut lines = Vec::new();
+		while let Ok(RemoteEvent::Line { logfile, line }) = rx.try_recv() {
+			assert_eq!(logfile, "http:127.0.0.1/node1");
+			lines.push(line);
+		}
+		assert_eq!(lines, vec!["line 1", "line 2"]);
+	}
+	#[tokio::test]
+	async fn it_drops_senders_of_overlong_lines_and_chunks() {
+		let long_line = format!("vdash-node: node1\nline 1\n{}\n", "x".repeat(MAX_LINE_BYTES + 1));
+		let huge_chunk = String::from("POST /node1 HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\nffffffff\r\n");
+		for request in [long_line, huge_chunk] {
+			let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+			let address = listener.local_addr().unwrap();
+			let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+
+			let client = tokio::spawn(async move {
+				let mut stream = TcpStream::connect(address).await.unwrap();
+				// The listener may close the connection before all is written
+				let _ = stream.write_all(request.as_bytes()).await;
+			});
+
+			let (stream, _peer) = listener.accept().await.unwrap();
+			let error = handle_connection(stream, "127.0.0.1", &ListenerNodes::default(), &tx).await.unwrap_err();
+			assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+			client.await.unwrap();
+
+			// Only lines within the limit were sent
+			while let Ok(RemoteEvent::Line { line, .. }) = rx.try_recv() {
+				assert_eq!(line, "line 1");
+			}
+		}
+	}
+}
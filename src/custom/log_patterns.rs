@@ -0,0 +1,174 @@
+//! Literal text recognised in node log messages
+//!
+//! The patterns are compiled once into an Aho-Corasick automaton shared by
+//! all monitors, so each line is scanned once to find which of them it
+//! contains, rather than searched again for each pattern in turn. What is
+//! done with a line containing each pattern is given by TIMED_DATA_RULES and
+//! STATE_RULES (see NodeMetrics::process_logfile_entry()).
+//!
+//! The automaton also holds the literal text which must be present for each
+//! --custom-metric regex to match, so that only the regexes which might match
+//! a line are run on it. A regex without such text is run on every line.
+use std::sync::LazyLock;
+
+use aho_corasick::AhoCorasick;
+
+use super::custom_metrics::{CustomMetric, CUSTOM_METRICS};
+
+/// Each pattern, in the same order as PATTERNS
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LogPattern {
+	RecordRetrieved,
+	RecordWritten,
+	SpendPut,
+	RegisterEdited,
+	StorageCost,
+	Payment,
+	PeersInRoutingTable,
+	Shunned,
+	NodePid,
+	EventsChannelClosed,
+	PaymentQuote,
+	Metrics,
+	WalletBalance,
+}
+
+const PATTERNS: [(LogPattern, &str); 13] = [
+	(LogPattern::RecordRetrieved, "Retrieved record from disk"),
+	(LogPattern::RecordWritten, "Wrote record"),
+	(LogPattern::SpendPut, "ValidSpendRecordPutFromNetwork"),
+	(LogPattern::RegisterEdited, "Editing Register success"),
+	(LogPattern::StorageCost, "Cost is now"),
+	(LogPattern::Payment, "Total payment of"),
+	(LogPattern::PeersInRoutingTable, "PeersInRoutingTable"),
+	(LogPattern::Shunned, "consider us as BAD"),
+	(LogPattern::NodePid, "Node (PID: "),
+	(LogPattern::EventsChannelClosed, "Node events channel closed"),
+	(LogPattern::PaymentQuote, "Created payment quote for"),
+	(LogPattern::Metrics, "ant_logging::metrics"),
+	(LogPattern::WalletBalance, "The new wallet balance is"),
+];
+
+impl LogPattern {
+	/// Every pattern, in the order of PATTERNS
+	pub fn all() -> impl Iterator<Item = LogPattern> {
+		PATTERNS.iter().map(|(pattern, _)| *pattern)
+	}
+}
+
+/// What finding each text in the automaton means
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Found {
+	Pattern(LogPattern),
+	CustomMetric(usize), // Index in CUSTOM_METRICS
+}
+
+struct PatternMatcher {
+	automaton: AhoCorasick,
+	found: Vec<Found>,                     // By automaton pattern ID
+	unfiltered_custom_metrics: Vec<usize>, // Custom metrics to try on every line
+}
+
+impl PatternMatcher {
+	fn new(custom_metrics: &[CustomMetric]) -> PatternMatcher {
+		let mut texts: Vec<Vec<u8>> = PATTERNS.iter().map(|(_, text)| text.as_bytes().to_vec()).collect();
+		let mut found: Vec<Found> = PATTERNS.iter().map(|(pattern, _)| Found::Pattern(*pattern)).collect();
+		let mut unfiltered_custom_metrics = Vec::new();
+		for (index, metric) in custom_metrics.iter().enumerate() {
+			match metric.required_literals() {
+				Some(literals) => {
+					for literal in literals {
+						texts.push(literal);
+						found.push(Found::CustomMetric(index));
+					}
+				}
+				None => unfiltered_custom_metrics.push(index),
+			}
+		}
+
+		PatternMatcher {
+			automaton: AhoCorasick::new(texts).expect("The log patterns failed to compile. This is a bug."),
+			found,
+			unfiltered_custom_metrics,
+		}
+	}
+
+	fn patterns_of(&self, line: &str) -> LinePatterns {
+		let mut patterns = LinePatterns {
+			found: 0,
+			custom_metrics: self.unfiltered_custom_metrics.clone(),
+		};
+		for found in self.automaton.find_overlapping_iter(line) {
+			match self.found[found.pattern().as_usize()] {
+				Found::Pattern(pattern) => patterns.found |= 1 << pattern as u32,
+				Found::CustomMetric(index) => patterns.custom_metrics.push(index),
+			}
+		}
+		patterns.custom_metrics.sort_unstable();
+		patterns.custom_metrics.dedup();
+		patterns
+	}
+}
+
+static PATTERN_MATCHER: LazyLock<PatternMatcher> = LazyLock::new(|| PatternMatcher::new(&CUSTOM_METRICS));
+
+/// The patterns contained in a line
+pub struct LinePatterns {
+	found: u32, // Bit for each LogPattern
+	custom_metrics: Vec<usize>,
+}
+
+impl LinePatterns {
+	pub fn of(line: &str) -> LinePatterns {
+		PATTERN_MATCHER.patterns_of(line)
+	}
+
+	pub fn contains(&self, pattern: LogPattern) -> bool {
+		self.found & (1 << pattern as u32) != 0
+	}
+
+	/// Indexes in CUSTOM_METRICS of those which might match the line
+	pub fn custom_metrics(&self) -> &[usize] {
+		&self.custom_metrics
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::custom::custom_metrics::parse_custom_metrics;
+
+	#[test]
+	fn it_finds_each_pattern_in_a_line() {
+		for (index, (pattern, text)) in PATTERNS.iter().enumerate() {
+			assert_eq!(*pattern as usize, index, "PATTERNS is not in the order of LogPattern");
+			let line = format!("[2024-03-23T19:38:32.350118Z INFO ant_node] ({}) 42", text);
+			let patterns = LinePatterns::of(&line);
+			assert!(patterns.contains(*pattern));
+			let others = PATTERNS.iter().filter(|(other, _)| patterns.contains(*other)).count();
+			assert_eq!(others, 1, "'{}' matched more than one pattern", text);
+		}
+		assert!(!LinePatterns::of("Node (PID 1234)").contains(LogPattern::NodePid));
+	}
+
+	#[test]
+	fn it_finds_custom_metrics_which_might_match() {
+		let metrics = parse_custom_metrics(&[
+			String::from("Replicated:cumulative=Replicated (\\d+) records"),
+			String::from("Close group:mmm=(?:close|far) group size is ([\\d,]+)"),
+			String::from("Digits=\\d+ peers"),
+		])
+		.unwrap();
+		let matcher = PatternMatcher::new(&metrics);
+
+		let line = "[2024-03-23T19:38:32.350118Z INFO ant_node] Replicated 12 records, Wrote record";
+		let patterns = matcher.patterns_of(line);
+		assert!(patterns.contains(LogPattern::RecordWritten));
+		assert_eq!(patterns.custom_metrics(), &[0, 2]);
+
+		// Found more than once, or only in part
+		let patterns = matcher.patterns_of("far group size is 3, close group size is 5 Replicated");
+		assert_eq!(patterns.custom_metrics(), &[1, 2]);
+		assert_eq!(matcher.patterns_of("nothing to see").custom_metrics(), &[2]);
+	}
+}
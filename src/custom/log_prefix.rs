@@ -0,0 +1,187 @@
+//! Fast decoding of the start of each logfile line
+//!
+//! Every line read is decoded, which with big logfiles dominates startup, so
+//! rather than a regex and chrono's general purpose parser a line such as:
+//!
+//!   "[2024-03-23T19:00:00.123456Z INFO ant_node::node] Message text"
+//!
+//! is split by hand, and its timestamp parsed by a TimestampParser which
+//! reuses the date and time to the minute from the previous line, since
+//! consecutive lines nearly always share them. Timestamps in other forms
+//! fall back to DateTime::parse_from_str().
+//!
+//! This depends only on chrono so that benches/decode_metadata.rs can include it.
+use chrono::{DateTime, Duration, FixedOffset, NaiveDateTime, Offset, ParseError, Utc};
+
+/// Characters in a timestamp such as "2024-03-23T19:00:00.123456Z"
+const TIME_STRING_CHARS: usize = 27;
+
+/// The parts of a logfile line, borrowed from it
+#[derive(Debug, PartialEq)]
+pub struct LinePrefix<'a> {
+	pub time_string: &'a str,
+	pub category: &'a str,
+	pub source: &'a str,
+	pub message: &'a str,
+}
+
+/// Split a line of the form "[<time> <CATEGORY> <source>] <message>", where
+/// the time is TIME_STRING_CHARS without a space and the category is four to
+/// six capital letters. Anything before the first '[' which starts such a
+/// prefix is ignored.
+pub fn split_log_line(line: &str) -> Option<LinePrefix<'_>> {
+	let mut search_from = 0;
+	while let Some(offset) = line[search_from..].find('[') {
+		let start = search_from + offset + 1;
+		if let Some(prefix) = split_after_bracket(&line[start..]) {
+			return Some(prefix);
+		}
+		search_from = start;
+	}
+	None
+}
+
+fn split_after_bracket(text: &str) -> Option<LinePrefix<'_>> {
+	let (time_end, _) = text.char_indices().nth(TIME_STRING_CHARS)?;
+	let time_string = &text[..time_end];
+	if time_string.contains(' ') {
+		return None;
+	}
+	let text = text[time_end..].strip_prefix(' ')?;
+
+	let category_len = text.bytes().take_while(u8::is_ascii_uppercase).count();
+	if !(4..=6).contains(&category_len) {
+		return None;
+	}
+	let (category, text) = text.split_at(category_len);
+	let text = text.strip_prefix(' ')?;
+
+	let source_end = text.find(']')?;
+	let source = &text[..source_end];
+	let text = text[source_end..].strip_prefix("] ")?;
+	let message = text.split('\n').next().unwrap_or("");
+
+	Some(LinePrefix {
+		time_string,
+		category,
+		source,
+		message,
+	})
+}
+
+/// Parses timestamps, remembering the minute of the last so that only the
+/// seconds need parsing for the lines which follow it
+#[derive(Default)]
+pub struct TimestampParser {
+	minute: Option<(String, DateTime<Utc>)>, // "2024-03-23T19:00" and its time
+}
+
+impl TimestampParser {
+	/// Parse an RFC 3339 timestamp, the same as DateTime::parse_from_str(time_string, "%+")
+	pub fn parse(&mut self, time_string: &str) -> Result<DateTime<FixedOffset>, ParseError> {
+		match self.parse_utc(time_string) {
+			Some(time) => Ok(time.with_timezone(&Utc.fix())),
+			None => DateTime::parse_from_str(time_string, "%+"),
+		}
+	}
+
+	/// Parse "YYYY-MM-DDTHH:MM:SS[.fraction]Z", or None if in any other form
+	fn parse_utc(&mut self, time_string: &str) -> Option<DateTime<Utc>> {
+		let bytes = time_string.as_bytes();
+		if bytes.len() < 20 || bytes[16] != b':' || bytes.last() != Some(&b'Z') {
+			return None;
+		}
+		let seconds = two_digits(&bytes[17..19]).filter(|seconds| *seconds < 60)?;
+		let nanoseconds = match &bytes[19..bytes.len() - 1] {
+			[] => 0,
+			[b'.', fraction @ ..] if (1..=9).contains(&fraction.len()) => {
+				let mut nanoseconds = 0;
+				for digit in fraction {
+					if !digit.is_ascii_digit() {
+						return None;
+					}
+					nanoseconds = nanoseconds * 10 + (digit - b'0') as i64;
+				}
+				nanoseconds * 10_i64.pow(9 - fraction.len() as u32)
+			}
+			_ => return None,
+		};
+
+		let minute_string = &time_string[..16];
+		let minute = match &self.minute {
+			Some((cached_string, minute)) if cached_string == minute_string => *minute,
+			_ => {
+				let minute = NaiveDateTime::parse_from_str(minute_string, "%Y-%m-%dT%H:%M").ok()?.and_utc();
+				self.minute = Some((minute_string.to_string(), minute));
+				minute
+			}
+		};
+		Some(minute + Duration::seconds(seconds) + Duration::nanoseconds(nanoseconds))
+	}
+}
+
+fn two_digits(bytes: &[u8]) -> Option<i64> {
+	match bytes {
+		[tens, units] if tens.is_ascii_digit() && units.is_ascii_digit() => {
+			Some(((tens - b'0') * 10 + (units - b'0')) as i64)
+		}
+		_ => None,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn it_splits_log_lines() {
+		let line = "[2024-03-23T19:00:00.123456Z INFO ant_node::node] Wrote record [abc] ok";
+		assert_eq!(
+			split_log_line(line),
+			Some(LinePrefix {
+				time_string: "2024-03-23T19:00:00.123456Z",
+				category: "INFO",
+				source: "ant_node::node",
+				message: "Wrote record [abc] ok",
+			})
+		);
+
+		// A '[' before the prefix is skipped
+		let prefixed = "node1 [x] [2024-03-23T19:00:00.123456Z ERROR ant_node] Failed";
+		assert_eq!(split_log_line(prefixed).map(|prefix| prefix.category), Some("ERROR"));
+
+		for not_a_prefix in [
+			"",
+			"  continued",
+			"[2024-03-23T19:00:00.123Z INFO ant_node] Short timestamp",
+			"[2024-03-23T19:00:00.123456Z Info ant_node] Lower case category",
+			"[2024-03-23T19:00:00.123456Z WARNING ant_node] Long category",
+			"[2024-03-23T19:00:00.123456Z INFO ant_node]No space after source",
+		] {
+			assert_eq!(split_log_line(not_a_prefix), None, "{}", not_a_prefix);
+		}
+	}
+
+	#[test]
+	fn it_parses_timestamps_as_chrono_does() {
+		let mut parser = TimestampParser::default();
+		for time_string in [
+			"2024-03-23T19:00:00.123456Z",
+			"2024-03-23T19:00:59.999999Z",
+			"2024-03-23T19:01:00.5Z",
+			"2024-03-23T19:01:07Z",
+			"2024-03-23T19:01:07.123456789Z",
+			"2024-03-23T20:01:07.123+01:00",
+			"2024-12-31T23:59:59.000001Z",
+		] {
+			assert_eq!(
+				parser.parse(time_string),
+				DateTime::parse_from_str(time_string, "%+"),
+				"{}",
+				time_string
+			);
+		}
+		assert!(parser.parse("2024-03-23T19:00:00.12345xZ").is_err());
+		assert!(parser.parse("not a time").is_err());
+	}
+}
@@ -7,11 +7,15 @@ use serde::{Serialize, Deserialize};
 use serde_json;
 use chrono::{DateTime, Utc};
 
-use super::app::{LogMonitor, NodeMetrics};
+use super::node_metrics::{LogMonitor, NodeMetrics};
 
 const CHECKPOINT_EXT: &str = "vdash";
 const CHECKPOINT_TMP_EXT: &str = "vdash-tmp";
 
+/// Version of the checkpoint format, increased when LogfileCheckpoint or
+/// NodeMetrics change shape. Checkpoints without a version are version 0.
+pub const CHECKPOINT_VERSION: u32 = 1;
+
 pub fn save_checkpoint(monitor: &mut LogMonitor) -> Result<String, Error> {
     let mut checkpoint_tmp_path = PathBuf::from(&monitor.logfile);
     if !checkpoint_tmp_path.set_extension(CHECKPOINT_TMP_EXT) {
@@ -44,7 +48,8 @@ pub fn save_checkpoint(monitor: &mut LogMonitor) -> Result<String, Error> {
 }
 
 /// Look for and attempt to update metrics from a checkpoint
-/// Returns Ok() if the checkpoint was found and restored
+/// Returns Ok() if the checkpoint was found and restored, or an error of kind
+/// InvalidData if it exists but can't be used
 pub fn restore_checkpoint(monitor: &mut LogMonitor) -> Result<String, Error> {
     let mut checkpoint_path = PathBuf::from(&monitor.logfile);
     if !checkpoint_path.set_extension(CHECKPOINT_EXT) {
@@ -56,12 +61,9 @@ pub fn restore_checkpoint(monitor: &mut LogMonitor) -> Result<String, Error> {
 
     match fs::read_to_string(&checkpoint_path) {
         Ok(checkpoint_string) => {
-            match serde_json::from_str(checkpoint_string.as_str()) {
+            match parse_checkpoint(&checkpoint_string) {
                 Ok(checkpoint) => monitor.from_checkpoint(&checkpoint),
-
-                // TODO could be versioning issue (e.g. any change in serialized structs)
-                // TODO maybe report so user can delete invalid checkpoint file
-                Err(e) => return Err(Error::new(ErrorKind::Other, e.to_string())),
+                Err(e) => return Err(Error::new(ErrorKind::InvalidData, format!("checkpoint {:?} {}", checkpoint_path.as_os_str(), e))),
             };
         },
         // Suppress console error message when checkpoint file is not found
@@ -71,8 +73,52 @@ pub fn restore_checkpoint(monitor: &mut LogMonitor) -> Result<String, Error> {
     Ok(format!("checkpoint restored from: {:?}", checkpoint_path.as_os_str()))
 }
 
+/// The path of the checkpoint for a logfile
+pub fn checkpoint_path(logfile: &str) -> PathBuf {
+    let mut checkpoint_path = PathBuf::from(logfile);
+    checkpoint_path.set_extension(CHECKPOINT_EXT);
+    checkpoint_path
+}
+
+/// Parse a checkpoint, migrating it from an earlier version if necessary
+fn parse_checkpoint(checkpoint_string: &str) -> Result<LogfileCheckpoint, String> {
+    let value: serde_json::Value = serde_json::from_str(checkpoint_string).map_err(|e| format!("is unreadable ({})", e))?;
+    let version = value.get("version").and_then(|version| version.as_u64()).unwrap_or(0) as u32;
+    if version > CHECKPOINT_VERSION {
+        return Err(format!("was saved by a newer vdash (version {})", version));
+    }
+
+    match serde_json::from_str::<LogfileCheckpoint>(checkpoint_string) {
+        Ok(checkpoint) => Ok(checkpoint),
+        Err(e) if version < CHECKPOINT_VERSION => migrate_checkpoint(value).map_err(|_| format!("could not be migrated from version {} ({})", version, e)),
+        Err(e) => Err(format!("is incompatible ({})", e)),
+    }
+}
+
+/// Best-effort migration of a checkpoint saved by an earlier version of vdash,
+/// using default values for any fields it lacks
+fn migrate_checkpoint(mut value: serde_json::Value) -> Result<LogfileCheckpoint, serde_json::Error> {
+    fn add_missing(value: &mut serde_json::Value, defaults: &serde_json::Value) {
+        if let (Some(object), Some(default_object)) = (value.as_object_mut(), defaults.as_object()) {
+            for (key, default_value) in default_object {
+                match object.get_mut(key) {
+                    Some(existing) => add_missing(existing, default_value),
+                    None => { object.insert(key.clone(), default_value.clone()); },
+                }
+            }
+        }
+    }
+
+    add_missing(&mut value, &serde_json::to_value(LogfileCheckpoint::new())?);
+    let mut checkpoint: LogfileCheckpoint = serde_json::from_value(value)?;
+    checkpoint.version = CHECKPOINT_VERSION;
+    Ok(checkpoint)
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LogfileCheckpoint {
+    #[serde(default)]
+    pub version: u32,
     pub latest_entry_time: Option<DateTime<Utc>>,
     pub monitor_index: usize,
     pub monitor_metrics: NodeMetrics,
@@ -81,9 +127,44 @@ pub struct LogfileCheckpoint {
 impl LogfileCheckpoint {
     pub fn new() -> LogfileCheckpoint {
         LogfileCheckpoint {
+            version: CHECKPOINT_VERSION,
             latest_entry_time: None,
             monitor_index: 0,
             monitor_metrics: NodeMetrics::new(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_migrates_or_rejects_older_checkpoints() {
+        let mut value = serde_json::to_value(LogfileCheckpoint::new()).unwrap();
+        value.as_object_mut().unwrap().remove("version");
+        let metrics = value["monitor_metrics"].as_object_mut().unwrap();
+        metrics.remove("records_stored");
+        metrics.insert(String::from("removed_since"), serde_json::json!(1));
+        metrics["node_peer_id"] = serde_json::json!("12D3KooWTest");
+
+        let checkpoint = parse_checkpoint(&value.to_string()).unwrap();
+        assert_eq!(checkpoint.version, CHECKPOINT_VERSION);
+        assert_eq!(checkpoint.monitor_metrics.node_peer_id, Some(String::from("12D3KooWTest")));
+
+        value["version"] = serde_json::json!(CHECKPOINT_VERSION + 1);
+        assert!(parse_checkpoint(&value.to_string()).unwrap_err().contains("newer vdash"));
+        value["version"] = serde_json::json!(0);
+        value["monitor_metrics"]["records_stored"] = serde_json::json!("not a number");
+        assert!(parse_checkpoint(&value.to_string()).unwrap_err().contains("could not be migrated"));
+        assert!(parse_checkpoint("{").unwrap_err().contains("unreadable"));
+    }
+
+    #[test]
+    fn it_keeps_amounts_above_u64() {
+        let mut checkpoint = LogfileCheckpoint::new();
+        checkpoint.monitor_metrics.attos_earned.total = u64::MAX as u128 * 10;
+        let checkpoint = parse_checkpoint(&serde_json::to_string(&checkpoint).unwrap()).unwrap();
+        assert_eq!(checkpoint.monitor_metrics.attos_earned.total, u64::MAX as u128 * 10);
+    }
+}
@@ -0,0 +1,78 @@
+//! Shortened logfile names for display
+//!
+//! Logfiles found with deep glob paths share a long leading directory, which
+//! makes titles unreadable. Shortened names replace the directory shared by
+//! every logfile with '...', so "/var/antctl/services/antnode42/logs/antnode.log"
+//! is shown as ".../antnode42/logs/antnode.log". The full path is shown in
+//! the status bar.
+
+/// Parts of a path at the end of each name which are always kept, so a node's
+/// directory is shown even when there is only one logfile
+const KEEP_COMPONENTS: usize = 3;
+
+/// Shortened names replace this with ELLIPSIS
+const ELLIPSIS: &str = ".../";
+
+/// The leading directory, ending in '/', shared by all the logfiles and not
+/// including any of their last KEEP_COMPONENTS components. Empty if there is none.
+pub fn common_logfile_prefix(logfiles: &[String]) -> String {
+	let Some(first) = logfiles.first() else {
+		return String::new();
+	};
+	let mut prefix_len = first.len();
+	for logfile in logfiles {
+		let common = first
+			.bytes()
+			.zip(logfile.bytes())
+			.take_while(|(a, b)| a == b)
+			.count();
+		prefix_len = prefix_len.min(common).min(kept_start(logfile));
+	}
+	// End on a separator so that whole directories are left out (and the prefix is valid UTF-8)
+	match first.as_bytes()[..prefix_len].iter().rposition(|byte| *byte == b'/') {
+		Some(separator) => first[..=separator].to_string(),
+		None => String::new(),
+	}
+}
+
+/// Start of the last KEEP_COMPONENTS components of a path
+fn kept_start(logfile: &str) -> usize {
+	logfile
+		.rmatch_indices('/')
+		.nth(KEEP_COMPONENTS - 1)
+		.map_or(0, |(separator, _)| separator + 1)
+}
+
+/// The logfile without prefix, if that makes it shorter
+pub fn short_logfile_name(logfile: &str, prefix: &str) -> String {
+	match logfile.strip_prefix(prefix) {
+		Some(rest) if prefix.len() > ELLIPSIS.len() => format!("{}{}", ELLIPSIS, rest),
+		_ => logfile.to_string(),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn it_shortens_logfile_names_to_what_differs() {
+		let logfiles = vec![
+			String::from("/var/antctl/services/antnode1/logs/antnode.log"),
+			String::from("/var/antctl/services/antnode42/logs/antnode.log"),
+		];
+		let prefix = common_logfile_prefix(&logfiles);
+		assert_eq!(prefix, "/var/antctl/services/");
+		assert_eq!(short_logfile_name(&logfiles[1], &prefix), ".../antnode42/logs/antnode.log");
+
+		// The last components are kept when there is only one logfile
+		let prefix = common_logfile_prefix(&logfiles[..1]);
+		assert_eq!(prefix, "/var/antctl/services/");
+
+		let logfiles = vec![String::from("/home/a/node/antnode.log"), String::from("host:/home/b/antnode.log")];
+		assert_eq!(common_logfile_prefix(&logfiles), "");
+		assert_eq!(short_logfile_name(&logfiles[0], "/h/"), "/home/a/node/antnode.log");
+		assert_eq!(common_logfile_prefix(&[]), "");
+		assert_eq!(common_logfile_prefix(&[String::from("antnode.log")]), "");
+	}
+}
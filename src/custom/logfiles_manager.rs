@@ -1,15 +1,44 @@
 use linemux::MuxedLines;
 use std::collections::HashMap;
-use glob::glob;
+use glob::{glob, Pattern};
+
+use chrono::{DateTime, Duration, Utc};
 
 use crate::custom::app::{LogMonitor, DashState};
 
+const INCOMPATIBLE_CHECKPOINT_MESSAGE_S: i64 = 30;
+const REATTACH_BACKOFF_MIN_S: i64 = 5;      // Delay before first attempt to re-attach a logfile after an error
+const REATTACH_BACKOFF_MAX_S: i64 = 300;    // Delay doubles after each failed attempt up to this
+
+/// A logfile which stopped being followed after an error, to be re-attached
+pub struct Reattach {
+    pub attempts: u32,
+    pub next_attempt: DateTime<Utc>,
+}
+
+impl Reattach {
+    pub fn new() -> Reattach {
+        Reattach { attempts: 0, next_attempt: Utc::now() + Duration::seconds(REATTACH_BACKOFF_MIN_S) }
+    }
+
+    /// Schedule the next attempt after one has failed
+    pub fn backoff(&mut self) {
+        self.attempts += 1;
+        let delay_s = REATTACH_BACKOFF_MIN_S.saturating_mul(1 << self.attempts.min(16)).min(REATTACH_BACKOFF_MAX_S);
+        self.next_attempt = Utc::now() + Duration::seconds(delay_s);
+    }
+}
+
 pub struct LogfilesManager {
     pub logfiles_added: Vec<String>,
     pub globpaths: Vec<String>,
+    pub snapshot_globpaths: Vec<Pattern>,  // Logfiles updated by snapshot (e.g. rsync) rather than live
 
     pub logfiles_monitored: Vec<String>,    // Paths to all logfiles being monitored
     pub logfiles_failed: Vec<String>,       // Paths to any files which failed to begin monitoring
+    pub incompatible_checkpoints: Vec<String>, // Logfiles whose checkpoint couldn't be restored
+    pub logfiles_reattaching: HashMap<String, Reattach>, // Logfiles which failed while being followed
+    pub logfiles_removed: Vec<String>,      // Removed by the user so not added again by a re-scan
 
     pub linemux_files: MuxedLines,
 }
@@ -17,19 +46,23 @@ pub struct LogfilesManager {
 // TODO maybe add UI for display of lists (paths/globpaths/failed paths)
 // TODO maybe add UI for adding paths/globpaths interactively
 impl LogfilesManager {
-    pub fn new(globpaths: Vec<String>) -> LogfilesManager {
+    pub fn new(globpaths: Vec<String>, snapshot_globpaths: Vec<String>) -> std::io::Result<LogfilesManager> {
         match MuxedLines::new() {
-            Ok(linemux) => return LogfilesManager {
+            Ok(linemux) => return Ok(LogfilesManager {
                 logfiles_added: Vec::new(),
                 globpaths: globpaths,
+                snapshot_globpaths: snapshot_globpaths.iter().filter_map(|g| Pattern::new(g).ok()).collect(),
 
                 logfiles_monitored: Vec::new(),
                 logfiles_failed: Vec::new(),
+                incompatible_checkpoints: Vec::new(),
+                logfiles_reattaching: HashMap::new(),
+                logfiles_removed: Vec::new(),
 
                 linemux_files: linemux,
-            },
+            }),
 
-            Err(e) => panic!("Initialisation failed at MuxedLines::new(): {}", e)
+            Err(e) => Err(std::io::Error::new(e.kind(), format!("Unable to follow logfiles, MuxedLines::new() failed: {}", e))),
         }
     }
 
@@ -47,9 +80,14 @@ impl LogfilesManager {
         }
     }
 
+    /// True if the logfile is matched by a --snapshot-glob-path
+    pub fn is_snapshot_path(&self, fullpath: &str) -> bool {
+        self.snapshot_globpaths.iter().any(|pattern| pattern.matches(fullpath))
+    }
+
     // Attempts to setup a LogMonitor for the logfile at fullpath
     pub async fn monitor_path(&mut self, fullpath: &String, monitors: &mut HashMap<String, LogMonitor>, dash_state: &mut DashState, disable_status: bool) {
-        if self.logfiles_added.contains(&fullpath) {
+        if self.logfiles_added.contains(&fullpath) || self.logfiles_removed.contains(&fullpath) {
             return;
         }
 
@@ -59,6 +97,9 @@ impl LogfilesManager {
 
         let checkpoint_result = super::logfile_checkpoints::restore_checkpoint(&mut monitor);
 
+        // Set after restoring the checkpoint as the flag isn't saved with the metrics
+        monitor.metrics.is_snapshot_source = self.is_snapshot_path(fullpath);
+
         let checkpoint_was_restored = match checkpoint_result {
             Ok(message) => {
                 if message.len() > 0 {
@@ -66,10 +107,17 @@ impl LogfilesManager {
                 };
                 true
             },
+            Err(e) if e.kind() == std::io::ErrorKind::InvalidData => {
+                // Metrics are rebuilt from the whole logfile, but offer to delete the checkpoint
+                if !self.incompatible_checkpoints.contains(fullpath) { self.incompatible_checkpoints.push(fullpath.to_string()); }
+                let message = format!("{}, rebuilding from the logfile. Press 'D' to delete incompatible checkpoints", e);
+                dash_state.vdash_status.message(&message, Some(chrono::Duration::seconds(INCOMPATIBLE_CHECKPOINT_MESSAGE_S)));
+                false
+            }
             Err(e) => {
                 let message = &e.to_string();
                 if message.len() > 0 && !disable_status { dash_state.vdash_status.message(&format!("{}", message), None); }
-                false   // TODO note: do I need to handle version errors in some way? (due to change in serialised struct)
+                false
             }
         };
 
@@ -103,8 +151,8 @@ impl LogfilesManager {
             }
             Err(e) => {
                 if !self.logfiles_failed.contains(&fullpath) { self.logfiles_failed.push(fullpath.to_string()); }
-                eprintln!("...load failed: {}", e);
-                eprintln!( "Note: it is ok for the file not to exist, but the file's parent directory must exist." );
+                let message = format!("...load failed: {} (it is ok for the file not to exist, but the file's parent directory must exist)", e);
+                dash_state.vdash_status.message(&message, None);
             }
         }
     }
@@ -113,18 +161,126 @@ impl LogfilesManager {
     pub async fn scan_globpath(&mut self, globpath: String, monitors: &mut HashMap<String, LogMonitor>, dash_state: &mut DashState, disable_status: bool) {
         if !disable_status { dash_state.vdash_status.message(&format!("globpath: {}", globpath), None); }
 
-        let paths_to_scan = globpath.clone();
+        let paths = match glob(&globpath) {
+            Ok(paths) => paths,
+            Err(e) => {
+                dash_state.vdash_status.message(&format!("Invalid glob path '{}': {}", globpath, e), None);
+                return;
+            }
+        };
         if !self.globpaths.contains(&globpath) { self.globpaths.push(globpath) }
 
-        for entry in glob(paths_to_scan.as_str()).unwrap() {
+        for entry in paths {
             match entry {
                 Ok(path) => {
                     if let Some(filepath) = path.to_str() {
                         self.monitor_path(&filepath.to_string(), monitors, dash_state, disable_status).await
                     }
                 },
-                Err(e) => eprintln!("...globpath failed: {}", e),
+                Err(e) => dash_state.vdash_status.message(&format!("...globpath failed: {}", e), None),
+            }
+        }
+    }
+
+    /// Stop tracking a logfile whose node has been removed. Lines may still arrive
+    /// for it, as linemux can't stop following a file.
+    pub fn forget_logfile(&mut self, logfile: &str, removed_by_user: bool) {
+        self.logfiles_added.retain(|added| added != logfile);
+        self.logfiles_monitored.retain(|monitored| monitored != logfile);
+        self.logfiles_failed.retain(|failed| failed != logfile);
+        self.incompatible_checkpoints.retain(|incompatible| incompatible != logfile);
+        self.logfiles_reattaching.remove(logfile);
+        if removed_by_user && !self.logfiles_removed.iter().any(|removed| removed == logfile) {
+            self.logfiles_removed.push(logfile.to_string());
+        }
+    }
+
+    /// Handle an error from following logfiles. linemux doesn't say which logfile failed so
+    /// each is checked, and those which can no longer be opened are shown with the error in
+    /// their status and scheduled to be re-attached. Returns the number of logfiles affected.
+    pub fn handle_tail_error(&mut self, error: &std::io::Error, monitors: &mut HashMap<String, LogMonitor>, dash_state: &mut DashState) -> usize {
+        let mut failed = 0;
+        for (logfile, monitor) in monitors.iter_mut() {
+            if !monitor.is_node() || monitor.is_remote || self.logfiles_reattaching.contains_key(logfile) {
+                continue;
+            }
+            if let Err(e) = std::fs::File::open(logfile) {
+                monitor.metrics.tail_error = Some(e.to_string());
+                monitor.metrics.update_node_status_string();
+                self.logfiles_reattaching.insert(logfile.clone(), Reattach::new());
+                failed += 1;
             }
         }
+
+        let message = match failed {
+            0 => format!("Logfile error: {}", error),
+            _ => format!("Logfile error: {} ({} logfiles will be re-attached when readable)", error, failed),
+        };
+        dash_state.vdash_status.message(&message, None);
+        failed
     }
-}
\ No newline at end of file
+
+    /// Attempt to re-attach any logfiles which are due, backing off after each failure
+    pub async fn reattach_logfiles(&mut self, monitors: &mut HashMap<String, LogMonitor>, dash_state: &mut DashState) {
+        let now = Utc::now();
+        let due: Vec<String> = self.logfiles_reattaching.iter()
+            .filter(|(_, reattach)| reattach.next_attempt <= now)
+            .map(|(logfile, _)| logfile.clone())
+            .collect();
+
+        for logfile in due {
+            let result = match std::fs::File::open(&logfile) {
+                Ok(_) => self.linemux_files.add_file(&logfile).await.map(|_| ()),
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(()) => {
+                    self.logfiles_reattaching.remove(&logfile);
+                    if let Some(monitor) = monitors.get_mut(&logfile) {
+                        monitor.metrics.tail_error = None;
+                        monitor.metrics.update_node_status_string();
+                    }
+                    dash_state.vdash_status.message(&format!("Re-attached logfile: {}", logfile), None);
+                }
+                Err(e) => {
+                    if let Some(monitor) = monitors.get_mut(&logfile) {
+                        monitor.metrics.tail_error = Some(e.to_string());
+                        monitor.metrics.update_node_status_string();
+                    }
+                    if let Some(reattach) = self.logfiles_reattaching.get_mut(&logfile) {
+                        reattach.backoff();
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_backs_off_reattach_attempts() {
+        let mut reattach = Reattach::new();
+        reattach.backoff();
+        assert_eq!(reattach.attempts, 1);
+        let first_delay = reattach.next_attempt - Utc::now();
+        assert!(first_delay > Duration::seconds(REATTACH_BACKOFF_MIN_S) && first_delay <= Duration::seconds(2 * REATTACH_BACKOFF_MIN_S));
+
+        for _ in 0..40 {
+            reattach.backoff();
+        }
+        assert!(reattach.next_attempt - Utc::now() <= Duration::seconds(REATTACH_BACKOFF_MAX_S));
+    }
+
+    #[tokio::test]
+    async fn it_reports_an_invalid_glob_path() {
+        let mut logfiles_manager = LogfilesManager::new(Vec::new(), Vec::new()).unwrap();
+        let mut monitors = HashMap::new();
+        let mut dash_state = DashState::new();
+        logfiles_manager.scan_globpath("[".to_string(), &mut monitors, &mut dash_state, true).await;
+        assert!(dash_state.vdash_status.get_status().starts_with("Invalid glob path '['"));
+        assert!(logfiles_manager.globpaths.is_empty());
+    }
+}
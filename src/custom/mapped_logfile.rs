@@ -0,0 +1,94 @@
+//! Memory mapped reading of large logfiles when they are first loaded (see --mmap)
+//!
+//! A logfile of several GB takes a long time to read through a BufReader,
+//! even reusing one line buffer. With --mmap a logfile of at least
+//! MMAP_MIN_BYTES is instead mapped into memory and its lines borrowed from
+//! the mapping, so only lines which are not valid UTF-8 need a copy (with the
+//! invalid bytes replaced).
+//!
+//! Only the length of the logfile when it is opened is mapped, so lines
+//! appended while it loads are left for linemux to follow. But if a logfile is
+//! truncated while mapped (for example by logrotate with 'copytruncate'),
+//! reading the missing part raises SIGBUS and ends vdash. That can't be ruled
+//! out for logfiles vdash doesn't control, so mapping is only done when asked.
+use std::borrow::Cow;
+use std::fs::File;
+
+use memmap2::Mmap;
+
+/// Smaller logfiles are read as quickly without mapping them
+pub const MMAP_MIN_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Map a logfile which is large enough to benefit, or None to read it normally.
+/// Only for use with --mmap, by which the user says their logfiles are not
+/// truncated while vdash loads them.
+pub fn map_logfile(file: &File) -> std::io::Result<Option<Mmap>> {
+	if file.metadata()?.len() < MMAP_MIN_BYTES {
+		return Ok(None);
+	}
+	// Safety: the mapping is only read, and is dropped once the logfile has loaded.
+	// A mapped file changed by another process is undefined behaviour in Rust's
+	// terms, but appending to a logfile leaves the mapped bytes as they were, and
+	// the one change which doesn't (truncation) is excluded by the user giving --mmap.
+	let mapped = unsafe { Mmap::map(file)? };
+	#[cfg(unix)]
+	let _ = mapped.advise(memmap2::Advice::Sequential);
+	Ok(Some(mapped))
+}
+
+/// A line without its line ending, with any bytes which are not valid UTF-8
+/// replaced, and whether there were any
+pub fn lossy_line(bytes: &[u8]) -> (Cow<'_, str>, bool) {
+	let bytes = bytes.strip_suffix(b"\n").unwrap_or(bytes);
+	let bytes = bytes.strip_suffix(b"\r").unwrap_or(bytes);
+	let line = String::from_utf8_lossy(bytes);
+	let is_invalid = matches!(line, Cow::Owned(_));
+	(line, is_invalid)
+}
+
+/// The lines of a logfile's contents, as given by lossy_line()
+pub struct LossyLines<'a> {
+	remaining: &'a [u8],
+}
+
+impl<'a> LossyLines<'a> {
+	pub fn new(contents: &'a [u8]) -> LossyLines<'a> {
+		LossyLines { remaining: contents }
+	}
+}
+
+impl<'a> Iterator for LossyLines<'a> {
+	type Item = (Cow<'a, str>, bool);
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.remaining.is_empty() {
+			return None;
+		}
+		let end = self.remaining.iter().position(|byte| *byte == b'\n').map_or(self.remaining.len(), |newline| newline + 1);
+		let (line, remaining) = self.remaining.split_at(end);
+		self.remaining = remaining;
+		Some(lossy_line(line))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn it_splits_contents_into_lossy_lines() {
+		let contents = b"first\r\nsecond \xff line\n\nlast";
+		let lines: Vec<(Cow<str>, bool)> = LossyLines::new(contents).collect();
+		assert_eq!(
+			lines,
+			vec![
+				(Cow::Borrowed("first"), false),
+				(Cow::Owned(String::from("second \u{FFFD} line")), true),
+				(Cow::Borrowed(""), false),
+				(Cow::Borrowed("last"), false),
+			]
+		);
+		assert!(matches!(lines[0].0, Cow::Borrowed(_)));
+		assert_eq!(LossyLines::new(b"").count(), 0);
+	}
+}
@@ -0,0 +1,260 @@
+//! History of node metrics and events in an SQLite database (see --db)
+//!
+//! Every --db-interval seconds a sample of each node's metrics is added to the
+//! samples table, and each tick any events (as for --events-out) are added to
+//! the events table, so that history can be analysed long after it has left
+//! the in-memory timelines. For example:
+//!
+//!   SELECT node, max(CAST(attos_earned AS REAL)) - min(CAST(attos_earned AS REAL)) FROM samples
+//!     WHERE time > strftime('%Y-%m-%dT%H:%M:%S', 'now', '-7 days') GROUP BY node;
+//!
+//! Amounts in attos are TEXT (decimal digits), as an SQLite INTEGER holds only
+//! about 9.2 ANT. Casting them to REAL keeps about 15 significant digits.
+//!
+//! Rows are sent to a writer thread which adds whatever has arrived in one
+//! transaction, so the UI never waits for the disk.
+use std::collections::HashMap;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread::JoinHandle;
+
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::{params, Connection};
+use serde_json::Value;
+
+use super::background_results;
+use super::app::{node_status_as_string, LogMonitor};
+use super::events_out::EventsOut;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS samples (
+	time TEXT NOT NULL,
+	node INTEGER NOT NULL,
+	logfile TEXT NOT NULL,
+	peer_id TEXT,
+	status TEXT NOT NULL,
+	attos_earned TEXT NOT NULL,
+	storage_cost TEXT NOT NULL,
+	records_stored INTEGER NOT NULL,
+	puts INTEGER NOT NULL,
+	gets INTEGER NOT NULL,
+	errors INTEGER NOT NULL,
+	peers INTEGER NOT NULL,
+	memory_mb INTEGER NOT NULL,
+	cpu_percent REAL NOT NULL
+);
+CREATE INDEX IF NOT EXISTS samples_by_logfile ON samples (logfile, time);
+CREATE TABLE IF NOT EXISTS events (
+	time TEXT NOT NULL,
+	node INTEGER NOT NULL,
+	logfile TEXT NOT NULL,
+	event TEXT NOT NULL,
+	details TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS events_by_time ON events (time);
+";
+
+/// One row of the samples table
+#[derive(Clone, Debug)]
+pub struct NodeSample {
+	pub time: DateTime<Utc>,
+	pub node: usize,
+	pub logfile: String,
+	pub peer_id: Option<String>,
+	pub status: String,
+	pub attos_earned: u128,
+	pub storage_cost: u128,
+	pub records_stored: u64,
+	pub puts: u64,
+	pub gets: u64,
+	pub errors: u64,
+	pub peers: u64,
+	pub memory_mb: u64,
+	pub cpu_percent: f32,
+}
+
+impl NodeSample {
+	pub fn of(monitor: &LogMonitor, logfile: &str, time: &DateTime<Utc>) -> NodeSample {
+		let metrics = &monitor.metrics;
+		NodeSample {
+			time: *time,
+			node: monitor.index + 1,
+			logfile: logfile.to_string(),
+			peer_id: metrics.node_peer_id.clone(),
+			status: node_status_as_string(&metrics.node_status),
+			attos_earned: metrics.attos_earned.total,
+			storage_cost: metrics.storage_cost.most_recent,
+			records_stored: metrics.records_stored,
+			puts: metrics.activity_puts.total,
+			gets: metrics.activity_gets.total,
+			errors: metrics.activity_errors.total,
+			peers: metrics.peers_connected.most_recent,
+			memory_mb: metrics.memory_used_mb.most_recent,
+			cpu_percent: metrics.cpu_usage_percent,
+		}
+	}
+}
+
+/// Rows to add in one transaction
+#[derive(Default)]
+struct Batch {
+	samples: Vec<NodeSample>,
+	events: Vec<Value>,
+}
+
+pub struct MetricsDb {
+	pub path: String,
+	interval: Duration,
+	next_sample: Option<DateTime<Utc>>,
+	events: EventsOut,
+	sender: Option<Sender<Batch>>,
+	writer: Option<JoinHandle<()>>,
+}
+
+impl MetricsDb {
+	/// Open or create the database at path, and start the thread which writes to it
+	pub fn open(path: &str, interval: Duration) -> Result<MetricsDb, String> {
+		let connection = Connection::open(path)
+			.and_then(|connection| connection.execute_batch(SCHEMA).map(|_| connection))
+			.map_err(|e| format!("unable to open database {}: {}", path, e))?;
+		let (sender, receiver) = channel::<Batch>();
+		let db_path = path.to_string();
+		let writer = std::thread::spawn(move || write_batches(connection, receiver, &db_path));
+		Ok(MetricsDb {
+			path: path.to_string(),
+			interval,
+			next_sample: None,
+			events: EventsOut::new(path),
+			sender: Some(sender),
+			writer: Some(writer),
+		})
+	}
+
+	/// Send any new events, and a sample of every node when one is due, to be written
+	pub fn record(&mut self, monitors: &HashMap<String, LogMonitor>, now: &DateTime<Utc>) {
+		let mut batch = Batch {
+			samples: Vec::new(),
+			events: self.events.take_events(monitors, now),
+		};
+		if self.next_sample.map_or(true, |next_sample| *now >= next_sample) {
+			self.next_sample = Some(*now + self.interval);
+			batch.samples = monitors
+				.iter()
+				.filter(|(_logfile, monitor)| monitor.is_node())
+				.map(|(logfile, monitor)| NodeSample::of(monitor, logfile, now))
+				.collect();
+			batch.samples.sort_by_key(|sample| sample.node);
+		}
+		if batch.samples.is_empty() && batch.events.is_empty() {
+			return;
+		}
+		if let Some(sender) = &self.sender {
+			let _ = sender.send(batch);
+		}
+	}
+}
+
+impl Drop for MetricsDb {
+	/// Finish writing what has been sent before vdash exits
+	fn drop(&mut self) {
+		self.sender = None;
+		if let Some(writer) = self.writer.take() {
+			let _ = writer.join();
+		}
+	}
+}
+
+/// Write each batch received, along with any others waiting, until the sender is dropped
+fn write_batches(mut connection: Connection, receiver: Receiver<Batch>, path: &str) {
+	let mut failing = false;
+	while let Ok(batch) = receiver.recv() {
+		let mut batches = vec![batch];
+		batches.extend(receiver.try_iter());
+		match write_batch(&mut connection, &batches) {
+			Ok(()) => failing = false,
+			// Report a failure once rather than every tick
+			Err(e) if !failing => {
+				failing = true;
+				background_results::push(format!("Failed to write to database {}: {}", path, e));
+			}
+			Err(_) => {}
+		}
+	}
+}
+
+fn write_batch(connection: &mut Connection, batches: &[Batch]) -> rusqlite::Result<()> {
+	let transaction = connection.transaction()?;
+	{
+		let mut insert_sample = transaction.prepare_cached(
+			"INSERT INTO samples (time, node, logfile, peer_id, status, attos_earned, storage_cost, records_stored,
+				puts, gets, errors, peers, memory_mb, cpu_percent)
+				VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+		)?;
+		let mut insert_event = transaction
+			.prepare_cached("INSERT INTO events (time, node, logfile, event, details) VALUES (?1, ?2, ?3, ?4, ?5)")?;
+		for batch in batches {
+			for sample in &batch.samples {
+				insert_sample.execute(params![
+					sample.time.to_rfc3339(),
+					sample.node as i64,
+					sample.logfile,
+					sample.peer_id,
+					sample.status,
+					sample.attos_earned.to_string(),
+					sample.storage_cost.to_string(),
+					sample.records_stored as i64,
+					sample.puts as i64,
+					sample.gets as i64,
+					sample.errors as i64,
+					sample.peers as i64,
+					sample.memory_mb as i64,
+					sample.cpu_percent as f64,
+				])?;
+			}
+			for event in &batch.events {
+				insert_event.execute(params![
+					event["time"].as_str().unwrap_or_default(),
+					event["node"].as_i64().unwrap_or_default(),
+					event["logfile"].as_str().unwrap_or_default(),
+					event["event"].as_str().unwrap_or_default(),
+					event.to_string(),
+				])?;
+			}
+		}
+	}
+	transaction.commit()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn it_writes_samples_and_events_in_the_background() {
+		let dir = tempfile::TempDir::new().unwrap();
+		let path = dir.path().join("vdash.db").to_string_lossy().to_string();
+		let now = Utc::now();
+		let mut monitors = HashMap::new();
+		let mut monitor = LogMonitor::new(String::from("node1/antnode.log"));
+		monitor.metrics.attos_earned.total = 20_000_000_000_000_000_000; // More than an INTEGER holds
+		monitors.insert(String::from("node1/antnode.log"), monitor);
+
+		let mut db = MetricsDb::open(&path, Duration::seconds(60)).unwrap();
+		db.record(&monitors, &now);
+		db.record(&monitors, &(now + Duration::seconds(1)));
+		monitors.get_mut("node1/antnode.log").unwrap().metrics.earnings_ledger.record_payment(&now, 50, None);
+		db.record(&monitors, &(now + Duration::seconds(61)));
+		drop(db);
+
+		let connection = Connection::open(&path).unwrap();
+		let count = |table: &str| -> i64 {
+			connection.query_row(&format!("SELECT count(*) FROM {}", table), [], |row| row.get(0)).unwrap()
+		};
+		assert_eq!(count("samples"), 2);
+		assert_eq!(count("events"), 1);
+		let attos: String = connection.query_row("SELECT attos_earned FROM samples LIMIT 1", [], |row| row.get(0)).unwrap();
+		assert_eq!(attos, "20000000000000000000");
+		assert!(!background_results::take().iter().any(|result| result.contains("database")));
+
+		assert!(MetricsDb::open(&dir.path().join("missing").join("vdash.db").to_string_lossy(), Duration::seconds(60)).is_err());
+	}
+}
@@ -0,0 +1,182 @@
+//! Push node metrics to a time-series database over UDP (see --metrics-push)
+//!
+//! Each tick every node's metrics are sent as InfluxDB line protocol, e.g.
+//!
+//!   vdash_node,node=1,peer_id=12D3KooW... earnings=1200,gets=52i,... 1711222712000000000
+//!
+//! or as statsd gauges (e.g. vdash.node1.gets:52|g), so the nodes vdash
+//! monitors can feed existing dashboards (InfluxDB, Telegraf, Graphite etc.)
+//! without running a separate agent. Lines are packed into datagrams small
+//! enough not to be fragmented.
+use std::collections::HashMap;
+use std::net::UdpSocket;
+
+use chrono::{DateTime, Utc};
+
+use super::app::LogMonitor;
+
+/// Largest datagram sent, to avoid fragmentation on typical networks
+const MAX_DATAGRAM: usize = 1400;
+
+/// Fields which are amounts in attos. An InfluxDB integer is an i64, which holds
+/// only about 9.2 ANT, so these are sent as floats rather than capped.
+const AMOUNT_FIELDS: [&str; 2] = ["earnings", "storage_cost"];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PushFormat {
+	Influx,
+	Statsd,
+}
+
+impl PushFormat {
+	pub fn parse(name: &str) -> Result<PushFormat, String> {
+		match name.to_lowercase().as_str() {
+			"influx" => Ok(PushFormat::Influx),
+			"statsd" => Ok(PushFormat::Statsd),
+			_ => Err(format!("unknown --metrics-push-format '{}' (use influx or statsd)", name)),
+		}
+	}
+}
+
+pub struct MetricsPush {
+	pub target: String,
+	format: PushFormat,
+	socket: UdpSocket,
+	failing: bool,
+}
+
+impl MetricsPush {
+	/// Prepare to send to target, a host:port
+	pub fn new(target: &str, format: PushFormat) -> Result<MetricsPush, String> {
+		let socket = UdpSocket::bind("0.0.0.0:0")
+			.and_then(|socket| socket.connect(target).map(|_| socket))
+			.and_then(|socket| socket.set_nonblocking(true).map(|_| socket))
+			.map_err(|e| format!("unable to push metrics to {}: {}", target, e))?;
+		Ok(MetricsPush {
+			target: target.to_string(),
+			format,
+			socket,
+			failing: false,
+		})
+	}
+
+	/// Send the metrics of every node. Returns an error the first time sending fails,
+	/// but not again until it has succeeded.
+	pub fn push(&mut self, monitors: &HashMap<String, LogMonitor>, now: &DateTime<Utc>) -> Result<(), String> {
+		let lines = metric_lines(monitors, now, self.format);
+		for datagram in datagrams(&lines) {
+			if let Err(e) = self.socket.send(datagram.as_bytes()) {
+				let first_failure = !self.failing;
+				self.failing = true;
+				return if first_failure { Err(format!("Failed to push metrics to {}: {}", self.target, e)) } else { Ok(()) };
+			}
+		}
+		self.failing = false;
+		Ok(())
+	}
+}
+
+/// The lines to send for the metrics of every node
+pub fn metric_lines(monitors: &HashMap<String, LogMonitor>, now: &DateTime<Utc>, format: PushFormat) -> Vec<String> {
+	let mut nodes: Vec<&LogMonitor> = monitors.values().filter(|monitor| monitor.is_node()).collect();
+	nodes.sort_by_key(|monitor| monitor.index);
+
+	let mut lines = Vec::<String>::new();
+	for monitor in nodes {
+		let metrics = &monitor.metrics;
+		let fields = [
+			("earnings", metrics.attos_earned.total),
+			("storage_cost", metrics.storage_cost.most_recent),
+			("records", metrics.records_stored.into()),
+			("puts", metrics.activity_puts.total.into()),
+			("gets", metrics.activity_gets.total.into()),
+			("errors", metrics.activity_errors.total.into()),
+			("peers", metrics.peers_connected.most_recent.into()),
+			("memory_mb", metrics.memory_used_mb.most_recent.into()),
+			("active", metrics.is_node_active() as u128),
+		];
+		match format {
+			PushFormat::Influx => {
+				let mut tags = format!("node={}", monitor.index + 1);
+				if let Some(peer_id) = &metrics.node_peer_id {
+					tags += &format!(",peer_id={}", escape_tag(peer_id));
+				}
+				tags += &format!(",logfile={}", escape_tag(&monitor.logfile));
+				let values: Vec<String> = fields
+					.iter()
+					.map(|(name, value)| match AMOUNT_FIELDS.contains(name) {
+						true => format!("{}={}", name, *value as f64),
+						false => format!("{}={}i", name, value),
+					})
+					.collect();
+				let nanos = now.timestamp_nanos_opt().unwrap_or_default();
+				lines.push(format!("vdash_node,{} {} {}", tags, values.join(","), nanos));
+			}
+			PushFormat::Statsd => {
+				for (name, value) in fields {
+					lines.push(format!("vdash.node{}.{}:{}|g", monitor.index + 1, name, value));
+				}
+			}
+		}
+	}
+	lines
+}
+
+/// Escape the characters which are special in an InfluxDB tag value
+fn escape_tag(value: &str) -> String {
+	value.replace('\\', "\\\\").replace(',', "\\,").replace('=', "\\=").replace(' ', "\\ ")
+}
+
+/// Lines joined into datagrams of at most MAX_DATAGRAM bytes (unless one line is longer)
+fn datagrams(lines: &[String]) -> Vec<String> {
+	let mut datagrams = Vec::<String>::new();
+	let mut datagram = String::new();
+	for line in lines {
+		if !datagram.is_empty() && datagram.len() + line.len() + 1 > MAX_DATAGRAM {
+			datagrams.push(std::mem::take(&mut datagram));
+		}
+		datagram += line;
+		datagram.push('\n');
+	}
+	if !datagram.is_empty() {
+		datagrams.push(datagram);
+	}
+	datagrams
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn it_pushes_lines_for_each_node() {
+		let now: DateTime<Utc> = "2024-03-23T19:38:32Z".parse().unwrap();
+		let mut monitor = LogMonitor::new(String::from("/var/log/node 1/antnode.log"));
+		monitor.metrics.activity_gets.total = 52;
+		monitor.metrics.attos_earned.total = 20_000_000_000_000_000_000; // 20 ANT, more than an i64 holds
+		monitor.metrics.node_peer_id = Some(String::from("12D3KooWTest"));
+		let monitors = HashMap::from([(monitor.logfile.clone(), monitor)]);
+
+		let lines = metric_lines(&monitors, &now, PushFormat::Influx);
+		assert_eq!(lines.len(), 1);
+		assert!(lines[0].starts_with("vdash_node,node=1,peer_id=12D3KooWTest,logfile=/var/log/node\\ 1/antnode.log earnings=20000000000000000000,"));
+		assert!(lines[0].contains(",gets=52i,"));
+		assert!(lines[0].ends_with(" 1711222712000000000"));
+
+		let lines = metric_lines(&monitors, &now, PushFormat::Statsd);
+		assert!(lines.contains(&String::from("vdash.node1.gets:52|g")));
+		assert!(lines.contains(&String::from("vdash.node1.earnings:20000000000000000000|g")));
+
+		let long_lines = vec![String::from("x").repeat(1000); 3];
+		assert_eq!(datagrams(&long_lines).len(), 3);
+		assert_eq!(datagrams(&lines).len(), 1);
+		assert!(PushFormat::parse("graphite").is_err());
+
+		let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+		let mut metrics_push = MetricsPush::new(&receiver.local_addr().unwrap().to_string(), PushFormat::Statsd).unwrap();
+		metrics_push.push(&monitors, &now).unwrap();
+		let mut buffer = [0u8; MAX_DATAGRAM];
+		let received = receiver.recv(&mut buffer).unwrap();
+		assert!(String::from_utf8_lossy(&buffer[..received]).contains("vdash.node1.gets:52|g\n"));
+	}
+}
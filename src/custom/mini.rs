@@ -0,0 +1,197 @@
+//! --mini output, a line or a few in place of the dashboard
+//!
+//! For a tmux or screen status bar, or a small pane beside other work. The
+//! first line has the totals for all nodes and each other line one active
+//! node, rotating through them every MINI_ROTATE_S seconds.
+//!
+//! On a terminal the lines are rewritten in place, without the alternate
+//! screen or raw mode, so Ctrl-C stops vdash as usual. Otherwise, as when run
+//! by tmux's #(vdash --mini), each change is printed as new lines, of which
+//! tmux shows the last.
+use std::io::{IsTerminal, Stdout, Write};
+
+use chrono::{DateTime, Utc};
+use crossterm::{
+	cursor, queue,
+	style::Print,
+	terminal::{self, Clear, ClearType},
+};
+
+use super::app::{App, DashState};
+use super::ui::monetary_string;
+
+/// Seconds each active node is shown before the next
+pub const MINI_ROTATE_S: i64 = 5;
+
+const SEPARATOR: &str = " │ ";
+
+/// The lines to show for app at now
+pub fn mini_lines(app: &App, line_count: usize, now: &DateTime<Utc>) -> Vec<String> {
+	let today = now.date_naive();
+	let mut node_count = 0;
+	let mut attos_today: u128 = 0;
+	let mut errors: u64 = 0;
+	let mut active_nodes = Vec::<(usize, String)>::new();
+	for monitor in app.monitors.values().filter(|monitor| monitor.is_node()) {
+		let metrics = &monitor.metrics;
+		node_count += 1;
+		attos_today += metrics
+			.earnings_ledger
+			.payments()
+			.filter(|entry| entry.time.date_naive() == today)
+			.map(|entry| entry.attos)
+			.sum::<u128>();
+		errors += metrics.activity_errors.total;
+		if metrics.is_node_active() {
+			let node = format!(
+				"#{} {} {} peers {} records",
+				monitor.index + 1,
+				metrics.node_status_string,
+				metrics.peers_connected.most_recent,
+				metrics.records_stored
+			);
+			active_nodes.push((monitor.index, node));
+		}
+	}
+	active_nodes.sort_by_key(|(index, _)| *index);
+
+	let totals = format!(
+		"vdash {}/{} active{}today {}{}errors {}",
+		active_nodes.len(),
+		node_count,
+		SEPARATOR,
+		earnings_text(&app.dash_state, attos_today),
+		SEPARATOR,
+		errors
+	);
+	let nodes: Vec<String> = active_nodes.into_iter().map(|(_, node)| node).collect();
+	let rotation = (now.timestamp() / MINI_ROTATE_S) as usize;
+	format_mini_lines(totals, &nodes, line_count, rotation)
+}
+
+/// Earnings in currency when the dashboard is showing it, or else in the token unit of the nodes
+fn earnings_text(dash_state: &DashState, attos: u128) -> String {
+	if dash_state.ui_uses_currency && dash_state.currency_per_token.is_some() {
+		monetary_string(dash_state, attos)
+	} else {
+		format!("{} {}", monetary_string(dash_state, attos), dash_state.token_unit.name())
+	}
+}
+
+/// Always line_count lines (at least one), so that those written before are all overwritten
+fn format_mini_lines(totals: String, nodes: &[String], line_count: usize, rotation: usize) -> Vec<String> {
+	if line_count <= 1 {
+		if nodes.is_empty() {
+			return vec![totals];
+		}
+		return vec![format!("{}{}{}", totals, SEPARATOR, nodes[rotation % nodes.len()])];
+	}
+
+	let mut lines = vec![totals];
+	let shown = nodes.len().min(line_count - 1);
+	if shown > 0 {
+		let first = rotation * shown % nodes.len();
+		lines.extend((0..shown).map(|i| nodes[(first + i) % nodes.len()].clone()));
+	}
+	lines.resize(line_count, String::new());
+	lines
+}
+
+/// Writes the lines when they change, in place if out is a terminal
+pub struct MiniWriter<W: Write> {
+	out: W,
+	is_terminal: bool,
+	last_lines: Vec<String>,
+}
+
+impl MiniWriter<Stdout> {
+	pub fn stdout() -> MiniWriter<Stdout> {
+		let out = std::io::stdout();
+		let is_terminal = out.is_terminal();
+		MiniWriter::new(out, is_terminal)
+	}
+}
+
+impl<W: Write> MiniWriter<W> {
+	pub fn new(out: W, is_terminal: bool) -> MiniWriter<W> {
+		MiniWriter {
+			out,
+			is_terminal,
+			last_lines: Vec::new(),
+		}
+	}
+
+	pub fn write(&mut self, lines: Vec<String>) -> std::io::Result<()> {
+		if lines == self.last_lines {
+			return Ok(());
+		}
+
+		if self.is_terminal {
+			// Lines which wrap would leave the cursor below where the next write starts
+			let width = terminal::size().map_or(usize::MAX, |(columns, _)| columns as usize);
+			if self.last_lines.len() > 1 {
+				queue!(self.out, cursor::MoveUp((self.last_lines.len() - 1) as u16))?;
+			}
+			for (i, line) in lines.iter().enumerate() {
+				if i > 0 {
+					queue!(self.out, Print("\r\n"))?;
+				}
+				let line: String = line.chars().take(width.saturating_sub(1)).collect();
+				queue!(self.out, cursor::MoveToColumn(0), Clear(ClearType::CurrentLine), Print(line))?;
+			}
+		} else {
+			for line in lines.iter() {
+				writeln!(self.out, "{}", line)?;
+			}
+		}
+		self.out.flush()?;
+		self.last_lines = lines;
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn nodes(count: usize) -> Vec<String> {
+		(1..=count).map(|n| format!("#{}", n)).collect()
+	}
+
+	#[test]
+	fn it_rotates_through_active_nodes() {
+		let totals = String::from("totals");
+		assert_eq!(format_mini_lines(totals.clone(), &nodes(3), 1, 0), vec!["totals │ #1"]);
+		assert_eq!(format_mini_lines(totals.clone(), &nodes(3), 1, 4), vec!["totals │ #2"]);
+		assert_eq!(format_mini_lines(totals.clone(), &nodes(0), 1, 4), vec!["totals"]);
+
+		// Each line a different node, moving on by a page of them
+		assert_eq!(format_mini_lines(totals.clone(), &nodes(5), 3, 0), vec!["totals", "#1", "#2"]);
+		assert_eq!(format_mini_lines(totals.clone(), &nodes(5), 3, 1), vec!["totals", "#3", "#4"]);
+		assert_eq!(format_mini_lines(totals.clone(), &nodes(5), 3, 2), vec!["totals", "#5", "#1"]);
+
+		// Padded so that every line written before is overwritten
+		assert_eq!(format_mini_lines(totals, &nodes(1), 3, 7), vec!["totals", "#1", ""]);
+	}
+
+	#[test]
+	fn it_writes_only_changes() {
+		let mut output = Vec::<u8>::new();
+		{
+			let mut writer = MiniWriter::new(&mut output, false);
+			writer.write(vec![String::from("one")]).unwrap();
+			writer.write(vec![String::from("one")]).unwrap();
+			writer.write(vec![String::from("two")]).unwrap();
+		}
+		assert_eq!(String::from_utf8(output).unwrap(), "one\ntwo\n");
+
+		let mut output = Vec::<u8>::new();
+		{
+			let mut writer = MiniWriter::new(&mut output, true);
+			writer.write(vec![String::from("one"), String::from("two")]).unwrap();
+			writer.write(vec![String::from("one"), String::from("three")]).unwrap();
+		}
+		let output = String::from_utf8(output).unwrap();
+		assert!(output.contains("two") && output.ends_with("three"));
+	}
+}
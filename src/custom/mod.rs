@@ -1,15 +1,121 @@
-pub mod app;
 pub mod app_timelines;
+pub mod averages;
+pub mod cell_changes;
+pub mod config_file;
+pub mod custom_metrics;
+pub mod debug_lines;
+pub mod disk_space;
+pub mod earnings_ledger;
+pub mod error_categories;
+pub mod fleet_history;
+pub mod generic_logs;
+pub mod log_patterns;
+pub mod log_prefix;
 pub mod logfile_checkpoints;
-pub mod logfiles_manager;
+pub mod mapped_logfile;
+pub mod node_filter;
+pub mod node_groups;
+pub mod node_metrics;
 pub mod opt;
+pub mod parse_pipeline;
+pub mod parser_stats;
+pub mod percentiles;
+pub mod process_metrics;
+pub mod profile;
+pub mod replay;
+pub mod resource_envelope;
+pub mod restart_history;
+pub mod session;
+pub mod store_size;
+#[cfg(test)]
+mod test_support;
 pub mod timelines;
+pub mod token_units;
+pub mod top_messages;
 pub mod web_requests;
+pub mod wallet_watch;
+
+// The dashboard, which the vdash binary needs and other users of the library don't
+#[cfg(feature = "tui")]
+pub mod anomalies;
+#[cfg(feature = "tui")]
+pub mod antctl;
+#[cfg(feature = "tui")]
+pub mod app;
+#[cfg(feature = "tui")]
+pub mod background_results;
+#[cfg(feature = "tui")]
+pub mod clipboard;
+#[cfg(feature = "tui")]
+pub mod command_palette;
+#[cfg(feature = "tui")]
+pub mod control_socket;
+#[cfg(feature = "tui")]
+pub mod efficiency;
+#[cfg(feature = "tui")]
+pub mod events_out;
+#[cfg(feature = "tui")]
+pub mod fleet_map;
+#[cfg(feature = "tui")]
+pub mod frame_limiter;
+#[cfg(feature = "tui")]
+pub mod journal_logs;
+#[cfg(feature = "tui")]
+pub mod keymap;
+#[cfg(feature = "tui")]
+pub mod log_listener;
+#[cfg(feature = "tui")]
+pub mod logfile_names;
+#[cfg(feature = "tui")]
+pub mod logfiles_manager;
+#[cfg(feature = "tui")]
+pub mod metrics_db;
+#[cfg(feature = "tui")]
+pub mod metrics_push;
+#[cfg(feature = "tui")]
+pub mod mini;
+#[cfg(feature = "tui")]
+pub mod node_jump;
+#[cfg(feature = "tui")]
+pub mod number_format;
+#[cfg(feature = "tui")]
+pub mod preferences;
+#[cfg(feature = "tui")]
+pub mod remote_logs;
+#[cfg(feature = "tui")]
+pub mod setup_wizard;
+#[cfg(feature = "tui")]
+pub mod tax_report;
+#[cfg(feature = "tui")]
+pub mod terminal_guard;
+#[cfg(feature = "tui")]
+pub mod timeline_window;
+#[cfg(feature = "tui")]
+pub mod triggers;
+#[cfg(feature = "tui")]
 pub mod ui;
+#[cfg(feature = "tui")]
 pub mod ui_debug;
+#[cfg(feature = "tui")]
+pub mod ui_earnings;
+#[cfg(feature = "tui")]
+pub mod ui_fleet;
+#[cfg(feature = "tui")]
 pub mod ui_help;
+#[cfg(feature = "tui")]
 pub mod ui_keyboard;
+#[cfg(feature = "tui")]
 pub mod ui_node;
+#[cfg(feature = "tui")]
+pub mod ui_summary_breakdown;
+#[cfg(feature = "tui")]
 pub mod ui_summary_table;
+#[cfg(feature = "tui")]
 pub mod ui_summary;
+#[cfg(feature = "tui")]
 pub mod ui_status;
+#[cfg(feature = "tui")]
+pub mod ui_status_bar;
+
+#[cfg(all(test, feature = "tui"))]
+mod tui_tests;
@@ -0,0 +1,313 @@
+//! Node tags and filter expressions
+//!
+//! Nodes are tagged using --tag or at runtime with '#', and the filter prompt
+//! ('/') restricts the Summary table and totals to nodes matching an expression
+//! such as 'tag:home AND status:connected'. Terms are combined with AND, OR
+//! and NOT, with AND taking precedence over OR. Supported terms:
+//!
+//!   tag:<tag>         node has the tag
+//!   status:<text>     node status contains text (e.g. status:shunned)
+//!   version:<text>    running version contains text
+//!   node:<number>     node number as shown in the Summary
+//!   name:<text>       logfile path contains text
+//!   <text>            logfile path contains text
+//!
+//! Text terms can also be written with '=' or '!=' (e.g. status=INACTIVE), or
+//! as '<field> contains <text>'. Numeric Summary columns are compared with =,
+//! !=, <, <=, > or >=, as earnings (in attos), cost, records, puts, gets,
+//! errors, peers or ram (e.g. errors>0 or earnings=0).
+//!
+//! Text matching ignores case.
+use super::node_metrics::LogMonitor;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Term {
+	Tag(String),
+	Status(String),
+	Version(String),
+	Node(usize),
+	Logfile(String),
+	Compare(Field, Comparison, u128),
+}
+
+/// Numeric Summary columns which can be compared
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Field {
+	Earnings,
+	StoreCost,
+	Records,
+	Puts,
+	Gets,
+	Errors,
+	Peers,
+	Ram,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Comparison {
+	Equal,
+	NotEqual,
+	Less,
+	LessOrEqual,
+	Greater,
+	GreaterOrEqual,
+}
+
+/// Operators in the order they are looked for, so that "<=" is found before "<"
+const COMPARISONS: &[(&str, Comparison)] = &[
+	("!=", Comparison::NotEqual),
+	("<=", Comparison::LessOrEqual),
+	(">=", Comparison::GreaterOrEqual),
+	("=", Comparison::Equal),
+	("<", Comparison::Less),
+	(">", Comparison::Greater),
+	(":", Comparison::Equal),
+];
+
+const TEXT_FIELDS: &[&str] = &["tag", "status", "version", "node", "name"];
+
+#[derive(Clone, Debug, PartialEq)]
+struct Condition {
+	negated: bool,
+	term: Term,
+}
+
+#[derive(Clone, Debug)]
+pub struct NodeFilter {
+	pub expression: String,
+	any_of: Vec<Vec<Condition>>, // OR of ANDs
+}
+
+impl NodeFilter {
+	pub fn parse(expression: &str) -> Result<NodeFilter, String> {
+		let mut any_of = vec![Vec::<Condition>::new()];
+		let mut negated = false;
+		let mut expect_term = true;
+
+		for word in words(expression) {
+			let word = word.as_str();
+			match word.to_ascii_uppercase().as_str() {
+				"AND" if !expect_term => expect_term = true,
+				"OR" if !expect_term => {
+					any_of.push(Vec::new());
+					expect_term = true;
+				}
+				"NOT" => {
+					negated = !negated;
+					expect_term = true;
+				}
+				"AND" | "OR" => return Err(format!("unexpected '{}'", word)),
+				_ => {
+					// Adjacent terms are combined with AND
+					let (term, term_negated) = parse_term(word)?;
+					any_of.last_mut().unwrap().push(Condition {
+						negated: negated != term_negated,
+						term,
+					});
+					negated = false;
+					expect_term = false;
+				}
+			}
+		}
+
+		if expect_term && !expression.trim().is_empty() {
+			return Err(String::from("expression is incomplete"));
+		}
+		Ok(NodeFilter {
+			expression: expression.trim().to_string(),
+			any_of,
+		})
+	}
+
+	pub fn matches(&self, monitor: &LogMonitor) -> bool {
+		self.any_of.iter().any(|all_of| {
+			all_of
+				.iter()
+				.all(|condition| condition.negated != term_matches(&condition.term, monitor))
+		})
+	}
+}
+
+/// Words of an expression, with '<field> contains <text>' joined into one as
+/// '<field>:<text>' and quotes removed from around text
+fn words(expression: &str) -> Vec<String> {
+	let mut words = Vec::<String>::new();
+	let mut split = expression.split_whitespace().peekable();
+	while let Some(word) = split.next() {
+		let is_text_field = TEXT_FIELDS.contains(&word.to_lowercase().as_str());
+		if is_text_field && split.peek().is_some_and(|next| next.eq_ignore_ascii_case("contains")) {
+			split.next();
+			if let Some(text) = split.next() {
+				words.push(format!("{}:{}", word, text.trim_matches('"')));
+				continue;
+			}
+			words.push(word.to_string());
+			words.push(String::from("contains"));
+			continue;
+		}
+		words.push(word.to_string());
+	}
+	words
+}
+
+/// A term, and whether it is negated (by '!=')
+fn parse_term(word: &str) -> Result<(Term, bool), String> {
+	let lowercase = word.to_lowercase();
+	let operator = COMPARISONS
+		.iter()
+		.filter_map(|(operator, comparison)| lowercase.find(operator).map(|at| (at, *operator, *comparison)))
+		// The first in the word, and of those the longest
+		.min_by_key(|(at, operator, _)| (*at, std::cmp::Reverse(operator.len())));
+	let Some((at, operator, comparison)) = operator else {
+		return Ok((Term::Logfile(lowercase), false));
+	};
+	let name = &lowercase[..at];
+	let value = lowercase[at + operator.len()..].trim_matches('"').to_string();
+
+	if let Some(field) = parse_field(name) {
+		let number = value
+			.parse::<u128>()
+			.map_err(|_| format!("{} must be compared with a number, not '{}'", name, value))?;
+		return Ok((Term::Compare(field, comparison, number), false));
+	}
+
+	let negated = match comparison {
+		Comparison::Equal => false,
+		Comparison::NotEqual => true,
+		_ if TEXT_FIELDS.contains(&name) => return Err(format!("{} can only be matched with : = or !=", name)),
+		_ => return Ok((Term::Logfile(lowercase), false)),
+	};
+	let term = match name {
+		"tag" => Term::Tag(value),
+		"status" => Term::Status(value),
+		"version" => Term::Version(value),
+		"name" => Term::Logfile(value),
+		"node" => Term::Node(
+			value
+				.parse::<usize>()
+				.map_err(|_| format!("invalid node number '{}'", value))?,
+		),
+		_ => return Ok((Term::Logfile(lowercase), false)),
+	};
+	Ok((term, negated))
+}
+
+fn parse_field(name: &str) -> Option<Field> {
+	match name {
+		"earnings" => Some(Field::Earnings),
+		"cost" | "storecost" => Some(Field::StoreCost),
+		"records" => Some(Field::Records),
+		"puts" => Some(Field::Puts),
+		"gets" => Some(Field::Gets),
+		"errors" => Some(Field::Errors),
+		"peers" => Some(Field::Peers),
+		"ram" | "memory" => Some(Field::Ram),
+		_ => None,
+	}
+}
+
+fn field_value(field: Field, monitor: &LogMonitor) -> u128 {
+	let metrics = &monitor.metrics;
+	match field {
+		Field::Earnings => metrics.attos_earned.total,
+		Field::StoreCost => metrics.storage_cost.most_recent,
+		Field::Records => metrics.records_stored.into(),
+		Field::Puts => metrics.activity_puts.total.into(),
+		Field::Gets => metrics.activity_gets.total.into(),
+		Field::Errors => metrics.activity_errors.total.into(),
+		Field::Peers => metrics.peers_connected.most_recent.into(),
+		Field::Ram => metrics.memory_used_mb.most_recent.into(),
+	}
+}
+
+fn term_matches(term: &Term, monitor: &LogMonitor) -> bool {
+	match term {
+		Term::Tag(tag) => monitor.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)),
+		Term::Status(status) => monitor.metrics.node_status_string.to_lowercase().contains(status),
+		Term::Version(version) => monitor
+			.metrics
+			.running_version
+			.as_ref()
+			.is_some_and(|v| v.to_lowercase().contains(version)),
+		Term::Node(number) => monitor.index + 1 == *number,
+		Term::Logfile(text) => monitor.logfile.to_lowercase().contains(text),
+		Term::Compare(field, comparison, number) => {
+			let value = field_value(*field, monitor);
+			match comparison {
+				Comparison::Equal => value == *number,
+				Comparison::NotEqual => value != *number,
+				Comparison::Less => value < *number,
+				Comparison::LessOrEqual => value <= *number,
+				Comparison::Greater => value > *number,
+				Comparison::GreaterOrEqual => value >= *number,
+			}
+		}
+	}
+}
+
+/// Tags given by --tag <tag>=<glob> values whose glob matches the logfile
+pub fn tags_for_logfile(opt_tags: &[String], logfile: &str) -> Vec<String> {
+	let mut tags = Vec::<String>::new();
+	for opt_tag in opt_tags {
+		if let Some((tag, globpath)) = opt_tag.split_once('=') {
+			if let Ok(pattern) = glob::Pattern::new(globpath) {
+				if pattern.matches(logfile) && !tags.iter().any(|t| t == tag) {
+					tags.push(tag.to_string());
+				}
+			}
+		}
+	}
+	tags
+}
+
+/// Parse comma separated tags as entered at the tag prompt
+pub fn parse_tags(text: &str) -> Vec<String> {
+	text
+		.split(',')
+		.map(|tag| tag.trim().to_string())
+		.filter(|tag| !tag.is_empty())
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn it_filters_by_tag_status_and_logfile() {
+		let mut monitor = LogMonitor::new(String::from("/home/ant/vps1/antnode.log"));
+		monitor.tags = tags_for_logfile(&[String::from("vps=/home/ant/vps*/*.log")], &monitor.logfile);
+		monitor.metrics.node_status_string = String::from("Connected");
+		assert_eq!(monitor.tags, vec!["vps"]);
+
+		let matches = |expression: &str| NodeFilter::parse(expression).unwrap().matches(&monitor);
+		assert!(matches("tag:vps AND status:connected"));
+		assert!(matches("tag:home OR vps1"));
+		assert!(!matches("tag:vps AND NOT status:conn"));
+		assert!(matches("tag:home OR tag:vps status:connected"));
+		assert!(!matches("tag:home"));
+
+		assert!(NodeFilter::parse("tag:vps AND").is_err());
+		assert!(NodeFilter::parse("OR tag:vps").is_err());
+	}
+
+	#[test]
+	fn it_filters_by_comparing_columns() {
+		let mut monitor = LogMonitor::new(String::from("/home/ant/oracle1/antnode.log"));
+		monitor.metrics.node_status_string = String::from("INACTIVE");
+		monitor.metrics.activity_errors.total = 3;
+
+		let matches = |expression: &str| NodeFilter::parse(expression).unwrap().matches(&monitor);
+		assert!(matches("status=INACTIVE"));
+		assert!(!matches("status!=inactive"));
+		assert!(matches("errors>0 AND earnings=0"));
+		assert!(matches("errors>=3 errors<=3 errors!=2"));
+		assert!(!matches("errors<3"));
+		assert!(matches("name contains \"oracle\""));
+		assert!(!matches("NOT name contains oracle"));
+		assert!(matches("name=oracle1 OR records>10"));
+
+		assert!(NodeFilter::parse("errors>lots").is_err());
+		assert!(NodeFilter::parse("status>2").is_err());
+	}
+}
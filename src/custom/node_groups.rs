@@ -0,0 +1,173 @@
+//! Grouping of nodes in the Summary (see --group and --group-by)
+//!
+//! Nodes can be put in a group explicitly with --group <group>=<glob>, or by
+//! --group-by using a component of the logfile path (e.g. the mount point of a
+//! machine's logs) or the host a remote node is on. The Summary then shows a
+//! header row with subtotals for each group above its nodes, and a group can be
+//! collapsed to just its header by pressing 'enter' on it.
+use std::collections::HashMap;
+
+use super::node_metrics::LogMonitor;
+
+/// Group of nodes not matched by --group or --group-by
+pub const UNGROUPED_NAME: &str = "other";
+
+/// Entries of DashState::logfile_names_sorted for group header rows start with this
+const GROUP_ROW_PREFIX: &str = "\u{0}group:";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum GroupBy {
+	Host,                  // Host of remote nodes, 'local' for the rest
+	PathComponent(usize),  // Numbered from 1 for the first directory
+}
+
+impl GroupBy {
+	/// Parse a --group-by value, which is 'host' or the number of a path component
+	pub fn parse(group_by: &str) -> Result<GroupBy, String> {
+		if group_by.eq_ignore_ascii_case("host") {
+			return Ok(GroupBy::Host);
+		}
+		match group_by.parse::<usize>() {
+			Ok(component) if component > 0 => Ok(GroupBy::PathComponent(component)),
+			_ => Err(format!("invalid --group-by '{}', expected 'host' or a path component number", group_by)),
+		}
+	}
+
+	fn group_for_logfile(&self, logfile: &str) -> Option<String> {
+		match self {
+			GroupBy::Host => Some(host_for_logfile(logfile)),
+			GroupBy::PathComponent(component) => logfile
+				.split(|c| c == '/' || c == '\\')
+				.filter(|part| !part.is_empty())
+				.nth(component - 1)
+				.map(|part| part.to_string()),
+		}
+	}
+}
+
+/// The machine a node's logs come from, judged by the name of its monitor
+fn host_for_logfile(logfile: &str) -> String {
+	for prefix in ["http:", "tcp:"] {
+		if let Some(source) = logfile.strip_prefix(prefix) {
+			return source.split('/').next().unwrap_or(source).to_string();
+		}
+	}
+	// Tailed over SSH as user@host:/path (a Windows drive letter is not a host)
+	match logfile.split_once(':') {
+		Some((destination, _path)) if destination.len() > 1 && !destination.contains(['/', '\\']) && destination != "journal" => {
+			destination.rsplit('@').next().unwrap_or(destination).to_string()
+		}
+		_ => String::from("local"),
+	}
+}
+
+/// The group of a logfile from --group <group>=<glob> values, or else from --group-by
+pub fn group_for_logfile(opt_groups: &[String], group_by: &Option<GroupBy>, logfile: &str) -> Option<String> {
+	for opt_group in opt_groups {
+		if let Some((group, globpath)) = opt_group.split_once('=') {
+			if glob::Pattern::new(globpath).is_ok_and(|pattern| pattern.matches(logfile)) {
+				return Some(group.to_string());
+			}
+		}
+	}
+	group_by.as_ref().and_then(|group_by| group_by.group_for_logfile(logfile))
+}
+
+pub fn group_row_key(group: &str) -> String {
+	format!("{}{}", GROUP_ROW_PREFIX, group)
+}
+
+/// The group named by a group header entry in DashState::logfile_names_sorted
+pub fn group_of_row_key(key: &str) -> Option<&str> {
+	key.strip_prefix(GROUP_ROW_PREFIX)
+}
+
+/// Subtotals shown in a group's header row
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GroupTotals {
+	pub nodes: usize,
+	pub active_nodes: usize,
+	pub attos_earned: u128,
+	pub records_stored: u64,
+	pub errors: u64,
+}
+
+/// Insert a header before each group of the sorted logfiles, keeping their order within
+/// each group. Groups are in name order, and members of collapsed groups are left out.
+pub fn group_rows(
+	logfiles_sorted: &[String],
+	monitors: &HashMap<String, LogMonitor>,
+	collapsed_groups: &[String],
+) -> (Vec<String>, HashMap<String, GroupTotals>) {
+	let mut groups = Vec::<(String, Vec<String>)>::new();
+	let mut totals = HashMap::<String, GroupTotals>::new();
+	for logfile in logfiles_sorted {
+		let Some(monitor) = monitors.get(logfile) else {
+			continue;
+		};
+		let group = monitor.group.clone().unwrap_or(String::from(UNGROUPED_NAME));
+
+		let group_totals = totals.entry(group.clone()).or_default();
+		group_totals.nodes += 1;
+		group_totals.active_nodes += if monitor.metrics.is_node_active() { 1 } else { 0 };
+		group_totals.attos_earned = group_totals.attos_earned.saturating_add(monitor.metrics.attos_earned.total);
+		group_totals.records_stored += monitor.metrics.records_stored;
+		group_totals.errors += monitor.metrics.activity_errors.total;
+
+		match groups.iter_mut().find(|(name, _members)| *name == group) {
+			Some((_name, members)) => members.push(logfile.clone()),
+			None => groups.push((group, vec![logfile.clone()])),
+		}
+	}
+	groups.sort_by(|a, b| a.0.cmp(&b.0));
+
+	let mut rows = Vec::<String>::new();
+	for (group, members) in groups {
+		rows.push(group_row_key(&group));
+		if !collapsed_groups.contains(&group) {
+			rows.extend(members);
+		}
+	}
+	(rows, totals)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn it_finds_groups_for_logfiles() {
+		let by_component = Some(GroupBy::parse("2").unwrap());
+		assert_eq!(group_for_logfile(&[], &by_component, "/mnt/vps1/antnode1/antnode.log").as_deref(), Some("vps1"));
+		let groups = [String::from("home=/home/*/antnode.log")];
+		assert_eq!(group_for_logfile(&groups, &by_component, "/home/ant/antnode.log").as_deref(), Some("home"));
+		assert_eq!(group_for_logfile(&[], &None, "/home/ant/antnode.log"), None);
+
+		let by_host = Some(GroupBy::parse("host").unwrap());
+		assert_eq!(group_for_logfile(&[], &by_host, "ant@vps2:/var/log/antnode/antnode1/antnode.log").as_deref(), Some("vps2"));
+		assert_eq!(group_for_logfile(&[], &by_host, "tcp:10.0.0.5/node1").as_deref(), Some("10.0.0.5"));
+		assert_eq!(group_for_logfile(&[], &by_host, "C:\\ProgramData\\autonomi\\antnode.log").as_deref(), Some("local"));
+		assert_eq!(group_for_logfile(&[], &by_host, "journal:antnode1.service").as_deref(), Some("local"));
+		assert!(GroupBy::parse("0").is_err());
+	}
+
+	#[test]
+	fn it_inserts_group_rows_with_totals() {
+		let mut monitors = HashMap::new();
+		for (logfile, group, attos) in [("a", Some("vps2"), 10), ("b", None, 20), ("c", Some("vps2"), 30), ("d", Some("vps1"), 5)] {
+			let mut monitor = LogMonitor::new(logfile.to_string());
+			monitor.group = group.map(String::from);
+			monitor.metrics.attos_earned.total = attos;
+			monitors.insert(logfile.to_string(), monitor);
+		}
+		let sorted: Vec<String> = ["c", "b", "a", "d"].iter().map(|s| s.to_string()).collect();
+
+		let (rows, totals) = group_rows(&sorted, &monitors, &[String::from("vps1")]);
+		let expected: Vec<String> = vec![group_row_key("other"), "b".into(), group_row_key("vps1"), group_row_key("vps2"), "c".into(), "a".into()];
+		assert_eq!(rows, expected);
+		assert_eq!(group_of_row_key(&rows[2]), Some("vps1"));
+		assert_eq!(totals["vps2"].nodes, 2);
+		assert_eq!(totals["vps2"].attos_earned, 40);
+		assert_eq!(totals["vps1"].attos_earned, 5);
+	}
+}
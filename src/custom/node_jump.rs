@@ -0,0 +1,104 @@
+//! Choosing a node to jump to by number or a fuzzy match (see 'j')
+//!
+//! With dozens of nodes, stepping through them to reach one is slow. The jump
+//! prompt takes the node's number (the Summary '#' column) or part of its
+//! logfile path or tags, where the characters typed need only appear in order
+//! (so "n42log" matches ".../antnode42/logs/antnode.log").
+
+/// Score of a match of the whole text within a candidate, above any which is only in order
+const CONTIGUOUS_SCORE: i64 = 1_000_000;
+
+/// How well query matches candidate, ignoring case, or None if its characters
+/// don't all appear in order. Contiguous matches score highest, then those with
+/// the fewest characters skipped between the first and last matched.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+	let query = query.to_lowercase();
+	let candidate = candidate.to_lowercase();
+	if query.is_empty() {
+		return None;
+	}
+	if let Some(position) = candidate.rfind(&query) {
+		// Prefer a match near the end, where paths differ
+		return Some(CONTIGUOUS_SCORE - (candidate.len() - position - query.len()) as i64);
+	}
+
+	let mut wanted = query.chars().peekable();
+	let mut skipped = 0;
+	let mut started = false;
+	for c in candidate.chars() {
+		match wanted.peek() {
+			Some(next) if *next == c => {
+				wanted.next();
+				started = true;
+			}
+			Some(_) if started => skipped += 1,
+			Some(_) => {}
+			None => break,
+		}
+	}
+	if wanted.peek().is_some() {
+		return None;
+	}
+	Some(CONTIGUOUS_SCORE / 2 - skipped)
+}
+
+/// The logfile of the node to jump to: the one numbered text, or else the best
+/// match of text in a logfile path or tag. nodes are (logfile, index, tags).
+pub fn find_node<'a>(text: &str, nodes: impl Iterator<Item = (&'a str, usize, &'a [String])>) -> Option<String> {
+	let text = text.trim();
+	let nodes: Vec<(&str, usize, &[String])> = nodes.collect();
+	if let Ok(number) = text.parse::<usize>() {
+		if let Some((logfile, _, _)) = nodes.iter().find(|(_, index, _)| index + 1 == number) {
+			return Some(logfile.to_string());
+		}
+	}
+
+	nodes
+		.iter()
+		.filter_map(|(logfile, index, tags)| {
+			let score = std::iter::once(*logfile)
+				.chain(tags.iter().map(String::as_str))
+				.filter_map(|candidate| fuzzy_score(text, candidate))
+				.max()?;
+			Some((score, *index, *logfile))
+		})
+		// Best score, then lowest number
+		.max_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)))
+		.map(|(_, _, logfile)| logfile.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn it_finds_nodes_by_number_or_fuzzy_match() {
+		let tags = [vec![], vec![String::from("home")], vec![]];
+		let logfiles = [
+			"/var/antctl/services/antnode1/logs/antnode.log",
+			"/var/antctl/services/antnode42/logs/antnode.log",
+			"/var/antctl/services/antnode7/logs/antnode.log",
+		];
+		let nodes = || {
+			logfiles
+				.iter()
+				.zip(tags.iter())
+				.enumerate()
+				.map(|(index, (logfile, tags))| (*logfile, index, tags.as_slice()))
+		};
+
+		assert_eq!(find_node("2", nodes()).unwrap(), logfiles[1]);
+		assert_eq!(find_node(" 3 ", nodes()).unwrap(), logfiles[2]);
+		assert_eq!(find_node("node42", nodes()).unwrap(), logfiles[1]);
+		assert_eq!(find_node("n7log", nodes()).unwrap(), logfiles[2]);
+		assert_eq!(find_node("HOME", nodes()).unwrap(), logfiles[1]);
+		// A number which isn't a node's is matched like any other text
+		assert_eq!(find_node("42", nodes()).unwrap(), logfiles[1]);
+		assert_eq!(find_node("antnode", nodes()).unwrap(), logfiles[0]);
+		assert_eq!(find_node("zzz", nodes()), None);
+		assert_eq!(find_node("", nodes()), None);
+
+		assert!(fuzzy_score("node42", "antnode42") > fuzzy_score("n42", "antnode42"));
+		assert_eq!(fuzzy_score("ab", "ba"), None);
+	}
+}
@@ -0,0 +1,1684 @@
+//! The metrics of each node, gathered from the lines of its logfile
+//!
+//! A LogMonitor follows one logfile, keeping its most recent lines and the
+//! NodeMetrics parsed from them. This is the part of vdash which other tools
+//! can use through the vdash_core library (see src/lib.rs), so it has nothing
+//! of the dashboard: loading logfiles and the App which shows them are in
+//! app.rs, which re-exports what is here for the rest of the dashboard.
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Error, Write};
+use std::sync::{LazyLock, Mutex};
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use tempfile::NamedTempFile;
+
+use crate::shared::util::StatefulList;
+
+use super::app_timelines::{
+	AppTimelines, CONNECTIONS_TIMELINE_KEY, EARNINGS_TIMELINE_KEY, ERRORS_TIMELINE_KEY, GETS_TIMELINE_KEY,
+	PUTS_TIMELINE_KEY, RAM_TIMELINE_KEY, STORAGE_COST_TIMELINE_KEY,
+};
+use super::averages::DecayedAverage;
+use super::cell_changes::CellChanges;
+use super::custom_metrics::CUSTOM_METRICS;
+use super::debug_lines::DebugLines;
+use super::disk_space::DiskSpace;
+use super::earnings_ledger::EarningsLedger;
+use super::error_categories::{category_of, ERROR_CATEGORIES};
+use super::fleet_history::{DailyTotals, DayTotals};
+use super::generic_logs::{is_generic_logfile, GenericMetrics};
+use super::log_patterns::{LinePatterns, LogPattern};
+use super::log_prefix::{split_log_line, LinePrefix, TimestampParser};
+use super::logfile_checkpoints::{save_checkpoint, LogfileCheckpoint};
+use super::mapped_logfile::lossy_line;
+use super::node_filter::tags_for_logfile;
+use super::node_groups::{group_for_logfile, GroupBy};
+use super::opt::{default_opt, Opt};
+use super::parser_stats::ParserStats;
+use super::percentiles::PercentileSketch;
+use super::process_metrics::ProcessSample;
+use super::profile::profile_scope;
+use super::replay::{dashboard_time, replay_time};
+use super::resource_envelope::ResourceEnvelope;
+use super::restart_history::RestartHistory;
+use super::session::SessionCounts;
+use super::store_size::StoreSize;
+use super::timelines::{get_duration_text, BucketValue};
+use super::token_units::TokenUnit;
+use super::top_messages::TopMessages;
+use super::wallet_watch::WalletWatch;
+
+pub(crate) static DEBUG_LOGFILE: LazyLock<Mutex<Option<NamedTempFile>>> =
+	LazyLock::new(|| Mutex::<Option<NamedTempFile>>::new(None));
+
+#[macro_export]
+macro_rules! debug_log {
+	($message:expr) => {
+		unsafe {
+			debug_log($message);
+		}
+	};
+}
+pub use crate::debug_log;
+
+pub unsafe fn debug_log(message: &str) {
+	// --debug-window - prints parser results for every logfile
+	// to a temp logfile which is displayed by the Debug view.
+	match &(*DEBUG_LOGFILE.lock().unwrap()) {
+		Some(f) => {
+			use std::io::Seek;
+			// A debug message which can't be written is dropped rather than ending vdash
+			if let Ok(mut file) = f.reopen() {
+				if file.seek(std::io::SeekFrom::End(0)).is_ok() {
+					let _ = writeln!(file, "{}", message);
+				}
+			}
+		}
+		None => (),
+	};
+}
+
+/// The options vdash is using, which are its defaults until given others with set_opt()
+pub static OPT: LazyLock<Mutex<Opt>> = LazyLock::new(|| Mutex::<Opt>::new(default_opt()));
+
+/// Use opt for OPT. Options which build the line patterns, such as --custom-metric and
+/// --error-category, only have an effect when given before the first line is parsed.
+pub fn set_opt(opt: Opt) {
+	*OPT.lock().unwrap() = opt;
+}
+
+const NODE_INACTIVITY_TIMEOUT_S: i64 = 20; // Seconds with no log message before node becomes 'inactive'
+
+pub struct LogMonitor {
+	pub index: usize,
+	pub content: StatefulList<String>,
+	max_content: usize, // Limit number of lines in content
+	max_unfocused_content: usize, // Limit while without focus, when the content isn't shown
+	pub keeps_log_text: bool,     // False with --no-log-text, when content is always empty
+	pub has_focus: bool,
+	pub logfile: String,
+	pub metrics: NodeMetrics,
+	pub metrics_status: StatefulList<String>,
+	pub is_debug_dashboard_log: bool,
+	pub debug_lines: Option<DebugLines>, // Parser output of recent lines, with --debug-window
+	pub is_remote: bool, // Tailed over SSH (see --remote)
+	pub generic: Option<GenericMetrics>, // Not a node logfile (see --generic)
+	pub tags: Vec<String>,
+	pub group: Option<String>, // See --group and --group-by
+	pub resource_envelope: ResourceEnvelope,
+	pub latest_checkpoint_time: Option<DateTime<Utc>>,
+	pub summary_changes: CellChanges, // Summary row as last formatted (see --highlight-changes)
+	pub summary_trend: Vec<BucketValue>, // Buckets shown in the Summary Trend column
+	pub session_start: Option<SessionCounts>, // Counts when first seen this session (see session.rs)
+}
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+static NEXT_MONITOR: AtomicUsize = AtomicUsize::new(0);
+
+/// Number monitors from the first again, so each App made by the TUI tests has nodes 1, 2...
+#[cfg(test)]
+pub fn reset_monitor_indexes() {
+	NEXT_MONITOR.store(0, Ordering::Relaxed);
+}
+
+fn next_unused_index(monitors: &mut HashMap<String, LogMonitor>) -> usize {
+	let mut next_index = 0;
+
+	let mut index_unused = false;
+	while !index_unused {
+		next_index = NEXT_MONITOR.fetch_add(1, Ordering::Relaxed);
+
+		index_unused = true;
+		for (_logfile, monitor) in monitors.iter() {
+			if next_index == monitor.index {
+				index_unused = false;
+			}
+		}
+	}
+
+	next_index
+}
+
+impl LogMonitor {
+	pub fn new(logfile_path: String) -> LogMonitor {
+		let mut is_debug_dashboard_log = false;
+		if let Some(debug_logfile) = &*DEBUG_LOGFILE.lock().unwrap() {
+			if let Some(debug_logfile_path) = debug_logfile.path().to_str() {
+				is_debug_dashboard_log = logfile_path.eq(debug_logfile_path);
+			}
+		}
+
+		let (opt_debug_window, opt_lines_max, opt_lines_unfocused, opt_no_log_text, opt_tags, opt_resource_envelopes, opt_groups, opt_group_by, opt_generic, opt_timeline_steps) = {
+			let opt = OPT.lock().unwrap();
+			(
+				opt.debug_window,
+				opt.lines_max,
+				opt.lines_unfocused,
+				opt.no_log_text,
+				opt.tag.clone(),
+				opt.resource_envelope.clone(),
+				opt.group.clone(),
+				opt.group_by.clone(),
+				opt.generic.clone(),
+				opt.timeline_steps,
+			)
+		};
+		let generic = if is_generic_logfile(&opt_generic, &logfile_path) {
+			Some(GenericMetrics::new(opt_timeline_steps))
+		} else {
+			None
+		};
+		let debug_lines = (opt_debug_window && !is_debug_dashboard_log && generic.is_none()).then(DebugLines::default);
+		let tags = tags_for_logfile(&opt_tags, &logfile_path);
+		let group_by = opt_group_by.and_then(|group_by| GroupBy::parse(&group_by).ok());
+		let group = group_for_logfile(&opt_groups, &group_by, &logfile_path);
+		let resource_envelope = ResourceEnvelope::for_logfile(&opt_resource_envelopes, &logfile_path);
+		LogMonitor {
+			index: 0,
+			logfile: logfile_path,
+			max_content: opt_lines_max,
+			max_unfocused_content: opt_lines_unfocused.min(opt_lines_max),
+			keeps_log_text: !opt_no_log_text || is_debug_dashboard_log,
+			metrics: NodeMetrics::new(),
+			content: StatefulList::with_items(vec![]),
+			has_focus: false,
+			metrics_status: StatefulList::with_items(vec![]),
+			is_debug_dashboard_log,
+			debug_lines,
+			is_remote: false,
+			generic,
+			tags,
+			group,
+			resource_envelope,
+			latest_checkpoint_time: None,
+			summary_changes: CellChanges::default(),
+			summary_trend: Vec::new(),
+			session_start: None,
+		}
+	}
+
+	/// Resolve any clash between self.index and index of other monitors which may happen
+	/// when mixing creation of new monitors with initialisation by restoring a checkpoint.
+	///
+	/// For a restored checkpoint the metrics should be set, so if one has metrics and the other
+	/// doesn't, the former is treated as older and given the lower index.
+	pub fn canonicalise_monitor_index(&mut self, monitors: &mut HashMap<String, LogMonitor>) {
+		if self.index == 0 {
+			self.index = NEXT_MONITOR.fetch_add(1, Ordering::Relaxed);
+		}
+
+		let existing_index = NEXT_MONITOR.fetch_add(0, Ordering::Relaxed);
+		let next_index = next_unused_index(monitors);
+
+		let mut clash_monitor = None;
+		for (other_logfile, other) in monitors.iter_mut() {
+			if self.index == other.index && &self.logfile != other_logfile {
+				clash_monitor = Some(other);
+			}
+		}
+
+		if let Some(other) = clash_monitor {
+			let mut lower_index = self.index;
+			let mut higher_index = next_index;
+			if lower_index > higher_index {
+				lower_index = next_index;
+				higher_index = self.index;
+			}
+
+			// Default
+			self.index = higher_index;
+			other.index = lower_index;
+
+			// If we know the earlier of the two metrics, use that to order the index in self and other
+			if let Some(self_start_time) = self.metrics.node_started {
+				let flip = if let Some(other_start_time) = other.metrics.node_started {
+					self_start_time < other_start_time
+				} else {
+					true
+				};
+				if flip {
+					self.index = lower_index;
+					other.index = higher_index;
+				}
+			}
+		} else {
+			// next_index not used so restore state to avoid unnecessary increments
+			NEXT_MONITOR.store(existing_index, Ordering::Relaxed);
+		}
+	}
+
+	pub fn is_node(&self) -> bool {
+		return !self.is_debug_dashboard_log && self.generic.is_none();
+	}
+
+	/// Count a line of a generic logfile, which has none of the metadata of node log lines
+	fn append_generic_line(&mut self, line: &str) -> Result<(), std::io::Error> {
+		if let Some(generic) = &mut self.generic {
+			generic.record_line(line, &dashboard_time());
+		}
+		self._append_to_content(line)
+	}
+
+	pub fn from_checkpoint(&mut self, checkpoint: &LogfileCheckpoint) {
+		self.index = checkpoint.monitor_index;
+		self.latest_checkpoint_time = checkpoint.latest_entry_time;
+		self.metrics = checkpoint.monitor_metrics.clone();
+		self.metrics.app_timelines.restore(checkpoint.latest_entry_time);
+	}
+
+	pub fn to_checkpoint(&mut self, checkpoint: &mut LogfileCheckpoint) {
+		checkpoint.latest_entry_time = self.latest_checkpoint_time;
+		checkpoint.monitor_index = self.index;
+		checkpoint.monitor_metrics = self.metrics.clone();
+	}
+
+	/// With --debug-window, keep the parser output of the line just appended, returning
+	/// it to be added to the debug window if self has focus
+	pub fn record_parser_output(&mut self) -> Option<String> {
+		let debug_lines = self.debug_lines.as_mut()?;
+		let parser_output = self.metrics.parser_output.clone();
+		debug_lines.push(parser_output.clone());
+		self.has_focus.then_some(parser_output)
+	}
+
+	pub fn append_to_content(
+		&mut self,
+		line: &str,
+		checkpoint_interval: u64,
+	) -> Result<String, std::io::Error> {
+		if self.generic.is_some() {
+			self.append_generic_line(line)?;
+			return Ok("".to_string());
+		}
+		self.append_parsed_line(line, LogEntry::decode_metadata(line), &LinePatterns::of(line), checkpoint_interval)
+	}
+
+	/// As append_to_content() for a line whose metadata and patterns have already
+	/// been found (see ParsePipeline)
+	pub fn append_parsed_line(
+		&mut self,
+		line: &str,
+		entry_metadata: Option<LogMeta>,
+		patterns: &LinePatterns,
+		checkpoint_interval: u64,
+	) -> Result<String, std::io::Error> {
+		if self.generic.is_some() {
+			self.append_generic_line(line)?;
+			return Ok("".to_string());
+		}
+		self.metrics.parser_output = format!("LogMeta::decode_metadata() failed on: {}", line); // For debugging
+																																													// debug_log!(&self.parser_output.clone());
+
+		self.metrics.entry_metadata = entry_metadata;
+
+		if self.metrics.entry_metadata.is_none() {
+			// debug_log!("gather_metrics() - skipping bec. metadata missing");
+			self.metrics.lines_without_metadata += 1;
+			return Ok("".to_string()); // Skip until start of first log message
+		}
+
+		self._append_to_content(line)?; // Show in TUI
+		if self.is_debug_dashboard_log {
+			return Ok("".to_string());
+		}
+
+		self.metrics.gather_metrics(&line, patterns)?;
+
+		if checkpoint_interval > 0 {
+			// Checkpoints disabled by zero interval
+			return self.update_checkpoint(checkpoint_interval);
+		}
+
+		Ok("".to_string())
+	}
+
+	pub fn update_checkpoint(&mut self, checkpoint_interval: u64) -> Result<String, Error> {
+		if let Some(metadata) = &self.metrics.entry_metadata {
+			if self.latest_checkpoint_time.is_none() {
+				return save_checkpoint(self);
+			} else {
+				if let Some(latest_checkpoint_time) = self.latest_checkpoint_time {
+					if latest_checkpoint_time + Duration::seconds(checkpoint_interval as i64)
+						< metadata.message_time
+					{
+						return save_checkpoint(self);
+					}
+				}
+			}
+		}
+
+		Ok("".to_string())
+	}
+
+	pub fn append_to_content_from_time(
+		&mut self,
+		line: &str,
+		after_time: Option<DateTime<Utc>>,
+	) -> Result<(), std::io::Error> {
+		if self.generic.is_some() {
+			return self.append_generic_line(line);
+		}
+		self.metrics.parser_output = format!("LogMeta::decode_metadata() failed on: {}", line); // For debugging
+																																													// debug_log!(&self.parser_output.clone());
+
+		if let Some(entry_metadata) = LogEntry::decode_metadata(line) {
+			if let Some(after_time) = after_time {
+				if !entry_metadata.message_time.gt(&after_time) {
+					return Ok(());
+				}
+			}
+
+			self.metrics.entry_metadata = Some(entry_metadata);
+		} else {
+			// debug_log!("gather_metrics() - skipping bec. metadata missing");
+			self.metrics.lines_without_metadata += 1;
+			if after_time.is_some() {
+				return Ok(());
+			}
+		}
+
+		self._append_to_content(line)?; // Show in TUI
+		if self.is_debug_dashboard_log {
+			return Ok(());
+		}
+
+		self.metrics.gather_metrics(&line, &LinePatterns::of(line))?;
+
+		Ok(())
+	}
+
+	pub fn _append_to_content(&mut self, text: &str) -> Result<(), std::io::Error> {
+		if !self.keeps_log_text {
+			return Ok(());
+		}
+		self.content.items.push(text.to_string());
+		let len = self.content.items.len();
+		let max_content = self.content_limit();
+		if len > max_content {
+			self.content.items = self.content.items.split_off(len - max_content);
+		} else {
+			self.content.state.select(Some(len - 1));
+		}
+		Ok(())
+	}
+
+	/// Lines of content kept, which are fewer without focus as the logfile isn't shown
+	fn content_limit(&self) -> usize {
+		if self.has_focus { self.max_content } else { self.max_unfocused_content }
+	}
+
+	/// Give the monitor focus, reading the end of the logfile to fill the content
+	/// trimmed while it didn't have focus (unless load_tail is false)
+	pub fn gain_focus(&mut self, load_tail: bool) {
+		self.has_focus = true;
+		if !load_tail || !self.keeps_log_text || self.is_remote || self.content.items.len() >= self.max_content {
+			return;
+		}
+		let Ok(mut f) = File::open(&self.logfile) else {
+			return;
+		};
+		match read_tail_lines(&mut f, self.max_content) {
+			Ok(lines) if lines.len() > self.content.items.len() => {
+				self.content.items = lines;
+				self.content.state.select(Some(self.content.items.len() - 1));
+			}
+			Ok(_) => {}
+			Err(e) => error!("Unable to read the end of {}: {}", self.logfile, e),
+		}
+	}
+
+	/// Take focus away, freeing all but the most recent lines of content
+	pub fn lose_focus(&mut self) {
+		self.has_focus = false;
+		let len = self.content.items.len();
+		if len > self.max_unfocused_content {
+			self.content.items.drain(..len - self.max_unfocused_content);
+			self.content.items.shrink_to_fit();
+			self.content.state.select(self.content.items.len().checked_sub(1));
+		}
+	}
+}
+
+thread_local! {
+	// Each parse pipeline worker has its own, see LogEntry::decode_metadata()
+	static TIMESTAMP_PARSER: RefCell<TimestampParser> = RefCell::new(TimestampParser::default());
+}
+
+#[derive(PartialEq, Clone, Default, Debug, Serialize, Deserialize)]
+pub enum NodeStatus {
+	Started,
+	Connected,
+	#[default]
+	Stopped,
+	Shunned,
+}
+
+pub fn node_status_as_string(node_status: &NodeStatus) -> String {
+	match node_status {
+		NodeStatus::Connected => "Connected".to_string(),
+		NodeStatus::Stopped => "Stopped".to_string(),
+		NodeStatus::Started => "Started".to_string(),
+		NodeStatus::Shunned => "SHUNNED".to_string(),
+	}
+}
+
+/// Values summarised by an MmmStat: u64 for counts, and u128 for amounts in attos
+/// which can pass u64::MAX (about 18 ANT) when totalled
+pub trait StatValue: Copy + PartialOrd {
+	const ZERO: Self;
+	const MAX: Self;
+	fn add_saturating(self, other: Self) -> Self;
+	fn divided_by(self, count: u64) -> Self;
+}
+
+macro_rules! impl_stat_value {
+	($($value_type:ty),*) => {
+		$(impl StatValue for $value_type {
+			const ZERO: Self = 0;
+			const MAX: Self = <$value_type>::MAX;
+			fn add_saturating(self, other: Self) -> Self {
+				self.saturating_add(other)
+			}
+			fn divided_by(self, count: u64) -> Self {
+				self / count as $value_type
+			}
+		})*
+	};
+}
+impl_stat_value!(u64, u128);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MmmStat<T = u64> {
+	sample_count: u64,
+
+	pub most_recent: T,
+	pub total: T,
+	pub min: T,
+	pub mean: T,
+	pub max: T,
+}
+
+impl<T: StatValue> MmmStat<T> {
+	pub fn new() -> MmmStat<T> {
+		MmmStat {
+			sample_count: 0,
+			most_recent: T::ZERO,
+			total: T::ZERO,
+			min: T::MAX,
+			mean: T::ZERO,
+			max: T::ZERO,
+		}
+	}
+
+	pub fn add_sample(&mut self, value: T) {
+		self.most_recent = value;
+		self.sample_count += 1;
+		self.total = self.total.add_saturating(value);
+		self.mean = self.total.divided_by(self.sample_count);
+
+		if self.min > value || self.min == T::MAX {
+			self.min = value;
+		}
+		if self.max < value {
+			self.max = value;
+		}
+	}
+}
+
+/// What is done with a line containing a LogPattern, returning true if nothing more is
+/// to be taken from it. Only the first rule whose pattern is in a line is applied, so to
+/// handle another log message add its pattern to LogPattern and a rule here.
+type LineRule = (LogPattern, fn(&mut NodeMetrics, &String, &LogMeta) -> bool);
+
+const TIMED_DATA_RULES: [LineRule; 8] = [
+	(LogPattern::RecordRetrieved, NodeMetrics::handle_record_retrieved),
+	(LogPattern::RecordWritten, NodeMetrics::handle_record_stored),
+	(LogPattern::SpendPut, NodeMetrics::handle_record_stored),
+	(LogPattern::RegisterEdited, NodeMetrics::handle_record_stored), // TODO: no longer present, find new log message
+	(LogPattern::StorageCost, NodeMetrics::handle_storage_cost),
+	(LogPattern::Payment, NodeMetrics::handle_payment),
+	(LogPattern::PeersInRoutingTable, NodeMetrics::handle_peers_in_routing_table),
+	(LogPattern::Shunned, NodeMetrics::handle_shunned),
+];
+
+/// Applied after TIMED_DATA_RULES unless one of those has handled the line
+const STATE_RULES: [LineRule; 5] = [
+	(LogPattern::EventsChannelClosed, NodeMetrics::handle_events_channel_closed),
+	(LogPattern::PaymentQuote, NodeMetrics::handle_quoting_metrics),
+	(LogPattern::StorageCost, NodeMetrics::handle_quoting_metrics),
+	(LogPattern::Metrics, NodeMetrics::handle_logged_metrics),
+	(LogPattern::WalletBalance, NodeMetrics::handle_wallet_balance),
+];
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NodeMetrics {
+	pub node_started: Option<DateTime<Utc>>,
+	pub running_message: Option<String>,
+	pub running_version: Option<String>,
+	pub node_process_id: Option<u64>,
+	pub node_peer_id: Option<String>,
+	pub category_count: HashMap<String, usize>,
+
+	pub app_timelines: AppTimelines,
+
+	pub entry_metadata: Option<LogMeta>,
+	pub node_status: NodeStatus,
+	pub node_bad_behaviour: String,
+	pub node_status_string: String,
+	pub node_inactive: bool,
+
+	// Logfile is updated by periodic snapshots (see --snapshot-glob-path)
+	#[serde(skip)]
+	pub is_snapshot_source: bool,
+
+	// Logfile exists but could not be read
+	#[serde(skip)]
+	pub permission_denied: bool,
+
+	// Error which stopped the logfile being followed, until it is re-attached
+	#[serde(skip)]
+	pub tail_error: Option<String>,
+
+	// Lines loaded with bytes which are not valid UTF-8 (replaced when loaded)
+	#[serde(default)]
+	pub invalid_utf8_lines: u64,
+
+	// Lines without the prefix of a log message, such as those continuing one
+	#[serde(default)]
+	pub lines_without_metadata: u64,
+
+	// Values which could not be parsed from a line containing their pattern
+	#[serde(default)]
+	pub parse_failures: u64,
+
+	// Time of the first log entry parsed from the logfile
+	#[serde(default)]
+	pub first_entry_time: Option<DateTime<Utc>>,
+
+	pub activity_gets: MmmStat,
+	pub activity_puts: MmmStat,
+	pub activity_errors: MmmStat,
+	#[serde(default)]
+	pub error_categories: HashMap<String, u64>, // ERROR lines by category (see --error-category)
+	#[serde(default)]
+	pub top_messages: TopMessages, // Most frequent ERROR and WARN messages
+	#[serde(default)]
+	pub parser_stats: ParserStats, // Lines matched by each parser rule, kept across restarts
+	pub attos_earned: MmmStat<u128>,
+	pub storage_cost: MmmStat<u128>,
+	pub peers_connected: MmmStat,
+	pub memory_used_mb: MmmStat,
+
+	// Time-decayed averages, which follow current conditions more closely than the means above
+	#[serde(default)]
+	pub storage_cost_recent: DecayedAverage,
+	#[serde(default)]
+	pub peers_connected_recent: DecayedAverage,
+	#[serde(default)]
+	pub storage_cost_percentiles: PercentileSketch, // Of every storage cost, in attos
+
+	// Denomination of amounts in the logfile, which are scaled to attos when parsed
+	#[serde(default)]
+	pub token_unit: TokenUnit,
+
+	// Keyed by CustomMetric::key (see --custom-metric)
+	#[serde(default)]
+	pub custom_metrics: HashMap<String, MmmStat>,
+
+	pub wallet_balance: u128,
+	pub latest_earning: u128,
+
+	#[serde(default)]
+	pub earnings_ledger: EarningsLedger,
+
+	#[serde(default)]
+	pub restart_history: RestartHistory,
+
+	#[serde(default)]
+	pub daily_totals: DailyTotals,
+
+	// Wallet balance file (see --wallet-poll)
+	#[serde(skip)]
+	pub wallet_watch: WalletWatch,
+
+	// Free space of the node's device (see --disk-poll)
+	#[serde(skip)]
+	pub disk_space: DiskSpace,
+
+	// Size of the node's record store (see --store-poll)
+	#[serde(skip)]
+	pub store_size: StoreSize,
+
+	pub records_stored: u64,
+	pub records_max: u64,
+
+	// Bytes stored, from older logs which give this rather than records
+	#[serde(default)]
+	pub space_used: u64,
+	#[serde(default)]
+	pub space_max: u64,
+	#[serde(default)]
+	pub records_per_type: Vec<(u32, u64)>, // (data type, records) from QuotingMetrics
+
+	pub shun_notifications: u64,
+
+	pub system_cpu: f32,
+	pub system_memory: f32,
+	pub system_memory_used_mb: f32,
+	pub system_memory_usage_percent: f32,
+
+	pub interface_name: String,
+	pub bytes_received: u64,
+	pub bytes_transmitted: u64,
+	pub total_mb_received: f32,
+	pub total_mb_transmitted: f32,
+
+	pub cpu_usage_percent: f32,
+	pub cpu_usage_percent_max: f32,
+
+	// Time of the latest metrics line in the logfile, and whether CPU and memory
+	// have since been sampled from the node process instead (see --process-metrics)
+	#[serde(skip)]
+	pub latest_logged_metrics: Option<DateTime<Utc>>,
+	#[serde(skip)]
+	pub resources_sampled: bool,
+
+	// The process with node_process_id is not running (see --pid-check)
+	#[serde(skip)]
+	pub process_dead: bool,
+	pub bytes_read: u64,
+	pub bytes_written: u64,
+	pub total_mb_read: f32,
+	pub total_mb_written: f32,
+
+	pub parser_output: String,
+}
+
+impl NodeMetrics {
+	pub fn new() -> NodeMetrics {
+		let mut metrics = NodeMetrics {
+			// Start
+			node_started: None,
+			running_message: None,
+			running_version: None,
+			node_process_id: None,
+			node_peer_id: None,
+
+			// Logfile entries
+			entry_metadata: None,
+
+			// A predefined set of Timelines (Sparklines)
+			app_timelines: AppTimelines::new(),
+
+			// Counts
+			category_count: HashMap::new(),
+			activity_gets: MmmStat::new(),
+			activity_puts: MmmStat::new(),
+			activity_errors: MmmStat::new(),
+			error_categories: HashMap::new(),
+			top_messages: TopMessages::default(),
+			parser_stats: ParserStats::default(),
+
+			// Storage Payments
+			attos_earned: MmmStat::new(),
+			storage_cost: MmmStat::new(),
+			peers_connected: MmmStat::new(),
+			storage_cost_recent: DecayedAverage::default(),
+			peers_connected_recent: DecayedAverage::default(),
+			storage_cost_percentiles: PercentileSketch::default(),
+			token_unit: TokenUnit::Attos,
+
+			// State (node)
+			node_status: NodeStatus::Stopped,
+			node_bad_behaviour: String::from(""),
+			node_status_string: String::from(""),
+			node_inactive: false,
+			is_snapshot_source: false,
+			permission_denied: false,
+			tail_error: None,
+			invalid_utf8_lines: 0,
+			lines_without_metadata: 0,
+			parse_failures: 0,
+			first_entry_time: None,
+			custom_metrics: HashMap::new(),
+
+			// State (network)
+
+			// Wallet event:
+			wallet_balance: 0,
+			latest_earning: 0,
+			earnings_ledger: EarningsLedger::default(),
+			restart_history: RestartHistory::default(),
+			daily_totals: DailyTotals::default(),
+			wallet_watch: WalletWatch::default(),
+			disk_space: DiskSpace::default(),
+			store_size: StoreSize::default(),
+
+			// Storage use:
+			records_stored: 0,
+			records_max: 0,
+			space_used: 0,
+			space_max: 0,
+			records_per_type: Vec::new(),
+
+			shun_notifications: 0,
+
+			system_cpu: 0.0,
+			system_memory: 0.0,
+			system_memory_used_mb: 0.0,
+			system_memory_usage_percent: 0.0,
+
+			interface_name: String::from("unknown"),
+			bytes_received: 0,
+			bytes_transmitted: 0,
+			total_mb_received: 0.0,
+			total_mb_transmitted: 0.0,
+
+			memory_used_mb: MmmStat::new(),
+			cpu_usage_percent: 0.0,
+			cpu_usage_percent_max: 0.0,
+			latest_logged_metrics: None,
+			resources_sampled: false,
+			process_dead: false,
+			bytes_read: 0,
+			bytes_written: 0,
+			total_mb_read: 0.0,
+			total_mb_written: 0.0,
+
+			// Debug
+			parser_output: String::from("-"),
+		};
+		metrics.update_timelines(&dashboard_time());
+		metrics
+	}
+
+	pub fn is_node_active(&self) -> bool {
+		return !self.node_inactive;
+	}
+
+	pub fn update_node_status_string(&mut self) {
+		let node_inactive_timeout = Duration::seconds(NODE_INACTIVITY_TIMEOUT_S);
+
+		let mut node_status_string = node_status_as_string(&self.node_status);
+
+		if self.permission_denied {
+			self.node_inactive = true;
+			node_status_string = String::from("Permission denied");
+		} else if let Some(tail_error) = &self.tail_error {
+			self.node_inactive = true;
+			node_status_string = format!("ERROR {} (retrying)", tail_error);
+		} else if self.process_dead {
+			self.node_inactive = true;
+			node_status_string = format!("PROCESS DEAD (PID {})", self.node_process_id.unwrap_or(0));
+		} else if self.node_status == NodeStatus::Shunned {
+			node_status_string = format!(
+				"Shunned x{} ({})",
+				self.shun_notifications, self.node_bad_behaviour
+			);
+		} else if let Some(snapshot_age) = self.snapshot_age() {
+			// Snapshots arrive late, so judge activity by the log timestamps alone
+			let snapshot_stale_timeout = Duration::seconds(OPT.lock().unwrap().snapshot_stale_timeout);
+			self.node_inactive = snapshot_age > snapshot_stale_timeout;
+			node_status_string = format!(
+				"{} (snapshot {})",
+				if self.node_inactive { "INACTIVE" } else { node_status_string.as_str() },
+				get_duration_text(snapshot_age)
+			);
+		} else if let Some(metadata) = &self.entry_metadata {
+			// When replaying, activity is judged by the log timestamps alone
+			let idle_time = match replay_time() {
+				Some(replay_time) => replay_time - metadata.message_time,
+				None => Utc::now() - metadata.system_time,
+			};
+			if idle_time > node_inactive_timeout {
+				self.node_inactive = true;
+				node_status_string = format!("INACTIVE ({})", get_duration_text(idle_time));
+			} else {
+				self.node_inactive = false;
+			}
+		}
+
+		self.node_status_string = node_status_string;
+	}
+
+	/// For a snapshot source, the time since its most recent log entry
+	pub fn snapshot_age(&self) -> Option<Duration> {
+		if !self.is_snapshot_source {
+			return None;
+		}
+		self
+			.entry_metadata
+			.as_ref()
+			.map(|metadata| dashboard_time() - metadata.message_time)
+	}
+
+	fn reset_metrics(&mut self) {
+		self.node_status = NodeStatus::Started;
+		self.activity_gets = MmmStat::new();
+		self.activity_puts = MmmStat::new();
+		self.activity_errors = MmmStat::new();
+		self.error_categories.clear();
+		self.top_messages.clear();
+		self.storage_cost = MmmStat::new();
+		self.peers_connected = MmmStat::new();
+		self.storage_cost_recent = DecayedAverage::default();
+		self.peers_connected_recent = DecayedAverage::default();
+		self.storage_cost_percentiles = PercentileSketch::default();
+		self.memory_used_mb = MmmStat::new();
+		self.custom_metrics.clear();
+	}
+
+	///! Process a line from a  Node logfile.
+	///! Use a created LogMeta to update metrics.
+	pub fn gather_metrics(&mut self, line: &str, patterns: &LinePatterns) -> Result<(), std::io::Error> {
+		profile_scope!("gather_metrics");
+		let entry = LogEntry {
+			logstring: String::from(line),
+		};
+		let entry_metadata = self.entry_metadata.as_ref().unwrap().clone();
+		let entry_time = entry_metadata.message_time;
+
+		debug_log!(format!("gather_metrics() entry_time: {:?}", entry_time).as_str());
+
+		self.update_timelines(&entry_time);
+		if self.first_entry_time.is_none() {
+			self.first_entry_time = Some(entry_time);
+		}
+		self.parser_output = entry_metadata.parser_output.clone();
+		self.process_logfile_entry(&entry.logstring, &entry_metadata, patterns); // May overwrite self.parser_output
+		self.restart_history.note_entry(&entry_time);
+		self.daily_totals.record(
+			&entry_time,
+			DayTotals {
+				attos_earned: self.attos_earned.total,
+				puts: self.activity_puts.total,
+				gets: self.activity_gets.total,
+				errors: self.activity_errors.total,
+				records_stored: self.records_stored,
+			},
+		);
+
+		// --debug-window - prints parser results for every logfile to a temp logfile
+		// shown by the Debug view (the debug window follows the node with focus).
+		debug_log!(&self.parser_output.clone());
+
+		Ok(())
+	}
+
+	pub fn update_timelines(&mut self, now: &DateTime<Utc>) {
+		self.app_timelines.update_timelines(now);
+	}
+
+	///! Return a LogMeta and capture metadata for logfile node start:
+	///!	'Running safenode v0.98.32' or 'Running antnode v0.1.0'
+	pub fn parse_start(&mut self, line: &String, entry_metadata: &LogMeta, patterns: &LinePatterns) -> bool {
+		let running_prefix = if line.starts_with("Running antnode ") {
+			String::from("Running antnode ")
+		} else {
+			String::from("Running safenode ")
+		};
+
+		if line.starts_with(&running_prefix) {
+			self.set_node_status(NodeStatus::Started);
+			let message = line.to_string();
+			let version = String::from(line[running_prefix.len()..].to_string());
+			self.node_started = Some(entry_metadata.message_time);
+			self.parser_output = format!(
+				"START node {} at {}",
+				String::from(version.clone()),
+				self
+					.node_started
+					.map_or(String::from("None"), |m| format!("{}", m))
+			);
+
+			self
+				.restart_history
+				.record_start(&entry_metadata.message_time, &version);
+			self.running_message = Some(message);
+			self.running_version = Some(version);
+			self.reset_metrics();
+			return true;
+		}
+
+		let process_id_prefix = "Node (PID: ";
+		if patterns.contains(LogPattern::NodePid) {
+			self.node_process_id = self.parse_u64(process_id_prefix, line);
+			self.process_dead = false;
+			let process_id = match &self.node_process_id {
+				Some(process_id) => process_id.to_string(),
+				None => String::from("unknown"),
+			};
+
+			if let Some(peer_id) = self.parse_string("PeerId: ", line) {
+				self.parser_output = format!(
+					"Node pid: {} peer_id: {}",
+					String::from(process_id.clone()),
+					peer_id
+				);
+				self.node_peer_id = Some(peer_id);
+			}
+			return true;
+		}
+
+		false
+	}
+
+	///! Process a logfile entry
+	///! Returns true if node is being shunned, or the line has been processed and can be discarded
+	pub fn process_logfile_entry(&mut self, line: &String, entry_metadata: &LogMeta, patterns: &LinePatterns) -> bool {
+		self.parse_token_unit(line);
+		self.parser_stats.record(line, patterns);
+		self.parse_custom_metrics(line, &entry_metadata.message_time, patterns);
+		return self.apply_rules(&TIMED_DATA_RULES, line, entry_metadata, patterns)
+			|| self.parse_states(&line, &entry_metadata, patterns)
+			|| self.parse_start(&line, &entry_metadata, patterns);
+	}
+
+	/// Apply the first of the rules whose pattern is in the line, returning its result
+	fn apply_rules(&mut self, rules: &[LineRule], line: &String, entry_metadata: &LogMeta, patterns: &LinePatterns) -> bool {
+		match rules.iter().find(|(pattern, _)| patterns.contains(*pattern)) {
+			Some((_, handler)) => handler(self, line, entry_metadata),
+			None => false,
+		}
+	}
+
+	fn handle_record_retrieved(&mut self, _line: &String, entry_metadata: &LogMeta) -> bool {
+		self.count_get(&entry_metadata.message_time);
+		self.set_node_status(NodeStatus::Connected);
+		true
+	}
+
+	fn handle_record_stored(&mut self, _line: &String, entry_metadata: &LogMeta) -> bool {
+		self.count_put(&entry_metadata.message_time);
+		self.set_node_status(NodeStatus::Connected);
+		true
+	}
+
+	fn handle_storage_cost(&mut self, line: &String, entry_metadata: &LogMeta) -> bool {
+		if let Some(storage_cost) = self.parse_u128("Cost is now ", line) {
+			let storage_cost = self.token_unit.to_attos(storage_cost);
+			// Ignore storage cost of zero as that means the record is already paid for
+			if storage_cost > 0 {
+				self.count_storage_cost(&entry_metadata.message_time, storage_cost);
+				self.parser_output = format!("Storage cost: {}", storage_cost);
+			}
+		};
+		false // Continue processing for records stored (parse_records_stored())
+	}
+
+	fn handle_payment(&mut self, line: &String, entry_metadata: &LogMeta) -> bool {
+		// Older logs wrap the amount, as in 'NanoTokens(10) nanos'
+		let amount_line = line.replace("NanoTokens(", "").replace("AttoTokens(", "");
+		if let Some(amount) = self.parse_u128("Total payment of", &amount_line) {
+			let attos_earned = self.token_unit.to_attos(amount);
+			let record = line
+				.split_once("for record")
+				.map(|(_, record)| record.trim().to_string())
+				.filter(|record| !record.is_empty());
+			self.count_attos_earned(&entry_metadata.message_time, attos_earned, record);
+			self.parser_output = format!("Payment received: {}", attos_earned);
+			return true;
+		};
+		false
+	}
+
+	fn handle_peers_in_routing_table(&mut self, line: &String, entry_metadata: &LogMeta) -> bool {
+		let mut parser_output = String::from("connected peers:");
+		if let Some(peers_connected) = self.parse_u64("PeersInRoutingTable(", line) {
+			self.count_peers_connected(&entry_metadata.message_time, peers_connected);
+			parser_output = format!("{} {}", &parser_output, peers_connected);
+		};
+		self.parser_output = parser_output;
+		true
+	}
+
+	fn handle_shunned(&mut self, line: &String, _entry_metadata: &LogMeta) -> bool {
+		let mut parser_output = String::from("Node being SHUNNED");
+		self.set_node_status(NodeStatus::Shunned);
+		self.shun_notifications = self.shun_notifications + 1;
+		if let Some(bad_behaviour) = self.parse_string("due to \"", line) {
+			self.node_bad_behaviour = bad_behaviour.clone();
+			parser_output = format!("Shunned due to '{}'", bad_behaviour);
+		};
+		self.parser_output = parser_output;
+		true
+	}
+
+	/// Note the unit of amounts named by a payment line, or else implied by the binary a node is running
+	fn parse_token_unit(&mut self, line: &str) {
+		if let Some(token_unit) = TokenUnit::of_line(line) {
+			if line.contains("Total payment of") || self.attos_earned.total == 0 {
+				self.token_unit = token_unit;
+			}
+		}
+	}
+
+	/// Check every line for custom metrics, whatever else it contains
+	fn parse_custom_metrics(&mut self, line: &str, entry_time: &DateTime<Utc>, patterns: &LinePatterns) {
+		for metric in patterns.custom_metrics().iter().map(|index| &CUSTOM_METRICS[*index]) {
+			if let Some(value) = metric.value_in(line) {
+				self.custom_metrics.entry(metric.key.clone()).or_insert_with(MmmStat::new).add_sample(value);
+				self.apply_timeline_sample(&metric.key, entry_time, value.into());
+			}
+		}
+	}
+
+	// Set status unless currently shunned
+	fn set_node_status(&mut self, new_status: NodeStatus) {
+		if self.node_status != NodeStatus::Shunned {
+			self.node_status = new_status;
+		}
+	}
+
+	///! Update data metrics from a handler response logfile entry
+	///! Returns true if the line has been processed and can be discarded
+	fn parse_string(&mut self, prefix: &str, line: &String) -> Option<String> {
+		let mut string = "";
+		if let Some(mut string_start) = line.find(prefix) {
+			string_start += prefix.len();
+
+			if let Some(string_end) = line[string_start..].find("\"") {
+				string = line.as_str()[string_start..string_start + string_end].as_ref()
+			} else {
+				string = line.as_str()[string_start..].as_ref()
+			}
+			if string.is_empty() {
+				self.parse_failures += 1;
+				self.parser_output = format!("failed to parse string after {} in: {}", prefix, line);
+			}
+		};
+
+		if string.len() > 0 {
+			Some(String::from(string))
+		} else {
+			None
+		}
+	}
+
+	///! Capture state updates from a logfile entry
+	///! Returns true if the line has been processed and can be discarded
+	fn parse_states(&mut self, line: &String, entry_metadata: &LogMeta, patterns: &LinePatterns) -> bool {
+		if entry_metadata.category.eq("ERROR") {
+			self.count_error(&entry_metadata.message_time, line);
+		}
+		self.top_messages.record(&entry_metadata.category, &entry_metadata.message, &entry_metadata.message_time);
+
+		// if content.contains("Skipping ") {
+		// 	let mut parser_output = String::from("Connected ({} lag)");
+		// 	if let Some(events_skipped) = self.parse_usize("Skipping ", content) {
+		// 		parser_output = format!("{} ({})", &parser_output, events_skipped);
+		// 	};
+		// 	self.parser_output = parser_output;
+		// 	return true;
+		// }
+
+		self.apply_rules(&STATE_RULES, line, entry_metadata, patterns)
+	}
+
+	// Node Status
+	fn handle_events_channel_closed(&mut self, _line: &String, _entry_metadata: &LogMeta) -> bool {
+		self.set_node_status(NodeStatus::Stopped);
+		self.parser_output = String::from("Node status: Disconnected");
+		true
+	}
+
+	fn handle_quoting_metrics(&mut self, line: &String, _entry_metadata: &LogMeta) -> bool {
+		self.parse_records_stored(line);
+		true
+	}
+
+	// Metrics
+	fn handle_logged_metrics(&mut self, line: &String, entry_metadata: &LogMeta) -> bool {
+		let &content = &line.as_str();
+		self.latest_logged_metrics = Some(entry_metadata.message_time);
+		self.resources_sampled = false;
+		// System
+		let mut parser_output = String::from("system_cpu_usage_percent:");
+		if let Some(system_cpu) = self.parse_float32("system_cpu_usage_percent\":", content) {
+			self.system_cpu = system_cpu;
+			parser_output = format!("{} gl_cpu: {}", &parser_output, system_cpu);
+		};
+		if let Some(system_memory) = self.parse_float32("system_total_memory_mb\":", content) {
+			self.system_memory = system_memory;
+			parser_output = format!("{} , System Memory: {}", &parser_output, system_memory);
+		};
+		if let Some(system_memory_used_mb) = self.parse_float32("system_memory_used_mb\":", content) {
+			self.system_memory_used_mb = system_memory_used_mb;
+			parser_output = format!(
+				"{} , System Memory Use (MB): {}",
+				&parser_output, system_memory_used_mb
+			);
+		};
+		if let Some(system_memory_usage_percent) =
+			self.parse_float32("system_memory_usage_percent\":", content)
+		{
+			self.system_memory_usage_percent = system_memory_usage_percent;
+			parser_output = format!(
+				"{} , System Memory Use (%): {}",
+				&parser_output, system_memory_usage_percent
+			);
+		};
+
+		// Networking
+		if let Some(interface_name) = self.parse_word("interface_name\":", content) {
+			self.interface_name = String::from(interface_name.clone());
+			parser_output = format!("{} , interface_name: {}", &parser_output, interface_name);
+		};
+		if let Some(bytes_received) = self.parse_u64("bytes_received\":", content) {
+			self.bytes_received = bytes_received;
+			parser_output = format!("{} , bytes_received: {}", &parser_output, bytes_received);
+		};
+		if let Some(bytes_transmitted) = self.parse_u64("bytes_transmitted\":", content) {
+			self.bytes_transmitted = bytes_transmitted;
+			parser_output = format!(
+				"{} , bytes_transmitted: {}",
+				&parser_output, bytes_transmitted
+			);
+		};
+		if let Some(total_mb_received) = self.parse_float32("total_mb_received\":", content) {
+			self.total_mb_received = total_mb_received;
+			parser_output = format!(
+				"{} , total_mb_received: {}",
+				&parser_output, total_mb_received
+			);
+		};
+		if let Some(total_mb_transmitted) = self.parse_float32("total_mb_transmitted\":", content) {
+			self.total_mb_transmitted = total_mb_transmitted;
+			parser_output = format!(
+				"{} , total_mb_transmitted: {}",
+				&parser_output, total_mb_transmitted
+			);
+		};
+
+		// Node Resources
+		if let Some(cpu_usage_percent) = self.parse_float32("\"cpu_usage_percent\":", content) {
+			self.cpu_usage_percent = cpu_usage_percent;
+			if cpu_usage_percent > self.cpu_usage_percent_max {
+				self.cpu_usage_percent_max = cpu_usage_percent;
+			}
+			parser_output = format!(
+				"{}  cpu: {}, cpu_max {}",
+				&parser_output, cpu_usage_percent, self.cpu_usage_percent_max
+			);
+		};
+		if let Some(memory_used_mb) = self.parse_float32("\"memory_used_mb\":", content) {
+			self.count_memory_used_mb(&entry_metadata.message_time, memory_used_mb as u64);
+			parser_output = format!("{} , memory: {}", &parser_output, memory_used_mb);
+		};
+		if let Some(bytes_read) = self.parse_u64("bytes_read\":", content) {
+			self.bytes_read = bytes_read;
+			parser_output = format!("{} , bytes_read: {}", &parser_output, bytes_read);
+		};
+		if let Some(bytes_written) = self.parse_u64("bytes_written\":", content) {
+			self.bytes_written = bytes_written;
+			parser_output = format!("{} , bytes_written: {}", &parser_output, bytes_written);
+		};
+		if let Some(total_mb_read) = self.parse_float32("total_mb_read\":", content) {
+			self.total_mb_read = total_mb_read;
+			parser_output = format!("{} , total_mb_read: {}", &parser_output, total_mb_read);
+		};
+		if let Some(total_mb_written) = self.parse_float32("total_mb_written\":", content) {
+			self.total_mb_written = total_mb_written;
+			parser_output = format!(
+				"{} , total_mb_written: {}",
+				&parser_output, total_mb_written
+			);
+		};
+
+		self.parser_output = parser_output;
+		true
+	}
+
+	// Misc stats
+	fn handle_wallet_balance(&mut self, line: &String, _entry_metadata: &LogMeta) -> bool {
+		let &content = &line.as_str();
+		let mut parser_output = String::from("");
+
+		if let Some(wallet_balance) = self.parse_u128("wallet balance is ", content) {
+			self.wallet_balance = wallet_balance;
+			parser_output = format!("{} , wallet_balance: {}", &parser_output, wallet_balance);
+		};
+		if let Some(latest_earning) = self.parse_u128("after earning ", content) {
+			self.latest_earning = latest_earning;
+			parser_output = format!("{} , latest_earning: {}", &parser_output, latest_earning);
+		};
+		self.parser_output = parser_output;
+		true
+	}
+
+	// fn parse_usize(&mut self, prefix: &str, content: &str) -> Option<usize> {
+	// 	if let Some(position) = content.find(prefix) {
+	// 		let word: Vec<&str> = content[position + prefix.len()..]
+	// 			.trim()
+	// 			.splitn(2, |c| c == ' ' || c == ',' || c== '}')
+	// 			.collect();
+	// 		if word.len() > 0 {
+	// 			match word[0].parse::<usize>() {
+	// 				Ok(value) => return Some(value),
+	// 				Err(_e) => self.parser_output = format!("failed to parse '{}' as usize from: '{}'", word[0], &content[position + prefix.len()..]),
+	// 			}
+	// 		}
+	// 	}
+	// 	None
+	// }
+
+	///! Capture the records stored from the QuotingMetrics of a payment quote or storage
+	///! cost line (e.g. 'close_records_stored: 1234, ... max_records: 16384'), or the
+	///! bytes stored (used_space and max_capacity) given instead by older logs
+	fn parse_records_stored(&mut self, line: &str) {
+		let mut parser_output = Vec::<String>::new();
+		if let Some(records_stored) = self.parse_u64("records_stored: ", line) {
+			self.records_stored = records_stored;
+			parser_output.push(format!("Records stored: {}", records_stored));
+		}
+		if let Some(records_max) = self.parse_u64("max_records: ", line) {
+			self.records_max = records_max;
+			parser_output.push(format!("Max records: {}", records_max));
+		}
+		if let Some(records_per_type) = parse_records_per_type(line) {
+			parser_output.push(format!("Records per type: {:?}", records_per_type));
+			self.records_per_type = records_per_type;
+		}
+		if let Some(space_used) = self.parse_u64("used_space: ", line) {
+			self.space_used = space_used;
+			parser_output.push(format!("Space used: {}", space_used));
+		}
+		if let Some(space_max) = self.parse_u64("max_capacity: ", line) {
+			self.space_max = space_max;
+			parser_output.push(format!("Max space: {}", space_max));
+		}
+		if !parser_output.is_empty() {
+			self.parser_output = parser_output.join(", ");
+		}
+	}
+
+	fn parse_u64(&mut self, prefix: &str, content: &str) -> Option<u64> {
+		self.parse_integer(prefix, content)
+	}
+
+	fn parse_u128(&mut self, prefix: &str, content: &str) -> Option<u128> {
+		self.parse_integer(prefix, content)
+	}
+
+	fn parse_integer<T: std::str::FromStr>(&mut self, prefix: &str, content: &str) -> Option<T> {
+		if let Some(position) = content.find(prefix) {
+			let word: Vec<&str> = content[position + prefix.len()..]
+				.trim()
+				.splitn(2, |c| c == ' ' || c == ',' || c == '}' || c == ')')
+				.collect();
+			if word.len() > 0 {
+				match word[0].parse::<T>() {
+					Ok(value) => return Some(value),
+					Err(_e) => {
+						self.parse_failures += 1;
+						self.parser_output = format!(
+							"failed to parse '{}' as {} from: '{}'",
+							word[0],
+							std::any::type_name::<T>(),
+							&content[position + prefix.len()..]
+						)
+					}
+				}
+			}
+		}
+		None
+	}
+
+	fn parse_float32(&mut self, prefix: &str, content: &str) -> Option<f32> {
+		if let Some(position) = content.find(prefix) {
+			let word: Vec<&str> = content[position + prefix.len()..]
+				.trim()
+				.splitn(2, |c| c == ' ' || c == ',' || c == '}')
+				.collect();
+			if word.len() > 0 {
+				match word[0].parse::<f32>() {
+					Ok(value) => return Some(value),
+					Err(_e) => {
+						self.parse_failures += 1;
+						self.parser_output = format!(
+							"failed to parse '{}' as float from: '{}'",
+							word[0],
+							&content[position + prefix.len()..]
+						)
+					}
+				}
+			}
+		}
+		None
+	}
+
+	fn parse_word(&mut self, prefix: &str, content: &str) -> Option<String> {
+		if let Some(start) = content.find(prefix) {
+			let word: Vec<&str> = content[start + prefix.len()..]
+				.trim_start()
+				.splitn(2, |c| c == ' ' || c == ',' || c == '}')
+				.collect();
+			if word.len() > 0 {
+				return Some(word[0].to_string());
+			} else {
+				self.parse_failures += 1;
+				self.parser_output = format!("failed to parse word at: '{}'", &content[start..]);
+			}
+		}
+		None
+	}
+
+	fn count_get(&mut self, time: &DateTime<Utc>) {
+		self.activity_gets.add_sample(1);
+		self.apply_timeline_sample(GETS_TIMELINE_KEY, time, 1);
+	}
+
+	fn count_put(&mut self, time: &DateTime<Utc>) {
+		self.activity_puts.add_sample(1);
+		self.apply_timeline_sample(PUTS_TIMELINE_KEY, time, 1);
+	}
+
+	fn count_error(&mut self, time: &DateTime<Utc>, line: &str) {
+		self.activity_errors.add_sample(1);
+		*self.error_categories.entry(category_of(&ERROR_CATEGORIES, line).to_string()).or_insert(0) += 1;
+		self.apply_timeline_sample(ERRORS_TIMELINE_KEY, time, 1);
+	}
+
+	fn count_attos_earned(&mut self, time: &DateTime<Utc>, attos_earned: u128, record: Option<String>) {
+		self.attos_earned.add_sample(attos_earned);
+		self.earnings_ledger.record_payment(time, attos_earned, record);
+		self.apply_timeline_sample(EARNINGS_TIMELINE_KEY, time, attos_earned);
+	}
+
+	fn count_storage_cost(&mut self, time: &DateTime<Utc>, storage_cost: u128) {
+		self.storage_cost.add_sample(storage_cost);
+		self.storage_cost_recent.add_sample(time, storage_cost as f64);
+		self.storage_cost_percentiles.add(storage_cost);
+		self.apply_timeline_sample(STORAGE_COST_TIMELINE_KEY, time, storage_cost);
+	}
+
+	fn count_peers_connected(&mut self, time: &DateTime<Utc>, connections: u64) {
+		self.peers_connected.add_sample(connections);
+		self.peers_connected_recent.add_sample(time, connections as f64);
+		self.apply_timeline_sample(CONNECTIONS_TIMELINE_KEY, time, connections.into());
+	}
+
+	/// Use CPU and memory measured from the node process in place of logged metrics
+	pub fn apply_process_sample(&mut self, time: &DateTime<Utc>, sample: &ProcessSample) {
+		self.cpu_usage_percent = sample.cpu_usage_percent;
+		if sample.cpu_usage_percent > self.cpu_usage_percent_max {
+			self.cpu_usage_percent_max = sample.cpu_usage_percent;
+		}
+		self.count_memory_used_mb(time, sample.memory_used_mb);
+		self.resources_sampled = true;
+	}
+
+	fn count_memory_used_mb(&mut self, time: &DateTime<Utc>, memory_used_mb: u64) {
+		self.memory_used_mb.add_sample(memory_used_mb);
+		self.apply_timeline_sample(RAM_TIMELINE_KEY, time, memory_used_mb.into());
+	}
+
+	fn apply_timeline_sample(&mut self, timeline_key: &str, time: &DateTime<Utc>, value: BucketValue) {
+		if let Some(timeline) = self.app_timelines.get_timeline_by_key(timeline_key) {
+			timeline.update_value(time, value);
+		}
+	}
+}
+
+/// Metadata for a logfile line
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LogMeta {
+	pub category: String, // First word ('INFO', 'WARN' etc.)
+	pub message_time: DateTime<Utc>,
+	pub system_time: DateTime<Utc>,
+	pub source: String,
+	pub message: String,
+
+	pub parser_output: String,
+}
+
+impl LogMeta {
+	pub fn clone(&self) -> LogMeta {
+		LogMeta {
+			category: self.category.clone(),
+			message_time: self.message_time,
+			system_time: self.system_time,
+			source: self.source.clone(),
+			message: self.message.clone(),
+			parser_output: self.parser_output.clone(),
+		}
+	}
+}
+
+/// The records of each data type from the QuotingMetrics in a line, given as
+/// 'records_per_type: [(0, 1200), (3, 4)]'
+fn parse_records_per_type(line: &str) -> Option<Vec<(u32, u64)>> {
+	let (_, list) = line.split_once("records_per_type: [")?;
+	let (list, _) = list.split_once(']')?;
+	list.split(')')
+		.map(|pair| pair.trim_start_matches([',', ' ', '(']))
+		.filter(|pair| !pair.is_empty())
+		.map(|pair| {
+			let (data_type, count) = pair.split_once(',')?;
+			Some((data_type.trim().parse().ok()?, count.trim().parse().ok()?))
+		})
+		.collect()
+}
+
+/// The last max_lines lines of a logfile, reading back from the end in blocks
+/// so that only the tail of a large logfile is read
+fn read_tail_lines<R: std::io::Read + std::io::Seek>(reader: &mut R, max_lines: usize) -> std::io::Result<Vec<String>> {
+	use std::io::{BufRead, Cursor, SeekFrom};
+	const TAIL_BLOCK_SIZE: u64 = 64 * 1024;
+
+	let mut start = reader.seek(SeekFrom::End(0))?;
+	let mut tail = Vec::<u8>::new();
+	// A line ending before the first line wanted shows that it is complete
+	while start > 0 && tail.iter().filter(|byte| **byte == b'\n').count() <= max_lines {
+		let block_start = start.saturating_sub(TAIL_BLOCK_SIZE);
+		let mut block = vec![0; (start - block_start) as usize];
+		reader.seek(SeekFrom::Start(block_start))?;
+		reader.read_exact(&mut block)?;
+		block.extend_from_slice(&tail);
+		tail = block;
+		start = block_start;
+	}
+
+	let mut tail = Cursor::new(tail);
+	if start > 0 {
+		tail.read_until(b'\n', &mut Vec::new())?; // Skip the partial line
+	}
+	let mut lines = Vec::new();
+	let mut bytes = Vec::new();
+	while let Some((line, _is_invalid)) = read_lossy_line(&mut tail, &mut bytes)? {
+		lines.push(line.into_owned());
+	}
+	if lines.len() > max_lines {
+		lines.drain(..lines.len() - max_lines);
+	}
+	Ok(lines)
+}
+
+/// Read the next line without its line ending, replacing any bytes which are
+/// not valid UTF-8 so that a corrupted logfile can still be loaded. Returns
+/// the line, borrowed from bytes unless it had invalid bytes, and whether it
+/// had any, or None at the end of the file.
+pub(crate) fn read_lossy_line<'a, R: std::io::BufRead>(
+	reader: &mut R,
+	bytes: &'a mut Vec<u8>,
+) -> std::io::Result<Option<(std::borrow::Cow<'a, str>, bool)>> {
+	bytes.clear();
+	if reader.read_until(b'\n', bytes)? == 0 {
+		return Ok(None);
+	}
+	Ok(Some(lossy_line(bytes)))
+}
+
+/// The metrics vdash gathers from the lines of a node's logfile, given in order, without
+/// a LogMonitor or the TUI. Lines before the first log message are skipped, and those
+/// continuing a message are taken with its metadata, as when vdash loads a logfile.
+///
+/// Options such as --custom-metric are vdash's defaults unless given beforehand with
+/// set_opt(). The command line and config file of the program calling this aren't read.
+pub fn parse_lines(lines: &[&str]) -> NodeMetrics {
+	let mut metrics = NodeMetrics::new();
+	for line in lines {
+		match LogEntry::decode_metadata(line) {
+			Some(entry_metadata) => metrics.entry_metadata = Some(entry_metadata),
+			None => {
+				metrics.lines_without_metadata += 1;
+				if metrics.entry_metadata.is_none() {
+					continue;
+				}
+			}
+		}
+		let _ = metrics.gather_metrics(line, &LinePatterns::of(line));
+	}
+	metrics
+}
+
+/// Used to build a history of what is in the log, one LogMeta per line
+pub struct LogEntry {
+	pub logstring: String, // One line of raw text from the logfile
+}
+
+impl LogEntry {
+	/// Decode metadata from logfile line when present. Example input lines:
+	/// " INFO 2022-01-15T20:21:02.659471Z [sn/src/node/routing/core/mod.rs:L211]:"
+	/// "	 ➤ Writing our latest PrefixMap to disk"
+	/// " ERROR 2022-01-15T20:21:07.643598Z [sn/src/node/routing/api/dispatcher.rs:L450]:"
+	pub fn decode_metadata(line: &str) -> Option<LogMeta> {
+		profile_scope!("decode_metadata");
+		if line.is_empty() {
+			return None;
+		}
+
+		if let Some(LinePrefix { time_string, category, source, message }) = split_log_line(line) {
+			let time_str: String;
+
+			let time_utc: DateTime<Utc>;
+
+			match TIMESTAMP_PARSER.with(|parser| parser.borrow_mut().parse(time_string)) {
+				Ok(time) => {
+					time_utc = time.with_timezone(&Utc);
+					time_str = format!("{}", time);
+				}
+				Err(e) => {
+					debug_log!(format!("ERROR parsing logfile time: {}", e).as_str());
+					return None;
+				}
+			};
+			let parser_output = format!(
+				"c: {}, t: {}, s: {}, m: {}",
+				category, time_str, source, message
+			);
+
+			return Some(LogMeta {
+				category: String::from(category),
+				message_time: time_utc,
+				system_time: Utc::now(),
+				source: String::from(source),
+				message: String::from(message),
+				parser_output,
+			});
+		}
+		None
+	}
+}
+
+#[cfg(test)]
+mod tests {
+
+	mod logfile_loading {
+		use std::borrow::Cow;
+		use std::io::Cursor;
+
+		use crate::custom::node_metrics::{read_lossy_line, read_tail_lines};
+
+		#[test]
+		fn it_loads_lines_which_are_not_valid_utf8() {
+			let mut log = Cursor::new(b"[2024-03-23T19:00:00Z INFO ant_node] ok\r\nbad \xff\xfe bytes\nlast".to_vec());
+			let mut bytes = Vec::new();
+			let line = read_lossy_line(&mut log, &mut bytes).unwrap();
+			assert_eq!(line, Some((Cow::Borrowed("[2024-03-23T19:00:00Z INFO ant_node] ok"), false)));
+			let (line, is_invalid) = read_lossy_line(&mut log, &mut bytes).unwrap().unwrap();
+			assert_eq!(line, "bad \u{FFFD}\u{FFFD} bytes");
+			assert!(is_invalid);
+			assert_eq!(read_lossy_line(&mut log, &mut bytes).unwrap(), Some((Cow::Borrowed("last"), false)));
+			assert_eq!(read_lossy_line(&mut log, &mut bytes).unwrap(), None);
+		}
+
+		#[test]
+		fn it_reads_the_tail_of_a_logfile() {
+			let log: String = (0..50_000).map(|i| format!("[2024-03-23T19:00:00Z INFO ant_node] Entry {}\n", i)).collect();
+			let lines = read_tail_lines(&mut Cursor::new(log.as_bytes()), 3).unwrap();
+			assert_eq!(lines.len(), 3);
+			assert!(lines[0].ends_with("Entry 49997"));
+			assert!(lines[2].ends_with("Entry 49999"));
+
+			let lines = read_tail_lines(&mut Cursor::new(log.as_bytes()), 5_000).unwrap();
+			assert_eq!(lines.len(), 5_000);
+			assert!(lines[0].ends_with("Entry 45000"));
+
+			let lines = read_tail_lines(&mut Cursor::new(b"first\nlast".to_vec()), 10).unwrap();
+			assert_eq!(lines, vec![String::from("first"), String::from("last")]);
+			assert!(read_tail_lines(&mut Cursor::new(Vec::new()), 10).unwrap().is_empty());
+		}
+	}
+
+	mod log_parsing {
+		use std::str::FromStr;
+
+		use chrono::{DateTime, Utc};
+
+		use crate::custom::node_metrics::{parse_records_per_type, LogEntry};
+
+		#[test]
+		fn it_parses() {
+			let message_time = "2024-03-23T19:38:32.350118Z";
+			let source = "sn_networking::event";
+			let category = "WARN";
+			let message = "MsgReceivedError: InternalMsgChannelDropped";
+			let line = format!("[{} {} {}] {}", message_time, category, source, message);
+			let metadata = LogEntry::decode_metadata(&line).unwrap();
+
+			let message_time: DateTime<Utc> = DateTime::from_str(message_time).unwrap();
+
+			assert_eq!(metadata.category, category);
+			assert_eq!(metadata.message_time, message_time);
+			assert_eq!(metadata.source, source);
+			assert_eq!(metadata.message, message);
+		}
+
+		#[test]
+		fn it_parses_records_per_type() {
+			let line = "Cost is now 42 for quoting_metrics QuotingMetrics { close_records_stored: 13, records_per_type: [(0, 12), (3, 1)], max_records: 4096 }";
+			assert_eq!(parse_records_per_type(line), Some(vec![(0, 12), (3, 1)]));
+			assert_eq!(parse_records_per_type("records_per_type: [], max_records: 4096"), Some(vec![]));
+			assert_eq!(parse_records_per_type("records_per_type: [(0, x)]"), None);
+			assert_eq!(parse_records_per_type("close_records_stored: 13"), None);
+		}
+	}
+
+	mod stats {
+		use crate::custom::node_metrics::{MmmStat, NodeMetrics};
+
+		#[test]
+		fn it_totals_amounts_beyond_u64() {
+			let mut stat = MmmStat::<u128>::new();
+			stat.add_sample(u64::MAX as u128);
+			stat.add_sample(u64::MAX as u128);
+			assert_eq!(stat.total, 2 * u64::MAX as u128);
+			assert_eq!(stat.mean, u64::MAX as u128);
+
+			let mut counts = MmmStat::<u64>::new();
+			counts.add_sample(u64::MAX);
+			counts.add_sample(1);
+			assert_eq!(counts.total, u64::MAX);
+
+			let mut metrics = NodeMetrics::new();
+			assert_eq!(
+				metrics.parse_u128("Total payment of", "Total payment of 30000000000000000000 attos"),
+				Some(30_000_000_000_000_000_000)
+			);
+		}
+	}
+}
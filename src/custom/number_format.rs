@@ -0,0 +1,130 @@
+//! How counts and amounts are written in the UI (see --number-format)
+//!
+//! Large numbers such as GETS or attos are hard to read as raw integers, so
+//! they can instead be grouped in thousands (123,456,789) or abbreviated with
+//! SI prefixes to three significant figures (123M). Amounts of ANT smaller
+//! than one use the SI prefixes m, µ and n (e.g. 1.23µ).
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum NumberFormat {
+	#[default]
+	Plain,
+	Grouped,
+	Si,
+}
+
+impl NumberFormat {
+	pub fn parse(name: &str) -> Result<NumberFormat, String> {
+		match name.to_lowercase().as_str() {
+			"plain" => Ok(NumberFormat::Plain),
+			"grouped" => Ok(NumberFormat::Grouped),
+			"si" => Ok(NumberFormat::Si),
+			_ => Err(format!("unknown --number-format '{}' (use plain, grouped or si)", name)),
+		}
+	}
+}
+
+const LARGE_PREFIXES: [&str; 6] = ["k", "M", "G", "T", "P", "E"];
+const SMALL_PREFIXES: [&str; 3] = ["m", "µ", "n"];
+
+/// A count, such as records or GETS, or a whole number of attos
+pub fn format_count(format: NumberFormat, value: u128) -> String {
+	match format {
+		NumberFormat::Plain => value.to_string(),
+		NumberFormat::Grouped => group_thousands(&value.to_string()),
+		NumberFormat::Si => si_string(value as f64, 0),
+	}
+}
+
+/// An amount with a fractional part, such as ANT, given to decimals places unless abbreviated
+pub fn format_amount(format: NumberFormat, value: f64, decimals: usize) -> String {
+	match format {
+		NumberFormat::Plain => format!("{:.*}", decimals, value),
+		NumberFormat::Grouped => {
+			let text = format!("{:.*}", decimals, value);
+			match text.split_once('.') {
+				Some((whole, fraction)) => format!("{}.{}", group_thousands(whole), fraction),
+				None => group_thousands(&text),
+			}
+		}
+		NumberFormat::Si => si_string(value, decimals),
+	}
+}
+
+/// Digits with a comma between each group of three, keeping any leading '-'
+fn group_thousands(digits: &str) -> String {
+	let (sign, digits) = digits.strip_prefix('-').map_or(("", digits), |digits| ("-", digits));
+	let mut grouped = String::new();
+	for (index, digit) in digits.chars().enumerate() {
+		if index > 0 && (digits.len() - index) % 3 == 0 {
+			grouped.push(',');
+		}
+		grouped.push(digit);
+	}
+	format!("{}{}", sign, grouped)
+}
+
+/// Value to three significant figures with an SI prefix, or as it is (to decimals places)
+/// when between 1 and 1000, or zero
+fn si_string(value: f64, decimals: usize) -> String {
+	let magnitude = value.abs();
+	if magnitude == 0.0 || (1.0..1000.0).contains(&magnitude) || (magnitude < 1.0 && decimals == 0) {
+		return format!("{:.*}", if magnitude.fract() == 0.0 { 0 } else { decimals.min(3) }, value);
+	}
+	let (scaled, prefix) = if magnitude >= 1000.0 {
+		let mut scaled = value;
+		let mut prefix = "";
+		for large_prefix in LARGE_PREFIXES {
+			if scaled.abs() < 999.5 {
+				break;
+			}
+			scaled /= 1000.0;
+			prefix = large_prefix;
+		}
+		(scaled, prefix)
+	} else {
+		let mut scaled = value;
+		let mut prefix = "";
+		for small_prefix in SMALL_PREFIXES {
+			if scaled.abs() >= 1.0 {
+				break;
+			}
+			scaled *= 1000.0;
+			prefix = small_prefix;
+		}
+		(scaled, prefix)
+	};
+	let places = match scaled.abs() {
+		scaled if scaled >= 99.95 => 0,
+		scaled if scaled >= 9.995 => 1,
+		_ => 2,
+	};
+	format!("{:.*}{}", places, scaled, prefix)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn it_formats_numbers_plainly_grouped_or_with_si_prefixes() {
+		assert_eq!(format_count(NumberFormat::Plain, 123456789), "123456789");
+		assert_eq!(format_count(NumberFormat::Grouped, 123456789), "123,456,789");
+		assert_eq!(format_count(NumberFormat::Grouped, 12345), "12,345");
+		assert_eq!(format_count(NumberFormat::Grouped, 999), "999");
+		assert_eq!(format_count(NumberFormat::Si, 123456789), "123M");
+		assert_eq!(format_count(NumberFormat::Si, 1234567), "1.23M");
+		assert_eq!(format_count(NumberFormat::Si, 12345), "12.3k");
+		assert_eq!(format_count(NumberFormat::Si, 999_999), "1.00M");
+		assert_eq!(format_count(NumberFormat::Si, 42), "42");
+		assert_eq!(format_count(NumberFormat::Si, 0), "0");
+
+		assert_eq!(format_amount(NumberFormat::Plain, 0.000001234, 9), "0.000001234");
+		assert_eq!(format_amount(NumberFormat::Grouped, 12345.5, 9), "12,345.500000000");
+		assert_eq!(format_amount(NumberFormat::Si, 0.000001234, 9), "1.23µ");
+		assert_eq!(format_amount(NumberFormat::Si, 0.25, 9), "250m");
+		assert_eq!(format_amount(NumberFormat::Si, 12.5, 9), "12.500");
+		assert_eq!(format_amount(NumberFormat::Si, 0.0, 9), "0");
+		assert!(NumberFormat::parse("roman").is_err());
+	}
+}
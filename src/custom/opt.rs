@@ -4,6 +4,8 @@ pub static MIN_TIMELINE_STEPS: usize = 10;
 
 pub use structopt::StructOpt;
 
+use super::config_file::config_args;
+
 #[derive(StructOpt, Debug)]
 #[structopt(
 	about = "Monitor Autonomi Network nodes in the terminal.\nNavigate using tab and arrow keys."
@@ -13,6 +15,22 @@ pub struct Opt {
 	#[structopt(short = "l", long, default_value = "100")]
 	pub lines_max: usize,
 
+	/// Lines kept for each logfile while its node doesn't have focus. The rest, up to
+	/// --lines-max, are read from the end of the logfile when the node gains focus.
+	#[structopt(long, default_value = "10")]
+	pub lines_unfocused: usize,
+
+	/// Keep no lines of logfile text, only the metrics gathered from them, which saves a
+	/// lot of memory with hundreds of nodes. The Node Status logfile is shown as disabled.
+	#[structopt(long)]
+	pub no_log_text: bool,
+
+	/// Map logfiles of 64MB or more into memory when first loading them rather than
+	/// reading them, which is faster but ends vdash if a logfile is truncated while it
+	/// loads (as by logrotate with copytruncate)
+	#[structopt(long)]
+	pub mmap: bool,
+
 	/// Event update tick in milliseconds (controls screen refresh rate)
 	#[structopt(long, default_value = "200")]
 	pub tick_rate: u64,
@@ -21,6 +39,37 @@ pub struct Opt {
 	#[structopt(short, long, default_value = "210")]
 	pub timeline_steps: usize,
 
+	/// Timelines to show in Node Status, in order, as a comma separated list (e.g.
+	/// "earnings,errors,puts,gets,storage,connections" hides RAM). Timelines are:
+	/// earnings, storage, puts, gets, connections, ram and errors.
+	#[structopt(long)]
+	pub timelines: Option<String>,
+
+	/// Colour of a timeline as <timeline>=<colour>, where the colour is a name such as
+	/// LightRed or #rrggbb (e.g. "errors=LightRed"). Can be provided multiple times.
+	#[structopt(long, multiple = true)]
+	pub timeline_colour: Vec<String>,
+
+	/// Add a timescale to those the timelines can be zoomed through, as a number and unit
+	/// of s, m, h, d or w (e.g. "10s" or "6h" columns). Can be provided multiple times.
+	#[structopt(long, multiple = true)]
+	pub timescale: Vec<String>,
+
+	/// Add a metric with its own timeline and Summary column, as <name>=<regex>. The value
+	/// is the number in the regex's first capture group, or 1 for each matching line if it
+	/// has none. Append :cumulative to the name to add up the values, or :mmm to show min,
+	/// mean and max (e.g. "Replicated:cumulative=Replicated (\d+) records"). Can be
+	/// provided multiple times.
+	#[structopt(long, multiple = true)]
+	pub custom_metric: Vec<String>,
+
+	/// Classify node ERROR lines matching a regex into a category, as <category>=<regex>
+	/// (e.g. "storage=(?i)record|chunk"). Naming connection, storage or payment replaces
+	/// the built in regex for that category, and other names add a category. Lines
+	/// matching none are 'other'. Can be provided multiple times.
+	#[structopt(long, multiple = true)]
+	pub error_category: Vec<String>,
+
 	/// Ignore any existing logfile content
 	#[structopt(short, long)]
 	pub ignore_existing: bool,
@@ -32,11 +81,216 @@ pub struct Opt {
 	#[structopt(name = "glob-path", short, long, multiple = true)]
 	pub glob_paths: Vec<String>,
 
+	/// A 'glob' path (as for --glob-path) for logfiles which arrive as periodic snapshots
+	/// (e.g. via rsync or sshfs) rather than being written live. For these nodes activity
+	/// is judged from log timestamps only and the age of the snapshot is shown.
+	#[structopt(long, multiple = true)]
+	pub snapshot_glob_path: Vec<String>,
+
+	/// Logfiles on a remote machine to tail over SSH, as user@host:/path/glob (e.g.
+	/// "ant@vps1:/var/log/antnode/*/antnode.log"). Can be provided multiple times.
+	/// Uses the local ssh client, so keys must allow login without a password prompt.
+	#[structopt(long, multiple = true)]
+	pub remote: Vec<String>,
+
+	/// Accept logfile lines pushed over TCP or HTTP on this port of the loopback interface (or
+	/// on address:port, such as 0.0.0.0:4000), such as from 'tail -F antnode.log | nc host 4000'.
+	/// Each node's stream is shown as a node. There is no authentication.
+	#[structopt(long)]
+	pub listen: Option<String>,
+
+	/// Read the logs of systemd units matching a glob from journald (e.g. "antnode*"),
+	/// for nodes which log to the journal rather than files. Can be provided multiple times.
+	#[structopt(long, multiple = true)]
+	pub journal: Vec<String>,
+
+	/// Seconds since the latest log entry before a snapshot node is shown as INACTIVE
+	#[structopt(long, default_value = "1800")]
+	pub snapshot_stale_timeout: i64,
+
 	/// Enable periodic scan of any glob paths every so many seconds. 0 to disable.
 	#[structopt(long, default_value = "0")]
 	pub glob_scan: i64,
 
-	/// Set checkpoint interval in seconds (0 will disable checkpoints). vdash saves node statistics every few seconds so that it doesn't lose data when restarted.
+	/// Remove nodes whose logfile no longer exists after they have been inactive for this many
+	/// seconds, along with their checkpoints. 0 to disable.
+	#[structopt(long, default_value = "0")]
+	pub prune_after: i64,
+
+	/// Poll each node's wallet balance file every so many seconds, reconciling changes
+	/// with logged earnings to show withdrawals and flag mismatches. 0 to disable.
+	#[structopt(long, default_value = "0")]
+	pub wallet_poll: i64,
+
+	/// Wallet balance file relative to the node's root directory (the parent of the
+	/// directory containing its logfile). Holds a token amount (e.g. 1.5) or whole attos.
+	#[structopt(long, default_value = "wallet/balance")]
+	pub wallet_filename: String,
+
+	/// Check the free space of the device holding each node's root directory (the parent
+	/// of the directory containing its logfile) every so many seconds. 0 to disable.
+	#[structopt(long, default_value = "30")]
+	pub disk_poll: i64,
+
+	/// Alert when the free space of a node's device falls below this percentage
+	#[structopt(long, default_value = "10")]
+	pub disk_free_alert: f64,
+
+	/// Sum the size of each local node's record store every so many seconds, scanning
+	/// one node at a time spread over the interval. 0 to disable.
+	#[structopt(long, default_value = "0")]
+	pub store_poll: i64,
+
+	/// Record store directory relative to the node's root directory (see --store-poll)
+	#[structopt(long, default_value = "record_store")]
+	pub store_dirname: String,
+
+	/// Measure the CPU and memory of each local node's process (by the PID in its logfile)
+	/// when the logfile has no recent metrics lines, as with newer node releases.
+	#[structopt(long)]
+	pub process_metrics: bool,
+
+	/// Check every so many seconds that each local node's process (by the PID in its logfile)
+	/// is still running, showing its status as PROCESS DEAD if not. 0 to disable.
+	#[structopt(long, default_value = "5")]
+	pub pid_check: i64,
+
+	/// Node version to expect (e.g. 0.112.6). Nodes running a different version are
+	/// highlighted in the Summary. If not given, the version run by most nodes is expected.
+	#[structopt(long)]
+	pub latest_version: Option<String>,
+
+	/// Command used to control nodes with antctl (e.g. "antctl" or "sudo antctl").
+	/// Enables ctrl-r (restart), ctrl-t (stop) and ctrl-u (upgrade) for the selected node.
+	#[structopt(long)]
+	pub antctl_command: Option<String>,
+
+	/// Tag nodes whose logfile matches a glob, as <tag>=<glob> (e.g. "home=/home/*/antnode*/logs/*.log").
+	/// Can be provided multiple times. Tags can be used in the filter ('/').
+	#[structopt(long, multiple = true)]
+	pub tag: Vec<String>,
+
+	/// Monitor logfiles which match a glob in generic mode, for logs which are not from a
+	/// node (e.g. "/var/log/*.log" for auth.log and kern.log). These show counts of lines by
+	/// level and the rate lines arrive instead of node metrics. Can be provided multiple times.
+	#[structopt(long, multiple = true)]
+	pub generic: Vec<String>,
+
+	/// Group nodes in the Summary with subtotals for each group, by a component of the
+	/// logfile path (e.g. 2 puts "/mnt/vps1/antnode1/antnode.log" in group "vps1"), or by
+	/// 'host' to group remote nodes by the machine they are on.
+	#[structopt(long)]
+	pub group_by: Option<String>,
+
+	/// Put nodes whose logfile matches a glob in a group, as <group>=<glob> (e.g.
+	/// "vps1=/mnt/vps1/**/antnode.log"). Can be provided multiple times, and takes
+	/// precedence over --group-by.
+	#[structopt(long, multiple = true)]
+	pub group: Vec<String>,
+
+	/// Replay the logfiles rather than monitoring them. Use left and right arrows to step
+	/// back and forward through time, and space to play.
+	#[structopt(long)]
+	pub replay: bool,
+
+	/// Blank the dashboard after this many minutes without a keypress (0 never blanks).
+	/// Logfiles continue to be monitored and any key restores the dashboard.
+	#[structopt(long, default_value = "0")]
+	pub idle_minutes: u64,
+
+	/// Dim the dashboard instead of blanking it when idle (see --idle-minutes)
+	#[structopt(long)]
+	pub idle_dim: bool,
+
+	/// Show a line of totals and active nodes, updated in place without taking over the
+	/// terminal, instead of the dashboard (e.g. for a tmux status bar or small pane).
+	/// Stop with Ctrl-C.
+	#[structopt(long)]
+	pub mini: bool,
+
+	/// Lines shown by --mini. The first has the totals and the rest one active node each,
+	/// rotating through them. With one line the totals and a node share it.
+	#[structopt(long, default_value = "1")]
+	pub mini_lines: usize,
+
+	/// Colour Summary cells for this many seconds after their value changes, such as new
+	/// earnings in green and new errors in red (0 to disable)
+	#[structopt(long, default_value = "5")]
+	pub highlight_changes: u64,
+
+	/// How counts and amounts are written: plain (1234567), grouped (1,234,567) or si (1.23M)
+	#[structopt(long, default_value = "plain")]
+	pub number_format: String,
+
+	/// Keys to use: 'default', or 'vim' where j and k move down and up, and g and G go to the
+	/// top and bottom (with 'J' to jump to a node, 'K' to keep a sort key and 'z' for groups)
+	#[structopt(long, default_value = "default")]
+	pub keymap: String,
+
+	/// Bind a key to an action, as <key>=<action> (e.g. "ctrl-n=next-node" or "Q=none" to
+	/// unbind). Keys are a character or enter, esc, space, tab, up, down, left, right, pageup,
+	/// pagedown, home or end, optionally after ctrl-. See the README for the actions.
+	/// Can be provided multiple times.
+	#[structopt(long, multiple = true)]
+	pub key: Vec<String>,
+
+	/// Expected resource use of nodes as ram:<MB>,cpu:<percent> (e.g. "ram:600,cpu:40"). Nodes
+	/// using more are highlighted. Precede with <glob>= to apply only to matching logfiles.
+	/// Can be provided multiple times, with later values overriding earlier ones.
+	#[structopt(long, multiple = true)]
+	pub resource_envelope: Vec<String>,
+
+	/// Run a shell command when a node meets a condition, as <condition>=<command> (e.g.
+	/// "stopped=systemctl restart antnode@$NODE"). Conditions are stopped, inactive, errors>N
+	/// (errors in the last minute) and earnings. The command is given NODE_PATH, NODE, PEER_ID,
+	/// METRIC and VALUE in its environment. Can be provided multiple times.
+	#[structopt(long, multiple = true)]
+	pub trigger: Vec<String>,
+
+	/// When quitting, write a report of what each node did while vdash was open (earnings,
+	/// GETS, PUTS, errors and restarts) to this file
+	#[structopt(long)]
+	pub session_report: Option<String>,
+
+	/// Append events (payments, node status changes and alerts) to this file as they happen, one
+	/// JSON object per line, for use by your own scripts
+	#[structopt(long)]
+	pub events_out: Option<String>,
+
+	/// Keep a history of every node's metrics and events in this SQLite database, for analysis
+	/// over longer periods than the timelines hold
+	#[structopt(long)]
+	pub db: Option<String>,
+
+	/// Seconds between samples of node metrics written to the --db database
+	#[structopt(long, default_value = "60")]
+	pub db_interval: i64,
+
+	/// Push every node's metrics each tick over UDP to this host:port, such as an InfluxDB or
+	/// Telegraf UDP listener or a statsd server
+	#[structopt(long)]
+	pub metrics_push: Option<String>,
+
+	/// Format of metrics sent to --metrics-push: influx (line protocol) or statsd (gauges)
+	#[structopt(long, default_value = "influx")]
+	pub metrics_push_format: String,
+
+	/// Answer JSON-RPC requests from scripts on this unix socket path, or on a TCP host:port
+	/// (which needs --control-token), to query nodes and metrics and to run commands
+	#[structopt(long)]
+	pub control: Option<String>,
+
+	/// Token which must be given as "token" in each request to a TCP --control address
+	#[structopt(long)]
+	pub control_token: Option<String>,
+
+	/// Don't print messages to the console while starting (they are shown in the status line
+	/// and logged instead), for when output is captured by a supervisor such as systemd. Errors
+	/// which prevent vdash from starting are still printed.
+	#[structopt(long)]
+	pub quiet: bool,
+
+	/// Set checkpoint interval in seconds (0 will disable checkpoints). vdash saves node statistics every few seconds so that it doesn't lose data when restarted, including the history shown by the timelines.
 	#[structopt(long, default_value = "300")]
 	pub checkpoint_interval: u64,
 
@@ -45,11 +299,13 @@ pub struct Opt {
 	#[structopt(long, default_value = "-1")]
 	pub currency_token_rate: f64,
 
-	/// Fiat currency name for API
+	/// Fiat currency name for API, or a comma separated list (e.g. "USD,EUR,GBP").
+	/// Use 'c' to cycle through the currencies when more than one is given.
 	#[structopt(long, default_value = "USD")]
 	pub currency_apiname: String,
 
-	/// Single character symbol for currency (e.g. "£" or "€")
+	/// Single character symbol for currency (e.g. "£" or "€"), or a comma
+	/// separated list matching --currency-apiname (e.g. "$,€,£")
 	#[structopt(long, default_value = "$")]
 	pub currency_symbol: String,
 
@@ -69,14 +325,58 @@ pub struct Opt {
 	#[structopt(long, default_value = "30")]
 	pub coinmarketcap_interval: usize,
 
+	/// Cryptocompare.com API polling interval (minutes). This API does not need a key.
+	#[structopt(long, default_value = "10")]
+	pub cryptocompare_interval: usize,
+
+	/// Comma separated list of price APIs in order of preference. If one fails to provide
+	/// a price the next is tried. APIs which need a key are skipped if no key is given.
+	#[structopt(long, default_value = "coingecko,coinmarketcap,cryptocompare")]
+	pub price_apis: String,
+
 	/// One or more logfiles to monitor
 	#[structopt(name = "LOGFILE")]
 	pub files: Vec<String>,
 
-	/// Parses first logfile *only* and adds a debug output window (accessed with l/r arrow)
-	/// Also shows smaller debug output window to the right of the node view for the logfile
+	/// Adds a debug output window (accessed with l/r arrow). Also shows a smaller debug window
+	/// to the right of the node view, with the parser output of whichever node has focus
 	#[structopt(short, long)]
 	pub debug_window: bool,
+
+	/// Copy ERROR and WARN lines, and lines which can't be parsed, from every monitored logfile
+	/// into the debug window as they arrive (use with --debug-window)
+	#[structopt(long)]
+	pub anomalies: bool,
+
+	/// Append ERROR and WARN lines, and lines which can't be parsed, from every monitored
+	/// logfile to this file as they arrive
+	#[structopt(long)]
+	pub anomalies_file: Option<String>,
+
+	/// Record timing of parsing and rendering, printing a summary per subsystem on exit
+	#[structopt(long)]
+	pub profile: bool,
+
+	/// On exit, print how many lines of each node were matched by each parser rule and how
+	/// many by none, to spot rules which stopped matching after a change to the node's logs
+	#[structopt(long)]
+	pub parser_stats: bool,
+}
+
+/// Options from the command line, or from the config file when vdash is run without arguments.
+/// Only the vdash binary reads these, giving them to set_opt() before OPT is used.
+pub fn opt_from_args_or_config() -> Opt {
+	if std::env::args_os().len() <= 1 {
+		if let Some(args) = config_args() {
+			return Opt::from_iter(std::iter::once(get_app_name()).chain(args));
+		}
+	}
+	Opt::from_args()
+}
+
+/// vdash's default options, which OPT holds until set_opt() is given others
+pub fn default_opt() -> Opt {
+	Opt::from_iter([get_app_name()])
 }
 
 pub fn get_app_name() -> String {
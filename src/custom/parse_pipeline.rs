@@ -0,0 +1,106 @@
+//! Parsing of logfile lines by worker tasks, off the UI thread
+//!
+//! Decoding the metadata of a line and matching it against the log patterns
+//! are the costly parts of handling it, so with many busy nodes they are done
+//! by worker tasks rather than in the main loop. Each logfile's lines go to
+//! the same worker so they stay in order, and the parsed lines come back on
+//! one channel for the main loop to apply to the monitors, up to
+//! MAX_LINES_PER_BATCH at a time with the view updated once for them all.
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+use super::node_metrics::{LogEntry, LogMeta};
+use super::log_patterns::LinePatterns;
+
+/// Most parsed lines applied to the monitors between checks for input
+pub const MAX_LINES_PER_BATCH: usize = 1000;
+
+const MAX_WORKERS: usize = 4;
+
+/// A line from a logfile with what was found in it
+pub struct ParsedLine {
+	pub source: String,
+	pub line: String,
+	pub metadata: Option<LogMeta>, // None for lines which continue the previous message
+	pub patterns: LinePatterns,
+}
+
+impl ParsedLine {
+	pub fn parse(source: String, line: String) -> ParsedLine {
+		let metadata = LogEntry::decode_metadata(&line);
+		let patterns = LinePatterns::of(&line);
+		ParsedLine {
+			source,
+			line,
+			metadata,
+			patterns,
+		}
+	}
+}
+
+/// Sends lines to the workers, whose parsed lines arrive on the receiver given by spawn()
+pub struct ParsePipeline {
+	workers: Vec<UnboundedSender<(String, String)>>,
+}
+
+impl ParsePipeline {
+	/// Start a worker task for each CPU, up to MAX_WORKERS. Must be called within a tokio runtime.
+	pub fn spawn() -> (ParsePipeline, UnboundedReceiver<ParsedLine>) {
+		let worker_count = std::thread::available_parallelism().map_or(1, |count| count.get()).min(MAX_WORKERS);
+		let (parsed_tx, parsed_rx) = mpsc::unbounded_channel();
+		let workers = (0..worker_count)
+			.map(|_| {
+				let (line_tx, mut line_rx) = mpsc::unbounded_channel::<(String, String)>();
+				let parsed_tx = parsed_tx.clone();
+				tokio::spawn(async move {
+					while let Some((source, line)) = line_rx.recv().await {
+						if parsed_tx.send(ParsedLine::parse(source, line)).is_err() {
+							break;
+						}
+					}
+				});
+				line_tx
+			})
+			.collect();
+		(ParsePipeline { workers }, parsed_rx)
+	}
+
+	/// Queue a line from the logfile at source to be parsed
+	pub fn send(&self, source: String, line: String) {
+		let mut hasher = DefaultHasher::new();
+		source.hash(&mut hasher);
+		let worker = &self.workers[hasher.finish() as usize % self.workers.len()];
+		let _ = worker.send((source, line));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::custom::log_patterns::LogPattern;
+
+	#[tokio::test]
+	async fn it_parses_each_logfiles_lines_in_order() {
+		let (pipeline, mut parsed_rx) = ParsePipeline::spawn();
+		for i in 0..100 {
+			for source in ["node1", "node2"] {
+				let line = format!("[2024-03-23T19:00:{:02}.000000Z INFO ant_node] Wrote record {}", i % 60, i);
+				pipeline.send(String::from(source), line);
+			}
+		}
+		pipeline.send(String::from("node1"), String::from("  continued"));
+
+		let mut received = Vec::new();
+		while received.len() < 201 {
+			received.push(parsed_rx.recv().await.unwrap());
+		}
+		let node1: Vec<&ParsedLine> = received.iter().filter(|parsed| parsed.source == "node1").collect();
+		assert_eq!(node1.len(), 101);
+		assert!(node1[42].line.ends_with("Wrote record 42"));
+		assert!(node1[42].patterns.contains(LogPattern::RecordWritten));
+		assert_eq!(node1[42].metadata.as_ref().unwrap().category, "INFO");
+		assert!(node1[100].metadata.is_none());
+	}
+}
@@ -0,0 +1,122 @@
+//! How many lines of a node were matched by each parser rule
+//!
+//! When a node release changes the text of a log message, the rule looking for
+//! it silently stops matching and its metric stops changing. Counting the lines
+//! each rule matched, and those no rule matched, makes this easy to spot in the
+//! Node view's Diagnostics tab, or in the report printed by --parser-stats.
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::node_metrics::LogMonitor;
+use super::log_patterns::{LinePatterns, LogPattern};
+
+/// Name of the rule for the line giving the node's version when it starts
+pub const START_RULE: &str = "NodeStart";
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ParserStats {
+	pub lines: u64,
+	pub unmatched: u64,
+	rule_lines: HashMap<String, u64>,
+}
+
+impl ParserStats {
+	/// Count a line given to the parser rules, and each rule it matches
+	pub fn record(&mut self, line: &str, patterns: &LinePatterns) {
+		self.lines += 1;
+		let mut matched = false;
+		for pattern in LogPattern::all().filter(|pattern| patterns.contains(*pattern)) {
+			*self.rule_lines.entry(format!("{:?}", pattern)).or_insert(0) += 1;
+			matched = true;
+		}
+		if line.starts_with("Running antnode ") || line.starts_with("Running safenode ") {
+			*self.rule_lines.entry(String::from(START_RULE)).or_insert(0) += 1;
+			matched = true;
+		}
+		if !matched {
+			self.unmatched += 1;
+		}
+	}
+
+	/// Percentage of lines matched by at least one rule
+	pub fn coverage_percent(&self) -> f64 {
+		if self.lines == 0 {
+			return 0.0;
+		}
+		100.0 * (self.lines - self.unmatched) as f64 / self.lines as f64
+	}
+
+	/// Every rule and the lines it matched, those matching none first as
+	/// they are the ones which may have stopped working
+	pub fn rule_counts(&self) -> Vec<(String, u64)> {
+		let mut counts: Vec<(String, u64)> = LogPattern::all()
+			.map(|pattern| format!("{:?}", pattern))
+			.chain(std::iter::once(String::from(START_RULE)))
+			.map(|rule| {
+				let count = self.rule_lines.get(&rule).copied().unwrap_or(0);
+				(rule, count)
+			})
+			.collect();
+		counts.sort_by(|(a_rule, a_count), (b_rule, b_count)| a_count.cmp(b_count).then(a_rule.cmp(b_rule)));
+		counts
+	}
+
+	pub fn summary_text(&self) -> String {
+		format!(
+			"{} lines, {:.1}% matched, {} unmatched",
+			self.lines,
+			self.coverage_percent(),
+			self.unmatched
+		)
+	}
+}
+
+/// The parser stats of each node, in the order of the Summary
+pub fn parser_stats_report(monitors: &HashMap<String, LogMonitor>) -> String {
+	let mut nodes: Vec<&LogMonitor> = monitors.values().filter(|monitor| monitor.is_node()).collect();
+	nodes.sort_by_key(|monitor| monitor.index);
+
+	let mut report = String::from("Parser coverage (lines matched by each rule):\n");
+	for monitor in nodes {
+		let stats = &monitor.metrics.parser_stats;
+		report += &format!("\nNode {} {}\n  {}\n", monitor.index + 1, monitor.logfile, stats.summary_text());
+		for (rule, count) in stats.rule_counts() {
+			let never = if count == 0 { "  (never matched)" } else { "" };
+			report += &format!("  {:<22} {:>10}{}\n", rule, count, never);
+		}
+	}
+	report
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn it_counts_lines_by_rule_and_unmatched() {
+		let mut stats = ParserStats::default();
+		for line in [
+			"Running antnode v0.112.6",
+			"[2024-03-23T19:40:11.806641Z INFO ant_networking] Wrote record 0x01 to disk",
+			"[2024-03-23T19:40:12.806641Z INFO ant_networking] Wrote record 0x02 to disk",
+			"[2024-03-23T19:40:13.806641Z INFO ant_node] Cost is now 10 after Created payment quote for 0x03",
+			"[2024-03-23T19:40:14.806641Z INFO ant_node] Something new",
+		] {
+			stats.record(line, &LinePatterns::of(line));
+		}
+		assert_eq!(stats.lines, 5);
+		assert_eq!(stats.unmatched, 1);
+		assert_eq!(stats.coverage_percent(), 80.0);
+		assert_eq!(stats.summary_text(), "5 lines, 80.0% matched, 1 unmatched");
+
+		let counts = stats.rule_counts();
+		assert_eq!(counts.len(), LogPattern::all().count() + 1);
+		assert_eq!(counts.last().unwrap(), &(String::from("RecordWritten"), 2));
+		assert!(counts.contains(&(String::from(START_RULE), 1)));
+		assert!(counts.contains(&(String::from("StorageCost"), 1)));
+		assert!(counts.contains(&(String::from("PaymentQuote"), 1)));
+		// Rules which never matched come first
+		assert_eq!(counts[0], (String::from("EventsChannelClosed"), 0));
+	}
+}
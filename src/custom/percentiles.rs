@@ -0,0 +1,111 @@
+//! Approximate percentiles of a metric, such as p95 storage cost, in little space
+//!
+//! Values are counted in bins whose bounds grow geometrically, so that each
+//! percentile is within RELATIVE_ACCURACY of the true value however many
+//! values are added. When there are more than MAX_BINS the lowest bins are
+//! merged, which loses accuracy only for the lowest percentiles. This is the
+//! approach of DDSketch (Masson et al, 2019).
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::timelines::BucketValue;
+
+const RELATIVE_ACCURACY: f64 = 0.02;
+const GAMMA: f64 = (1.0 + RELATIVE_ACCURACY) / (1.0 - RELATIVE_ACCURACY);
+const MAX_BINS: usize = 256;
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PercentileSketch {
+	count: u64,
+	zero_count: u64,
+	bins: BTreeMap<i32, u64>, // Count of values in (GAMMA^(key-1), GAMMA^key]
+}
+
+impl PercentileSketch {
+	pub fn add(&mut self, value: BucketValue) {
+		self.count += 1;
+		if value == 0 {
+			self.zero_count += 1;
+		} else {
+			let key = ((value as f64).ln() / GAMMA.ln()).ceil() as i32;
+			*self.bins.entry(key).or_default() += 1;
+			self.collapse();
+		}
+	}
+
+	/// Add the values counted by other
+	pub fn merge(&mut self, other: &PercentileSketch) {
+		self.count += other.count;
+		self.zero_count += other.zero_count;
+		for (key, count) in &other.bins {
+			*self.bins.entry(*key).or_default() += count;
+		}
+		self.collapse();
+	}
+
+	fn collapse(&mut self) {
+		while self.bins.len() > MAX_BINS {
+			if let Some((_, count)) = self.bins.pop_first() {
+				if let Some(mut lowest) = self.bins.first_entry() {
+					*lowest.get_mut() += count;
+				}
+			}
+		}
+	}
+
+	/// Value below which the given fraction (e.g. 0.95) of values fall, or None if there are none
+	pub fn percentile(&self, fraction: f64) -> Option<BucketValue> {
+		if self.count == 0 {
+			return None;
+		}
+		let rank = (fraction.clamp(0.0, 1.0) * (self.count - 1) as f64).round() as u64;
+		if rank < self.zero_count {
+			return Some(0);
+		}
+		let mut counted = self.zero_count;
+		for (key, count) in &self.bins {
+			counted += count;
+			if counted > rank {
+				// The middle of the bin, which is within RELATIVE_ACCURACY of every value in it
+				return Some((2.0 * GAMMA.powi(*key) / (GAMMA + 1.0)).round() as BucketValue);
+			}
+		}
+		None
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn it_estimates_percentiles_within_the_accuracy() {
+		let mut sketch = PercentileSketch::default();
+		assert_eq!(sketch.percentile(0.5), None);
+		for value in 1..=1000 {
+			sketch.add(value);
+		}
+		let within = |estimate: Option<BucketValue>, expected: f64| {
+			(estimate.unwrap() as f64 - expected).abs() <= expected * RELATIVE_ACCURACY + 1.0
+		};
+		assert!(within(sketch.percentile(0.5), 500.0));
+		assert!(within(sketch.percentile(0.95), 950.0));
+		assert!(within(sketch.percentile(0.99), 990.0));
+
+		let mut other = PercentileSketch::default();
+		other.add(0);
+		sketch.merge(&other);
+		assert_eq!(sketch.percentile(0.0), Some(0));
+		assert!(within(sketch.percentile(0.5), 500.0));
+
+		// Values spread over more bins than are kept lose accuracy only at the bottom
+		let values: Vec<BucketValue> = (0..800).map(|i| 1.05_f64.powi(i) as BucketValue).collect();
+		let mut wide = PercentileSketch::default();
+		for value in &values {
+			wide.add(*value);
+		}
+		assert!(wide.bins.len() <= MAX_BINS);
+		assert!(within(wide.percentile(0.99), values[791] as f64));
+	}
+}
@@ -0,0 +1,66 @@
+//! Preferences chosen while using vdash, kept between runs
+//!
+//! These are saved as JSON in the same directory as the config file (see
+//! setup_wizard) whenever they change, and restored when vdash starts.
+use std::fs;
+use std::io::Error;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use super::config_file::config_path;
+
+const PREFERENCES_FILENAME: &str = "preferences.json";
+
+/// A Summary column to sort by, identified by its heading
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SortKey {
+	pub column: String,
+	pub ascending: bool,
+}
+
+/// Layout of the Node Status view
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NodeLayout {
+	pub timelines_height: u16, // Rows for timelines when the logfile is shown
+	pub logfile_visible: bool,
+	pub top_timeline: usize,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Preferences {
+	// Summary sort order, most significant first
+	#[serde(default)]
+	pub summary_sort: Vec<SortKey>,
+
+	#[serde(default)]
+	pub node_layout: Option<NodeLayout>,
+
+	// Timelines drawn as braille lines rather than bars
+	#[serde(default)]
+	pub sparkline_braille: bool,
+}
+
+pub fn preferences_path() -> Option<PathBuf> {
+	Some(config_path()?.parent()?.join(PREFERENCES_FILENAME))
+}
+
+impl Preferences {
+	/// Preferences saved by an earlier run, or the defaults
+	pub fn load() -> Preferences {
+		preferences_path()
+			.and_then(|path| fs::read_to_string(path).ok())
+			.and_then(|json| serde_json::from_str(&json).ok())
+			.unwrap_or_default()
+	}
+
+	pub fn save(&self) -> Result<(), Error> {
+		let Some(path) = preferences_path() else {
+			return Ok(());
+		};
+		if let Some(dir) = path.parent() {
+			fs::create_dir_all(dir)?;
+		}
+		fs::write(path, serde_json::to_string_pretty(self)?)
+	}
+}
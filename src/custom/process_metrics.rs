@@ -0,0 +1,102 @@
+//! CPU and memory of node processes, measured directly (see --process-metrics)
+//!
+//! Nodes have logged their resource use in metrics lines, but newer releases
+//! may not. For nodes whose logfile has no recent metrics line, vdash can
+//! instead sample the process with the PID given in the logfile and feed the
+//! same metrics and timelines.
+//!
+//! The PID is also used to check that each node process is still running
+//! (see --pid-check), so a node which has died is shown at once rather than
+//! when its logfile has been quiet for long enough to be judged inactive.
+use chrono::{DateTime, Duration, Utc};
+use sysinfo::{Pid, ProcessRefreshKind, System};
+
+/// How often node processes are sampled (matches the interval of logged metrics)
+pub const SAMPLE_INTERVAL_S: i64 = 5;
+
+/// Logged metrics older than this are taken to have stopped
+pub const LOGGED_METRICS_STALE_S: i64 = 60;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProcessSample {
+	pub cpu_usage_percent: f32,
+	pub memory_used_mb: u64,
+}
+
+pub struct ProcessSampler {
+	system: System,
+	pub next_sample: Option<DateTime<Utc>>,
+}
+
+impl ProcessSampler {
+	pub fn new() -> ProcessSampler {
+		ProcessSampler {
+			system: System::new(),
+			next_sample: None,
+		}
+	}
+
+	/// True if it is time to sample again, in which case the next sample is scheduled interval_s later
+	pub fn is_due(&mut self, now: &DateTime<Utc>, interval_s: i64) -> bool {
+		if self.next_sample.is_some_and(|next_sample| *now < next_sample) {
+			return false;
+		}
+		self.next_sample = Some(*now + Duration::seconds(interval_s));
+		true
+	}
+
+	/// True if a process with the PID is running
+	pub fn is_running(&mut self, pid: u64) -> bool {
+		let Ok(pid) = u32::try_from(pid) else {
+			return false;
+		};
+		self.system.refresh_process_specifics(Pid::from_u32(pid), ProcessRefreshKind::new())
+	}
+
+	/// CPU and memory of a process, or None if it is not running. CPU use is
+	/// since the previous sample, so the first sample of a process shows none.
+	pub fn sample(&mut self, pid: u64) -> Option<ProcessSample> {
+		let pid = Pid::from_u32(u32::try_from(pid).ok()?);
+		if !self
+			.system
+			.refresh_process_specifics(pid, ProcessRefreshKind::new().with_cpu().with_memory())
+		{
+			return None;
+		}
+		let process = self.system.process(pid)?;
+		Some(ProcessSample {
+			cpu_usage_percent: process.cpu_usage(),
+			memory_used_mb: process.memory() / (1024 * 1024),
+		})
+	}
+}
+
+/// True unless the logfile has had a metrics line recently
+pub fn needs_sampling(latest_logged_metrics: Option<DateTime<Utc>>, now: &DateTime<Utc>) -> bool {
+	latest_logged_metrics.is_none_or(|time| *now - time > Duration::seconds(LOGGED_METRICS_STALE_S))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn it_samples_processes_without_logged_metrics() {
+		let now = Utc::now();
+		assert!(needs_sampling(None, &now));
+		assert!(needs_sampling(Some(now - Duration::seconds(LOGGED_METRICS_STALE_S + 1)), &now));
+		assert!(!needs_sampling(Some(now - Duration::seconds(5)), &now));
+
+		let mut sampler = ProcessSampler::new();
+		assert!(sampler.is_due(&now, SAMPLE_INTERVAL_S));
+		assert!(!sampler.is_due(&(now + Duration::seconds(1)), SAMPLE_INTERVAL_S));
+		assert!(sampler.is_due(&(now + Duration::seconds(SAMPLE_INTERVAL_S)), SAMPLE_INTERVAL_S));
+
+		let sample = sampler.sample(std::process::id() as u64).unwrap();
+		assert!(sample.memory_used_mb > 0);
+		assert_eq!(sampler.sample(u32::MAX as u64), None);
+
+		assert!(sampler.is_running(std::process::id() as u64));
+		assert!(!sampler.is_running(u32::MAX as u64));
+	}
+}
@@ -0,0 +1,99 @@
+//! Optional profiling of the main subsystems (--profile)
+//!
+//! Each profiled scope enters a tracing span, so a tracing subscriber
+//! (e.g. tracing-flame) can produce flamegraphs, and when --profile is
+//! given also accumulates simple timing statistics which are printed
+//! when vdash exits.
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+static PROFILE_ENABLED: AtomicBool = AtomicBool::new(false);
+
+static PROFILE_STATS: LazyLock<Mutex<BTreeMap<&'static str, ProfileStats>>> =
+	LazyLock::new(|| Mutex::new(BTreeMap::new()));
+
+/// Enter a tracing span and time the rest of the enclosing scope
+#[macro_export]
+macro_rules! profile_scope {
+	($subsystem:literal) => {
+		let _profile_span = tracing::trace_span!($subsystem).entered();
+		let _profile_timer = $crate::custom::profile::ProfileTimer::new($subsystem);
+	};
+}
+pub use crate::profile_scope;
+
+#[derive(Default)]
+struct ProfileStats {
+	calls: u64,
+	total: Duration,
+	max: Duration,
+}
+
+pub fn enable_profiling() {
+	PROFILE_ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn is_profiling() -> bool {
+	PROFILE_ENABLED.load(Ordering::Relaxed)
+}
+
+/// Records the time from creation to drop against a subsystem
+pub struct ProfileTimer {
+	subsystem: &'static str,
+	start: Option<Instant>,
+}
+
+impl ProfileTimer {
+	pub fn new(subsystem: &'static str) -> ProfileTimer {
+		ProfileTimer {
+			subsystem,
+			start: if is_profiling() { Some(Instant::now()) } else { None },
+		}
+	}
+}
+
+impl Drop for ProfileTimer {
+	fn drop(&mut self) {
+		if let Some(start) = self.start {
+			let elapsed = start.elapsed();
+			let mut stats = PROFILE_STATS.lock().unwrap();
+			let entry = stats.entry(self.subsystem).or_default();
+			entry.calls += 1;
+			entry.total += elapsed;
+			if elapsed > entry.max {
+				entry.max = elapsed;
+			}
+		}
+	}
+}
+
+/// Timing summary for each profiled subsystem, or None if profiling is off
+pub fn profile_report() -> Option<String> {
+	if !is_profiling() {
+		return None;
+	}
+
+	let stats = PROFILE_STATS.lock().unwrap();
+	let mut report = format!(
+		"{:<24} {:>10} {:>12} {:>12} {:>12}\n",
+		"subsystem", "calls", "total ms", "mean us", "max us"
+	);
+	for (subsystem, s) in stats.iter() {
+		let mean_us = if s.calls > 0 {
+			s.total.as_micros() / s.calls as u128
+		} else {
+			0
+		};
+		report += &format!(
+			"{:<24} {:>10} {:>12} {:>12} {:>12}\n",
+			subsystem,
+			s.calls,
+			s.total.as_millis(),
+			mean_us,
+			s.max.as_micros()
+		);
+	}
+	Some(report)
+}
@@ -0,0 +1,188 @@
+//! Tail logfiles on remote machines over SSH (see --remote)
+//!
+//! Each remote source runs 'tail -F' on the remote host using the local ssh
+//! client, so keys and settings in ~/.ssh/config apply. tail prefixes the
+//! output of each file with a '==> path <==' header, which is used to attribute
+//! lines to a monitor named 'user@host:path'.
+use std::process::Stdio;
+
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::{ChildStderr, Command};
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::task::JoinHandle;
+
+pub enum RemoteEvent {
+	Line { logfile: String, line: String },
+	Closed { destination: String, message: String },
+}
+
+/// A --remote value of the form user@host:/path/glob
+#[derive(Clone, Debug, PartialEq)]
+pub struct RemoteSource {
+	pub destination: String,
+	pub path_glob: String,
+}
+
+impl RemoteSource {
+	pub fn parse(remote: &str) -> Option<RemoteSource> {
+		let (destination, path_glob) = remote.split_once(':')?;
+		if destination.is_empty() || path_glob.is_empty() {
+			return None;
+		}
+		Some(RemoteSource {
+			destination: destination.to_string(),
+			path_glob: path_glob.to_string(),
+		})
+	}
+
+	/// Name of the monitor for a file on this remote host
+	pub fn logfile_name(&self, remote_path: &str) -> String {
+		format!("{}:{}", self.destination, remote_path)
+	}
+}
+
+/// Path given in a tail header line such as '==> /var/log/antnode.log <=='
+fn parse_tail_header(line: &str) -> Option<&str> {
+	line.strip_prefix("==> ")?.strip_suffix(" <==")
+}
+
+/// Read a child's stderr as it is written, so that the child can't fill the pipe and
+/// stall while its stdout is read, keeping only the last line to say why it ended
+pub fn spawn_stderr_reader(stderr: Option<ChildStderr>) -> JoinHandle<Option<String>> {
+	tokio::spawn(async move {
+		let mut last_line = None;
+		if let Some(stderr) = stderr {
+			let mut lines = BufReader::new(stderr).lines();
+			while let Ok(Some(line)) = lines.next_line().await {
+				if !line.trim().is_empty() {
+					last_line = Some(line);
+				}
+			}
+		}
+		last_line
+	})
+}
+
+/// A path glob for the remote shell to expand, with every character which isn't
+/// plainly part of a path escaped except those of the glob (* ? [ ]) and a leading
+/// '~/' for the remote user's home directory, so that the shell can match files
+/// with it but do nothing else
+fn shell_glob(path_glob: &str) -> String {
+	let (mut escaped, path_glob) = match path_glob.strip_prefix("~/") {
+		Some(path_glob) => (String::from("~/"), path_glob),
+		None => (String::new(), path_glob),
+	};
+	for c in path_glob.chars() {
+		match c {
+			'*' | '?' | '[' | ']' | '/' | '.' | '_' | '-' => escaped.push(c),
+			c if c.is_ascii_alphanumeric() => escaped.push(c),
+			// A backslash before a newline would remove it
+			'\n' => escaped.push_str("'\n'"),
+			c => {
+				escaped.push('\\');
+				escaped.push(c);
+			}
+		}
+	}
+	escaped
+}
+
+/// Start tailing a remote source, sending its lines to tx
+pub fn spawn_remote_tail(source: RemoteSource, tx: UnboundedSender<RemoteEvent>) {
+	tokio::spawn(async move {
+		let remote_command = format!("tail -v -n +1 -F -- {}", shell_glob(&source.path_glob));
+		let child = Command::new("ssh")
+			.args(["-o", "BatchMode=yes", "--", &source.destination, &remote_command])
+			.stdin(Stdio::null())
+			.stdout(Stdio::piped())
+			.stderr(Stdio::piped())
+			.kill_on_drop(true)
+			.spawn();
+
+		let mut child = match child {
+			Ok(child) => child,
+			Err(e) => {
+				let _ = tx.send(RemoteEvent::Closed {
+					destination: source.destination.clone(),
+					message: format!("failed to run ssh: {}", e),
+				});
+				return;
+			}
+		};
+
+		let stderr_reader = spawn_stderr_reader(child.stderr.take());
+		if let Some(stdout) = child.stdout.take() {
+			let mut lines = BufReader::new(stdout).lines();
+			let mut remote_path: Option<String> = None;
+			while let Ok(Some(line)) = lines.next_line().await {
+				if let Some(path) = parse_tail_header(&line) {
+					remote_path = Some(path.to_string());
+					continue;
+				}
+				if let Some(path) = &remote_path {
+					let event = RemoteEvent::Line {
+						logfile: source.logfile_name(path),
+						line,
+					};
+					if tx.send(event).is_err() {
+						return;
+					}
+				}
+			}
+		}
+
+		let _ = child.wait().await;
+		let message = stderr_reader.await.ok().flatten().unwrap_or_else(|| String::from("connection closed"));
+		let _ = tx.send(RemoteEvent::Closed {
+			destination: source.destination.clone(),
+			message,
+		});
+	});
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn it_parses_remote_sources_and_tail_headers() {
+		let source = RemoteSource::parse("ant@vps1:/var/log/antnode/*/antnode.log").unwrap();
+		assert_eq!(source.destination, "ant@vps1");
+		assert_eq!(source.path_glob, "/var/log/antnode/*/antnode.log");
+		assert!(RemoteSource::parse("/var/log/antnode.log").is_none());
+
+		let path = parse_tail_header("==> /var/log/antnode/antnode1/antnode.log <==").unwrap();
+		assert_eq!(
+			source.logfile_name(path),
+			"ant@vps1:/var/log/antnode/antnode1/antnode.log"
+		);
+		assert!(parse_tail_header("[2024-03-23T19:38:32.350118Z INFO ant_node] ==> x").is_none());
+	}
+
+	#[cfg(unix)]
+	#[tokio::test]
+	async fn it_reads_stderr_while_stdout_is_read() {
+		// More on stderr than a pipe holds, before anything on stdout
+		let mut child = Command::new("sh")
+			.args(["-c", "yes 123456789 | head -c 300000 >&2; echo done; echo 'last error' >&2"])
+			.stdout(Stdio::piped())
+			.stderr(Stdio::piped())
+			.spawn()
+			.unwrap();
+		let stderr_reader = spawn_stderr_reader(child.stderr.take());
+		let mut lines = BufReader::new(child.stdout.take().unwrap()).lines();
+		let first_line = tokio::time::timeout(std::time::Duration::from_secs(10), lines.next_line()).await;
+		assert_eq!(first_line.unwrap().unwrap(), Some(String::from("done")));
+		child.wait().await.unwrap();
+		assert_eq!(stderr_reader.await.unwrap(), Some(String::from("last error")));
+	}
+
+	#[test]
+	fn it_escapes_all_but_the_glob_for_the_remote_shell() {
+		assert_eq!(shell_glob("/var/log/antnode/*/antnode.log"), "/var/log/antnode/*/antnode.log");
+		assert_eq!(shell_glob("~/.local/share/autonomi/node/antnode[0-9]?/logs/*.log"), "~/.local/share/autonomi/node/antnode[0-9]?/logs/*.log");
+		assert_eq!(shell_glob("/home/ant/My Logs/*.log; rm -rf ~"), "/home/ant/My\\ Logs/*.log\\;\\ rm\\ -rf\\ \\~");
+		assert_eq!(shell_glob("/$(reboot)/`id`/a'b\"c"), "/\\$\\(reboot\\)/\\`id\\`/a\\'b\\\"c");
+		assert_eq!(shell_glob("/logs\nreboot"), "/logs'\n'reboot");
+	}
+}
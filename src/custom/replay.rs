@@ -0,0 +1,151 @@
+//! Replay complete logfiles, scrubbing through time (see --replay)
+//!
+//! The lines of each logfile are held in memory with their times so that the
+//! monitors can be rebuilt from the lines up to any time in the logfiles. The
+//! dashboard then shows that time rather than now: timelines end at it, and
+//! uptime and activity are judged from it (see dashboard_time()).
+//!
+//! Stepping forward processes only the lines since the previous time, while
+//! stepping back rebuilds each monitor from the start of its logfile.
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::sync::{LazyLock, Mutex};
+
+use chrono::{DateTime, Utc};
+
+use super::node_metrics::LogEntry;
+
+// Time shown by the dashboard while replaying
+static REPLAY_TIME: LazyLock<Mutex<Option<DateTime<Utc>>>> = LazyLock::new(|| Mutex::new(None));
+
+/// The time being replayed, if replaying logfiles
+pub fn replay_time() -> Option<DateTime<Utc>> {
+	*REPLAY_TIME.lock().unwrap()
+}
+
+pub fn set_replay_time(time: Option<DateTime<Utc>>) {
+	*REPLAY_TIME.lock().unwrap() = time;
+}
+
+/// The time shown by the dashboard: now, or the time being replayed
+pub fn dashboard_time() -> DateTime<Utc> {
+	replay_time().unwrap_or_else(Utc::now)
+}
+
+pub struct ReplayLogfile {
+	pub logfile: String,
+	pub lines: Vec<(DateTime<Utc>, String)>, // Lines without a time have that of the line before
+	pub next_line: usize,                     // First line not yet given to the monitor
+}
+
+pub struct Replay {
+	pub logfiles: Vec<ReplayLogfile>,
+	pub start_time: DateTime<Utc>,
+	pub end_time: DateTime<Utc>,
+	pub time: DateTime<Utc>,
+	pub playing: bool,
+}
+
+impl Replay {
+	/// Read the logfiles into memory. Returns None if none of them has a timed line.
+	pub fn load(logfiles: &[String]) -> Option<Replay> {
+		let replay_logfiles: Vec<ReplayLogfile> = logfiles
+			.iter()
+			.filter_map(|logfile| {
+				let file = File::open(logfile).ok()?;
+				Some(ReplayLogfile {
+					logfile: logfile.clone(),
+					lines: timed_lines(BufReader::new(file).lines().map_while(Result::ok)),
+					next_line: 0,
+				})
+			})
+			.collect();
+
+		let times = replay_logfiles
+			.iter()
+			.flat_map(|replay_logfile| [replay_logfile.lines.first(), replay_logfile.lines.last()])
+			.flatten()
+			.map(|(time, _line)| *time);
+		let start_time = times.clone().min()?;
+		let end_time = times.max()?;
+		Some(Replay {
+			logfiles: replay_logfiles,
+			start_time,
+			end_time,
+			time: end_time,
+			playing: false,
+		})
+	}
+
+	/// Forget the lines given to the monitors, ready to rebuild them
+	pub fn rewind(&mut self) {
+		for replay_logfile in self.logfiles.iter_mut() {
+			replay_logfile.next_line = 0;
+		}
+	}
+
+	pub fn clamp_time(&self, time: DateTime<Utc>) -> DateTime<Utc> {
+		time.clamp(self.start_time, self.end_time)
+	}
+
+	pub fn status_text(&self) -> String {
+		format!(
+			"REPLAY {} {}",
+			self.time.format("%Y-%m-%d %H:%M:%S"),
+			if self.playing { "playing" } else { "paused" }
+		)
+	}
+}
+
+impl ReplayLogfile {
+	/// Lines not yet given to the monitor up to and including time
+	pub fn take_lines_until(&mut self, time: &DateTime<Utc>) -> &[(DateTime<Utc>, String)] {
+		let start = self.next_line;
+		while self.next_line < self.lines.len() && self.lines[self.next_line].0 <= *time {
+			self.next_line += 1;
+		}
+		&self.lines[start..self.next_line]
+	}
+}
+
+/// Attach the time of the most recent metadata to each line, dropping any before the first
+fn timed_lines(lines: impl Iterator<Item = String>) -> Vec<(DateTime<Utc>, String)> {
+	let mut timed_lines = Vec::new();
+	let mut time: Option<DateTime<Utc>> = None;
+	for line in lines {
+		if let Some(metadata) = LogEntry::decode_metadata(&line) {
+			time = Some(metadata.message_time);
+		}
+		if let Some(time) = time {
+			timed_lines.push((time, line));
+		}
+	}
+	timed_lines
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn it_gives_lines_up_to_the_replay_time() {
+		let lines = [
+			"preamble without metadata",
+			"[2024-03-23T19:00:00.000000Z INFO ant_node] ",
+			"Running antnode v0.1.0",
+			"[2024-03-23T19:10:00.000000Z INFO ant_node] Wrote record",
+		]
+		.map(String::from);
+		let mut replay_logfile = ReplayLogfile {
+			logfile: String::from("antnode.log"),
+			lines: timed_lines(lines.into_iter()),
+			next_line: 0,
+		};
+		assert_eq!(replay_logfile.lines.len(), 3);
+
+		let time = |text: &str| DateTime::parse_from_rfc3339(text).unwrap().with_timezone(&Utc);
+		assert_eq!(replay_logfile.take_lines_until(&time("2024-03-23T19:05:00Z")).len(), 2);
+		assert!(replay_logfile.take_lines_until(&time("2024-03-23T19:05:00Z")).is_empty());
+		assert_eq!(replay_logfile.take_lines_until(&time("2024-03-23T19:10:00Z")).len(), 1);
+	}
+}
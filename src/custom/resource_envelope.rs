@@ -0,0 +1,114 @@
+//! Expected resource use of nodes (see --resource-envelope)
+//!
+//! Each node can be given an envelope of the RAM and CPU it is expected to
+//! use. Nodes using more are highlighted, which catches a node version with a
+//! memory leak long before it reaches any absolute limit.
+//!
+//! Envelopes are given as 'ram:<MB>,cpu:<percent>', optionally preceded by a
+//! glob and '=' to apply only to matching logfiles (e.g.
+//! '/var/log/antnode/*/antnode.log=ram:800'). Where more than one matches a
+//! logfile, limits given later replace those given earlier.
+use super::node_metrics::NodeMetrics;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ResourceEnvelope {
+	pub ram_mb: Option<u64>,
+	pub cpu_percent: Option<f32>,
+}
+
+impl ResourceEnvelope {
+	/// The envelope for a logfile from --resource-envelope values
+	pub fn for_logfile(opt_envelopes: &[String], logfile: &str) -> ResourceEnvelope {
+		let mut envelope = ResourceEnvelope::default();
+		for opt_envelope in opt_envelopes {
+			let (globpath, limits) = match opt_envelope.rsplit_once('=') {
+				Some((globpath, limits)) => (Some(globpath), limits),
+				None => (None, opt_envelope.as_str()),
+			};
+			let matches = globpath.is_none_or(|globpath| {
+				glob::Pattern::new(globpath).is_ok_and(|pattern| pattern.matches(logfile))
+			});
+			if matches {
+				envelope.apply_limits(limits);
+			}
+		}
+		envelope
+	}
+
+	fn apply_limits(&mut self, limits: &str) {
+		for limit in limits.split(',') {
+			match limit.trim().split_once(':') {
+				Some(("ram", mb)) => self.ram_mb = mb.trim().parse::<u64>().ok().or(self.ram_mb),
+				Some(("cpu", percent)) => {
+					self.cpu_percent = percent.trim().trim_end_matches('%').parse::<f32>().ok().or(self.cpu_percent)
+				}
+				_ => {}
+			}
+		}
+	}
+
+	pub fn is_set(&self) -> bool {
+		self.ram_mb.is_some() || self.cpu_percent.is_some()
+	}
+
+	/// Descriptions of the limits exceeded by the node's latest usage (e.g. "RAM 812/600 MB")
+	pub fn breaches(&self, metrics: &NodeMetrics) -> Vec<String> {
+		let mut breaches = Vec::<String>::new();
+		if let Some(ram_mb) = self.ram_mb {
+			let used_mb = metrics.memory_used_mb.most_recent;
+			if used_mb > ram_mb {
+				breaches.push(format!("RAM {}/{} MB", used_mb, ram_mb));
+			}
+		}
+		if let Some(cpu_percent) = self.cpu_percent {
+			if metrics.cpu_usage_percent > cpu_percent {
+				breaches.push(format!("CPU {:.1}/{}%", metrics.cpu_usage_percent, cpu_percent));
+			}
+		}
+		breaches
+	}
+
+	pub fn is_breached(&self, metrics: &NodeMetrics) -> bool {
+		!self.breaches(metrics).is_empty()
+	}
+
+	/// The limits for display (e.g. "RAM 600 MB, CPU 40%")
+	pub fn limits_text(&self) -> String {
+		let mut limits = Vec::<String>::new();
+		if let Some(ram_mb) = self.ram_mb {
+			limits.push(format!("RAM {} MB", ram_mb));
+		}
+		if let Some(cpu_percent) = self.cpu_percent {
+			limits.push(format!("CPU {}%", cpu_percent));
+		}
+		limits.join(", ")
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn it_applies_matching_envelopes_and_detects_breaches() {
+		let opt_envelopes = [
+			String::from("ram:600,cpu:40"),
+			String::from("/var/log/antnode/vps*/antnode.log=ram:1200"),
+		];
+		let envelope = ResourceEnvelope::for_logfile(&opt_envelopes, "/var/log/antnode/vps1/antnode.log");
+		assert_eq!(envelope.ram_mb, Some(1200));
+		assert_eq!(envelope.cpu_percent, Some(40.0));
+		let envelope = ResourceEnvelope::for_logfile(&opt_envelopes, "/home/ant/node1/antnode.log");
+		assert_eq!(envelope.ram_mb, Some(600));
+		assert_eq!(envelope.limits_text(), "RAM 600 MB, CPU 40%");
+
+		let mut metrics = NodeMetrics::new();
+		metrics.memory_used_mb.add_sample(812);
+		metrics.cpu_usage_percent = 12.5;
+		assert_eq!(envelope.breaches(&metrics), vec!["RAM 812/600 MB"]);
+		metrics.memory_used_mb.add_sample(500);
+		assert!(!envelope.is_breached(&metrics));
+
+		assert!(!ResourceEnvelope::for_logfile(&[], "antnode.log").is_set());
+	}
+}
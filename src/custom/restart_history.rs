@@ -0,0 +1,121 @@
+//! Restart history of a node
+//!
+//! Each 'Running antnode' (or 'Running safenode') line is recorded as a start
+//! of the node. The gap between the last entry before a start and the start
+//! itself is counted as downtime, which gives an uptime percentage over the
+//! period the logfile has been monitored.
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NodeStart {
+	pub time: DateTime<Utc>,
+	pub version: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RestartHistory {
+	pub starts: Vec<NodeStart>,
+
+	first_entry_time: Option<DateTime<Utc>>,
+	last_entry_time: Option<DateTime<Utc>>,
+	downtime_s: i64,
+}
+
+impl RestartHistory {
+	/// Note the time of every logfile entry, after it has been processed
+	pub fn note_entry(&mut self, time: &DateTime<Utc>) {
+		if self.first_entry_time.is_none() {
+			self.first_entry_time = Some(*time);
+		}
+		if self.last_entry_time.is_none_or(|last| *time > last) {
+			self.last_entry_time = Some(*time);
+		}
+	}
+
+	/// Record a start of the node, counting any gap since the previous entry as downtime
+	pub fn record_start(&mut self, time: &DateTime<Utc>, version: &str) {
+		if let Some(last_entry_time) = self.last_entry_time {
+			if *time > last_entry_time {
+				self.downtime_s += (*time - last_entry_time).num_seconds();
+			}
+		}
+		if self.first_entry_time.is_none() {
+			self.first_entry_time = Some(*time);
+		}
+
+		self.starts.push(NodeStart {
+			time: *time,
+			version: version.to_string(),
+		});
+	}
+
+	/// Time of the first entry seen in the logfile
+	pub fn first_entry_time(&self) -> Option<DateTime<Utc>> {
+		self.first_entry_time
+	}
+
+	/// Number of starts after the first
+	pub fn restart_count(&self) -> usize {
+		self.starts.len().saturating_sub(1)
+	}
+
+	/// Percentage of the monitored period the node was running. If the node
+	/// is no longer active the time since its last entry counts as downtime.
+	pub fn uptime_percent(&self, now: &DateTime<Utc>, node_inactive: bool) -> Option<f64> {
+		let first_entry_time = self.first_entry_time?;
+		let last_entry_time = self.last_entry_time.unwrap_or(first_entry_time);
+
+		let mut downtime = Duration::seconds(self.downtime_s);
+		let end_time = if node_inactive && *now > last_entry_time {
+			downtime = downtime + (*now - last_entry_time);
+			*now
+		} else {
+			last_entry_time
+		};
+
+		let period_s = (end_time - first_entry_time).num_seconds();
+		if period_s <= 0 {
+			return None;
+		}
+
+		let uptime_s = (period_s - downtime.num_seconds()).max(0);
+		Some(100.0 * uptime_s as f64 / period_s as f64)
+	}
+}
+
+/// Uptime percentage as text for display, or "-" when not known
+pub fn uptime_percent_text(uptime_percent: Option<f64>) -> String {
+	uptime_percent.map_or(String::from("-"), |percent| format!("{:.1}%", percent))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::str::FromStr;
+
+	fn time(s: &str) -> DateTime<Utc> {
+		DateTime::from_str(s).unwrap()
+	}
+
+	#[test]
+	fn it_counts_gaps_before_restarts_as_downtime() {
+		let mut history = RestartHistory::default();
+		history.record_start(&time("2024-03-23T10:00:00Z"), "v0.1.0");
+		history.note_entry(&time("2024-03-23T10:00:00Z"));
+		history.note_entry(&time("2024-03-23T11:00:00Z"));
+
+		// Down for an hour then restarted
+		history.record_start(&time("2024-03-23T12:00:00Z"), "v0.1.1");
+		history.note_entry(&time("2024-03-23T12:00:00Z"));
+		history.note_entry(&time("2024-03-23T14:00:00Z"));
+
+		assert_eq!(history.restart_count(), 1);
+		let now = time("2024-03-23T14:00:00Z");
+		assert_eq!(history.uptime_percent(&now, false), Some(75.0));
+
+		// Inactive for a further four hours
+		let now = time("2024-03-23T18:00:00Z");
+		assert_eq!(history.uptime_percent(&now, true), Some(37.5));
+	}
+}
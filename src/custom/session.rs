@@ -0,0 +1,136 @@
+//! What nodes did while vdash was open (see --session-report and 'd')
+//!
+//! Each node's counts are noted on the first tick after it is seen, once its
+//! logfile history or checkpoint has been loaded. The Summary can then show
+//! earnings, PUTS, GETS and errors since that point instead of in total, and
+//! a report of the session can be written when vdash quits.
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+
+use super::node_metrics::{LogMonitor, NodeMetrics};
+use super::timelines::get_duration_text;
+use super::token_units::attos_to_ant;
+
+/// Counts which accumulate over a session
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SessionCounts {
+	pub attos_earned: u128,
+	pub puts: u64,
+	pub gets: u64,
+	pub errors: u64,
+	pub restarts: u64,
+}
+
+impl SessionCounts {
+	pub fn of(metrics: &NodeMetrics) -> SessionCounts {
+		SessionCounts {
+			attos_earned: metrics.attos_earned.total,
+			puts: metrics.activity_puts.total,
+			gets: metrics.activity_gets.total,
+			errors: metrics.activity_errors.total,
+			restarts: metrics.restart_history.restart_count() as u64,
+		}
+	}
+
+	/// Counts added since start
+	pub fn since(&self, start: &SessionCounts) -> SessionCounts {
+		SessionCounts {
+			attos_earned: self.attos_earned.saturating_sub(start.attos_earned),
+			puts: self.puts.saturating_sub(start.puts),
+			gets: self.gets.saturating_sub(start.gets),
+			errors: self.errors.saturating_sub(start.errors),
+			restarts: self.restarts.saturating_sub(start.restarts),
+		}
+	}
+
+	pub fn add(&mut self, other: &SessionCounts) {
+		self.attos_earned = self.attos_earned.saturating_add(other.attos_earned);
+		self.puts += other.puts;
+		self.gets += other.gets;
+		self.errors += other.errors;
+		self.restarts += other.restarts;
+	}
+}
+
+/// A node's counts since the session started, which are zero until they have been noted
+pub fn session_counts(monitor: &LogMonitor) -> SessionCounts {
+	monitor
+		.session_start
+		.as_ref()
+		.map_or_else(SessionCounts::default, |start| SessionCounts::of(&monitor.metrics).since(start))
+}
+
+/// A plain text report of what each node did during the session, with totals
+pub fn session_report(monitors: &HashMap<String, LogMonitor>, started: &DateTime<Utc>, ended: &DateTime<Utc>) -> String {
+	let mut nodes: Vec<&LogMonitor> = monitors.values().filter(|monitor| monitor.is_node()).collect();
+	nodes.sort_by_key(|monitor| monitor.index);
+
+	let mut report = String::from("vdash session report\n\n");
+	report += &format!("Started:  {}\n", started.format("%Y-%m-%d %H:%M:%S UTC"));
+	report += &format!("Ended:    {}\n", ended.format("%Y-%m-%d %H:%M:%S UTC"));
+	report += &format!("Duration: {}\n\n", get_duration_text(*ended - *started));
+
+	let row = |node: &str, counts: &SessionCounts, logfile: &str| {
+		format!(
+			"{:>5} {:>15.9} {:>10} {:>10} {:>8} {:>8}  {}\n",
+			node,
+			attos_to_ant(counts.attos_earned),
+			counts.gets,
+			counts.puts,
+			counts.errors,
+			counts.restarts,
+			logfile
+		)
+	};
+	report += &format!(
+		"{:>5} {:>15} {:>10} {:>10} {:>8} {:>8}  {}\n",
+		"Node", "Earned (ANT)", "GETS", "PUTS", "Errors", "Restarts", "Logfile"
+	);
+	let mut totals = SessionCounts::default();
+	for monitor in nodes.iter() {
+		let counts = session_counts(monitor);
+		totals.add(&counts);
+		report += &row(&(monitor.index + 1).to_string(), &counts, &monitor.logfile);
+	}
+	report += &row("All", &totals, &format!("{} nodes", nodes.len()));
+	report
+}
+
+/// Write the session report to path
+pub fn write_session_report(
+	path: &str,
+	monitors: &HashMap<String, LogMonitor>,
+	started: &DateTime<Utc>,
+	ended: &DateTime<Utc>,
+) -> std::io::Result<()> {
+	std::fs::write(path, session_report(monitors, started, ended))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use chrono::Duration;
+
+	#[test]
+	fn it_reports_counts_since_the_session_started() {
+		let started: DateTime<Utc> = "2024-03-23T10:00:00Z".parse().unwrap();
+		let mut monitor = LogMonitor::new(String::from("node1/antnode.log"));
+		monitor.metrics.attos_earned.total = 1_000_000_000_000_000_000;
+		monitor.metrics.activity_gets.total = 40;
+		assert_eq!(session_counts(&monitor), SessionCounts::default());
+
+		monitor.session_start = Some(SessionCounts::of(&monitor.metrics));
+		monitor.metrics.attos_earned.total += 500_000_000_000_000_000;
+		monitor.metrics.activity_gets.total += 12;
+		monitor.metrics.activity_errors.total += 2;
+		let counts = session_counts(&monitor);
+		assert_eq!((counts.attos_earned, counts.gets, counts.errors), (500_000_000_000_000_000, 12, 2));
+
+		let monitors = HashMap::from([(monitor.logfile.clone(), monitor)]);
+		let report = session_report(&monitors, &started, &(started + Duration::hours(2)));
+		assert!(report.contains("Started:  2024-03-23 10:00:00 UTC"));
+		assert!(report.contains("    1     0.500000000         12          0        2        0  node1/antnode.log"));
+		assert!(report.contains("  All     0.500000000         12          0        2        0  1 nodes"));
+	}
+}
@@ -0,0 +1,340 @@
+//! First-run setup wizard
+//!
+//! When vdash is started without any arguments and there is no config file
+//! it shows this wizard rather than exiting with a usage error. The wizard
+//! offers the logfile locations used by antctl, node-launchpad and older
+//! safenode installs (showing how many logfiles each matches now), lets you
+//! add your own 'glob' path and choose a currency, then writes these to the
+//! config file, from which vdash reads its options whenever it is run without
+//! arguments (see config_file.rs).
+use std::error::Error;
+use std::fs;
+use std::io::stdout;
+use std::path::PathBuf;
+
+use crossterm::event::{self, Event as CEvent, KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+	backend::CrosstermBackend,
+	style::{Color, Modifier, Style},
+	widgets::{Block, Borders, List, ListItem},
+	Frame, Terminal,
+};
+
+use super::config_file::{config_path, home_dir};
+use super::opt::{get_app_name, get_app_version};
+use super::terminal_guard::{restore_terminal, TerminalGuard};
+use super::ui::{push_blank, push_multiline_text, push_subheading, push_text};
+
+/// Currency choices offered as (--currency-apiname, --currency-symbol)
+const CURRENCY_CHOICES: [(&str, &str); 7] = [
+	("USD", "$"),
+	("EUR", "€"),
+	("GBP", "£"),
+	("CAD", "$"),
+	("AUD", "$"),
+	("JPY", "¥"),
+	("USD,EUR,GBP", "$,€,£"),
+];
+
+/// True when vdash has no arguments and no config file, so should run the wizard
+pub fn is_first_run() -> bool {
+	std::env::args_os().len() <= 1 && config_path().is_some_and(|path| !path.exists())
+}
+
+/// A place node logfiles may be found
+pub struct LogLocation {
+	pub description: String,
+	pub glob_path: String,
+	pub matches: usize,
+	pub selected: bool,
+}
+
+impl LogLocation {
+	pub fn new(description: &str, glob_path: &str) -> LogLocation {
+		let matches = match glob::glob(glob_path) {
+			Ok(paths) => paths.filter_map(Result::ok).count(),
+			Err(_) => 0,
+		};
+		LogLocation {
+			description: description.to_string(),
+			glob_path: glob_path.to_string(),
+			matches,
+			selected: matches > 0,
+		}
+	}
+}
+
+/// Logfile locations used by the Autonomi node applications on this OS
+fn common_log_locations() -> Vec<LogLocation> {
+	let home = home_dir().unwrap_or_default();
+	let in_home = |path: &str| home.join(path).to_string_lossy().to_string();
+
+	if cfg!(windows) {
+		vec![
+			LogLocation::new("node-launchpad", "C:\\ProgramData\\autonomi\\node\\*\\logs\\antnode.log"),
+			LogLocation::new("antnode run directly", &in_home("AppData\\Roaming\\autonomi\\node\\*\\logs\\antnode.log")),
+		]
+	} else if cfg!(target_os = "macos") {
+		vec![
+			LogLocation::new("node-launchpad or antctl", &in_home("Library/Application Support/autonomi/node/*/logs/antnode.log")),
+		]
+	} else {
+		vec![
+			LogLocation::new("antctl (system services)", "/var/log/antnode/*/antnode.log"),
+			LogLocation::new("node-launchpad or antctl --user", &in_home(".local/share/autonomi/node/*/logs/antnode.log")),
+			LogLocation::new("older safenode installs", &in_home(".local/share/safe/node/*/logs/safenode.log")),
+		]
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WizardStep {
+	Logfiles,
+	Currency,
+	Confirm,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum WizardAction {
+	Continue,
+	Quit,
+	Finish,
+}
+
+pub struct SetupWizard {
+	pub step: WizardStep,
+	pub locations: Vec<LogLocation>,
+	pub location_index: usize,
+	pub currency_index: usize,
+	pub custom_path: Option<String>, // Being typed when Some
+	pub message: Option<String>,
+}
+
+impl SetupWizard {
+	pub fn new(locations: Vec<LogLocation>) -> SetupWizard {
+		SetupWizard {
+			step: WizardStep::Logfiles,
+			locations,
+			location_index: 0,
+			currency_index: 0,
+			custom_path: None,
+			message: None,
+		}
+	}
+
+	/// Update the wizard for a keypress
+	pub fn handle_key(&mut self, key: &KeyEvent) -> WizardAction {
+		if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+			return WizardAction::Quit;
+		}
+		self.message = None;
+
+		if let Some(custom_path) = self.custom_path.as_mut() {
+			match key.code {
+				KeyCode::Enter => {
+					let glob_path = custom_path.trim().to_string();
+					self.custom_path = None;
+					if !glob_path.is_empty() {
+						let mut location = LogLocation::new("your path", &glob_path);
+						location.selected = true;
+						self.locations.push(location);
+						self.location_index = self.locations.len() - 1;
+					}
+				}
+				KeyCode::Esc => self.custom_path = None,
+				KeyCode::Backspace => {
+					custom_path.pop();
+				}
+				KeyCode::Char(c) => custom_path.push(c),
+				_ => {}
+			}
+			return WizardAction::Continue;
+		}
+
+		match (self.step, key.code) {
+			(_, KeyCode::Char('q')) => return WizardAction::Quit,
+
+			(WizardStep::Logfiles, KeyCode::Up) => self.location_index = self.location_index.saturating_sub(1),
+			(WizardStep::Logfiles, KeyCode::Down) => {
+				if self.location_index + 1 < self.locations.len() {
+					self.location_index += 1;
+				}
+			}
+			(WizardStep::Logfiles, KeyCode::Char(' ')) => {
+				if let Some(location) = self.locations.get_mut(self.location_index) {
+					location.selected = !location.selected;
+				}
+			}
+			(WizardStep::Logfiles, KeyCode::Char('a')) => self.custom_path = Some(String::new()),
+			(WizardStep::Logfiles, KeyCode::Enter) => {
+				if self.locations.iter().any(|location| location.selected) {
+					self.step = WizardStep::Currency;
+				} else {
+					self.message = Some(String::from("Select at least one location, or press 'a' to add one"));
+				}
+			}
+
+			(WizardStep::Currency, KeyCode::Up) => self.currency_index = self.currency_index.saturating_sub(1),
+			(WizardStep::Currency, KeyCode::Down) => {
+				if self.currency_index + 1 < CURRENCY_CHOICES.len() {
+					self.currency_index += 1;
+				}
+			}
+			(WizardStep::Currency, KeyCode::Enter) => self.step = WizardStep::Confirm,
+			(WizardStep::Currency, KeyCode::Esc) => self.step = WizardStep::Logfiles,
+
+			(WizardStep::Confirm, KeyCode::Enter) => return WizardAction::Finish,
+			(WizardStep::Confirm, KeyCode::Esc) => self.step = WizardStep::Currency,
+			_ => {}
+		}
+		WizardAction::Continue
+	}
+
+	/// The config file to write for the choices made
+	pub fn config_contents(&self) -> String {
+		let mut contents = String::from(
+			"# vdash options, used when vdash is run without arguments. Written by the setup wizard.\n\
+			 # One option per line as on the command line, without quotes (see vdash --help).\n",
+		);
+		for location in self.locations.iter().filter(|location| location.selected) {
+			contents += &format!("--glob-path {}\n", location.glob_path);
+		}
+		let (apiname, symbol) = CURRENCY_CHOICES[self.currency_index];
+		contents += &format!("--currency-apiname {}\n", apiname);
+		contents += &format!("--currency-symbol {}\n", symbol);
+		contents
+	}
+}
+
+/// Show the wizard and write the config file. Returns false if the user quit instead.
+pub fn run_setup_wizard() -> Result<bool, Box<dyn Error>> {
+	let path = config_path().ok_or("unable to find a directory for the config file")?;
+	let mut wizard = SetupWizard::new(common_log_locations());
+
+	let _terminal_guard = TerminalGuard::enter(false)?;
+	let mut terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
+	terminal.clear()?;
+
+	let result = loop {
+		terminal.draw(|f| draw_setup_wizard(f, &wizard, &path))?;
+		if let CEvent::Key(key) = event::read()? {
+			match wizard.handle_key(&key) {
+				WizardAction::Continue => {}
+				WizardAction::Quit => break Ok(false),
+				WizardAction::Finish => break Ok(true),
+			}
+		}
+	};
+
+	restore_terminal()?;
+	terminal.show_cursor()?;
+
+	if let Ok(true) = result {
+		if let Some(dir) = path.parent() {
+			fs::create_dir_all(dir)?;
+		}
+		fs::write(&path, wizard.config_contents())?;
+		println!("Saved vdash options to {}", path.to_string_lossy());
+	}
+	result
+}
+
+pub fn draw_setup_wizard(f: &mut Frame, wizard: &SetupWizard, path: &PathBuf) {
+	let selected_style = Style::default().fg(Color::Black).bg(Color::Green).add_modifier(Modifier::BOLD);
+	let mut items = Vec::<ListItem>::new();
+
+	push_blank(&mut items);
+	match wizard.step {
+		WizardStep::Logfiles => {
+			push_subheading(&mut items, &String::from("    Step 1 of 3: Where are your node logfiles?"));
+			push_blank(&mut items);
+			for (i, location) in wizard.locations.iter().enumerate() {
+				let text = format!(
+					"    [{}] {:<34} {:>4} logfiles   {}",
+					if location.selected { "x" } else { " " },
+					location.description,
+					location.matches,
+					location.glob_path
+				);
+				let style = if i == wizard.location_index { Some(selected_style) } else { None };
+				push_text(&mut items, &text, style);
+			}
+			push_blank(&mut items);
+			if let Some(custom_path) = &wizard.custom_path {
+				push_text(&mut items, &format!("    Glob path: {}_", custom_path), Some(Style::default().fg(Color::White)));
+				push_text(&mut items, &String::from("    Press 'enter' to add the path, or 'esc' to cancel."), None);
+			} else {
+				push_multiline_text(
+					&mut items,
+					"    Up and down arrows select a location, 'space' ticks or unticks it.
+    'a' adds a 'glob' path of your own (e.g. /home/ant/nodes/*/logs/antnode.log).
+    Press 'enter' to continue, or 'q' to quit.",
+				);
+			}
+		}
+		WizardStep::Currency => {
+			push_subheading(&mut items, &String::from("    Step 2 of 3: Which currency should earnings be shown in?"));
+			push_blank(&mut items);
+			for (i, (apiname, symbol)) in CURRENCY_CHOICES.iter().enumerate() {
+				let style = if i == wizard.currency_index { Some(selected_style) } else { None };
+				push_text(&mut items, &format!("    {:<12} {}", apiname, symbol), style);
+			}
+			push_blank(&mut items);
+			push_text(&mut items, &String::from("    Up and down arrows to choose, 'enter' to continue, 'esc' to go back or 'q' to quit."), None);
+		}
+		WizardStep::Confirm => {
+			push_subheading(&mut items, &format!("    Step 3 of 3: Save these options to {}", path.to_string_lossy()));
+			push_blank(&mut items);
+			for line in wizard.config_contents().lines() {
+				push_text(&mut items, &format!("    {}", line), Some(Style::default().fg(Color::White)));
+			}
+			push_blank(&mut items);
+			push_text(&mut items, &String::from("    Press 'enter' to save and start vdash, 'esc' to go back or 'q' to quit."), None);
+			push_text(&mut items, &String::from("    You can edit this file later, or run vdash with arguments to ignore it."), None);
+		}
+	}
+
+	if let Some(message) = &wizard.message {
+		push_blank(&mut items);
+		push_text(&mut items, &format!("    {}", message), Some(Style::default().fg(Color::Red)));
+	}
+
+	let title = format!("{} v{} - Setup", get_app_name(), get_app_version());
+	let wizard_widget = List::new(items).block(Block::default().borders(Borders::ALL).title(title));
+	f.render_widget(wizard_widget, f.size());
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::custom::config_file::parse_config;
+
+	fn press(wizard: &mut SetupWizard, code: KeyCode) -> WizardAction {
+		wizard.handle_key(&KeyEvent::new(code, KeyModifiers::NONE))
+	}
+
+	#[test]
+	fn it_writes_config_for_choices() {
+		let mut wizard = SetupWizard::new(vec![LogLocation::new("nowhere", "/nonexistent-vdash-dir/*/antnode.log")]);
+		assert!(!wizard.locations[0].selected);
+		assert_eq!(press(&mut wizard, KeyCode::Enter), WizardAction::Continue);
+		assert_eq!(wizard.step, WizardStep::Logfiles);
+
+		press(&mut wizard, KeyCode::Char('a'));
+		for c in "/tmp/nodes/*/antnode.log".chars() {
+			press(&mut wizard, KeyCode::Char(c));
+		}
+		press(&mut wizard, KeyCode::Enter);
+		press(&mut wizard, KeyCode::Enter);
+		assert_eq!(wizard.step, WizardStep::Currency);
+		press(&mut wizard, KeyCode::Down);
+		press(&mut wizard, KeyCode::Enter);
+		assert_eq!(press(&mut wizard, KeyCode::Enter), WizardAction::Finish);
+
+		let config = wizard.config_contents();
+		assert_eq!(
+			parse_config(&config),
+			vec!["--glob-path", "/tmp/nodes/*/antnode.log", "--currency-apiname", "EUR", "--currency-symbol", "€"]
+		);
+	}
+}
@@ -0,0 +1,83 @@
+//! Size of a node's record store on disk (see --store-poll)
+//!
+//! The record store is the directory --store-dirname in the node's root
+//! directory (as for --disk-poll), whose files are summed so that the storage
+//! used is known even when the node doesn't log it. Walking a large store is
+//! slow, so the App scans one node at a time spread over the poll interval
+//! rather than every node at once.
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::disk_space::DiskSpace;
+
+#[derive(Clone, Debug, Default)]
+pub struct StoreSize {
+	pub bytes: Option<u64>,
+	pub files: u64,
+	pub error: Option<String>,
+}
+
+impl StoreSize {
+	/// The record store of a node, store_dirname within its data directory
+	pub fn store_dir(logfile: &str, store_dirname: &str) -> Option<PathBuf> {
+		Some(DiskSpace::data_dir(logfile)?.join(store_dirname))
+	}
+
+	/// Sum the size of the files in the record store at path
+	pub fn poll(&mut self, path: &Path) {
+		let mut files = 0;
+		match directory_size(path, &mut files) {
+			Ok(bytes) => {
+				self.bytes = Some(bytes);
+				self.files = files;
+				self.error = None;
+			}
+			Err(e) => self.error = Some(format!("{}: {}", path.display(), e)),
+		}
+	}
+}
+
+/// Total size of the files in a directory and its subdirectories, not following symlinks
+fn directory_size(path: &Path, files: &mut u64) -> io::Result<u64> {
+	let mut bytes = 0;
+	for entry in fs::read_dir(path)? {
+		let entry = entry?;
+		let file_type = entry.file_type()?;
+		if file_type.is_dir() {
+			bytes += directory_size(&entry.path(), files)?;
+		} else if file_type.is_file() {
+			bytes += entry.metadata()?.len();
+			*files += 1;
+		}
+	}
+	Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn it_sums_the_files_in_a_record_store() {
+		assert_eq!(
+			StoreSize::store_dir("/var/antctl/services/antnode1/logs/antnode.log", "record_store"),
+			Some(PathBuf::from("/var/antctl/services/antnode1/record_store"))
+		);
+
+		let dir = tempfile::TempDir::new().unwrap();
+		fs::write(dir.path().join("a"), vec![0u8; 1000]).unwrap();
+		fs::create_dir(dir.path().join("sub")).unwrap();
+		fs::write(dir.path().join("sub").join("b"), vec![0u8; 24]).unwrap();
+
+		let mut store_size = StoreSize::default();
+		store_size.poll(dir.path());
+		assert_eq!(store_size.bytes, Some(1024));
+		assert_eq!(store_size.files, 2);
+		assert!(store_size.error.is_none());
+
+		store_size.poll(&dir.path().join("missing"));
+		assert!(store_size.error.is_some());
+		assert_eq!(store_size.bytes, Some(1024));
+	}
+}
@@ -0,0 +1,238 @@
+//! Payments report for tax purposes, valuing each payment when it was received
+//!
+//! Payments received while vdash was running are valued using the price
+//! recorded in the earnings ledger. Others (e.g. loaded from older logfiles)
+//! are valued at the daily closing price for the day received, fetched from the
+//! CryptoCompare history API. The report is written in the background and
+//! the result shown in the status bar.
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde_json::Value;
+
+use super::background_results;
+use super::app::LogMonitor;
+use super::ui::ATTOS_PER_ANT;
+#[cfg(not(feature = "minimal"))]
+use super::web_requests::CRYPTOCOMPARE_SAFE_TOKEN_NAME;
+
+// Maximum days of history returned by one request to the CryptoCompare API
+#[cfg(not(feature = "minimal"))]
+const MAX_HISTORY_DAYS: i64 = 2000;
+
+/// A payment received by one of the monitored nodes
+#[derive(Clone, Debug)]
+pub struct TaxReportEntry {
+	pub time: DateTime<Utc>,
+	pub node_index: usize,
+	pub logfile: String,
+	pub peer_id: Option<String>,
+	pub record: Option<String>,
+	pub attos: u128,
+	pub recorded_price: Option<f64>, // Price in the report currency when received
+}
+
+/// Token price per day (UTC) from closing prices
+pub type DailyPrices = BTreeMap<NaiveDate, f64>;
+
+/// All payments received by the monitored nodes, oldest first
+pub fn collect_tax_report_entries(
+	monitors: &HashMap<String, LogMonitor>,
+	currency_apiname: &str,
+) -> Vec<TaxReportEntry> {
+	super::earnings_ledger::collect_node_earnings(monitors)
+		.into_iter()
+		.map(|e| TaxReportEntry {
+			time: e.entry.time,
+			node_index: e.node_index,
+			logfile: e.logfile.to_string(),
+			peer_id: monitors
+				.get(e.logfile)
+				.and_then(|monitor| monitor.metrics.node_peer_id.clone()),
+			record: e.entry.record.clone(),
+			attos: e.entry.attos,
+			recorded_price: match (&e.entry.currency_apiname, e.entry.currency_per_token) {
+				(Some(apiname), Some(rate)) if apiname.eq_ignore_ascii_case(currency_apiname) => Some(rate),
+				_ => None,
+			},
+		})
+		.collect()
+}
+
+/// Fetch daily closing prices in the currency from since until today
+#[cfg(not(feature = "minimal"))]
+async fn fetch_daily_prices(currency_apiname: &str, since: NaiveDate) -> Result<DailyPrices, Box<dyn std::error::Error>> {
+	let days = (Utc::now().date_naive() - since).num_days().clamp(1, MAX_HISTORY_DAYS);
+	let response = reqwest::Client::new()
+		.get("https://min-api.cryptocompare.com/data/v2/histoday")
+		.query(&[
+			("fsym", CRYPTOCOMPARE_SAFE_TOKEN_NAME),
+			("tsym", &currency_apiname.to_uppercase()),
+			("limit", &days.to_string()),
+		])
+		.send()
+		.await?;
+
+	let body = response.text().await?;
+	let json = serde_json::from_str::<Value>(&body)?;
+	if json["Response"].as_str() == Some("Error") {
+		let message = json["Message"].as_str().unwrap_or("price history not available");
+		return Err(message.into());
+	}
+	Ok(parse_daily_prices(&json))
+}
+
+/// A minimal build has no web access, so only prices recorded when received are used
+#[cfg(feature = "minimal")]
+async fn fetch_daily_prices(_currency_apiname: &str, _since: NaiveDate) -> Result<DailyPrices, Box<dyn std::error::Error>> {
+	Err("price history not available in a minimal build".into())
+}
+
+#[cfg_attr(feature = "minimal", allow(dead_code))]
+fn parse_daily_prices(json: &Value) -> DailyPrices {
+	let mut daily_prices = DailyPrices::new();
+	if let Some(days) = json["Data"]["Data"].as_array() {
+		for day in days {
+			if let (Some(time), Some(close)) = (day["time"].as_i64(), day["close"].as_f64()) {
+				// Days before the token was listed have a price of zero
+				if close > 0.0 {
+					if let Some(time) = DateTime::from_timestamp(time, 0) {
+						daily_prices.insert(time.date_naive(), close);
+					}
+				}
+			}
+		}
+	}
+	daily_prices
+}
+
+/// Write payments to a CSV file with their value when received.
+/// Returns the number of payments which could not be valued.
+pub fn write_tax_report_csv(
+	path: &Path,
+	entries: &[TaxReportEntry],
+	currency_apiname: &str,
+	daily_prices: &DailyPrices,
+) -> std::io::Result<usize> {
+	let mut unvalued = 0;
+	let mut file = File::create(path)?;
+	writeln!(
+		file,
+		"date,time,node,peer_id,logfile,record,attos,ant,currency,price_per_ant,value,price_source"
+	)?;
+	for e in entries {
+		let (price, price_source) = match e.recorded_price {
+			Some(price) => (Some(price), "received"),
+			None => match daily_prices.get(&e.time.date_naive()) {
+				Some(price) => (Some(*price), "daily close"),
+				None => (None, ""),
+			},
+		};
+		let ant = e.attos as f64 / ATTOS_PER_ANT;
+		let (price, value) = match price {
+			Some(price) => (format!("{:.9}", price), format!("{:.9}", price * ant)),
+			None => {
+				unvalued += 1;
+				(String::new(), String::new())
+			}
+		};
+		writeln!(
+			file,
+			"{},{},{},{},\"{}\",\"{}\",{},{:.18},{},{},{},{}",
+			e.time.date_naive(),
+			e.time.to_rfc3339(),
+			e.node_index + 1,
+			e.peer_id.as_deref().unwrap_or(""),
+			e.logfile.replace('"', "\"\""),
+			e.record.as_deref().unwrap_or("").replace('"', "\"\""),
+			e.attos,
+			ant,
+			currency_apiname,
+			price,
+			value,
+			price_source
+		)?;
+	}
+	Ok(unvalued)
+}
+
+/// Fetch price history for payments not valued when received, then write the report
+pub fn spawn_tax_report(path: String, entries: Vec<TaxReportEntry>, currency_apiname: String) {
+	tokio::spawn(async move {
+		let mut daily_prices = DailyPrices::new();
+		let mut price_error = None;
+		let oldest_unvalued = entries
+			.iter()
+			.filter(|e| e.recorded_price.is_none())
+			.map(|e| e.time.date_naive())
+			.min();
+		if let Some(since) = oldest_unvalued {
+			match fetch_daily_prices(&currency_apiname, since).await {
+				Ok(prices) => daily_prices = prices,
+				Err(e) => price_error = Some(e.to_string()),
+			}
+		}
+
+		let result = match write_tax_report_csv(Path::new(&path), &entries, &currency_apiname, &daily_prices) {
+			Ok(0) => format!("Exported {} payments valued in {} to {}", entries.len(), currency_apiname, path),
+			Ok(unvalued) => format!(
+				"Exported {} payments to {}, {} without a price{}",
+				entries.len(),
+				path,
+				unvalued,
+				price_error.map_or(String::new(), |e| format!(" ({})", e))
+			),
+			Err(e) => format!("Failed to export tax report: {}", e),
+		};
+		background_results::push(result);
+	});
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::str::FromStr;
+
+	#[test]
+	fn it_values_payments_when_received() {
+		let json = serde_json::from_str::<Value>(
+			r#"{"Response":"Success","Data":{"Data":[
+				{"time":1709164800,"close":0.0},
+				{"time":1709251200,"close":0.5},
+				{"time":1709337600,"close":0.75}]}}"#,
+		)
+		.unwrap();
+		let daily_prices = parse_daily_prices(&json);
+		assert_eq!(daily_prices.len(), 2);
+
+		let entry = |time: &str, recorded_price| TaxReportEntry {
+			time: DateTime::from_str(time).unwrap(),
+			node_index: 0,
+			logfile: String::from("/logs/antnode.log"),
+			peer_id: None,
+			record: Some(String::from("8a3f")),
+			attos: 2_000_000_000_000_000_000,
+			recorded_price,
+		};
+		let entries = vec![
+			entry("2024-03-01T10:00:00Z", None),
+			entry("2024-03-02T10:00:00Z", Some(1.0)),
+			entry("2024-03-05T10:00:00Z", None),
+		];
+
+		let path = std::env::temp_dir().join(format!("vdash-tax-report-{}.csv", std::process::id()));
+		let unvalued = write_tax_report_csv(&path, &entries, "USD", &daily_prices).unwrap();
+		let csv = std::fs::read_to_string(&path).unwrap();
+		std::fs::remove_file(&path).unwrap();
+
+		assert_eq!(unvalued, 1);
+		let lines: Vec<&str> = csv.lines().collect();
+		assert!(lines[1].ends_with("USD,0.500000000,1.000000000,daily close"));
+		assert!(lines[2].ends_with("USD,1.000000000,2.000000000,received"));
+		assert!(lines[3].ends_with("USD,,,"));
+		assert!(lines[1].contains("\"8a3f\""));
+	}
+}
@@ -0,0 +1,81 @@
+//! Restoring the terminal however vdash ends
+//!
+//! The dashboard and setup wizard put the terminal into raw mode on the
+//! alternate screen. If vdash then panicked, or returned early with an error,
+//! the terminal was left that way: the message lost with the alternate screen
+//! and the shell unusable until 'reset'. A TerminalGuard restores the terminal
+//! when dropped, and the hook set by install_panic_hook() restores it before a
+//! panic message is printed, so that the message can be read.
+use std::io::stdout;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crossterm::{
+	cursor,
+	event::{DisableMouseCapture, EnableMouseCapture},
+	execute,
+	terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+
+/// True from TerminalGuard::enter() until the terminal is restored
+static TERMINAL_ENTERED: AtomicBool = AtomicBool::new(false);
+
+/// Raw mode on the alternate screen until dropped
+pub struct TerminalGuard;
+
+impl TerminalGuard {
+	pub fn enter(mouse_capture: bool) -> std::io::Result<TerminalGuard> {
+		enable_raw_mode()?;
+		TERMINAL_ENTERED.store(true, Ordering::SeqCst);
+		// The guard is made first so the terminal is restored if what follows fails
+		let guard = TerminalGuard;
+		execute!(stdout(), EnterAlternateScreen)?;
+		if mouse_capture {
+			execute!(stdout(), EnableMouseCapture)?;
+		}
+		Ok(guard)
+	}
+}
+
+impl Drop for TerminalGuard {
+	fn drop(&mut self) {
+		let _ = restore_terminal();
+	}
+}
+
+/// Leave raw mode and the alternate screen if TerminalGuard::enter() has entered them,
+/// doing nothing if not or if they have already been left
+pub fn restore_terminal() -> std::io::Result<()> {
+	if !TERMINAL_ENTERED.swap(false, Ordering::SeqCst) {
+		return Ok(());
+	}
+	// Each is tried whatever happened to those before
+	let raw_mode_result = disable_raw_mode();
+	let screen_result = execute!(stdout(), DisableMouseCapture, LeaveAlternateScreen, cursor::Show);
+	raw_mode_result.and(screen_result)
+}
+
+/// Restore the terminal before any panic is reported, and then end vdash
+///
+/// A panic in a task or thread other than main would otherwise leave the
+/// dashboard running without it, such as one no longer parsing lines.
+pub fn install_panic_hook() {
+	let default_hook = std::panic::take_hook();
+	std::panic::set_hook(Box::new(move |info| {
+		let _ = restore_terminal();
+		default_hook(info);
+		eprintln!("\nvdash has stopped after an unexpected error. Please report it at https://github.com/happybeing/vdash/issues");
+		std::process::exit(101);
+	}));
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn it_leaves_a_terminal_which_was_not_entered() {
+		assert!(!TERMINAL_ENTERED.load(Ordering::SeqCst));
+		assert!(restore_terminal().is_ok());
+		assert!(restore_terminal().is_ok());
+	}
+}
@@ -0,0 +1,90 @@
+//! Sample logfiles for testing and benchmarking parsing
+//!
+//! Each fixture in tests/fixtures is an excerpt of a real node logfile with
+//! the metrics it should give checked below, so changes to parsing which
+//! alter what vdash shows for a known logfile fail 'cargo test'. The benches
+//! in benches/parse_lines.rs parse the same files.
+use super::node_metrics::parse_lines;
+use super::node_metrics::NodeMetrics;
+
+/// (name, contents) of each logfile in tests/fixtures
+pub const FIXTURES: [(&str, &str); 3] = [
+	("antnode-earning.log", include_str!("../../tests/fixtures/antnode-earning.log")),
+	("antnode-shunned.log", include_str!("../../tests/fixtures/antnode-shunned.log")),
+	("safenode-nanos.log", include_str!("../../tests/fixtures/safenode-nanos.log")),
+];
+
+/// The lines of the named fixture
+pub fn fixture_lines(name: &str) -> Vec<&'static str> {
+	match FIXTURES.iter().find(|(fixture, _)| *fixture == name) {
+		Some((_, contents)) => contents.lines().collect(),
+		None => panic!("No fixture named '{}' in tests/fixtures", name),
+	}
+}
+
+/// The metrics parse_lines() gives for the named fixture
+pub fn parse_fixture(name: &str) -> NodeMetrics {
+	parse_lines(&fixture_lines(name))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::custom::node_metrics::NodeStatus;
+	use crate::custom::token_units::TokenUnit;
+
+	#[test]
+	fn it_parses_an_earning_antnode() {
+		let metrics = parse_fixture("antnode-earning.log");
+		assert_eq!(metrics.running_version.as_deref(), Some("v0.112.6"));
+		assert_eq!(metrics.node_process_id, Some(31337));
+		assert_eq!(metrics.node_status, NodeStatus::Connected);
+		assert_eq!(metrics.token_unit, TokenUnit::Attos);
+
+		assert_eq!(metrics.activity_puts.total, 2);
+		assert_eq!(metrics.activity_gets.total, 1);
+		assert_eq!(metrics.activity_errors.total, 1);
+		assert_eq!(metrics.attos_earned.total, 4925);
+		assert_eq!(metrics.attos_earned.max, 2507);
+		assert_eq!(metrics.storage_cost.most_recent, 2507);
+		assert_eq!(metrics.wallet_balance, 4925);
+		assert_eq!(metrics.latest_earning, 2507);
+
+		assert_eq!(metrics.peers_connected.most_recent, 31);
+		assert_eq!(metrics.records_stored, 322);
+		assert_eq!(metrics.records_max, 4096);
+		assert_eq!(metrics.system_cpu, 12.5);
+	}
+
+	#[test]
+	fn it_parses_a_shunned_antnode() {
+		let metrics = parse_fixture("antnode-shunned.log");
+		assert_eq!(metrics.node_process_id, Some(4242));
+		assert_eq!(metrics.activity_puts.total, 1);
+		assert_eq!(metrics.shun_notifications, 1);
+		assert_eq!(metrics.activity_errors.total, 1);
+		// Stopping doesn't hide that the node was shunned
+		assert_eq!(metrics.node_status, NodeStatus::Shunned);
+	}
+
+	#[test]
+	fn it_parses_payments_in_nanos_from_a_safenode() {
+		let metrics = parse_fixture("safenode-nanos.log");
+		assert_eq!(metrics.running_version.as_deref(), Some("v0.110.0"));
+		assert_eq!(metrics.node_process_id, Some(5150));
+		assert_eq!(metrics.token_unit, TokenUnit::Nanos);
+		assert_eq!(metrics.attos_earned.total, 10_000_000_000);
+		assert_eq!(metrics.activity_puts.total, 1);
+	}
+
+	#[test]
+	fn it_skips_lines_before_the_first_message() {
+		let mut lines = vec!["Some preamble", "  with a Wrote record in it"];
+		lines.extend(fixture_lines("safenode-nanos.log"));
+		let metrics = parse_lines(&lines);
+		assert_eq!(metrics.activity_puts.total, 1);
+		// The preamble and the line after the first message
+		assert_eq!(metrics.lines_without_metadata, 3);
+		assert_eq!(metrics.first_entry_time.unwrap().to_rfc3339(), "2024-03-23T19:38:32.350118+00:00");
+	}
+}
@@ -0,0 +1,102 @@
+//! Selecting the period shown by the Node Status timelines
+//!
+//! Timelines normally end at the current time. The window can be panned back
+//! through the history held in the timeline buckets ('[' and ']'), or set to a
+//! period entered at a prompt ('w') such as '14:00-16:00 yesterday'. The
+//! window is held as its end time so that it stays put as new buckets are added.
+use chrono::{DateTime, Days, Duration, NaiveDate, NaiveTime, Utc};
+
+use super::app_timelines::TIMESCALES;
+
+/// Columns to move the timelines by for each pan
+pub const PAN_COLUMNS: i32 = 10;
+
+/// Parse a period such as '14:00-16:00', '14:00-16:00 yesterday' or
+/// '2024-03-23 14:00-16:00' (UTC), relative to today's date
+pub fn parse_timeline_window(text: &str, today: NaiveDate) -> Result<(DateTime<Utc>, DateTime<Utc>), String> {
+	let mut date = today;
+	let mut times: Option<&str> = None;
+	for word in text.split_whitespace() {
+		if word.eq_ignore_ascii_case("today") {
+			date = today;
+		} else if word.eq_ignore_ascii_case("yesterday") {
+			date = today.pred_opt().ok_or("invalid date")?;
+		} else if let Ok(parsed_date) = NaiveDate::parse_from_str(word, "%Y-%m-%d") {
+			date = parsed_date;
+		} else if times.is_none() && word.contains('-') {
+			times = Some(word);
+		} else {
+			return Err(format!("not understood '{}'", word));
+		}
+	}
+
+	let (start, end) = times
+		.and_then(|times| times.split_once('-'))
+		.ok_or("expected a period such as 14:00-16:00")?;
+	let parse_time = |time: &str| {
+		NaiveTime::parse_from_str(time, "%H:%M").map_err(|_| format!("invalid time '{}'", time))
+	};
+	let start = date.and_time(parse_time(start)?).and_utc();
+	let mut end = date.and_time(parse_time(end)?).and_utc();
+	if end <= start {
+		// The period ends on the following day (e.g. 22:00-02:00)
+		end = end.checked_add_days(Days::new(1)).ok_or("invalid date")?;
+	}
+	Ok((start, end))
+}
+
+/// The shortest timescale whose buckets reach back from now to start, if any
+pub fn timescale_reaching(start: &DateTime<Utc>, now: &DateTime<Utc>, timeline_steps: usize) -> Option<usize> {
+	TIMESCALES
+		.iter()
+		.position(|(_name, bucket_duration)| *now - *start <= *bucket_duration * timeline_steps as i32)
+}
+
+/// Number of the most recent buckets to leave out so the timeline ends at window_end
+pub fn pan_columns(
+	window_end: &Option<DateTime<Utc>>,
+	latest_bucket_time: &Option<DateTime<Utc>>,
+	bucket_duration: Duration,
+	num_buckets: usize,
+) -> usize {
+	match (window_end, latest_bucket_time) {
+		(Some(window_end), Some(latest_bucket_time)) => {
+			let latest_end = *latest_bucket_time + bucket_duration;
+			if *window_end >= latest_end || bucket_duration.num_seconds() == 0 {
+				return 0;
+			}
+			let columns = (latest_end - *window_end).num_seconds() / bucket_duration.num_seconds();
+			(columns as usize).min(num_buckets.saturating_sub(1))
+		}
+		_ => 0,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::str::FromStr;
+
+	#[test]
+	fn it_parses_windows_and_positions_timelines() {
+		let today = NaiveDate::from_ymd_opt(2024, 3, 23).unwrap();
+		let time = |text: &str| DateTime::<Utc>::from_str(text).unwrap();
+
+		let (start, end) = parse_timeline_window("14:00-16:00 yesterday", today).unwrap();
+		assert_eq!(start, time("2024-03-22T14:00:00Z"));
+		assert_eq!(end, time("2024-03-22T16:00:00Z"));
+		let (_start, end) = parse_timeline_window("2024-03-01 22:00-02:00", today).unwrap();
+		assert_eq!(end, time("2024-03-02T02:00:00Z"));
+		assert!(parse_timeline_window("14:00", today).is_err());
+
+		// Yesterday afternoon is too far back for minute columns (with 100 steps)
+		let now = time("2024-03-23T10:00:00Z");
+		let start = time("2024-03-22T14:00:00Z");
+		assert_eq!(timescale_reaching(&start, &now, 100), Some(2));
+
+		let latest_bucket_time = Some(time("2024-03-23T09:00:00Z"));
+		let window_end = Some(time("2024-03-22T16:00:00Z"));
+		assert_eq!(pan_columns(&window_end, &latest_bucket_time, Duration::hours(1), 100), 18);
+		assert_eq!(pan_columns(&None, &latest_bucket_time, Duration::hours(1), 100), 0);
+	}
+}
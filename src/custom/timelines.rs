@@ -2,7 +2,8 @@ use chrono::{DateTime, Duration, Utc};
 use ratatui::style::Color;
 use std::collections::HashMap;
 
-use crate::custom::app::debug_log;
+use crate::custom::node_metrics::debug_log;
+use crate::custom::percentiles::PercentileSketch;
 
 pub fn get_duration_text(duration: Duration) -> String {
 	return if duration.num_weeks() > 104 {
@@ -20,8 +21,18 @@ pub fn get_duration_text(duration: Duration) -> String {
 	};
 }
 
-pub fn get_max_buckets_value(buckets: &Vec<u64>) -> u64 {
-	let mut max: u64 = 0;
+/// A value in a timeline. Earnings are in attos, so a u64 (about 18 ANT) isn't
+/// enough for the total of a busy node, nor of many nodes.
+pub type BucketValue = u128;
+
+/// Total of values, which saturates rather than overflowing (which a BucketValue of attos can't
+/// in practice, as u128::MAX attos is far more than the supply of tokens)
+pub fn saturating_sum(values: &[BucketValue]) -> BucketValue {
+	values.iter().fold(0, |total, value| total.saturating_add(*value))
+}
+
+pub fn get_max_buckets_value(buckets: &Vec<BucketValue>) -> BucketValue {
+	let mut max: BucketValue = 0;
 	for i in 0..buckets.len() - 1 {
 		if buckets[i] > max {
 			max = buckets[i];
@@ -30,8 +41,8 @@ pub fn get_max_buckets_value(buckets: &Vec<u64>) -> u64 {
 	return max;
 }
 
-pub fn get_min_buckets_value(buckets: &Vec<u64>) -> u64 {
-	let mut min: u64 = u64::MAX;
+pub fn get_min_buckets_value(buckets: &Vec<BucketValue>) -> BucketValue {
+	let mut min: BucketValue = BucketValue::MAX;
 	for i in 0..buckets.len() - 1 {
 		if buckets[i] > 0 && buckets[i] < min {
 			min = buckets[i];
@@ -64,13 +75,28 @@ pub fn get_min_buckets_value(buckets: &Vec<u64>) -> u64 {
 ///! implement timelines of min, mean and max values for
 ///! a given metric.
 
-/// Specify min, mean, max series (as opposed to value series)
+/// Specify min, mean, max or percentile series (as opposed to value series)
 #[derive(Default)]
 pub enum MinMeanMax {
 	#[default]
 	Min = 1,
 	Mean = 2,
 	Max = 3,
+	P50 = 4,
+	P95 = 5,
+	P99 = 6,
+}
+
+impl MinMeanMax {
+	/// The fraction of values below a percentile series, such as 0.95 for P95
+	pub fn percentile(&self) -> Option<f64> {
+		match self {
+			MinMeanMax::P50 => Some(0.5),
+			MinMeanMax::P95 => Some(0.95),
+			MinMeanMax::P99 => Some(0.99),
+			_ => None,
+		}
+	}
 }
 
 use serde::{Deserialize, Serialize};
@@ -84,7 +110,7 @@ pub struct Timeline {
 	pub is_cumulative: bool,
 	pub colour: Color,
 
-	pub last_non_zero_value: u64,
+	pub last_non_zero_value: BucketValue,
 	buckets: HashMap<String, Buckets>,
 }
 
@@ -111,11 +137,26 @@ impl Timeline {
 		&self.name
 	}
 
+	/// Add a timescale, filled from the history of any shorter one which fits into it
 	pub fn add_bucket_set(&mut self, name: &'static str, duration: Duration, num_buckets: usize) {
-		self.buckets.insert(
-			name.to_string(),
-			Buckets::new(duration, num_buckets, self.is_mmm),
-		);
+		let mut bs = Buckets::new(duration, num_buckets, self.is_mmm);
+		self.backfill(&mut bs);
+		self.buckets.insert(name.to_string(), bs);
+	}
+
+	/// Fill new buckets from the longest of the existing buckets which fit into
+	/// them evenly, so a new timescale shows the history already collected rather
+	/// than starting empty. Returns false if none do.
+	fn backfill(&self, bs: &mut Buckets) -> bool {
+		let finer = self
+			.buckets
+			.values()
+			.filter(|finer| finer.bucket_time.is_some() && finer.divides(bs))
+			.max_by_key(|finer| finer.bucket_duration);
+		match finer {
+			Some(finer) => bs.rebucket_from(finer, self.is_cumulative),
+			None => false,
+		}
 	}
 
 	pub fn get_bucket_set(&self, timescale_name: &str) -> Option<&Buckets> {
@@ -130,7 +171,7 @@ impl Timeline {
 		&mut self,
 		timescale_name: &str,
 		mmm_ui_mode: Option<&MinMeanMax>,
-	) -> Option<&Vec<u64>> {
+	) -> Option<&Vec<BucketValue>> {
 		if let Some(bucket_set) = self.buckets.get(timescale_name) {
 			return Some(bucket_set.buckets(mmm_ui_mode));
 		} else {
@@ -142,7 +183,7 @@ impl Timeline {
 		&self,
 		timescale_name: &str,
 		mmm_ui_mode: Option<&MinMeanMax>,
-	) -> Option<&Vec<u64>> {
+	) -> Option<&Vec<BucketValue>> {
 		if let Some(bucket_set) = self.buckets.get(timescale_name) {
 			return Some(bucket_set.buckets(mmm_ui_mode));
 		} else {
@@ -160,7 +201,40 @@ impl Timeline {
 		}
 	}
 
-	pub fn update_value(&mut self, time: &DateTime<Utc>, value: u64) {
+	/// Reconcile a Timeline restored from a checkpoint with a newly created
+	/// one, keeping the restored values but taking the presentation and bucket
+	/// sets of the new one (whose timescales or number of buckets may differ)
+	pub fn restore(&mut self, current: Timeline) {
+		self.name = current.name;
+		self.units_text = current.units_text;
+		self.colour = current.colour;
+		if self.is_mmm != current.is_mmm || self.is_cumulative != current.is_cumulative {
+			// The values can't be reused, so start again
+			self.is_mmm = current.is_mmm;
+			self.is_cumulative = current.is_cumulative;
+			self.buckets = current.buckets;
+			return;
+		}
+
+		self.buckets.retain(|name, _| current.buckets.contains_key(name));
+		for bs in self.buckets.values_mut() {
+			bs.add_missing_percentiles();
+		}
+		for (name, current_bs) in current.buckets {
+			match self.buckets.get_mut(&name) {
+				Some(bs) if bs.bucket_duration == current_bs.bucket_duration => {
+					bs.resize(current_bs.num_buckets, self.is_cumulative);
+				}
+				_ => {
+					let mut current_bs = current_bs;
+					self.backfill(&mut current_bs);
+					self.buckets.insert(name, current_bs);
+				}
+			}
+		}
+	}
+
+	pub fn update_value(&mut self, time: &DateTime<Utc>, value: BucketValue) {
 		// debug_log!("update_value()");
 
 		if value > 0 {
@@ -221,21 +295,29 @@ pub struct Buckets {
 	#[serde_as(as = "DurationSeconds<i64>")]
 	pub bucket_duration: Duration,
 	pub num_buckets: usize,
-	pub values_total: u64,
-	pub values_min: u64,
-	pub values_max: u64,
+	pub values_total: BucketValue,
+	pub values_min: BucketValue,
+	pub values_max: BucketValue,
 
 	pub is_mmm: bool,
 
 	// if !is_mmm we only use buckets
-	pub buckets: Vec<u64>, // Value series
+	pub buckets: Vec<BucketValue>, // Value series
 
 	// if is_mmm use only the following
-	pub buckets_count: Vec<u64>, // Number of values added to a bucket (timeslot)
-	pub buckets_total: Vec<u64>, // Total of all values added to a given bucket (timeslot)
-	pub buckets_min: Vec<u64>,   // Min of all values
-	pub buckets_mean: Vec<u64>,  // Average
-	pub buckets_max: Vec<u64>,   // Max
+	pub buckets_count: Vec<BucketValue>, // Number of values added to a bucket (timeslot)
+	pub buckets_total: Vec<BucketValue>, // Total of all values added to a given bucket (timeslot)
+	pub buckets_min: Vec<BucketValue>,   // Min of all values
+	pub buckets_mean: Vec<BucketValue>,  // Average
+	pub buckets_max: Vec<BucketValue>,   // Max
+	#[serde(default)]
+	pub buckets_p50: Vec<BucketValue>, // Approximate percentiles, from buckets_sketch
+	#[serde(default)]
+	pub buckets_p95: Vec<BucketValue>,
+	#[serde(default)]
+	pub buckets_p99: Vec<BucketValue>,
+	#[serde(default)]
+	pub buckets_sketch: Vec<PercentileSketch>,
 
 	pub buckets_need_init: Vec<u64>, // Filled with 1 and set to 0 after init
 }
@@ -252,7 +334,7 @@ impl Buckets {
 			bucket_duration,
 			num_buckets,
 			values_total: 0,
-			values_min: u64::MAX,
+			values_min: BucketValue::MAX,
 			values_max: 0,
 			total_duration: bucket_duration * num_buckets as i32,
 
@@ -264,6 +346,10 @@ impl Buckets {
 			buckets_min: vec![0; mmm_buckets_size],
 			buckets_mean: vec![0; mmm_buckets_size],
 			buckets_max: vec![0; mmm_buckets_size],
+			buckets_p50: vec![0; mmm_buckets_size],
+			buckets_p95: vec![0; mmm_buckets_size],
+			buckets_p99: vec![0; mmm_buckets_size],
+			buckets_sketch: vec![PercentileSketch::default(); mmm_buckets_size],
 
 			buckets_need_init: vec![1; mmm_buckets_size],
 		};
@@ -294,6 +380,9 @@ impl Buckets {
 						&mut self.buckets_min,
 						&mut self.buckets_mean,
 						&mut self.buckets_max,
+						&mut self.buckets_p50,
+						&mut self.buckets_p95,
+						&mut self.buckets_p99,
 					]
 					.iter_mut()
 					{
@@ -302,6 +391,10 @@ impl Buckets {
 							buckets.remove(0);
 						}
 					}
+					self.buckets_sketch.push(PercentileSketch::default());
+					if self.buckets_sketch.len() > self.num_buckets {
+						self.buckets_sketch.remove(0);
+					}
 
 					self.buckets_need_init.push(1);
 					if self.buckets_need_init.len() > self.num_buckets {
@@ -338,7 +431,7 @@ impl Buckets {
 		};
 	}
 
-	pub fn bucket_update_value(&mut self, index: usize, value: u64, is_cumulative: bool) {
+	pub fn bucket_update_value(&mut self, index: usize, value: BucketValue, is_cumulative: bool) {
 		// debug_log!(format!("bucket_update_value(index:{}, value:{}, is_cum:{}) is_mmm:{}", index, value, is_cumulative, self.is_mmm).as_str());
 		if self.is_mmm {
 			debug_log!(format!(
@@ -353,13 +446,16 @@ impl Buckets {
 
 				self.buckets_count[index] = 0;
 				self.buckets_total[index] = 0;
-				self.buckets_min[index] = u64::MAX;
+				self.buckets_min[index] = BucketValue::MAX;
 				self.buckets_mean[index] = 0;
 				self.buckets_max[index] = 0;
+				self.buckets_sketch[index] = PercentileSketch::default();
 			}
 			self.buckets_count[index] += 1;
-			self.buckets_total[index] += value;
+			self.buckets_total[index] = self.buckets_total[index].saturating_add(value);
 			self.buckets_mean[index] = self.buckets_total[index] / self.buckets_count[index];
+			self.buckets_sketch[index].add(value);
+			self.update_percentiles(index);
 
 			if value < self.buckets_min[index] {
 				self.buckets_min[index] = value
@@ -376,14 +472,14 @@ impl Buckets {
 			}
 		} else {
 			if is_cumulative {
-				self.buckets[index] += value;
+				self.buckets[index] = self.buckets[index].saturating_add(value);
 				if self.buckets[index] < self.values_min {
 					self.values_min = self.buckets[index]
 				}
 				if self.buckets[index] > self.values_max {
 					self.values_max = self.buckets[index]
 				}
-				self.values_total += value;
+				self.values_total = self.values_total.saturating_add(value);
 			} else {
 				self.buckets[index] = value;
 				if value < self.values_min {
@@ -416,17 +512,158 @@ impl Buckets {
 		return String::from("(zero duration)");
 	}
 
+	/// Change the number of buckets, dropping or adding (empty) buckets at the
+	/// start so that the most recent buckets are kept
+	pub fn resize(&mut self, num_buckets: usize, is_cumulative: bool) {
+		fn resize_front<T: Copy>(buckets: &mut Vec<T>, len: usize, fill: T) -> Vec<T> {
+			if buckets.len() > len {
+				return buckets.drain(..buckets.len() - len).collect();
+			}
+			let padding = vec![fill; len - buckets.len()];
+			buckets.splice(0..0, padding);
+			Vec::new()
+		}
+
+		if num_buckets == 0 || num_buckets == self.num_buckets {
+			return;
+		}
+		if self.is_mmm {
+			for buckets in [
+				&mut self.buckets_count,
+				&mut self.buckets_total,
+				&mut self.buckets_min,
+				&mut self.buckets_mean,
+				&mut self.buckets_max,
+				&mut self.buckets_p50,
+				&mut self.buckets_p95,
+				&mut self.buckets_p99,
+			] {
+				resize_front(buckets, num_buckets, 0);
+			}
+			resize_front(&mut self.buckets_need_init, num_buckets, 1);
+			if self.buckets_sketch.len() > num_buckets {
+				self.buckets_sketch.drain(..self.buckets_sketch.len() - num_buckets);
+			} else {
+				let padding = vec![PercentileSketch::default(); num_buckets - self.buckets_sketch.len()];
+				self.buckets_sketch.splice(0..0, padding);
+			}
+		} else {
+			let dropped = resize_front(&mut self.buckets, num_buckets, 0);
+			if is_cumulative {
+				self.values_total -= saturating_sum(&dropped).min(self.values_total);
+			}
+		}
+		self.num_buckets = num_buckets;
+		self.total_duration = self.bucket_duration * num_buckets as i32;
+	}
+
+	/// True if these buckets are shorter than, and fit a whole number of times into, those of other
+	pub fn divides(&self, other: &Buckets) -> bool {
+		match (self.bucket_duration.num_nanoseconds(), other.bucket_duration.num_nanoseconds()) {
+			(Some(finer), Some(coarser)) => {
+				self.is_mmm == other.is_mmm && finer > 0 && coarser > finer && coarser % finer == 0
+			}
+			_ => false,
+		}
+	}
+
+	/// Fill new (empty) buckets with the values of finer buckets which divide
+	/// them, so a timescale added since a checkpoint was saved shows the history
+	/// already collected. Returns false, leaving the buckets empty, if finer
+	/// doesn't divide them.
+	pub fn rebucket_from(&mut self, finer: &Buckets, is_cumulative: bool) -> bool {
+		let Some(bucket_time) = finer.bucket_time else {
+			return false;
+		};
+		if !finer.divides(self) {
+			return false;
+		}
+		let ratio = (self.bucket_duration.num_nanoseconds().unwrap_or(0)
+			/ finer.bucket_duration.num_nanoseconds().unwrap_or(1)) as usize;
+
+		// The latest buckets of both start at bucket_time, so the latest finer bucket
+		// fills the latest bucket, and each earlier bucket takes ratio finer ones
+		self.bucket_time = Some(bucket_time);
+		self.earliest_time = finer.earliest_time;
+		self.latest_time = finer.latest_time;
+		let finer_len = if finer.is_mmm { finer.buckets_count.len() } else { finer.buckets.len() };
+		let len = if self.is_mmm { self.buckets_count.len() } else { self.buckets.len() };
+		for back in 0..len {
+			let first_back = if back == 0 { 0 } else { (back - 1) * ratio + 1 };
+			if first_back >= finer_len {
+				break;
+			}
+			let last_back = (back * ratio).min(finer_len - 1);
+			let indices = (finer_len - 1 - last_back)..(finer_len - first_back);
+			let index = len - 1 - back;
+			if self.is_mmm {
+				let mut initialised = indices.filter(|i| finer.buckets_need_init[*i] == 0 && finer.buckets_count[*i] > 0).peekable();
+				if initialised.peek().is_none() {
+					continue;
+				}
+				self.buckets_need_init[index] = 0;
+				self.buckets_min[index] = BucketValue::MAX;
+				for i in initialised {
+					self.buckets_count[index] += finer.buckets_count[i];
+					self.buckets_total[index] = self.buckets_total[index].saturating_add(finer.buckets_total[i]);
+					self.buckets_min[index] = self.buckets_min[index].min(finer.buckets_min[i]);
+					self.buckets_max[index] = self.buckets_max[index].max(finer.buckets_max[i]);
+					self.buckets_sketch[index].merge(&finer.buckets_sketch[i]);
+				}
+				self.buckets_mean[index] = self.buckets_total[index] / self.buckets_count[index];
+				self.update_percentiles(index);
+			} else if is_cumulative {
+				self.buckets[index] = saturating_sum(&finer.buckets[indices]);
+			} else {
+				// The most recent value
+				self.buckets[index] = finer.buckets[indices].iter().rev().find(|value| **value > 0).copied().unwrap_or(0);
+			}
+		}
+
+		if is_cumulative && !self.is_mmm {
+			self.values_total = saturating_sum(&self.buckets);
+			self.values_min = self.buckets.iter().filter(|value| **value > 0).min().copied().unwrap_or(BucketValue::MAX);
+			self.values_max = self.buckets.iter().max().copied().unwrap_or(0);
+		} else {
+			self.values_min = finer.values_min;
+			self.values_max = finer.values_max;
+		}
+		true
+	}
+
+	fn update_percentiles(&mut self, index: usize) {
+		let sketch = &self.buckets_sketch[index];
+		self.buckets_p50[index] = sketch.percentile(0.5).unwrap_or(0);
+		self.buckets_p95[index] = sketch.percentile(0.95).unwrap_or(0);
+		self.buckets_p99[index] = sketch.percentile(0.99).unwrap_or(0);
+	}
+
+	/// Give buckets restored from a checkpoint saved before percentiles were
+	/// kept empty percentiles, so they fill from now on
+	pub fn add_missing_percentiles(&mut self) {
+		if self.is_mmm && self.buckets_sketch.len() != self.buckets_count.len() {
+			let len = self.buckets_count.len();
+			self.buckets_p50 = vec![0; len];
+			self.buckets_p95 = vec![0; len];
+			self.buckets_p99 = vec![0; len];
+			self.buckets_sketch = vec![PercentileSketch::default(); len];
+		}
+	}
+
 	pub fn num_buckets(&self) -> usize {
 		return self.num_buckets;
 	}
 
-	pub fn buckets(&self, mmm_ui_mode: Option<&MinMeanMax>) -> &Vec<u64> {
+	pub fn buckets(&self, mmm_ui_mode: Option<&MinMeanMax>) -> &Vec<BucketValue> {
 		if self.is_mmm {
 			return match mmm_ui_mode {
 				None => &self.buckets,
 				Some(MinMeanMax::Min) => &self.buckets_min,
 				Some(MinMeanMax::Mean) => &self.buckets_mean,
 				Some(MinMeanMax::Max) => &self.buckets_max,
+				Some(MinMeanMax::P50) => &self.buckets_p50,
+				Some(MinMeanMax::P95) => &self.buckets_p95,
+				Some(MinMeanMax::P99) => &self.buckets_p99,
 			};
 		} else {
 			return &self.buckets;
@@ -0,0 +1,130 @@
+//! Denomination of the token amounts in node logfiles
+//!
+//! Autonomi nodes log payments and storage costs in attos (10^-18 ANT) but
+//! older safenode logs gave them in nanos (10^-9 SNT), for example:
+//!
+//!   Total payment of NanoTokens(10) nanos for record ...
+//!
+//! Each node's unit is taken from its payment lines when they name it, or
+//! else from the binary named by its 'Running' line. Amounts are scaled to
+//! attos when parsed so that nodes can be totalled together, and shown in
+//! nanos only when every node logs nanos.
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::node_metrics::LogMonitor;
+
+const ATTOS_PER_NANO: u128 = 1_000_000_000;
+pub const ATTOS_PER_ANT: f64 = 1e18;
+
+pub fn attos_to_ant(attos: u128) -> f64 {
+	attos as f64 / ATTOS_PER_ANT
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum TokenUnit {
+	#[default]
+	Attos,
+	Nanos,
+}
+
+impl TokenUnit {
+	/// The unit a logfile line shows its node uses, if any
+	pub fn of_line(line: &str) -> Option<TokenUnit> {
+		if line.contains("Total payment of") {
+			if line.contains(" nanos") || line.contains("NanoTokens(") {
+				return Some(TokenUnit::Nanos);
+			}
+			if line.contains(" attos") || line.contains("AttoTokens(") {
+				return Some(TokenUnit::Attos);
+			}
+		} else if line.starts_with("Running safenode ") {
+			return Some(TokenUnit::Nanos);
+		} else if line.starts_with("Running antnode ") {
+			return Some(TokenUnit::Attos);
+		}
+		None
+	}
+
+	/// Name of the unit, as shown beside amounts
+	pub fn name(&self) -> &'static str {
+		match self {
+			TokenUnit::Attos => "attos",
+			TokenUnit::Nanos => "nanos",
+		}
+	}
+
+	/// Name of the token the unit divides
+	pub fn token_name(&self) -> &'static str {
+		match self {
+			TokenUnit::Attos => "ANT",
+			TokenUnit::Nanos => "SNT",
+		}
+	}
+
+	/// An amount logged in this unit as attos
+	pub fn to_attos(&self, amount: u128) -> u128 {
+		match self {
+			TokenUnit::Attos => amount,
+			TokenUnit::Nanos => amount.saturating_mul(ATTOS_PER_NANO),
+		}
+	}
+
+	/// An amount in attos as this unit
+	pub fn from_attos(&self, attos: u128) -> u128 {
+		match self {
+			TokenUnit::Attos => attos,
+			TokenUnit::Nanos => attos / ATTOS_PER_NANO,
+		}
+	}
+}
+
+/// An amount as JSON: a number while it fits in u64, which is as far as most JSON
+/// readers go, or else a string of digits
+pub fn attos_value(attos: u128) -> Value {
+	u64::try_from(attos).map_or_else(|_| Value::from(attos.to_string()), Value::from)
+}
+
+/// The unit to show amounts in: nanos if every node logs nanos, otherwise attos
+pub fn fleet_token_unit(monitors: &HashMap<String, LogMonitor>) -> TokenUnit {
+	let mut nodes = monitors.values().filter(|monitor| monitor.is_node()).peekable();
+	if nodes.peek().is_some() && nodes.all(|monitor| monitor.metrics.token_unit == TokenUnit::Nanos) {
+		TokenUnit::Nanos
+	} else {
+		TokenUnit::Attos
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn it_detects_the_unit_from_the_log() {
+		assert_eq!(TokenUnit::of_line("Running safenode v0.98.32"), Some(TokenUnit::Nanos));
+		assert_eq!(TokenUnit::of_line("Running antnode v0.1.0"), Some(TokenUnit::Attos));
+		assert_eq!(
+			TokenUnit::of_line("Total payment of NanoTokens(10) nanos for record 0x01"),
+			Some(TokenUnit::Nanos)
+		);
+		assert_eq!(TokenUnit::of_line("Total payment of 10 attos for record 0x01"), Some(TokenUnit::Attos));
+		assert_eq!(TokenUnit::of_line("Cost is now 42 for quoting_metrics"), None);
+
+		assert_eq!(TokenUnit::Nanos.to_attos(10), 10_000_000_000);
+		assert_eq!(TokenUnit::Nanos.from_attos(10_000_000_000), 10);
+		assert_eq!(TokenUnit::Nanos.to_attos(u64::MAX as u128), u64::MAX as u128 * 1_000_000_000);
+		assert_eq!(attos_value(250), Value::from(250));
+		assert_eq!(attos_value(u64::MAX as u128 + 1), Value::from("18446744073709551616"));
+
+		let mut monitors = HashMap::new();
+		let mut monitor = LogMonitor::new(String::from("node1/safenode.log"));
+		monitor.metrics.token_unit = TokenUnit::Nanos;
+		monitors.insert(monitor.logfile.clone(), monitor);
+		assert_eq!(fleet_token_unit(&monitors), TokenUnit::Nanos);
+		let monitor = LogMonitor::new(String::from("node2/antnode.log"));
+		monitors.insert(monitor.logfile.clone(), monitor);
+		assert_eq!(fleet_token_unit(&monitors), TokenUnit::Attos);
+	}
+}
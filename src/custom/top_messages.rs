@@ -0,0 +1,159 @@
+//! The most frequent ERROR and WARN messages of a node
+//!
+//! Messages are normalised before they are counted, so that those which differ
+//! only in numbers, peer ids or hashes are counted as one. The Node view can
+//! then list what is going wrong without scrolling the raw logfile.
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Distinct messages kept per node. When full, the least frequent is dropped to make room.
+pub const MAX_MESSAGES: usize = 500;
+
+/// Levels of the messages counted
+pub const LEVELS_COUNTED: [&str; 2] = ["ERROR", "WARN"];
+
+static NORMALISE_PATTERNS: LazyLock<Vec<(Regex, &str)>> = LazyLock::new(|| {
+	[
+		(r"\b12D3KooW[1-9A-HJ-NP-Za-km-z]+\b", "<peer>"),
+		(r"\b(0x)?[0-9a-fA-F]{16,}\b", "<hex>"),
+		(r"\d+(\.\d+)?", "#"),
+	]
+	.iter()
+	.map(|(pattern, replacement)| {
+		(
+			Regex::new(pattern).expect("The message normalising regexes failed to compile. This is a bug."),
+			*replacement,
+		)
+	})
+	.collect()
+});
+
+/// A message with peer ids, hashes and numbers replaced by placeholders
+pub fn normalise_message(message: &str) -> String {
+	let mut normalised = message.trim().to_string();
+	for (regex, replacement) in NORMALISE_PATTERNS.iter() {
+		normalised = regex.replace_all(&normalised, *replacement).to_string();
+	}
+	normalised
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MessageCount {
+	pub level: String,
+	pub message: String, // Normalised
+	pub count: u64,
+	pub last_seen: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct TopMessages {
+	messages: HashMap<String, MessageCount>,
+}
+
+impl TopMessages {
+	/// Count a message if its level is one of LEVELS_COUNTED
+	pub fn record(&mut self, level: &str, message: &str, time: &DateTime<Utc>) {
+		if !LEVELS_COUNTED.contains(&level) {
+			return;
+		}
+		let message = normalise_message(message);
+		let key = format!("{} {}", level, message);
+
+		if let Some(existing) = self.messages.get_mut(&key) {
+			existing.count += 1;
+			if *time > existing.last_seen {
+				existing.last_seen = *time;
+			}
+			return;
+		}
+
+		if self.messages.len() >= MAX_MESSAGES {
+			let least_frequent = self
+				.messages
+				.iter()
+				.min_by_key(|(_key, counted)| (counted.count, counted.last_seen))
+				.map(|(key, _counted)| key.clone());
+			if let Some(least_frequent) = least_frequent {
+				self.messages.remove(&least_frequent);
+			}
+		}
+		self.messages.insert(
+			key,
+			MessageCount {
+				level: level.to_string(),
+				message,
+				count: 1,
+				last_seen: *time,
+			},
+		);
+	}
+
+	/// The most frequent messages, most recently seen first when counts are equal
+	pub fn top(&self, limit: usize) -> Vec<&MessageCount> {
+		let mut messages: Vec<&MessageCount> = self.messages.values().collect();
+		messages.sort_by(|a, b| b.count.cmp(&a.count).then(b.last_seen.cmp(&a.last_seen)));
+		messages.truncate(limit);
+		messages
+	}
+
+	/// The message of level seen most recently
+	pub fn latest(&self, level: &str) -> Option<&MessageCount> {
+		self.messages.values().filter(|counted| counted.level == level).max_by_key(|counted| counted.last_seen)
+	}
+
+	pub fn len(&self) -> usize {
+		self.messages.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.messages.is_empty()
+	}
+
+	pub fn clear(&mut self) {
+		self.messages.clear();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn it_counts_normalised_messages() {
+		let time = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+		let mut top = TopMessages::default();
+		top.record(
+			"ERROR",
+			"Failed to dial 12D3KooWBgPmyh4oBtLcTuB3fLpkEEF5sSzb3bYrxb4yKczyRCsd after 3 attempts",
+			&time("2024-03-23T19:00:00Z"),
+		);
+		top.record(
+			"ERROR",
+			"Failed to dial 12D3KooWQwL2hXkScXwyhuYtKGBrF1ZpzgVcaXNs1qmMCHc6m2bq after 5 attempts",
+			&time("2024-03-23T19:05:00Z"),
+		);
+		top.record("WARN", "Record 7f3a9c0d1e2b4a5f6c7d8e9f0a1b2c3d not found", &time("2024-03-23T19:01:00Z"));
+		top.record("INFO", "Connected to 12 peers", &time("2024-03-23T19:02:00Z"));
+
+		assert_eq!(top.len(), 2);
+		let messages = top.top(10);
+		assert_eq!(messages[0].message, "Failed to dial <peer> after # attempts");
+		assert_eq!(messages[0].count, 2);
+		assert_eq!(messages[0].last_seen, time("2024-03-23T19:05:00Z"));
+		assert_eq!(messages[1].message, "Record <hex> not found");
+		assert_eq!(messages[1].level, "WARN");
+		assert_eq!(top.latest("ERROR").unwrap().last_seen, time("2024-03-23T19:05:00Z"));
+		assert_eq!(top.latest("WARN").unwrap().message, "Record <hex> not found");
+		assert!(top.latest("INFO").is_none());
+
+		for i in 0..MAX_MESSAGES {
+			top.record("WARN", &format!("Distinct message {}", "x".repeat(i)), &time("2024-03-23T19:10:00Z"));
+		}
+		assert_eq!(top.len(), MAX_MESSAGES);
+		assert_eq!(top.top(1)[0].count, 2);
+	}
+}
@@ -0,0 +1,221 @@
+//! Commands run when a node meets a condition (see --trigger)
+//!
+//! Triggers are given as '<condition>=<command>', for example:
+//!
+//!   stopped=systemctl restart antnode@$NODE
+//!   errors>20=notify-send "vdash" "$NODE_PATH logged $VALUE errors in a minute"
+//!   earnings=paplay /usr/share/sounds/freedesktop/stereo/complete.oga
+//!
+//! The conditions are 'stopped' (a node's status changes to Stopped),
+//! 'inactive' (a node stops logging), 'errors>N' (more than N errors logged
+//! in the last minute) and 'earnings' (a payment is received). Commands are
+//! run by the shell with NODE_PATH (the node's logfile), NODE (its number),
+//! PEER_ID, METRIC and VALUE in the environment. As with --events-out, only
+//! changes after vdash has first seen a node fire its triggers.
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use chrono::{DateTime, Duration, Utc};
+use tokio::process::Command;
+
+use super::background_results;
+use super::app::LogMonitor;
+use super::events_out::EventsOut;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Condition {
+	Stopped,
+	Inactive,
+	ErrorsPerMinute(u64),
+	Earnings,
+}
+
+impl Condition {
+	fn parse(condition: &str) -> Result<Condition, String> {
+		match condition.trim().to_lowercase().as_str() {
+			"stopped" => Ok(Condition::Stopped),
+			"inactive" => Ok(Condition::Inactive),
+			"earnings" => Ok(Condition::Earnings),
+			condition => match condition.strip_prefix("errors>").map(|limit| limit.trim().parse::<u64>()) {
+				Some(Ok(limit)) => Ok(Condition::ErrorsPerMinute(limit)),
+				_ => Err(format!("unknown --trigger condition '{}' (use stopped, inactive, errors>N or earnings)", condition)),
+			},
+		}
+	}
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Trigger {
+	pub condition: Condition,
+	pub command: String,
+}
+
+impl Trigger {
+	/// Parse a --trigger value, '<condition>=<command>'
+	pub fn parse(trigger: &str) -> Result<Trigger, String> {
+		let Some((condition, command)) = trigger.split_once('=') else {
+			return Err(format!("--trigger '{}' should be <condition>=<command>", trigger));
+		};
+		if command.trim().is_empty() {
+			return Err(format!("--trigger '{}' has no command", trigger));
+		}
+		Ok(Trigger {
+			condition: Condition::parse(condition)?,
+			command: command.trim().to_string(),
+		})
+	}
+}
+
+/// A trigger command to run, with its environment
+#[derive(Clone, Debug, PartialEq)]
+pub struct Firing {
+	pub command: String,
+	pub env: Vec<(String, String)>,
+}
+
+pub struct Triggers {
+	triggers: Vec<Trigger>,
+	events: EventsOut,
+	error_totals: HashMap<String, VecDeque<(DateTime<Utc>, u64)>>, // Errors logged by a node over the last minute
+	errors_raised: HashSet<(usize, String)>,                        // Triggers (by index) fired for a logfile and not yet reset
+}
+
+impl Triggers {
+	/// Triggers for --trigger values
+	pub fn new(opt_triggers: &[String]) -> Result<Triggers, String> {
+		Ok(Triggers {
+			triggers: opt_triggers.iter().map(|trigger| Trigger::parse(trigger)).collect::<Result<_, _>>()?,
+			events: EventsOut::new(""),
+			error_totals: HashMap::new(),
+			errors_raised: HashSet::new(),
+		})
+	}
+
+	/// Run the commands of triggers whose conditions have been met since the last call
+	pub fn run(&mut self, monitors: &HashMap<String, LogMonitor>, now: &DateTime<Utc>) {
+		for firing in self.take_firings(monitors, now) {
+			spawn_command(firing);
+		}
+	}
+
+	/// Commands due to run since the last call
+	pub fn take_firings(&mut self, monitors: &HashMap<String, LogMonitor>, now: &DateTime<Utc>) -> Vec<Firing> {
+		let mut firings = Vec::<Firing>::new();
+		for event in self.events.take_events(monitors, now) {
+			let Some(monitor) = event["logfile"].as_str().and_then(|logfile| monitors.get(logfile)) else {
+				continue;
+			};
+			let (condition, metric, value) = match event["event"].as_str() {
+				Some("status") if event["to"] == "Stopped" => (Condition::Stopped, "status", String::from("Stopped")),
+				Some("alert") if event["alert"] == "inactive" && event["raised"] == true => {
+					(Condition::Inactive, "inactive", String::from("true"))
+				}
+				Some("payment") => (Condition::Earnings, "earnings", event["attos"].to_string().replace('"', "")),
+				_ => continue,
+			};
+			for trigger in self.triggers.iter().filter(|trigger| trigger.condition == condition) {
+				firings.push(firing(trigger, monitor, metric, &value));
+			}
+		}
+
+		let mut logfiles: Vec<&String> = monitors.keys().filter(|logfile| monitors[*logfile].is_node()).collect();
+		logfiles.sort_by_key(|logfile| monitors[*logfile].index);
+		for logfile in logfiles {
+			let monitor = &monitors[logfile];
+			let totals = self.error_totals.entry(logfile.clone()).or_default();
+			totals.push_back((*now, monitor.metrics.activity_errors.total));
+			while totals.front().is_some_and(|(time, _)| *now - *time > Duration::minutes(1)) {
+				totals.pop_front();
+			}
+			let minute_ago_total = totals.front().map_or(0, |(_, total)| *total);
+			let errors_per_minute = monitor.metrics.activity_errors.total.saturating_sub(minute_ago_total);
+			for (index, trigger) in self.triggers.iter().enumerate() {
+				let Condition::ErrorsPerMinute(limit) = trigger.condition else {
+					continue;
+				};
+				let key = (index, logfile.clone());
+				if errors_per_minute <= limit {
+					self.errors_raised.remove(&key);
+				} else if self.errors_raised.insert(key) {
+					firings.push(firing(trigger, monitor, "errors_per_minute", &errors_per_minute.to_string()));
+				}
+			}
+		}
+		firings
+	}
+}
+
+fn firing(trigger: &Trigger, monitor: &LogMonitor, metric: &str, value: &str) -> Firing {
+	Firing {
+		command: trigger.command.clone(),
+		env: vec![
+			(String::from("NODE_PATH"), monitor.logfile.clone()),
+			(String::from("NODE"), (monitor.index + 1).to_string()),
+			(String::from("PEER_ID"), monitor.metrics.node_peer_id.clone().unwrap_or_default()),
+			(String::from("METRIC"), metric.to_string()),
+			(String::from("VALUE"), value.to_string()),
+		],
+	}
+}
+
+/// Run the command in the background using the shell, noting any failure
+fn spawn_command(firing: Firing) {
+	let Firing { command: shell_command, env } = firing;
+	let mut command = if cfg!(windows) { Command::new("cmd") } else { Command::new("sh") };
+	command.arg(if cfg!(windows) { "/C" } else { "-c" }).arg(&shell_command).envs(env);
+	tokio::spawn(async move {
+		let failure = match command.output().await {
+			Ok(output) if output.status.success() => return,
+			Ok(output) => format!(
+				"Trigger '{}' failed: {}",
+				shell_command,
+				String::from_utf8_lossy(&output.stderr).lines().last().unwrap_or("unknown error")
+			),
+			Err(e) => format!("Failed to run trigger '{}': {}", shell_command, e),
+		};
+		background_results::push(failure);
+	});
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::custom::app::NodeStatus;
+
+	#[test]
+	fn it_fires_triggers_for_changes_after_nodes_are_seen() {
+		assert_eq!(
+			Trigger::parse("errors>5=echo $VALUE=errors"),
+			Ok(Trigger { condition: Condition::ErrorsPerMinute(5), command: String::from("echo $VALUE=errors") })
+		);
+		assert!(Trigger::parse("errors>lots=echo").is_err());
+		assert!(Trigger::parse("stopped").is_err());
+		assert!(Trigger::parse("stopped= ").is_err());
+
+		let now = Utc::now();
+		let mut monitors = HashMap::new();
+		let mut monitor = LogMonitor::new(String::from("node1/antnode.log"));
+		monitor.metrics.node_status = NodeStatus::Connected;
+		monitor.metrics.activity_errors.total = 100;
+		monitors.insert(monitor.logfile.clone(), monitor);
+		let opt_triggers = [String::from("stopped=restart"), String::from("errors>5=alert"), String::from("earnings=ding")];
+		let mut triggers = Triggers::new(&opt_triggers).unwrap();
+		assert!(triggers.take_firings(&monitors, &now).is_empty());
+
+		let monitor = monitors.get_mut("node1/antnode.log").unwrap();
+		monitor.metrics.node_status = NodeStatus::Stopped;
+		monitor.metrics.activity_errors.total = 110;
+		monitor.metrics.earnings_ledger.record_payment(&now, 250, None);
+		let firings = triggers.take_firings(&monitors, &(now + Duration::seconds(10)));
+		let commands: Vec<&str> = firings.iter().map(|firing| firing.command.as_str()).collect();
+		assert_eq!(commands, ["ding", "restart", "alert"]);
+		assert!(firings[0].env.contains(&(String::from("VALUE"), String::from("250"))));
+		assert!(firings[2].env.contains(&(String::from("METRIC"), String::from("errors_per_minute"))));
+		assert!(firings[2].env.contains(&(String::from("NODE_PATH"), String::from("node1/antnode.log"))));
+
+		// Errors fire again only after falling back to the limit
+		assert!(triggers.take_firings(&monitors, &(now + Duration::seconds(20))).is_empty());
+		assert!(triggers.take_firings(&monitors, &(now + Duration::seconds(90))).is_empty());
+		monitors.get_mut("node1/antnode.log").unwrap().metrics.activity_errors.total = 120;
+		assert_eq!(triggers.take_firings(&monitors, &(now + Duration::seconds(100))).len(), 1);
+	}
+}
@@ -0,0 +1,1101 @@
+//! End-to-end tests which drive the TUI
+//!
+//! Each test writes synthetic node logfiles, creates an App for them as
+//! vdash would, then sends scripted key events and checks the text rendered
+//! to a ratatui TestBackend for the active view.
+use std::fs;
+use std::path::Path;
+
+use chrono::{Duration, Utc};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde_json::json;
+use ratatui::{backend::TestBackend, Terminal};
+use structopt::StructOpt;
+use tempfile::TempDir;
+
+use super::app::{set_opt, App, DashViewMain, NodeStatus};
+use super::node_metrics::reset_monitor_indexes;
+use super::antctl::{NodeAction, PendingNodeAction};
+use super::averages::AverageMode;
+use super::control_socket::handle_control_request;
+use super::mini::mini_lines;
+use super::opt::Opt;
+use super::preferences::Preferences;
+use super::token_units::TokenUnit;
+use super::ui::draw_dashboard;
+use super::ui_keyboard::handle_keyboard_event;
+use super::ui_status_bar::status_bar_parts;
+use super::ui_summary_table::SUMMARY_COLUMNS;
+
+const TERMINAL_WIDTH: u16 = 200;
+const TERMINAL_HEIGHT: u16 = 50;
+
+// The App is configured through the global OPT so tests must not overlap. It is
+// only given the args below, never those of the test binary (e.g. --nocapture).
+static HARNESS_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+pub struct TuiHarness {
+	pub app: App,
+	terminal: Terminal<TestBackend>,
+	_logs_dir: TempDir,
+}
+
+impl TuiHarness {
+	/// Create an App monitoring one logfile per entry in node_logs. Each logfile
+	/// is at <tempdir>/node-<n>/logs/antnode.log
+	pub async fn new(node_logs: &[String]) -> TuiHarness {
+		TuiHarness::with_args(node_logs, &[]).await
+	}
+
+	/// As new() with additional vdash command line arguments
+	pub async fn with_args(node_logs: &[String], extra_args: &[&str]) -> TuiHarness {
+		let logs_dir = TempDir::new().unwrap();
+		// Keep saved preferences out of the user's config directory
+		std::env::set_var("XDG_CONFIG_HOME", logs_dir.path());
+		let mut args = vec![
+			String::from("vdash"),
+			String::from("--checkpoint-interval"),
+			String::from("0"),
+		];
+		args.extend(extra_args.iter().map(|arg| arg.to_string()));
+		// Keep logfiles from being taken as further values of options given more than once
+		args.push(String::from("--"));
+		for (i, log) in node_logs.iter().enumerate() {
+			let node_logs_dir = logs_dir.path().join(format!("node-{}", i + 1)).join("logs");
+			fs::create_dir_all(&node_logs_dir).unwrap();
+			let logfile = node_logs_dir.join("antnode.log");
+			fs::write(&logfile, log).unwrap();
+			args.push(logfile.to_string_lossy().to_string());
+		}
+		set_opt(Opt::from_iter(args));
+
+		reset_monitor_indexes();
+		let app = App::new().await.unwrap();
+		let terminal = Terminal::new(TestBackend::new(TERMINAL_WIDTH, TERMINAL_HEIGHT)).unwrap();
+		TuiHarness {
+			app,
+			terminal,
+			_logs_dir: logs_dir,
+		}
+	}
+
+	pub async fn press(&mut self, code: KeyCode) {
+		self.press_with(code, KeyModifiers::NONE).await;
+	}
+
+	pub async fn press_with(&mut self, code: KeyCode, modifiers: KeyModifiers) {
+		let event = KeyEvent::new(code, modifiers);
+		assert!(handle_keyboard_event(&mut self.app, &event, false).await);
+	}
+
+	/// Press each character of keys in turn
+	pub async fn type_keys(&mut self, keys: &str) {
+		for c in keys.chars() {
+			self.press(KeyCode::Char(c)).await;
+		}
+	}
+
+	/// Draw the dashboard and return the rendered lines
+	pub fn render(&mut self) -> Vec<String> {
+		let app = &mut self.app;
+		self.terminal.draw(|f| draw_dashboard(f, app)).unwrap();
+
+		let buffer = self.terminal.backend().buffer();
+		buffer
+			.content()
+			.chunks(buffer.area.width as usize)
+			.map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>())
+			.collect()
+	}
+
+	pub fn assert_shows(&mut self, text: &str) {
+		let lines = self.render();
+		assert!(
+			lines.iter().any(|line| line.contains(text)),
+			"'{}' not rendered in:\n{}",
+			text,
+			lines.join("\n")
+		);
+	}
+
+	/// Index of the first rendered line containing text
+	pub fn line_containing(&mut self, text: &str) -> Option<usize> {
+		self.render().iter().position(|line| line.contains(text))
+	}
+}
+
+/// A synthetic node logfile starting minutes_ago with the given payments
+pub fn node_log(version: &str, minutes_ago: i64, payments: &[u64]) -> String {
+	node_log_with_peer_id(version, minutes_ago, payments, "12D3KooWTest")
+}
+
+pub fn node_log_with_peer_id(version: &str, minutes_ago: i64, payments: &[u64], peer_id: &str) -> String {
+	let start = Utc::now() - Duration::minutes(minutes_ago);
+	let time = |seconds: i64| (start + Duration::seconds(seconds)).format("%Y-%m-%dT%H:%M:%S%.6fZ");
+
+	let mut log = format!("[{} INFO ant_node] \nRunning antnode {}\n", time(0), version);
+	log += &format!("[{} INFO ant_node] Node (PID: 1234) with PeerId: {}\n", time(1), peer_id);
+	for (i, attos) in payments.iter().enumerate() {
+		let seconds = 10 * (i as i64 + 1);
+		log += &format!("[{} INFO ant_networking] Wrote record 0x01 to disk\n", time(seconds));
+		log += &format!("[{} INFO ant_node] Total payment of {} attos for record\n", time(seconds + 1), attos);
+	}
+	log
+}
+
+#[tokio::test]
+async fn it_drives_views_focus_and_sorting() {
+	let _lock = HARNESS_LOCK.lock().await;
+	let mut tui = TuiHarness::new(&[
+		node_log("v0.1.0", 30, &[100, 200]),
+		node_log("v0.1.1", 20, &[5000]),
+	])
+	.await;
+
+	// Summary lists both nodes with their versions
+	assert!(tui.app.dash_state.main_view == DashViewMain::DashSummary);
+	tui.assert_shows("Summary of Monitored Nodes");
+	tui.assert_shows(" Summary │ sort: Node ▲ │ timescale: ");
+	tui.assert_shows("v0.1.0");
+	tui.assert_shows("v0.1.1");
+	tui.assert_shows("12D3KooWTest");
+	tui.assert_shows("   Uptime       Avail ");
+
+	// Sorting by the Earnings column and reversing the order
+	tui.press(KeyCode::Right).await;
+	let lower = tui.line_containing("v0.1.0").unwrap();
+	let higher = tui.line_containing("v0.1.1").unwrap();
+	assert!(lower < higher);
+	tui.press(KeyCode::Char(' ')).await;
+	assert!(tui.line_containing("v0.1.1").unwrap() < tui.line_containing("v0.1.0").unwrap());
+
+	// Totals are pinned below the node rows
+	let totals = tui.line_containing("Totals: 2 nodes").unwrap();
+	assert!(tui.line_containing("v0.1.0").unwrap() < totals);
+
+	// Node view and cycling focus between nodes
+	tui.press(KeyCode::Enter).await;
+	assert!(tui.app.dash_state.main_view == DashViewMain::DashNode);
+	tui.assert_shows("Node Uptime");
+	tui.assert_shows(" Node │ sort: Earnings ▼");
+	let first_focus = tui.app.logfile_with_focus.clone();
+	tui.press(KeyCode::Right).await;
+	assert_ne!(first_focus, tui.app.logfile_with_focus);
+	tui.press(KeyCode::Left).await;
+	assert_eq!(first_focus, tui.app.logfile_with_focus);
+
+	// Help returns to the previous view
+	tui.type_keys("?").await;
+	tui.assert_shows("Keyboard Commands");
+	tui.press(KeyCode::Enter).await;
+	assert!(tui.app.dash_state.main_view == DashViewMain::DashNode);
+
+	// Earnings ledger lists every payment
+	tui.type_keys("e").await;
+	assert!(tui.app.dash_state.main_view == DashViewMain::DashEarnings);
+	tui.assert_shows("Earnings Ledger");
+	assert_eq!(tui.app.dash_state.earnings_window_rows.items.len(), 3);
+	tui.assert_shows("5000");
+
+	// Fleet comparison has a row per node
+	tui.type_keys("f").await;
+	tui.assert_shows("Fleet Daily Comparison");
+	assert_eq!(tui.app.dash_state.fleet_window_rows.items.len(), 2);
+
+	tui.type_keys("s").await;
+	assert!(tui.app.dash_state.main_view == DashViewMain::DashSummary);
+}
+
+#[tokio::test]
+async fn it_warns_of_duplicate_peer_ids() {
+	let _lock = HARNESS_LOCK.lock().await;
+	let mut tui = TuiHarness::new(&[
+		node_log_with_peer_id("v0.1.0", 30, &[100], "12D3KooWNodeA"),
+		node_log_with_peer_id("v0.1.0", 30, &[100], "12D3KooWNodeB"),
+	])
+	.await;
+	assert!(tui.line_containing("WARNING").is_none());
+
+	let mut tui = TuiHarness::new(&[
+		node_log_with_peer_id("v0.1.0", 30, &[100], "12D3KooWNodeA"),
+		node_log_with_peer_id("v0.1.0", 30, &[100], "12D3KooWNodeA"),
+		node_log_with_peer_id("v0.1.0", 30, &[100], "12D3KooWNodeB"),
+	])
+	.await;
+	tui.assert_shows("WARNING: logfiles share a PeerId");
+	let warning = tui.render()[tui.line_containing("PeerId 12D3KooWNodeA is in:").unwrap()].clone();
+	assert!(warning.contains("node-1/logs/antnode.log"));
+	assert!(warning.contains("node-2/logs/antnode.log"));
+	assert!(!warning.contains("node-3"));
+}
+
+#[tokio::test]
+async fn it_blanks_when_idle_and_wakes_on_a_key() {
+	let _lock = HARNESS_LOCK.lock().await;
+	let mut tui = TuiHarness::new(&[node_log("v0.1.0", 30, &[100])]).await;
+	tui.app.dash_state.idle_timeout = Some(Duration::minutes(5));
+	tui.app.dash_state.last_input_time = Utc::now() - Duration::minutes(6);
+	assert!(tui.line_containing("Summary of Monitored Nodes").is_none());
+
+	// The key which wakes the dashboard isn't acted on
+	tui.press(KeyCode::Char('e')).await;
+	assert!(tui.app.dash_state.main_view == DashViewMain::DashSummary);
+	tui.assert_shows("Summary of Monitored Nodes");
+
+	tui.app.dash_state.idle_dim = true;
+	tui.app.dash_state.last_input_time = Utc::now() - Duration::minutes(6);
+	tui.assert_shows("Summary of Monitored Nodes");
+}
+
+#[tokio::test]
+async fn it_replays_logfiles_through_time() {
+	let _lock = HARNESS_LOCK.lock().await;
+	let mut tui = TuiHarness::with_args(&[node_log("v0.1.0", 30, &[100, 200])], &["--replay"]).await;
+	let earned = |tui: &TuiHarness| tui.app.monitors.values().next().unwrap().metrics.attos_earned.total;
+
+	// Replay starts at the end of the logfiles
+	tui.assert_shows("REPLAY");
+	assert_eq!(earned(&tui), 300);
+
+	// Stepping back a second is before the second payment
+	tui.press(KeyCode::Left).await;
+	assert_eq!(earned(&tui), 100);
+	tui.press(KeyCode::Right).await;
+	assert_eq!(earned(&tui), 300);
+
+	// Playing from the end starts again at the beginning
+	tui.press(KeyCode::Char(' ')).await;
+	assert_eq!(earned(&tui), 0);
+	for _ in 0..30 {
+		tui.app.update_timelines_on_tick();
+	}
+	assert_eq!(earned(&tui), 300);
+	assert!(!tui.app.dash_state.replay.as_ref().unwrap().playing);
+}
+
+#[tokio::test]
+async fn it_pans_and_selects_the_timeline_window() {
+	let _lock = HARNESS_LOCK.lock().await;
+	let mut tui = TuiHarness::new(&[node_log("v0.1.0", 30, &[100])]).await;
+	tui.press(KeyCode::Char('n')).await;
+
+	tui.press(KeyCode::Char('[')).await;
+	tui.assert_shows("ending");
+	tui.press(KeyCode::Char(']')).await;
+	assert!(tui.app.dash_state.timeline_window_end.is_none());
+
+	tui.press(KeyCode::Char('w')).await;
+	tui.type_keys("00:00-01:00 yesterday").await;
+	tui.press(KeyCode::Enter).await;
+	assert_eq!(tui.app.dash_state.get_active_timescale_name(), Some("1 hour columns"));
+	tui.assert_shows("01:00 ('[' ']' to pan)");
+}
+
+#[tokio::test]
+async fn it_maps_the_fleet_by_age_and_records() {
+	let _lock = HARNESS_LOCK.lock().await;
+	let mut tui = TuiHarness::new(&[node_log("v0.1.0", 30, &[100]), node_log("v0.1.0", 60, &[])]).await;
+	tui.press(KeyCode::Char('f')).await;
+	tui.assert_shows("Fleet Map");
+	let row = tui.line_containing("< 1 day").unwrap();
+	assert!(tui.render()[row].contains("2"));
+}
+
+#[tokio::test]
+async fn it_removes_and_prunes_nodes() {
+	let _lock = HARNESS_LOCK.lock().await;
+	let mut tui = TuiHarness::with_args(
+		&[node_log("v0.1.0", 30, &[100]), node_log("v0.1.1", 30, &[200])],
+		&["--prune-after", "60"],
+	)
+	.await;
+
+	// Removal needs confirming
+	let removed = tui.app.logfile_with_focus.clone();
+	tui.press(KeyCode::Char('x')).await;
+	tui.press(KeyCode::Char('n')).await;
+	assert_eq!(tui.app.monitors.len(), 2);
+	tui.press(KeyCode::Char('x')).await;
+	tui.press(KeyCode::Char('y')).await;
+	assert_eq!(tui.app.monitors.len(), 1);
+	assert!(!tui.app.logfiles_manager.logfiles_added.contains(&removed));
+	assert_ne!(tui.app.logfile_with_focus, removed);
+
+	// A node is pruned once its logfile is gone and it has been inactive long enough
+	let remaining = tui.app.logfile_with_focus.clone();
+	fs::remove_file(&remaining).unwrap();
+	tui.app.prune_stale_nodes();
+	assert_eq!(tui.app.monitors.len(), 1);
+	let metadata = tui.app.monitors.get_mut(&remaining).unwrap().metrics.entry_metadata.as_mut().unwrap();
+	metadata.system_time = Utc::now() - Duration::minutes(2);
+	tui.app.prune_stale_nodes();
+	assert!(tui.app.monitors.is_empty());
+	assert!(tui.app.dash_state.main_view == DashViewMain::DashSummary);
+}
+
+#[tokio::test]
+async fn it_pages_through_many_nodes() {
+	let _lock = HARNESS_LOCK.lock().await;
+	let logs: Vec<String> = (0..60)
+		.map(|i| node_log_with_peer_id("v0.1.0", 30, &[100], &format!("12D3KooWNode{}", i)))
+		.collect();
+	let mut tui = TuiHarness::new(&logs).await;
+	tui.render();
+	let page_rows = tui.app.dash_state.summary_window_page_rows;
+	assert!(page_rows > 0 && page_rows < 60);
+	tui.assert_shows(&format!("{} of 60 nodes", page_rows));
+
+	tui.press(KeyCode::Home).await;
+	tui.render();
+	assert_eq!(tui.app.dash_state.summary_window_first_row, 0);
+
+	// The page only moves as far as needed to keep the selection in view
+	tui.press(KeyCode::PageDown).await;
+	tui.render();
+	assert_eq!(tui.app.dash_state.summary_window_rows.state.selected(), Some(page_rows));
+	assert_eq!(tui.app.dash_state.summary_window_first_row, 1);
+
+	tui.press(KeyCode::End).await;
+	tui.render();
+	assert_eq!(tui.app.dash_state.summary_window_first_row, 60 - page_rows);
+	tui.press(KeyCode::PageUp).await;
+	assert_eq!(tui.app.dash_state.summary_window_rows.state.selected(), Some(59 - page_rows));
+}
+
+#[tokio::test]
+async fn it_sorts_ties_by_kept_columns_and_saves_the_order() {
+	let _lock = HARNESS_LOCK.lock().await;
+	let mut tui = TuiHarness::new(&[
+		node_log_with_peer_id("v0.1.0", 30, &[300], "12D3KooWRich"),
+		node_log_with_peer_id("v0.1.0", 30, &[50], "12D3KooWPoor"),
+		node_log_with_peer_id("v0.1.1", 30, &[100], "12D3KooWNewer"),
+	])
+	.await;
+
+	// Keep Earnings (ascending) then sort by Version
+	tui.press(KeyCode::Right).await;
+	tui.press(KeyCode::Char('k')).await;
+	while SUMMARY_COLUMNS[tui.app.dash_state.summary_window_heading_selected].1 != "Version" {
+		tui.press(KeyCode::Right).await;
+	}
+	let poor = tui.line_containing("12D3KooWPoor").unwrap();
+	let rich = tui.line_containing("12D3KooWRich").unwrap();
+	let newer = tui.line_containing("12D3KooWNewer").unwrap();
+	assert!(poor < rich && rich < newer);
+
+	let saved = Preferences::load().summary_sort;
+	let columns: Vec<&str> = saved.iter().map(|sort_key| sort_key.column.as_str()).collect();
+	assert_eq!(columns, vec!["Version", "Earnings"]);
+
+	// Pressing 'k' on a kept column stops using it
+	while tui.app.dash_state.summary_window_heading_selected != 1 {
+		tui.press(KeyCode::Left).await;
+	}
+	tui.press(KeyCode::Char('k')).await;
+	assert!(tui.app.dash_state.summary_sort_then_by.is_empty());
+}
+
+#[tokio::test]
+async fn it_monitors_generic_logfiles() {
+	let _lock = HARNESS_LOCK.lock().await;
+	let auth_log = String::from("Oct 16 10:00:01 host sshd[99]: error: PAM: Authentication failure\nOct 16 10:00:02 host sshd[99]: Accepted publickey\n");
+	let mut tui = TuiHarness::with_args(
+		&[node_log("v0.1.0", 30, &[100]), auth_log],
+		&["--generic", "*/node-2/logs/antnode.log"],
+	)
+	.await;
+
+	tui.assert_shows("GENERIC 2 lines");
+	assert_eq!(tui.app.monitors.values().filter(|monitor| monitor.is_node()).count(), 1);
+
+	let generic_row = tui.app.dash_state.logfile_names_sorted.iter().position(|logfile| logfile.contains("node-2"));
+	tui.app.dash_state.summary_window_rows.state.select(generic_row);
+	tui.press(KeyCode::Enter).await;
+	assert!(tui.app.dash_state.main_view == DashViewMain::DashNode);
+	tui.assert_shows("Generic logfile (not a node)");
+	tui.assert_shows("sshd[99]: Accepted publickey");
+}
+
+#[tokio::test]
+async fn it_lists_the_top_errors_of_a_node() {
+	let _lock = HARNESS_LOCK.lock().await;
+	let time = (Utc::now() - Duration::minutes(5)).format("%Y-%m-%dT%H:%M:%S%.6fZ");
+	let mut log = node_log("v0.1.0", 30, &[100]);
+	for peer_id in ["12D3KooWAbc", "12D3KooWDef", "12D3KooWGhi"] {
+		log += &format!("[{} ERROR ant_networking] Outgoing connection error to {}: timeout 30s\n", time, peer_id);
+	}
+	log += &format!("[{} WARN ant_node] Record store is 95% full\n", time);
+	let mut tui = TuiHarness::new(&[log]).await;
+
+	tui.press(KeyCode::Enter).await;
+	assert!(tui.app.dash_state.main_view == DashViewMain::DashNode);
+	tui.assert_shows("Errors by Category");
+	tui.press(KeyCode::Char('v')).await;
+	tui.assert_shows("Top Errors (2 distinct");
+	tui.assert_shows("      3  ");
+	tui.assert_shows("ERROR  Outgoing connection error to <peer>: timeout #s");
+	tui.assert_shows("WARN   Record store is #% full");
+	tui.press(KeyCode::Char('v')).await;
+	tui.assert_shows("Outgoing connection error to 12D3KooWAbc");
+}
+
+#[tokio::test]
+async fn it_splits_the_summary_and_selected_node() {
+	let _lock = HARNESS_LOCK.lock().await;
+	let mut tui = TuiHarness::new(&[
+		node_log_with_peer_id("v0.1.0", 30, &[100], "12D3KooWFirst"),
+		node_log_with_peer_id("v0.1.1", 20, &[200], "12D3KooWSecond"),
+	])
+	.await;
+
+	tui.press(KeyCode::Char('b')).await;
+	assert!(tui.app.dash_state.is_split_view());
+	tui.assert_shows(" Summary + Node │");
+	tui.assert_shows("Summary of Monitored Nodes");
+	tui.assert_shows("Node Uptime");
+
+	// The node below follows the selected row
+	tui.press(KeyCode::Down).await;
+	tui.render();
+	let selected = tui.app.dash_state.summary_window_rows.state.selected().unwrap();
+	assert_eq!(tui.app.logfile_with_focus, tui.app.dash_state.logfile_names_sorted[selected]);
+
+	tui.press(KeyCode::Char('b')).await;
+	assert!(!tui.app.dash_state.is_split_view());
+	assert!(tui.line_containing("Node Uptime").is_none());
+}
+
+#[tokio::test]
+async fn it_resizes_the_node_layout_and_saves_it() {
+	let _lock = HARNESS_LOCK.lock().await;
+	let mut tui = TuiHarness::new(&[node_log("v0.1.0", 30, &[100])]).await;
+
+	tui.press(KeyCode::Enter).await;
+	let height = tui.app.dash_state.node_timelines_height;
+	tui.press_with(KeyCode::Down, KeyModifiers::CONTROL).await;
+	tui.press_with(KeyCode::Down, KeyModifiers::CONTROL).await;
+	assert_eq!(tui.app.dash_state.node_timelines_height, height + 4);
+	tui.press(KeyCode::Char('t')).await;
+	tui.press(KeyCode::Char('l')).await;
+
+	let layout = Preferences::load().node_layout.unwrap();
+	assert_eq!(layout.timelines_height, height + 4);
+	assert!(!layout.logfile_visible);
+	assert_eq!(layout.top_timeline, 1);
+
+	// Restored by a new App
+	let app = App::new().await.unwrap();
+	assert_eq!(app.dash_state.node_timelines_height, height + 4);
+	assert!(!app.dash_state.node_logfile_visible);
+	assert_eq!(app.dash_state.top_timeline, 1);
+}
+
+#[tokio::test]
+async fn it_draws_braille_timelines_and_saves_the_choice() {
+	let _lock = HARNESS_LOCK.lock().await;
+	let mut tui = TuiHarness::new(&[node_log("v0.1.0", 30, &[100, 200])]).await;
+	let is_braille = |c: char| ('\u{2801}'..='\u{28FF}').contains(&c);
+
+	tui.press(KeyCode::Enter).await;
+	assert!(!tui.render().iter().any(|line| line.chars().any(is_braille)));
+
+	tui.press(KeyCode::Char('.')).await;
+	assert!(tui.app.dash_state.sparkline_braille);
+	assert!(tui.render().iter().any(|line| line.chars().any(is_braille)));
+	assert!(Preferences::load().sparkline_braille);
+
+	tui.press(KeyCode::Char('.')).await;
+	assert!(!Preferences::load().sparkline_braille);
+}
+
+#[tokio::test]
+async fn it_moves_a_cursor_through_timeline_buckets() {
+	let _lock = HARNESS_LOCK.lock().await;
+	let mut tui = TuiHarness::new(&[node_log("v0.1.0", 30, &[100, 200])]).await;
+
+	tui.press(KeyCode::Enter).await;
+	tui.press(KeyCode::Char('y')).await;
+	assert_eq!(tui.app.dash_state.timeline_cursor, Some(0));
+	tui.assert_shows("┤");
+	tui.assert_shows(", cursor ");
+
+	tui.press(KeyCode::Left).await;
+	tui.press(KeyCode::Left).await;
+	tui.press(KeyCode::Right).await;
+	assert_eq!(tui.app.dash_state.timeline_cursor, Some(1));
+	tui.assert_shows(", cursor 0 ");
+
+	tui.press(KeyCode::Char('y')).await;
+	assert_eq!(tui.app.dash_state.timeline_cursor, None);
+	assert_eq!(tui.line_containing(", cursor "), None);
+}
+
+#[tokio::test]
+async fn it_breaks_down_a_timeline_by_node() {
+	let _lock = HARNESS_LOCK.lock().await;
+	let mut tui = TuiHarness::new(&[node_log("v0.1.0", 30, &[100]), node_log("v0.1.0", 20, &[200])]).await;
+
+	tui.assert_shows("GETS by node (2 of 2, 'a' next)");
+	tui.press(KeyCode::Char('a')).await;
+	assert_eq!(tui.app.dash_state.breakdown_timeline, "connections");
+	tui.assert_shows("Connections by node (2 of 2");
+}
+
+#[tokio::test]
+async fn it_shortens_logfile_names_to_what_differs() {
+	let _lock = HARNESS_LOCK.lock().await;
+	let mut tui = TuiHarness::new(&[node_log("v0.1.0", 30, &[100]), node_log("v0.1.0", 20, &[200])]).await;
+	let logs_dir = tui._logs_dir.path().to_string_lossy().to_string();
+
+	tui.press(KeyCode::Enter).await;
+	tui.assert_shows("Node Log (.../node-1/logs/antnode.log)");
+	// The full path is at the end of the status bar
+	let parts = status_bar_parts(&mut tui.app.dash_state, &tui.app.monitors, &tui.app.logfile_with_focus);
+	assert_eq!(parts.last().unwrap(), &format!("{}/node-1/logs/antnode.log", logs_dir));
+}
+
+#[tokio::test]
+async fn it_jumps_to_a_node_by_number_or_name() {
+	let _lock = HARNESS_LOCK.lock().await;
+	let mut tui = TuiHarness::new(&[node_log("v0.1.0", 30, &[100]), node_log("v0.1.0", 20, &[200])]).await;
+
+	tui.press(KeyCode::Char('f')).await;
+	tui.press(KeyCode::Char('j')).await;
+	tui.type_keys("2").await;
+	tui.press(KeyCode::Enter).await;
+	assert!(tui.app.dash_state.main_view == DashViewMain::DashNode);
+	assert!(tui.app.logfile_with_focus.ends_with("node-2/logs/antnode.log"));
+
+	tui.press(KeyCode::Char('j')).await;
+	tui.type_keys("e-1lg").await;
+	tui.press(KeyCode::Enter).await;
+	assert!(tui.app.logfile_with_focus.ends_with("node-1/logs/antnode.log"));
+
+	tui.press(KeyCode::Char('j')).await;
+	tui.type_keys("zzz").await;
+	tui.press(KeyCode::Enter).await;
+	tui.assert_shows("No node matches 'zzz'");
+	assert!(tui.app.logfile_with_focus.ends_with("node-1/logs/antnode.log"));
+}
+
+#[tokio::test]
+async fn it_uses_vim_keys_and_rebinds_keys() {
+	let _lock = HARNESS_LOCK.lock().await;
+	let logs = [node_log("v0.1.0", 30, &[100]), node_log("v0.1.0", 20, &[200]), node_log("v0.1.0", 10, &[])];
+	let mut tui = TuiHarness::with_args(&logs, &["--keymap", "vim", "--key", "ctrl-n=node-view"]).await;
+	let selected = |tui: &TuiHarness| tui.app.dash_state.summary_window_rows.state.selected();
+
+	tui.press(KeyCode::Char('G')).await;
+	assert_eq!(selected(&tui), Some(2));
+	tui.press(KeyCode::Char('k')).await;
+	assert_eq!(selected(&tui), Some(1));
+	tui.press(KeyCode::Char('g')).await;
+	assert_eq!(selected(&tui), Some(0));
+	tui.press(KeyCode::Char('j')).await;
+	assert_eq!(selected(&tui), Some(1));
+
+	tui.press_with(KeyCode::Char('n'), KeyModifiers::CONTROL).await;
+	assert!(tui.app.dash_state.main_view == DashViewMain::DashNode);
+	tui.press(KeyCode::Char('h')).await;
+	tui.assert_shows("'n' or ctrl-n");
+	tui.assert_shows("down or 'j'");
+}
+
+#[tokio::test]
+async fn it_generates_help_from_the_keymap_and_scrolls_it() {
+	let _lock = HARNESS_LOCK.lock().await;
+	let mut tui = TuiHarness::with_args(&[node_log("v0.1.0", 30, &[100])], &["--key", "Q=none", "--key", "ctrl-q=quit"]).await;
+
+	tui.press(KeyCode::Char('?')).await;
+	let first = tui.line_containing("For vdash command usage").unwrap();
+	assert!(tui.line_containing("'q' or ctrl-q").is_some());
+	assert!(tui.line_containing("'x'            :   Export the Earnings Ledger").is_some());
+
+	tui.press(KeyCode::Down).await;
+	tui.press(KeyCode::Down).await;
+	assert_eq!(tui.line_containing("vdash --help").unwrap(), first - 1);
+	tui.press(KeyCode::Home).await;
+	assert_eq!(tui.line_containing("vdash --help").unwrap(), first + 1);
+	tui.press(KeyCode::End).await;
+	tui.assert_shows("To exit Help press 'enter'");
+	assert!(tui.line_containing("For vdash command usage").is_none());
+}
+
+#[tokio::test]
+async fn it_runs_commands_from_the_palette() {
+	let _lock = HARNESS_LOCK.lock().await;
+	let mut tui = TuiHarness::new(&[node_log("v0.1.0", 30, &[100]), node_log("v0.1.0", 20, &[200, 300])]).await;
+	let csv_path = tui._logs_dir.path().join("nodes.csv");
+
+	tui.press(KeyCode::Char(':')).await;
+	tui.type_keys("sort earnings desc").await;
+	tui.press(KeyCode::Enter).await;
+	assert_eq!(SUMMARY_COLUMNS[tui.app.dash_state.summary_window_heading_selected].1, "Earnings");
+	assert!(!tui.app.dash_state.logfile_names_sorted_ascending);
+	assert!(tui.app.dash_state.logfile_names_sorted[0].ends_with("node-2/logs/antnode.log"));
+
+	tui.press(KeyCode::Char(':')).await;
+	tui.type_keys(&format!("export csv {}", csv_path.display())).await;
+	tui.press(KeyCode::Enter).await;
+	let csv = fs::read_to_string(&csv_path).unwrap();
+	let lines: Vec<&str> = csv.lines().collect();
+	assert_eq!(lines.len(), 3);
+	assert!(lines[0].starts_with("Node,Earnings,"));
+	assert!(lines[1].starts_with("2,500,"));
+
+	tui.press(KeyCode::Char(':')).await;
+	tui.type_keys("fleet-view").await;
+	tui.press(KeyCode::Enter).await;
+	assert!(tui.app.dash_state.main_view == DashViewMain::DashFleet);
+
+	tui.press(KeyCode::Char(':')).await;
+	tui.type_keys("bogus").await;
+	tui.press(KeyCode::Enter).await;
+	tui.assert_shows("Command failed, unknown command 'bogus'");
+}
+
+#[tokio::test]
+async fn it_filters_summary_rows_by_column_values() {
+	let _lock = HARNESS_LOCK.lock().await;
+	let mut tui = TuiHarness::new(&[node_log("v0.1.0", 30, &[100]), node_log("v0.1.0", 20, &[])]).await;
+
+	tui.press(KeyCode::Char('/')).await;
+	tui.type_keys("earnings=0").await;
+	tui.press(KeyCode::Enter).await;
+	assert_eq!(tui.app.dash_state.logfile_names_sorted.len(), 1);
+	assert!(tui.app.dash_state.logfile_names_sorted[0].ends_with("node-2/logs/antnode.log"));
+	tui.assert_shows("[filter: earnings=0, esc clears]");
+
+	tui.press(KeyCode::Esc).await;
+	assert!(tui.app.dash_state.node_filter.is_none());
+	assert_eq!(tui.app.dash_state.logfile_names_sorted.len(), 2);
+}
+
+#[tokio::test]
+async fn it_highlights_summary_cells_which_change() {
+	let _lock = HARNESS_LOCK.lock().await;
+	let mut tui = TuiHarness::new(&[node_log("v0.1.0", 30, &[100])]).await;
+	let earnings = SUMMARY_COLUMNS.iter().position(|(_, heading, _)| heading == "Earnings").unwrap();
+	let errors = SUMMARY_COLUMNS.iter().position(|(_, heading, _)| heading == "Errors").unwrap();
+	let logfile = tui.app.dash_state.logfile_names_sorted[0].clone();
+	let highlight = tui.app.dash_state.change_highlight;
+	tui.app.update_summary_window();
+
+	tui.app.monitors.get_mut(&logfile).unwrap().metrics.attos_earned.total += 5_000_000_000_000_000;
+	tui.app.update_summary_window();
+	let changes = &tui.app.monitors[&logfile].summary_changes;
+	assert!(changes.is_recent(earnings, Utc::now(), highlight));
+	assert!(!changes.is_recent(errors, Utc::now(), highlight));
+	assert!(!changes.is_recent(earnings, Utc::now() + highlight, highlight));
+	tui.render();
+}
+
+#[tokio::test]
+async fn it_shows_a_trend_column_for_the_chosen_timeline() {
+	let _lock = HARNESS_LOCK.lock().await;
+	let mut tui = TuiHarness::new(&[node_log("v0.1.0", 30, &[100])]).await;
+	let logfile = tui.app.dash_state.logfile_names_sorted[0].clone();
+
+	assert_eq!(tui.app.monitors[&logfile].summary_trend.len(), 12);
+	assert!(tui.app.dash_state.summary_window_rows.items[0].contains("▁▁▁▁▁▁▁▁▁▁▁▁"));
+	let headings = tui.line_containing("StoreCost").unwrap();
+	assert!(tui.render()[headings].contains("GETS         GETS"));
+
+	tui.press(KeyCode::Char('a')).await;
+	assert!(tui.render()[headings].contains("GETS  Connections"));
+}
+
+#[tokio::test]
+async fn it_reads_records_stored_or_space_used() {
+	let _lock = HARNESS_LOCK.lock().await;
+	let time = Utc::now().format("%Y-%m-%dT%H:%M:%S%.6fZ");
+	let records = node_log("v0.1.0", 10, &[])
+		+ &format!(
+			"[{} INFO ant_node] Cost is now 42 for quoting_metrics QuotingMetrics {{ data_type: 0, close_records_stored: 321, records_per_type: [(0, 300), (3, 21)], max_records: 4096, received_payment_count: 0 }}\n",
+			time
+		);
+	let space = node_log("v0.1.0", 10, &[]) + &format!("[{} INFO ant_node] Cost is now 7 for used_space: 5000000, max_capacity: 20000000\n", time);
+	let mut tui = TuiHarness::new(&[records, space]).await;
+
+	let metrics = |tui: &TuiHarness, n: usize| tui.app.monitors[&tui.app.dash_state.logfile_names_sorted[n]].metrics.clone();
+	assert_eq!((metrics(&tui, 0).records_stored, metrics(&tui, 0).records_max), (321, 4096));
+	assert_eq!((metrics(&tui, 1).space_used, metrics(&tui, 1).space_max), (5_000_000, 20_000_000));
+
+	tui.press(KeyCode::Enter).await;
+	tui.assert_shows("321/4096");
+	tui.assert_shows("  Chunk    :        300");
+	tui.assert_shows("  Scratchpad:         21");
+	tui.press(KeyCode::Right).await;
+	tui.assert_shows("Space Used");
+	tui.assert_shows("5/20 MB");
+}
+
+#[tokio::test]
+async fn it_sums_each_record_store_in_turn() {
+	let _lock = HARNESS_LOCK.lock().await;
+	let mut tui = TuiHarness::with_args(&[node_log("v0.1.0", 10, &[]), node_log("v0.1.0", 10, &[])], &["--store-poll", "60"]).await;
+	let logfiles = tui.app.dash_state.logfile_names_sorted.clone();
+	for logfile in logfiles.iter() {
+		let store_dir = Path::new(logfile).parent().unwrap().parent().unwrap().join("record_store");
+		fs::create_dir(&store_dir).unwrap();
+		fs::write(store_dir.join("record"), vec![0u8; 3_000_000]).unwrap();
+	}
+
+	tui.app.poll_store_sizes();
+	let scanned = |tui: &TuiHarness| logfiles.iter().filter(|logfile| tui.app.monitors[*logfile].metrics.store_size.bytes.is_some()).count();
+	assert_eq!(scanned(&tui), 1);
+	tui.app.poll_store_sizes();
+	assert_eq!(scanned(&tui), 1);
+	tui.app.next_store_poll = None;
+	tui.app.poll_store_sizes();
+	assert_eq!(scanned(&tui), 2);
+
+	tui.press(KeyCode::Enter).await;
+	tui.assert_shows("Store Size :       3 MB");
+}
+
+#[tokio::test]
+async fn it_shows_earnings_per_gb_and_per_1k_gets() {
+	let _lock = HARNESS_LOCK.lock().await;
+	let mut tui = TuiHarness::new(&[node_log("v0.1.0", 10, &[]), node_log("v0.1.0", 10, &[])]).await;
+	let logfile = tui.app.dash_state.logfile_names_sorted[0].clone();
+	let monitor = tui.app.monitors.get_mut(&logfile).unwrap();
+	monitor.metrics.attos_earned.total = 2_000_000_000_000_000_000;
+	monitor.metrics.activity_gets.total = 4_000;
+	monitor.metrics.store_size.bytes = Some(500_000_000);
+	tui.app.update_summary_window();
+
+	let column = SUMMARY_COLUMNS.iter().position(|(_, heading, _)| heading == "Earn/GB").unwrap();
+	let cell = |tui: &TuiHarness, n: usize| {
+		let logfile = &tui.app.dash_state.logfile_names_sorted[n];
+		tui.app.monitors[logfile].summary_changes.cells[column].trim().to_string()
+	};
+	assert_eq!(cell(&tui, 0), "4.000000000");
+	assert_eq!(cell(&tui, 1), "-");
+	tui.assert_shows("Earn/GB");
+
+	tui.press(KeyCode::Enter).await;
+	tui.assert_shows("Earn/GB     :  4.000000000 ANT");
+	tui.assert_shows("Earn/1k GETS:  0.500000000 ANT");
+}
+
+#[tokio::test]
+async fn it_answers_requests_on_the_control_socket() {
+	let _lock = HARNESS_LOCK.lock().await;
+	let mut tui = TuiHarness::new(&[node_log("v0.1.0", 30, &[100]), node_log("v0.1.0", 20, &[200, 300])]).await;
+
+	let response = handle_control_request(&mut tui.app, &json!({ "jsonrpc": "2.0", "id": 1, "method": "list_nodes" }), false).await;
+	assert_eq!(response["id"], 1);
+	assert_eq!(response["result"].as_array().unwrap().len(), 2);
+	assert_eq!(response["result"][1]["node"], 2);
+	assert_eq!(response["result"][1]["peer_id"], "12D3KooWTest");
+
+	let request = json!({ "id": 2, "method": "get_metrics", "params": { "node": 2 } });
+	let response = handle_control_request(&mut tui.app, &request, false).await;
+	assert_eq!(response["result"]["metrics"]["Earnings"], "500");
+
+	let request = json!({ "id": 3, "method": "command", "params": { "command": "sort earnings desc" } });
+	handle_control_request(&mut tui.app, &request, false).await;
+	let response = handle_control_request(&mut tui.app, &json!({ "id": 4, "method": "get_summary" }), false).await;
+	assert_eq!(response["result"]["view"], "Summary");
+	assert_eq!(response["result"]["nodes"][0]["node"], 2);
+	assert_eq!(response["result"]["totals"]["earnings"], 600);
+
+	let request = json!({ "id": 5, "method": "command", "params": { "command": "quit" } });
+	assert_eq!(handle_control_request(&mut tui.app, &request, false).await["error"]["code"], -32602);
+	let response = handle_control_request(&mut tui.app, &json!({ "id": 6, "method": "restart_everything" }), false).await;
+	assert_eq!(response["error"]["code"], -32601);
+}
+
+#[tokio::test]
+async fn it_shows_counts_for_the_session_and_reports_them() {
+	let _lock = HARNESS_LOCK.lock().await;
+	let report_dir = TempDir::new().unwrap();
+	let report_path = report_dir.path().join("session.txt").to_string_lossy().to_string();
+	let mut tui = TuiHarness::with_args(&[node_log("v0.1.0", 30, &[100])], &["--session-report", &report_path]).await;
+	let logfile = tui.app.dash_state.logfile_names_sorted[0].clone();
+	let gets = SUMMARY_COLUMNS.iter().position(|(_, heading, _)| heading == "GETS").unwrap();
+	tui.app.monitors.get_mut(&logfile).unwrap().metrics.activity_gets.total += 5;
+	tui.app.note_session_start();
+	tui.app.monitors.get_mut(&logfile).unwrap().metrics.activity_gets.total += 7;
+
+	tui.press(KeyCode::Char('d')).await;
+	assert!(tui.app.dash_state.session_delta);
+	assert_eq!(tui.app.monitors[&logfile].summary_changes.cells[gets].trim(), "7");
+	tui.assert_shows("+GETS");
+	tui.assert_shows("+counts since");
+
+	tui.press(KeyCode::Char('d')).await;
+	assert_eq!(tui.app.monitors[&logfile].summary_changes.cells[gets].trim(), "12");
+
+	assert!(tui.app.write_session_report().unwrap().is_ok());
+	let report = fs::read_to_string(&report_path).unwrap();
+	assert!(report.contains("Earned (ANT)"));
+	assert!(report.lines().any(|line| line.starts_with("    1 ") && line.contains(&format!("{:>11} ", 7))));
+}
+
+#[tokio::test]
+async fn it_totals_earnings_by_day_and_week() {
+	let _lock = HARNESS_LOCK.lock().await;
+	let mut tui = TuiHarness::new(&[node_log("v0.1.0", 30, &[100]), node_log("v0.1.0", 20, &[200, 300])]).await;
+
+	tui.type_keys("e").await;
+	assert_eq!(tui.app.dash_state.earnings_window_rows.items.len(), 3);
+	tui.type_keys("d").await;
+	tui.assert_shows("Earnings Ledger by Day");
+	tui.assert_shows("Day (UTC)");
+	let rows = tui.app.dash_state.earnings_window_rows.items.clone();
+	let all_rows: Vec<&String> = rows.iter().filter(|row| row.contains(" All ")).collect();
+	let payments: u64 = all_rows.iter().map(|row| row.split_whitespace().nth(2).unwrap().parse::<u64>().unwrap()).sum();
+	assert_eq!(payments, 3);
+	assert!(rows.len() >= 3);
+
+	tui.type_keys("d").await;
+	tui.assert_shows("Earnings Ledger by Week");
+	tui.type_keys("d").await;
+	tui.assert_shows("Time (UTC)");
+	assert_eq!(tui.app.dash_state.earnings_window_rows.items.len(), 3);
+}
+
+#[tokio::test]
+async fn it_groups_or_abbreviates_large_counts() {
+	let _lock = HARNESS_LOCK.lock().await;
+	let mut tui = TuiHarness::with_args(&[node_log("v0.1.0", 30, &[100])], &["--number-format", "grouped"]).await;
+	let logfile = tui.app.dash_state.logfile_names_sorted[0].clone();
+	tui.app.monitors.get_mut(&logfile).unwrap().metrics.activity_gets.total = 1_234_567;
+	tui.assert_shows("1,234,567");
+	tui.press(KeyCode::Enter).await;
+	tui.assert_shows("1,234,567");
+
+	let mut tui = TuiHarness::with_args(&[node_log("v0.1.0", 30, &[100])], &["--number-format", "si"]).await;
+	let logfile = tui.app.dash_state.logfile_names_sorted[0].clone();
+	tui.app.monitors.get_mut(&logfile).unwrap().metrics.activity_gets.total = 1_234_567;
+	tui.assert_shows("1.23M");
+}
+
+#[tokio::test]
+async fn it_shows_nanos_for_nodes_which_log_them() {
+	let _lock = HARNESS_LOCK.lock().await;
+	let time = (Utc::now() - Duration::minutes(10)).format("%Y-%m-%dT%H:%M:%S%.6fZ");
+	let mut log = format!("[{} INFO safenode] \nRunning safenode v0.98.32\n", time);
+	log += &format!("[{} INFO sn_node] Total payment of NanoTokens(25) nanos for record 0x01\n", time);
+	let mut tui = TuiHarness::new(&[log]).await;
+	let logfile = tui.app.dash_state.logfile_names_sorted[0].clone();
+	assert_eq!(tui.app.monitors[&logfile].metrics.token_unit, TokenUnit::Nanos);
+	assert_eq!(tui.app.monitors[&logfile].metrics.attos_earned.total, 25_000_000_000);
+
+	tui.render();
+	assert_eq!(tui.app.dash_state.token_unit, TokenUnit::Nanos);
+	tui.assert_shows("SNT");
+	assert_eq!(status_bar_parts(&mut tui.app.dash_state, &tui.app.monitors, &tui.app.logfile_with_focus)[3], "nanos");
+}
+
+#[tokio::test]
+async fn it_weights_or_decays_the_average_storage_cost() {
+	let _lock = HARNESS_LOCK.lock().await;
+	let mut tui = TuiHarness::new(&[node_log("v0.1.0", 30, &[]), node_log("v0.1.0", 20, &[])]).await;
+	for (logfile, (storage_cost, records)) in tui.app.dash_state.logfile_names_sorted.clone().iter().zip([(100, 1), (400, 3)]) {
+		let metrics = &mut tui.app.monitors.get_mut(logfile).unwrap().metrics;
+		metrics.storage_cost.add_sample(storage_cost);
+		metrics.storage_cost_recent.add_sample(&Utc::now(), storage_cost as f64);
+		metrics.records_stored = records;
+	}
+	tui.assert_shows("min          mean           max");
+
+	tui.press(KeyCode::Char('=')).await;
+	tui.assert_shows("min      weighted           max");
+	assert!(tui.render().iter().any(|line| line.contains("Storage Cost") && line.contains("325")));
+
+	tui.press(KeyCode::Char('=')).await;
+	assert_eq!(tui.app.dash_state.average_mode, AverageMode::Recent);
+	tui.assert_shows("min        recent           max");
+}
+
+#[tokio::test]
+async fn it_cycles_storage_cost_through_percentiles() {
+	let _lock = HARNESS_LOCK.lock().await;
+	let time = Utc::now().format("%Y-%m-%dT%H:%M:%S%.6fZ");
+	let mut log = node_log("v0.1.0", 10, &[]);
+	for cost in (10..=100).step_by(10) {
+		log += &format!("[{} INFO ant_node] Cost is now {} for used_space: 5000000, max_capacity: 20000000\n", time, cost);
+	}
+	let mut tui = TuiHarness::new(&[log]).await;
+	tui.press(KeyCode::Enter).await;
+	tui.assert_shows("(10-100)attos/MB ");
+
+	tui.type_keys("mmm").await;
+	tui.assert_shows(" p95 ");
+	tui.assert_shows("Cost p95    : 1");
+	tui.type_keys("mm").await;
+	tui.assert_shows(" Min ");
+}
+
+#[tokio::test]
+async fn it_keeps_no_log_text_when_disabled() {
+	let _lock = HARNESS_LOCK.lock().await;
+	let mut tui = TuiHarness::with_args(&[node_log("v0.1.0", 30, &[100, 200])], &["--no-log-text"]).await;
+	let monitor = tui.app.monitors.get(&tui.app.logfile_with_focus).unwrap();
+	assert!(monitor.content.items.is_empty());
+	assert_eq!(monitor.metrics.attos_earned.total, 300);
+
+	tui.press(KeyCode::Enter).await;
+	tui.assert_shows("disabled (--no-log-text)");
+	assert!(tui.app.monitors.get(&tui.app.logfile_with_focus).unwrap().content.items.is_empty());
+}
+
+#[tokio::test]
+async fn it_shows_totals_and_active_nodes_in_mini_lines() {
+	let _lock = HARNESS_LOCK.lock().await;
+	let logs = [node_log("v0.1.0", 1, &[100, 200]), node_log("v0.1.0", 1, &[50])];
+	let mut tui = TuiHarness::with_args(&logs, &["--mini", "--mini-lines", "3"]).await;
+	for monitor in tui.app.monitors.values_mut() {
+		monitor.metrics.node_inactive = monitor.index == 1;
+	}
+	// Today is that of the latest payment, so the payments seconds before it are on the same day
+	let latest_payment = tui
+		.app
+		.monitors
+		.values()
+		.flat_map(|monitor| monitor.metrics.earnings_ledger.entries.iter())
+		.map(|entry| entry.time)
+		.max()
+		.unwrap();
+
+	let lines = mini_lines(&tui.app, 3, &latest_payment);
+	assert_eq!(lines.len(), 3);
+	assert_eq!(lines[0], "vdash 1/2 active │ today 350 attos │ errors 0");
+	assert!(lines[1].starts_with("#1 "), "{}", lines[1]);
+	assert_eq!(lines[2], "");
+}
+
+#[tokio::test]
+async fn it_cuts_a_long_status_to_fit_and_shows_it_in_the_status_bar() {
+	let _lock = HARNESS_LOCK.lock().await;
+	let mut tui = TuiHarness::new(&[node_log("v0.1.0", 30, &[100])]).await;
+	let bad_behaviour = "ReplicationFailure ".repeat(20);
+	for monitor in tui.app.monitors.values_mut() {
+		monitor.metrics.node_status = NodeStatus::Shunned;
+		monitor.metrics.shun_notifications = 1;
+		monitor.metrics.node_bad_behaviour = bad_behaviour.clone();
+	}
+	tui.app.update_summary_window();
+
+	let lines = tui.render();
+	let row = lines.iter().find(|line| line.contains("Shunned x1")).unwrap();
+	assert!(row.trim_end_matches(['│', ' ']).ends_with('…'), "{}", row);
+
+	// The selected node's status is shown in full instead of the key hints
+	tui.press(KeyCode::Down).await;
+	let parts = status_bar_parts(&mut tui.app.dash_state, &tui.app.monitors, &tui.app.logfile_with_focus);
+	let status = format!("Node 1: Shunned x1 ({})", bad_behaviour);
+	assert!(parts.iter().any(|part| part.starts_with(&status)), "{:?}", parts);
+}
+
+#[tokio::test]
+async fn it_switches_the_node_view_to_diagnostics() {
+	let _lock = HARNESS_LOCK.lock().await;
+	let mut tui = TuiHarness::new(&[node_log("v0.1.0", 30, &[100])]).await;
+	tui.press(KeyCode::Enter).await;
+	tui.assert_shows("Node Uptime");
+
+	tui.press(KeyCode::Char('!')).await;
+	tui.assert_shows("Node  1 Diagnostics ('!' for status)");
+	tui.assert_shows("12D3KooWTest");
+	tui.assert_shows("1234");
+	tui.assert_shows("node-1/logs/antnode.log");
+	tui.assert_shows("No timestamp");
+	tui.assert_shows("Parser Coverage");
+	tui.assert_shows("% matched");
+	let row = tui.line_containing("RecordWritten").unwrap();
+	assert!(tui.render()[row].contains("RecordWritten                  1"), "{}", tui.render()[row]);
+	assert!(tui.line_containing("Node Uptime").is_none());
+
+	tui.press(KeyCode::Char('!')).await;
+	tui.assert_shows("Node Uptime");
+}
+
+#[tokio::test]
+async fn it_shows_the_parser_output_of_the_focused_node_in_the_debug_window() {
+	let _lock = HARNESS_LOCK.lock().await;
+	let mut tui =
+		TuiHarness::with_args(&[node_log("v0.1.0", 30, &[100]), node_log("v0.1.0", 20, &[200])], &["--debug-window"]).await;
+	let debug_window_shows = |tui: &TuiHarness, text: &str| tui.app.dash_state.debug_window_list.items.iter().any(|line| line == text);
+
+	// Every logfile is monitored, each keeping its own parser output
+	assert_eq!(tui.app.monitors.values().filter(|monitor| monitor.debug_lines.is_some()).count(), 2);
+
+	tui.app.change_focus_to(1);
+	assert!(debug_window_shows(&tui, "Payment received: 200"));
+	assert!(!debug_window_shows(&tui, "Payment received: 100"));
+	tui.assert_shows("Node 2 parser output");
+
+	tui.app.change_focus_to(0);
+	assert!(debug_window_shows(&tui, "Payment received: 100"));
+	assert!(!debug_window_shows(&tui, "Payment received: 200"));
+}
+
+#[tokio::test]
+async fn it_takes_no_key_as_confirmation_once_the_prompt_times_out() {
+	let _lock = HARNESS_LOCK.lock().await;
+	let mut tui = TuiHarness::new(&[node_log("v0.1.0", 30, &[100])]).await;
+	let pending = |expires| PendingNodeAction {
+		action: NodeAction::Restart,
+		service_name: String::from("antnode1"),
+		node_index: 0,
+		expires,
+	};
+
+	tui.app.dash_state.pending_node_action = Some(pending(Utc::now() - Duration::seconds(1)));
+	tui.press(KeyCode::Char('y')).await;
+	assert!(tui.app.dash_state.pending_node_action.is_none());
+	assert!(!tui.app.dash_state.vdash_status.get_status().contains("requested"));
+
+	// Still answered while the prompt is shown
+	tui.app.dash_state.pending_node_action = Some(pending(Utc::now() + Duration::seconds(30)));
+	tui.press(KeyCode::Char('n')).await;
+	assert!(tui.app.dash_state.pending_node_action.is_none());
+	assert!(tui.app.dash_state.vdash_status.get_status().contains("cancelled"));
+
+	// And expired on a tick without any key
+	tui.app.dash_state.pending_node_action = Some(pending(Utc::now() - Duration::seconds(1)));
+	tui.app.expire_confirmations(&Utc::now());
+	assert!(tui.app.dash_state.pending_node_action.is_none());
+
+	// A stale 'y' doesn't remove a node and delete its checkpoint
+	let logfile = tui.app.logfile_with_focus.clone();
+	tui.app.dash_state.pending_node_removal = Some((logfile, Utc::now() - Duration::seconds(1)));
+	tui.press(KeyCode::Char('y')).await;
+	assert!(tui.app.dash_state.pending_node_removal.is_none());
+	assert_eq!(tui.app.monitors.len(), 1);
+}
+
+#[tokio::test]
+async fn it_checks_node_processes_on_every_tick() {
+	let _lock = HARNESS_LOCK.lock().await;
+	// A PID which can't be running
+	let log = node_log("v0.1.0", 30, &[100]).replace("(PID: 1234)", "(PID: 4294967296)");
+	let mut tui = TuiHarness::with_args(&[log], &["--pid-check", "1"]).await;
+	assert!(!tui.app.monitors.values().any(|monitor| monitor.metrics.process_dead));
+
+	// As the main loop does for a tick, including one which arrives during a burst of keys
+	tui.app.on_tick().await;
+	let monitor = tui.app.monitors.values().next().unwrap();
+	assert!(monitor.metrics.process_dead);
+	assert!(monitor.metrics.node_status_string.contains("PROCESS DEAD (PID 4294967296)"));
+}
+
+#[tokio::test]
+async fn it_keeps_the_given_token_rate_when_cycling_currencies() {
+	let _lock = HARNESS_LOCK.lock().await;
+	let mut tui = TuiHarness::with_args(
+		&[node_log("v0.1.0", 30, &[100])],
+		&["--currency-apiname", "USD,EUR", "--currency-token-rate", "2.5"],
+	)
+	.await;
+	assert_eq!(tui.app.dash_state.currency_per_token, Some(2.5));
+
+	// Neither currency has a price from the web APIs
+	tui.press(KeyCode::Char('c')).await;
+	assert!(tui.app.dash_state.vdash_status.get_status().contains("Currency: EUR"));
+	assert_eq!(tui.app.dash_state.currency_per_token, Some(2.5));
+}
@@ -1,16 +1,25 @@
-pub const ATTOS_PER_ANT: f64 = 1e18;
-
 /// Terminal based interface and dashboard
 ///
 /// Edit src/custom/ui.rs to create a customised fork of logtail-dash
+use chrono::Utc;
+
 use super::app::{App, DashState, DashViewMain};
+use super::logfile_names::common_logfile_prefix;
+use super::number_format::{format_amount, format_count};
 use super::ui_debug::draw_debug_dash;
+use super::ui_earnings::draw_earnings_dash;
+use super::ui_fleet::draw_fleet_dash;
 use super::ui_help::draw_help_dash;
 use super::ui_node::draw_node_dash;
+use super::ui_status_bar::draw_status_bar;
+use super::profile::profile_scope;
+use super::timelines::BucketValue;
+use super::token_units::fleet_token_unit;
+pub use super::token_units::{attos_to_ant, ATTOS_PER_ANT};
 use super::ui_summary::draw_summary_dash;
 
-/// Provides string representation of an attos amount, in either attos or currency depending on dash_state
-pub fn monetary_string(dash_state: &DashState, attos: u64) -> String {
+/// Provides string representation of an attos amount, in either the unit logged by the nodes or currency depending on dash_state
+pub fn monetary_string(dash_state: &DashState, attos: u128) -> String {
 	if dash_state.ui_uses_currency && dash_state.currency_per_token.is_some() {
 		let value = (dash_state.currency_per_token.unwrap() * (attos as f64)) / 2 as f64;
 		return if value >= 0.01 {
@@ -19,53 +28,95 @@ pub fn monetary_string(dash_state: &DashState, attos: u64) -> String {
 			format!("{:<1}{:.9}", dash_state.currency_symbol, value)
 		};
 	} else {
-		return format!("{}", attos);
+		return format_count(dash_state.number_format, dash_state.token_unit.from_attos(attos));
+	}
+}
+
+/// Provides string representation of a count, such as records or GETS, in the number format of dash_state
+pub fn count_string(dash_state: &DashState, value: u64) -> String {
+	format_count(dash_state.number_format, u128::from(value))
+}
+
+/// Provides string representation of a value in the currency of dash_state
+pub fn currency_string(dash_state: &DashState, value: f64) -> String {
+	if value >= 0.01 {
+		format!("{:<1}{:.2}", dash_state.currency_symbol, value)
+	} else {
+		format!("{:<1}{:.9}", dash_state.currency_symbol, value)
 	}
 }
 
 /// Provides string representation of an ANT amount, in either attos or currency depending on dash_state
-pub fn monetary_string_ant(dash_state: &DashState, attos: u64) -> String {
+pub fn monetary_string_ant(dash_state: &DashState, attos: u128) -> String {
 	if dash_state.ui_uses_currency && dash_state.currency_per_token.is_some() {
 		let value = dash_state.currency_per_token.unwrap() * attos_to_ant(attos);
-		return if value >= 0.01 {
-			format!("{:<1}{:.2}", dash_state.currency_symbol, value)
-		} else {
-			format!("{:<1}{:.9}", dash_state.currency_symbol, value)
-		};
+		return currency_string(dash_state, value);
 	} else {
-		return if attos >= 10000000000000000000 {
-			format!("{:.9}", attos_to_ant(attos))
-		} else {
-			format!("{:.9}", attos_to_ant(attos))
-		};
+		return format_amount(dash_state.number_format, attos_to_ant(attos), 9);
 	}
 }
 
-fn attos_to_ant(attos: u64) -> f64 {
-	attos as f64 / ATTOS_PER_ANT
-}
-
 #[path = "../widgets/mod.rs"]
 pub mod widgets;
 use self::widgets::sparkline::Sparkline2;
 
 use ratatui::{
-	layout::Rect,
-	style::{Color, Style},
+	layout::{Constraint, Direction, Layout, Rect},
+	style::{Color, Modifier, Style},
 	text::Line,
 	widgets::{Block, ListItem},
 	Frame,
 };
 
 pub fn draw_dashboard(f: &mut Frame, app: &mut App) {
+	profile_scope!("draw_dashboard");
+	let idle = app.dash_state.is_idle(&Utc::now());
+	if idle && !app.dash_state.idle_dim {
+		return; // Leaves the screen blank
+	}
+
+	app.dash_state.logfile_prefix = common_logfile_prefix(&app.logfiles_manager.logfiles_added);
+	app.dash_state.token_unit = fleet_token_unit(&app.monitors);
+
+	// Each view is drawn above the status bar
+	let chunks = Layout::default()
+		.direction(Direction::Vertical)
+		.constraints([Constraint::Min(0), Constraint::Length(1)].as_ref())
+		.split(f.size());
+	let area = chunks[0];
+
 	match app.dash_state.main_view {
-		DashViewMain::DashSummary => draw_summary_dash(f, &mut app.dash_state, &mut app.monitors),
-		DashViewMain::DashNode => draw_node_dash(f, &mut app.dash_state, &mut app.monitors),
-		DashViewMain::DashHelp => draw_help_dash(f, &mut app.dash_state),
-		DashViewMain::DashDebug => draw_debug_dash(f, &mut app.dash_state, &mut app.monitors),
+		DashViewMain::DashSummary if app.dash_state.split_view => draw_split_dash(f, area, app),
+		DashViewMain::DashSummary => draw_summary_dash(f, area, &mut app.dash_state, &mut app.monitors),
+		DashViewMain::DashNode => draw_node_dash(f, area, &mut app.dash_state, &mut app.monitors),
+		DashViewMain::DashHelp => draw_help_dash(f, area, &mut app.dash_state),
+		DashViewMain::DashEarnings => draw_earnings_dash(f, area, &mut app.dash_state, &mut app.monitors),
+		DashViewMain::DashFleet => draw_fleet_dash(f, area, &mut app.dash_state, &mut app.monitors),
+		DashViewMain::DashDebug => draw_debug_dash(f, area, &mut app.dash_state, &mut app.monitors),
+	}
+	draw_status_bar(f, chunks[1], &mut app.dash_state, &app.monitors, &app.logfile_with_focus);
+
+	if idle {
+		let area = f.size();
+		f.buffer_mut().set_style(
+			area,
+			Style::default().fg(Color::DarkGray).bg(Color::Reset).add_modifier(Modifier::DIM),
+		);
 	}
 }
 
+/// The Summary above the node of its selected row, without the node's logfile
+fn draw_split_dash(f: &mut Frame, area: Rect, app: &mut App) {
+	app.focus_summary_selection();
+	let chunks = Layout::default()
+		.direction(Direction::Vertical)
+		.constraints([Constraint::Percentage(45), Constraint::Percentage(55)].as_ref())
+		.split(area);
+
+	draw_summary_dash(f, chunks[0], &mut app.dash_state, &mut app.monitors);
+	draw_node_dash(f, chunks[1], &mut app.dash_state, &mut app.monitors);
+}
+
 pub fn push_subheading(items: &mut Vec<ListItem>, subheading: &String) {
 	items.push(
 		ListItem::new(vec![Line::from(subheading.clone())]).style(Style::default().fg(Color::Yellow)),
@@ -114,19 +165,56 @@ pub fn push_metric_with_units(
 pub fn draw_sparkline(
 	f: &mut Frame,
 	area: Rect,
-	buckets: &Vec<u64>,
+	buckets: &Vec<BucketValue>,
 	title: &str,
 	fg_colour: ratatui::style::Color,
 ) {
-	let sparkline = Sparkline2::default()
+	draw_sparkline_with_max(f, area, buckets, title, fg_colour, None);
+}
+
+/// As draw_sparkline() but with an optional vertical scale shared with other sparklines
+pub fn draw_sparkline_with_max(
+	f: &mut Frame,
+	area: Rect,
+	buckets: &Vec<BucketValue>,
+	title: &str,
+	fg_colour: ratatui::style::Color,
+	max: Option<BucketValue>,
+) {
+	let mut sparkline = Sparkline2::default()
 		.block(Block::default().title(title))
 		.data(buckets_right_justify(&buckets, area.width))
 		.style(Style::default().fg(fg_colour));
+	if let Some(max) = max {
+		sparkline = sparkline.max(max);
+	}
+	f.render_widget(sparkline, area);
+}
+
+/// As draw_sparkline_with_max() but drawn as a line of braille dots with min on the bottom row
+pub fn draw_braille_sparkline(
+	f: &mut Frame,
+	area: Rect,
+	buckets: &Vec<BucketValue>,
+	title: &str,
+	fg_colour: ratatui::style::Color,
+	min: BucketValue,
+	max: Option<BucketValue>,
+) {
+	let mut sparkline = Sparkline2::default()
+		.block(Block::default().title(title))
+		.data(buckets_right_justify(&buckets, area.width))
+		.style(Style::default().fg(fg_colour))
+		.braille(true)
+		.min(min);
+	if let Some(max) = max {
+		sparkline = sparkline.max(max);
+	}
 	f.render_widget(sparkline, area);
 }
 
 // Right justify and truncate (left) a set of buckets to width
-fn buckets_right_justify(buckets: &Vec<u64>, width: u16) -> &[u64] {
+fn buckets_right_justify(buckets: &Vec<BucketValue>, width: u16) -> &[BucketValue] {
 	let width = width as usize;
 	if width < buckets.len() {
 		return &buckets[buckets.len() - width..];
@@ -17,17 +17,19 @@ use super::ui_node::draw_logfile;
 
 pub fn draw_debug_dash(
 	f: &mut Frame,
+	area: Rect,
 	_dash_state: &DashState,
 	monitors: &mut HashMap<String, LogMonitor>,
 ) {
 	for (logfile, monitor) in monitors.iter_mut() {
 		if monitor.is_debug_dashboard_log {
-			draw_logfile(f, f.size(), logfile, monitor);
+			draw_logfile(f, area, logfile, monitor);
 		}
 	}
 }
 
-pub fn draw_debug_window(f: &mut Frame, area: Rect, dash_state: &mut DashState) {
+/// The debug window, with title_suffix (if not empty) appended to its title
+pub fn draw_debug_window(f: &mut Frame, area: Rect, dash_state: &mut DashState, title_suffix: &str) {
 	let highlight_style = match dash_state.debug_window_has_focus {
 		true => Style::default()
 			.bg(Color::LightGreen)
@@ -45,11 +47,16 @@ pub fn draw_debug_window(f: &mut Frame, area: Rect, dash_state: &mut DashState)
 		})
 		.collect();
 
+	let mut title = format!("{} v{} - {}", get_app_name(), get_app_version(), String::from(DEBUG_WINDOW_NAME));
+	if !title_suffix.is_empty() {
+		title = format!("{} ({})", title, title_suffix);
+	}
+
 	let debug_window_widget = List::new(items)
 		.block(
 			Block::default()
 				.borders(Borders::ALL)
-				.title(format!("{} v{} - {}", get_app_name(), get_app_version(), String::from(DEBUG_WINDOW_NAME))),
+				.title(title),
 			)
 		.highlight_style(highlight_style);
 
@@ -0,0 +1,139 @@
+//! Terminal based interface and dashboard
+//!
+use std::collections::HashMap;
+
+use super::app::{DashState, LogMonitor, EARNINGS_WINDOW_NAME};
+use super::earnings_ledger::{EarningsPeriod, NodeEarningsEntry, PeriodEarnings};
+use super::opt::{get_app_name, get_app_version};
+use super::ui::{attos_to_ant, currency_string};
+
+use ratatui::{
+	layout::{Constraint, Direction, Layout, Rect},
+	style::{Color, Modifier, Style},
+	text::Line,
+	widgets::{Block, Borders, List, ListItem},
+	Frame,
+};
+
+const EARNINGS_HEADING: &str = "Time (UTC)             Node                  Attos              ANT      Value Then       Value Now";
+const DAY_HEADING: &str = "Day (UTC)              Node   Payments                  Attos              ANT      Value Then       Value Now";
+const WEEK_HEADING: &str = "Week (UTC)             Node   Payments                  Attos              ANT      Value Then       Value Now";
+
+pub fn format_earnings_row(dash_state: &DashState, currency_apiname: &str, e: &NodeEarningsEntry) -> String {
+	let value_then = e
+		.entry
+		.value_then(currency_apiname)
+		.map_or(String::from("-"), |value| currency_string(dash_state, value));
+	let value_now = dash_state.currency_per_token.map_or(String::from("-"), |rate| {
+		currency_string(dash_state, rate * attos_to_ant(e.entry.attos))
+	});
+
+	// Older payments are rolled up into one entry for each day
+	let time = match e.entry.payments {
+		1 => e.entry.time.format("%Y-%m-%d %H:%M:%S").to_string(),
+		payments => format!("{} {} paid", e.entry.time.format("%Y-%m-%d"), payments),
+	};
+	format!(
+		"{:<22} {:>4} {:>22} {:>16.9} {:>15} {:>15}",
+		time,
+		e.node_index + 1,
+		e.entry.attos,
+		attos_to_ant(e.entry.attos),
+		value_then,
+		value_now
+	)
+}
+
+/// Row of payments totalled for a period (see 'd'), for all nodes or one
+pub fn format_period_row(dash_state: &DashState, period: EarningsPeriod, row: &PeriodEarnings) -> String {
+	let value_then = row.value_then.map_or(String::from("-"), |value| currency_string(dash_state, value));
+	let value_now = dash_state.currency_per_token.map_or(String::from("-"), |rate| {
+		currency_string(dash_state, rate * attos_to_ant(row.attos))
+	});
+	let node = row.node_index.map_or(String::from("All"), |index| (index + 1).to_string());
+
+	format!(
+		"{:<22} {:>4} {:>10} {:>22} {:>16.9} {:>15} {:>15}",
+		period.label(row.start),
+		node,
+		row.payments,
+		row.attos,
+		attos_to_ant(row.attos),
+		value_then,
+		value_now
+	)
+}
+
+pub fn draw_earnings_dash(
+	f: &mut Frame,
+	area: Rect,
+	dash_state: &mut DashState,
+	_monitors: &mut HashMap<String, LogMonitor>,
+) {
+	let period_name = match dash_state.earnings_period {
+		EarningsPeriod::Payment => "",
+		EarningsPeriod::Day => " by Day",
+		EarningsPeriod::Week => " by Week",
+	};
+	let window_widget = Block::default().borders(Borders::ALL).title(format!(
+		"{}{}  ({} v{})",
+		String::from(EARNINGS_WINDOW_NAME),
+		period_name,
+		get_app_name(),
+		get_app_version()
+	));
+	f.render_widget(window_widget, area);
+
+	let chunks = Layout::default()
+		.direction(Direction::Vertical)
+		.constraints([Constraint::Length(1), Constraint::Min(0)].as_ref())
+		.margin(1)
+		.split(area);
+
+	draw_earnings_heading(f, chunks[0], dash_state.earnings_period);
+	draw_earnings_rows(f, chunks[1], dash_state);
+}
+
+fn draw_earnings_heading(f: &mut Frame, area: Rect, period: EarningsPeriod) {
+	let heading_style = Style::default().fg(Color::White).bg(Color::Black);
+	let heading = match period {
+		EarningsPeriod::Payment => EARNINGS_HEADING,
+		EarningsPeriod::Day => DAY_HEADING,
+		EarningsPeriod::Week => WEEK_HEADING,
+	};
+	let heading_widget = List::new(vec![
+		ListItem::new(vec![Line::from(heading)]).style(heading_style)
+	]);
+	f.render_widget(heading_widget, area);
+}
+
+fn draw_earnings_rows(f: &mut Frame, area: Rect, dash_state: &mut DashState) {
+	let highlight_style = Style::default()
+		.bg(Color::LightGreen)
+		.add_modifier(Modifier::BOLD);
+
+	let items: Vec<ListItem> = dash_state
+		.earnings_window_rows
+		.items
+		.iter()
+		.map(|s| {
+			// Totals for all nodes stand out from those of each node
+			let style = if s.get(23..27) == Some(" All") {
+				Style::default().fg(Color::LightGreen).add_modifier(Modifier::BOLD)
+			} else {
+				Style::default().fg(Color::White)
+			};
+			ListItem::new(vec![Line::from(s.clone())]).style(style)
+		})
+		.collect();
+
+	let earnings_widget = List::new(items)
+		.block(Block::default())
+		.highlight_style(highlight_style);
+
+	f.render_stateful_widget(
+		earnings_widget,
+		area,
+		&mut dash_state.earnings_window_rows.state,
+	);
+}
@@ -0,0 +1,169 @@
+//! Terminal based interface and dashboard
+//!
+use std::collections::HashMap;
+
+use chrono::Days;
+
+use super::replay::dashboard_time;
+
+use super::app::{DashState, LogMonitor, FLEET_WINDOW_NAME};
+use super::fleet_history::{is_significant_change, DayActivity};
+use super::fleet_map::{FleetMap, AGE_BANDS, RECORDS_BANDS};
+use super::opt::{get_app_name, get_app_version};
+use super::ui::monetary_string_ant;
+
+use ratatui::{
+	layout::{Constraint, Direction, Layout, Rect},
+	style::{Color, Modifier, Style},
+	text::{Line, Span},
+	widgets::{Block, Borders, List, ListItem},
+	Frame,
+};
+
+const FLEET_HEADING: &str = "Node        Earned Today       Yesterday        Week Ago    PUTS Today  Yesterday   Week Ago    Records Today  Yesterday   Week Ago";
+
+/// A row comparing today, yesterday and the same day last week for a node,
+/// and whether yesterday differs significantly from a week before
+pub fn format_fleet_row(dash_state: &DashState, monitor: &LogMonitor) -> (String, bool) {
+	let today = dashboard_time().date_naive();
+	let daily = &monitor.metrics.daily_totals;
+	let activity = |days_ago: u64| {
+		today
+			.checked_sub_days(Days::new(days_ago))
+			.and_then(|date| daily.activity_on(&date))
+	};
+	let (today, yesterday, week_ago) = (activity(0), activity(1), activity(7));
+
+	let earned = |a: &Option<DayActivity>| {
+		a.map_or(String::from("-"), |a| monetary_string_ant(dash_state, a.attos_earned))
+	};
+	let puts = |a: &Option<DayActivity>| a.map_or(String::from("-"), |a| a.puts.to_string());
+	let records = |a: &Option<DayActivity>| a.map_or(String::from("-"), |a| a.records_stored.to_string());
+
+	let significant = match (yesterday, week_ago) {
+		(Some(yesterday), Some(week_ago)) => {
+			is_significant_change(week_ago.attos_earned, yesterday.attos_earned)
+				|| is_significant_change(week_ago.puts.into(), yesterday.puts.into())
+		}
+		_ => false,
+	};
+
+	let row = format!(
+		"{:>4} {:>19} {:>15} {:>15} {:>13} {:>10} {:>10} {:>16} {:>10} {:>10}",
+		monitor.index + 1,
+		earned(&today),
+		earned(&yesterday),
+		earned(&week_ago),
+		puts(&today),
+		puts(&yesterday),
+		puts(&week_ago),
+		records(&today),
+		records(&yesterday),
+		records(&week_ago),
+	);
+	(row, significant)
+}
+
+pub fn draw_fleet_dash(
+	f: &mut Frame,
+	area: Rect,
+	dash_state: &mut DashState,
+	monitors: &mut HashMap<String, LogMonitor>,
+) {
+	let window_widget = Block::default().borders(Borders::ALL).title(format!(
+		"{}  ({} v{})",
+		String::from(FLEET_WINDOW_NAME),
+		get_app_name(),
+		get_app_version()
+	));
+	f.render_widget(window_widget, area);
+
+	let chunks = Layout::default()
+		.direction(Direction::Vertical)
+		.constraints(
+			[
+				Constraint::Length(1),
+				Constraint::Min(0),
+				Constraint::Length(AGE_BANDS.len() as u16 + 3),
+			]
+			.as_ref(),
+		)
+		.margin(1)
+		.split(area);
+
+	draw_fleet_heading(f, chunks[0]);
+	draw_fleet_rows(f, chunks[1], dash_state);
+	draw_fleet_map(f, chunks[2], &FleetMap::from_monitors(monitors.values(), &dashboard_time()));
+}
+
+fn draw_fleet_heading(f: &mut Frame, area: Rect) {
+	let heading_style = Style::default().fg(Color::White).bg(Color::Black);
+	let heading_widget = List::new(vec![
+		ListItem::new(vec![Line::from(FLEET_HEADING)]).style(heading_style)
+	]);
+	f.render_widget(heading_widget, area);
+}
+
+fn draw_fleet_rows(f: &mut Frame, area: Rect, dash_state: &mut DashState) {
+	let highlight_style = Style::default()
+		.bg(Color::LightGreen)
+		.add_modifier(Modifier::BOLD);
+
+	let items: Vec<ListItem> = dash_state
+		.fleet_window_rows
+		.items
+		.iter()
+		.enumerate()
+		.map(|(i, s)| {
+			let significant = dash_state.fleet_rows_significant.get(i).copied().unwrap_or(false);
+			let fg = if significant { Color::Yellow } else { Color::White };
+			ListItem::new(vec![Line::from(s.clone())]).style(Style::default().fg(fg))
+		})
+		.collect();
+
+	let fleet_widget = List::new(items)
+		.block(Block::default())
+		.highlight_style(highlight_style);
+
+	f.render_stateful_widget(fleet_widget, area, &mut dash_state.fleet_window_rows.state);
+}
+
+// Grid of node counts, brighter where more nodes are
+fn draw_fleet_map(f: &mut Frame, area: Rect, fleet_map: &FleetMap) {
+	const LABEL_WIDTH: usize = 12;
+	const CELL_WIDTH: usize = 10;
+
+	let mut heading = format!("{:<LABEL_WIDTH$}", "Age");
+	for (records_label, _) in RECORDS_BANDS {
+		heading += &format!("{:>CELL_WIDTH$}", records_label);
+	}
+	let mut items = vec![ListItem::new(Line::from(heading)).style(Style::default().fg(Color::White).bg(Color::Black))];
+
+	for (row, (age_label, _)) in AGE_BANDS.iter().enumerate() {
+		let mut spans = vec![Span::raw(format!("{:<LABEL_WIDTH$}", age_label))];
+		for count in fleet_map.counts[row] {
+			let colour = match count {
+				0 => Color::DarkGray,
+				count if count * 3 <= fleet_map.max_count => Color::Green,
+				count if count * 3 <= fleet_map.max_count * 2 => Color::LightGreen,
+				_ => Color::Yellow,
+			};
+			let text = if count == 0 { String::from("-") } else { count.to_string() };
+			spans.push(Span::styled(format!("{:>CELL_WIDTH$}", text), Style::default().fg(colour)));
+		}
+		items.push(ListItem::new(Line::from(spans)));
+	}
+	if fleet_map.unknown_age > 0 {
+		items.push(ListItem::new(Line::from(format!(
+			"{} nodes have no log entries yet",
+			fleet_map.unknown_age
+		))));
+	}
+
+	let map_widget = List::new(items).block(
+		Block::default()
+			.borders(Borders::TOP)
+			.title("Fleet Map: nodes by age (rows) and records stored (columns)"),
+	);
+	f.render_widget(map_widget, area);
+}
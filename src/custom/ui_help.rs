@@ -1,6 +1,9 @@
 ///! Terminal based interface and dashboard
 ///!
+///! The keys shown in Help are those of the keymap in use, so they include
+///! any changed with --keymap and --key.
 use super::app::{DashState, HELP_WINDOW_NAME};
+use super::keymap::{key_name, Action, Keymap};
 use crate::custom::opt::{get_app_name, get_app_version};
 use crate::custom::ui::{push_blank, push_multiline_text, push_subheading, push_text};
 
@@ -10,8 +13,132 @@ use ratatui::{
 	Frame,
 };
 
-pub fn draw_help_dash(f: &mut Frame, dash_state: &mut DashState) {
-	draw_help_window(f, f.size(), dash_state);
+/// Width of the column of keys, before each description
+const KEYS_WIDTH: usize = 15;
+
+/// Help for the actions, by section, in the order shown. Descriptions may continue
+/// over more than one line.
+const HELP_SECTIONS: &[(&str, &[(Action, &str)])] = &[
+	(
+		"Keyboard Commands",
+		&[
+			(Action::NodeView, "Switch to Node Status where you can cycle through status of each node."),
+			(Action::SummaryView, "Switch to Summary of all monitored nodes."),
+			(Action::Select, "Switch between Summary and Node Status, expand or collapse a group, or go back from\nHelp, Earnings or Fleet."),
+			(Action::EarningsView, "Switch to Earnings Ledger listing every payment received."),
+			(Action::ExportCsv, "Export the Earnings Ledger to a CSV file (when viewing the ledger)."),
+			(Action::EarningsPeriod, "Cycle the Earnings Ledger through each payment, totals by day and totals by week."),
+			(Action::ExportTaxReport, "Export a tax report CSV, valuing payments when received (when viewing the ledger)."),
+			(Action::SplitView, "Split the Summary to show the node of the selected row below it. Press again to go back."),
+			(Action::FleetView, "Switch to Fleet view comparing today, yesterday and a week ago for each node,\nwith a map of nodes by age and records stored."),
+			(Action::RestartNode, "Restart the selected node using antctl (requires --antctl-command)."),
+			(Action::StopNode, "Stop the selected node using antctl."),
+			(Action::UpgradeNode, "Upgrade the selected node using antctl."),
+			(Action::Filter, "Filter the Summary table and totals (e.g. status=INACTIVE OR errors>0, name contains oracle)."),
+			(Action::ClearFilter, "Clear the filter to show all nodes."),
+			(Action::Tags, "Set tags for the selected node (comma separated)."),
+			(Action::JumpToNode, "Jump to a node by its number, or part of its logfile path or tags (e.g. n42log), from any view."),
+			(Action::CommandPalette, "Enter a command: sort <column> [asc|desc], filter [expression], export csv|earnings|tax [path],\nadd-glob <glob>, or the name of any action (see --key)."),
+			(Action::Up, "Move the selection up, or scroll the logfile or Help."),
+			(Action::Down, "Move the selection down, or scroll the logfile or Help."),
+			(Action::PageUp, "Scroll the Summary or Help a page up."),
+			(Action::PageDown, "Scroll the Summary or Help a page down."),
+			(Action::Top, "Go to the first node in the Summary, or the top of Help."),
+			(Action::Bottom, "Go to the last node in the Summary, or the end of Help."),
+			(Action::ReverseSort, "Reverse the order of the selected Summary column."),
+			(Action::KeepSortKey, "Keep the order of the selected column for ties when sorting by another. Press again to stop."),
+			(Action::NextBreakdown, "Change the timeline shown for each node beside the Summary statistics and in its Trend column (e.g. GETS)."),
+			(Action::ToggleGroups, "Show or hide groups of nodes in the Summary."),
+			(Action::NextAverage, "Cycle the Summary's average Storage Cost and Connections through the mean, the mean weighted\nby records stored, and a recent average which favours the last hour."),
+			(Action::SessionDelta, "Show Earnings, PUTS, GETS and Errors since vdash started rather than in total. Press again for totals."),
+			(Action::CopyNodeIds, "Copy the PeerId and logfile path of the selected node to the clipboard."),
+			(Action::Rescan, "Re-scan any 'glob' paths to add new nodes."),
+			(Action::RemoveNode, "Remove the selected node and delete its checkpoint (in Summary or Node Status)."),
+			(Action::DeleteCheckpoints, "Delete checkpoints which could not be restored (metrics are rebuilt from the logfile)."),
+			(Action::ToggleCurrency, "Toggle between attos and a currency (if rate specified on the command line)."),
+			(Action::NextCurrency, "Cycle through currencies (if more than one given with --currency-apiname)."),
+			(Action::Quit, "Quit vdash."),
+			(Action::Help, "Shows this help."),
+		],
+	),
+	(
+		"Node Status: selecting a node",
+		&[
+			(Action::NextNode, "Cycle forward through multiple monitored nodes."),
+			(Action::PreviousNode, "Cycle backwards through multiple monitored nodes."),
+		],
+	),
+	(
+		"Node Status: timelines",
+		&[
+			(Action::ZoomOut, "Zoom timeline out."),
+			(Action::ZoomIn, "Zoom timeline in. Add timescales to zoom through with --timescale (e.g. 10s, 6h)."),
+			(Action::PanBack, "Pan timelines back through their history."),
+			(Action::PanForward, "Pan timelines forward through their history."),
+			(Action::TimelineWindow, "Show timelines for a period (e.g. 14:00-16:00 yesterday), or enter nothing for now."),
+			(Action::NextMmm, "Cycle through min, mean, max, p50, p95 and p99 values for non-cumulative timelines (e.g. Storage Cost)."),
+			(Action::AllMmm, "Toggle showing min, mean and max values together for non-cumulative timelines."),
+			(Action::Braille, "Toggle drawing timelines as high resolution braille lines (from min to max) or bars."),
+			(Action::TimelineCursor, "Toggle a vertical scale and cursor on timelines."),
+			(Action::CursorOlder, "Move the timeline cursor to an older bucket (when shown)."),
+			(Action::CursorNewer, "Move the timeline cursor to a newer bucket (when shown)."),
+			(Action::EarningsUnits, "Cycle earnings timeline units through attos, ANT and currency (if available)."),
+			(Action::NextTimeline, "Scroll timelines up if some are hidden due to lack of vertical space."),
+			(Action::PreviousTimeline, "Scroll timelines down."),
+			(Action::ToggleLogfile, "Toggle between show logfile plus 3 timelines and hide logfile to show more timelines."),
+			(Action::TimelinesShorter, "Give the timelines fewer rows, and the logfile more."),
+			(Action::TimelinesTaller, "Give the timelines more rows, and the logfile fewer."),
+			(Action::TopMessages, "Toggle between the logfile and the node's most frequent ERROR and WARN messages."),
+			(Action::Diagnostics, "Switch between the node's Status and Diagnostics tabs (PeerId, PID, checkpoint, parse failures, parser coverage etc.)."),
+		],
+	),
+	(
+		"Replaying logfiles (with --replay)",
+		&[
+			(Action::ReplayBack, "Step back through time."),
+			(Action::ReplayForward, "Step forward through time."),
+			(Action::PlayPause, "Play or pause."),
+		],
+	),
+];
+
+/// Actions only available with --debug-window
+const DEBUG_ACTIONS: &[(Action, &str)] = &[
+	(Action::DebugView, "Show the debug window."),
+	(Action::DebugLine, "Send a line to the debug window."),
+];
+
+pub fn draw_help_dash(f: &mut Frame, area: Rect, dash_state: &mut DashState) {
+	draw_help_window(f, area, dash_state);
+}
+
+/// Keys bound to action, leaving out an upper case letter whose lower case also is
+fn keys_text(keymap: &Keymap, action: Action) -> String {
+	let names: Vec<String> = keymap.keys_for(action).into_iter().map(key_name).collect();
+	let shown: Vec<&str> = names
+		.iter()
+		.filter(|name| **name == name.to_lowercase() || !names.contains(&name.to_lowercase()))
+		.map(String::as_str)
+		.collect();
+	if shown.is_empty() {
+		return String::from("(no key)");
+	}
+	shown.join(" or ")
+}
+
+fn push_actions(items: &mut Vec<ListItem>, keymap: &Keymap, actions: &[(Action, &str)]) {
+	for (action, description) in actions {
+		let keys = keys_text(keymap, *action);
+		for (i, line) in description.lines().enumerate() {
+			let keys = if i == 0 { keys.as_str() } else { "" };
+			let separator = if i == 0 { ":" } else { " " };
+			push_text(
+				items,
+				&format!("    {:<width$}{}   {}", keys, separator, line, width = KEYS_WIDTH),
+				None,
+			);
+		}
+	}
 }
 
 pub fn draw_help_window(f: &mut Frame, area: Rect, dash_state: &mut DashState) {
@@ -25,66 +152,51 @@ pub fn draw_help_window(f: &mut Frame, area: Rect, dash_state: &mut DashState) {
 	);
 	push_text(&mut items, &String::from("        vdash --help"), None);
 
-	push_blank(&mut items);
-	push_subheading(&mut items, &String::from("    Keyboard Commands"));
-	push_multiline_text(
-		&mut items,
-		"
-    'n' or 'enter' :   Switch to Node Status where you can cycle through status of each node.\n
-    's' or 'enter' :   Switch to Summary of all monitored nodes.\n
-    'r'            :   Re-scan any 'glob' paths to add new nodes.\n
-    '$'            :   Toggle between attos and a currency (if rate specified on the command line).
-
-	'q'            :   Quit vdash.
-    'h' or '?'     :   Shows this help. Press 'n' or 's' to exit help.",
-	);
+	for (heading, actions) in HELP_SECTIONS {
+		push_blank(&mut items);
+		push_subheading(&mut items, &format!("    {}", heading));
+		push_blank(&mut items);
+		push_actions(&mut items, &dash_state.keymap, actions);
+	}
+	if dash_state.debug_window {
+		push_blank(&mut items);
+		push_subheading(&mut items, &String::from("    Debugging (with --debug-window)"));
+		push_blank(&mut items);
+		push_actions(&mut items, &dash_state.keymap, DEBUG_ACTIONS);
+	}
 
 	push_blank(&mut items);
-	push_blank(&mut items);
-	push_subheading(
+	push_multiline_text(
 		&mut items,
-		&String::from("    Node Status: selecting a node"),
+		"    Errors by Category counts the node's ERROR lines as connection, storage, payment or other (see --error-category).
+    If started with --idle-minutes the dashboard is blanked (or dimmed with --idle-dim) when idle. Any key restores it.
+    Summary cells are coloured for a few seconds when their value changes (see --highlight-changes).
+    Large counts can be shown as 1,234,567 or 1.23M (see --number-format).
+    Keys can be changed with --keymap and --key (see the README).",
 	);
-	push_blank(&mut items);
-
-	push_text(&mut items, &String::from("    Use right arrow and left arrow to cycle forward and backwards through multiple monitored nodes."), None);
-	push_blank(&mut items);
-
-	push_blank(&mut items);
-	push_blank(&mut items);
-	push_subheading(&mut items, &String::from("    Node Status: timelines"));
-
-	push_multiline_text(&mut items,"
-    'o' or '-'     :   Zoom timeline out.
-    'i' or '+'     :   Zoom timeline in.
-
-    'm'            :   Cycle through min, mean, max values for non-cumulative timelines (e.g. Storage Cost).
-
-    't':           :   Scroll timelines up if some are hidden due to lack of vertical space.
-    'T':           :   Scroll timelines down.
-
-    'l'            :   Toggle between show logfile plus 3 timelines and hide logfile to show more timelines.
-	");
 
 	push_blank(&mut items);
 	push_subheading(&mut items, &String::from("    To exit Help press 'enter'"));
 
-	if dash_state.debug_window {
-		push_blank(&mut items);
-		push_blank(&mut items);
-		push_text(&mut items, &String::from("    'g' for debug window"), None);
-	}
-
 	let help_title_text = format!(
 		"{} v{} - {}",
 		get_app_name(),
 		get_app_version(),
 		String::from(HELP_WINDOW_NAME)
 	);
+	let rows = items.len();
 	let help_widget = List::new(items).block(
 		Block::default()
 			.borders(Borders::ALL)
 			.title(help_title_text),
 	);
-	f.render_stateful_widget(help_widget, area, &mut dash_state.help_status.state);
+
+	// Keep the last page in view however far it has been scrolled
+	let page_rows = area.height.saturating_sub(2) as usize;
+	dash_state.help_page_rows = page_rows;
+	let state = &mut dash_state.help_status.state;
+	*state.offset_mut() = state.offset().min(rows.saturating_sub(page_rows));
+	// A List scrolls back to show its selected (or first) item, so select the top row
+	state.select(Some(state.offset()));
+	f.render_stateful_widget(help_widget, area, state);
 }
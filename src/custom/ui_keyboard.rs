@@ -1,27 +1,116 @@
+use chrono::Utc;
 use crossterm::event::KeyCode;
 
-use crate::custom::app::{App, DashViewMain, set_main_view};
+use crate::custom::antctl::NodeAction;
+use crate::custom::app::{App, DashViewMain, EarningsUnits, PromptKind, set_main_view};
+use crate::custom::command_palette::run_command;
+use crate::custom::keymap::{Action, Key};
 
 /// Handle a keyboard event and return false to cause exit of app (vdash)
-pub async fn handle_keyboard_event(mut app: &mut App, event: &crossterm::event::KeyEvent, opt_debug_window: bool) -> bool {
+pub async fn handle_keyboard_event(app: &mut App, event: &crossterm::event::KeyEvent, opt_debug_window: bool) -> bool {
 
-    match event.code {
-        // For debugging, ~ sends a line to the debug_window
-        KeyCode::Char('~') => app.dash_state._debug_window(format!("Event::Input({:#?})", event).as_str()),
+    // A key pressed while idle only restores the dashboard
+    let now = Utc::now();
+    let was_idle = app.dash_state.is_idle(&now);
+    app.dash_state.last_input_time = now;
+    if was_idle {
+        return true;
+    }
 
-        KeyCode::Char('q')|
-        KeyCode::Char('Q') => {
+    // Until it times out, any key answers a request to confirm a node action
+    app.expire_confirmations(&now);
+    if app.dash_state.pending_node_action.is_some() {
+        app.confirm_node_action(event.code == KeyCode::Char('y') || event.code == KeyCode::Char('Y'));
+        return true;
+    }
+
+    if app.dash_state.pending_node_removal.is_some() {
+        app.confirm_node_removal(event.code == KeyCode::Char('y') || event.code == KeyCode::Char('Y'));
+        return true;
+    }
+
+    if app.dash_state.input_prompt.is_some() {
+        match event.code {
+            KeyCode::Enter if app.dash_state.input_prompt.as_ref().is_some_and(|prompt| prompt.kind == PromptKind::Command) => {
+                let text = app.dash_state.input_prompt.take().map(|prompt| prompt.text).unwrap_or_default();
+                return run_command(app, &text, event, opt_debug_window).await;
+            },
+            KeyCode::Enter => app.finish_input_prompt(true),
+            KeyCode::Esc => app.finish_input_prompt(false),
+            KeyCode::Backspace => {
+                if let Some(prompt) = app.dash_state.input_prompt.as_mut() { prompt.text.pop(); }
+                app.show_input_prompt();
+            },
+            KeyCode::Char(c) => {
+                if let Some(prompt) = app.dash_state.input_prompt.as_mut() { prompt.text.push(c); }
+                app.show_input_prompt();
+            },
+            _ => {}
+        }
+        return true;
+    }
+
+    for action in app.dash_state.keymap.actions(Key::from_event(event)).collect::<Vec<Action>>() {
+        if action == Action::Quit {
             return false;
+        }
+        if perform_action(app, action, event, opt_debug_window).await {
+            break;
+        }
+    }
+
+    return true;
+}
+
+/// Perform the action, returning false if it doesn't apply in the current view
+pub async fn perform_action(mut app: &mut App, action: Action, event: &crossterm::event::KeyEvent, opt_debug_window: bool) -> bool {
+    let view = app.dash_state.main_view;
+    let summary_or_node = view == DashViewMain::DashSummary || view == DashViewMain::DashNode;
+    match action {
+        Action::Quit |
+        Action::Unbound => return false,
+
+        // For debugging, sends a line to the debug_window
+        Action::DebugLine => app.dash_state._debug_window(format!("Event::Input({:#?})", event).as_str()),
+        Action::DebugView => {
+            if opt_debug_window { set_main_view(DashViewMain::DashDebug, &mut app); }
+        },
+
+        // With the timeline cursor shown, arrows move it through the buckets
+        Action::CursorOlder|
+        Action::CursorNewer => {
+            if app.dash_state.timeline_cursor.is_none() || view != DashViewMain::DashNode {
+                return false;
+            }
+            app.move_timeline_cursor(action == Action::CursorOlder);
         },
-        KeyCode::Enter => {
-            if app.dash_state.main_view == DashViewMain::DashHelp {
+
+        // When replaying, arrows step through time in the Summary and Node views
+        Action::ReplayBack|
+        Action::ReplayForward|
+        Action::PlayPause => {
+            if app.dash_state.replay.is_none() || !summary_or_node {
+                return false;
+            }
+            match action {
+                Action::PlayPause => app.toggle_replay_playing(),
+                _ => app.replay_step(action == Action::ReplayForward),
+            }
+        },
+        Action::Select => {
+            if view == DashViewMain::DashHelp ||
+               view == DashViewMain::DashEarnings ||
+               view == DashViewMain::DashFleet {
                 set_main_view(app.dash_state.previous_main_view, &mut app);
             } else {
                 if app.logfiles_manager.logfiles_added.len() > 0 {
-                    if app.dash_state.main_view == DashViewMain::DashNode {
+                    if view == DashViewMain::DashNode {
                         app.preserve_node_selection();
                         set_main_view(DashViewMain::DashSummary, &mut app);
-                    } else if app.dash_state.main_view == DashViewMain::DashSummary {
+                    } else if view == DashViewMain::DashSummary {
+                        if app.toggle_selected_group() {
+                            return true;
+                        }
                         app.preserve_node_selection();
                         set_main_view(DashViewMain::DashNode, &mut app);
                     }
@@ -29,66 +118,175 @@ pub async fn handle_keyboard_event(mut app: &mut App, event: &crossterm::event::
             }
         }
 
-        KeyCode::Char(' ') => {
-            if app.dash_state.main_view == DashViewMain::DashSummary {
-                app.dash_state.logfile_names_sorted_ascending = !app.dash_state.logfile_names_sorted_ascending;
-                app.update_summary_window();
+        Action::ReverseSort => {
+            if view == DashViewMain::DashSummary {
+                app.toggle_sort_direction();
             }
         }
 
-        KeyCode::Char('$') => {
+        Action::ToggleCurrency => {
             if app.dash_state.currency_per_token.is_some() {
                 app.dash_state.ui_uses_currency = !app.dash_state.ui_uses_currency;
+                app.dash_state.earnings_units = if app.dash_state.ui_uses_currency { EarningsUnits::Currency } else { EarningsUnits::Attos };
                 app.update_summary_window();
             }
         }
 
-        KeyCode::Char('s')|
-        KeyCode::Char('S') => {
+        Action::NextCurrency => app.next_currency(),
+
+        Action::SummaryView => {
             app.preserve_node_selection();
             set_main_view(DashViewMain::DashSummary, &mut app);
         },
 
-        KeyCode::Char('h')|
-        KeyCode::Char('H')|
-        KeyCode::Char('?') => set_main_view(DashViewMain::DashHelp, &mut app),
-        KeyCode::Char('n')|
-        KeyCode::Char('N') => {
+        Action::EarningsView => {
+            app.update_earnings_window();
+            set_main_view(DashViewMain::DashEarnings, app);
+        },
+
+        Action::SplitView => app.toggle_split_view(),
+
+        Action::FleetView => {
+            app.update_fleet_window();
+            set_main_view(DashViewMain::DashFleet, app);
+        },
+
+        Action::ExportCsv|
+        Action::ExportTaxReport => {
+            if view != DashViewMain::DashEarnings {
+                return false;
+            }
+            if action == Action::ExportCsv { app.export_earnings_csv(None) } else { app.export_tax_report(None) }
+        },
+        Action::RemoveNode => {
+            if !summary_or_node {
+                return false;
+            }
+            app.preserve_node_selection();
+            app.request_node_removal();
+        },
+
+        Action::RestartNode|
+        Action::StopNode|
+        Action::UpgradeNode => {
+            app.preserve_node_selection();
+            app.request_node_action(match action {
+                Action::RestartNode => NodeAction::Restart,
+                Action::StopNode => NodeAction::Stop,
+                _ => NodeAction::Upgrade,
+            });
+        },
+
+        Action::DeleteCheckpoints => app.delete_incompatible_checkpoints(),
+        Action::ToggleGroups => app.toggle_summary_groups(),
+        Action::KeepSortKey => {
+            if view != DashViewMain::DashSummary { return false; }
+            app.keep_sort_key();
+        },
+        Action::EarningsPeriod => {
+            if view != DashViewMain::DashEarnings { return false; }
+            app.next_earnings_period();
+        },
+        Action::SessionDelta => {
+            if view != DashViewMain::DashSummary { return false; }
+            app.toggle_session_delta();
+        },
+        Action::NextBreakdown => {
+            if view != DashViewMain::DashSummary { return false; }
+            app.next_breakdown_timeline();
+        },
+
+        Action::Filter => app.start_input_prompt(PromptKind::Filter),
+        Action::ClearFilter => {
+            if app.dash_state.node_filter.is_none() { return false; }
+            app.set_node_filter("");
+        },
+        Action::Tags => {
+            app.preserve_node_selection();
+            app.start_input_prompt(PromptKind::Tags);
+        },
+        Action::JumpToNode => app.start_input_prompt(PromptKind::JumpToNode),
+        Action::CommandPalette => app.start_input_prompt(PromptKind::Command),
+
+        Action::CopyNodeIds => {
+            app.preserve_node_selection();
+            app.copy_node_ids_to_clipboard();
+        },
+
+        Action::Help => set_main_view(DashViewMain::DashHelp, &mut app),
+        Action::NodeView => {
             if app.logfiles_manager.logfiles_added.len() > 0 {
                 app.preserve_node_selection();
                 set_main_view(DashViewMain::DashNode, &mut app);
             }
         },
 
-        KeyCode::Char('+')|
-        KeyCode::Char('i')|
-        KeyCode::Char('I') => app.scale_timeline_up(),
-        KeyCode::Char('-')|
-        KeyCode::Char('o')|
-        KeyCode::Char('O') => app.scale_timeline_down(),
+        Action::ZoomIn => app.scale_timeline_up(),
+        Action::ZoomOut => app.scale_timeline_down(),
 
-        KeyCode::Char('l')|
-        KeyCode::Char('L') => app.toggle_logfile_area(),
+        Action::PanBack => app.pan_timelines(true),
+        Action::PanForward => app.pan_timelines(false),
+        Action::TimelineWindow => app.start_input_prompt(PromptKind::TimelineWindow),
+
+        Action::ToggleLogfile => app.toggle_logfile_area(),
+        Action::TopMessages => {
+            if view != DashViewMain::DashNode { return false; }
+            app.toggle_top_messages();
+        },
+        Action::Diagnostics => {
+            if view != DashViewMain::DashNode { return false; }
+            app.toggle_node_diagnostics();
+        },
+        Action::TimelinesTaller|
+        Action::TimelinesShorter => {
+            if view == DashViewMain::DashNode {
+                app.resize_node_timelines(action == Action::TimelinesTaller);
+            }
+        },
 
-        KeyCode::Char('m')|
-        KeyCode::Char('M') => app.bump_mmm_ui_mode(),
+        Action::NextMmm => app.bump_mmm_ui_mode(),
+        Action::AllMmm => app.toggle_mmm_all_series(),
+        Action::NextAverage => {
+            if view != DashViewMain::DashSummary { return false; }
+            app.bump_average_mode();
+        },
+        Action::Braille => app.toggle_sparkline_braille(),
+        Action::TimelineCursor => {
+            if view != DashViewMain::DashNode { return false; }
+            app.toggle_timeline_cursor();
+        },
 
-        KeyCode::Char('r')|
-        KeyCode::Char('R') => app.scan_glob_paths(false, false).await,
+        Action::EarningsUnits => app.bump_earnings_units(),
 
-        KeyCode::Char('t') => app.top_timeline_next(),
-        KeyCode::Char('T') => app.top_timeline_previous(),
+        Action::Rescan => app.scan_glob_paths(false, false).await,
 
-        KeyCode::Down => app.handle_arrow_down(),
-        KeyCode::Up => app.handle_arrow_up(),
-        KeyCode::Right|
-        KeyCode::Tab => app.change_focus_next(),
-        KeyCode::Left => app.change_focus_previous(),
+        Action::NextTimeline => app.top_timeline_next(),
+        Action::PreviousTimeline => app.top_timeline_previous(),
 
-        KeyCode::Char('g') => {
-            if opt_debug_window { set_main_view(DashViewMain::DashDebug, &mut app); }
+        Action::PageDown|
+        Action::PageUp if view == DashViewMain::DashHelp => {
+            let rows = app.dash_state.help_page_rows as isize;
+            app.scroll_help(if action == Action::PageDown { rows } else { -rows });
+        },
+        Action::Top|
+        Action::Bottom if view == DashViewMain::DashHelp => {
+            app.scroll_help(if action == Action::Bottom { isize::MAX } else { isize::MIN });
         },
-        _ => {}
+        Action::PageDown|
+        Action::PageUp => {
+            if view != DashViewMain::DashSummary { return false; }
+            app.summary_page(action == Action::PageDown);
+        },
+        Action::Top|
+        Action::Bottom => {
+            if view != DashViewMain::DashSummary { return false; }
+            app.summary_select_end(action == Action::Bottom);
+        },
+
+        Action::Down => app.handle_arrow_down(),
+        Action::Up => app.handle_arrow_up(),
+        Action::NextNode => app.change_focus_next(),
+        Action::PreviousNode => app.change_focus_previous(),
     };
 
     return true;
@@ -1,65 +1,83 @@
 ///! Terminal based interface and dashboard
 ///!
-use chrono::Utc;
+use super::replay::dashboard_time;
 use std::collections::HashMap;
 
+use chrono::{DateTime, Local, Utc};
+
 #[path = "../widgets/mod.rs"]
 pub mod widgets;
 use self::widgets::gauge::Gauge2;
 
-use super::app::{DashState, LogMonitor};
-use super::timelines::Timeline;
-use crate::custom::app_timelines::EARNINGS_UNITS_TEXT;
-use crate::custom::timelines::{get_duration_text, get_max_buckets_value, get_min_buckets_value};
+use super::app::{DashState, EarningsUnits, LogMonitor};
+use super::efficiency::{attos_per_1k_gets, attos_per_gb, bytes_stored};
+use super::error_categories::category_counts;
+use super::generic_logs::LEVELS;
+use super::logfile_names::short_logfile_name;
+use super::restart_history::uptime_percent_text;
+use super::timeline_window::pan_columns;
+use super::timelines::{Buckets, Timeline};
+use crate::custom::app_timelines::{EARNINGS_UNITS_TEXT, STORAGE_COST_UNITS_TEXT};
+use crate::custom::timelines::{get_duration_text, get_max_buckets_value, get_min_buckets_value, saturating_sum, BucketValue};
 
 use crate::custom::ui::{
-	draw_sparkline, monetary_string, monetary_string_ant, push_metric, push_metric_with_units,
-	push_subheading,
+	attos_to_ant, count_string, currency_string, draw_braille_sparkline, draw_sparkline, draw_sparkline_with_max, monetary_string_ant,
+	push_metric, push_metric_with_units, push_subheading,
 };
 
 use ratatui::{
 	layout::{Constraint, Direction, Layout, Rect},
 	style::{Color, Modifier, Style},
-	text::Line,
-	widgets::{Block, Borders, List, ListItem},
+	text::{Line, Span},
+	widgets::{Block, Borders, List, ListItem, Paragraph},
 	Frame,
 };
 
 pub fn draw_node_dash(
 	f: &mut Frame,
+	area: Rect,
 	dash_state: &mut DashState,
 	monitors: &mut HashMap<String, LogMonitor>,
 ) {
-	let size = f.size();
+	// Tall enough for the storage rows of the node with focus
+	let node_rows = monitors
+		.values()
+		.find(|monitor| monitor.has_focus)
+		.map_or(2 + 3 + OTHER_METRICS_ROWS, |monitor| 2 + storage_rows(monitor) + OTHER_METRICS_ROWS);
+
 	let chunks_with_3_bands = Layout::default()
 		.direction(Direction::Vertical)
 		.constraints(
 			[
-				Constraint::Length(12), // Stats summary and graphs
-				Constraint::Length(18), // Timelines
+				Constraint::Length(node_rows), // Stats summary and graphs
+				Constraint::Length(dash_state.node_timelines_height), // Timelines
 				Constraint::Min(0),     // Logfile panel
 			]
 			.as_ref(),
 		)
-		.split(size);
+		.split(area);
 
 	let chunks_with_2_bands = Layout::default()
 		.direction(Direction::Vertical)
 		.constraints(
 			[
-				Constraint::Length(12), // Stats summary and graphs
+				Constraint::Length(node_rows), // Stats summary and graphs
 				Constraint::Min(0),     // Timelines
 			]
 			.as_ref(),
 		)
-		.split(size);
+		.split(area);
 
 	for entry in monitors.into_iter() {
 		let (logfile, mut monitor) = entry;
 		if monitor.has_focus {
-			if dash_state.node_logfile_visible {
+			if monitor.generic.is_some() {
+				draw_generic_dash(f, area, dash_state, &logfile, &mut monitor);
+				return;
+			}
+			if dash_state.node_logfile_visible && !dash_state.is_split_view() {
 				// Stats and Graphs / Timelines / Logfile
-				draw_node(f, chunks_with_3_bands[0], dash_state, &mut monitor);
+				draw_node_or_diagnostics(f, chunks_with_3_bands[0], dash_state, &mut monitor);
 				draw_timelines_panel(f, chunks_with_3_bands[1], dash_state, &mut monitor);
 				draw_bottom_panel(
 					f,
@@ -71,7 +89,7 @@ pub fn draw_node_dash(
 				return;
 			} else {
 				// Stats and Graphs / Timelines
-				draw_node(f, chunks_with_2_bands[0], dash_state, &mut monitor);
+				draw_node_or_diagnostics(f, chunks_with_2_bands[0], dash_state, &mut monitor);
 				draw_timelines_panel(f, chunks_with_2_bands[1], dash_state, &mut monitor);
 				return;
 			}
@@ -79,13 +97,128 @@ pub fn draw_node_dash(
 	}
 
 	// In debug mode there's one node dash and this provide the debug dash
-	crate::custom::ui_debug::draw_debug_dash(f, dash_state, monitors);
+	crate::custom::ui_debug::draw_debug_dash(f, area, dash_state, monitors);
+}
+
+/// Line counts, a timeline of lines and the content of a logfile monitored in generic mode
+fn draw_generic_dash(f: &mut Frame, area: Rect, dash_state: &mut DashState, logfile: &String, monitor: &mut LogMonitor) {
+	let chunks = Layout::default()
+		.direction(Direction::Vertical)
+		.constraints(
+			[
+				Constraint::Length(LEVELS.len() as u16 + 5), // Line counts
+				Constraint::Length(8),                       // Lines timeline
+				Constraint::Min(0),                          // Logfile panel
+			]
+			.as_ref(),
+		)
+		.split(area);
+
+	if let Some(generic) = &monitor.generic {
+		let mut items = Vec::<ListItem>::new();
+		push_subheading(&mut items, &String::from("Generic logfile (not a node)"));
+		push_metric(&mut items, &"Lines".to_string(), &generic.lines.to_string());
+		push_metric(&mut items, &"Lines/min".to_string(), &generic.lines_per_minute().to_string());
+		for level in LEVELS {
+			push_metric(&mut items, &level.to_string(), &generic.level_count(level).to_string());
+		}
+		let latest_txt = generic
+			.latest_line_time
+			.map_or(String::from("-"), |time| time.format("%Y-%m-%d %H:%M:%S").to_string());
+		push_metric(&mut items, &"Latest line".to_string(), &latest_txt);
+
+		let stats_widget = List::new(items).block(
+			Block::default()
+				.borders(Borders::ALL)
+				.title(format!("Log {:>2} Status", monitor.index + 1)),
+		);
+		f.render_widget(stats_widget, chunks[0]);
+
+		if let Some(active_timescale_name) = dash_state.get_active_timescale_name() {
+			let window_widget = Block::default()
+				.borders(Borders::ALL)
+				.title(timelines_panel_title(dash_state, active_timescale_name));
+			f.render_widget(window_widget, chunks[1]);
+			let timeline_area = Layout::default().margin(1).constraints([Constraint::Min(0)].as_ref()).split(chunks[1])[0];
+			draw_timeline(f, timeline_area, dash_state, &generic.lines_timeline, active_timescale_name);
+		}
+	}
+
+	draw_logfile(f, chunks[2], &short_logfile_name(logfile, &dash_state.logfile_prefix), monitor);
+}
+
+fn draw_node_or_diagnostics(f: &mut Frame, area: Rect, dash_state: &mut DashState, monitor: &mut LogMonitor) {
+	if dash_state.node_diagnostics_visible {
+		draw_node_diagnostics(f, area, monitor);
+	} else {
+		draw_node(f, area, dash_state, monitor);
+	}
+}
+
+/// What vdash knows about a node and its logfile, otherwise only seen with --debug-window
+fn draw_node_diagnostics(f: &mut Frame, area: Rect, monitor: &LogMonitor) {
+	let metrics = &monitor.metrics;
+	let time_txt = |time: Option<DateTime<Utc>>| {
+		time.map_or(String::from("-"), |time| {
+			time.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S").to_string()
+		})
+	};
+
+	let mut items = Vec::<ListItem>::new();
+	push_metric(&mut items, &"Peer Id".to_string(), &metrics.node_peer_id.clone().unwrap_or(String::from("-")));
+	let pid_txt = match metrics.node_process_id {
+		Some(pid) if metrics.process_dead => format!("{} (not running)", pid),
+		Some(pid) => pid.to_string(),
+		None => String::from("-"),
+	};
+	push_metric(&mut items, &"PID".to_string(), &pid_txt);
+	push_metric(&mut items, &"Version".to_string(), &metrics.running_version.clone().unwrap_or(String::from("-")));
+	push_metric(&mut items, &"Logfile".to_string(), &monitor.logfile);
+	push_metric(&mut items, &"Checkpoint".to_string(), &time_txt(monitor.latest_checkpoint_time));
+	push_metric(&mut items, &"No timestamp".to_string(), &format!("{} lines", metrics.lines_without_metadata));
+	push_metric(&mut items, &"Not parsed".to_string(), &format!("{} values", metrics.parse_failures));
+	push_metric(&mut items, &"Not UTF-8".to_string(), &format!("{} lines", metrics.invalid_utf8_lines));
+	push_metric(&mut items, &"First entry".to_string(), &time_txt(metrics.first_entry_time));
+	let latest_entry_time = metrics.entry_metadata.as_ref().map(|metadata| metadata.message_time);
+	push_metric(&mut items, &"Latest entry".to_string(), &time_txt(latest_entry_time));
+	push_metric(&mut items, &"Parser".to_string(), &metrics.parser_output);
+
+	let chunks = Layout::default()
+		.direction(Direction::Horizontal)
+		.constraints([Constraint::Min(40), Constraint::Length(70)].as_ref())
+		.split(area);
+
+	let heading = format!("Node {:>2} Diagnostics ('!' for status)", monitor.index + 1);
+	let diagnostics_widget = List::new(items).block(Block::default().borders(Borders::ALL).title(heading));
+	f.render_widget(diagnostics_widget, chunks[0]);
+	draw_parser_coverage(f, chunks[1], monitor);
+}
+
+/// Lines matched by each parser rule, two rules to a row with those never matched first
+fn draw_parser_coverage(f: &mut Frame, area: Rect, monitor: &LogMonitor) {
+	let stats = &monitor.metrics.parser_stats;
+	let mut items = Vec::<ListItem>::new();
+	push_subheading(&mut items, &stats.summary_text());
+	for pair in stats.rule_counts().chunks(2) {
+		let spans: Vec<Span> = pair
+			.iter()
+			.map(|(rule, count)| {
+				let colour = if *count == 0 { Color::LightYellow } else { Color::Blue };
+				Span::styled(format!("{:<20}{:>12}  ", rule, count), Style::default().fg(colour))
+			})
+			.collect();
+		items.push(ListItem::new(Line::from(spans)));
+	}
+
+	let coverage_widget = List::new(items).block(Block::default().borders(Borders::ALL).title("Parser Coverage"));
+	f.render_widget(coverage_widget, area);
 }
 
 fn draw_node(f: &mut Frame, area: Rect, dash_state: &mut DashState, monitor: &mut LogMonitor) {
 	// Columns:
 	let constraints = [
 		Constraint::Length(40), // Stats summary
+		Constraint::Length(28), // Errors by category
 		Constraint::Min(10),    // Graphs
 	];
 
@@ -95,7 +228,28 @@ fn draw_node(f: &mut Frame, area: Rect, dash_state: &mut DashState, monitor: &mu
 		.split(area);
 
 	draw_node_stats(f, dash_state, chunks[0], monitor);
-	draw_node_storage(f, chunks[1], dash_state, monitor);
+	draw_node_error_categories(f, chunks[1], dash_state, monitor);
+	draw_node_storage(f, chunks[2], dash_state, monitor);
+}
+
+fn draw_node_error_categories(f: &mut Frame, area: Rect, dash_state: &DashState, monitor: &LogMonitor) {
+	let mut items = Vec::<ListItem>::new();
+	for (category, count) in category_counts(&monitor.metrics.error_categories) {
+		push_metric(&mut items, &category.to_string(), &count_string(dash_state, count));
+	}
+	push_metric(&mut items, &"total".to_string(), &count_string(dash_state, monitor.metrics.activity_errors.total));
+
+	let errors_widget = List::new(items).block(Block::default().borders(Borders::ALL).title("Errors by Category"));
+	f.render_widget(errors_widget, area);
+}
+
+/// Per-category error counts, as shown in the title of the debug window
+pub fn error_categories_text(monitor: &LogMonitor) -> String {
+	category_counts(&monitor.metrics.error_categories)
+		.iter()
+		.map(|(category, count)| format!("{} {}", category, count))
+		.collect::<Vec<String>>()
+		.join(", ")
 }
 
 fn draw_node_stats(
@@ -121,20 +275,40 @@ fn draw_node_stats(
 
 	let mut node_uptime_txt = String::from("Start time unknown");
 	if let Some(node_start_time) = monitor.metrics.node_started {
-		node_uptime_txt = get_duration_text(Utc::now() - node_start_time);
+		node_uptime_txt = get_duration_text(dashboard_time() - node_start_time);
 	}
 	push_metric(&mut items, &"Node Uptime".to_string(), &node_uptime_txt);
 
+	let restarts_txt = format!(
+		"{}  (uptime {})",
+		monitor.metrics.restart_history.restart_count(),
+		uptime_percent_text(
+			monitor
+				.metrics
+				.restart_history
+				.uptime_percent(&dashboard_time(), monitor.metrics.node_inactive)
+		)
+	);
+	push_metric(&mut items, &"Restarts".to_string(), &restarts_txt);
+
 	push_metric(
 		&mut items,
 		&"Status".to_string(),
 		&monitor.metrics.node_status_string,
 	);
 
+	if let Some(snapshot_age) = monitor.metrics.snapshot_age() {
+		push_metric(
+			&mut items,
+			&"Snapshot Age".to_string(),
+			&get_duration_text(snapshot_age),
+		);
+	}
+
 	let units_text = if dash_state.ui_uses_currency {
 		""
 	} else {
-		"ANT" // crate::custom::app_timelines::EARNINGS_UNITS_TEXT
+		dash_state.token_unit.token_name()
 	};
 
 	let wallet_balance = monetary_string_ant(dash_state, monitor.metrics.wallet_balance);
@@ -145,6 +319,32 @@ fn draw_node_stats(
 		&units_text.to_string(),
 	);
 
+	let wallet_watch = &monitor.metrics.wallet_watch;
+	if let Some(wallet_file_balance) = wallet_watch.balance {
+		push_metric_with_units(
+			&mut items,
+			&"Wallet File".to_string(),
+			&monetary_string_ant(dash_state, wallet_file_balance),
+			&units_text.to_string(),
+		);
+		push_metric_with_units(
+			&mut items,
+			&"Withdrawn".to_string(),
+			&monetary_string_ant(dash_state, wallet_watch.withdrawn),
+			&units_text.to_string(),
+		);
+		if wallet_watch.mismatched > 0 {
+			push_metric_with_units(
+				&mut items,
+				&"Mismatch".to_string(),
+				&monetary_string_ant(dash_state, wallet_watch.mismatched),
+				&units_text.to_string(),
+			);
+		}
+	} else if let Some(error) = &wallet_watch.error {
+		push_metric(&mut items, &"Wallet File".to_string(), error);
+	}
+
 	let storage_payments_txt = monetary_string_ant(dash_state, monitor.metrics.attos_earned.total);
 	push_metric_with_units(
 		&mut items,
@@ -153,41 +353,73 @@ fn draw_node_stats(
 		&units_text.to_string(),
 	);
 
+	// Efficiency, for comparing the profitability of nodes
+	let metrics = &monitor.metrics;
+	let per_gb = bytes_stored(metrics).and_then(|bytes| attos_per_gb(metrics.attos_earned.total, bytes));
+	let per_1k_gets = attos_per_1k_gets(metrics.attos_earned.total, metrics.activity_gets.total);
+	for (label, attos) in [("Earn/GB", per_gb), ("Earn/1k GETS", per_1k_gets)] {
+		push_metric_with_units(
+			&mut items,
+			&label.to_string(),
+			&attos.map_or(String::from("-"), |attos| monetary_string_ant(dash_state, attos)),
+			&units_text.to_string(),
+		);
+	}
+
 	let chunk_fee_txt = if monitor.metrics.storage_cost.most_recent == 0 {
 		String::from("unknown")
 	} else {
 		format!(
 			"{} ({}-{}){} ",
-			monitor.metrics.storage_cost.most_recent.to_string(),
-			monitor.metrics.storage_cost.min.to_string(),
-			monitor.metrics.storage_cost.max.to_string(),
-			crate::custom::app_timelines::STORAGE_COST_UNITS_TEXT,
+			dash_state.token_unit.from_attos(monitor.metrics.storage_cost.most_recent),
+			dash_state.token_unit.from_attos(monitor.metrics.storage_cost.min),
+			dash_state.token_unit.from_attos(monitor.metrics.storage_cost.max),
+			format!("{}/MB", dash_state.token_unit.name()),
 		)
 	};
 
 	push_metric(&mut items, &"Storage Cost".to_string(), &chunk_fee_txt);
 
+	// The percentile chosen with 'm', of every storage cost logged
+	if let Some(fraction) = dash_state.mmm_ui_mode().percentile() {
+		if let Some(attos) = monitor.metrics.storage_cost_percentiles.percentile(fraction) {
+			push_metric(
+				&mut items,
+				&format!("Cost p{:.0}", fraction * 100.0),
+				&format!("{} {}/MB", dash_state.token_unit.from_attos(attos), dash_state.token_unit.name()),
+			);
+		}
+	}
+
 	let connections_text = format!("{}", monitor.metrics.peers_connected.most_recent);
 	push_metric(&mut items, &"Connections".to_string(), &connections_text);
 
 	push_metric(
 		&mut items,
 		&"PUTS".to_string(),
-		&monitor.metrics.activity_puts.total.to_string(),
+		&count_string(dash_state, monitor.metrics.activity_puts.total),
 	);
 
 	push_metric(
 		&mut items,
 		&"GETS".to_string(),
-		&monitor.metrics.activity_gets.total.to_string(),
+		&count_string(dash_state, monitor.metrics.activity_gets.total),
 	);
 
 	push_metric(
 		&mut items,
 		&"ERRORS".to_string(),
-		&monitor.metrics.activity_errors.total.to_string(),
+		&count_string(dash_state, monitor.metrics.activity_errors.total),
 	);
 
+	if monitor.metrics.invalid_utf8_lines > 0 {
+		push_metric(
+			&mut items,
+			&"Invalid Lines".to_string(),
+			&format!("{} (not UTF-8)", monitor.metrics.invalid_utf8_lines),
+		);
+	}
+
 	push_subheading(&mut items, &"".to_string());
 	let heading = format!("Node {:>2} Status", monitor.index + 1);
 	let monitor_widget = List::new(items).block(
@@ -207,7 +439,7 @@ fn draw_timelines_panel(
 	if let Some(active_timescale_name) = dash_state.get_active_timescale_name() {
 		let window_widget = Block::default()
 			.borders(Borders::ALL)
-			.title(format!("Timeline - {}", active_timescale_name).to_string());
+			.title(timelines_panel_title(dash_state, active_timescale_name));
 		f.render_widget(window_widget, area);
 
 		// For debugging the bucket state
@@ -236,15 +468,22 @@ fn draw_timelines_panel(
 		// }
 
 		const NUM_TIMELINES_VISIBLE: u16 = 3;
+		let num_timelines = monitor.metrics.app_timelines.get_num_timelines().max(1) as u16;
 		let num_timelines_visible = if dash_state.node_logfile_visible {
-			NUM_TIMELINES_VISIBLE
+			NUM_TIMELINES_VISIBLE.min(num_timelines)
 		} else {
-			crate::custom::app_timelines::APP_TIMELINES.len() as u16
+			num_timelines
 		};
 
-		let chunks_slim = Layout::default()
+		// Timelines above a one line legend
+		let panel_chunks = Layout::default()
 			.direction(Direction::Vertical)
 			.margin(1)
+			.constraints([Constraint::Min(0), Constraint::Length(1)].as_ref())
+			.split(area);
+
+		let chunks_slim = Layout::default()
+			.direction(Direction::Vertical)
 			.constraints(
 				[
 					// Three timelines
@@ -254,11 +493,10 @@ fn draw_timelines_panel(
 				]
 				.as_ref(),
 			)
-			.split(area);
+			.split(panel_chunks[0]);
 
 		let chunks_fat = Layout::default()
 			.direction(Direction::Vertical)
-			.margin(1)
 			.constraints(
 				[
 					// Tailored to display all timelines in APP_TIMELINES (currently 7)
@@ -272,8 +510,9 @@ fn draw_timelines_panel(
 				]
 				.as_ref(),
 			)
-			.split(area);
+			.split(panel_chunks[0]);
 
+		let mut legend = Vec::<Span>::new();
 		let mut index = dash_state.top_timeline_index() + 1;
 		for i in 1..=num_timelines_visible {
 			if index > monitor.metrics.app_timelines.get_num_timelines() {
@@ -301,9 +540,104 @@ fn draw_timelines_panel(
 					timeline,
 					active_timescale_name,
 				);
+				push_legend_entry(&mut legend, dash_state, timeline);
 			}
 			index += 1;
 		}
+
+		legend.push(Span::styled(
+			"('u' earnings units)",
+			Style::default().fg(Color::DarkGray),
+		));
+		f.render_widget(
+			List::new(vec![ListItem::new(Line::from(legend))]),
+			panel_chunks[1],
+		);
+	}
+}
+
+fn timelines_panel_title(dash_state: &DashState, active_timescale_name: &str) -> String {
+	let mut title = format!("Timeline - {}", active_timescale_name);
+	if let Some(window_end) = dash_state.timeline_window_end {
+		title += &format!(" ending {} ('[' ']' to pan)", window_end.format("%Y-%m-%d %H:%M"));
+	}
+	if let Some(replay) = &dash_state.replay {
+		title += &format!("  ({})", replay.status_text());
+	}
+	title
+}
+
+/// The buckets up to the end of the timeline window and, if it isn't now, the
+/// period visible in width columns (e.g. "2 hrs to 03-22 16:00")
+fn window_buckets(dash_state: &DashState, bucket_set: &Buckets, buckets: &[BucketValue], width: u16) -> (Vec<BucketValue>, Option<String>) {
+	let pan = pan_columns(
+		&dash_state.timeline_window_end,
+		&bucket_set.bucket_time,
+		bucket_set.bucket_duration,
+		buckets.len(),
+	);
+	if pan == 0 {
+		return (buckets.to_vec(), None);
+	}
+
+	let window = buckets[..buckets.len() - pan].to_vec();
+	let visible_columns = window.len().min(width as usize);
+	let period_text = bucket_set.bucket_time.map(|bucket_time| {
+		let window_end = bucket_time + bucket_set.bucket_duration * (1 - pan as i32);
+		format!(
+			"{} to {}",
+			get_duration_text(bucket_set.bucket_duration * visible_columns as i32),
+			window_end.format("%m-%d %H:%M")
+		)
+	});
+	(window, period_text)
+}
+
+fn push_legend_entry<'a>(legend: &mut Vec<Span<'a>>, dash_state: &DashState, timeline: &Timeline) {
+	let units_text = timeline_units_text(dash_state, timeline);
+	let entry_text = if units_text.is_empty() {
+		format!("{}  ", timeline.name)
+	} else {
+		format!("{} ({})  ", timeline.name, units_text)
+	};
+	legend.push(Span::styled("■ ", Style::default().fg(timeline.colour)));
+	legend.push(Span::raw(entry_text));
+}
+
+// Units shown for a timeline, which for earnings depends on the units selected in the UI
+// and for amounts on the unit the nodes log them in
+fn timeline_units_text(dash_state: &DashState, timeline: &Timeline) -> String {
+	if timeline.units_text == STORAGE_COST_UNITS_TEXT {
+		return format!("{}/MB", dash_state.token_unit.name());
+	}
+	if timeline.units_text != EARNINGS_UNITS_TEXT {
+		return timeline.units_text.clone();
+	}
+
+	match dash_state.earnings_units {
+		EarningsUnits::Attos => String::from(dash_state.token_unit.name()),
+		EarningsUnits::Ant => String::from(dash_state.token_unit.token_name()),
+		EarningsUnits::Currency => dash_state.currency_symbol.trim().to_string(),
+	}
+}
+
+// A timeline value in the units of timeline_units_text(), for timelines other than earnings
+fn timeline_value(dash_state: &DashState, timeline: &Timeline, value: BucketValue) -> BucketValue {
+	if timeline.units_text == STORAGE_COST_UNITS_TEXT {
+		dash_state.token_unit.from_attos(value)
+	} else {
+		value
+	}
+}
+
+// Earnings amount in the units selected in the UI
+fn earnings_amount_text(dash_state: &DashState, attos: BucketValue) -> String {
+	match (dash_state.earnings_units, dash_state.currency_per_token) {
+		(EarningsUnits::Currency, Some(currency_per_token)) => {
+			currency_string(dash_state, currency_per_token * attos_to_ant(attos))
+		}
+		(EarningsUnits::Ant, _) => format!("{:.9} {}", attos_to_ant(attos), dash_state.token_unit.token_name()),
+		_ => format!("{} {}", dash_state.token_unit.from_attos(attos), dash_state.token_unit.name()),
 	}
 }
 
@@ -322,29 +656,48 @@ fn draw_timeline(
 			MinMeanMax::Min => " Min ",
 			MinMeanMax::Mean => " Mean",
 			MinMeanMax::Max => " Max ",
+			MinMeanMax::P50 => " p50 ",
+			MinMeanMax::P95 => " p95 ",
+			MinMeanMax::P99 => " p99 ",
 		}
 	} else {
 		""
 	};
 
+	if timeline.is_mmm && dash_state.mmm_all_series {
+		draw_timeline_mmm_all_series(f, area, dash_state, timeline, active_timescale_name);
+		return;
+	}
+
 	if let Some(bucket_set) = timeline.get_bucket_set(active_timescale_name) {
 		if let Some(buckets) = timeline.get_buckets(active_timescale_name, Some(mmm_ui_mode)) {
 			// dash_state._debug_window(format!("bucket[0-2 to max]: {},{},{},{} to {}, for {}", buckets[0], buckets[1], buckets[2], buckets[3], buckets[buckets.len()-1], display_name).as_str());
-			let duration_text = bucket_set.get_duration_text();
+			let all_buckets_len = buckets.len();
+			let (buckets, window_period_text) = window_buckets(dash_state, bucket_set, buckets, area.width);
+			let (period_text, values_total) = match window_period_text {
+				Some(period_text) => {
+					let visible_columns = buckets.len().min(area.width as usize);
+					(period_text, saturating_sum(&buckets[buckets.len() - visible_columns..]))
+				}
+				None => (format!("last {}", bucket_set.get_duration_text()), bucket_set.values_total),
+			};
 
-			let mut max_bucket_value = get_max_buckets_value(buckets);
-			let mut min_bucket_value = get_min_buckets_value(buckets);
+			let mut max_bucket_value = get_max_buckets_value(&buckets);
+			let mut min_bucket_value = get_min_buckets_value(&buckets);
+			let is_earnings = timeline.units_text == EARNINGS_UNITS_TEXT;
 			let label_stats = if timeline.is_cumulative {
-				if dash_state.ui_uses_currency && timeline.units_text == EARNINGS_UNITS_TEXT {
+				if is_earnings {
 					format!(
-						"{} in last {}",
-						monetary_string(dash_state, bucket_set.values_total),
-						duration_text
+						"{} in {}",
+						earnings_amount_text(dash_state, values_total),
+						period_text
 					)
 				} else {
 					format!(
-						"{} {} in last {}",
-						bucket_set.values_total, timeline.units_text, duration_text
+						"{} {} in {}",
+						timeline_value(dash_state, timeline, values_total),
+						timeline_units_text(dash_state, timeline),
+						period_text
 					)
 				}
 			} else {
@@ -353,31 +706,206 @@ fn draw_timeline(
 				if max_bucket_value == 0 {
 					max_bucket_value = timeline.last_non_zero_value;
 				}
-				if min_bucket_value == u64::MAX || min_bucket_value == 0 {
+				if min_bucket_value == BucketValue::MAX || min_bucket_value == 0 {
 					min_bucket_value = max_bucket_value;
 				}
 				format!(
-					"range {}-{} {} in last {}",
-					min_bucket_value, max_bucket_value, timeline.units_text, duration_text
+					"range {}-{} {} in {}",
+					timeline_value(dash_state, timeline, min_bucket_value),
+					timeline_value(dash_state, timeline, max_bucket_value),
+					timeline_units_text(dash_state, timeline),
+					period_text
 				)
 			};
-			let label_scale = if max_bucket_value > 0 {
+			// Braille lines of a varying metric are drawn from its minimum rather than zero
+			let scale_min = if dash_state.sparkline_braille && !timeline.is_cumulative {
+				min_bucket_value
+			} else {
+				0
+			};
+			let label_scale = if max_bucket_value > 0 && is_earnings {
 				format!(
-					" (vertical scale: 0-{} {})",
-					max_bucket_value, timeline.units_text
+					" (vertical scale: {}-{})",
+					earnings_amount_text(dash_state, scale_min),
+					earnings_amount_text(dash_state, max_bucket_value)
+				)
+			} else if max_bucket_value > 0 {
+				format!(
+					" (vertical scale: {}-{} {})",
+					timeline_value(dash_state, timeline, scale_min),
+					timeline_value(dash_state, timeline, max_bucket_value),
+					timeline_units_text(dash_state, timeline)
 				)
 			} else {
 				String::from("")
 			};
+
+			// With the cursor on, the axis takes columns from the left of the sparkline
+			let (area, cursor_column, label_cursor) = match dash_state.timeline_cursor {
+				Some(cursor) => {
+					let max = if max_bucket_value > 0 { max_bucket_value } else { get_max_buckets_value(&buckets) };
+					let area = draw_timeline_axis(f, area, scale_min, max);
+					let visible_columns = buckets.len().min(area.width as usize);
+					if visible_columns == 0 {
+						(area, None, String::from(""))
+					} else {
+						let cursor = cursor.min(visible_columns - 1);
+						dash_state.timeline_cursor = Some(cursor);
+						let value = buckets[buckets.len() - 1 - cursor];
+						let value_text = if is_earnings {
+							earnings_amount_text(dash_state, value)
+						} else {
+							format!(
+								"{} {}",
+								timeline_value(dash_state, timeline, value),
+								timeline_units_text(dash_state, timeline)
+							)
+						};
+						// Columns back from the latest bucket, which starts at bucket_time
+						let columns_back = (all_buckets_len - buckets.len() + cursor) as i32;
+						let time_text = bucket_set.bucket_time.map_or(String::from(""), |bucket_time| {
+							(bucket_time - bucket_set.bucket_duration * columns_back)
+								.format(" at %m-%d %H:%M:%S")
+								.to_string()
+						});
+						(area, Some(visible_columns - 1 - cursor), format!(", cursor {}{}", value_text, time_text))
+					}
+				}
+				None => (area, None, String::from("")),
+			};
+
 			let timeline_label = format!(
-				"{}{}: {}{}",
-				timeline.name, mmm_text, label_stats, label_scale
+				"{}{}: {}{}{}",
+				timeline.name, mmm_text, label_stats, label_scale, label_cursor
 			);
-			draw_sparkline(f, area, &buckets, &timeline_label, timeline.colour);
+			if dash_state.sparkline_braille {
+				let max = if max_bucket_value > 0 { Some(max_bucket_value) } else { None };
+				draw_braille_sparkline(f, area, &buckets, &timeline_label, timeline.colour, scale_min, max);
+			} else {
+				draw_sparkline(f, area, &buckets, &timeline_label, timeline.colour);
+			}
+			if let Some(column) = cursor_column {
+				// Highlight the bucket under the cursor, below the title row
+				let x = area.x + column as u16;
+				for y in area.y + 1..area.y + area.height {
+					f.buffer_mut().get_mut(x, y).set_bg(Color::DarkGray);
+				}
+			}
 		};
 	};
 }
 
+// Draw a vertical scale from min to max beside the rows of a sparkline below its title,
+// and return the area left for the sparkline
+fn draw_timeline_axis(f: &mut Frame, area: Rect, min: BucketValue, max: BucketValue) -> Rect {
+	let chart_height = area.height.saturating_sub(1) as usize;
+	if chart_height == 0 {
+		return area;
+	}
+	let max_text = max.to_string();
+	let min_text = min.min(max).to_string();
+	let mid_text = ((min.min(max) + max) / 2).to_string();
+	let label_width = max_text.len().max(min_text.len());
+	let axis_width = (label_width + 1) as u16;
+	if axis_width >= area.width {
+		return area;
+	}
+
+	let mut lines = vec![Line::from("")];
+	for row in 0..chart_height {
+		let label = if row == 0 {
+			Some(&max_text)
+		} else if row == chart_height - 1 {
+			Some(&min_text)
+		} else if chart_height >= 5 && row == chart_height / 2 {
+			Some(&mid_text)
+		} else {
+			None
+		};
+		lines.push(Line::from(match label {
+			Some(label) => format!("{:>width$}┤", label, width = label_width),
+			None => format!("{:>width$}│", "", width = label_width),
+		}));
+	}
+	let axis_area = Rect { width: axis_width, ..area };
+	f.render_widget(Paragraph::new(lines).style(Style::default().fg(Color::Gray)), axis_area);
+
+	Rect {
+		x: area.x + axis_width,
+		width: area.width - axis_width,
+		..area
+	}
+}
+
+// Draw the min, mean and max series of a timeline as three thin sparklines with a common scale
+fn draw_timeline_mmm_all_series(
+	f: &mut Frame,
+	area: Rect,
+	dash_state: &DashState,
+	timeline: &Timeline,
+	active_timescale_name: &str,
+) {
+	use crate::custom::timelines::MinMeanMax;
+
+	let Some(bucket_set) = timeline.get_bucket_set(active_timescale_name) else {
+		return;
+	};
+	let (min_buckets, period_text) =
+		window_buckets(dash_state, bucket_set, bucket_set.buckets(Some(&MinMeanMax::Min)), area.width);
+	let (mean_buckets, _) = window_buckets(dash_state, bucket_set, bucket_set.buckets(Some(&MinMeanMax::Mean)), area.width);
+	let (max_buckets, _) = window_buckets(dash_state, bucket_set, bucket_set.buckets(Some(&MinMeanMax::Max)), area.width);
+	let period_text = period_text.unwrap_or_else(|| format!("last {}", bucket_set.get_duration_text()));
+
+	let mut max_bucket_value = get_max_buckets_value(&max_buckets);
+	if max_bucket_value == 0 {
+		max_bucket_value = timeline.last_non_zero_value;
+	}
+	let mut min_bucket_value = get_min_buckets_value(&min_buckets);
+	if min_bucket_value == BucketValue::MAX || min_bucket_value == 0 {
+		min_bucket_value = max_bucket_value;
+	}
+
+	let scale_min = if dash_state.sparkline_braille { min_bucket_value } else { 0 };
+	let timeline_label = format!(
+		"{} Min/Mean/Max: range {}-{} {} in {} (vertical scale: {}-{} {})",
+		timeline.name,
+		timeline_value(dash_state, timeline, min_bucket_value),
+		timeline_value(dash_state, timeline, max_bucket_value),
+		timeline_units_text(dash_state, timeline),
+		period_text,
+		timeline_value(dash_state, timeline, scale_min),
+		timeline_value(dash_state, timeline, max_bucket_value),
+		timeline_units_text(dash_state, timeline)
+	);
+
+	let rows = Layout::default()
+		.direction(Direction::Vertical)
+		.constraints(
+			[
+				Constraint::Length(1),
+				Constraint::Ratio(1, 3),
+				Constraint::Ratio(1, 3),
+				Constraint::Ratio(1, 3),
+			]
+			.as_ref(),
+		)
+		.split(area);
+
+	f.render_widget(Block::default().title(timeline_label), rows[0]);
+	let series = [
+		(max_buckets, Color::LightRed),
+		(mean_buckets, timeline.colour),
+		(min_buckets, Color::Green),
+	];
+	for (i, (buckets, colour)) in series.iter().enumerate() {
+		if dash_state.sparkline_braille {
+			draw_braille_sparkline(f, rows[i + 1], buckets, "", *colour, scale_min, Some(max_bucket_value));
+		} else {
+			draw_sparkline_with_max(f, rows[i + 1], buckets, "", *colour, Some(max_bucket_value));
+		}
+	}
+}
+
 fn draw_bottom_panel(
 	f: &mut Frame,
 	area: Rect,
@@ -397,13 +925,60 @@ fn draw_bottom_panel(
 			.constraints(constraints.as_ref())
 			.split(area);
 
-		draw_logfile(f, chunks[0], &logfile, monitor);
-		crate::custom::ui_debug::draw_debug_window(f, chunks[1], dash_state);
+		draw_logfile_or_top_messages(f, chunks[0], dash_state, &logfile, monitor);
+		let title_suffix = format!("Node {} parser output, errors: {}", monitor.index + 1, error_categories_text(monitor));
+		crate::custom::ui_debug::draw_debug_window(f, chunks[1], dash_state, &title_suffix);
+	} else {
+		draw_logfile_or_top_messages(f, area, dash_state, &logfile, monitor);
+	}
+}
+
+fn draw_logfile_or_top_messages(
+	f: &mut Frame,
+	area: Rect,
+	dash_state: &DashState,
+	logfile: &String,
+	monitor: &mut LogMonitor,
+) {
+	if dash_state.top_messages_visible {
+		draw_top_messages(f, area, monitor);
 	} else {
-		draw_logfile(f, area, &logfile, monitor);
+		draw_logfile(f, area, &short_logfile_name(logfile, &dash_state.logfile_prefix), monitor);
 	}
 }
 
+/// The most frequent distinct ERROR and WARN messages, with counts and when each was last seen
+fn draw_top_messages(f: &mut Frame, area: Rect, monitor: &LogMonitor) {
+	let top_messages = &monitor.metrics.top_messages;
+	let mut items = Vec::<ListItem>::new();
+	if top_messages.is_empty() {
+		items.push(ListItem::new(Line::from("No ERROR or WARN messages yet")));
+	} else {
+		items.push(
+			ListItem::new(Line::from(format!("{:>7}  {:<19}  {:<5}  {}", "Count", "Last Seen", "Level", "Message")))
+				.style(Style::default().fg(Color::Yellow)),
+		);
+		let rows = area.height.saturating_sub(3) as usize;
+		for counted in top_messages.top(rows) {
+			let colour = if counted.level == "ERROR" { Color::LightRed } else { Color::LightYellow };
+			items.push(
+				ListItem::new(Line::from(format!(
+					"{:>7}  {:<19}  {:<5}  {}",
+					counted.count,
+					counted.last_seen.format("%Y-%m-%d %H:%M:%S"),
+					counted.level,
+					counted.message
+				)))
+				.style(Style::default().fg(colour)),
+			);
+		}
+	}
+
+	let title = format!("Top Errors ({} distinct, 'v' for logfile)", top_messages.len());
+	let top_messages_widget = List::new(items).block(Block::default().borders(Borders::ALL).title(title));
+	f.render_widget(top_messages_widget, area);
+}
+
 pub fn draw_logfile(f: &mut Frame, area: Rect, logfile: &String, monitor: &mut LogMonitor) {
 	let highlight_style = match monitor.has_focus {
 		true => Style::default()
@@ -412,17 +987,25 @@ pub fn draw_logfile(f: &mut Frame, area: Rect, logfile: &String, monitor: &mut L
 		false => Style::default().add_modifier(Modifier::BOLD),
 	};
 
-	let items: Vec<ListItem> = monitor
-		.content
-		.items
-		.iter()
-		.map(|s| {
-			ListItem::new(vec![Line::from(s.clone())])
-				.style(Style::default().fg(Color::Black).bg(Color::White))
-		})
-		.collect();
+	let items: Vec<ListItem> = if monitor.keeps_log_text {
+		monitor
+			.content
+			.items
+			.iter()
+			.map(|s| {
+				ListItem::new(vec![Line::from(s.clone())])
+					.style(Style::default().fg(Color::Black).bg(Color::White))
+			})
+			.collect()
+	} else {
+		vec![ListItem::new(Line::from("disabled (--no-log-text)"))]
+	};
 
-	let node_log_title = format!("Node Log ({})", logfile);
+	let node_log_title = if monitor.generic.is_some() {
+		format!("Log ({})", logfile)
+	} else {
+		format!("Node Log ({})", logfile)
+	};
 
 	let logfile_widget = List::new(items)
 		.block(
@@ -435,11 +1018,40 @@ pub fn draw_logfile(f: &mut Frame, area: Rect, logfile: &String, monitor: &mut L
 	f.render_stateful_widget(logfile_widget, area, &mut monitor.content.state);
 }
 
+/// Rows for the network and load metrics below the storage gauges
+const OTHER_METRICS_ROWS: u16 = 9;
+
+/// The types of record stored by the node and how many of each, when the log gives them
+fn record_types(monitor: &LogMonitor) -> Vec<(u32, u64)> {
+	monitor
+		.metrics
+		.records_per_type
+		.iter()
+		.filter(|(_data_type, count)| *count > 0)
+		.copied()
+		.collect()
+}
+
+/// True if the size of the node's record store has been measured (with --store-poll)
+fn has_store_row(monitor: &LogMonitor) -> bool {
+	let store_size = &monitor.metrics.store_size;
+	store_size.bytes.is_some() || store_size.error.is_some()
+}
+
+/// Rows for the storage heading, gauges and a bar for each type of record stored
+fn storage_rows(monitor: &LogMonitor) -> u16 {
+	3 + record_types(monitor).len() as u16 + has_store_row(monitor) as u16
+}
+
+/// Names of the types of record stored, in the order antnode numbers them. Registers
+/// are now stored as graph entries and pointers.
+const RECORD_TYPE_NAMES: [&str; 4] = ["Chunk", "GraphEntry", "Pointer", "Scratchpad"];
+
 // TODO split into two sub functions, one for gauges, one for text strings
 fn draw_node_storage(
 	f: &mut Frame,
 	area: Rect,
-	_dash_state: &mut DashState,
+	dash_state: &mut DashState,
 	monitor: &mut LogMonitor,
 ) {
 	let heading = format!("Node {:>2} Resources", monitor.index + 1);
@@ -456,14 +1068,19 @@ fn draw_node_storage(
 		);
 	f.render_stateful_widget(monitor_widget, area, &mut monitor.content.state);
 
+	// A bar for each type of record stored, when the log gives them
+	let record_types = record_types(monitor);
+	let storage_rows = storage_rows(monitor);
+	let store_row = has_store_row(monitor);
+
 	// Two rows top=gauges / bottom=text
 	let rows = Layout::default()
 		.direction(Direction::Vertical)
 		.margin(1)
 		.constraints(
 			[
-				Constraint::Length(2), // Rows for storage gauges
-				Constraint::Min(8),    // Rows for other metrics
+				Constraint::Length(storage_rows),     // Rows for storage gauges
+				Constraint::Min(OTHER_METRICS_ROWS), // Rows for other metrics
 			]
 			.as_ref(),
 		)
@@ -482,46 +1099,87 @@ fn draw_node_storage(
 	gauges_column.height = 1;
 
 	// One gauge gap for heading, and an extra gauge so the last one drawn doesn't expand to the bottom
-	let constraints = vec![Constraint::Length(1); 1 + 2];
+	let constraints = vec![Constraint::Length(1); storage_rows as usize + 1];
 	let gauges = Layout::default()
 		.direction(Direction::Vertical)
 		.constraints::<&[Constraint]>(constraints.as_ref())
 		.split(columns[1]);
 
-	let max_string = if monitor.metrics.records_max > 0 {
-		format!("/{}", monitor.metrics.records_max)
-	} else {
-		String::from("")
-	};
-	push_storage_metric(
-		&mut storage_items,
-		&"Records".to_string(),
-		&format!("{}{}", monitor.metrics.records_stored, max_string),
-	);
+	// Logs which give records stored are preferred, falling back to the space used given by older logs
+	let metrics = &monitor.metrics;
+	let (stored_label, stored_text, stored_ratio) =
+		if metrics.records_stored == 0 && metrics.records_max == 0 && metrics.space_max > 0 {
+			(
+				"Space Used",
+				format!("{:.0}/{:.0} MB", metrics.space_used as f64 / 1e6, metrics.space_max as f64 / 1e6),
+				ratio(metrics.space_used, metrics.space_max),
+			)
+		} else {
+			let max_string = if metrics.records_max > 0 {
+				format!("/{}", count_string(dash_state, metrics.records_max))
+			} else {
+				String::from("")
+			};
+			(
+				"Records",
+				format!("{}{}", count_string(dash_state, metrics.records_stored), max_string),
+				ratio(metrics.records_stored, metrics.records_max.max(1)),
+			)
+		};
+	push_storage_metric(&mut storage_items, &stored_label.to_string(), &stored_text);
 
-	let denominator = if monitor.metrics.records_max > 0 {
-		monitor.metrics.records_max
-	} else {
-		1
-	};
 	let gauge = Gauge2::default()
 		.block(Block::default())
 		.gauge_style(Style::default().fg(Color::Yellow))
-		.ratio(ratio(monitor.metrics.records_stored, denominator));
+		.ratio(stored_ratio);
 	f.render_widget(gauge, gauges[1]);
 
-	// TODO lobby to re-instate in node logfile
-	// push_storage_metric(
-	// 	&mut storage_items,
-	// 	&"Space Avail".to_string(),
-	// 	&max_string
-	// );
+	// Share of the records of each type
+	let records_typed = record_types.iter().map(|(_data_type, count)| count).sum::<u64>();
+	for (row, (data_type, count)) in record_types.iter().enumerate() {
+		push_storage_metric(&mut storage_items, &format!("  {}", record_type_name(*data_type)), &count_string(dash_state, *count));
+		let gauge = Gauge2::default()
+			.block(Block::default())
+			.gauge_style(Style::default().fg(Color::LightYellow))
+			.ratio(ratio(*count, records_typed));
+		f.render_widget(gauge, gauges[2 + row]);
+	}
 
-	// push_storage_metric(
-	// 	&mut storage_items,
-	// 	&"Space Free".to_string(),
-	// 	&device_limit_string
-	// );
+	// Size of the record store when scanned, as a share of the space it could use
+	let disk_space = &monitor.metrics.disk_space;
+	let mut row = 2 + record_types.len();
+	if store_row {
+		let store_size = &monitor.metrics.store_size;
+		let store_text = match store_size.bytes {
+			Some(bytes) => format!("{:.0} MB", bytes as f64 / 1e6),
+			None => String::from("error"),
+		};
+		push_storage_metric(&mut storage_items, &"Store Size".to_string(), &store_text);
+		if let (Some(bytes), Some(free)) = (store_size.bytes, disk_space.free) {
+			let gauge = Gauge2::default()
+				.block(Block::default())
+				.gauge_style(Style::default().fg(Color::Yellow))
+				.ratio(ratio(bytes, bytes + free));
+			f.render_widget(gauge, gauges[row]);
+		}
+		row += 1;
+	}
+
+	let (space_free_text, free_ratio) = match (disk_space.free, disk_space.total) {
+		(Some(free), Some(total)) => (
+			format!("{:.0}/{:.0} GB", free as f64 / 1e9, total as f64 / 1e9),
+			ratio(free, total.max(1)),
+		),
+		_ => (String::from(if disk_space.error.is_some() { "error" } else { "unknown" }), 0.0),
+	};
+	push_storage_metric(&mut storage_items, &"Space Free".to_string(), &space_free_text);
+
+	let free_colour = if disk_space.low { Color::LightRed } else { Color::Green };
+	let gauge = Gauge2::default()
+		.block(Block::default())
+		.gauge_style(Style::default().fg(free_colour))
+		.ratio(free_ratio);
+	f.render_widget(gauge, gauges[row]);
 
 	let storage_text_widget = List::new(storage_items).block(Block::default().borders(Borders::NONE));
 	f.render_widget(storage_text_widget, columns[0]);
@@ -562,14 +1220,26 @@ fn draw_node_storage(
 
 	let node_text = format!(
 		"{:<13}: CPU {:8.2} (MAX {:2.2}) MEM {}MB",
-		"Node",
+		if monitor.metrics.resources_sampled { "Node (PID)" } else { "Node" },
 		monitor.metrics.cpu_usage_percent,
 		monitor.metrics.cpu_usage_percent_max,
 		monitor.metrics.memory_used_mb.most_recent,
 	);
+	let envelope_breaches = monitor.resource_envelope.breaches(&monitor.metrics);
+	let node_colour = if envelope_breaches.is_empty() { Color::Blue } else { Color::LightMagenta };
 	text_items.push(
-		ListItem::new(vec![Line::from(node_text.clone())]).style(Style::default().fg(Color::Blue)),
+		ListItem::new(vec![Line::from(node_text.clone())]).style(Style::default().fg(node_colour)),
 	);
+	if monitor.resource_envelope.is_set() {
+		let envelope_text = if envelope_breaches.is_empty() {
+			format!("{:<13}: {}", "Envelope", monitor.resource_envelope.limits_text())
+		} else {
+			format!("{:<13}: EXCEEDED {}", "Envelope", envelope_breaches.join(", "))
+		};
+		text_items.push(
+			ListItem::new(vec![Line::from(envelope_text)]).style(Style::default().fg(node_colour)),
+		);
+	}
 
 	let system_text = format!(
 		"{:<13}: CPU {:8.2} MEM {:.0} / {:.0} MB {:.1}%",
@@ -595,6 +1265,14 @@ fn draw_node_storage(
 // 	bytes.get_appropriate_unit(false).format(fractional_digits)
 // }
 
+/// Name of an antnode record (data) type, as numbered in its QuotingMetrics
+fn record_type_name(data_type: u32) -> String {
+	match RECORD_TYPE_NAMES.get(data_type as usize) {
+		Some(name) => name.to_string(),
+		None => format!("Type {}", data_type),
+	}
+}
+
 // Return ratio from two u64
 fn ratio(numerator: u64, denomimator: u64) -> f64 {
 	let percent = numerator as f64 / denomimator as f64;
@@ -10,6 +10,7 @@ pub struct StatusMessage {
 
 	clear_at_time: Option<DateTime<chrono::Utc>>,
 	to_console: bool,
+	to_log: bool,
 }
 
 /// Send a status message to the console, or store it for display with a duration (e.g. by terminal GUI)
@@ -21,6 +22,7 @@ impl StatusMessage {
 			default_message: String::from(default_message),
 			clear_at_time: None,
 			to_console: true,
+			to_log: false,
 		}
 	}
 
@@ -31,8 +33,12 @@ impl StatusMessage {
 	pub fn disable_to_console(&mut self) {	self.reset(); self.to_console = false; }
 	pub fn enable_to_console(&mut self) {	self.to_console = true; }
 
+	/// Send messages to the log rather than the console until disable_to_console() (see --quiet)
+	pub fn log_instead_of_console(&mut self) {	self.to_console = false; self.to_log = true; }
+
 	pub fn message(&mut self, new_message: &String, new_duration: Option<Duration>) {
 		if self.to_console { eprintln!("{}", new_message); }
+		if self.to_log { log::info!("{}", new_message); }
 		self.current_message = Some(String::from(new_message));
 
 		let duration = if let Some(duration) = new_duration {
@@ -0,0 +1,119 @@
+///! Status bar shown on the bottom line of every view
+///!
+use std::collections::HashMap;
+
+use super::app::{DashState, DashViewMain, LogMonitor, WEB_PRICES};
+use super::node_groups::group_of_row_key;
+use super::ui_summary_table::{status_detail, SUMMARY_COLUMNS};
+
+use ratatui::{
+	layout::Rect,
+	style::{Color, Modifier, Style},
+	text::{Line, Span},
+	widgets::Paragraph,
+	Frame,
+};
+
+const SEPARATOR: &str = " │ ";
+
+pub fn view_name(main_view: &DashViewMain) -> &'static str {
+	match main_view {
+		DashViewMain::DashSummary => "Summary",
+		DashViewMain::DashNode => "Node",
+		DashViewMain::DashHelp => "Help",
+		DashViewMain::DashEarnings => "Earnings",
+		DashViewMain::DashFleet => "Fleet",
+		DashViewMain::DashDebug => "Debug",
+	}
+}
+
+/// The most useful keys in each view
+fn key_hints(main_view: &DashViewMain) -> &'static str {
+	match main_view {
+		DashViewMain::DashSummary => "←/→ sort column  space reverse  enter node  b split  / filter  ? help  q quit",
+		DashViewMain::DashNode => "←/→ node  i/o zoom  [/] pan  l logfile  v top errors  s summary  ? help  q quit",
+		DashViewMain::DashHelp => "enter back  q quit",
+		DashViewMain::DashEarnings => "x export CSV  X tax report  enter back  ? help  q quit",
+		DashViewMain::DashFleet => "s summary  n node  ? help  q quit",
+		DashViewMain::DashDebug => "s summary  q quit",
+	}
+}
+
+/// Text of each part of the status bar. A status message, when there is one, takes the place of the key hints,
+/// as does the full status of the node selected in the Summary, whose Status column may be cut short.
+pub fn status_bar_parts(
+	dash_state: &mut DashState,
+	monitors: &HashMap<String, LogMonitor>,
+	logfile_with_focus: &str,
+) -> Vec<String> {
+	let column = dash_state.summary_window_heading_selected;
+	let sort = format!(
+		"sort: {} {}",
+		SUMMARY_COLUMNS.get(column).map_or("-", |(_metric, heading, _format)| heading.as_str()),
+		if dash_state.logfile_names_sorted_ascending { '▲' } else { '▼' }
+	);
+	let timescale = format!("timescale: {}", dash_state.get_active_timescale_name().unwrap_or("-"));
+	let currency = if dash_state.ui_uses_currency && dash_state.currency_per_token.is_some() {
+		format!("{} {}", dash_state.currency_symbol, WEB_PRICES.lock().unwrap().currency_apiname)
+	} else {
+		String::from(dash_state.token_unit.name())
+	};
+
+	// get_status() clears a message which has expired
+	dash_state.vdash_status.get_status();
+	let selected_node = selected_logfile(dash_state, logfile_with_focus)
+		.filter(|_| dash_state.main_view == DashViewMain::DashSummary)
+		.and_then(|logfile| monitors.get(&logfile))
+		.filter(|monitor| monitor.is_node());
+	let status = match (&dash_state.vdash_status.current_message, selected_node) {
+		(Some(message), _) => message.clone(),
+		(None, Some(monitor)) => status_detail(monitor),
+		(None, None) => String::from(key_hints(&dash_state.main_view)),
+	};
+
+	let view = if dash_state.is_split_view() { "Summary + Node" } else { view_name(&dash_state.main_view) };
+	let mut parts = vec![String::from(view), sort, timescale, currency, status];
+	// Views show logfile names without the directory they share, so give the full path here
+	if let Some(logfile) = selected_logfile(dash_state, logfile_with_focus).filter(|_| !dash_state.logfile_prefix.is_empty()) {
+		parts.push(logfile);
+	}
+	parts
+}
+
+/// The logfile of the node selected in the Summary or shown in the Node view
+fn selected_logfile(dash_state: &DashState, logfile_with_focus: &str) -> Option<String> {
+	let logfile = match dash_state.main_view {
+		DashViewMain::DashSummary => dash_state
+			.summary_window_rows
+			.state
+			.selected()
+			.and_then(|selected| dash_state.logfile_names_sorted.get(selected))
+			.map(String::as_str)?,
+		DashViewMain::DashNode => logfile_with_focus,
+		_ => return None,
+	};
+	if logfile.is_empty() || group_of_row_key(logfile).is_some() {
+		return None;
+	}
+	Some(logfile.to_string())
+}
+
+pub fn draw_status_bar(
+	f: &mut Frame,
+	area: Rect,
+	dash_state: &mut DashState,
+	monitors: &HashMap<String, LogMonitor>,
+	logfile_with_focus: &str,
+) {
+	let bar_style = Style::default().fg(Color::Black).bg(Color::Gray);
+	let parts = status_bar_parts(dash_state, monitors, logfile_with_focus);
+
+	let mut spans = vec![Span::styled(format!(" {}", parts[0]), bar_style.add_modifier(Modifier::BOLD))];
+	for part in &parts[1..] {
+		spans.push(Span::styled(SEPARATOR, bar_style));
+		spans.push(Span::styled(part.clone(), bar_style));
+	}
+
+	let status_bar_widget = Paragraph::new(Line::from(spans)).style(bar_style);
+	f.render_widget(status_bar_widget, area);
+}
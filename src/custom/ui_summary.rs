@@ -3,16 +3,21 @@
 use std::collections::HashMap;
 
 use super::app::{DashState, LogMonitor, MmmStat, SUMMARY_WINDOW_NAME};
+use super::averages::{AverageMode, WeightedMean};
+use super::earnings_ledger::EarningsValue;
+use super::keymap::{key_name, Action};
 
 use super::opt::{get_app_name, get_app_version};
 use super::ui::{
-	monetary_string, monetary_string_ant, push_blank, push_metric, push_price, push_subheading,
-	ATTOS_PER_ANT,
+	attos_to_ant, count_string, currency_string, draw_sparkline, monetary_string, monetary_string_ant, push_blank,
+	push_metric, push_price, push_subheading,
 };
+use super::timelines::{BucketValue, MinMeanMax, Timeline};
 use super::web_requests::{BTC_TICKER, SAFE_TOKEN_TICKER};
 
 use ratatui::{
 	layout::{Constraint, Direction, Layout, Rect},
+	style::{Color, Style},
 	widgets::{Block, Borders, List, ListItem},
 	Frame,
 };
@@ -20,14 +25,20 @@ struct SummaryStats {
 	node_count: u32,
 	active_node_count: u32,
 
-	storage_cost: MmmStat,
+	storage_cost: MmmStat<u128>,
 	records: MmmStat,
-	earnings: MmmStat,
+	earnings: MmmStat<u128>,
 	puts: MmmStat,
 	gets: MmmStat,
 	errors: MmmStat,
 	connections: MmmStat,
 	ram: MmmStat,
+
+	// Weighted or recent averages, when chosen in place of the means above
+	storage_cost_average: WeightedMean,
+	connections_average: WeightedMean,
+
+	earnings_value: EarningsValue, // Earnings valued at time of receipt
 }
 
 impl SummaryStats {
@@ -47,6 +58,11 @@ impl SummaryStats {
 			errors: MmmStat::new(),
 			connections: MmmStat::new(),
 			ram: MmmStat::new(),
+
+			storage_cost_average: WeightedMean::default(),
+			connections_average: WeightedMean::default(),
+
+			earnings_value: EarningsValue::default(),
 		};
 
 		summary_stats.calculate_summary_stats(&dash_state, &monitors);
@@ -55,12 +71,18 @@ impl SummaryStats {
 
 	fn calculate_summary_stats(
 		&mut self,
-		_dash_state: &DashState,
+		dash_state: &DashState,
 		monitors: &HashMap<String, LogMonitor>,
 	) {
+		let currency_apiname = super::app::WEB_PRICES.lock().unwrap().currency_apiname.clone();
+
 		for entry in monitors.into_iter() {
 			let (_logfile, monitor) = entry;
-			if monitor.is_node() {
+			let filtered_out = dash_state
+				.node_filter
+				.as_ref()
+				.is_some_and(|node_filter| !node_filter.matches(monitor));
+			if monitor.is_node() && !filtered_out {
 				self.node_count += 1;
 				self.active_node_count += if monitor.metrics.is_node_active() {
 					1
@@ -84,6 +106,23 @@ impl SummaryStats {
 				self
 					.ram
 					.add_sample(u64::from(monitor.metrics.memory_used_mb.most_recent));
+				let metrics = &monitor.metrics;
+				match dash_state.average_mode {
+					AverageMode::Mean => {}
+					AverageMode::Weighted => {
+						let records = metrics.records_stored as f64;
+						self.storage_cost_average.add(metrics.storage_cost.most_recent as f64, records);
+						self.connections_average.add(metrics.peers_connected.most_recent as f64, records);
+					}
+					AverageMode::Recent => {
+						self.storage_cost_average.add(metrics.storage_cost_recent.value, 1.0);
+						self.connections_average.add(metrics.peers_connected_recent.value, 1.0);
+					}
+				}
+				monitor
+					.metrics
+					.earnings_ledger
+					.add_value_then(&currency_apiname, &mut self.earnings_value);
 			}
 		}
 	}
@@ -91,32 +130,84 @@ impl SummaryStats {
 
 pub fn draw_summary_dash(
 	f: &mut Frame,
+	area: Rect,
 	dash_state: &mut DashState,
 	monitors: &mut HashMap<String, LogMonitor>,
 ) {
+	let duplicate_peer_ids = crate::custom::ui_summary_table::duplicate_peer_ids(monitors);
+	let warning_height = if duplicate_peer_ids.is_empty() {
+		0
+	} else {
+		duplicate_peer_ids.len().min(MAX_WARNING_LINES) as u16 + 2
+	};
+
 	let constraints = [
-		Constraint::Length(13), // Summary statistics for all nodes
-		Constraint::Min(0),     // Header above line of details for each node
+		Constraint::Length(13),             // Summary statistics for all nodes
+		Constraint::Length(warning_height), // Warning of PeerIds shared by more than one logfile
+		Constraint::Min(0),                 // Header above line of details for each node
 	];
 
 	let chunks = Layout::default()
 		.direction(Direction::Vertical)
 		.constraints(constraints.as_ref())
 		.margin(1)
-		.split(f.size());
+		.split(area);
 
 	let summary_list_widget = Block::default().borders(Borders::ALL).title(format!(
-		"{}  ({} v{}:  {})",
-		String::from(SUMMARY_WINDOW_NAME),
+		"{}  ({} v{})",
+		summary_title(dash_state),
 		get_app_name(),
-		get_app_version(),
-		&dash_state.vdash_status.get_status()
+		get_app_version()
 	));
 
-	f.render_widget(summary_list_widget, f.size());
+	f.render_widget(summary_list_widget, area);
 
 	draw_summary_stats_window(f, chunks[0], dash_state, monitors);
-	crate::custom::ui_summary_table::draw_summary_table_window(f, chunks[1], dash_state, monitors);
+	if !duplicate_peer_ids.is_empty() {
+		draw_duplicate_peer_ids_warning(f, chunks[1], &duplicate_peer_ids);
+	}
+	crate::custom::ui_summary_table::draw_summary_table_window(f, chunks[2], dash_state, monitors);
+}
+
+fn summary_title(dash_state: &DashState) -> String {
+	let mut title = String::from(SUMMARY_WINDOW_NAME);
+	if let Some(node_filter) = &dash_state.node_filter {
+		title += &format!(" [filter: {}", node_filter.expression);
+		if let Some(key) = dash_state.keymap.keys_for(Action::ClearFilter).first() {
+			title += &format!(", {} clears", key_name(*key));
+		}
+		title += "]";
+	}
+	if let Some(replay) = &dash_state.replay {
+		title += &format!(" [{}]", replay.status_text());
+	}
+	title
+}
+
+const MAX_WARNING_LINES: usize = 4;
+
+fn draw_duplicate_peer_ids_warning(f: &mut Frame, area: Rect, duplicate_peer_ids: &[(String, Vec<String>)]) {
+	let mut items: Vec<ListItem> = duplicate_peer_ids
+		.iter()
+		.take(MAX_WARNING_LINES)
+		.map(|(peer_id, logfiles)| ListItem::new(format!("PeerId {} is in: {}", peer_id, logfiles.join(", "))))
+		.collect();
+	if duplicate_peer_ids.len() > MAX_WARNING_LINES {
+		items.pop();
+		items.push(ListItem::new(format!(
+			"...and {} more PeerIds",
+			duplicate_peer_ids.len() - MAX_WARNING_LINES + 1
+		)));
+	}
+
+	let warning_widget = List::new(items)
+		.block(
+			Block::default()
+				.borders(Borders::ALL)
+				.title("WARNING: logfiles share a PeerId so their metrics are counted more than once"),
+		)
+		.style(Style::default().fg(Color::LightRed));
+	f.render_widget(warning_widget, area);
 }
 
 fn draw_summary_stats_window(
@@ -127,7 +218,8 @@ fn draw_summary_stats_window(
 ) {
 	let constraints = [
 		Constraint::Length(81), // Summary Statistics Panel (left)
-		Constraint::Length(15), // Live Prices Panel (right)
+		Constraint::Length(15), // Live Prices Panel
+		Constraint::Min(0),     // Nodes breakdown of one timeline (right)
 	];
 
 	let chunks = Layout::default()
@@ -136,7 +228,11 @@ fn draw_summary_stats_window(
 		.split(area);
 
 	draw_summary_stats(f, chunks[0], dash_state, monitors);
-	draw_live_prices(f, chunks[1], dash_state, monitors);
+	// A minimal build has no web prices to show
+	if !cfg!(feature = "minimal") {
+		draw_live_prices(f, chunks[1], dash_state, monitors);
+	}
+	super::ui_summary_breakdown::draw_node_breakdown(f, chunks[2], dash_state, monitors);
 }
 
 fn draw_summary_stats(
@@ -155,7 +251,7 @@ fn draw_summary_stats(
 	let units_text = if dash_state.ui_uses_currency {
 		""
 	} else {
-		crate::custom::app_timelines::EARNINGS_UNITS_TEXT
+		dash_state.token_unit.name()
 	};
 
 	push_subheading(
@@ -168,29 +264,47 @@ fn draw_summary_stats(
 	let earnings_text = format!(
 		"{:>14} {:<6}{:>12}  {:>12}  {:>12}",
 		monetary_string_ant(dash_state, ss.earnings.total),
-		"ANT", //was ' {:<6}'
+		dash_state.token_unit.token_name(),
 		monetary_string_ant(dash_state, ss.earnings.min),
 		monetary_string_ant(dash_state, ss.earnings.mean),
 		monetary_string_ant(dash_state, ss.earnings.max)
 	);
-	let records_text = format!(
-		"{:>14} {:<6}{:>12}  {:>12}  {:>12}",
-		ss.records.total, "", ss.records.min, ss.records.mean, ss.records.max
-	);
-	let puts_text = format!(
-		"{:>14} {:<6}{:>12}  {:>12}  {:>12}",
-		ss.puts.total, "", ss.puts.min, ss.puts.mean, ss.puts.max
-	);
-	let gets_text = format!(
-		"{:>14} {:<6}{:>12}  {:>12}  {:>12}",
-		ss.gets.total, "", ss.gets.min, ss.gets.mean, ss.gets.max
-	);
-	let errors_text = format!(
-		"{:>14} {:<6}{:>12}  {:>12}  {:>12}",
-		ss.errors.total, "", ss.errors.min, ss.errors.mean, ss.errors.max
-	);
+	let count_text = |stat: &MmmStat| {
+		format!(
+			"{:>14} {:<6}{:>12}  {:>12}  {:>12}",
+			count_string(dash_state, stat.total),
+			"",
+			count_string(dash_state, stat.min),
+			count_string(dash_state, stat.mean),
+			count_string(dash_state, stat.max)
+		)
+	};
+	let records_text = count_text(&ss.records);
+	let puts_text = count_text(&ss.puts);
+	let gets_text = count_text(&ss.gets);
+	let errors_text = count_text(&ss.errors);
 
 	push_metric(&mut items, &"Earnings".to_string(), &earnings_text);
+	if dash_state.ui_uses_currency && dash_state.currency_per_token.is_some() {
+		// Earnings above use the latest price, here they are valued when received
+		let unpriced_text = if ss.earnings_value.attos_unvalued > 0 {
+			format!(
+				"(now), not priced: {:.9} {}",
+				attos_to_ant(ss.earnings_value.attos_unvalued),
+				dash_state.token_unit.token_name()
+			)
+		} else {
+			String::from("(now)")
+		};
+		let value_then_text = format!(
+			"{:>14} {:<6}{:>12} {}",
+			currency_string(dash_state, ss.earnings_value.value_then),
+			"",
+			monetary_string_ant(dash_state, ss.earnings_value.attos_valued),
+			unpriced_text
+		);
+		push_metric(&mut items, &"Value Then".to_string(), &value_then_text);
+	}
 	push_metric(&mut items, &"Records".to_string(), &records_text);
 	push_metric(&mut items, &"PUTS".to_string(), &puts_text);
 	push_metric(&mut items, &"GETS".to_string(), &gets_text);
@@ -199,22 +313,31 @@ fn draw_summary_stats(
 	push_blank(&mut items);
 	push_subheading(
 		&mut items,
-		&String::from(
-			"                                            min          mean           max         ",
-		),
+		&format!("{:>47}{:>14}{:>14}{:9}", "min", dash_state.average_mode.heading(), "max", ""),
 	);
+	let (storage_cost_average, connections_average) = match dash_state.average_mode {
+		AverageMode::Mean => (Some(ss.storage_cost.mean), Some(u128::from(ss.connections.mean))),
+		_ => (
+			ss.storage_cost_average.mean().map(|mean| mean.round() as u128),
+			ss.connections_average.mean().map(|mean| mean.round() as u128),
+		),
+	};
 	let storage_cost_text = format!(
 		"{:>14} {:<6}{:>12}  {:>12}  {:>12} {}",
 		"-",
 		"",
 		monetary_string(dash_state, ss.storage_cost.min),
-		monetary_string(dash_state, ss.storage_cost.mean),
+		storage_cost_average.map_or(String::from("-"), |average| monetary_string(dash_state, average)),
 		monetary_string(dash_state, ss.storage_cost.max),
 		units_text
 	);
 	let connections_text = format!(
 		"{:>14} {:<6}{:>12}  {:>12}  {:>12}",
-		"-", "", ss.connections.min, ss.connections.mean, ss.connections.max
+		"-",
+		"",
+		ss.connections.min,
+		connections_average.map_or(String::from("-"), |average| average.to_string()),
+		ss.connections.max
 	);
 	let ram_text = format!(
 		"{:>14} {:<6}{:>12}  {:>12}  {:>12} {}",
@@ -232,7 +355,7 @@ fn draw_summary_stats(
 fn draw_live_prices(
 	f: &mut Frame,
 	area: Rect,
-	_dash_state: &mut DashState,
+	dash_state: &mut DashState,
 	_monitors: &mut HashMap<String, LogMonitor>,
 ) {
 	let mut items = Vec::<ListItem>::new();
@@ -280,5 +403,32 @@ fn draw_live_prices(
 			.split(area);
 
 		f.render_widget(items_widget, chunks[0]);
+
+		if let Some(price_history) = prices.price_history() {
+			draw_price_history(f, chunks[1], dash_state, price_history);
+		}
 	}
 }
+
+// Sparkline of the token price, offset by the lowest price so that movement is visible
+fn draw_price_history(f: &mut Frame, area: Rect, dash_state: &DashState, price_history: &Timeline) {
+	let Some(timescale_name) = dash_state.get_active_timescale_name() else {
+		return;
+	};
+	let Some(buckets) = price_history.get_buckets(timescale_name, Some(&MinMeanMax::Mean)) else {
+		return;
+	};
+
+	let min_price = buckets
+		.iter()
+		.filter(|price| **price > 0)
+		.min()
+		.copied()
+		.unwrap_or(0);
+	let price_movement: Vec<BucketValue> = buckets
+		.iter()
+		.map(|price| if *price > 0 { price - min_price + 1 } else { 0 })
+		.collect();
+
+	draw_sparkline(f, area, &price_movement, "History", price_history.colour);
+}
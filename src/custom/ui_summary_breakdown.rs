@@ -0,0 +1,110 @@
+///! Summary panel with one timeline per node for a chosen metric
+///!
+///! Each node gets a one row sparkline of the same timeline (GETS unless
+///! another is chosen with 'a'), all on a common vertical scale, so it is
+///! easy to see whether every node is quiet or just one of them.
+use std::collections::HashMap;
+
+use super::app::{DashState, LogMonitor};
+use super::node_groups::group_of_row_key;
+use super::timelines::{saturating_sum, BucketValue};
+use super::ui::widgets::sparkline::Sparkline2;
+
+use ratatui::{
+	layout::Rect,
+	style::{Color, Style},
+	widgets::{Block, Borders, Paragraph},
+	Frame,
+};
+
+/// Panels narrower than this are not drawn
+const MIN_BREAKDOWN_WIDTH: u16 = 16;
+
+struct NodeSeries {
+	label: String,
+	buckets: Vec<BucketValue>,
+	total: BucketValue,
+}
+
+pub fn draw_node_breakdown(
+	f: &mut Frame,
+	area: Rect,
+	dash_state: &DashState,
+	monitors: &mut HashMap<String, LogMonitor>,
+) {
+	if area.width < MIN_BREAKDOWN_WIDTH || area.height < 3 {
+		return;
+	}
+	let Some(timescale_name) = dash_state.get_active_timescale_name() else {
+		return;
+	};
+
+	let inner_width = area.width.saturating_sub(2) as usize;
+	let mut timeline_name = dash_state.breakdown_timeline.clone();
+	let mut colour = Color::Green;
+	let mut series = Vec::<NodeSeries>::new();
+	for logfile in dash_state.logfile_names_sorted.iter().filter(|logfile| group_of_row_key(logfile).is_none()) {
+		let Some(monitor) = monitors.get_mut(logfile) else {
+			continue;
+		};
+		if !monitor.is_node() {
+			continue;
+		}
+		let label = format!("{}", monitor.index + 1);
+		let Some(timeline) = monitor.metrics.app_timelines.get_timeline_by_key(&dash_state.breakdown_timeline) else {
+			continue;
+		};
+		let Some(buckets) = timeline.get_buckets(timescale_name, Some(dash_state.mmm_ui_mode())) else {
+			continue;
+		};
+		let visible = &buckets[buckets.len().saturating_sub(inner_width)..];
+		timeline_name = timeline.name.clone();
+		colour = timeline.colour;
+		series.push(NodeSeries {
+			label,
+			buckets: visible.to_vec(),
+			total: saturating_sum(visible),
+		});
+	}
+
+	// Busiest first, so the nodes doing the most are shown when not all fit
+	series.sort_by(|a, b| b.total.cmp(&a.total));
+	let rows = area.height.saturating_sub(2) as usize;
+	let node_count = series.len();
+	series.truncate(rows);
+
+	let title = format!("{} by node ({} of {}, 'a' next)", timeline_name, series.len(), node_count);
+	let block = Block::default().borders(Borders::ALL).title(title);
+	let inner = block.inner(area);
+	f.render_widget(block, area);
+
+	let label_width = series.iter().map(|node| node.label.len()).max().unwrap_or(0) as u16 + 1;
+	if inner.width <= label_width {
+		return;
+	}
+	// A common scale makes the nodes comparable
+	let max = series
+		.iter()
+		.filter_map(|node| node.buckets.iter().max().copied())
+		.max()
+		.unwrap_or(0);
+
+	for (row, node) in series.iter().enumerate() {
+		let y = inner.y + row as u16;
+		let label_area = Rect::new(inner.x, y, label_width, 1);
+		f.render_widget(
+			Paragraph::new(format!("{:>width$}", node.label, width = label_width as usize - 1))
+				.style(Style::default().fg(Color::Gray)),
+			label_area,
+		);
+
+		let spark_area = Rect::new(inner.x + label_width, y, inner.width - label_width, 1);
+		let data = &node.buckets[node.buckets.len().saturating_sub(spark_area.width as usize)..];
+		let sparkline = Sparkline2::default()
+			.data(data)
+			.max(max)
+			.braille(dash_state.sparkline_braille)
+			.style(Style::default().fg(colour));
+		f.render_widget(sparkline, spark_area);
+	}
+}
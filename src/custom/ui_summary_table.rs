@@ -1,13 +1,27 @@
 use std::collections::HashMap;
+use std::sync::LazyLock;
 
-use super::app::{DashState, LogMonitor};
-use super::ui::{monetary_string, monetary_string_ant};
+use chrono::{Duration, Local};
+
+use super::app::{DashState, LogMonitor, OPT};
+use super::app_timelines::timeline_specs;
+use super::custom_metrics::CUSTOM_METRICS;
+use super::efficiency::{attos_per_gb, bytes_stored};
+use super::generic_logs::GenericMetrics;
+use super::node_groups::group_of_row_key;
+use super::preferences::SortKey;
+use super::restart_history::uptime_percent_text;
+use super::session::{session_counts, SessionCounts};
+use super::timelines::{get_duration_text, saturating_sum, BucketValue};
+use super::ui::{count_string, monetary_string, monetary_string_ant};
+
+use super::replay::dashboard_time;
 
 use ratatui::{
 	layout::{Constraint, Direction, Layout, Rect},
 	style::{Color, Modifier, Style},
 	text::{Line, Span},
-	widgets::{Block, List, ListItem},
+	widgets::{Block, List, ListItem, ListState, Paragraph},
 	Frame,
 };
 
@@ -17,17 +31,24 @@ use strfmt::{strfmt, strfmt_builder};
 pub enum NodeMetric {
 	Index,
 	StoragePayments,
+	EarningsPerGb, // Efficiency of a node's earnings (see efficiency.rs)
 	StorageCost,
 	Records,
 	Puts,
 	Gets,
+	Trend, // Recent buckets of the timeline chosen with 'a'
 	Errors,
 	Peers,
 	Memory,
+	Version,
+	Uptime,
+	Availability,
+	PeerId,
 	Status,
+	Custom(usize), // Index in CUSTOM_METRICS
 }
 
-pub const COLUMN_HEADERS: [(NodeMetric, &str, &str); 10] = [
+pub const COLUMN_HEADERS: [(NodeMetric, &str, &str); 16] = [
 	//  (node_metric,                   key/heading, format_string)
 	(NodeMetric::Index, "Node", "{index:>4} "),
 	(
@@ -35,104 +56,649 @@ pub const COLUMN_HEADERS: [(NodeMetric, &str, &str); 10] = [
 		"Earnings",
 		"{storage_payments:>13} ",
 	),
+	(NodeMetric::EarningsPerGb, "Earn/GB", "{earnings_per_gb:>13} "),
 	(NodeMetric::StorageCost, "StoreCost", "{storage_cost:>13} "),
 	(NodeMetric::Records, "Records", "{records_stored:>11} "),
 	(NodeMetric::Puts, "PUTS", "{puts:>11} "),
 	(NodeMetric::Gets, "GETS", "{gets:>11} "),
+	(NodeMetric::Trend, "Trend", "{trend:>12} "),
 	(NodeMetric::Errors, "Errors", "{errors:>11} "),
 	(NodeMetric::Peers, "Peers", "{connections:>7} "),
 	(NodeMetric::Memory, "MB RAM", "{memory:>7} "),
-	(NodeMetric::Status, "Status", "  {status:<500} "),
+	(NodeMetric::Version, "Version", "{version:>10} "),
+	(NodeMetric::Uptime, "Uptime", "{uptime:>9} "),
+	(NodeMetric::Availability, "Avail", "{availability:>11} "),
+	(NodeMetric::PeerId, "PeerId", "{peer_id:>14} "),
+	(NodeMetric::Status, "Status", "  {status}"), // Last, so cut to fit (see truncate_line())
 ];
 
+/// COLUMN_HEADERS with a column before Status for each custom metric (see --custom-metric)
+pub static SUMMARY_COLUMNS: LazyLock<Vec<(NodeMetric, String, String)>> = LazyLock::new(|| {
+	let mut columns: Vec<(NodeMetric, String, String)> = COLUMN_HEADERS
+		.iter()
+		.map(|(metric, heading, format_string)| (*metric, heading.to_string(), format_string.to_string()))
+		.collect();
+	let status_column = columns.len() - 1;
+	for (index, metric) in CUSTOM_METRICS.iter().enumerate() {
+		let width = metric.name.chars().count().max(8);
+		columns.insert(
+			status_column + index,
+			(NodeMetric::Custom(index), metric.name.clone(), format!("{{custom:>{}}} ", width)),
+		);
+	}
+	columns
+});
+
+/// Number of timeline buckets shown in the Trend column
+const TREND_BUCKETS: usize = 12;
+
+/// Levels of the Trend column sparkline, lowest first
+const TREND_BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// The most recent buckets of the node's timeline chosen for the Summary (see 'a'),
+/// over the active timescale
+fn trend_buckets(dash_state: &DashState, monitor: &mut LogMonitor) -> Vec<BucketValue> {
+	let Some(timescale_name) = dash_state.get_active_timescale_name() else {
+		return Vec::new();
+	};
+	let Some(timeline) = monitor.metrics.app_timelines.get_timeline_by_key(&dash_state.breakdown_timeline) else {
+		return Vec::new();
+	};
+	let Some(buckets) = timeline.get_buckets(timescale_name, Some(dash_state.mmm_ui_mode())) else {
+		return Vec::new();
+	};
+	buckets[buckets.len().saturating_sub(TREND_BUCKETS)..].to_vec()
+}
+
+/// A one line sparkline of buckets, scaled to the largest so that each row shows its own trend
+fn trend_text(buckets: &[BucketValue]) -> String {
+	let max = buckets.iter().max().copied().unwrap_or(0);
+	buckets
+		.iter()
+		.map(|value| match max {
+			0 => TREND_BARS[0],
+			max => TREND_BARS[(*value * (TREND_BARS.len() - 1) as BucketValue).div_ceil(max) as usize],
+		})
+		.collect()
+}
+
+/// Name of the timeline shown in the Trend column, for its heading
+fn trend_heading(dash_state: &DashState) -> String {
+	timeline_specs()
+		.into_iter()
+		.find(|(key, ..)| *key == dash_state.breakdown_timeline)
+		.map_or(String::from("Trend"), |(_key, name, ..)| name)
+}
+
+/// The value of a custom metric shown in the Summary
+fn custom_metric_value(monitor: &LogMonitor, index: usize) -> u64 {
+	CUSTOM_METRICS
+		.get(index)
+		.map_or(0, |metric| metric.summary_value(monitor.metrics.custom_metrics.get(&metric.key)))
+}
+
 pub fn sort_nodes_by_column(
 	dash_state: &mut DashState,
 	monitors: &mut HashMap<String, LogMonitor>,
 ) {
 	use std::cmp::Ordering;
 
-	let sort_by = COLUMN_HEADERS[dash_state.summary_window_heading_selected].0;
+	// The selected column, then any further sort keys
+	let primary_column = dash_state.summary_window_heading_selected;
+	let mut sort_keys = vec![(primary_column, dash_state.logfile_names_sorted_ascending)];
+	sort_keys.extend(
+		dash_state
+			.summary_sort_then_by
+			.iter()
+			.filter(|(column, _ascending)| *column != primary_column)
+			.copied(),
+	);
 
-	// let logfile_with_focus = dash_state.logfile
 	dash_state.logfile_names_sorted.sort_by(|a, b| {
-		let mut ordering = Ordering::Equal;
-		if let Some(a) = monitors.get(a) {
-			if let Some(b) = monitors.get(b) {
-				ordering = match sort_by {
-					NodeMetric::Index => a.index.cmp(&b.index),
-					NodeMetric::StoragePayments => a
-						.metrics
-						.attos_earned
-						.total
-						.cmp(&b.metrics.attos_earned.total),
-					NodeMetric::StorageCost => a
-						.metrics
-						.storage_cost
-						.most_recent
-						.cmp(&b.metrics.storage_cost.most_recent),
-					NodeMetric::Records => a.metrics.records_stored.cmp(&b.metrics.records_stored),
-					NodeMetric::Puts => a
-						.metrics
-						.activity_puts
-						.total
-						.cmp(&b.metrics.activity_puts.total),
-					NodeMetric::Gets => a
-						.metrics
-						.activity_gets
-						.total
-						.cmp(&b.metrics.activity_gets.total),
-					NodeMetric::Errors => a
-						.metrics
-						.activity_errors
-						.total
-						.cmp(&b.metrics.activity_errors.total),
-					NodeMetric::Peers => a
-						.metrics
-						.peers_connected
-						.most_recent
-						.cmp(&b.metrics.peers_connected.most_recent),
-					NodeMetric::Memory => a
-						.metrics
-						.memory_used_mb
-						.most_recent
-						.cmp(&b.metrics.memory_used_mb.most_recent),
-					NodeMetric::Status => a
-						.metrics
-						.node_status_string
-						.cmp(&b.metrics.node_status_string),
-				}
-			}
+		let (Some(a), Some(b)) = (monitors.get(a), monitors.get(b)) else {
+			return Ordering::Equal;
 		};
-		if dash_state.logfile_names_sorted_ascending {
-			ordering
-		} else {
-			ordering.reverse()
+		for (column, ascending) in sort_keys.iter() {
+			let ordering = compare_by_metric(SUMMARY_COLUMNS[*column].0, a, b, dash_state.session_delta);
+			if ordering != Ordering::Equal {
+				return if *ascending { ordering } else { ordering.reverse() };
+			}
 		}
+		Ordering::Equal
 	});
 }
 
+fn compare_by_metric(sort_by: NodeMetric, a: &LogMonitor, b: &LogMonitor, session_delta: bool) -> std::cmp::Ordering {
+	use std::cmp::Ordering;
+
+	let (a_counts, b_counts) = (shown_counts(session_delta, a), shown_counts(session_delta, b));
+	match sort_by {
+		NodeMetric::Index => a.index.cmp(&b.index),
+		NodeMetric::StoragePayments => a_counts.attos_earned.cmp(&b_counts.attos_earned),
+		NodeMetric::EarningsPerGb => earnings_per_gb(a).cmp(&earnings_per_gb(b)),
+		NodeMetric::StorageCost => a
+			.metrics
+			.storage_cost
+			.most_recent
+			.cmp(&b.metrics.storage_cost.most_recent),
+		NodeMetric::Records => a.metrics.records_stored.cmp(&b.metrics.records_stored),
+		NodeMetric::Puts => a_counts.puts.cmp(&b_counts.puts),
+		NodeMetric::Gets => a_counts.gets.cmp(&b_counts.gets),
+		NodeMetric::Trend => saturating_sum(&a.summary_trend).cmp(&saturating_sum(&b.summary_trend)),
+		NodeMetric::Errors => a_counts.errors.cmp(&b_counts.errors),
+		NodeMetric::Peers => a
+			.metrics
+			.peers_connected
+			.most_recent
+			.cmp(&b.metrics.peers_connected.most_recent),
+		NodeMetric::Memory => a
+			.metrics
+			.memory_used_mb
+			.most_recent
+			.cmp(&b.metrics.memory_used_mb.most_recent),
+		NodeMetric::Version => version_key(&a.metrics.running_version)
+			.cmp(&version_key(&b.metrics.running_version)),
+		NodeMetric::Uptime => node_uptime(a).cmp(&node_uptime(b)),
+		NodeMetric::Availability => uptime_percent(a)
+			.partial_cmp(&uptime_percent(b))
+			.unwrap_or(Ordering::Equal),
+		NodeMetric::PeerId => a.metrics.node_peer_id.cmp(&b.metrics.node_peer_id),
+		NodeMetric::Status => a
+			.metrics
+			.node_status_string
+			.cmp(&b.metrics.node_status_string),
+		NodeMetric::Custom(index) => custom_metric_value(a, index).cmp(&custom_metric_value(b, index)),
+	}
+}
+
+/// The Summary sort order to save as a preference
+pub fn sort_preference(dash_state: &DashState) -> Vec<SortKey> {
+	let primary_column = dash_state.summary_window_heading_selected;
+	let mut sort_keys = vec![(primary_column, dash_state.logfile_names_sorted_ascending)];
+	sort_keys.extend(dash_state.summary_sort_then_by.iter().filter(|(column, _)| *column != primary_column));
+	sort_keys
+		.into_iter()
+		.map(|(column, ascending)| SortKey {
+			column: SUMMARY_COLUMNS[column].1.clone(),
+			ascending,
+		})
+		.collect()
+}
+
+/// Restore a Summary sort order saved as a preference, ignoring columns which no longer exist
+pub fn apply_sort_preference(dash_state: &mut DashState, sort_keys: &[SortKey]) {
+	let mut columns = sort_keys.iter().filter_map(|sort_key| {
+		SUMMARY_COLUMNS
+			.iter()
+			.position(|(_metric, heading, _format)| *heading == sort_key.column)
+			.map(|column| (column, sort_key.ascending))
+	});
+	if let Some((column, ascending)) = columns.next() {
+		dash_state.summary_window_heading_selected = column;
+		dash_state.logfile_names_sorted_ascending = ascending;
+		dash_state.summary_sort_then_by = columns.collect();
+	}
+}
+
+/// Arrow for the direction of a sort key: solid for the selected column, outline for
+/// further sort keys
+fn sort_indicator(dash_state: &DashState, column: usize) -> Option<char> {
+	if column == dash_state.summary_window_heading_selected {
+		return Some(if dash_state.logfile_names_sorted_ascending { '▲' } else { '▼' });
+	}
+	dash_state
+		.summary_sort_then_by
+		.iter()
+		.find(|(then_by, _ascending)| *then_by == column)
+		.map(|(_column, ascending)| if *ascending { '△' } else { '▽' })
+}
+
+/// Numeric parts of a version (e.g. "v0.112.6" gives [0, 112, 6]) for sorting
+fn version_key(version: &Option<String>) -> Vec<u64> {
+	version.as_ref().map_or(Vec::new(), |version| {
+		version
+			.trim_start_matches('v')
+			.split(|c: char| !c.is_ascii_digit())
+			.filter_map(|part| part.parse::<u64>().ok())
+			.collect()
+	})
+}
+
+/// The version nodes are expected to run: --latest-version if given, otherwise
+/// the version run by most of the monitored nodes
+pub fn expected_node_version(monitors: &HashMap<String, LogMonitor>) -> Option<String> {
+	if let Some(latest_version) = &OPT.lock().unwrap().latest_version {
+		return Some(latest_version.trim_start_matches('v').to_string());
+	}
+
+	let mut version_counts = HashMap::<String, usize>::new();
+	for monitor in monitors.values() {
+		if let Some(version) = &monitor.metrics.running_version {
+			*version_counts
+				.entry(version.trim_start_matches('v').to_string())
+				.or_insert(0) += 1;
+		}
+	}
+	version_counts
+		.into_iter()
+		.max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(&a.0)))
+		.map(|(version, _count)| version)
+}
+
+/// True if the node is running a version other than expected_version
+pub fn is_outdated_version(monitor: &LogMonitor, expected_version: &Option<String>) -> bool {
+	match (&monitor.metrics.running_version, expected_version) {
+		(Some(version), Some(expected_version)) => version.trim_start_matches('v') != expected_version,
+		_ => false,
+	}
+}
+
+/// PeerIds reported by more than one monitor with their logfiles, sorted by PeerId.
+/// This happens if a logfile is copied or matched by overlapping globs, and means
+/// the node's metrics are counted more than once.
+pub fn duplicate_peer_ids(monitors: &HashMap<String, LogMonitor>) -> Vec<(String, Vec<String>)> {
+	let mut logfiles_by_peer_id = HashMap::<&str, Vec<String>>::new();
+	for (logfile, monitor) in monitors.iter() {
+		if let Some(peer_id) = &monitor.metrics.node_peer_id {
+			logfiles_by_peer_id
+				.entry(peer_id.as_str())
+				.or_default()
+				.push(logfile.clone());
+		}
+	}
+
+	let mut duplicates: Vec<(String, Vec<String>)> = logfiles_by_peer_id
+		.into_iter()
+		.filter(|(_peer_id, logfiles)| logfiles.len() > 1)
+		.map(|(peer_id, mut logfiles)| {
+			logfiles.sort();
+			(peer_id.to_string(), logfiles)
+		})
+		.collect();
+	duplicates.sort();
+	duplicates
+}
+
+/// Start and end of a PeerId (e.g. "12D3Ko…x8pQzV"), enough to tell nodes apart
+fn truncated_peer_id(peer_id: &Option<String>) -> String {
+	match peer_id {
+		Some(peer_id) if peer_id.chars().count() > 13 => {
+			let chars: Vec<char> = peer_id.chars().collect();
+			let start: String = chars[..6].iter().collect();
+			let end: String = chars[chars.len() - 6..].iter().collect();
+			format!("{}…{}", start, end)
+		}
+		Some(peer_id) => peer_id.clone(),
+		None => String::from("-"),
+	}
+}
+
+fn uptime_percent(monitor: &LogMonitor) -> Option<f64> {
+	monitor
+		.metrics
+		.restart_history
+		.uptime_percent(&dashboard_time(), monitor.metrics.node_inactive)
+}
+
+/// Time since the node last started, or None if it is not running
+fn node_uptime(monitor: &LogMonitor) -> Option<Duration> {
+	if monitor.metrics.node_inactive {
+		return None;
+	}
+	monitor.metrics.node_started.map(|node_started| dashboard_time() - node_started)
+}
+
+/// Uptime percentage followed by the number of restarts
+fn availability_text(monitor: &LogMonitor) -> String {
+	format!(
+		"{} x{}",
+		uptime_percent_text(uptime_percent(monitor)),
+		monitor.metrics.restart_history.restart_count()
+	)
+}
+
+/// The counts shown in the Earnings, PUTS, GETS and Errors columns: in total, or since the
+/// session started when showing session deltas (see 'd')
+fn shown_counts(session_delta: bool, monitor: &LogMonitor) -> SessionCounts {
+	if session_delta {
+		session_counts(monitor)
+	} else {
+		SessionCounts::of(&monitor.metrics)
+	}
+}
+
 pub fn format_table_row(dash_state: &DashState, monitor: &mut LogMonitor) -> String {
-	let mut row_text = String::from("");
+	if let Some(generic) = &monitor.generic {
+		return format_generic_row(monitor.index, generic);
+	}
+	let counts = shown_counts(dash_state.session_delta, monitor);
+	let mut cells = Vec::<String>::new();
 
-	for i in 0..COLUMN_HEADERS.len() {
-		let (metric, _heading, format_string) = &COLUMN_HEADERS[i];
-		row_text += &match metric {
+	for (metric, _heading, format_string) in SUMMARY_COLUMNS.iter() {
+		cells.push(match metric {
             NodeMetric::Index =>            { strfmt!(format_string, index => monitor.index + 1).unwrap() },
-            NodeMetric::StoragePayments =>  { strfmt!(format_string, storage_payments  => monetary_string_ant(dash_state, monitor.metrics.attos_earned.total)).unwrap() },
+            NodeMetric::StoragePayments =>  { strfmt!(format_string, storage_payments  => monetary_string_ant(dash_state, counts.attos_earned)).unwrap() },
+            NodeMetric::EarningsPerGb =>    { strfmt!(format_string, earnings_per_gb => earnings_per_gb(monitor).map_or(String::from("-"), |attos| monetary_string_ant(dash_state, attos))).unwrap() },
             NodeMetric::StorageCost =>      { strfmt!(format_string, storage_cost => monetary_string(dash_state, monitor.metrics.storage_cost.most_recent)).unwrap() },
-            NodeMetric::Records =>          { strfmt!(format_string, records_stored => monitor.metrics.records_stored).unwrap() },
-            NodeMetric::Puts =>             { strfmt!(format_string, puts => monitor.metrics.activity_puts.total).unwrap() },
-            NodeMetric::Gets =>             { strfmt!(format_string, gets => monitor.metrics.activity_gets.total).unwrap() },
-            NodeMetric::Errors =>           { strfmt!(format_string, errors => monitor.metrics.activity_errors.total).unwrap() },
+            NodeMetric::Records =>          { strfmt!(format_string, records_stored => count_string(dash_state, monitor.metrics.records_stored)).unwrap() },
+            NodeMetric::Puts =>             { strfmt!(format_string, puts => count_string(dash_state, counts.puts)).unwrap() },
+            NodeMetric::Gets =>             { strfmt!(format_string, gets => count_string(dash_state, counts.gets)).unwrap() },
+            NodeMetric::Trend =>            {
+                monitor.summary_trend = trend_buckets(dash_state, monitor);
+                strfmt!(format_string, trend => trend_text(&monitor.summary_trend)).unwrap()
+            },
+            NodeMetric::Errors =>           { strfmt!(format_string, errors => count_string(dash_state, counts.errors)).unwrap() },
             NodeMetric::Peers =>            { strfmt!(format_string, connections => monitor.metrics.peers_connected.most_recent).unwrap() },
             NodeMetric::Memory =>           { strfmt!(format_string, memory => monitor.metrics.memory_used_mb.most_recent).unwrap() },
+            NodeMetric::Version =>          { strfmt!(format_string, version => monitor.metrics.running_version.clone().unwrap_or(String::from("-"))).unwrap() },
+            NodeMetric::Uptime =>           { strfmt!(format_string, uptime => node_uptime(monitor).map_or(String::from("-"), get_duration_text)).unwrap() },
+            NodeMetric::Availability =>     { strfmt!(format_string, availability => availability_text(monitor)).unwrap() },
+            NodeMetric::PeerId =>           { strfmt!(format_string, peer_id => truncated_peer_id(&monitor.metrics.node_peer_id)).unwrap() },
             NodeMetric::Status =>           { strfmt!(format_string, status => monitor.metrics.node_status_string.clone()).unwrap() },
+            NodeMetric::Custom(index) =>    { strfmt!(format_string, custom => custom_metric_value(monitor, *index)).unwrap() },
+        });
+	}
+
+	let row_text = cells.concat();
+	monitor.summary_changes.update(cells, dashboard_time());
+	row_text
+}
+
+/// Colour for a cell of the column whose value has just changed, or None for columns
+/// which change too often to highlight
+fn change_colour(metric: NodeMetric) -> Option<Color> {
+	match metric {
+		NodeMetric::StoragePayments => Some(Color::LightGreen),
+		NodeMetric::Errors => Some(Color::LightRed),
+		NodeMetric::Records | NodeMetric::Puts | NodeMetric::Gets | NodeMetric::Custom(_) => Some(Color::LightCyan),
+		NodeMetric::Status | NodeMetric::Version => Some(Color::LightYellow),
+		_ => None,
+	}
+}
+
+/// A node's Summary row with the cells which have recently changed highlighted
+fn highlighted_row(dash_state: &DashState, monitor: &LogMonitor, row_text: &str, style: Style) -> Line<'static> {
+	let changes = &monitor.summary_changes;
+	if dash_state.change_highlight <= Duration::zero() || changes.cells.concat() != row_text {
+		return Line::from(Span::styled(row_text.to_string(), style));
+	}
+	let now = dashboard_time();
+	let mut start = 0;
+	let spans = changes
+		.cells
+		.iter()
+		.enumerate()
+		.map(|(column, cell)| {
+			let text = row_text[start..start + cell.len()].to_string();
+			start += cell.len();
+			let colour = SUMMARY_COLUMNS
+				.get(column)
+				.and_then(|(metric, _, _)| change_colour(*metric))
+				.filter(|_| changes.is_recent(column, now, dash_state.change_highlight));
+			match colour {
+				Some(colour) => Span::styled(text, style.fg(Color::Black).bg(colour)),
+				None => Span::styled(text, style),
+			}
+		})
+		.collect::<Vec<Span>>();
+	Line::from(spans)
+}
+
+/// line cut to width, ending with '…' if any of it is cut. Rows are not padded to the
+/// width, so only those with a long status (the last column) are normally cut.
+pub fn truncate_line(line: Line<'static>, width: usize) -> Line<'static> {
+	let line_width: usize = line.spans.iter().map(|span| span.content.chars().count()).sum();
+	if line_width <= width {
+		return line;
+	}
+
+	let mut remaining = width.saturating_sub(1);
+	let mut spans = Vec::<Span>::new();
+	for span in line.spans {
+		if remaining == 0 {
+			break;
+		}
+		let text: String = span.content.chars().take(remaining).collect();
+		remaining -= text.chars().count();
+		spans.push(Span::styled(text, span.style));
+	}
+	if width > 0 {
+		let style = spans.last().map_or(Style::default(), |span| span.style);
+		spans.push(Span::styled("…", style));
+	}
+	Line::from(spans)
+}
+
+/// A node's full status for the status bar, which has room for more than the Status column
+pub fn status_detail(monitor: &LogMonitor) -> String {
+	let metrics = &monitor.metrics;
+	let mut detail = format!("Node {}: {}", monitor.index + 1, metrics.node_status_string);
+	if metrics.node_inactive {
+		if let Some(metadata) = &metrics.entry_metadata {
+			let idle = (dashboard_time() - metadata.message_time).num_seconds().max(0);
+			detail += &format!(
+				", no entries for {}h {:02}m {:02}s since {}",
+				idle / 3600,
+				idle / 60 % 60,
+				idle % 60,
+				metadata.message_time.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S")
+			);
+		}
+	}
+	if let Some(error) = metrics.top_messages.latest("ERROR") {
+		detail += &format!(
+			", last error at {}: {}",
+			error.last_seen.with_timezone(&Local).format("%H:%M:%S"),
+			error.message
+		);
+	}
+	detail
+}
+
+/// Row for a logfile monitored in generic mode (see --generic), which has only a status
+fn format_generic_row(index: usize, generic: &GenericMetrics) -> String {
+	let status = generic.status_text();
+	let mut row_text = String::from("");
+
+	for (metric, _heading, format_string) in SUMMARY_COLUMNS.iter() {
+		row_text += &match metric {
+            NodeMetric::Index =>            { strfmt!(format_string, index => index + 1).unwrap() },
+            NodeMetric::StoragePayments =>  { strfmt!(format_string, storage_payments  => "").unwrap() },
+            NodeMetric::EarningsPerGb =>    { strfmt!(format_string, earnings_per_gb => "").unwrap() },
+            NodeMetric::StorageCost =>      { strfmt!(format_string, storage_cost => "").unwrap() },
+            NodeMetric::Records =>          { strfmt!(format_string, records_stored => "").unwrap() },
+            NodeMetric::Puts =>             { strfmt!(format_string, puts => "").unwrap() },
+            NodeMetric::Gets =>             { strfmt!(format_string, gets => "").unwrap() },
+            NodeMetric::Trend =>            { strfmt!(format_string, trend => "").unwrap() },
+            NodeMetric::Errors =>           { strfmt!(format_string, errors => generic.level_count("ERROR")).unwrap() },
+            NodeMetric::Peers =>            { strfmt!(format_string, connections => "").unwrap() },
+            NodeMetric::Memory =>           { strfmt!(format_string, memory => "").unwrap() },
+            NodeMetric::Version =>          { strfmt!(format_string, version => "").unwrap() },
+            NodeMetric::Uptime =>           { strfmt!(format_string, uptime => "").unwrap() },
+            NodeMetric::Availability =>     { strfmt!(format_string, availability => "").unwrap() },
+            NodeMetric::PeerId =>           { strfmt!(format_string, peer_id => "").unwrap() },
+            NodeMetric::Status =>           { strfmt!(format_string, status => status.clone()).unwrap() },
+            NodeMetric::Custom(_index) =>   { strfmt!(format_string, custom => "").unwrap() },
+        };
+	}
+
+	row_text
+}
+
+/// Header row for a group (see --group-by) with subtotals in the Earnings, Records and Errors columns
+pub fn format_group_row(dash_state: &DashState, group: &str) -> String {
+	let totals = dash_state.group_totals.get(group).cloned().unwrap_or_default();
+	let marker = if dash_state.collapsed_groups.iter().any(|collapsed| collapsed == group) { "+" } else { "-" };
+	let status = format!("[{}] {} nodes, {} active", group, totals.nodes, totals.active_nodes);
+	let mut row_text = String::from("");
+
+	for (metric, _heading, format_string) in SUMMARY_COLUMNS.iter() {
+		row_text += &match metric {
+            NodeMetric::Index =>            { strfmt!(format_string, index => marker).unwrap() },
+            NodeMetric::StoragePayments =>  { strfmt!(format_string, storage_payments  => monetary_string_ant(dash_state, totals.attos_earned)).unwrap() },
+            NodeMetric::EarningsPerGb =>    { strfmt!(format_string, earnings_per_gb => "").unwrap() },
+            NodeMetric::StorageCost =>      { strfmt!(format_string, storage_cost => "").unwrap() },
+            NodeMetric::Records =>          { strfmt!(format_string, records_stored => count_string(dash_state, totals.records_stored)).unwrap() },
+            NodeMetric::Puts =>             { strfmt!(format_string, puts => "").unwrap() },
+            NodeMetric::Gets =>             { strfmt!(format_string, gets => "").unwrap() },
+            NodeMetric::Trend =>            { strfmt!(format_string, trend => "").unwrap() },
+            NodeMetric::Errors =>           { strfmt!(format_string, errors => count_string(dash_state, totals.errors)).unwrap() },
+            NodeMetric::Peers =>            { strfmt!(format_string, connections => "").unwrap() },
+            NodeMetric::Memory =>           { strfmt!(format_string, memory => "").unwrap() },
+            NodeMetric::Version =>          { strfmt!(format_string, version => "").unwrap() },
+            NodeMetric::Uptime =>           { strfmt!(format_string, uptime => "").unwrap() },
+            NodeMetric::Availability =>     { strfmt!(format_string, availability => "").unwrap() },
+            NodeMetric::PeerId =>           { strfmt!(format_string, peer_id => "").unwrap() },
+            NodeMetric::Status =>           { strfmt!(format_string, status => status.clone()).unwrap() },
+            NodeMetric::Custom(_index) =>   { strfmt!(format_string, custom => "").unwrap() },
+        };
+	}
+
+	row_text
+}
+
+/// Footer row with totals across the nodes in the Summary (respecting any filter), or
+/// means for StoreCost, Peers and Avail
+pub fn format_totals_row(dash_state: &DashState, monitors: &HashMap<String, LogMonitor>) -> String {
+	let nodes: Vec<&LogMonitor> = monitors
+		.values()
+		.filter(|monitor| monitor.is_node())
+		.filter(|monitor| dash_state.node_filter.as_ref().map_or(true, |node_filter| node_filter.matches(monitor)))
+		.collect();
+	let total = |value: fn(&LogMonitor) -> u64| nodes.iter().map(|monitor| value(monitor)).sum::<u64>();
+	let mean = |value: fn(&LogMonitor) -> u64| if nodes.is_empty() { 0 } else { total(value) / nodes.len() as u64 };
+	let storage_costs =
+		nodes.iter().fold(0u128, |total, monitor| total.saturating_add(monitor.metrics.storage_cost.most_recent));
+	let mean_storage_cost = if nodes.is_empty() { 0 } else { storage_costs / nodes.len() as u128 };
+	let uptimes: Vec<f64> = nodes.iter().filter_map(|monitor| uptime_percent(monitor)).collect();
+	let mean_uptime = if uptimes.is_empty() { None } else { Some(uptimes.iter().sum::<f64>() / uptimes.len() as f64) };
+	let active_nodes = nodes.iter().filter(|monitor| monitor.metrics.is_node_active()).count();
+	let mut counts = SessionCounts::default();
+	for monitor in nodes.iter() {
+		counts.add(&shown_counts(dash_state.session_delta, monitor));
+	}
+	let status = format!("Totals: {} nodes, {} active (mean cost, peers, avail)", nodes.len(), active_nodes);
+	let mut row_text = String::from("");
+
+	for (metric, _heading, format_string) in SUMMARY_COLUMNS.iter() {
+		row_text += &match metric {
+            NodeMetric::Index =>            { strfmt!(format_string, index => "All").unwrap() },
+            NodeMetric::StoragePayments =>  { strfmt!(format_string, storage_payments  => monetary_string_ant(dash_state, counts.attos_earned)).unwrap() },
+            NodeMetric::EarningsPerGb =>    { strfmt!(format_string, earnings_per_gb => network_earnings_per_gb(&nodes).map_or(String::from("-"), |attos| monetary_string_ant(dash_state, attos))).unwrap() },
+            NodeMetric::StorageCost =>      { strfmt!(format_string, storage_cost => monetary_string(dash_state, mean_storage_cost)).unwrap() },
+            NodeMetric::Records =>          { strfmt!(format_string, records_stored => count_string(dash_state, total(|m| m.metrics.records_stored))).unwrap() },
+            NodeMetric::Puts =>             { strfmt!(format_string, puts => count_string(dash_state, counts.puts)).unwrap() },
+            NodeMetric::Gets =>             { strfmt!(format_string, gets => count_string(dash_state, counts.gets)).unwrap() },
+            NodeMetric::Trend =>            { strfmt!(format_string, trend => "").unwrap() },
+            NodeMetric::Errors =>           { strfmt!(format_string, errors => count_string(dash_state, counts.errors)).unwrap() },
+            NodeMetric::Peers =>            { strfmt!(format_string, connections => mean(|m| m.metrics.peers_connected.most_recent)).unwrap() },
+            NodeMetric::Memory =>           { strfmt!(format_string, memory => total(|m| m.metrics.memory_used_mb.most_recent)).unwrap() },
+            NodeMetric::Version =>          { strfmt!(format_string, version => "").unwrap() },
+            NodeMetric::Uptime =>           { strfmt!(format_string, uptime => "").unwrap() },
+            NodeMetric::Availability =>     { strfmt!(format_string, availability => uptime_percent_text(mean_uptime)).unwrap() },
+            NodeMetric::PeerId =>           { strfmt!(format_string, peer_id => "").unwrap() },
+            NodeMetric::Status =>           { strfmt!(format_string, status => status.clone()).unwrap() },
+            NodeMetric::Custom(index) =>    { strfmt!(format_string, custom => custom_metric_total(&nodes, *index)).unwrap() },
         };
 	}
 
 	row_text
 }
 
+/// Attos a node has earned per GB it stores, if its storage is known
+fn earnings_per_gb(monitor: &LogMonitor) -> Option<u128> {
+	attos_per_gb(monitor.metrics.attos_earned.total, bytes_stored(&monitor.metrics)?)
+}
+
+/// Attos earned per GB stored across the nodes whose storage is known
+fn network_earnings_per_gb(nodes: &[&LogMonitor]) -> Option<u128> {
+	let (attos, bytes) = nodes
+		.iter()
+		.filter_map(|monitor| Some((monitor.metrics.attos_earned.total, bytes_stored(&monitor.metrics)?)))
+		.fold((0u128, 0u64), |(attos, bytes), (earned, stored)| (attos.saturating_add(earned), bytes + stored));
+	attos_per_gb(attos, bytes)
+}
+
+/// Total of a cumulative custom metric over nodes, or the mean of any other
+fn custom_metric_total(nodes: &[&LogMonitor], index: usize) -> u64 {
+	let total = nodes.iter().map(|monitor| custom_metric_value(monitor, index)).sum::<u64>();
+	match CUSTOM_METRICS.get(index) {
+		Some(metric) if !metric.is_cumulative && !nodes.is_empty() => total / nodes.len() as u64,
+		_ => total,
+	}
+}
+
+/// A node's value in a Summary column, unformatted for CSV (earnings in attos, uptime in seconds)
+fn csv_value(monitor: &LogMonitor, metric: NodeMetric) -> String {
+	let metrics = &monitor.metrics;
+	match metric {
+		NodeMetric::Index => (monitor.index + 1).to_string(),
+		NodeMetric::StoragePayments => metrics.attos_earned.total.to_string(),
+		NodeMetric::EarningsPerGb => earnings_per_gb(monitor).map_or(String::new(), |attos| attos.to_string()),
+		NodeMetric::StorageCost => metrics.storage_cost.most_recent.to_string(),
+		NodeMetric::Records => metrics.records_stored.to_string(),
+		NodeMetric::Puts => metrics.activity_puts.total.to_string(),
+		NodeMetric::Gets => metrics.activity_gets.total.to_string(),
+		NodeMetric::Trend => monitor.summary_trend.iter().map(BucketValue::to_string).collect::<Vec<String>>().join(" "),
+		NodeMetric::Errors => metrics.activity_errors.total.to_string(),
+		NodeMetric::Peers => metrics.peers_connected.most_recent.to_string(),
+		NodeMetric::Memory => metrics.memory_used_mb.most_recent.to_string(),
+		NodeMetric::Version => metrics.running_version.clone().unwrap_or_default(),
+		NodeMetric::Uptime => node_uptime(monitor).map_or(String::new(), |uptime| uptime.num_seconds().to_string()),
+		NodeMetric::Availability => uptime_percent(monitor).map_or(String::new(), |percent| format!("{:.1}", percent)),
+		NodeMetric::PeerId => metrics.node_peer_id.clone().unwrap_or_default(),
+		NodeMetric::Status => metrics.node_status_string.clone(),
+		NodeMetric::Custom(index) => custom_metric_value(monitor, index).to_string(),
+	}
+}
+
+/// A node's value in each Summary column by heading, unformatted as for CSV
+pub fn summary_values(monitor: &LogMonitor) -> Vec<(String, String)> {
+	SUMMARY_COLUMNS
+		.iter()
+		.map(|(metric, heading, _format)| (heading.clone(), csv_value(monitor, *metric)))
+		.collect()
+}
+
+fn csv_field(text: &str) -> String {
+	if text.contains([',', '"', '\n']) {
+		format!("\"{}\"", text.replace('"', "\"\""))
+	} else {
+		text.to_string()
+	}
+}
+
+/// Write the nodes listed in the Summary, in its order, with a column for each of
+/// its columns and the logfile. Returns the number of nodes written.
+pub fn write_summary_csv(
+	path: &std::path::Path,
+	dash_state: &DashState,
+	monitors: &HashMap<String, LogMonitor>,
+) -> std::io::Result<usize> {
+	use std::io::Write;
+
+	let mut file = std::fs::File::create(path)?;
+	let headings: Vec<String> = SUMMARY_COLUMNS.iter().map(|(_, heading, _)| csv_field(heading)).collect();
+	writeln!(file, "{},Logfile", headings.join(","))?;
+
+	let mut nodes = 0;
+	for logfile in dash_state.logfile_names_sorted.iter() {
+		let Some(monitor) = monitors.get(logfile) else {
+			continue;
+		};
+		if !monitor.is_node() {
+			continue;
+		}
+		let values: Vec<String> = SUMMARY_COLUMNS
+			.iter()
+			.map(|(metric, _, _)| csv_field(&csv_value(monitor, *metric)))
+			.collect();
+		writeln!(file, "{},{}", values.join(","), csv_field(logfile))?;
+		nodes += 1;
+	}
+	Ok(nodes)
+}
+
 pub fn draw_summary_table_window(
 	f: &mut Frame,
 	area: Rect,
@@ -142,6 +708,8 @@ pub fn draw_summary_table_window(
 	let constraints = [
 		Constraint::Length(1), // Heading
 		Constraint::Min(0),    // List
+		Constraint::Length(1), // Totals
+		Constraint::Length(1), // Nodes shown
 	];
 
 	let chunks = Layout::default()
@@ -150,23 +718,72 @@ pub fn draw_summary_table_window(
 		.split(area);
 
 	draw_summary_headings(f, chunks[0], dash_state, monitors);
-	draw_summary_rows(f, chunks[1], dash_state, monitors);
+	let (first_row, end_row) = draw_summary_rows(f, chunks[1], dash_state, monitors);
+	draw_summary_totals(f, chunks[2], dash_state, monitors);
+	draw_summary_rows_shown(f, chunks[3], dash_state, first_row, end_row);
+}
+
+/// The totals stay below the rows however far they are scrolled
+fn draw_summary_totals(f: &mut Frame, area: Rect, dash_state: &DashState, monitors: &HashMap<String, LogMonitor>) {
+	let totals_widget = Paragraph::new(format_totals_row(dash_state, monitors))
+		.style(Style::default().fg(Color::White).bg(Color::DarkGray).add_modifier(Modifier::BOLD));
+	f.render_widget(totals_widget, area);
+}
+
+/// First row and the row after the last to show in page_rows, moving on from the previous
+/// first row only as far as needed to keep the selected row in view
+pub fn visible_rows(len: usize, selected: Option<usize>, previous_first: usize, page_rows: usize) -> (usize, usize) {
+	let page_rows = page_rows.max(1);
+	let mut first = previous_first.min(len.saturating_sub(page_rows));
+	if let Some(selected) = selected {
+		if selected < first {
+			first = selected;
+		} else if selected >= first + page_rows {
+			first = selected + 1 - page_rows;
+		}
+	}
+	(first, (first + page_rows).min(len))
+}
+
+fn draw_summary_rows_shown(f: &mut Frame, area: Rect, dash_state: &DashState, first_row: usize, end_row: usize) {
+	let is_node_row = |logfile: &String| group_of_row_key(logfile).is_none();
+	let node_count = dash_state.logfile_names_sorted.iter().filter(|logfile| is_node_row(logfile)).count();
+	let nodes_shown = dash_state.logfile_names_sorted[first_row.min(end_row)..end_row.min(dash_state.logfile_names_sorted.len())]
+		.iter()
+		.filter(|logfile| is_node_row(logfile))
+		.count();
+
+	let mut text = format!("{} of {} nodes", nodes_shown, node_count);
+	if nodes_shown < node_count {
+		text += "  (PgUp/PgDn, Home/End to scroll)";
+	}
+	if dash_state.session_delta {
+		text += &format!("  +counts since {}", dash_state.session_started.with_timezone(&chrono::Local).format("%H:%M"));
+	}
+	let shown_widget = Paragraph::new(text).style(Style::default().fg(Color::Gray));
+	f.render_widget(shown_widget, area);
 }
 
 pub fn initialise_summary_headings(dash_state: &mut DashState) {
-	for i in 0..COLUMN_HEADERS.len() {
-		let (metric, heading, format_string) = &COLUMN_HEADERS[i];
+	for (metric, heading, format_string) in SUMMARY_COLUMNS.iter() {
 		dash_state.summary_window_headings.items.push(match metric {
-			NodeMetric::Index => strfmt!(format_string, index => *heading).unwrap(),
-			NodeMetric::StoragePayments => strfmt!(format_string, storage_payments => *heading).unwrap(),
-			NodeMetric::StorageCost => strfmt!(format_string, storage_cost => *heading).unwrap(),
-			NodeMetric::Records => strfmt!(format_string, records_stored => *heading).unwrap(),
-			NodeMetric::Puts => strfmt!(format_string, puts => *heading).unwrap(),
-			NodeMetric::Gets => strfmt!(format_string, gets => *heading).unwrap(),
-			NodeMetric::Errors => strfmt!(format_string, errors => *heading).unwrap(),
-			NodeMetric::Peers => strfmt!(format_string, connections => *heading).unwrap(),
-			NodeMetric::Memory => strfmt!(format_string, memory => *heading).unwrap(),
-			NodeMetric::Status => strfmt!(format_string, status => *heading).unwrap(),
+			NodeMetric::Index => strfmt!(format_string, index => heading.clone()).unwrap(),
+			NodeMetric::StoragePayments => strfmt!(format_string, storage_payments => heading.clone()).unwrap(),
+			NodeMetric::EarningsPerGb => strfmt!(format_string, earnings_per_gb => heading.clone()).unwrap(),
+			NodeMetric::StorageCost => strfmt!(format_string, storage_cost => heading.clone()).unwrap(),
+			NodeMetric::Records => strfmt!(format_string, records_stored => heading.clone()).unwrap(),
+			NodeMetric::Puts => strfmt!(format_string, puts => heading.clone()).unwrap(),
+			NodeMetric::Gets => strfmt!(format_string, gets => heading.clone()).unwrap(),
+			NodeMetric::Trend => strfmt!(format_string, trend => heading.clone()).unwrap(),
+			NodeMetric::Errors => strfmt!(format_string, errors => heading.clone()).unwrap(),
+			NodeMetric::Peers => strfmt!(format_string, connections => heading.clone()).unwrap(),
+			NodeMetric::Memory => strfmt!(format_string, memory => heading.clone()).unwrap(),
+			NodeMetric::Version => strfmt!(format_string, version => heading.clone()).unwrap(),
+			NodeMetric::Uptime => strfmt!(format_string, uptime => heading.clone()).unwrap(),
+			NodeMetric::Availability => strfmt!(format_string, availability => heading.clone()).unwrap(),
+			NodeMetric::PeerId => strfmt!(format_string, peer_id => heading.clone()).unwrap(),
+			NodeMetric::Status => strfmt!(format_string, status => heading.clone()).unwrap(),
+			NodeMetric::Custom(_index) => strfmt!(format_string, custom => heading.clone()).unwrap(),
 		});
 	}
 }
@@ -188,8 +805,29 @@ fn draw_summary_headings(
 		.items
 		.iter()
 		.map(|s| {
+			// Show the sort direction in the heading's padding
+			let mut heading = match SUMMARY_COLUMNS.get(index) {
+				Some((NodeMetric::Trend, _heading, format_string)) => {
+					strfmt!(format_string, trend => trend_heading(dash_state)).unwrap()
+				}
+				// Mark columns counting from the start of the session, keeping their width
+				Some((NodeMetric::StoragePayments | NodeMetric::Puts | NodeMetric::Gets | NodeMetric::Errors, heading, _))
+					if dash_state.session_delta =>
+				{
+					s.replacen(&format!(" {}", heading), &format!("+{}", heading), 1)
+				}
+				_ => s.clone(),
+			};
+			if let Some(indicator) = sort_indicator(dash_state, index) {
+				if heading.starts_with(' ') {
+					heading.replace_range(0..1, &indicator.to_string());
+				} else {
+					heading.pop();
+					heading.push(indicator);
+				}
+			}
 			Span::styled(
-				s.clone(),
+				heading,
 				if dash_state.summary_window_heading_selected != index {
 					index += 1;
 					heading_style
@@ -208,30 +846,80 @@ fn draw_summary_headings(
 	f.render_widget(summary_header_widget, area);
 }
 
+/// Draw the rows which fit in area, returning the first row and the row after the last
 fn draw_summary_rows(
 	f: &mut Frame,
 	area: Rect,
 	dash_state: &mut DashState,
-	_monitors: &mut HashMap<String, LogMonitor>,
-) {
+	monitors: &mut HashMap<String, LogMonitor>,
+) -> (usize, usize) {
 	let highlight_style = Style::default()
 		.bg(Color::LightGreen)
 		.add_modifier(Modifier::BOLD);
 
-	let items: Vec<ListItem> = dash_state
-		.summary_window_rows
-		.items
+	// Highlight nodes which need upgrading, exceed their resource envelope, or share a PeerId with another monitor
+	let expected_version = expected_node_version(monitors);
+	let duplicate_peer_ids: Vec<String> = duplicate_peer_ids(monitors)
+		.into_iter()
+		.map(|(peer_id, _logfiles)| peer_id)
+		.collect();
+	// Only rows in view are styled and rendered, which matters with hundreds of nodes
+	let page_rows = area.height as usize;
+	let width = area.width as usize;
+	let len = dash_state.summary_window_rows.items.len();
+	let selected = dash_state.summary_window_rows.state.selected().filter(|selected| *selected < len);
+	let (first_row, end_row) = visible_rows(
+		len,
+		selected,
+		dash_state.summary_window_first_row,
+		page_rows,
+	);
+	dash_state.summary_window_first_row = first_row;
+	dash_state.summary_window_page_rows = page_rows;
+
+	let items: Vec<ListItem> = dash_state.summary_window_rows.items[first_row..end_row]
 		.iter()
-		.map(|s| ListItem::new(vec![Line::from(s.clone())]).style(Style::default().fg(Color::White)))
+		.enumerate()
+		.map(|(i, s)| (first_row + i, s))
+		.map(|(i, s)| {
+			let logfile = dash_state.logfile_names_sorted.get(i);
+			if logfile.is_some_and(|logfile| group_of_row_key(logfile).is_some()) {
+				return ListItem::new(vec![truncate_line(Line::from(s.clone()), width)])
+					.style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD));
+			}
+			let monitor = logfile.and_then(|logfile| monitors.get(logfile));
+			let duplicate = monitor.is_some_and(|monitor| {
+				monitor
+					.metrics
+					.node_peer_id
+					.as_ref()
+					.is_some_and(|peer_id| duplicate_peer_ids.contains(peer_id))
+			});
+			let outdated = monitor.is_some_and(|monitor| is_outdated_version(monitor, &expected_version));
+			let over_envelope = monitor.is_some_and(|monitor| monitor.resource_envelope.is_breached(&monitor.metrics));
+			let fg = if duplicate {
+				Color::LightRed
+			} else if over_envelope {
+				Color::LightMagenta
+			} else if outdated {
+				Color::Yellow
+			} else {
+				Color::White
+			};
+			match monitor {
+				Some(monitor) => {
+					ListItem::new(vec![truncate_line(highlighted_row(dash_state, monitor, s, Style::default().fg(fg)), width)])
+				}
+				None => ListItem::new(vec![truncate_line(Line::from(s.clone()), width)]).style(Style::default().fg(fg)),
+			}
+		})
 		.collect();
 
 	let summary_window_widget = List::new(items)
 		.block(Block::default())
 		.highlight_style(highlight_style);
 
-	f.render_stateful_widget(
-		summary_window_widget,
-		area,
-		&mut dash_state.summary_window_rows.state,
-	);
+	let mut page_state = ListState::default().with_selected(selected.map(|selected| selected - first_row));
+	f.render_stateful_widget(summary_window_widget, area, &mut page_state);
+	(first_row, end_row)
 }
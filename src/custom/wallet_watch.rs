@@ -0,0 +1,135 @@
+//! Watch a node's wallet balance file (see --wallet-poll)
+//!
+//! Changes in the wallet balance are reconciled with the earnings logged
+//! since the previous poll. A balance lower than expected is counted as a
+//! withdrawal, while a balance higher than expected is flagged as a mismatch
+//! because the logfile has not accounted for it.
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+
+use super::token_units::ATTOS_PER_ANT;
+
+const ANT_DECIMALS: usize = 18;
+
+#[derive(Clone, Debug)]
+pub struct WalletChange {
+	pub time: DateTime<Utc>,
+	pub balance: u128,
+	pub change: i128,      // Change in balance since the previous poll (attos)
+	pub unexplained: i128, // Change not accounted for by logged earnings (attos)
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct WalletWatch {
+	pub balance: Option<u128>,
+	pub changes: Vec<WalletChange>,
+	pub withdrawn: u128,
+	pub mismatched: u128, // Increases in balance not matched by logged earnings
+	pub error: Option<String>,
+
+	earnings_at_last_poll: u128,
+}
+
+impl WalletWatch {
+	/// The wallet balance file for a node, relative to its root directory which
+	/// is taken as the parent of the directory holding the logfile.
+	pub fn wallet_path(logfile: &str, wallet_filename: &str) -> Option<PathBuf> {
+		let node_root = Path::new(logfile).parent()?.parent()?;
+		Some(node_root.join(wallet_filename))
+	}
+
+	/// Read the wallet balance file and reconcile any change with earnings_total
+	pub fn poll(&mut self, path: &Path, now: &DateTime<Utc>, earnings_total: u128) -> Option<&WalletChange> {
+		let text = match std::fs::read_to_string(path) {
+			Ok(text) => text,
+			Err(e) => {
+				self.error = Some(format!("{}: {}", path.display(), e));
+				return None;
+			}
+		};
+
+		match parse_balance(&text) {
+			Some(balance) => {
+				self.error = None;
+				self.update_balance(now, balance, earnings_total)
+			}
+			None => {
+				self.error = Some(format!("{}: balance not recognised", path.display()));
+				None
+			}
+		}
+	}
+
+	/// Record a balance, returning the change if it differs from the previous balance
+	pub fn update_balance(&mut self, now: &DateTime<Utc>, balance: u128, earnings_total: u128) -> Option<&WalletChange> {
+		let expected_change = earnings_total as i128 - self.earnings_at_last_poll as i128;
+		let previous_balance = self.balance.replace(balance);
+		self.earnings_at_last_poll = earnings_total;
+
+		let previous_balance = previous_balance?;
+		if previous_balance == balance && expected_change == 0 {
+			return None;
+		}
+
+		let change = balance as i128 - previous_balance as i128;
+		let unexplained = change - expected_change;
+		if unexplained < 0 {
+			self.withdrawn += (-unexplained) as u128;
+		} else {
+			self.mismatched += unexplained as u128;
+		}
+
+		self.changes.push(WalletChange {
+			time: *now,
+			balance,
+			change,
+			unexplained,
+		});
+		self.changes.last()
+	}
+}
+
+/// Parse a balance given either as a decimal token amount (e.g. "1.000000001")
+/// or as a whole number of attos
+pub fn parse_balance(text: &str) -> Option<u128> {
+	let text = text.trim();
+	if !text.contains('.') {
+		return text.parse::<u128>().ok();
+	}
+
+	let (whole, fraction) = text.split_once('.')?;
+	if fraction.len() > ANT_DECIMALS || !fraction.chars().all(|c| c.is_ascii_digit()) {
+		return None;
+	}
+
+	let whole = if whole.is_empty() { 0 } else { whole.parse::<u128>().ok()? };
+	let fraction = format!("{:0<width$}", fraction, width = ANT_DECIMALS).parse::<u128>().ok()?;
+	whole.checked_mul(ATTOS_PER_ANT as u128)?.checked_add(fraction)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn it_reconciles_balance_changes_with_earnings() {
+		assert_eq!(parse_balance("1.5\n"), Some(1_500_000_000_000_000_000));
+		assert_eq!(parse_balance("42"), Some(42));
+		assert_eq!(parse_balance("x.1"), None);
+
+		let now = Utc::now();
+		let mut watch = WalletWatch::default();
+		assert!(watch.update_balance(&now, 1000, 0).is_none());
+
+		// Earned 500 but only 300 arrived: 200 withdrawn
+		let change = watch.update_balance(&now, 1300, 500).unwrap();
+		assert_eq!(change.change, 300);
+		assert_eq!(change.unexplained, -200);
+		assert_eq!(watch.withdrawn, 200);
+
+		// Balance increased without any logged earnings
+		watch.update_balance(&now, 1400, 500);
+		assert_eq!(watch.mismatched, 100);
+	}
+}
@@ -1,6 +1,68 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{LazyLock, Mutex};
+
 use chrono::{DateTime, Duration, Utc};
+use ratatui::style::Color;
+#[cfg(not(feature = "minimal"))]
 use serde_json::Value;
 
+use super::app_timelines::TIMESCALES;
+use super::timelines::{BucketValue, Timeline};
+
+#[cfg(not(any(feature = "web-prices", feature = "minimal")))]
+compile_error!("vdash needs the 'web-prices' feature, or 'minimal' to build without web price APIs");
+
+pub static WEB_PRICES: LazyLock<Mutex<WebPrices>> = LazyLock::new(|| Mutex::<WebPrices>::new(WebPrices::new()));
+
+/// Token prices are held in the price history Timeline as millionths of the currency
+pub const PRICE_HISTORY_UNITS_PER_CURRENCY: f64 = 1_000_000.0;
+
+/// Prices for one of the fiat currencies requested with --currency-apiname
+pub struct CurrencyPrices {
+	pub currency_apiname: String, // For API query (e.g. "USD")
+	pub currency_symbol: String,  // For UI (e.g. "$")
+
+	pub snt_rate: Option<f64>,
+	pub btc_rate: Option<f64>,
+
+	pub price_history: Timeline, // snt_rate over the vdash session
+}
+
+impl CurrencyPrices {
+	fn new(currency_apiname: String, currency_symbol: String) -> CurrencyPrices {
+		let opt_timeline_steps = super::node_metrics::OPT.lock().unwrap().timeline_steps;
+
+		let mut price_history = Timeline::new(
+			format!("{} price", currency_apiname),
+			currency_apiname.clone(),
+			true,
+			false,
+			Color::LightCyan,
+		);
+		for (name, duration) in TIMESCALES.iter() {
+			price_history.add_bucket_set(name, *duration, opt_timeline_steps);
+		}
+
+		CurrencyPrices {
+			currency_apiname,
+			currency_symbol,
+			snt_rate: None,
+			btc_rate: None,
+			price_history,
+		}
+	}
+
+	fn record_price(&mut self, time: &DateTime<Utc>) {
+		if let Some(snt_rate) = self.snt_rate {
+			self.price_history.update_current_time(time);
+			self
+				.price_history
+				.update_value(time, (snt_rate * PRICE_HISTORY_UNITS_PER_CURRENCY) as BucketValue);
+		}
+	}
+}
+
 pub struct WebPrices {
 	pub snt_rate: Option<f64>, // Currency value per SNT (e.g. 0.20)
 	pub btc_rate: Option<f64>, // Currency value per BTC
@@ -9,6 +71,10 @@ pub struct WebPrices {
 	pub currency_symbol: String,  // For UI (e.g. "$")
 
 	pub last_update_time: Option<DateTime<Utc>>,
+
+	// All requested currencies, the one at active_currency is mirrored in the fields above
+	pub currencies: Vec<CurrencyPrices>,
+	pub active_currency: usize,
 }
 
 impl WebPrices {
@@ -21,174 +87,352 @@ impl WebPrices {
 			currency_symbol: String::from(""),
 
 			last_update_time: None,
+
+			currencies: Vec::new(),
+			active_currency: 0,
+		}
+	}
+
+	/// Set the currencies from comma separated lists of API names and symbols
+	/// (e.g. "USD,EUR,GBP" and "$,€,£"). A currency without a symbol is
+	/// displayed using its API name.
+	pub fn set_currencies(&mut self, currency_apinames: &str, currency_symbols: &str) {
+		let symbols: Vec<&str> = currency_symbols.split(',').map(|s| s.trim()).collect();
+
+		self.currencies = parse_currency_apinames(currency_apinames)
+			.into_iter()
+			.enumerate()
+			.map(|(i, apiname)| {
+				let symbol = match symbols.get(i) {
+					Some(symbol) if !symbol.is_empty() => symbol.to_string(),
+					_ => format!("{} ", apiname),
+				};
+				CurrencyPrices::new(apiname, symbol)
+			})
+			.collect();
+		self.set_active_currency(0);
+	}
+
+	/// Make the index'th currency the one displayed
+	pub fn set_active_currency(&mut self, index: usize) {
+		if let Some(currency) = self.currencies.get(index) {
+			self.active_currency = index;
+			self.currency_apiname = currency.currency_apiname.clone();
+			self.currency_symbol = currency.currency_symbol.clone();
+			self.snt_rate = currency.snt_rate;
+			self.btc_rate = currency.btc_rate;
 		}
 	}
+
+	/// Cycle to the next currency, returning its API name
+	pub fn next_currency(&mut self) -> Option<String> {
+		if self.currencies.is_empty() {
+			return None;
+		}
+		self.set_active_currency((self.active_currency + 1) % self.currencies.len());
+		Some(self.currency_apiname.clone())
+	}
+
+	/// Price history of the active currency
+	pub fn price_history(&self) -> Option<&Timeline> {
+		self
+			.currencies
+			.get(self.active_currency)
+			.map(|currency| &currency.price_history)
+	}
+
+	/// Advance the price history timelines, carrying the latest prices forward
+	/// so there are no gaps between polls of the price APIs
+	pub fn update_timelines(&mut self, now: &DateTime<Utc>) {
+		for currency in self.currencies.iter_mut() {
+			currency.record_price(now);
+		}
+	}
+
+	/// Store rates for the currency with the given API name (case insensitive)
+	pub fn set_rates(&mut self, currency_apiname: &str, snt_rate: Option<f64>, btc_rate: Option<f64>) {
+		for (i, currency) in self.currencies.iter_mut().enumerate() {
+			if currency.currency_apiname.eq_ignore_ascii_case(currency_apiname) {
+				if snt_rate.is_some() {
+					currency.snt_rate = snt_rate;
+					currency.record_price(&Utc::now());
+				}
+				if btc_rate.is_some() {
+					currency.btc_rate = btc_rate;
+				}
+				if i == self.active_currency {
+					self.snt_rate = currency.snt_rate;
+					self.btc_rate = currency.btc_rate;
+				}
+			}
+		}
+	}
+}
+
+/// Split a comma separated --currency-apiname value into upper case API names
+pub fn parse_currency_apinames(currency_apinames: &str) -> Vec<String> {
+	currency_apinames
+		.split(',')
+		.map(|s| s.trim().to_uppercase())
+		.filter(|s| !s.is_empty())
+		.collect()
 }
 
-const DEFAULT_COINGECKO_POLL_INTERVAL: i64 = 30; // Minutes (based on free account)
-const DEFAULT_COINMARKETCAP_POLL_INTERVAL: i64 = 30; // Minutes (based on free account)
-const DEFAULT_SWITCH_API_POLL_INTERVAL: i64 = 5; // Minutes to wait after switching API
+const DEFAULT_SWITCH_API_POLL_INTERVAL: i64 = 5; // Seconds to wait after switching API
 
-pub struct WebPriceAPIs {
-	currency_apiname: String, // For API query (e.g. "USD")
+type PriceResult = Result<Option<f64>, Box<dyn std::error::Error>>;
+type PriceFuture<'a> = Pin<Box<dyn Future<Output = PriceResult> + 'a>>;
 
-	current_api_key: Option<String>,
-	switching_api_interval: Duration,
+/// A web API which provides token prices in one or more fiat currencies
+///
+/// get_prices() should store any rates obtained in WEB_PRICES and return
+/// the rate for the active currency if successful.
+pub trait PriceProvider {
+	fn name(&self) -> &'static str;
+	fn min_poll_interval(&self) -> Duration;
+	fn get_prices<'a>(&'a self, currency_apinames: &'a [String]) -> PriceFuture<'a>;
+}
 
-	// CoinGecko
-	coingecko_api_key: Option<String>,
-	coingecko_next_poll: Option<DateTime<Utc>>,
-	coingecko_min_poll_interval: Duration,
+struct ProviderState {
+	provider: Box<dyn PriceProvider>,
+	next_poll: Option<DateTime<Utc>>,
+}
+
+pub struct WebPriceAPIs {
+	currency_apinames: Vec<String>, // For API query (e.g. "USD", "EUR")
 
-	// CoinMarketCap Configuration
-	coinmarketcap_api_key: Option<String>,
-	coinmarketcap_next_poll: Option<DateTime<Utc>>,
-	coinmarketcap_min_poll_interval: Duration,
+	providers: Vec<ProviderState>, // In order of preference (see --price-apis)
+	current_provider: usize,
+	switching_api_interval: Duration,
 }
 
 pub const CMC_API_SAFE_TOKEN_NAME: &str = "EMAID"; // Coinmarketcap API
+pub const CRYPTOCOMPARE_SAFE_TOKEN_NAME: &str = "EMAID"; // CryptoCompare API
+
+pub const COINGECKO_API_NAME: &str = "coingecko";
+pub const COINMARKETCAP_API_NAME: &str = "coinmarketcap";
+pub const CRYPTOCOMPARE_API_NAME: &str = "cryptocompare";
 
 // For vdash UI:
 pub const SAFE_TOKEN_TICKER: &str = "SNT";
 pub const BTC_TICKER: &str = "BTC";
 
 impl WebPriceAPIs {
-	pub fn new(
-		coingecko_api_key: Option<String>,
-		coinmarketcap_api_key: Option<String>,
-		currency_apiname: &String,
-	) -> WebPriceAPIs {
-		WebPriceAPIs {
-			currency_apiname: currency_apiname.clone(),
+	/// Creates the price APIs from the command line options
+	pub fn new() -> WebPriceAPIs {
+		let opt = super::node_metrics::OPT.lock().unwrap();
 
-			current_api_key: None,
-			switching_api_interval: Duration::seconds(DEFAULT_SWITCH_API_POLL_INTERVAL),
+		// A minimal build has no price providers, so prices are never requested
+		#[cfg(not(feature = "minimal"))]
+		let providers = price_providers(&opt);
+		#[cfg(feature = "minimal")]
+		let providers = Vec::new();
 
-			coingecko_api_key: coingecko_api_key,
-			coingecko_next_poll: None,
-			coingecko_min_poll_interval: Duration::minutes(DEFAULT_COINGECKO_POLL_INTERVAL),
+		WebPriceAPIs {
+			currency_apinames: parse_currency_apinames(&opt.currency_apiname),
 
-			coinmarketcap_api_key: coinmarketcap_api_key,
-			coinmarketcap_next_poll: None,
-			coinmarketcap_min_poll_interval: Duration::minutes(DEFAULT_COINMARKETCAP_POLL_INTERVAL),
+			providers,
+			current_provider: 0,
+			switching_api_interval: Duration::seconds(DEFAULT_SWITCH_API_POLL_INTERVAL),
 		}
 	}
 
-	/// Call one of up to two web apis to get prices. Uses a minimum poll interval to
+	/// Name of the provider currently in use, if any
+	pub fn current_provider_name(&self) -> Option<&'static str> {
+		self
+			.providers
+			.get(self.current_provider)
+			.map(|state| state.provider.name())
+	}
+
+	/// Call the current web api to get prices. Uses a minimum poll interval to
 	/// avoid excessive use of the metered APIs and avoid slowing down other threads.
 	///
-	/// If the default API fails to return a value, switches to using the alternate API
+	/// If the current API fails to return a value, switches to using the next API
 	/// for the next cycle (setting a shorter interval for the retry).
 	///
 	/// Returns the currency_per_token rate if successful
-	pub async fn handle_web_requests(&mut self) -> Result<Option<f64>, Box<dyn std::error::Error>> {
+	pub async fn handle_web_requests(&mut self) -> PriceResult {
 		let now = Utc::now();
 
-		let mut currency_token_rate = None;
-		if self.coingecko_api_key.is_some() {
-			if self.current_api_key.is_none()
-				|| self.current_api_key.as_ref().unwrap() == self.coingecko_api_key.as_ref().unwrap()
-			{
-				if self.coingecko_next_poll.is_none() || self.coingecko_next_poll.unwrap() < now {
-					self.coingecko_next_poll = Some(now + self.coingecko_min_poll_interval);
-					currency_token_rate = self.get_coingecko_prices().await?;
-
-					if currency_token_rate.is_some() {
-						self.current_api_key = Some(self.coingecko_api_key.as_ref().unwrap().clone());
-					} else if self.coinmarketcap_api_key.is_some() {
-						self.coinmarketcap_next_poll = Some(now + self.switching_api_interval);
-						self.current_api_key = Some(self.coinmarketcap_api_key.as_ref().unwrap().clone());
-					}
-				}
+		let Some(state) = self.providers.get_mut(self.current_provider) else {
+			return Ok(None);
+		};
+		if state.next_poll.is_some_and(|next_poll| next_poll > now) {
+			return Ok(None);
+		}
+
+		state.next_poll = Some(now + state.provider.min_poll_interval());
+		let result = state.provider.get_prices(&self.currency_apinames).await;
+		match result {
+			Ok(Some(currency_token_rate)) => Ok(Some(currency_token_rate)),
+			Ok(None) => {
+				self.switch_to_next_provider(now);
+				Ok(None)
+			}
+			Err(e) => {
+				self.switch_to_next_provider(now);
+				Err(e)
 			}
 		}
+	}
 
-		if self.coinmarketcap_api_key.is_some() {
-			if self.current_api_key.is_none()
-				|| self.current_api_key.as_ref().unwrap() == self.coinmarketcap_api_key.as_ref().unwrap()
-			{
-				if self.coinmarketcap_next_poll.is_none() || self.coinmarketcap_next_poll.unwrap() < now {
-					self.coinmarketcap_next_poll = Some(now + self.coinmarketcap_min_poll_interval);
-					currency_token_rate = self.get_coinmarketcap_prices().await?;
-
-					if currency_token_rate.is_some() {
-						self.current_api_key = Some(self.coinmarketcap_api_key.as_ref().unwrap().clone());
-					} else if self.coingecko_api_key.is_some() {
-						self.coingecko_next_poll = Some(now + self.switching_api_interval);
-						self.current_api_key = Some(self.coingecko_api_key.as_ref().unwrap().clone());
-					}
-				}
+	fn switch_to_next_provider(&mut self, now: DateTime<Utc>) {
+		if self.providers.len() > 1 {
+			self.current_provider = (self.current_provider + 1) % self.providers.len();
+			self.providers[self.current_provider].next_poll = Some(now + self.switching_api_interval);
+		}
+	}
+}
+
+impl Default for WebPriceAPIs {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+/// Creates the price providers named in --price-apis, in the order given.
+/// Providers which need an API key are skipped if no key was provided.
+#[cfg(not(feature = "minimal"))]
+fn price_providers(opt: &super::opt::Opt) -> Vec<ProviderState> {
+	let mut providers = Vec::<ProviderState>::new();
+	for name in opt.price_apis.split(',').map(|s| s.trim().to_lowercase()) {
+		let provider: Option<Box<dyn PriceProvider>> = match name.as_str() {
+			COINGECKO_API_NAME => opt.coingecko_key.as_ref().map(|api_key| {
+				Box::new(CoinGeckoProvider {
+					api_key: api_key.clone(),
+					poll_interval: Duration::minutes(opt.coingecko_interval as i64),
+				}) as Box<dyn PriceProvider>
+			}),
+			COINMARKETCAP_API_NAME => opt.coinmarketcap_key.as_ref().map(|api_key| {
+				Box::new(CoinMarketCapProvider {
+					api_key: api_key.clone(),
+					poll_interval: Duration::minutes(opt.coinmarketcap_interval as i64),
+				}) as Box<dyn PriceProvider>
+			}),
+			CRYPTOCOMPARE_API_NAME => Some(Box::new(CryptoCompareProvider {
+				poll_interval: Duration::minutes(opt.cryptocompare_interval as i64),
+			})),
+			_ => {
+				let message = format!("Ignoring unknown price API: '{}'", name);
+				if opt.quiet { log::info!("{}", message) } else { eprintln!("{}", message) }
+				None
 			}
+		};
+
+		if let Some(provider) = provider {
+			providers.push(ProviderState {
+				provider,
+				next_poll: None,
+			});
 		}
+	}
+	providers
+}
+
+#[cfg(not(feature = "minimal"))]
+fn unrecognised_currency_error(currency_apiname: &str) -> Box<dyn std::error::Error> {
+	let message = format!(
+		"unrecognised API value for --currency-apiname option: {}",
+		currency_apiname
+	);
+	Box::new(std::io::Error::new(
+		std::io::ErrorKind::Other,
+		message.as_str(),
+	))
+}
 
-		Ok(currency_token_rate)
+#[cfg(not(feature = "minimal"))]
+pub struct CoinGeckoProvider {
+	api_key: String,
+	poll_interval: Duration,
+}
+
+#[cfg(not(feature = "minimal"))]
+impl PriceProvider for CoinGeckoProvider {
+	fn name(&self) -> &'static str {
+		COINGECKO_API_NAME
+	}
+
+	fn min_poll_interval(&self) -> Duration {
+		self.poll_interval
 	}
 
 	// Access price via API, lock the WebPrices object and store the new values
 	// Returns the currency_per_token rate if successful
-	pub async fn get_coingecko_prices(&mut self) -> Result<Option<f64>, Box<dyn std::error::Error>> {
-		if let Some(api_key) = &self.coingecko_api_key {
+	fn get_prices<'a>(&'a self, currency_apinames: &'a [String]) -> PriceFuture<'a> {
+		Box::pin(async move {
 			let client = reqwest::Client::new();
 			let url = "https://api.coingecko.com/api/v3/simple/price";
 			let response = client
 				.get(url)
-				.header("x-cg-demo-api-key", api_key)
+				.header("x-cg-demo-api-key", &self.api_key)
 				.query(&[
 					("ids", "maidsafecoin,bitcoin"),
-					(
-						"vs_currencies",
-						&format!("{}", self.currency_apiname).to_lowercase(),
-					),
+					("vs_currencies", &currency_apinames.join(",").to_lowercase()),
 				])
 				.send()
 				.await?;
 
 			let body = response.text().await?;
 			let json = serde_json::from_str::<Value>(&body)?;
-			let mut prices = super::app::WEB_PRICES.lock()?;
+			let mut prices = WEB_PRICES.lock()?;
 			let time_now = Some(Utc::now());
-			if let Some(btcprices) = json["bitcoin"].as_object() {
-				let currency_key = &self.currency_apiname.as_str().to_lowercase();
-				if !btcprices.contains_key(currency_key) {
-					let message = format!(
-						"unrecognised API value for --currency-apiname option: {}",
-						&self.currency_apiname.as_str()
+			for currency_apiname in currency_apinames {
+				let currency_key = currency_apiname.to_lowercase();
+				let mut btc_rate = None;
+				if let Some(btcprices) = json["bitcoin"].as_object() {
+					if !btcprices.contains_key(&currency_key) {
+						return Err(unrecognised_currency_error(currency_apiname));
+					}
+
+					btc_rate = btcprices[currency_key.as_str()].as_f64();
+				}
+				if let Some(token_prices) = json["maidsafecoin"].as_object() {
+					prices.set_rates(
+						currency_apiname,
+						token_prices[currency_key.as_str()].as_f64(),
+						btc_rate,
 					);
-					return Err(Box::new(std::io::Error::new(
-						std::io::ErrorKind::Other,
-						message.as_str(),
-					)));
+					prices.last_update_time = time_now;
 				}
-
-				prices.btc_rate = btcprices[self.currency_apiname.to_lowercase().as_str()].as_f64();
 			}
-			if let Some(token_prices) = json["maidsafecoin"].as_object() {
-				prices.snt_rate = token_prices[self.currency_apiname.to_lowercase().as_str()].as_f64();
-				prices.last_update_time = time_now;
-				return Ok(prices.snt_rate);
-			}
-		}
+			Ok(prices.snt_rate)
+		})
+	}
+}
+
+#[cfg(not(feature = "minimal"))]
+pub struct CoinMarketCapProvider {
+	api_key: String,
+	poll_interval: Duration,
+}
+
+#[cfg(not(feature = "minimal"))]
+impl PriceProvider for CoinMarketCapProvider {
+	fn name(&self) -> &'static str {
+		COINMARKETCAP_API_NAME
+	}
 
-		Ok(None)
+	fn min_poll_interval(&self) -> Duration {
+		self.poll_interval
 	}
 
 	// Access price via API, lock the WebPrices object and store the new values
 	// Returns the currency_per_token rate if successful
-	pub async fn get_coinmarketcap_prices(
-		&mut self,
-	) -> Result<Option<f64>, Box<dyn std::error::Error>> {
-		let mut currency_per_token = None;
-		let mut error = None;
+	fn get_prices<'a>(&'a self, currency_apinames: &'a [String]) -> PriceFuture<'a> {
+		Box::pin(async move {
+			let mut currency_per_token = None;
+			let mut error = None;
 
-		if let Some(api_key) = &self.coinmarketcap_api_key {
 			let response: reqwest::Response = reqwest::Client::builder()
 				.build()?
 				.get("https://pro-api.coinmarketcap.com/v2/cryptocurrency/quotes/latest")
-				.header("X-CMC_PRO_API_KEY", api_key)
+				.header("X-CMC_PRO_API_KEY", &self.api_key)
 				.header("Accept", "application/json")
 				.query(&[
 					("symbol", CMC_API_SAFE_TOKEN_NAME),
-					("convert", self.currency_apiname.as_str()),
+					("convert", currency_apinames.join(",").as_str()),
 				])
 				.send()
 				.await?;
@@ -200,37 +444,110 @@ impl WebPriceAPIs {
 				data["EMAID"].as_array().is_some_and(|emaid| {
 					emaid[0].as_object().is_some_and(|emaid_0| {
 						emaid_0["quote"].as_object().is_some_and(|quote| {
-							let currency_key = &self.currency_apiname.as_str().to_uppercase();
-							if !quote.contains_key(currency_key) {
-								let message = format!(
-									"unrecognised API value for --currency-apiname option: {}",
-									&self.currency_apiname.as_str()
-								);
-								error = Some(std::io::Error::new(
-									std::io::ErrorKind::Other,
-									message.as_str(),
-								));
-								return false;
-							}
-							quote[currency_key].as_object().is_some_and(|usd| {
-								usd["price"].as_f64().is_some_and(|token_price| {
-									let mut prices = super::app::WEB_PRICES.lock().unwrap();
-									prices.snt_rate = Some(token_price);
+							let mut prices = WEB_PRICES.lock().unwrap();
+							for currency_apiname in currency_apinames {
+								let currency_key = currency_apiname.to_uppercase();
+								if !quote.contains_key(&currency_key) {
+									error = Some(unrecognised_currency_error(currency_apiname));
+									return false;
+								}
+								if let Some(token_price) = quote[&currency_key]
+									.as_object()
+									.and_then(|currency| currency["price"].as_f64())
+								{
+									prices.set_rates(currency_apiname, Some(token_price), None);
 									prices.last_update_time = Some(Utc::now());
-									currency_per_token = Some(token_price);
-									true
-								})
-							})
+								}
+							}
+							currency_per_token = prices.snt_rate;
+							currency_per_token.is_some()
 						})
 					})
 				})
 			});
-		}
 
-		if error.is_some() {
-			return Err(Box::new(error.unwrap()));
-		}
+			if let Some(error) = error {
+				return Err(error);
+			}
+
+			Ok(currency_per_token)
+		})
+	}
+}
+
+/// CryptoCompare provides a public price API which doesn't need a key
+#[cfg(not(feature = "minimal"))]
+pub struct CryptoCompareProvider {
+	poll_interval: Duration,
+}
+
+#[cfg(not(feature = "minimal"))]
+impl PriceProvider for CryptoCompareProvider {
+	fn name(&self) -> &'static str {
+		CRYPTOCOMPARE_API_NAME
+	}
+
+	fn min_poll_interval(&self) -> Duration {
+		self.poll_interval
+	}
+
+	// Access price via API, lock the WebPrices object and store the new values
+	// Returns the currency_per_token rate if successful
+	fn get_prices<'a>(&'a self, currency_apinames: &'a [String]) -> PriceFuture<'a> {
+		Box::pin(async move {
+			let response = reqwest::Client::new()
+				.get("https://min-api.cryptocompare.com/data/pricemulti")
+				.query(&[
+					("fsyms", format!("{},BTC", CRYPTOCOMPARE_SAFE_TOKEN_NAME).as_str()),
+					("tsyms", currency_apinames.join(",").as_str()),
+				])
+				.send()
+				.await?;
+
+			let body = response.text().await?;
+			let json = serde_json::from_str::<Value>(&body)?;
+			let (Some(token_prices), btc_prices) = (
+				json[CRYPTOCOMPARE_SAFE_TOKEN_NAME].as_object(),
+				json["BTC"].as_object(),
+			) else {
+				return Ok(None);
+			};
+
+			let mut prices = WEB_PRICES.lock()?;
+			for currency_apiname in currency_apinames {
+				let currency_key = currency_apiname.to_uppercase();
+				if !token_prices.contains_key(&currency_key) {
+					return Err(unrecognised_currency_error(currency_apiname));
+				}
+				let btc_rate = btc_prices.and_then(|btc_prices| btc_prices[&currency_key].as_f64());
+				prices.set_rates(currency_apiname, token_prices[&currency_key].as_f64(), btc_rate);
+				prices.last_update_time = Some(Utc::now());
+			}
+			Ok(prices.snt_rate)
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::WebPrices;
+
+	#[test]
+	fn it_cycles_currencies() {
+		let mut prices = WebPrices::new();
+		prices.set_currencies("usd, EUR,GBP", "$,€");
+		assert_eq!(prices.currencies.len(), 3);
+		assert_eq!(prices.currency_symbol, "$");
+
+		prices.set_rates("eur", Some(0.5), Some(50000.0));
+		assert_eq!(prices.snt_rate, None);
+
+		assert_eq!(prices.next_currency(), Some(String::from("EUR")));
+		assert_eq!(prices.currency_symbol, "€");
+		assert_eq!(prices.snt_rate, Some(0.5));
 
-		Ok(currency_per_token)
+		assert_eq!(prices.next_currency(), Some(String::from("GBP")));
+		assert_eq!(prices.currency_symbol, "GBP ");
+		assert_eq!(prices.next_currency(), Some(String::from("USD")));
 	}
 }
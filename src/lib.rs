@@ -0,0 +1,52 @@
+//! vdash-core: the interpretation of node logfiles behind vdash
+//!
+//! The vdash binary is a terminal dashboard built on this library, which
+//! gathers metrics from the lines of Autonomi (and older Safe Network) node
+//! logfiles. Other tools, such as web dashboards or bots, can use it to
+//! interpret logfiles exactly as vdash does.
+//!
+//! The API for this is re-exported here:
+//!
+//! - parse_lines() gives the NodeMetrics for the lines of a logfile, and
+//!   set_opt() gives the options it uses, such as --custom-metric, which are
+//!   otherwise vdash's defaults
+//! - LogEntry::decode_metadata() gives the LogMeta of a single line, which
+//!   NodeMetrics::gather_metrics() then applies along with the LinePatterns
+//!   it contains
+//! - NodeMetrics holds its statistics as MmmStat values and a Timeline of
+//!   each in AppTimelines
+//! - save_checkpoint() and restore_checkpoint() keep the metrics of a
+//!   LogMonitor's logfile so it needn't be parsed again from the start
+//!
+//! ```
+//! let lines = [
+//! 	"[2024-11-05T10:02:42.530164Z INFO ant_node::put_validation] Total payment of AttoTokens(2507) attos for record 2b7e",
+//! 	"[2024-11-05T10:02:42.533310Z INFO ant_networking::record_store] Wrote record 2b7e to disk!",
+//! ];
+//! let metrics = vdash_core::parse_lines(&lines);
+//! assert_eq!(metrics.attos_earned.total, 2507);
+//! assert_eq!(metrics.activity_puts.total, 1);
+//! ```
+//!
+//! Everything else is under custom, which is public for the binary rather
+//! than as a stable API. The dashboard itself is only built with the 'tui'
+//! feature, which the binary needs, so a tool wanting only the metrics can
+//! depend on vdash with default-features = false (and the 'web-prices' or
+//! 'minimal' feature).
+
+#[macro_use]
+extern crate log;
+
+pub mod custom;
+
+///! logtail and its forks share code in src/
+#[path = "mod.rs"]
+pub mod shared;
+
+pub use custom::node_metrics::{parse_lines, set_opt, LogEntry, LogMeta, LogMonitor, MmmStat, NodeMetrics, NodeStatus};
+pub use custom::app_timelines::AppTimelines;
+pub use custom::log_patterns::{LinePatterns, LogPattern};
+pub use custom::logfile_checkpoints::{checkpoint_path, restore_checkpoint, save_checkpoint, LogfileCheckpoint};
+pub use custom::opt::{Opt, StructOpt};
+pub use custom::timelines::Timeline;
+pub use custom::token_units::TokenUnit;
@@ -14,7 +14,8 @@ use ratatui::{
 /// # Examples:
 ///
 /// ```
-/// # use ratatui::widgets::{Widget, Gauge2, Block, Borders};
+/// # use ratatui::widgets::{Block, Borders};
+/// # use vdash_core::custom::ui::widgets::gauge::Gauge2;
 /// # use ratatui::style::{Style, Color, Modifier};
 /// Gauge2::default()
 ///     .block(Block::default().borders(Borders::ALL).title("Progress"))
@@ -11,21 +11,35 @@ use ratatui::{
 ///
 /// Based on ratatui::widgets::Sparkline, but with origin
 /// on right hand edge.
+///
+/// In braille mode the data is drawn as a line of braille dots, four to
+/// a cell vertically, between min and max rather than as bars from zero.
+/// This shows small changes in a metric which varies little, such as
+/// storage cost, which bars would draw at almost the same height.
 #[derive(Debug, Clone)]
 pub struct Sparkline2<'a> {
 	/// A block to wrap the widget in
 	block: Option<Block<'a>>,
 	/// Widget style
 	style: Style,
-	/// A slice of the data to display
-	data: &'a [u64],
+	/// A slice of the data to display, which is u128 for amounts in attos
+	data: &'a [u128],
 	/// The maximum value to take to compute the maximum bar height (if nothing is specified, the
 	/// widget uses the max of the dataset)
-	max: Option<u64>,
+	max: Option<u128>,
+	/// The value drawn on the bottom row in braille mode. Values below it are
+	/// not drawn (if nothing is specified, zero)
+	min: Option<u128>,
 	/// A set of bar symbols used to represent the give data
 	bar_set: symbols::bar::Set,
+	/// Draw a line of braille dots instead of bars
+	braille: bool,
 }
 
+/// Braille dot bits of the left and right columns of a cell, top row first
+const BRAILLE_DOTS: [[u32; 4]; 2] = [[0x01, 0x02, 0x04, 0x40], [0x08, 0x10, 0x20, 0x80]];
+const BRAILLE_BLANK: u32 = 0x2800;
+
 impl<'a> Default for Sparkline2<'a> {
 	fn default() -> Sparkline2<'a> {
 		Sparkline2 {
@@ -33,7 +47,9 @@ impl<'a> Default for Sparkline2<'a> {
 			style: Default::default(),
 			data: &[],
 			max: None,
+			min: None,
 			bar_set: symbols::bar::NINE_LEVELS,
+			braille: false,
 		}
 	}
 }
@@ -49,20 +65,84 @@ impl<'a> Sparkline2<'a> {
 		self
 	}
 
-	pub fn data(mut self, data: &'a [u64]) -> Sparkline2<'a> {
+	pub fn data(mut self, data: &'a [u128]) -> Sparkline2<'a> {
 		self.data = data;
 		self
 	}
 
-	pub fn max(mut self, max: u64) -> Sparkline2<'a> {
+	pub fn max(mut self, max: u128) -> Sparkline2<'a> {
 		self.max = Some(max);
 		self
 	}
 
+	pub fn min(mut self, min: u128) -> Sparkline2<'a> {
+		self.min = Some(min);
+		self
+	}
+
 	pub fn bar_set(mut self, bar_set: symbols::bar::Set) -> Sparkline2<'a> {
 		self.bar_set = bar_set;
 		self
 	}
+
+	pub fn braille(mut self, braille: bool) -> Sparkline2<'a> {
+		self.braille = braille;
+		self
+	}
+
+	/// Draw one data point per cell, with the left column of dots midway
+	/// from the previous point so that the line is continuous
+	fn render_braille(&self, spark_area: Rect, buf: &mut Buffer) {
+		let width = min(spark_area.width as usize, self.data.len());
+		let height = spark_area.height as usize;
+		let levels = height as u128 * 4;
+		let data = &self.data[..width];
+		let max = self.max.unwrap_or_else(|| *data.iter().max().unwrap_or(&0));
+		let min_value = min(self.min.unwrap_or(0), max);
+
+		// Dot level of a value, counting up from zero on the bottom row
+		let level = |value: u128| -> Option<u128> {
+			if value < min_value {
+				None
+			} else if max == min_value {
+				Some(if max == 0 { 0 } else { (levels - 1) / 2 })
+			} else {
+				Some((min(value, max) - min_value) * (levels - 1) / (max - min_value))
+			}
+		};
+
+		let mut cells = vec![0u32; width * height];
+		let mut set_dots = |x: usize, column: usize, from: u128, to: u128| {
+			for y in from.min(to)..=from.max(to) {
+				let row = height - 1 - (y / 4) as usize;
+				cells[row * width + x] |= BRAILLE_DOTS[column][3 - (y % 4) as usize];
+			}
+		};
+
+		let mut previous: Option<u128> = None;
+		for (x, value) in data.iter().enumerate() {
+			let current = level(*value);
+			if let Some(current) = current {
+				let midway = previous.map_or(current, |previous| (previous + current) / 2);
+				set_dots(x, 0, previous.unwrap_or(current), midway);
+				set_dots(x, 1, midway, current);
+			}
+			previous = current;
+		}
+
+		for row in 0..height {
+			for x in 0..width {
+				let dots = cells[row * width + x];
+				let symbol = match char::from_u32(BRAILLE_BLANK + dots) {
+					Some(symbol) if dots != 0 => symbol.to_string(),
+					_ => String::from(" "),
+				};
+				buf.get_mut(spark_area.left() + x as u16, spark_area.top() + row as u16)
+					.set_symbol(&symbol)
+					.set_style(self.style);
+			}
+		}
+	}
 }
 
 impl<'a> Widget for Sparkline2<'a> {
@@ -80,9 +160,14 @@ impl<'a> Widget for Sparkline2<'a> {
 			return;
 		}
 
+		if self.braille {
+			self.render_braille(spark_area, buf);
+			return;
+		}
+
 		let max = match self.max {
 			Some(v) => v,
-			None => *self.data.iter().max().unwrap_or(&1u64),
+			None => *self.data.iter().max().unwrap_or(&1u128),
 		};
 		let max_index = min(spark_area.width as usize, self.data.len());
 		let mut data = self
@@ -91,12 +176,12 @@ impl<'a> Widget for Sparkline2<'a> {
 			.take(max_index)
 			.map(|e| {
 				if max != 0 {
-					e * u64::from(spark_area.height) * 8 / max
+					e * u128::from(spark_area.height) * 8 / max
 				} else {
 					0
 				}
 			})
-			.collect::<Vec<u64>>();
+			.collect::<Vec<u128>>();
 		for j in (0..spark_area.height).rev() {
 			for (i, d) in data.iter_mut().enumerate() {
 				let symbol = match *d {
@@ -143,4 +228,27 @@ mod tests {
 		let mut buffer = Buffer::empty(area);
 		widget.render(area, &mut buffer);
 	}
+
+	#[test]
+	fn it_draws_a_braille_line_between_min_and_max() {
+		let widget = Sparkline2::default().data(&[0, 8]).max(8).braille(true);
+		let area = Rect::new(0, 0, 2, 1);
+		let mut buffer = Buffer::empty(area);
+		widget.render(area, &mut buffer);
+		assert_eq!(buffer.get(0, 0).symbol(), "\u{28C0}");
+		assert_eq!(buffer.get(1, 0).symbol(), "\u{287C}");
+
+		let widget = Sparkline2::default().data(&[0, 8, 8]).min(5).max(8).braille(true);
+		let area = Rect::new(0, 0, 3, 2);
+		let mut buffer = Buffer::empty(area);
+		widget.render(area, &mut buffer);
+		assert_eq!(buffer.get(0, 1).symbol(), " ");
+		assert_eq!(buffer.get(1, 0).symbol(), "\u{2809}");
+		assert_eq!(buffer.get(1, 1).symbol(), " ");
+
+		let widget = Sparkline2::default().data(&[0, 0]).braille(true);
+		let mut buffer = Buffer::empty(area);
+		widget.render(area, &mut buffer);
+		assert_eq!(buffer.get(0, 1).symbol(), "\u{28C0}");
+	}
 }